@@ -0,0 +1,113 @@
+//! Peak memory use (RSS) of extraction, which criterion doesn't measure.
+//!
+//! Run with `cargo bench --bench memory`. By default this extracts the `samples` folder;
+//! set `MDK_ASSETS` to the game folder to measure a full extraction instead.
+//! Only works on Linux, where the peak can be read from and reset through `/proc/self`.
+use std::path::{Path, PathBuf};
+
+use mdk_parse::data_formats::Texture;
+use mdk_parse::data_formats::masking::MaskSettings;
+use mdk_parse::data_formats::palette_cycle::PaletteCycles;
+use mdk_parse::file_formats::extract_file;
+use mdk_parse::gamemode_formats::{self, AssetAliases, AssetFiles, ExtractOptions};
+use mdk_parse::{ExportSession, OutputWriter};
+
+/// Peak resident set size of this process in KiB
+fn peak_rss() -> u64 {
+	let status = std::fs::read_to_string("/proc/self/status").expect("peak rss needs linux");
+	let line = status
+		.lines()
+		.find_map(|line| line.strip_prefix("VmHWM:"))
+		.expect("missing VmHWM");
+	line.trim().trim_end_matches("kB").trim().parse().unwrap()
+}
+
+/// Runs `f`, returning how much it raised the peak resident set size in KiB
+fn measure<T>(f: impl FnOnce() -> T) -> u64 {
+	// resets the peak to the current size
+	std::fs::write("/proc/self/clear_refs", "5").expect("failed to reset peak rss");
+	let before = peak_rss();
+	std::hint::black_box(f());
+	peak_rss() - before
+}
+
+/// Keeping every frame of a long animation, as the level texture animations
+/// and `combine_animation_frames` used to (copied) and now do (borrowed)
+fn animation_frames() {
+	const FRAMES: usize = 64;
+	const SIZE: u16 = 256;
+	let data: Vec<u8> = (0..FRAMES * SIZE as usize * SIZE as usize)
+		.map(|i| i as u8)
+		.collect();
+	let frames: Vec<Texture> = data
+		.chunks_exact(SIZE as usize * SIZE as usize)
+		.map(|pixels| Texture::new(SIZE, SIZE, pixels))
+		.collect();
+
+	let copied = measure(|| {
+		frames
+			.iter()
+			.map(|frame| Texture::new(frame.width, frame.height, frame.pixels.to_vec()))
+			.collect::<Vec<_>>()
+	});
+	let borrowed = measure(|| frames.iter().map(Texture::to_borrowed).collect::<Vec<_>>());
+	println!("animation frames: copied {copied} KiB, borrowed {borrowed} KiB");
+}
+
+fn extract_samples() {
+	let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../samples");
+	let mut paths: Vec<PathBuf> = std::fs::read_dir(&samples)
+		.unwrap()
+		.map(|entry| entry.unwrap().path())
+		.collect();
+	paths.sort_unstable();
+	let symbols = Default::default();
+	let peak = measure(|| {
+		let session = ExportSession::new("output/bench");
+		session.begin_dry_run();
+		for path in &paths {
+			extract_file(path, &mut session.writer(), &symbols, None);
+		}
+		session.finish_dry_run()
+	});
+	println!("extract samples: {peak} KiB");
+}
+
+/// Everything the command line extracts by default, without writing anything
+fn extract_all(assets: &Path) {
+	let masking = MaskSettings::default();
+	let cycles = PaletteCycles::default();
+	let options = ExtractOptions {
+		save_sounds: true,
+		save_sound_banks: false,
+		save_textures: true,
+		save_meshes: true,
+		masking: &masking,
+		cycles: &cycles,
+	};
+	let symbols = Default::default();
+	let peak = measure(|| {
+		let mut files = AssetFiles::detect(assets, &AssetAliases::default());
+		let session = ExportSession::new("output/bench");
+		session.begin_dry_run();
+		let output: OutputWriter = session.writer();
+		gamemode_formats::parse_traverse(&options, &symbols, &output, &mut files);
+		gamemode_formats::parse_stream(&options, &output, &mut files);
+		gamemode_formats::parse_fall3d(&options, &output, &mut files);
+		gamemode_formats::parse_misc(false, &output, &mut files);
+		session.finish_dry_run()
+	});
+	println!("extract {}: {peak} KiB", assets.display());
+}
+
+fn main() {
+	// `cargo bench` passes `--bench`, and `cargo test` runs benches without it
+	if !std::env::args().any(|arg| arg == "--bench") {
+		return;
+	}
+	animation_frames();
+	match std::env::var_os("MDK_ASSETS") {
+		Some(assets) => extract_all(Path::new(&assets)),
+		None => extract_samples(),
+	}
+}
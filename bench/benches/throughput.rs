@@ -0,0 +1,168 @@
+//! Throughput of the parsers and exporters on synthetic data (see the `samples` folder),
+//! to check performance changes without needing the game files.
+use std::hint::black_box;
+use std::path::Path;
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use mdk_parse::data_formats::cmi_bytecode::CmiScript;
+use mdk_parse::data_formats::{Mesh, Texture};
+use mdk_parse::file_formats::extract_file;
+use mdk_parse::{ExportSession, OutputWriter, Reader, Vec3};
+
+fn reader(c: &mut Criterion) {
+	// floats are validated, so fill it with reasonable ones
+	let data: Vec<u8> = (0..0x4000)
+		.flat_map(|i| (i as f32 * 0.25).to_le_bytes())
+		.collect();
+	let mut group = c.benchmark_group("reader");
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("u32", |b| {
+		b.iter(|| {
+			let mut reader = Reader::new(&data);
+			let mut sum = 0u32;
+			while reader.remaining_len() >= 4 {
+				sum = sum.wrapping_add(reader.u32());
+			}
+			sum
+		})
+	});
+	group.bench_function("f32", |b| {
+		b.iter(|| {
+			let mut reader = Reader::new(&data);
+			let mut sum = 0.0;
+			while reader.remaining_len() >= 4 {
+				sum += reader.f32();
+			}
+			sum
+		})
+	});
+	group.bench_function("vec3", |b| {
+		b.iter(|| {
+			let mut reader = Reader::new(&data);
+			let mut sum = Vec3::default();
+			while reader.remaining_len() >= 12 {
+				sum += reader.vec3();
+			}
+			sum
+		})
+	});
+	group.finish();
+}
+
+/// A flat `size` by `size` grid of verts with two tris in each square
+fn grid_mesh(size: u16) -> Vec<u8> {
+	let mut data = Vec::new();
+	data.extend(1u32.to_le_bytes());
+	data.extend(*b"GRID\0\0\0\0\0\0\0\0\0\0\0\0");
+
+	data.extend((size as u32 * size as u32).to_le_bytes());
+	for z in 0..size {
+		for x in 0..size {
+			for n in [x as f32, 0.0, z as f32] {
+				data.extend(n.to_le_bytes());
+			}
+		}
+	}
+	data.extend((2 * (size as u32 - 1).pow(2)).to_le_bytes());
+	for z in 0..size - 1 {
+		for x in 0..size - 1 {
+			let i = z * size + x;
+			for indices in [[i, i + 1, i + size], [i + 1, i + size + 1, i + size]] {
+				for index in indices {
+					data.extend(index.to_le_bytes());
+				}
+				data.extend(0i16.to_le_bytes()); // texture 0
+				for uv in [0.0f32, 0.0, 1.0, 0.0, 0.0, 1.0] {
+					data.extend(uv.to_le_bytes());
+				}
+				data.extend(0u32.to_le_bytes()); // flags
+			}
+		}
+	}
+	let max = (size - 1) as f32;
+	for n in [0.0, max, 0.0, 0.0, 0.0, max] {
+		data.extend(n.to_le_bytes());
+	}
+	data.extend(0u32.to_le_bytes()); // reference points
+	data
+}
+
+fn mesh(c: &mut Criterion) {
+	let data = grid_mesh(64);
+	let mut group = c.benchmark_group("mesh");
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("try_parse", |b| {
+		b.iter(|| Mesh::try_parse(&mut Reader::new(black_box(&data)), false).unwrap())
+	});
+	group.finish();
+}
+
+/// A script of simple instructions, after a byte so it doesn't start at offset 0
+fn long_script(count: usize) -> Vec<u8> {
+	let mut data = vec![0xFF];
+	for i in 0..count {
+		data.push(0x05); // set camera zoom
+		data.extend((i as f32).to_le_bytes());
+		data.push(0x08); // set yaw
+		data.extend((i as i16).to_le_bytes());
+		data.push(0x06);
+		data.push(0x01); // set resume point
+	}
+	data.push(0xFF);
+	data
+}
+
+fn cmi_script(c: &mut Criterion) {
+	let data = long_script(1000);
+	let mut group = c.benchmark_group("cmi");
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("script", |b| {
+		b.iter(|| CmiScript::parse(Reader::new(black_box(&data)).clone_at(1)))
+	});
+	group.finish();
+}
+
+fn png_encode(c: &mut Criterion) {
+	let palette: Vec<u8> = (0..=255u8).flat_map(|i| [i, i / 2, 255 - i]).collect();
+	let pixels: Vec<u8> = (0..256 * 256u32).map(|i| (i ^ (i >> 8)) as u8).collect();
+	let texture = Texture::new(256, 256, pixels);
+	let output = OutputWriter::new_in_dir("output/bench");
+	let mut group = c.benchmark_group("texture");
+	group.throughput(Throughput::Bytes(256 * 256));
+	group.bench_function("png", |b| {
+		b.iter(|| texture.create_png("PNG", &output, Some(black_box(&palette))))
+	});
+	group.finish();
+}
+
+/// Extracts every sample file, without writing anything
+fn extract_samples(c: &mut Criterion) {
+	let samples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../samples");
+	let mut paths: Vec<_> = std::fs::read_dir(&samples)
+		.unwrap()
+		.map(|entry| entry.unwrap().path())
+		.collect();
+	paths.sort_unstable();
+	let symbols = Default::default();
+	c.bench_function("extract samples", |b| {
+		b.iter(|| {
+			let session = ExportSession::new("output/bench");
+			session.begin_dry_run();
+			for path in &paths {
+				let mut output = session.writer();
+				extract_file(path, &mut output, &symbols, None);
+			}
+			session.finish_dry_run()
+		})
+	});
+}
+
+criterion_group!(
+	benches,
+	reader,
+	mesh,
+	cmi_script,
+	png_encode,
+	extract_samples
+);
+criterion_main!(benches);
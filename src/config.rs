@@ -0,0 +1,185 @@
+//! Extraction settings loaded from `mdk-parse.toml`, so a setup doesn't need a long list of flags.
+//!
+//! Any flags given on the command line override the config.
+//!
+//! ```toml
+//! assets = "assets"
+//! output = "output"
+//! symbols = "symbols.json"
+//! layout = "{game_mode}/{level}/{asset_type}/{name}"
+//!
+//! [formats]
+//! videos = false
+//! sound-banks = true
+//!
+//! [export]
+//! up = "z"
+//! scale = 0.01
+//! mesh-format = "obj"
+//! anim-fps = 15.0
+//! texture-fps = "texture_fps.json"
+//! ```
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+	AnimFormat, ExportSettings, MeshFormat, MeshSplit, PngColour, PngCompression, PngFilter,
+	UpAxis, Winding,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+	/// The game's asset folder (`--assets`)
+	pub assets: Option<PathBuf>,
+	/// Where extractions, diffs and patches are saved (`--output`)
+	pub output: Option<PathBuf>,
+	/// CMI symbol file (`--symbols`)
+	pub symbols: Option<PathBuf>,
+	/// CMI opcode file (`--opcodes`)
+	pub opcodes: Option<PathBuf>,
+	/// Output layout template (`--layout`)
+	pub layout: Option<String>,
+	pub formats: FormatToggles,
+	pub export: ExportConfig,
+}
+
+/// Which kinds of assets a full extraction saves
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FormatToggles {
+	pub sounds: bool,
+	pub textures: bool,
+	pub meshes: bool,
+	pub videos: bool,
+	/// `--sound-banks`
+	pub sound_banks: bool,
+}
+
+impl Default for FormatToggles {
+	fn default() -> Self {
+		Self {
+			sounds: true,
+			textures: true,
+			meshes: true,
+			videos: true,
+			sound_banks: false,
+		}
+	}
+}
+
+/// [ExportSettings] overrides, named after the matching flags
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ExportConfig {
+	pub up: Option<String>,
+	pub scale: Option<f32>,
+	pub winding: Option<String>,
+	pub mesh_format: Option<String>,
+	pub bsp_split: Option<String>,
+	pub lods: Option<u32>,
+	pub anim_format: Option<String>,
+	pub anim_fps: Option<f32>,
+	/// Per-texture frame rates (`--texture-fps`)
+	pub texture_fps: Option<PathBuf>,
+	pub palette_tolerance: Option<u32>,
+	pub png_compression: Option<String>,
+	pub png_filter: Option<String>,
+	pub png_colour: Option<String>,
+}
+
+impl Config {
+	/// Loaded automatically from the working directory when `--config` isn't given
+	pub const FILE_NAME: &str = "mdk-parse.toml";
+
+	pub fn load(path: impl AsRef<Path>) -> Self {
+		let path = path.as_ref();
+		let text = std::fs::read_to_string(path)
+			.unwrap_or_else(|e| panic!("failed to read config file {}: {e}", path.display()));
+		Self::parse(&text)
+			.unwrap_or_else(|e| panic!("failed to parse config file {}: {e}", path.display()))
+	}
+
+	pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+		toml::from_str(text)
+	}
+
+	/// Overwrites the settings the config sets
+	pub fn apply(&self, settings: &mut ExportSettings) {
+		fn parse<T>(kind: &str, value: &Option<String>, parse: fn(&str) -> Option<T>) -> Option<T> {
+			let value = value.as_deref()?;
+			Some(parse(value).unwrap_or_else(|| panic!("invalid {kind} {value} in config")))
+		}
+		let export = &self.export;
+		if let Some(up_axis) = parse("up axis", &export.up, UpAxis::parse) {
+			settings.up_axis = up_axis;
+		}
+		if let Some(scale) = export.scale {
+			settings.scale = scale;
+		}
+		if let Some(winding) = parse("winding order", &export.winding, Winding::parse) {
+			settings.winding = winding;
+		}
+		if let Some(format) = parse("mesh format", &export.mesh_format, MeshFormat::parse) {
+			settings.mesh_format = format;
+		}
+		if let Some(split) = parse("bsp split", &export.bsp_split, MeshSplit::parse) {
+			settings.bsp_split = split;
+		}
+		if let Some(lods) = export.lods {
+			settings.lod_levels = lods;
+		}
+		if let Some(format) = parse("animation format", &export.anim_format, AnimFormat::parse) {
+			settings.anim_format = format;
+		}
+		if let Some(fps) = export.anim_fps {
+			settings.anim_fps = fps;
+		}
+		if let Some(tolerance) = export.palette_tolerance {
+			settings.palette_tolerance = tolerance;
+		}
+		let png_compression = &export.png_compression;
+		if let Some(level) = parse("png compression", png_compression, PngCompression::parse) {
+			settings.png_compression = level;
+		}
+		if let Some(filter) = parse("png filter", &export.png_filter, PngFilter::parse) {
+			settings.png_filter = filter;
+		}
+		if let Some(colour) = parse("png colour", &export.png_colour, PngColour::parse) {
+			settings.png_colour = colour;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_config() {
+		let config = Config::parse(
+			r#"
+			assets = "game/assets"
+			[formats]
+			videos = false
+			[export]
+			up = "z"
+			scale = 0.5
+			mesh-format = "obj"
+			"#,
+		)
+		.unwrap();
+		assert_eq!(config.assets.as_deref(), Some(Path::new("game/assets")));
+		assert!(config.formats.sounds && !config.formats.videos);
+
+		let mut settings = ExportSettings::default();
+		config.apply(&mut settings);
+		assert_eq!(settings.up_axis, UpAxis::Z);
+		assert_eq!(settings.scale, 0.5);
+		assert_eq!(settings.mesh_format, MeshFormat::Obj);
+		assert_eq!(settings.winding, Winding::CounterClockwise);
+
+		assert!(Config::parse("[export]\nup-axis = \"z\"").is_err());
+	}
+}
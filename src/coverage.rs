@@ -0,0 +1,239 @@
+//! Tracks which bytes of a file the parsers actually read,
+//! so the regions of each format that are still unknown are easy to find.
+//!
+//! Every [Reader](crate::Reader) marks the bytes it reads while [track] is running,
+//! which works across rebased and cloned readers since they all point into the same buffer.
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::ops::Range;
+
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+
+/// Which reports to write after extracting a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageOutput {
+	Text,
+	/// Text report plus a hexdump heatmap
+	Html,
+}
+
+struct Tracker {
+	start: usize,
+	counts: Vec<u16>,
+}
+
+thread_local! {
+	static TRACKER: RefCell<Option<Tracker>> = const { RefCell::new(None) };
+}
+
+/// Records how many times each byte of `data` is read while running `f`
+pub fn track<R>(data: &[u8], f: impl FnOnce() -> R) -> (R, Coverage) {
+	TRACKER.with_borrow_mut(|tracker| {
+		assert!(tracker.is_none(), "already tracking coverage");
+		*tracker = Some(Tracker {
+			start: data.as_ptr() as usize,
+			counts: vec![0; data.len()],
+		});
+	});
+	let result = f();
+	let tracker = TRACKER.take().unwrap();
+	(
+		result,
+		Coverage {
+			counts: tracker.counts,
+		},
+	)
+}
+
+/// Marks the bytes as read, if they are part of the tracked buffer
+pub(crate) fn mark(bytes: &[u8]) {
+	if bytes.is_empty() {
+		return;
+	}
+	TRACKER.with_borrow_mut(|tracker| {
+		let Some(tracker) = tracker else {
+			return;
+		};
+		let Some(start) = (bytes.as_ptr() as usize).checked_sub(tracker.start) else {
+			return;
+		};
+		if let Some(counts) = tracker.counts.get_mut(start..start + bytes.len()) {
+			for count in counts {
+				*count = count.saturating_add(1);
+			}
+		}
+	});
+}
+
+/// How many times each byte of a file was read
+pub struct Coverage {
+	counts: Vec<u16>,
+}
+
+impl Coverage {
+	pub fn len(&self) -> usize {
+		self.counts.len()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.counts.is_empty()
+	}
+	pub fn read_len(&self) -> usize {
+		self.counts.iter().filter(|&&count| count != 0).count()
+	}
+
+	/// Ranges of bytes that were never read
+	pub fn unread_ranges(&self) -> Vec<Range<usize>> {
+		let mut result: Vec<Range<usize>> = Vec::new();
+		for (pos, _) in self.counts.iter().enumerate().filter(|(_, c)| **c == 0) {
+			match result.last_mut() {
+				Some(range) if range.end == pos => range.end += 1,
+				_ => result.push(pos..pos + 1),
+			}
+		}
+		result
+	}
+
+	/// Lists the unread ranges with a preview of their contents
+	pub fn report(&self, data: &[u8]) -> String {
+		let mut report = String::new();
+		let percent = if self.is_empty() {
+			100.0
+		} else {
+			self.read_len() as f32 * 100.0 / self.len() as f32
+		};
+		writeln!(
+			report,
+			"read {} of {} bytes ({percent:.2}%)\n",
+			self.read_len(),
+			self.len()
+		)
+		.unwrap();
+		for range in self.unread_ranges() {
+			let preview = &data[range.start..range.end.min(range.start + 16)];
+			write!(
+				report,
+				"{:08X}..{:08X}\t{:6} bytes\t",
+				range.start,
+				range.end,
+				range.len()
+			)
+			.unwrap();
+			for byte in preview {
+				write!(report, "{byte:02X} ").unwrap();
+			}
+			if preview.len() < range.len() {
+				report.push_str("...");
+			}
+			report.push('\n');
+		}
+		report
+	}
+
+	/// Hexdump of the file coloured by how often each byte was read.
+	///
+	/// Runs of fully read rows are collapsed so the unread regions stand out.
+	pub fn html(&self, name: &str, data: &[u8]) -> String {
+		const ROW_SIZE: usize = 16;
+		let mut html = format!(
+			"<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{name} coverage</title><style>\n\
+			body {{ font-family: monospace; background: #222; color: #ccc; }}\n\
+			.u {{ background: #a33; color: #fff; }}\n\
+			.r1 {{ background: #353; }}\n\
+			.r2 {{ background: #575; }}\n\
+			.skip {{ color: #777; }}\n\
+			</style></head><body>\n<pre>\n"
+		);
+		html.push_str(&self.report(data).replace('<', "&lt;"));
+		html.push_str("\n<span class=\"u\">unread</span> <span class=\"r1\">read once</span> <span class=\"r2\">read more than once</span>\n\n");
+
+		let mut skipped_rows = 0;
+		for (row_index, (row, counts)) in data
+			.chunks(ROW_SIZE)
+			.zip(self.counts.chunks(ROW_SIZE))
+			.enumerate()
+		{
+			if !counts.contains(&0) {
+				skipped_rows += 1;
+				continue;
+			}
+			if skipped_rows != 0 {
+				writeln!(
+					html,
+					"<span class=\"skip\">... {skipped_rows} read rows</span>"
+				)
+				.unwrap();
+				skipped_rows = 0;
+			}
+			write!(html, "{:08X}  ", row_index * ROW_SIZE).unwrap();
+			for (byte, count) in row.iter().zip(counts) {
+				let class = match count {
+					0 => "u",
+					1 => "r1",
+					_ => "r2",
+				};
+				write!(html, "<span class=\"{class}\">{byte:02X}</span> ").unwrap();
+			}
+			for _ in row.len()..ROW_SIZE {
+				html.push_str("   ");
+			}
+			html.push(' ');
+			for &byte in row {
+				match byte {
+					b'<' => html.push_str("&lt;"),
+					b'>' => html.push_str("&gt;"),
+					b'&' => html.push_str("&amp;"),
+					b' '..=b'~' => html.push(byte as char),
+					_ => html.push('.'),
+				}
+			}
+			html.push('\n');
+		}
+		if skipped_rows != 0 {
+			writeln!(
+				html,
+				"<span class=\"skip\">... {skipped_rows} read rows</span>"
+			)
+			.unwrap();
+		}
+		html.push_str("</pre></body></html>\n");
+		html
+	}
+
+	#[cfg(feature = "export")]
+	pub fn save(&self, name: &str, data: &[u8], kind: CoverageOutput, output: &mut OutputWriter) {
+		let report_name = format!("{name} coverage");
+		output.write(&report_name, "txt", self.report(data));
+		if kind == CoverageOutput::Html {
+			output.write(&report_name, "html", self.html(name, data));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Reader;
+
+	#[test]
+	fn test_track() {
+		let data: Vec<u8> = (0..32).collect();
+		let ((), coverage) = track(&data, || {
+			let mut reader = Reader::new(&data);
+			reader.u32();
+			reader.skip(4);
+			let mut rebased = reader.rebased();
+			rebased.slice(8);
+			reader.clone_at(20).u16();
+			rebased.set_position(0);
+			rebased.u8();
+		});
+		assert_eq!(coverage.read_len(), 4 + 8 + 2);
+		assert_eq!(coverage.unread_ranges(), [4..8, 16..20, 22..32]);
+		assert_eq!(coverage.counts[8], 2);
+
+		// readers outside of tracking don't count
+		Reader::new(&data).u32();
+		assert!(TRACKER.with_borrow(Option::is_none));
+	}
+}
@@ -1,8 +1,14 @@
-use crate::{OutputWriter, Reader, Vec3, gltf};
+#[cfg(feature = "export")]
+use std::collections::HashMap;
+
+#[cfg(feature = "export")]
+use crate::{OutputWriter, gltf};
+use crate::{Reader, Vec3, Vec4};
 
 /// 3D vertex animations
 #[derive(Clone, PartialEq)]
 pub struct Animation<'a> {
+	/// How many frames of the base rate each frame lasts, see [Animation::frame_rate]
 	pub speed: f32,
 	pub target_vectors: Vec<Vec3>, // todo what exactly are these?
 	pub reference_points: Vec<Vec<Vec3>>,
@@ -13,6 +19,22 @@ pub struct Animation<'a> {
 pub struct AnimationPart<'a> {
 	pub name: &'a str,
 	pub point_paths: Vec<Vec<Vec3>>,
+	/// Per-frame rigid transforms, for parts animated by matrices rather than point offsets
+	pub transforms: Option<PartTransforms>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct PartTransforms {
+	/// Untransformed positions of the part's points
+	pub origin_points: Vec<Vec3>,
+	pub frames: Vec<PartTransform>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct PartTransform {
+	/// Unit quaternion (x, y, z, w)
+	pub rotation: Vec4,
+	pub translation: Vec3,
 }
 
 impl<'a> Animation<'a> {
@@ -44,6 +66,7 @@ impl<'a> Animation<'a> {
 			let scale = data.try_f32()?;
 			let mut point_paths: Vec<Vec<Vec3>> = Vec::new();
 			point_paths.resize_with(num_points, || Vec::with_capacity(num_frames));
+			let mut transforms = None;
 
 			if scale != 0.0 {
 				// origin points
@@ -82,6 +105,7 @@ impl<'a> Animation<'a> {
 
 				let origin_points = data.try_get_vec::<Vec3>(num_points)?;
 				// don't swizzle until after processing
+				let mut frames = Vec::with_capacity(num_frames);
 
 				for _ in 0..num_frames {
 					let transform = data.try_get::<[[i16; 4]; 3]>()?;
@@ -104,7 +128,17 @@ impl<'a> Animation<'a> {
 							.swizzle(),
 						)
 					}
+
+					frames.push(PartTransform {
+						rotation: matrix_to_quaternion(swizzle_matrix([r1, r2, r3])),
+						translation: Vec3::new(r1[3], r2[3], r3[3]).swizzle(),
+					});
 				}
+
+				transforms = Some(PartTransforms {
+					origin_points: Vec3::swizzle_vec(origin_points),
+					frames,
+				});
 			}
 
 			for path in &mut point_paths {
@@ -119,6 +153,7 @@ impl<'a> Animation<'a> {
 			parts.push(AnimationPart {
 				name: part_name,
 				point_paths,
+				transforms,
 			});
 		}
 
@@ -158,27 +193,34 @@ impl<'a> Animation<'a> {
 		self.target_vectors.len()
 	}
 
+	/// Frames per second when the game runs at `base_fps` (assumed to be 30)
+	pub fn frame_rate(&self, base_fps: f32) -> f32 {
+		base_fps / self.speed
+	}
+	/// Seconds from the first frame to the last
+	pub fn duration(&self, base_fps: f32) -> f32 {
+		(self.num_frames() - 1) as f32 / self.frame_rate(base_fps)
+	}
+
+	#[cfg(feature = "export")]
 	pub fn save_as(&self, name: &str, output: &mut OutputWriter) {
 		let num_frames = self.num_frames();
+		let base_fps = output.export_settings().anim_fps;
+		let frame_rate = self.frame_rate(base_fps);
 
-		let fps = 30.0;
-
-		let mut gltf = gltf::Gltf::new(name.into());
+		let mut gltf = gltf::Gltf::with_settings(name.into(), *output.export_settings());
 		let cube_mesh = Some(gltf.get_cube_mesh());
-		let animation = gltf.create_animation(name.into());
 		let root_node = gltf.get_root_node();
-		let base_timestamps = gltf.create_animation_timestamps(num_frames, fps / self.speed);
-		let interpolation = Some(gltf::AnimationInterpolationMode::Step);
+		let mut channels = ChannelWriter::new(&mut gltf, name, num_frames, frame_rate);
+		// so the original timing can be recovered
+		gltf.set_animation_extras(channels.animation, "speed", self.speed);
+		gltf.set_animation_extras(channels.animation, "base_fps", base_fps);
+		gltf.set_animation_extras(channels.animation, "frame_rate", frame_rate);
+		gltf.set_animation_extras(channels.animation, "num_frames", num_frames);
 
 		if self.target_vectors.iter().any(|p| *p != Vec3::default()) {
 			let node = gltf.create_child_node(root_node, "Target Vectors".into(), cube_mesh);
-			gltf.add_animation_translation(
-				animation,
-				node,
-				base_timestamps,
-				&self.target_vectors,
-				interpolation,
-			);
+			channels.translation(&mut gltf, node, &self.target_vectors);
 		}
 
 		if self
@@ -189,30 +231,491 @@ impl<'a> Animation<'a> {
 			let ref_node = gltf.create_child_node(root_node, "Reference Points".into(), None);
 			for (i, path) in self.reference_points.iter().enumerate() {
 				let node = gltf.create_child_node(ref_node, i.to_string(), cube_mesh);
-				gltf.add_animation_translation(
-					animation,
-					node,
-					base_timestamps,
-					path,
-					interpolation,
-				);
+				channels.translation(&mut gltf, node, path);
 			}
 		}
 
 		for part in &self.parts {
 			let part_node = gltf.create_child_node(root_node, part.name.into(), None);
+			if let Some(transforms) = &part.transforms {
+				// animate the part as a whole, with its points fixed in place underneath it
+				let (rotations, translations): (Vec<Vec4>, Vec<Vec3>) = transforms
+					.frames
+					.iter()
+					.map(|frame| (frame.rotation, frame.translation))
+					.unzip();
+				channels.translation(&mut gltf, part_node, &translations);
+				channels.rotation(&mut gltf, part_node, &rotations);
+				for (i, &point) in transforms.origin_points.iter().enumerate() {
+					let point_node = gltf.create_child_node(part_node, i.to_string(), cube_mesh);
+					gltf.set_node_position(point_node, point);
+				}
+				continue;
+			}
+			if output.export_settings().morph_targets {
+				channels.morph(&mut gltf, part_node, part.name, &part.point_paths);
+				continue;
+			}
 			for (i, path) in part.point_paths.iter().enumerate() {
 				let point_node = gltf.create_child_node(part_node, i.to_string(), cube_mesh);
-				gltf.add_animation_translation(
-					animation,
-					point_node,
-					base_timestamps,
-					path,
-					interpolation,
-				);
+				channels.translation(&mut gltf, point_node, path);
 			}
 		}
+		channels.finish(&mut gltf);
 
 		output.write(name, "anim.gltf", gltf.render_json().as_bytes());
+
+		let preview_frames = output.export_settings().anim_preview_frames;
+		if preview_frames != 0 {
+			let (width, height, pixels) = self.render_preview(preview_frames);
+			output.write_png(
+				&format!("{name}_preview"),
+				width,
+				height,
+				pixels,
+				Some(&PREVIEW_PALETTE),
+			);
+		}
+	}
+
+	/// Renders a contact sheet of evenly spaced frames, viewed orthographically from the front.
+	///
+	/// Part points are coloured by part and reference points are red, see [PREVIEW_PALETTE].
+	pub fn render_preview(&self, num_frames: usize) -> (u32, u32, Vec<u8>) {
+		const CELL: usize = 128;
+		const GAP: usize = 4;
+		const COLUMNS: usize = 8;
+
+		let total_frames = self.num_frames();
+		let num_frames = num_frames.clamp(1, total_frames);
+		let frames: Vec<usize> = (0..num_frames)
+			.map(|i| i * (total_frames - 1) / (num_frames - 1).max(1))
+			.collect();
+
+		// (colour, path) for every point
+		let paths: Vec<(u8, &[Vec3])> = self
+			.parts
+			.iter()
+			.enumerate()
+			.flat_map(|(i, part)| {
+				let colour = 3 + (i % (PREVIEW_PALETTE.len() / 3 - 3)) as u8;
+				part.point_paths.iter().map(move |path| (colour, &path[..]))
+			})
+			.chain(self.reference_points.iter().map(|path| (2, &path[..])))
+			.collect();
+
+		// frame the whole animation the same way so movement shows up
+		let sampled: Vec<Vec3> = paths
+			.iter()
+			.flat_map(|(_, path)| frames.iter().map(|&f| path[f]))
+			.collect();
+		let [min, max] = Vec3::calculate_bbox(&sampled);
+		let size = (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+		let scale = (CELL - 8) as f32 / size;
+		let centre = (min + max) * 0.5;
+
+		let columns = num_frames.min(COLUMNS);
+		let rows = num_frames.div_ceil(COLUMNS);
+		let width = columns * (CELL + GAP) - GAP;
+		let height = rows * (CELL + GAP) - GAP;
+		let mut pixels = vec![0; width * height];
+
+		for (cell, &frame) in frames.iter().enumerate() {
+			let left = cell % COLUMNS * (CELL + GAP);
+			let top = cell / COLUMNS * (CELL + GAP);
+			for y in top..top + CELL {
+				pixels[y * width + left..y * width + left + CELL].fill(1);
+			}
+			for &(colour, path) in &paths {
+				let point = path[frame];
+				let x = ((point.x - centre.x) * scale + CELL as f32 / 2.0) as usize;
+				let y = ((centre.y - point.y) * scale + CELL as f32 / 2.0) as usize;
+				// 2x2 dots
+				for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+					let (x, y) = ((x + dx).min(CELL - 1), (y + dy).min(CELL - 1));
+					pixels[(top + y) * width + left + x] = colour;
+				}
+			}
+		}
+		(width as u32, height as u32, pixels)
+	}
+}
+
+/// Adds the animation's channels, dropping keyframes (and channels) that don't change anything
+/// according to the [AnimCompression](crate::AnimCompression) settings.
+///
+/// Channels use step interpolation, so a keyframe holds its value until the next one.
+#[cfg(feature = "export")]
+struct ChannelWriter {
+	animation: gltf::AnimationIndex,
+	/// The time of every frame
+	times: Vec<f32>,
+	/// Timestamp accessors by the frames they have keys for
+	timestamps: HashMap<Vec<usize>, gltf::AccessorIndex>,
+	num_channels: usize,
+	/// The first dropped channel, in case every channel is dropped (animations need at least one)
+	pruned: Option<(gltf::NodeIndex, Vec3)>,
+}
+
+#[cfg(feature = "export")]
+impl ChannelWriter {
+	fn new(gltf: &mut gltf::Gltf, name: &str, num_frames: usize, fps: f32) -> Self {
+		let period = fps.recip();
+		Self {
+			animation: gltf.create_animation(name.into()),
+			times: (0..num_frames).map(|n| n as f32 * period).collect(),
+			timestamps: HashMap::new(),
+			num_channels: 0,
+			pruned: None,
+		}
+	}
+
+	fn translation(&mut self, gltf: &mut gltf::Gltf, node: gltf::NodeIndex, path: &[Vec3]) {
+		let values: Vec<[f32; 3]> = path.iter().map(|p| p.to_array()).collect();
+		let Some(frames) = self.keyframes(gltf, &values) else {
+			gltf.set_node_position(node, path[0]);
+			self.pruned.get_or_insert((node, path[0]));
+			return;
+		};
+		let path: Vec<Vec3> = frames.iter().map(|&frame| path[frame]).collect();
+		let timestamps = self.timestamps(gltf, frames);
+		let interpolation = Some(gltf::AnimationInterpolationMode::Step);
+		gltf.add_animation_translation(self.animation, node, timestamps, &path, interpolation);
+		self.num_channels += 1;
+	}
+
+	fn rotation(&mut self, gltf: &mut gltf::Gltf, node: gltf::NodeIndex, rotations: &[Vec4]) {
+		let Some(frames) = self.keyframes(gltf, rotations) else {
+			gltf.set_node_rotation(node, rotations[0]);
+			return;
+		};
+		let rotations: Vec<Vec4> = frames.iter().map(|&frame| rotations[frame]).collect();
+		let timestamps = self.timestamps(gltf, frames);
+		let interpolation = Some(gltf::AnimationInterpolationMode::Step);
+		gltf.add_animation_rotation(self.animation, node, timestamps, &rotations, interpolation);
+		self.num_channels += 1;
+	}
+
+	/// Animates a part's points as morph targets of a point cloud mesh on `node`,
+	/// with a target for each distinct pose and one-hot weights picking it every frame
+	fn morph(
+		&mut self, gltf: &mut gltf::Gltf, node: gltf::NodeIndex, name: &str,
+		point_paths: &[Vec<Vec3>],
+	) {
+		if point_paths.is_empty() {
+			return;
+		}
+		let base: Vec<Vec3> = point_paths.iter().map(|path| path[0]).collect();
+		let indices: Vec<u16> = (0..base.len() as u16).collect();
+		let mesh = gltf.create_mesh(name.into());
+		let primitive = gltf.add_mesh_primitive(mesh, &base, &indices, None);
+		gltf.set_primitive_mode(primitive, gltf::PrimitiveMode::Points);
+		gltf.set_node_mesh(node, mesh);
+
+		let tolerance = gltf.export_settings().anim_compression.tolerance;
+		let close = |a: f32, b: f32| (a - b).abs() <= tolerance.unwrap_or(0.0);
+		let same = |a: &[Vec3], b: &[Vec3]| {
+			(a.iter().zip(b)).all(|(a, b)| a.iter().zip(b.iter()).all(|(&a, &b)| close(a, b)))
+		};
+
+		// the pose of each frame, with None for the first frame's
+		let mut poses: Vec<Vec<Vec3>> = Vec::new();
+		let frame_poses: Vec<Option<usize>> = (0..self.times.len())
+			.map(|frame| {
+				let offsets: Vec<Vec3> = point_paths
+					.iter()
+					.zip(&base)
+					.map(|(path, &base)| path[frame] - base)
+					.collect();
+				if offsets
+					.iter()
+					.all(|offset| offset.iter().all(|&c| close(c, 0.0)))
+				{
+					return None;
+				}
+				let pose = poses.iter().position(|pose| same(pose, &offsets));
+				Some(pose.unwrap_or_else(|| {
+					poses.push(offsets);
+					poses.len() - 1
+				}))
+			})
+			.collect();
+		if poses.is_empty() {
+			// weights need at least one target
+			self.pruned.get_or_insert((node, Vec3::default()));
+			return;
+		}
+		for pose in &poses {
+			gltf.add_primitive_morph_target(primitive, pose);
+		}
+
+		let mut frames = vec![0];
+		for frame in 1..frame_poses.len() {
+			if tolerance.is_none() || frame_poses[frame] != frame_poses[frame - 1] {
+				frames.push(frame);
+			}
+		}
+		if *frames.last().unwrap() != frame_poses.len() - 1 {
+			frames.push(frame_poses.len() - 1);
+		}
+		let mut weights = vec![0.0; frames.len() * poses.len()];
+		for (key, &frame) in frames.iter().enumerate() {
+			if let Some(pose) = frame_poses[frame] {
+				weights[key * poses.len() + pose] = 1.0;
+			}
+		}
+		let timestamps = self.timestamps(gltf, frames);
+		let interpolation = Some(gltf::AnimationInterpolationMode::Step);
+		gltf.add_animation_weights(self.animation, node, timestamps, &weights, interpolation);
+		self.num_channels += 1;
+	}
+
+	/// Adds back a dropped channel if every channel was dropped
+	fn finish(mut self, gltf: &mut gltf::Gltf) {
+		if self.num_channels != 0 {
+			return;
+		}
+		if let Some((node, position)) = self.pruned {
+			let mut frames = vec![0];
+			if self.times.len() > 1 {
+				frames.push(self.times.len() - 1);
+			}
+			let path = vec![position; frames.len()];
+			let timestamps = self.timestamps(gltf, frames);
+			let interpolation = Some(gltf::AnimationInterpolationMode::Step);
+			gltf.add_animation_translation(self.animation, node, timestamps, &path, interpolation);
+		}
+	}
+
+	/// The frames to keep keys for, or `None` to drop the channel
+	fn keyframes<const N: usize>(
+		&self, gltf: &gltf::Gltf, values: &[[f32; N]],
+	) -> Option<Vec<usize>> {
+		let compression = gltf.export_settings().anim_compression;
+		let Some(tolerance) = compression.tolerance else {
+			return Some((0..values.len()).collect());
+		};
+		let mut result = vec![0];
+		let mut held = values[0];
+		for (frame, &value) in values.iter().enumerate().skip(1) {
+			if held
+				.iter()
+				.zip(&value)
+				.any(|(a, b)| (a - b).abs() > tolerance)
+			{
+				result.push(frame);
+				held = value;
+			}
+		}
+		if result.len() == 1 && compression.prune {
+			return None;
+		}
+		// keep the last frame so the animation doesn't get shorter
+		if *result.last().unwrap() != values.len() - 1 {
+			result.push(values.len() - 1);
+		}
+		Some(result)
+	}
+
+	fn timestamps(&mut self, gltf: &mut gltf::Gltf, frames: Vec<usize>) -> gltf::AccessorIndex {
+		*self.timestamps.entry(frames).or_insert_with_key(|frames| {
+			let times: Vec<f32> = frames.iter().map(|&frame| self.times[frame]).collect();
+			gltf.add_animation_timestamps(&times)
+		})
+	}
+}
+
+/// Colours of [Animation::render_preview]: transparent gaps, background,
+/// reference points, then a colour per part (repeating)
+pub const PREVIEW_PALETTE: [u8; 11 * 3] = [
+	0, 0, 0, //
+	40, 40, 48, //
+	255, 64, 64, //
+	255, 255, 255, //
+	255, 200, 64, //
+	96, 200, 255, //
+	128, 255, 128, //
+	255, 128, 255, //
+	255, 160, 96, //
+	160, 128, 255, //
+	128, 255, 224, //
+];
+
+/// Converts the rotation part of a transform row matrix into Y-up space (see [Vec3::swizzle])
+fn swizzle_matrix(rows: [[f32; 4]; 3]) -> [[f32; 3]; 3] {
+	// swizzling maps (x, y, z) to (x, z, -y)
+	const AXES: [(usize, f32); 3] = [(0, 1.0), (2, 1.0), (1, -1.0)];
+	AXES.map(|(row, row_sign)| AXES.map(|(col, col_sign)| rows[row][col] * row_sign * col_sign))
+}
+
+/// Converts a rotation matrix into a unit quaternion, ignoring any scale or skew
+fn matrix_to_quaternion(m: [[f32; 3]; 3]) -> Vec4 {
+	let trace = m[0][0] + m[1][1] + m[2][2];
+	let q = if trace > 0.0 {
+		let s = (trace + 1.0).sqrt() * 2.0;
+		[
+			(m[2][1] - m[1][2]) / s,
+			(m[0][2] - m[2][0]) / s,
+			(m[1][0] - m[0][1]) / s,
+			s / 4.0,
+		]
+	} else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+		let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+		[
+			s / 4.0,
+			(m[0][1] + m[1][0]) / s,
+			(m[0][2] + m[2][0]) / s,
+			(m[2][1] - m[1][2]) / s,
+		]
+	} else if m[1][1] > m[2][2] {
+		let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+		[
+			(m[0][1] + m[1][0]) / s,
+			s / 4.0,
+			(m[1][2] + m[2][1]) / s,
+			(m[0][2] - m[2][0]) / s,
+		]
+	} else {
+		let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+		[
+			(m[0][2] + m[2][0]) / s,
+			(m[1][2] + m[2][1]) / s,
+			s / 4.0,
+			(m[1][0] - m[0][1]) / s,
+		]
+	};
+	let len = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+	if len == 0.0 {
+		[0.0, 0.0, 0.0, 1.0]
+	} else {
+		q.map(|c| c / len)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_matrix_to_quaternion() {
+		let close = |a: Vec4, b: Vec4| a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-5);
+		let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+		assert!(close(matrix_to_quaternion(identity), [0.0, 0.0, 0.0, 1.0]));
+
+		// 90 degrees around z
+		let h = std::f32::consts::FRAC_1_SQRT_2;
+		let rot_z = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+		assert!(close(matrix_to_quaternion(rot_z), [0.0, 0.0, h, h]));
+
+		// 180 degrees around x
+		let rot_x = [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]];
+		assert!(close(matrix_to_quaternion(rot_x), [1.0, 0.0, 0.0, 0.0]));
+
+		// a rotation around the game's z (up) axis is a rotation around y once swizzled
+		let rows = [
+			[0.0, -1.0, 0.0, 0.0],
+			[1.0, 0.0, 0.0, 0.0],
+			[0.0, 0.0, 1.0, 0.0],
+		];
+		assert!(close(
+			matrix_to_quaternion(swizzle_matrix(rows)),
+			[0.0, h, 0.0, h]
+		));
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_keyframes() {
+		let mut settings = crate::ExportSettings::default();
+		let mut gltf = gltf::Gltf::with_settings("test".into(), settings);
+		let channels = ChannelWriter::new(&mut gltf, "test", 6, 30.0);
+		let path = [[0.0], [0.0], [1.0], [1.05], [1.0], [1.0]];
+		assert_eq!(channels.keyframes(&gltf, &path), Some(vec![0, 2, 3, 4, 5]));
+		assert_eq!(channels.keyframes(&gltf, &[[2.0]; 6]), None);
+
+		settings.anim_compression.tolerance = Some(0.1);
+		let gltf = gltf::Gltf::with_settings("test".into(), settings);
+		assert_eq!(channels.keyframes(&gltf, &path), Some(vec![0, 2, 5]));
+
+		settings.anim_compression = crate::AnimCompression {
+			prune: false,
+			tolerance: None,
+			quantize: false,
+		};
+		let gltf = gltf::Gltf::with_settings("test".into(), settings);
+		assert_eq!(
+			channels.keyframes(&gltf, &[[2.0]; 6]),
+			Some(vec![0, 1, 2, 3, 4, 5])
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_morph() {
+		let settings = crate::ExportSettings::default();
+		let mut gltf = gltf::Gltf::with_settings("test".into(), settings);
+		let mut channels = ChannelWriter::new(&mut gltf, "test", 5, 30.0);
+		let node = gltf.create_base_node("PART".into(), None);
+		// two points, moving up then back down
+		let still = vec![Vec3::new(1.0, 0.0, 0.0); 5];
+		let heights = [0.0, 1.0, 2.0, 1.0, 0.0];
+		let moving = heights.map(|y| Vec3::new(0.0, y, 0.0)).to_vec();
+		channels.morph(&mut gltf, node, "PART", &[still, moving]);
+		channels.finish(&mut gltf);
+
+		let json: serde_json::Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		let primitive = &json["meshes"][0]["primitives"][0];
+		assert_eq!(primitive["mode"], 0);
+		// frames 1 and 3 share a pose
+		assert_eq!(primitive["targets"].as_array().unwrap().len(), 2);
+		let channel = &json["animations"][0]["channels"][0];
+		assert_eq!(channel["target"]["path"], "weights");
+		let sampler = &json["animations"][0]["samplers"][0];
+		let times = &json["accessors"][sampler["input"].as_u64().unwrap() as usize];
+		let weights = &json["accessors"][sampler["output"].as_u64().unwrap() as usize];
+		assert_eq!(times["count"], 5);
+		assert_eq!(weights["count"], 5 * 2);
+	}
+
+	#[test]
+	fn test_timing() {
+		let anim = Animation {
+			speed: 2.0,
+			target_vectors: vec![Vec3::default(); 31],
+			reference_points: Vec::new(),
+			parts: Vec::new(),
+		};
+		assert_eq!(anim.frame_rate(30.0), 15.0);
+		assert_eq!(anim.duration(30.0), 2.0);
+		assert_eq!(anim.duration(60.0), 1.0);
+	}
+
+	#[test]
+	fn test_render_preview() {
+		// one point moving from the bottom left to the top right over 10 frames
+		let path: Vec<Vec3> = (0..10)
+			.map(|i| Vec3::new(i as f32, i as f32, 0.0))
+			.collect();
+		let anim = Animation {
+			speed: 1.0,
+			target_vectors: vec![Vec3::default(); 10],
+			reference_points: vec![vec![Vec3::default(); 10]],
+			parts: vec![AnimationPart {
+				name: "PART",
+				point_paths: vec![path],
+				transforms: None,
+			}],
+		};
+
+		let (width, height, pixels) = anim.render_preview(10);
+		assert_eq!((width, height), (8 * 132 - 4, 2 * 132 - 4));
+		let pixel = |x: usize, y: usize| pixels[y * width as usize + x];
+		assert_eq!(pixel(128, 0), 0); // gap
+		assert_eq!(pixel(64, 0), 1); // background
+		// first frame: the point and reference point overlap in the bottom left
+		assert_eq!(pixel(4, 124), 2);
+		// last frame (second row, second cell): the point is in the top right
+		assert_eq!(pixel(132 + 124, 132 + 4), 3);
+		assert_eq!(pixel(132 + 4, 132 + 124), 2);
 	}
 }
@@ -1,5 +1,7 @@
+#[cfg(feature = "export")]
+use crate::OutputWriter;
 use crate::data_formats::mesh::{Mesh, MeshGeo, MeshTri};
-use crate::{OutputWriter, Reader, Vec3};
+use crate::{Reader, Vec3};
 
 /// BSP data for level geometry.
 pub struct Bsp<'a> {
@@ -80,6 +82,8 @@ impl<'a> Bsp<'a> {
 			materials,
 			mesh_data,
 			reference_points: Vec::new(),
+			reference_point_usages: Default::default(),
+			part_usages: Default::default(),
 		};
 
 		mesh.remove_unused_materials();
@@ -87,7 +91,11 @@ impl<'a> Bsp<'a> {
 		Bsp { planes, mesh }
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save_as(&self, name: &str, output: &mut OutputWriter) {
-		self.mesh.save_as(name, output)
+		match self.mesh.with_split(output.export_settings().bsp_split) {
+			Some(mesh) => mesh.save_as(name, output),
+			None => self.mesh.save_as(name, output),
+		}
 	}
 }
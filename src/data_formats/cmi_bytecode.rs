@@ -1,9 +1,219 @@
 //! A mostly reverse-engineered parsing of the game's custom scripting bytecode
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Write;
 
-use crate::{Reader, Vec3};
+use serde::Deserialize;
+
+use crate::{Reader, Vec3, Warnings};
+
+/// User-supplied names for script variables and flags, keyed by target name
+/// (`Global`, `Arena`, `Entity`, `Door`, ...).
+///
+/// Loaded from a JSON file shaped like:
+/// ```json
+/// { "Global": { "vars": { "7": "boss_health" }, "flags": { "3": "door_open" } } }
+/// ```
+/// where variables are keyed by index and flags by bit number.
+#[derive(Default, Deserialize)]
+#[serde(transparent)]
+pub struct CmiSymbols {
+	targets: HashMap<String, CmiTargetSymbols>,
+	/// Decoding overrides for specific opcodes
+	#[serde(skip)]
+	pub opcodes: CmiOpcodes,
+}
+
+#[derive(Default, Deserialize)]
+struct CmiTargetSymbols {
+	#[serde(default)]
+	vars: HashMap<u8, String>,
+	#[serde(default)]
+	flags: HashMap<u8, String>,
+}
+
+impl CmiSymbols {
+	pub fn load(path: impl AsRef<std::path::Path>) -> Self {
+		let path = path.as_ref();
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read symbols file {}: {e}", path.display()));
+		serde_json::from_slice(&data)
+			.unwrap_or_else(|e| panic!("failed to parse symbols file {}: {e}", path.display()))
+	}
+
+	fn target(&self, target: u8) -> Option<&CmiTargetSymbols> {
+		self.targets.get(var_target(target))
+	}
+	fn var_name(&self, target: u8, index: u8) -> Option<&str> {
+		self.target(target)?.vars.get(&index).map(String::as_str)
+	}
+	fn flag_name(&self, target: u8, index: u8) -> Option<&str> {
+		self.target(target)?.flags.get(&index).map(String::as_str)
+	}
+}
+
+/// Overrides for how specific opcodes are decoded, replacing the built-in decoding entirely.
+///
+/// Loaded from a JSON file keyed by hex opcode, with operands given as `type` or `name: type`:
+/// ```json
+/// { "1E": { "name": "Set fog", "operands": ["distance: f32", "colour: u8", "block"] } }
+/// ```
+/// or registered in code with [Self::register] and [Self::register_custom].
+#[derive(Default)]
+pub struct CmiOpcodes(HashMap<u8, CmiOpcode>);
+
+pub struct CmiOpcode {
+	pub name: String,
+	pub decoder: CmiOpcodeDecoder,
+}
+
+pub enum CmiOpcodeDecoder {
+	/// Operands read in order, with optional names for the summary
+	Layout(Vec<(Option<String>, CmiOperand)>),
+	/// Reads the operands and returns their description
+	Custom(Box<dyn Fn(&mut Reader) -> String + Send + Sync>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmiOperand {
+	U8,
+	U16,
+	U32,
+	I32,
+	F32,
+	Vec2,
+	Vec3,
+	/// Length-prefixed string
+	Str,
+	/// Offset of another block in the script, which is then parsed too
+	Block,
+	/// Branch code and its target blocks
+	Branch,
+	/// A variable or constant value
+	Var,
+	Flag,
+}
+
+impl std::str::FromStr for CmiOperand {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(match s {
+			"u8" => CmiOperand::U8,
+			"u16" => CmiOperand::U16,
+			"u32" => CmiOperand::U32,
+			"i32" => CmiOperand::I32,
+			"f32" => CmiOperand::F32,
+			"vec2" => CmiOperand::Vec2,
+			"vec3" => CmiOperand::Vec3,
+			"str" => CmiOperand::Str,
+			"block" => CmiOperand::Block,
+			"branch" => CmiOperand::Branch,
+			"var" => CmiOperand::Var,
+			"flag" => CmiOperand::Flag,
+			_ => return Err(format!("unknown operand type {s}")),
+		})
+	}
+}
+
+impl CmiOpcodes {
+	pub fn load(path: impl AsRef<std::path::Path>) -> Self {
+		#[derive(Deserialize)]
+		struct RawOpcode {
+			name: String,
+			#[serde(default)]
+			operands: Vec<String>,
+		}
+
+		let path = path.as_ref();
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read opcodes file {}: {e}", path.display()));
+		let raw: HashMap<String, RawOpcode> = serde_json::from_slice(&data)
+			.unwrap_or_else(|e| panic!("failed to parse opcodes file {}: {e}", path.display()));
+
+		let mut result = Self::default();
+		for (opcode, raw) in raw {
+			let opcode = opcode.trim_start_matches("0x");
+			let opcode = u8::from_str_radix(opcode, 16)
+				.unwrap_or_else(|_| panic!("invalid opcode {opcode} in {}", path.display()));
+			let operands = raw
+				.operands
+				.iter()
+				.map(|operand| {
+					let (name, kind) = match operand.split_once(':') {
+						Some((name, kind)) => (Some(name.trim().to_owned()), kind.trim()),
+						None => (None, operand.trim()),
+					};
+					(
+						name,
+						kind.parse()
+							.unwrap_or_else(|e| panic!("{e} in {}", path.display())),
+					)
+				})
+				.collect();
+			result.register(opcode, raw.name, operands);
+		}
+		result
+	}
+
+	pub fn register(
+		&mut self, opcode: u8, name: String, operands: Vec<(Option<String>, CmiOperand)>,
+	) {
+		self.insert(opcode, name, CmiOpcodeDecoder::Layout(operands));
+	}
+	pub fn register_custom(
+		&mut self, opcode: u8, name: String,
+		decoder: impl Fn(&mut Reader) -> String + Send + Sync + 'static,
+	) {
+		self.insert(opcode, name, CmiOpcodeDecoder::Custom(Box::new(decoder)));
+	}
+	fn insert(&mut self, opcode: u8, name: String, decoder: CmiOpcodeDecoder) {
+		assert_ne!(opcode, 0xFF, "opcode FF marks the end of a block");
+		assert!(!name.contains([']', '\n']), "invalid opcode name {name:?}");
+		self.0.insert(opcode, CmiOpcode { name, decoder });
+	}
+
+	pub fn get(&self, opcode: u8) -> Option<&CmiOpcode> {
+		self.0.get(&opcode)
+	}
+}
+
+/// Decodes an overridden opcode, returning its operands' description
+fn decode_opcode(
+	opcode: &CmiOpcode, reader: &mut Reader, blocks: &mut Blocks, symbols: &CmiSymbols,
+) -> String {
+	let operands = match &opcode.decoder {
+		CmiOpcodeDecoder::Layout(operands) => operands,
+		CmiOpcodeDecoder::Custom(decoder) => return decoder(reader),
+	};
+	let mut result = String::new();
+	for (i, (name, operand)) in operands.iter().enumerate() {
+		if i != 0 {
+			result.push_str(", ");
+		}
+		match name {
+			Some(name) => write!(result, "{name}: "),
+			None => write!(result, "v{}: ", i + 1),
+		}
+		.unwrap();
+		match operand {
+			CmiOperand::U8 => write!(result, "{}", reader.u8()),
+			CmiOperand::U16 => write!(result, "{}", reader.u16()),
+			CmiOperand::U32 => write!(result, "{}", reader.u32()),
+			CmiOperand::I32 => write!(result, "{}", reader.i32()),
+			CmiOperand::F32 => write!(result, "{}", reader.f32()),
+			CmiOperand::Vec2 => write!(result, "{:?}", reader.vec2()),
+			CmiOperand::Vec3 => write!(result, "{:?}", reader.vec3()),
+			CmiOperand::Str => write!(result, "{}", reader.pascal_str()),
+			CmiOperand::Block => write!(result, "{}", read_block(blocks, reader)),
+			CmiOperand::Branch => write!(result, "{}", branch_code(blocks, reader)),
+			CmiOperand::Var => write!(result, "{}", var_or_data(reader, symbols)),
+			CmiOperand::Flag => write!(result, "{}", flag_var(reader, symbols)),
+		}
+		.unwrap();
+	}
+	result
+}
 
 struct FlagNames<'a> {
 	names: &'a [(u32, &'a str)],
@@ -61,10 +271,31 @@ fn tri_visflag(flag: u8) -> &'static str {
 	TRI_VISFLAGS[flag as usize]
 }
 
-fn push_block(blocks: &mut Vec<u32>, offset: u32) -> BlockInfo {
+/// The blocks found in a script so far, along with the control flow of the current instruction
+struct Blocks {
+	offsets: Vec<u32>,
+	flow: Vec<CmiFlow>,
+}
+impl std::ops::Deref for Blocks {
+	type Target = Vec<u32>;
+	fn deref(&self) -> &Vec<u32> {
+		&self.offsets
+	}
+}
+impl std::ops::DerefMut for Blocks {
+	fn deref_mut(&mut self) -> &mut Vec<u32> {
+		&mut self.offsets
+	}
+}
+
+fn push_block(blocks: &mut Blocks, offset: u32) -> BlockInfo {
+	push_block_flow(blocks, offset, CmiFlow::Block)
+}
+fn push_block_flow(blocks: &mut Blocks, offset: u32, flow: fn(u32) -> CmiFlow) -> BlockInfo {
 	if offset == 0 {
 		return BlockInfo { index: 0, offset };
 	}
+	blocks.flow.push(flow(offset));
 	let index = if let Some(index) = blocks.iter().position(|&o| o == offset) {
 		index
 	} else {
@@ -74,9 +305,14 @@ fn push_block(blocks: &mut Vec<u32>, offset: u32) -> BlockInfo {
 	};
 	BlockInfo { index, offset }
 }
-fn read_block(blocks: &mut Vec<u32>, reader: &mut Reader) -> BlockInfo {
+fn read_block(blocks: &mut Blocks, reader: &mut Reader) -> BlockInfo {
 	push_block(blocks, reader.u32())
 }
+fn read_block_flow(
+	blocks: &mut Blocks, reader: &mut Reader, flow: fn(u32) -> CmiFlow,
+) -> BlockInfo {
+	push_block_flow(blocks, reader.u32(), flow)
+}
 
 fn push_ext_block<'a>(
 	offsets: &mut CmiScript<'a>, target_name: &'a str, target_offset: u32, reason: &'static str,
@@ -139,15 +375,19 @@ impl std::fmt::Display for BranchInfo {
 		}
 	}
 }
-fn branch_code(blocks: &mut Vec<u32>, reader: &mut Reader) -> BranchInfo {
+fn branch_code(blocks: &mut Blocks, reader: &mut Reader) -> BranchInfo {
 	let code = reader.u8();
 	let mut target1 = Default::default();
 	let mut target2 = Default::default();
-	if code == 0xFE {
-		target1 = read_block(blocks, reader);
-		target2 = read_block(blocks, reader);
-	} else if code == 0xFC || code == 0xC {
-		target1 = read_block(blocks, reader);
+	match code {
+		0xFE => {
+			target1 = read_block_flow(blocks, reader, CmiFlow::Call);
+			target2 = read_block_flow(blocks, reader, CmiFlow::Call);
+		}
+		0xFC => target1 = read_block_flow(blocks, reader, CmiFlow::Call),
+		0xC => target1 = read_block_flow(blocks, reader, CmiFlow::Goto),
+		0xFD => blocks.flow.push(CmiFlow::Return),
+		_ => {}
 	}
 	BranchInfo {
 		code,
@@ -211,49 +451,69 @@ fn compare_with<T: std::fmt::Display>(reader: &mut Reader, value: T) -> CompInfo
 	}
 }
 
-struct VarOrData {
+struct VarOrData<'s> {
 	target: u8,
 	value: f32,
 	index: u8,
+	name: Option<&'s str>,
 }
-impl std::fmt::Display for VarOrData {
+impl std::fmt::Display for VarOrData<'_> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		if self.target == 3 {
 			self.value.fmt(f)
 		} else {
-			write!(f, "{}_vars[{}]", var_target(self.target), self.index)
+			write!(f, "{}_vars[{}]", var_target(self.target), self.index)?;
+			if let Some(name) = self.name {
+				write!(f, " ({name})")?;
+			}
+			Ok(())
+		}
+	}
+}
+impl VarOrData<'_> {
+	fn write(&self, action: &'static str, value: f32) -> CmiVarWrite {
+		CmiVarWrite {
+			target: var_target(self.target),
+			index: self.index,
+			action,
+			value,
 		}
 	}
 }
-fn var_or_data(reader: &mut Reader) -> VarOrData {
+fn var_or_data<'s>(reader: &mut Reader, symbols: &'s CmiSymbols) -> VarOrData<'s> {
 	let target = reader.u8();
 	let mut value = 0.0;
 	let mut index = 0;
+	let mut name = None;
 	if target == 3 {
 		value = reader.f32();
 	} else {
 		index = reader.u8();
+		name = symbols.var_name(target, index);
 	}
 	VarOrData {
 		target,
 		value,
 		index,
+		name,
 	}
 }
-fn simple_var(reader: &mut Reader) -> VarOrData {
+fn simple_var<'s>(reader: &mut Reader, symbols: &'s CmiSymbols) -> VarOrData<'s> {
 	let target = reader.u8();
 	let index = reader.u8();
 	VarOrData {
 		target,
 		value: 0.0,
 		index,
+		name: symbols.var_name(target, index),
 	}
 }
-struct FlagVar {
+struct FlagVar<'s> {
 	target: u8,
 	index: u8,
+	name: Option<&'s str>,
 }
-impl std::fmt::Display for FlagVar {
+impl std::fmt::Display for FlagVar<'_> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		let index = self.index & 31;
 
@@ -269,6 +529,10 @@ impl std::fmt::Display for FlagVar {
 			write!(f, "{}_flags[0x{:X}]", var_target(self.target), value)?;
 		}
 
+		if let Some(name) = self.name {
+			write!(f, " ({name})")?;
+		}
+
 		if self.index != index {
 			write!(f, " (index clipped: {})", self.index)
 		} else {
@@ -276,11 +540,15 @@ impl std::fmt::Display for FlagVar {
 		}
 	}
 }
-fn flag_var(reader: &mut Reader) -> FlagVar {
+fn flag_var<'s>(reader: &mut Reader, symbols: &'s CmiSymbols) -> FlagVar<'s> {
 	let target = reader.u8();
 	let index = reader.u8();
 	//assert_eq!(index & !31, 0, "flag value out of range");
-	FlagVar { target, index }
+	FlagVar {
+		target,
+		index,
+		name: symbols.flag_name(target, index & 31),
+	}
 }
 
 static DOOR_FLAG_NAMES: &[(u32, &str)] = &[
@@ -307,13 +575,162 @@ fn get_anim_name<'a>(reader: &Reader<'a>, anim_offset: u32) -> Option<&'a str> {
 #[derive(Default)]
 pub struct CmiScript<'a> {
 	pub summary: String,
+	/// The data the script was read from, which instruction offsets are relative to
+	pub data: &'a [u8],
 
 	pub anim_names: Vec<&'a str>,
 	pub anim_offsets: Vec<u32>,
 	pub path_offsets: Vec<u32>,
+	pub sounds: Vec<CmiSoundRef<'a>>,
+	/// Uses of the entity's mesh reference points
+	pub point_refs: Vec<CmiPointRef<'a>>,
+	/// Entities spawned at fixed positions
+	pub spawns: Vec<CmiSpawn<'a>>,
+	/// Mesh parts the script hides, shows or blows off
+	pub part_refs: Vec<CmiPartRef<'a>>,
+	/// Arenas the script shows or loads
+	pub arena_refs: Vec<CmiArenaRef<'a>>,
+	/// Variables the script sets or adds to
+	pub var_writes: Vec<CmiVarWrite>,
+	/// Health values the script sets (not counting destroying the entity)
+	pub health_values: Vec<u16>,
+	/// The first yaw the script sets, in degrees
+	pub yaw: Option<i16>,
 
 	pub called_scripts: Vec<CmiCalledScript<'a>>,
 	pub call_origins: Vec<CmiCallOrigin<'a>>, // used by caller cmi
+
+	pub instructions: Vec<CmiInstruction>,
+	/// Byte ranges of each parsed block (including the end marker)
+	pub block_ranges: Vec<std::ops::Range<u32>>,
+	/// Offsets of the blocks that ran into the end of the data before their end marker
+	pub truncated_blocks: Vec<u32>,
+	/// Byte ranges of the blocks that reached an invalid opcode, so were left out along with any blocks they found
+	pub quarantined_blocks: Vec<std::ops::Range<u32>>,
+	/// Invalid and unfinished instructions
+	pub warnings: Warnings<'a>,
+}
+
+/// A single parsed instruction, kept for opcode statistics
+#[derive(Debug)]
+pub struct CmiInstruction {
+	pub offset: u32,
+	pub size: u32,
+	pub opcode: u8,
+	/// Description from the summary (eg. `Set animation`)
+	pub name: String,
+	/// Every way the instruction might leave its block, in operand order
+	pub flow: Vec<CmiFlow>,
+}
+
+/// A way an instruction can leave the straight-line flow of its block.
+///
+/// Most are conditional, but the conditions can't be evaluated without running the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmiFlow {
+	/// Might run the block and then carry on
+	Call(u32),
+	/// Might carry on from the block instead
+	Goto(u32),
+	/// Might return from the current block
+	Return,
+	/// Always returns from the current block
+	AlwaysReturn,
+	/// Refers to a block that's run some other way (e.g. as a callback)
+	Block(u32),
+}
+impl CmiInstruction {
+	pub fn is_invalid(&self) -> bool {
+		self.name == "Invalid!"
+	}
+	/// Whether the opcode's meaning is still mostly guesswork
+	pub fn is_partially_known(&self) -> bool {
+		let name = self.name.to_ascii_lowercase();
+		["some", "?", "unknown", "weird", "nothing"]
+			.iter()
+			.any(|word| name.contains(word))
+	}
+}
+
+/// A sound played by a script, and where it's played from.
+///
+/// Sounds without a position or point are played from the entity itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CmiSoundRef<'a> {
+	pub name: &'a str,
+	pub position: Option<Vec3>,
+	/// The entity's mesh reference point the sound is played from
+	pub point: Option<u8>,
+	/// Flags from the play sound instruction selecting the position (0 for other instructions),
+	/// the rest of which probably control how it's heard
+	/// (`0x10`: fixed start position, `0x20`: reference point, `0x40`: fixed position)
+	pub sound_type: u8,
+}
+
+/// An instruction that uses one of the entity's mesh reference points as a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmiPointRef<'a> {
+	pub index: u8,
+	/// What happens at the point (eg. `Spawn alien`)
+	pub action: &'static str,
+	/// What is spawned there, if anything
+	pub target: &'a str,
+}
+
+/// An instruction that changes one of the entity's mesh parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmiPartRef<'a> {
+	pub name: &'a str,
+	/// `Hide`, `Show` or `Blow off`
+	pub action: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmiArenaRef<'a> {
+	pub name: &'a str,
+	/// `Show` or `Load`
+	pub action: &'static str,
+}
+
+/// An instruction that changes one of the script variables
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CmiVarWrite {
+	/// Whose variables (`Global`, `Arena`, `Entity`...)
+	pub target: &'static str,
+	pub index: u8,
+	/// `Set`, `Add` or `Add per second`
+	pub action: &'static str,
+	pub value: f32,
+}
+
+impl std::fmt::Display for CmiVarWrite {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let op = match self.action {
+			"Set" => "=",
+			_ => "+=",
+		};
+		write!(
+			f,
+			"{}_vars[{}] {op} {}",
+			self.target, self.index, self.value
+		)?;
+		if self.action == "Add per second" {
+			f.write_str(" * dt")?;
+		}
+		Ok(())
+	}
+}
+
+/// An entity spawned by a script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CmiSpawn<'a> {
+	pub name: &'a str,
+	/// Empty if not given
+	pub arena: &'a str,
+	pub position: Vec3,
+	/// Raw spawn angle (presumably degrees), for spawn opcodes that have one
+	pub angle: Option<f32>,
+	pub init_offset: u32,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -332,13 +749,123 @@ pub struct CmiCallOrigin<'a> {
 }
 
 impl<'a> CmiScript<'a> {
-	pub fn parse(mut reader: Reader<'a>) -> Self {
-		parse_cmi(&mut reader)
+	pub fn parse(reader: Reader<'a>) -> Self {
+		Self::parse_with_symbols(reader, &CmiSymbols::default())
+	}
+	pub fn parse_with_symbols(mut reader: Reader<'a>, symbols: &CmiSymbols) -> Self {
+		parse_cmi(&mut reader, symbols)
+	}
+
+	/// The summary with each instruction's bytes in hex underneath it,
+	/// to check the decoded operands against
+	pub fn disassembly(&self) -> String {
+		let instructions: HashMap<u32, &CmiInstruction> = self
+			.instructions
+			.iter()
+			.map(|instruction| (instruction.offset, instruction))
+			.collect();
+		let mut result = String::with_capacity(self.summary.len() * 2);
+		for line in self.summary.lines() {
+			result.push_str(line);
+			result.push('\n');
+			let Some(instruction) = line
+				.strip_prefix('[')
+				.and_then(|line| line.split_once(": "))
+				.and_then(|(offset, _)| u32::from_str_radix(offset, 16).ok())
+				.and_then(|offset| instructions.get(&offset))
+			else {
+				continue;
+			};
+			let start = instruction.offset as usize;
+			let bytes = self
+				.data
+				.get(start..start + instruction.size as usize)
+				.unwrap_or_default();
+			write_hex(&mut result, bytes, start);
+		}
+		result
+	}
+}
+
+/// Writes `data` (found at `offset`) as indented lines of hex
+fn write_hex(result: &mut String, data: &[u8], offset: usize) {
+	const BYTES_PER_LINE: usize = 16;
+	for (index, chunk) in data.chunks(BYTES_PER_LINE).enumerate() {
+		write!(result, "\t\t{:06X}:", offset + index * BYTES_PER_LINE).unwrap();
+		for byte in chunk {
+			write!(result, " {byte:02X}").unwrap();
+		}
+		result.push('\n');
 	}
 }
 
-fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
-	let mut result = CmiScript::default();
+/// Everything parsed before a block, so a block that turns out not to be bytecode
+/// (usually from a mis-decoded operand) can be undone along with any blocks it found
+struct BlockMark {
+	summary: usize,
+	blocks: usize,
+	instructions: usize,
+	anim_names: usize,
+	anim_offsets: usize,
+	path_offsets: usize,
+	sounds: usize,
+	point_refs: usize,
+	spawns: usize,
+	part_refs: usize,
+	arena_refs: usize,
+	var_writes: usize,
+	health_values: usize,
+	called_scripts: usize,
+	yaw: Option<i16>,
+}
+
+impl BlockMark {
+	fn new(script: &CmiScript, summary: &str, blocks: &Blocks) -> Self {
+		Self {
+			summary: summary.len(),
+			blocks: blocks.len(),
+			instructions: script.instructions.len(),
+			anim_names: script.anim_names.len(),
+			anim_offsets: script.anim_offsets.len(),
+			path_offsets: script.path_offsets.len(),
+			sounds: script.sounds.len(),
+			point_refs: script.point_refs.len(),
+			spawns: script.spawns.len(),
+			part_refs: script.part_refs.len(),
+			arena_refs: script.arena_refs.len(),
+			var_writes: script.var_writes.len(),
+			health_values: script.health_values.len(),
+			called_scripts: script.called_scripts.len(),
+			yaw: script.yaw,
+		}
+	}
+
+	/// Undoes everything since the mark (except warnings)
+	fn rollback(&self, script: &mut CmiScript, summary: &mut String, blocks: &mut Blocks) {
+		summary.truncate(self.summary);
+		blocks.truncate(self.blocks);
+		script.instructions.truncate(self.instructions);
+		script.anim_names.truncate(self.anim_names);
+		script.anim_offsets.truncate(self.anim_offsets);
+		script.path_offsets.truncate(self.path_offsets);
+		script.sounds.truncate(self.sounds);
+		script.point_refs.truncate(self.point_refs);
+		script.spawns.truncate(self.spawns);
+		script.part_refs.truncate(self.part_refs);
+		script.arena_refs.truncate(self.arena_refs);
+		script.var_writes.truncate(self.var_writes);
+		script.health_values.truncate(self.health_values);
+		script.called_scripts.truncate(self.called_scripts);
+		script.yaw = self.yaw;
+	}
+}
+
+fn parse_cmi<'a>(reader: &mut Reader<'a>, symbols: &CmiSymbols) -> CmiScript<'a> {
+	let mut result = CmiScript {
+		data: reader.buf(),
+		warnings: Warnings::new(reader.buf()),
+		..Default::default()
+	};
 
 	if reader.position() == 0 {
 		return result;
@@ -346,6 +873,8 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 
 	let mut summary = String::new();
 	let offsets = &mut result;
+	// a cut short script ends its block instead of the whole cmi
+	reader.set_lenient(true);
 
 	macro_rules! w {
 		()=>{};
@@ -360,7 +889,23 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 		};
 	}
 
-	let mut blocks = vec![reader.position() as u32];
+	let mut blocks = Blocks {
+		offsets: vec![reader.position() as u32],
+		flow: Vec::new(),
+	};
+	let mut pending_name_start = None;
+	macro_rules! finish_instruction {
+		() => {
+			if let Some(name_start) = pending_name_start.take() {
+				let instruction = offsets.instructions.last_mut().unwrap();
+				instruction.size = reader.position() as u32 - instruction.offset;
+				instruction.flow = std::mem::take(&mut blocks.flow);
+				let name = &summary[name_start..];
+				let name_end = name.find([']', '\n']).unwrap_or(name.len());
+				instruction.name = name[..name_end].to_owned();
+			}
+		};
+	}
 	let mut block_index = 0;
 
 	while block_index < blocks.len() {
@@ -373,18 +918,49 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 		}
 
 		reader.set_position(block_offset as usize);
+		reader.clear_overrun();
+		let mark = BlockMark::new(offsets, &summary, &blocks);
+		let mut truncated = false;
+		let mut invalid = false;
 		loop {
+			finish_instruction!();
 			let cmd_offset = reader.position();
+			if reader.overran() || reader.is_empty() {
+				truncated = true;
+				break;
+			}
 			let cmd = reader.u8();
 			if cmd == 0xFF {
 				break;
 			}
 			w!("[{cmd_offset:06X}: {cmd:02X} ");
+			pending_name_start = Some(summary.len());
+			offsets.instructions.push(CmiInstruction {
+				offset: cmd_offset as u32,
+				size: 0,
+				opcode: cmd,
+				name: String::new(),
+				flow: Vec::new(),
+			});
+
+			if let Some(opcode) = symbols.opcodes.get(cmd) {
+				let operands = decode_opcode(opcode, reader, &mut blocks, symbols);
+				if operands.is_empty() {
+					wl!("{}]", opcode.name);
+				} else {
+					wl!("{}] {operands}", opcode.name);
+				}
+				continue;
+			}
 
 			match cmd {
 				0x0 | 0x7 | 0x1E | 0xFE | 0xFF => {
-					eprintln!("invalid opcode {cmd:02X} at {cmd_offset:06X}!");
+					let data = &reader.buf()[cmd_offset..reader.position()];
+					offsets
+						.warnings
+						.push(format!("invalid opcode {cmd:02X}"), data);
 					wl!("Invalid!]");
+					invalid = true;
 					break;
 				}
 				0x01 => {
@@ -425,7 +1001,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let mut target_script = None;
 					if order_code == 7 {
 						let code = reader.u8();
-						assert!(code == 0xFC || code == 0xC);
+						assert!(code == 0xFC || code == 0xC || reader.overran());
 						let target = reader.u32();
 						target_script = Some(target);
 						w!("Run script ({target:06X})");
@@ -493,6 +1069,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 				}
 				0x08 => {
 					let angle = reader.i16().rem_euclid(360);
+					offsets.yaw.get_or_insert(angle);
 					wl!("Set yaw] angle: {angle}");
 				}
 				0x09 => {
@@ -540,6 +1117,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					if value == 0 {
 						wl!("Destroy entity]");
 					} else {
+						offsets.health_values.push(value);
 						wl!(
 							"Set entity health] value: {value}, (some flag set: {})",
 							64999 < value
@@ -606,6 +1184,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					w!("Hide parts] names: [");
 					for i in 0..count {
 						let part_name = reader.pascal_str();
+						offsets.part_refs.push(CmiPartRef {
+							name: part_name,
+							action: "Hide",
+						});
 						if i != 0 {
 							w!(", ");
 						}
@@ -618,6 +1200,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					w!("Show parts] names: [");
 					for i in 0..count {
 						let name = reader.pascal_str();
+						offsets.part_refs.push(CmiPartRef {
+							name,
+							action: "Show",
+						});
 						if i != 0 { w!(", {name}") } else { w!("{name}") }
 					}
 					wl!("]");
@@ -649,11 +1235,11 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch on vertical velocity] if {comp} {branch}");
 				}
 				0x27 => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Anim some facing value] {var_data}");
 				}
 				0x28 => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Anim facing yaw value] {var_data}");
 				}
 				0x29 => {
@@ -702,7 +1288,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 				}
 				0x32..=0x35 => {
 					let index = (cmd - 0x31) % 4;
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Set entity someCmiDataValue] values[{index}] = {var_data}");
 				}
 				0x36 => {
@@ -711,7 +1297,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch on distance to something] if {comp} {branch}");
 				}
 				0x37 => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Set entity someCmiDataValue] values[5] = {var_data}");
 				}
 				0x38 => {
@@ -725,7 +1311,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch if visible] distance: {distance}, angle: {angle}, {branch}");
 				}
 				0x3A => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Set anim framerate] framerate: {var_data}");
 				}
 				0x3B => {
@@ -753,6 +1339,11 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let name2 = reader.pascal_str();
 					let target = read_ext_block(reader, offsets, name2, "Spawn (3D)");
 					if has_name == 0 {
+						offsets.point_refs.push(CmiPointRef {
+							index: point_index,
+							action: "Spawn badguy",
+							target: name2,
+						});
 						wl!(
 							"Spawn badguy] point index: {point_index}, name: {name2}, target: {target}"
 						);
@@ -770,39 +1361,41 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("{} flag 0x10]", if set { "Set" } else { "Clear" });
 				}
 				0x40 => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Delay] {var_data}");
 				}
 				0x41 => {
-					let var = simple_var(reader);
+					let var = simple_var(reader, symbols);
 					let value = reader.f32();
+					offsets.var_writes.push(var.write("Set", value));
 					wl!("Set Variable] {var} = {value}");
 				}
 				0x42 => {
-					let var = simple_var(reader);
+					let var = simple_var(reader, symbols);
 					let value = reader.f32();
+					offsets.var_writes.push(var.write("Add", value));
 					wl!("Add to variable] {var} += {value}");
 				}
 				0x43 => {
-					let var = simple_var(reader);
+					let var = simple_var(reader, symbols);
 					let comp = compare_with(reader, var);
 					let branch = branch_code(&mut blocks, reader);
 					wl!("Branch on variable compare] if {comp} {branch}");
 				}
 				0x44 => {
-					let flag = flag_var(reader);
+					let flag = flag_var(reader, symbols);
 					wl!("Set flag var] {flag} = true");
 				}
 				0x45 => {
-					let flag = flag_var(reader);
+					let flag = flag_var(reader, symbols);
 					wl!("Clear flag var] {flag} = false");
 				}
 				0x46 => {
-					let flag = flag_var(reader);
+					let flag = flag_var(reader, symbols);
 					wl!("Toggle flag var] {flag} = (toggle)");
 				}
 				0x47 | 0x48 => {
-					let flag = flag_var(reader);
+					let flag = flag_var(reader, symbols);
 					let branch = branch_code(&mut blocks, reader);
 					let condition = if cmd == 0x47 { "== true" } else { "== false" };
 					wl!("Branch on flag var] if {flag} {condition} {branch}");
@@ -843,11 +1436,11 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 				}
 				0x51 => {
 					let scale_dt = reader.u8() == 1;
-					let speed = var_or_data(reader);
+					let speed = var_or_data(reader, symbols);
 					wl!("Move in facing dir?] speed: {speed}, use dt: {scale_dt}");
 				}
 				0x52 => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Set somedata2] {var_data}");
 				}
 				0x53 => {
@@ -855,7 +1448,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let code = reader.u8();
 					if code != 0xFF {
 						reader.set_position(pos);
-						let var_data = var_or_data(reader);
+						let var_data = var_or_data(reader, symbols);
 						wl!("Set maybeRadius] {var_data}");
 					} else {
 						let target = reader.f32();
@@ -864,7 +1457,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					}
 				}
 				0x54 => {
-					let value = var_or_data(reader);
+					let value = var_or_data(reader, symbols);
 					wl!("Set someCmiField11] {value}");
 				}
 				0x55 => {
@@ -875,6 +1468,13 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let pos = reader.vec3();
 					let name = reader.pascal_str();
 					let target = read_ext_block(reader, offsets, name, "Spawn (56)");
+					offsets.spawns.push(CmiSpawn {
+						name,
+						arena: "",
+						position: pos.swizzle(),
+						angle: None,
+						init_offset: target.offset,
+					});
 					wl!("Spawn entity 3] name: {name}, pos: {pos:?}, init: {target}");
 				}
 				0x57 => {
@@ -912,6 +1512,19 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					}
 
 					let sound_name = reader.pascal_str();
+					offsets.sounds.push(CmiSoundRef {
+						name: sound_name,
+						position: point1.map(Vec3::swizzle),
+						point: point1_index,
+						sound_type,
+					});
+					if let Some(index) = point1_index {
+						offsets.point_refs.push(CmiPointRef {
+							index,
+							action: "Play sound",
+							target: sound_name,
+						});
+					}
 
 					w!("Play? Sound] name: {sound_name}, type: {sound_type:X}");
 					let mut print_sound = |prefix, data, index| {
@@ -934,7 +1547,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Nothing?] name: {name}, value: {value}");
 				}
 				0x5B => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Set entity someCmiField4] {var_data}");
 				}
 
@@ -995,6 +1608,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 				}
 				0x64 => {
 					let name = reader.pascal_str();
+					offsets.arena_refs.push(CmiArenaRef {
+						name,
+						action: "Show",
+					});
 					wl!("Show arena] name: {name}");
 				}
 				0x65 => {
@@ -1024,6 +1641,12 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 				}
 				0x6B => {
 					let name = reader.pascal_str();
+					offsets.sounds.push(CmiSoundRef {
+						name,
+						position: None,
+						point: None,
+						sound_type: 0,
+					});
 					wl!("Start sound] sound: {name}");
 				}
 				0x6C => {
@@ -1055,6 +1678,13 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let pos = reader.vec3();
 					let name = reader.pascal_str();
 					let init_target = read_ext_block(reader, offsets, name, "Spawn (71)");
+					offsets.spawns.push(CmiSpawn {
+						name,
+						arena: "",
+						position: pos.swizzle(),
+						angle: None,
+						init_offset: init_target.offset,
+					});
 					wl!("Spawn alien] pos: {pos:?}, name: {name}, init target: {init_target}");
 				}
 				0x72 => {
@@ -1131,6 +1761,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					w!("Blow off parts] kind: {kind}, parts: [");
 					for i in 0..count {
 						let name = reader.pascal_str();
+						offsets.part_refs.push(CmiPartRef {
+							name,
+							action: "Blow off",
+						});
 						if i != 0 {
 							w!(", {name}");
 						} else {
@@ -1162,6 +1796,11 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 						if point_index == 0xFF {
 							wl!("Create bubble] chance: {value1}%, pos: {pos:?}");
 						} else {
+							offsets.point_refs.push(CmiPointRef {
+								index: point_index,
+								action: "Create bubble",
+								target: "",
+							});
 							wl!(
 								"Create bubble] chance: {value1}%, pos: somePoints[{point_index:?}]"
 							);
@@ -1177,7 +1816,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Do something with material] name: {name}, code: {code}, value: {value}");
 				}
 				0x86 => {
-					let var_data = var_or_data(reader);
+					let var_data = var_or_data(reader, symbols);
 					wl!("Add angle1] {var_data}");
 				}
 				0x87 => {
@@ -1283,6 +1922,13 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let object_name = reader.pascal_str();
 					let arena_name = reader.pascal_str();
 					let init_target = read_ext_block(reader, offsets, object_name, "Spawn Door");
+					offsets.spawns.push(CmiSpawn {
+						name: object_name,
+						arena: arena_name,
+						position: position.swizzle(),
+						angle: Some(angle),
+						init_offset: init_target.offset,
+					});
 					wl!(
 						"Spawn Door] pos: {position:?}, angle: {angle}, id: {id}, name: {object_name}, arena: {arena_name}, init target: {init_target}"
 					);
@@ -1312,6 +1958,21 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let close_sound = reader.pascal_str();
 					let open_finish_sound = reader.pascal_str();
 					let close_finish_sound = reader.pascal_str();
+					for name in [
+						open_sound,
+						close_sound,
+						open_finish_sound,
+						close_finish_sound,
+					] {
+						if !name.is_empty() {
+							offsets.sounds.push(CmiSoundRef {
+								name,
+								position: None,
+								point: None,
+								sound_type: 0,
+							});
+						}
+					}
 					wl!(
 						"Set door sounds] open: \"{open_sound}\", close: \"{close_sound}\", open finish: \"{open_finish_sound}\", close finish: \"{close_finish_sound}\""
 					);
@@ -1332,7 +1993,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Wait for anim progress] value: {value}");
 				}
 				0x9B => {
-					let value = var_or_data(reader);
+					let value = var_or_data(reader, symbols);
 					let branch = branch_code(&mut blocks, reader);
 					wl!("Branch on some stack value] value: {value}, {branch}");
 				}
@@ -1340,6 +2001,11 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let index = reader.u8();
 					let name = reader.pascal_str();
 					let init_target = read_ext_block(reader, offsets, name, "Spawn (9C)");
+					offsets.point_refs.push(CmiPointRef {
+						index,
+						action: "Spawn alien",
+						target: name,
+					});
 					wl!(
 						"Spawn alien] name: {name}, position: somePoints[{index}], init target: {init_target}"
 					);
@@ -1380,7 +2046,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 						0 => Vec3::default(),
 						1 | 2 => reader.vec3(),
 						n => {
-							println!("invalid 0x9f opcode {n}");
+							let data = &reader.buf()[cmd_offset..reader.position()];
+							offsets
+								.warnings
+								.push(format!("invalid 0x9F position type {n}"), data);
 							Vec3::default()
 						}
 					};
@@ -1444,7 +2113,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Set triangle vis? 2] id: {triangle_id}, num: {num}");
 				}
 				0xA9 => {
-					let data = var_or_data(reader);
+					let data = var_or_data(reader, symbols);
 					wl!("Set someCmiData3] value = {data}");
 				}
 				0xAA => {
@@ -1499,7 +2168,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch on flags 0x40000] {branch}");
 				}
 				0xB1 => {
-					let value = var_or_data(reader);
+					let value = var_or_data(reader, symbols);
 					wl!("Set some damage radius] value = {value}");
 				}
 				0xB2 => {
@@ -1553,15 +2222,16 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					}
 				}
 				0xB6 => {
-					eprintln!("encountered unfinished opcode 0xB6 at {block_offset:06X}");
-					let var = simple_var(reader);
+					let var = simple_var(reader, symbols);
 					let value = reader.f32();
+					let data = &reader.buf()[cmd_offset..reader.position()];
+					offsets.warnings.push("unfinished opcode B6", data);
 					// target?
 					// todo probably broken
 					wl!("Weird] var: {var}, value: {value}");
 				}
 				0xB7 => {
-					let var = simple_var(reader);
+					let var = simple_var(reader, symbols);
 					let count = reader.u8();
 					w!("Call by var index] index: {var}, targets: [");
 					for i in 0..count {
@@ -1668,8 +2338,9 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch on some alien value] value: {value}, {branch}");
 				}
 				0xC4 => {
-					eprintln!("encountered unfinished opcode 0xC4 at {block_offset:06X}");
-					let num = var_or_data(reader);
+					let num = var_or_data(reader, symbols);
+					let data = &reader.buf()[cmd_offset..reader.position()];
+					offsets.warnings.push("unfinished opcode C4", data);
 					wl!("Set dtiArenaNum] num: {num}");
 					// todo breaks out of loop here?
 				}
@@ -1687,7 +2358,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					);
 				}
 				0xC7 => {
-					let data = var_or_data(reader);
+					let data = var_or_data(reader, symbols);
 					wl!("Set someCmiData] {data}");
 				}
 				0xC8 => {
@@ -1749,7 +2420,7 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch on some alien stuff] {branch}");
 				}
 				0xD2 => {
-					let value = var_or_data(reader);
+					let value = var_or_data(reader, symbols);
 					wl!("Set someScale] scale: {value}");
 				}
 				0xD3 => {
@@ -1770,12 +2441,13 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Branch on angle to thing] if {comp} {branch}");
 				}
 				0xD7 => {
-					let value = var_or_data(reader);
+					let value = var_or_data(reader, symbols);
 					wl!("Increase some global field to value] value: {value}");
 				}
 				0xD8 => {
-					let var = simple_var(reader);
+					let var = simple_var(reader, symbols);
 					let value = reader.f32();
+					offsets.var_writes.push(var.write("Add per second", value));
 					wl!("Add var] {var} += {value} * dt");
 				}
 				0xD9 => {
@@ -1809,6 +2481,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 				}
 				0xDF => {
 					let name = reader.pascal_str();
+					offsets.arena_refs.push(CmiArenaRef {
+						name,
+						action: "Load",
+					});
 					wl!("Load arena] name: {name}");
 				}
 				0xE0 => {
@@ -1832,7 +2508,8 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					wl!("Set some stuff] pos: {pos:?}, value1: {value1}, value2: {value2}");
 				}
 				0xE3 => {
-					eprintln!("encountered unfinished opcode 0xB6 at {block_offset:06X}");
+					let data = &reader.buf()[cmd_offset..reader.position()];
+					offsets.warnings.push("unfinished opcode E3", data);
 					wl!("?]");
 					// todo
 					break;
@@ -1894,7 +2571,10 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let component = match reader.u8() {
 						n if n < 3 => (b'x' + n) as char,
 						n => {
-							eprintln!("invalid opcode 0xEE component {n}");
+							let data = &reader.buf()[cmd_offset..reader.position()];
+							offsets
+								.warnings
+								.push(format!("invalid 0xEE component {n}"), data);
 							'?'
 						}
 					};
@@ -2028,19 +2708,43 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 					let count = reader.u8();
 					w!("Random call] targets:");
 					for _ in 0..count {
-						let target = read_block(&mut blocks, reader);
+						let target = read_block_flow(&mut blocks, reader, CmiFlow::Call);
 						w!(" {target}");
 					}
 					wl!();
 				}
 				0xFD => {
+					blocks.flow.push(CmiFlow::AlwaysReturn);
 					wl!("Return]");
 				}
 			}
 		}
-		wl!("(end offset {:06X})\n", reader.position());
+		finish_instruction!();
+		let block_range = block_offset..reader.position() as u32;
+		if invalid {
+			mark.rollback(offsets, &mut summary, &mut blocks);
+			wl!("(quarantined, not valid bytecode)");
+			let data = &reader.buf()[block_offset as usize..reader.position()];
+			write_hex(&mut summary, data, block_offset as usize);
+			offsets.quarantined_blocks.push(block_range.clone());
+		}
+		offsets.block_ranges.push(block_range);
+		if truncated {
+			let data = reader
+				.buf()
+				.get(block_offset as usize..)
+				.unwrap_or_default();
+			offsets
+				.warnings
+				.push(format!("block at {block_offset:06X} truncated"), data);
+			offsets.truncated_blocks.push(block_offset);
+			wl!("(truncated at {:06X})\n", reader.position());
+		} else {
+			wl!("(end offset {:06X})\n", reader.position());
+		}
 		block_index += 1;
 	}
+	reader.set_lenient(false);
 
 	result.summary = summary;
 	result.anim_names.sort_unstable();
@@ -2057,6 +2761,296 @@ fn parse_cmi<'a>(reader: &mut Reader<'a>) -> CmiScript<'a> {
 
 #[cfg(test)]
 mod tests {
+	use super::*;
+
+	#[test]
+	fn test_symbols() {
+		let symbols: CmiSymbols = serde_json::from_str(
+			r#"{ "Global": { "vars": { "7": "boss_health" } }, "Door": { "flags": { "6": "locked" } } }"#,
+		)
+		.unwrap();
+
+		let data = [0u8, 7, 0, 8, 5, 6];
+		let mut reader = Reader::new(&data);
+		assert_eq!(
+			simple_var(&mut reader, &symbols).to_string(),
+			"Global_vars[7] (boss_health)"
+		);
+		assert_eq!(
+			simple_var(&mut reader, &symbols).to_string(),
+			"Global_vars[8]"
+		);
+		assert_eq!(
+			flag_var(&mut reader, &symbols).to_string(),
+			"Door_flags[LOCKED (0x40)] (locked)"
+		);
+	}
+
+	#[test]
+	fn test_instructions() {
+		// padding, set resume point, return, end
+		let data = [0xFFu8, 0x01, 0xFD, 0xFF];
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+
+		let opcodes: Vec<_> = script
+			.instructions
+			.iter()
+			.map(|i| (i.offset, i.size, i.opcode, i.name.as_str()))
+			.collect();
+		assert_eq!(
+			opcodes,
+			[
+				(1, 1, 0x01, "Set script resume point"),
+				(2, 1, 0xFD, "Return")
+			]
+		);
+		assert_eq!(script.block_ranges, vec![1..4]);
+	}
+
+	#[test]
+	fn test_disassembly() {
+		// sound from a fixed position, return, end
+		let mut data = vec![0xFFu8, 0x59, 0x40];
+		data.extend([1.0f32, 2.0, 3.0].iter().flat_map(|f| f.to_le_bytes()));
+		data.push(0x04);
+		data.extend(b"BOOP");
+		data.extend([0xFD, 0xFF]);
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+
+		// long instructions wrap every 16 bytes, lines that aren't instructions have no bytes
+		assert_eq!(
+			script.disassembly(),
+			"main (offset 000001)
+[000001: 59 Play? Sound] name: BOOP, type: 40, p1: data (Vec3(1.0, 2.0, 3.0)), p2: data (Vec3(1.0, 2.0, 3.0))
+\t\t000001: 59 40 00 00 80 3F 00 00 00 40 00 00 40 40 04 42
+\t\t000011: 4F 4F 50
+[000014: FD Return]
+\t\t000014: FD
+(end offset 000016)
+
+"
+		);
+		assert!(!script.summary.contains("\t\t"));
+	}
+
+	#[test]
+	fn test_sound_refs() {
+		// sound from reference point 2, sound from a fixed position, return, end
+		let mut data = vec![0xFFu8, 0x59, 0x20, 0x02, 0x04];
+		data.extend(b"BEEP");
+		data.extend([0x59, 0x40]);
+		data.extend([1.0f32, 2.0, 3.0].iter().flat_map(|f| f.to_le_bytes()));
+		data.push(0x04);
+		data.extend(b"BOOP");
+		data.extend([0xFD, 0xFF]);
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+
+		assert_eq!(
+			script.sounds,
+			[
+				CmiSoundRef {
+					name: "BEEP",
+					position: None,
+					point: Some(2),
+					sound_type: 0x20,
+				},
+				CmiSoundRef {
+					name: "BOOP",
+					position: Some(Vec3::new(1.0, 3.0, -2.0)),
+					point: None,
+					sound_type: 0x40,
+				},
+			]
+		);
+		assert_eq!(
+			script.point_refs,
+			[CmiPointRef {
+				index: 2,
+				action: "Play sound",
+				target: "BEEP",
+			}]
+		);
+	}
+
+	#[test]
+	fn test_opcode_overrides() {
+		// unknown opcode with a block, overridden return, end, then the block
+		let data = [
+			0xFFu8, 0x1E, 0x00, 0x00, 0x80, 0x3F, 0x0D, 0x00, 0x00, 0x00, 0xFD, 0x02, 0xFF, 0xFF,
+		];
+		let mut symbols = CmiSymbols::default();
+		symbols.opcodes.register(
+			0x1E,
+			"Set fog".to_owned(),
+			vec![
+				(Some("distance".to_owned()), CmiOperand::F32),
+				(None, CmiOperand::Block),
+			],
+		);
+		symbols
+			.opcodes
+			.register_custom(0xFD, "Return with code".to_owned(), |reader| {
+				format!("code: {}", reader.u8())
+			});
+		let script = CmiScript::parse_with_symbols(Reader::new(&data).clone_at(1), &symbols);
+
+		let names: Vec<_> = script
+			.instructions
+			.iter()
+			.map(|i| (i.offset, i.size, i.name.as_str()))
+			.collect();
+		assert_eq!(names, [(1, 9, "Set fog"), (10, 2, "Return with code")]);
+		assert_eq!(script.block_ranges, vec![1..13, 13..14]);
+		assert!(
+			script
+				.summary
+				.contains("Set fog] distance: 1, v2: block_1 (00000D)\n")
+		);
+		assert!(script.summary.contains("Return with code] code: 2\n"));
+		assert!(
+			script
+				.disassembly()
+				.contains("Return with code] code: 2\n\t\t00000A: FD 02\n")
+		);
+	}
+
+	#[test]
+	fn test_spawns() {
+		let mut data = vec![0xFF, 0x95];
+		for n in [1.0f32, 2.0, 3.0, 90.0] {
+			data.extend_from_slice(&n.to_le_bytes());
+		}
+		data.extend_from_slice(&5i32.to_le_bytes());
+		data.extend_from_slice(b"\x04DOOR\x07ARENA_1");
+		data.extend_from_slice(&0x123456u32.to_le_bytes());
+		// set yaw to -90, then 45
+		data.extend_from_slice(&[0x08, 0xA6, 0xFF, 0x08, 45, 0, 0xFF]);
+
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		assert_eq!(
+			script.spawns,
+			[CmiSpawn {
+				name: "DOOR",
+				arena: "ARENA_1",
+				position: Vec3::new(1.0, 3.0, -2.0),
+				angle: Some(90.0),
+				init_offset: 0x123456,
+			}]
+		);
+		assert_eq!(script.yaw, Some(270));
+	}
+
+	#[test]
+	fn test_warnings() {
+		// an unfinished opcode, then an invalid one
+		let data = [0xFF, 0xE3, 0x00, 0xFF];
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		assert_eq!(
+			script.warnings.report(),
+			"000001: unfinished opcode E3 [E3]\n"
+		);
+
+		let data = [0xFF, 0x01, 0x00, 0xFF];
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		let warnings: Vec<_> = script.warnings.iter().map(|w| (w.offset, w.data)).collect();
+		assert_eq!(warnings, [(Some(2), &[0][..])]);
+	}
+
+	#[test]
+	fn test_truncated_blocks() {
+		// main calls a block cut off partway through an f32, then a complete block
+		let mut data = vec![0xFF, 0xFC, 2];
+		data.extend_from_slice(&15u32.to_le_bytes());
+		data.extend_from_slice(&12u32.to_le_bytes());
+		data.extend_from_slice(&[0xFF, 0x01, 0xFD, 0xFF, 0x05, 0x00, 0x00]);
+
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		assert_eq!(script.truncated_blocks, [15]);
+		assert!(script.summary.contains("(truncated at 000012)"));
+		let opcodes: Vec<u8> = script.instructions.iter().map(|i| i.opcode).collect();
+		assert_eq!(
+			opcodes,
+			[0xFC, 0x05, 0x01, 0xFD],
+			"later blocks are still parsed"
+		);
+		assert_eq!(
+			script.warnings.report(),
+			"00000F: block at 00000F truncated [05 00 00]\n"
+		);
+
+		// missing end marker
+		let script = CmiScript::parse(Reader::new(&[0xFF, 0x01]).clone_at(1));
+		assert_eq!(script.truncated_blocks, [1]);
+	}
+
+	#[test]
+	fn test_quarantined_blocks() {
+		// main calls a block of garbage (which calls another block) and a valid block
+		let mut data = vec![0xFF, 0xFC, 2];
+		data.extend_from_slice(&15u32.to_le_bytes());
+		data.extend_from_slice(&12u32.to_le_bytes());
+		data.extend_from_slice(&[0xFF, 0x01, 0xFD, 0xFF, 0xFC, 1]);
+		data.extend_from_slice(&13u32.to_le_bytes());
+		data.push(0x00);
+
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		assert_eq!(script.quarantined_blocks, vec![15..22]);
+		let opcodes: Vec<u8> = script.instructions.iter().map(|i| i.opcode).collect();
+		assert_eq!(opcodes, [0xFC, 0x01, 0xFD]);
+		assert!(script.summary.contains(
+			"block_1 (offset 00000F)\n(quarantined, not valid bytecode)\n\t\t00000F: FC 01 0D 00 00 00 00\n"
+		));
+		assert!(
+			!script.summary.contains("block_3"),
+			"blocks found by garbage are dropped"
+		);
+		assert_eq!(script.warnings.len(), 1);
+	}
+
+	#[test]
+	fn test_part_refs() {
+		let mut data = vec![0xFF, 0x1F, 2];
+		data.extend_from_slice(b"\x05DOOR3\x04LID1");
+		data.extend_from_slice(&[0x20, 1]);
+		data.extend_from_slice(b"\x05DOOR3");
+		data.extend_from_slice(&[0x81, 0, 1]);
+		data.extend_from_slice(b"\x04LID1");
+		data.push(0xFF);
+
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		let refs: Vec<_> = script
+			.part_refs
+			.iter()
+			.map(|part| (part.name, part.action))
+			.collect();
+		assert_eq!(
+			refs,
+			[
+				("DOOR3", "Hide"),
+				("LID1", "Hide"),
+				("DOOR3", "Show"),
+				("LID1", "Blow off"),
+			]
+		);
+	}
+
+	#[test]
+	fn test_arena_refs() {
+		let mut data = vec![0xFF, 0x64];
+		data.extend_from_slice(b"\x06ARENA2");
+		data.push(0xDF);
+		data.extend_from_slice(b"\x06ARENA3");
+		data.push(0xFF);
+
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		let refs: Vec<_> = script
+			.arena_refs
+			.iter()
+			.map(|arena| (arena.name, arena.action))
+			.collect();
+		assert_eq!(refs, [("ARENA2", "Show"), ("ARENA3", "Load")]);
+	}
+
 	#[test]
 	fn test_index() {
 		for index in 0..255i32 {
@@ -0,0 +1,290 @@
+//! Lists the possible paths through a CMI script, to help read AI logic without running the game.
+//!
+//! Conditions depend on variables, flags and the state of the game world, none of which are known,
+//! so every conditional call, goto and return is followed both ways.
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::data_formats::cmi_bytecode::{CmiFlow, CmiInstruction, CmiScript};
+
+/// How far to follow a script
+#[derive(Debug, Clone, Copy)]
+pub struct CmiTracer {
+	/// Conditional branches to follow in a single trace
+	pub max_branches: usize,
+	/// Traces to find for each script
+	pub max_traces: usize,
+}
+
+impl Default for CmiTracer {
+	fn default() -> Self {
+		Self {
+			max_branches: 6,
+			max_traces: 32,
+		}
+	}
+}
+
+/// One possible path through a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CmiTrace {
+	pub steps: Vec<CmiTraceStep>,
+	pub end: CmiTraceEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmiTraceStep {
+	/// Offset of the instruction
+	pub offset: u32,
+	/// The branch taken, if the instruction has any and one was taken
+	pub taken: Option<CmiFlow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmiTraceEnd {
+	/// Returned from the script's main block
+	Return,
+	/// Reached an instruction (by offset) it had already run with the same calls in progress
+	Loop(u32),
+	/// Went into a block that wasn't parsed (e.g. at an invalid opcode)
+	Unknown(u32),
+	/// Stopped following after too many branches
+	BranchLimit,
+}
+
+/// Where execution is, as a block offset and instruction index within it
+type Position = (u32, usize);
+
+struct Trace {
+	position: Position,
+	/// Positions to carry on from after each call returns
+	calls: Vec<Position>,
+	steps: Vec<CmiTraceStep>,
+	branches: usize,
+	/// Instructions already run, with how many calls were in progress at the time
+	visited: Vec<(Position, usize)>,
+}
+
+impl CmiTracer {
+	pub fn trace(&self, script: &CmiScript) -> Vec<CmiTrace> {
+		let blocks = script_blocks(script);
+		let mut result = Vec::new();
+		let Some(main) = script.block_ranges.first() else {
+			return result;
+		};
+		let trace = Trace {
+			position: (main.start, 0),
+			calls: Vec::new(),
+			steps: Vec::new(),
+			branches: 0,
+			visited: Vec::new(),
+		};
+		self.follow(&blocks, trace, &mut result);
+		result
+	}
+
+	fn follow(
+		&self, blocks: &HashMap<u32, &[CmiInstruction]>, mut trace: Trace,
+		result: &mut Vec<CmiTrace>,
+	) {
+		let end = loop {
+			if result.len() >= self.max_traces {
+				return;
+			}
+			let (block, index) = trace.position;
+			let Some(instructions) = blocks.get(&block) else {
+				break CmiTraceEnd::Unknown(block);
+			};
+			let Some(instruction) = instructions.get(index) else {
+				// end of the block
+				match trace.calls.pop() {
+					Some(position) => {
+						trace.position = position;
+						continue;
+					}
+					None => break CmiTraceEnd::Return,
+				}
+			};
+
+			let visit = (trace.position, trace.calls.len());
+			if trace.visited.contains(&visit) {
+				break CmiTraceEnd::Loop(instruction.offset);
+			}
+			trace.visited.push(visit);
+			trace.steps.push(CmiTraceStep {
+				offset: instruction.offset,
+				taken: None,
+			});
+			let next = (block, index + 1);
+
+			if instruction.flow.contains(&CmiFlow::AlwaysReturn) {
+				trace.steps.last_mut().unwrap().taken = Some(CmiFlow::AlwaysReturn);
+				trace.position = (block, usize::MAX);
+				continue;
+			}
+			// blocks from other operands (e.g. timers and random jumps) aren't followed
+			let mut branches = instruction
+				.flow
+				.iter()
+				.filter(|flow| !matches!(flow, CmiFlow::Block(_)))
+				.peekable();
+			if branches.peek().is_none() {
+				trace.position = next;
+				continue;
+			}
+			if trace.branches >= self.max_branches {
+				break CmiTraceEnd::BranchLimit;
+			}
+			for &flow in branches {
+				let mut taken = Trace {
+					position: next,
+					calls: trace.calls.clone(),
+					steps: trace.steps.clone(),
+					branches: trace.branches + 1,
+					visited: trace.visited.clone(),
+				};
+				taken.steps.last_mut().unwrap().taken = Some(flow);
+				match flow {
+					CmiFlow::Call(target) => {
+						taken.calls.push(next);
+						taken.position = (target, 0);
+					}
+					CmiFlow::Goto(target) => taken.position = (target, 0),
+					CmiFlow::Return => taken.position = (block, usize::MAX),
+					CmiFlow::AlwaysReturn | CmiFlow::Block(_) => unreachable!(),
+				}
+				self.follow(blocks, taken, result);
+			}
+			// or carry on without branching
+			trace.branches += 1;
+			trace.position = next;
+		};
+		if result.len() < self.max_traces {
+			result.push(CmiTrace {
+				steps: trace.steps,
+				end,
+			});
+		}
+	}
+
+	/// Every trace of a script, with each step's line from the script's summary
+	pub fn report(&self, script: &CmiScript) -> String {
+		let lines = summary_lines(script);
+		let mut result = String::new();
+		for (index, trace) in self.trace(script).iter().enumerate() {
+			writeln!(result, "trace {}:", index + 1).unwrap();
+			for step in &trace.steps {
+				let line = lines.get(&step.offset).copied().unwrap_or("?");
+				write!(result, "\t{:06X}: {line}", step.offset).unwrap();
+				match step.taken {
+					None => {}
+					Some(CmiFlow::Call(target)) => write!(result, " -> call {target:06X}").unwrap(),
+					Some(CmiFlow::Goto(target)) => write!(result, " -> goto {target:06X}").unwrap(),
+					Some(CmiFlow::Return | CmiFlow::AlwaysReturn) => result.push_str(" -> return"),
+					Some(CmiFlow::Block(_)) => unreachable!(),
+				}
+				result.push('\n');
+			}
+			match trace.end {
+				CmiTraceEnd::Return => result.push_str("\t(end)\n\n"),
+				CmiTraceEnd::Loop(offset) => {
+					writeln!(result, "\t(loops to {offset:06X})\n").unwrap()
+				}
+				CmiTraceEnd::Unknown(offset) => {
+					writeln!(result, "\t(unknown block {offset:06X})\n").unwrap()
+				}
+				CmiTraceEnd::BranchLimit => result.push_str("\t(too many branches)\n\n"),
+			}
+		}
+		result
+	}
+}
+
+/// Each block's instructions by its offset
+fn script_blocks<'s>(script: &'s CmiScript) -> HashMap<u32, &'s [CmiInstruction]> {
+	// instructions are stored block by block, in the same order as the block ranges
+	let mut result = HashMap::new();
+	let mut rest = script.instructions.as_slice();
+	for range in &script.block_ranges {
+		let count = rest
+			.iter()
+			.take_while(|instruction| range.contains(&instruction.offset))
+			.count();
+		let (instructions, remaining) = rest.split_at(count);
+		result.insert(range.start, instructions);
+		rest = remaining;
+	}
+	result
+}
+
+/// Each instruction's description (after its offset and opcode) in the script's summary
+fn summary_lines<'s>(script: &'s CmiScript) -> HashMap<u32, &'s str> {
+	script
+		.summary
+		.lines()
+		.filter_map(|line| {
+			let (offset, rest) = line.strip_prefix('[')?.split_once(": ")?;
+			let offset = u32::from_str_radix(offset, 16).ok()?;
+			let (_opcode, rest) = rest.split_once(' ')?;
+			Some((offset, rest))
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Reader;
+
+	#[test]
+	fn test_trace() {
+		// main: branch on global3 { call block_1 }, return, end
+		// block_1: set resume point, end
+		let data = [
+			0xFFu8, 0x0D, 0xFC, 0x09, 0x00, 0x00, 0x00, 0xFD, 0xFF, 0x01, 0xFF,
+		];
+		let script = CmiScript::parse(Reader::new(&data).clone_at(1));
+		assert_eq!(
+			script.instructions[0].flow,
+			[CmiFlow::Call(9)],
+			"{}",
+			script.summary
+		);
+
+		let traces = CmiTracer::default().trace(&script);
+		let steps: Vec<Vec<(u32, Option<CmiFlow>)>> = traces
+			.iter()
+			.map(|trace| {
+				trace
+					.steps
+					.iter()
+					.map(|step| (step.offset, step.taken))
+					.collect()
+			})
+			.collect();
+		assert_eq!(
+			steps,
+			[
+				vec![
+					(1, Some(CmiFlow::Call(9))),
+					(9, None),
+					(7, Some(CmiFlow::AlwaysReturn))
+				],
+				vec![(1, None), (7, Some(CmiFlow::AlwaysReturn))],
+			]
+		);
+		assert!(traces.iter().all(|trace| trace.end == CmiTraceEnd::Return));
+
+		let report = CmiTracer::default().report(&script);
+		assert!(report.starts_with("trace 1:\n\t000001: "), "{report}");
+		assert!(report.contains(" -> call 000009\n"), "{report}");
+
+		let limited = CmiTracer {
+			max_branches: 0,
+			max_traces: 32,
+		};
+		let traces = limited.trace(&script);
+		assert_eq!(traces.len(), 1);
+		assert_eq!(traces[0].end, CmiTraceEnd::BranchLimit);
+	}
+}
@@ -1,8 +1,88 @@
 //! Parsing functions for the various image formats the game uses.
 //! Names are either arbitrary or have some vague references in the game code.
 
-use crate::Reader;
+#[cfg(feature = "export")]
+use crate::OutputWriter;
 use crate::data_formats::Texture;
+use crate::{Reader, Warnings};
+
+/// A raw image found by [sniff]
+#[derive(Clone, PartialEq)]
+pub enum SniffedImage<'a> {
+	/// Just a width, height and pixels
+	Basic(Texture<'a>),
+	/// A 600x360 image with runs of transparent pixels skipped
+	Overlay(Texture<'a>),
+	/// A 600x180 run-length encoded image
+	Rle(Texture<'a>),
+	/// A palette then a basic image, like LBB loading images
+	Palette(&'a [u8], Texture<'a>),
+	/// Two palettes to fade between, then a 600x360 run-length encoded image
+	Crossfade([&'a [u8]; 2], Texture<'a>),
+	/// One or more sprite frames
+	Animation(Vec<Texture<'a>>),
+}
+
+impl SniffedImage<'_> {
+	pub fn kind(&self) -> &'static str {
+		match self {
+			SniffedImage::Basic(_) => "basic",
+			SniffedImage::Overlay(_) => "overlay",
+			SniffedImage::Rle(_) => "rle",
+			SniffedImage::Palette(..) => "palette",
+			SniffedImage::Crossfade(..) => "crossfade",
+			SniffedImage::Animation(_) => "animation",
+		}
+	}
+
+	/// Saves the image, with `palette` for the layouts that don't have their own.
+	/// Crossfades are saved once with each palette.
+	#[cfg(feature = "export")]
+	pub fn save_as(&self, name: &str, output: &mut OutputWriter, palette: Option<&[u8]>) {
+		match self {
+			SniffedImage::Basic(texture)
+			| SniffedImage::Overlay(texture)
+			| SniffedImage::Rle(texture) => texture.save_as(name, output, palette),
+			SniffedImage::Palette(palette, texture) => texture.save_as(name, output, Some(palette)),
+			SniffedImage::Crossfade(palettes, texture) => {
+				for (i, palette) in palettes.iter().enumerate() {
+					texture.save_as(&format!("{name}_{}", i + 1), output, Some(palette));
+				}
+			}
+			SniffedImage::Animation(frames) => {
+				Texture::save_animated(frames, name, 30, output, palette)
+			}
+		}
+	}
+}
+
+/// Works out which of the game's raw image layouts `data` is, if any
+pub fn sniff(data: &[u8]) -> Option<SniffedImage<'_>> {
+	sniff_reader(&Reader::new(data))
+}
+/// Like [sniff], for data that's part of a bigger file (without moving the reader)
+pub fn sniff_reader<'a>(reader: &Reader<'a>) -> Option<SniffedImage<'a>> {
+	// the order matters, a few of these can be mistaken for each other
+	if let Some(texture) = try_parse_basic_image(&mut reader.clone()) {
+		return Some(SniffedImage::Basic(texture));
+	}
+	if let Some(texture) = try_parse_overlay_image(&mut reader.clone()) {
+		return Some(SniffedImage::Overlay(texture));
+	}
+	if let Some(texture) = try_parse_rle_image(&mut reader.clone()) {
+		return Some(SniffedImage::Rle(texture));
+	}
+	if let Some((palette, texture)) = try_parse_palette_image(&mut reader.clone()) {
+		return Some(SniffedImage::Palette(palette, texture));
+	}
+	if let Some((palettes, texture)) = try_parse_crossfade_image(&mut reader.clone()) {
+		return Some(SniffedImage::Crossfade(palettes, texture));
+	}
+	if let Some(frames) = try_parse_animation(&mut reader.clone()) {
+		return Some(SniffedImage::Animation(frames));
+	}
+	None
+}
 
 pub fn parse_animation(reader: &mut Reader) -> Vec<Texture<'static>> {
 	try_parse_animation(reader).expect("failed to parse animation")
@@ -99,6 +179,15 @@ pub fn parse_basic_image<'a>(reader: &mut Reader<'a>) -> Texture<'a> {
 	Texture::new(width, height, pixels)
 }
 
+/// The inverse of [parse_basic_image]
+pub fn write_basic_image(texture: &Texture) -> Vec<u8> {
+	let mut result = Vec::with_capacity(4 + texture.pixels.len());
+	result.extend_from_slice(&texture.width.to_le_bytes());
+	result.extend_from_slice(&texture.height.to_le_bytes());
+	result.extend_from_slice(&texture.pixels);
+	result
+}
+
 pub fn try_parse_palette_image<'a>(reader: &mut Reader<'a>) -> Option<(&'a [u8], Texture<'a>)> {
 	let palette = reader.try_slice(0x300)?;
 	let image = try_parse_basic_image(reader)?;
@@ -224,7 +313,10 @@ pub fn try_parse_crossfade_image<'a>(
 	Some(([lut1, lut2], Texture::new(width, height, pixels)))
 }
 
-pub fn parse_overlay_animation<'a>(reader: &mut Reader<'a>) -> Vec<Texture<'a>> {
+pub fn parse_overlay_animation<'a>(
+	reader: &mut Reader<'a>, warnings: &mut Warnings<'a>,
+) -> Vec<Texture<'a>> {
+	let source = reader.remaining_buf();
 	let num_frames = reader.u32() as usize;
 	let width = reader.u16();
 	let height = reader.u16();
@@ -244,7 +336,7 @@ pub fn parse_overlay_animation<'a>(reader: &mut Reader<'a>) -> Vec<Texture<'a>>
 		let mut meta = data.clone_at(metadata_offset as usize);
 		let mut src_pixels = data.clone_at(pixel_offset as usize);
 
-		let mut dest_pixels = frames.last().unwrap().pixels.clone().into_owned();
+		let mut dest_pixels = frames.last().unwrap().pixels.to_vec();
 
 		let mut dest_pixel_offset = meta.u16() as usize * 4;
 		let num_chunks = meta.u16();
@@ -263,8 +355,29 @@ pub fn parse_overlay_animation<'a>(reader: &mut Reader<'a>) -> Vec<Texture<'a>>
 	if frames.first() == frames.last() {
 		frames.pop();
 	} else {
-		eprintln!("texture doesn't loop properly!");
+		warnings.push("overlay animation doesn't loop", source);
 	}
 
 	frames
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sniff() {
+		let texture = Texture::new(2, 1, vec![1, 2]);
+		let basic = write_basic_image(&texture);
+		assert!(sniff(&basic) == Some(SniffedImage::Basic(texture.clone())));
+
+		let palette = [7; 0x300];
+		let lbb = [&palette[..], &basic].concat();
+		let Some(SniffedImage::Palette(found, image)) = sniff(&lbb) else {
+			panic!("palette image not found");
+		};
+		assert!(found == palette && image == texture);
+
+		assert!(sniff(&[1, 2, 3]).is_none());
+	}
+}
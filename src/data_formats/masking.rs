@@ -0,0 +1,159 @@
+//! Deciding which textures use colour 0 as a transparent colour key.
+//!
+//! Most textures treat colour 0 as transparent, but some use it as a regular (black) colour,
+//! so a texture is only masked if its colour 0 pixels look like cut-outs.
+//! Anything the heuristic gets wrong can be overridden by name.
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "export")]
+use std::fmt::Write;
+
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+use crate::data_formats::Texture;
+
+/// Minimum fraction of colour 0 pixels for a texture to be masked when they don't touch the border
+const MIN_INTERIOR_COVERAGE: f32 = 1.0 / 8.0;
+
+/// Why a texture was (or wasn't) masked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskReason {
+	/// Set in the overrides file
+	Override,
+	/// No colour 0 pixels at all
+	NoColourKey,
+	/// Colour 0 pixels touch the edge of a frame
+	Border,
+	/// Colour 0 pixels cover a large part of a frame
+	Coverage,
+	/// Only a few colour 0 pixels, inside the frame
+	Interior,
+}
+
+/// Per-texture masking overrides, loaded from a json object of `"NAME": true/false`.
+#[derive(Default, serde::Deserialize)]
+#[serde(transparent)]
+pub struct MaskSettings(HashMap<String, bool>);
+
+impl MaskSettings {
+	pub fn load(path: impl AsRef<std::path::Path>) -> Self {
+		let path = path.as_ref();
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read mask file {}: {e}", path.display()));
+		serde_json::from_slice(&data)
+			.unwrap_or_else(|e| panic!("failed to parse mask file {}: {e}", path.display()))
+	}
+
+	/// Decides whether a texture (or all frames of an animated texture) should be masked
+	pub fn is_masked(&self, name: &str, frames: &[Texture]) -> (bool, MaskReason) {
+		if let Some(&masked) = self.0.get(name) {
+			return (masked, MaskReason::Override);
+		}
+		let reason = detect_mask(frames);
+		(
+			matches!(reason, MaskReason::Border | MaskReason::Coverage),
+			reason,
+		)
+	}
+}
+
+fn detect_mask(frames: &[Texture]) -> MaskReason {
+	if !frames.iter().any(|frame| frame.pixels.contains(&0)) {
+		return MaskReason::NoColourKey;
+	}
+	if frames.iter().any(has_transparent_border) {
+		return MaskReason::Border;
+	}
+	let coverage = |frame: &Texture| {
+		frame.pixels.iter().filter(|p| **p == 0).count() as f32 / frame.pixels.len() as f32
+	};
+	if frames
+		.iter()
+		.any(|frame| coverage(frame) >= MIN_INTERIOR_COVERAGE)
+	{
+		MaskReason::Coverage
+	} else {
+		MaskReason::Interior
+	}
+}
+
+fn has_transparent_border(frame: &Texture) -> bool {
+	let width = frame.width as usize;
+	let height = frame.height as usize;
+	if width == 0 || height == 0 {
+		return false;
+	}
+	let pixels = &frame.pixels[..];
+	let top = &pixels[..width];
+	let bottom = &pixels[(height - 1) * width..];
+	let mut sides = (0..height).flat_map(|y| [pixels[y * width], pixels[y * width + width - 1]]);
+	top.contains(&0) || bottom.contains(&0) || sides.any(|p| p == 0)
+}
+
+/// Records the masking decision for each texture used by a mesh
+#[derive(Default)]
+pub struct MaskReport(BTreeMap<String, (bool, MaskReason)>);
+
+impl MaskReport {
+	pub fn record(&mut self, name: &str, decision: (bool, MaskReason)) {
+		if !self.0.contains_key(name) {
+			self.0.insert(name.to_owned(), decision);
+		}
+	}
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	#[cfg(feature = "export")]
+	pub fn save(&self, output: &mut OutputWriter) {
+		if self.is_empty() {
+			return;
+		}
+		let mut summary = String::from("name    \tmasked\treason\n");
+		for (name, (masked, reason)) in &self.0 {
+			writeln!(summary, "{name:8}\t{masked}\t{reason:?}").unwrap();
+		}
+		output.write("Masked Textures", "txt", &summary);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_is_masked() {
+		let settings = MaskSettings(HashMap::from([("FORCED".to_owned(), true)]));
+		let mut pixels = vec![1; 16 * 16];
+		let tex = |pixels: &Vec<u8>| Texture::new(16, 16, pixels.clone());
+
+		assert_eq!(
+			settings.is_masked("A", &[tex(&pixels)]),
+			(false, MaskReason::NoColourKey)
+		);
+		assert_eq!(
+			settings.is_masked("FORCED", &[tex(&pixels)]),
+			(true, MaskReason::Override)
+		);
+
+		pixels[5 * 16 + 5] = 0;
+		assert_eq!(
+			settings.is_masked("A", &[tex(&pixels)]),
+			(false, MaskReason::Interior)
+		);
+
+		pixels[15 * 16 + 3] = 0;
+		assert_eq!(
+			settings.is_masked("A", &[tex(&pixels)]),
+			(true, MaskReason::Border)
+		);
+
+		pixels[15 * 16 + 3] = 1;
+		for y in 2..14 {
+			pixels[y * 16 + 2..y * 16 + 14].fill(0);
+		}
+		assert_eq!(
+			settings.is_masked("A", &[tex(&pixels)]),
+			(true, MaskReason::Coverage)
+		);
+	}
+}
@@ -1,10 +1,13 @@
-//! Code for parsing 3D meshes as well as exporting them to GLTF.
+//! Code for parsing 3D meshes as well as exporting them to GLTF (or OBJ).
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
 
 use crate::data_formats::{Pen, Texture};
-use crate::gltf::AlphaMode;
-use crate::{OutputWriter, Reader, Vec2, Vec3, gltf};
+use crate::file_formats::mti::MtiFlags;
+#[cfg(feature = "export")]
+use crate::{MeshFormat, OutputWriter, gltf, gltf::AlphaMode, obj};
+use crate::{MeshSplit, Name, Reader, Vec2, Vec3};
 
 /// 3D mesh
 #[derive(PartialEq)]
@@ -12,6 +15,66 @@ pub struct Mesh<'a> {
 	pub materials: Vec<&'a str>,
 	pub mesh_data: MeshType<'a>,
 	pub reference_points: Vec<Vec3>,
+	/// What each reference point is used for, inferred from CMI scripts
+	pub reference_point_usages: BTreeMap<usize, Vec<ReferencePointUsage<'a>>>,
+	/// What scripts do with each submesh (by index), inferred from CMI scripts
+	pub part_usages: BTreeMap<usize, Vec<PartUsage>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferencePointUsage<'a> {
+	pub action: &'static str,
+	pub target: &'a str,
+	/// Offset of the script that uses the point
+	pub script_offset: u32,
+}
+
+/// A script instruction that hides, shows or blows off one of the mesh's parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartUsage {
+	/// The part name the script uses
+	pub part: Name,
+	/// e.g. `Hide`
+	pub action: &'static str,
+	/// Offset of the script that uses the part
+	pub script_offset: u32,
+}
+
+/// Special surfaces that engines will want their own shaders for.
+///
+/// This is a guess from how the pens look in-game: shiny pens reflect the sky like water,
+/// and translucent pens are see-through like glass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Surface {
+	Water,
+	Glass,
+}
+
+impl Surface {
+	pub fn of(pen: Pen) -> Option<Surface> {
+		match pen {
+			Pen::Shiny(_) => Some(Surface::Water),
+			Pen::Translucent(_) => Some(Surface::Glass),
+			_ => None,
+		}
+	}
+	pub fn name(self) -> &'static str {
+		match self {
+			Surface::Water => "Water",
+			Surface::Glass => "Glass",
+		}
+	}
+
+	/// A material named after the surface, tagged with a `surface` extra
+	#[cfg(feature = "export")]
+	fn create_gltf_material(self, gltf: &mut gltf::Gltf) -> gltf::MaterialIndex {
+		let material = match self {
+			Surface::Water => gltf.create_shiny_material(self.name().to_owned()),
+			Surface::Glass => gltf.create_translucent_material(self.name().to_owned()),
+		};
+		gltf.set_material_extras(material, "surface", self.name().to_ascii_lowercase());
+		material
+	}
 }
 
 /// Either a single mesh or a collection of submeshes
@@ -39,6 +102,54 @@ pub struct MeshGeo {
 	pub bbox: [Vec3; 2],
 }
 
+/// Totals from [Mesh::stats], for sanity checks without opening the exported meshes
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshStats<'a> {
+	/// 0 for single meshes
+	pub submeshes: usize,
+	pub verts: usize,
+	pub tris: usize,
+	/// Width, height and depth of the bounding box
+	pub size: Vec3,
+	pub materials: Vec<&'a str>,
+	/// How many tris have each flags value
+	pub flags: BTreeMap<u32, usize>,
+}
+
+impl MeshStats<'_> {
+	/// One row per mesh, sorted by name, with space separated lists
+	pub fn csv<'s>(meshes: impl IntoIterator<Item = (&'s str, Self)>) -> String
+	where
+		Self: 's,
+	{
+		let mut meshes: Vec<_> = meshes.into_iter().collect();
+		meshes.sort_unstable_by_key(|(name, _)| *name);
+		let mut result =
+			String::from("name,submeshes,verts,tris,width,height,depth,materials,flags\n");
+		for (name, stats) in meshes {
+			let flags: Vec<String> = stats
+				.flags
+				.iter()
+				.map(|(flags, count)| format!("{flags:08X}:{count}"))
+				.collect();
+			writeln!(
+				result,
+				"{name},{},{},{},{},{},{},{},{}",
+				stats.submeshes,
+				stats.verts,
+				stats.tris,
+				stats.size.x,
+				stats.size.y,
+				stats.size.z,
+				stats.materials.join(" "),
+				flags.join(" ")
+			)
+			.unwrap();
+		}
+		result
+	}
+}
+
 #[derive(Clone, PartialEq)]
 pub struct MeshTri {
 	pub indices: [u16; 3],
@@ -69,6 +180,60 @@ impl MeshGeo {
 		Some(MeshGeo { verts, tris, bbox })
 	}
 
+	/// The height of the mesh's shiny (water) floor, if it has one and it's flat
+	pub fn reflection_height(&self) -> Option<f32> {
+		let mut heights = self
+			.tris
+			.iter()
+			.filter(|tri| matches!(tri.material, Pen::Shiny(_)))
+			.flat_map(|tri| tri.indices.map(|i| self.verts[i as usize].y));
+		let height = heights.next()?;
+		heights.all(|y| (y - height).abs() < 1e-3).then_some(height)
+	}
+
+	/// Everything above the mesh's shiny floor (see [Self::reflection_height]) mirrored underneath it,
+	/// like the game draws reflections
+	pub fn reflect(&self) -> Option<MeshGeo> {
+		let height = self.reflection_height()?;
+		let mut remap = vec![u16::MAX; self.verts.len()];
+		let mut verts = Vec::new();
+		let tris: Vec<MeshTri> = self
+			.tris
+			.iter()
+			.filter(|tri| {
+				!matches!(tri.material, Pen::Shiny(_))
+					&& tri
+						.indices
+						.iter()
+						.any(|&i| self.verts[i as usize].y > height)
+			})
+			.map(|tri| {
+				let indices = tri.indices.map(|i| {
+					let index = &mut remap[i as usize];
+					if *index == u16::MAX {
+						let vert = self.verts[i as usize];
+						verts.push(Vec3::new(vert.x, 2.0 * height - vert.y, vert.z));
+						*index = (verts.len() - 1) as u16;
+					}
+					*index
+				});
+				// mirroring flips the winding
+				let [i1, i2, i3] = indices;
+				let [uv1, uv2, uv3] = tri.uvs;
+				MeshTri {
+					indices: [i1, i3, i2],
+					uvs: [uv1, uv3, uv2],
+					..tri.clone()
+				}
+			})
+			.collect();
+		if tris.is_empty() {
+			return None;
+		}
+		let bbox = Vec3::calculate_bbox(&verts);
+		Some(MeshGeo { verts, tris, bbox })
+	}
+
 	fn get_used_colours(&self, colours: &mut ColourMap) {
 		for tri in &self.tris {
 			if let Pen::Colour(colour) = tri.material {
@@ -83,7 +248,7 @@ impl MeshGeo {
 		let mut tri_map: HashMap<(u8, u16), u16> = HashMap::new();
 
 		self.tris.retain(|tri| {
-			let id = (tri.flags & TRIFLAG_ID_MASK) >> TRIFLAG_ID_SHIFT;
+			let id = tri.id();
 			if id == 0 {
 				return true;
 			}
@@ -103,7 +268,7 @@ impl MeshGeo {
 
 			let mut tri = tri.clone();
 			for i in &mut tri.indices {
-				*i = *tri_map.entry((id as u8, *i)).or_insert_with(|| {
+				*i = *tri_map.entry((id, *i)).or_insert_with(|| {
 					let n = target.verts.len();
 					target.verts.push(self.verts[*i as usize]);
 					n as u16
@@ -168,6 +333,82 @@ impl MeshGeo {
 	}
 }
 
+impl MeshGeo {
+	/// Combines several meshes (each moved by its origin) into one,
+	/// joining back up the verts they share
+	pub fn merge<'g>(geos: impl IntoIterator<Item = (&'g MeshGeo, Vec3)>) -> MeshGeo {
+		let mut result = MeshGeo::default();
+		let mut vert_indices: HashMap<[u32; 3], u16> = HashMap::new();
+		for (geo, origin) in geos {
+			let remap: Vec<u16> = geo
+				.verts
+				.iter()
+				.map(|&vert| {
+					let vert = vert + origin;
+					*vert_indices
+						.entry([vert.x.to_bits(), vert.y.to_bits(), vert.z.to_bits()])
+						.or_insert_with(|| {
+							result.verts.push(vert);
+							(result.verts.len() - 1) as u16
+						})
+				})
+				.collect();
+			result.tris.extend(geo.tris.iter().map(|tri| MeshTri {
+				indices: tri.indices.map(|i| remap[i as usize]),
+				..tri.clone()
+			}));
+		}
+		result.bbox = Vec3::calculate_bbox(&result.verts);
+		result
+	}
+
+	/// Splits the mesh into a submesh per material, named after the material (or pen)
+	pub fn split_by_material(self, materials: &[&str]) -> MeshType<'static> {
+		let mut groups: Vec<(Pen, MeshGeo, HashMap<u16, u16>)> = Vec::new();
+		for tri in &self.tris {
+			let index = match groups.iter().position(|(pen, ..)| *pen == tri.material) {
+				Some(index) => index,
+				None => {
+					groups.push((tri.material, MeshGeo::default(), HashMap::new()));
+					groups.len() - 1
+				}
+			};
+			let (_, geo, remap) = &mut groups[index];
+			let indices = tri.indices.map(|i| {
+				*remap.entry(i).or_insert_with(|| {
+					geo.verts.push(self.verts[i as usize]);
+					(geo.verts.len() - 1) as u16
+				})
+			});
+			geo.tris.push(MeshTri {
+				indices,
+				..tri.clone()
+			});
+		}
+		let submeshes = groups
+			.into_iter()
+			.map(|(pen, mut mesh_data, _)| {
+				mesh_data.bbox = Vec3::calculate_bbox(&mesh_data.verts);
+				let name = match pen {
+					Pen::Texture(index) if (index as usize) < materials.len() => {
+						materials[index as usize].to_owned()
+					}
+					pen => format!("{pen:?}"),
+				};
+				Submesh {
+					mesh_data,
+					name: name.into(),
+					origin: Vec3::default(),
+				}
+			})
+			.collect();
+		MeshType::Multimesh {
+			submeshes,
+			bbox: self.bbox,
+		}
+	}
+}
+
 const TRIFLAG_HIDDEN: u32 = 0x12;
 const TRIFLAG_OUTLINE_12: u32 = 0x10_00_00;
 const TRIFLAG_OUTLINE_23: u32 = 0x20_00_00;
@@ -178,7 +419,20 @@ const TRIFLAG_OUTLINE_MASK: u32 = 0xF0_00_00;
 const TRIFLAG_ID_MASK: u32 = 0xFF_00_00_00;
 const TRIFLAG_ID_SHIFT: u32 = 24;
 
+/// How far (in texture widths) uvs can go past the edge of a texture before it has to repeat
+#[cfg(feature = "export")]
+const UV_EPSILON: f32 = 1e-3;
+
+/// A submesh's mesh, and its outlines if they're exported separately
+#[cfg(feature = "export")]
+type SubmeshMeshes = (gltf::MeshIndex, Option<gltf::MeshIndex>);
+
 impl MeshTri {
+	/// Whether the game skips drawing this triangle
+	pub fn is_hidden(&self) -> bool {
+		self.flags & TRIFLAG_HIDDEN != 0
+	}
+
 	pub fn try_parse_slice(reader: &mut Reader, count: usize) -> Option<Vec<Self>> {
 		if count > 10000 {
 			return None;
@@ -230,11 +484,101 @@ impl MeshTri {
 	}
 
 	pub fn id(&self) -> u8 {
-		(self.flags >> 24) as u8
+		((self.flags & TRIFLAG_ID_MASK) >> TRIFLAG_ID_SHIFT) as u8
 	}
 }
 
 impl<'a> Mesh<'a> {
+	/// Interns the mesh's names (see [Name]) so it no longer borrows the file it was parsed from
+	pub fn into_owned(self) -> Mesh<'static> {
+		let intern = |name: &str| Name::new(name).as_str();
+		let mesh_data = match self.mesh_data {
+			MeshType::Single(geo) => MeshType::Single(geo),
+			MeshType::Multimesh { submeshes, bbox } => MeshType::Multimesh {
+				submeshes: submeshes
+					.into_iter()
+					.map(|sub| Submesh {
+						name: Cow::Owned(sub.name.into_owned()),
+						..sub
+					})
+					.collect(),
+				bbox,
+			},
+		};
+		Mesh {
+			materials: self.materials.into_iter().map(intern).collect(),
+			mesh_data,
+			reference_points: self.reference_points,
+			reference_point_usages: self
+				.reference_point_usages
+				.into_iter()
+				.map(|(index, usages)| {
+					let usages = usages
+						.into_iter()
+						.map(|usage| ReferencePointUsage {
+							target: intern(usage.target),
+							..usage
+						})
+						.collect();
+					(index, usages)
+				})
+				.collect(),
+			part_usages: self.part_usages,
+		}
+	}
+
+	/// A copy of level geometry (which is parsed split by ID, see [MeshGeo::split_by_id])
+	/// split another way, or `None` to keep it as it is.
+	///
+	/// Script part usages refer to the ID submeshes, so they aren't kept.
+	pub fn with_split(&self, split: MeshSplit) -> Option<Mesh<'a>> {
+		let geo = match &self.mesh_data {
+			MeshType::Multimesh { submeshes, .. } if split != MeshSplit::ById => {
+				MeshGeo::merge(submeshes.iter().map(|sub| (&sub.mesh_data, sub.origin)))
+			}
+			_ => return None,
+		};
+		let mesh_data = match split {
+			MeshSplit::ByMaterial => geo.split_by_material(&self.materials),
+			_ => MeshType::Single(geo),
+		};
+		Some(Mesh {
+			materials: self.materials.clone(),
+			mesh_data,
+			reference_points: self.reference_points.clone(),
+			reference_point_usages: self.reference_point_usages.clone(),
+			part_usages: BTreeMap::new(),
+		})
+	}
+
+	/// Finds the submesh a script's part name refers to, either by name or
+	/// (for level geometry split by [MeshGeo::split_by_id]) by the number at the end of the name
+	pub fn find_part(&self, part: &str) -> Option<usize> {
+		let MeshType::Multimesh { submeshes, .. } = &self.mesh_data else {
+			return None;
+		};
+		if let Some(index) = submeshes
+			.iter()
+			.position(|sub| sub.name.eq_ignore_ascii_case(part))
+		{
+			return Some(index);
+		}
+		let prefix = part.trim_end_matches(|c: char| c.is_ascii_digit());
+		let id: u8 = part[prefix.len()..].parse().ok()?;
+		submeshes.iter().position(|sub| sub.name.parse() == Ok(id))
+	}
+	/// Records a script using one of the mesh's parts, returns false if no submesh matches
+	pub fn add_part_usage(&mut self, usage: PartUsage) -> bool {
+		let Some(index) = self.find_part(usage.part.as_str()) else {
+			return false;
+		};
+		let usages = self.part_usages.entry(index).or_default();
+		if !usages.contains(&usage) {
+			usages.push(usage);
+		}
+		true
+	}
+
 	pub fn parse(reader: &mut Reader<'a>, is_multimesh: bool) -> Mesh<'a> {
 		Self::try_parse(reader, is_multimesh).expect("failed to read mesh")
 	}
@@ -285,6 +629,8 @@ impl<'a> Mesh<'a> {
 			materials: textures,
 			mesh_data,
 			reference_points,
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
 		};
 
 		result.remove_unused_materials();
@@ -292,6 +638,29 @@ impl<'a> Mesh<'a> {
 		Some(result)
 	}
 
+	pub fn stats(&self) -> MeshStats<'a> {
+		let (geos, submeshes, [min, max]) = match &self.mesh_data {
+			MeshType::Single(geo) => (vec![geo], 0, geo.bbox),
+			MeshType::Multimesh { submeshes, bbox } => (
+				submeshes.iter().map(|sub| &sub.mesh_data).collect(),
+				submeshes.len(),
+				*bbox,
+			),
+		};
+		let mut flags = BTreeMap::new();
+		for tri in geos.iter().flat_map(|geo| &geo.tris) {
+			*flags.entry(tri.flags).or_default() += 1;
+		}
+		MeshStats {
+			submeshes,
+			verts: geos.iter().map(|geo| geo.verts.len()).sum(),
+			tris: geos.iter().map(|geo| geo.tris.len()).sum(),
+			size: max - min,
+			materials: self.materials.clone(),
+			flags,
+		}
+	}
+
 	pub fn for_tris_mut(&mut self, mut func: impl FnMut(&mut [MeshTri])) {
 		match &mut self.mesh_data {
 			MeshType::Single(geo) => func(&mut geo.tris),
@@ -336,9 +705,15 @@ impl<'a> Mesh<'a> {
 			}
 		});
 	}
+}
 
+#[cfg(feature = "export")]
+impl<'a> Mesh<'a> {
 	pub fn save_as(&self, name: &str, output: &mut OutputWriter) {
-		let mut gltf = gltf::Gltf::new(name.to_owned());
+		if output.export_settings().mesh_format == MeshFormat::Obj {
+			return self.save_obj_as(name, output);
+		}
+		let mut gltf = gltf::Gltf::with_settings(name.to_owned(), *output.export_settings());
 
 		let root = gltf.get_root_node();
 		self.add_to_gltf(&mut gltf, name, Some(root));
@@ -348,10 +723,14 @@ impl<'a> Mesh<'a> {
 	pub fn save_textured_as(
 		&self, name: &str, output: &mut OutputWriter, textures: &mut impl TextureHolder<'a>,
 	) {
-		let mut gltf = gltf::Gltf::new(name.to_owned());
+		if output.export_settings().mesh_format == MeshFormat::Obj {
+			return self.save_textured_obj_as(name, output, textures);
+		}
+		let mut gltf = gltf::Gltf::with_settings(name.to_owned(), *output.export_settings());
 
 		let root = gltf.get_root_node();
-		self.add_to_gltf_textured(&mut gltf, name, Some(root), textures);
+		self.add_to_gltf_textured(&mut gltf, name, Some(root), textures, output);
+		gltf.map_image_uris(|uri| output.relative_uri(name, "gltf", uri));
 
 		output.write(name, "gltf", gltf.render_json().as_bytes());
 	}
@@ -362,47 +741,35 @@ impl<'a> Mesh<'a> {
 		let target = target.unwrap_or_else(|| gltf.create_node(name.to_owned(), None));
 
 		let create_submesh =
-			|gltf: &mut gltf::Gltf, name: String, geo: &MeshGeo| -> gltf::MeshIndex {
+			|gltf: &mut gltf::Gltf, name: String, geo: &MeshGeo| -> SubmeshMeshes {
+				let settings = *gltf.export_settings();
 				let indices: Vec<_> = geo
 					.tris
 					.iter()
 					.flat_map(|tri| {
 						let [i1, i2, i3] = tri.indices;
-						[i1, i3, i2]
+						settings.triangle([i1, i3, i2])
 					})
 					.collect();
-				gltf.create_mesh_from_primitive(name, &geo.verts, &indices, None, None)
-			};
-
-		match &self.mesh_data {
-			MeshType::Single(geo) => {
-				let submesh = create_submesh(gltf, name.to_owned(), geo);
-				gltf.set_node_mesh(target, submesh);
-			}
-			MeshType::Multimesh { submeshes, .. } => {
-				for sub in submeshes {
-					let submesh = create_submesh(gltf, sub.name.to_string(), &sub.mesh_data);
-					let sub_node =
-						gltf.create_child_node(target, sub.name.to_string(), Some(submesh));
-					gltf.set_node_position(sub_node, sub.origin);
+				let mesh = gltf.create_mesh(name);
+				let prim = gltf.add_mesh_primitive(mesh, &geo.verts, &indices, None);
+				if geo.tris.iter().any(|tri| tri.id() != 0) {
+					// verts are shared between triangles so the ids can't be an attribute
+					let ids: Vec<u8> = geo.tris.iter().map(MeshTri::id).collect();
+					gltf.set_primitive_extras(prim, "triangle_ids", ids);
 				}
-			}
-		}
+				(mesh, None)
+			};
 
-		if !self.reference_points.is_empty() {
-			gltf.create_points_nodes(
-				"Reference Points".into(),
-				&self.reference_points,
-				Some(target),
-			);
-		}
+		self.add_geo_to_gltf(gltf, name, target, create_submesh);
 
 		target
 	}
 
+	/// Like [Self::add_to_gltf] with materials, embedded textures are encoded like `output` saves pngs
 	pub fn add_to_gltf_textured(
 		&self, gltf: &mut gltf::Gltf, name: &str, target: Option<gltf::NodeIndex>,
-		textures: &mut impl TextureHolder<'a>,
+		textures: &mut impl TextureHolder<'a>, output: &OutputWriter,
 	) -> gltf::NodeIndex {
 		let mut materials: Vec<(TextureResult, Option<gltf::MaterialIndex>)> = self
 			.materials
@@ -414,8 +781,10 @@ impl<'a> Mesh<'a> {
 		let mut translucent_colours: Option<[[u8; 4]; 4]> = None;
 
 		let mut colour_mat: Option<gltf::MaterialIndex> = None;
+		// only used by outlines, translucent triangles are glass
 		let mut translucent_mat: Option<gltf::MaterialIndex> = None;
-		let mut shiny_mat: Option<gltf::MaterialIndex> = None;
+		let mut water_mat: Option<gltf::MaterialIndex> = None;
+		let mut glass_mat: Option<gltf::MaterialIndex> = None;
 
 		#[derive(Default)]
 		struct MeshPrimitive {
@@ -423,6 +792,7 @@ impl<'a> Mesh<'a> {
 			indices: Vec<u16>,
 			uvs: Vec<Vec2>,
 			colours: Vec<[u8; 4]>,
+			ids: Vec<u16>,
 			material: Option<gltf::MaterialIndex>,
 			uv_scale: Vec2,
 		}
@@ -432,6 +802,7 @@ impl<'a> Mesh<'a> {
 				self.indices.clear();
 				self.uvs.clear();
 				self.colours.clear();
+				self.ids.clear();
 				self.material = None;
 				self.uv_scale = [1.0; 2];
 			}
@@ -439,24 +810,30 @@ impl<'a> Mesh<'a> {
 
 		let mut prims = Vec::<MeshPrimitive>::new();
 		prims.resize_with(materials.len(), Default::default);
+		// whether each texture's uvs go outside of it on each axis
+		let mut repeats = vec![[false; 2]; materials.len()];
 		let mut colour_prim = MeshPrimitive::default();
 		let mut translucent_prim = MeshPrimitive::default();
 		let mut lines_prim = MeshPrimitive::default();
+		// the triangle, its flags and which of its edges each outline segment came from
+		let mut line_sources = Vec::<(usize, u32, &str)>::new();
 		let mut shiny_prim = MeshPrimitive::default();
 
-		let mut create_submesh = |gltf: &mut gltf::Gltf,
-		                          name: String,
-		                          geo: &MeshGeo|
-		 -> gltf::MeshIndex {
+		let create_submesh = |gltf: &mut gltf::Gltf,
+		                      name: String,
+		                      geo: &MeshGeo|
+		 -> SubmeshMeshes {
+			let settings = *gltf.export_settings();
 			for prim in &mut prims {
 				prim.clear()
 			}
 			colour_prim.clear();
 			translucent_prim.clear();
 			lines_prim.clear();
+			line_sources.clear();
 			shiny_prim.clear();
 
-			for tri in &geo.tris {
+			for (tri_index, tri) in geo.tris.iter().enumerate() {
 				let indices @ [i1, i2, i3] = tri.indices.map(|n| n as usize);
 
 				let flags = tri.flags;
@@ -511,14 +888,15 @@ impl<'a> Mesh<'a> {
 						lines_prim.verts.push(p3);
 						lines_prim.colours.push(colour);
 					}
-					if flags & TRIFLAG_OUTLINE_12 != 0 {
-						lines_prim.indices.extend([i1, i2]);
-					}
-					if flags & TRIFLAG_OUTLINE_13 != 0 {
-						lines_prim.indices.extend([i1, i3]);
-					}
-					if flags & TRIFLAG_OUTLINE_23 != 0 {
-						lines_prim.indices.extend([i2, i3]);
+					for (flag, edge, indices) in [
+						(TRIFLAG_OUTLINE_12, "12", [i1, i2]),
+						(TRIFLAG_OUTLINE_13, "13", [i1, i3]),
+						(TRIFLAG_OUTLINE_23, "23", [i2, i3]),
+					] {
+						if flags & flag != 0 {
+							lines_prim.indices.extend(indices);
+							line_sources.push((tri_index, flags, edge));
+						}
 					}
 				} // end outlines
 
@@ -538,11 +916,13 @@ impl<'a> Mesh<'a> {
 							width,
 							height,
 							masked,
+							flags,
 							..
 						}
 						| TextureResult::SaveEmbed {
 							texture: Texture { width, height, .. },
 							masked,
+							flags,
 						} => {
 							let prim = &mut prims[texture_index];
 							if prim.material.is_none() {
@@ -564,14 +944,26 @@ impl<'a> Mesh<'a> {
 											));
 										}
 										TextureResult::SaveEmbed { texture, .. } => {
+											let png = texture.create_png(
+												&material_name,
+												output,
+												Some(palette),
+											);
 											mat.1 = Some(gltf.create_texture_material_embedded(
 												material_name,
-												&texture.create_png(Some(palette)),
+												&png,
 												Some(alpha_mode),
 											));
 										}
 										_ => unreachable!(),
 									}
+									if let Some(flags) = flags {
+										gltf.set_material_extras(
+											mat.1.unwrap(),
+											"mti_flags",
+											flags.names(),
+										);
+									}
 								}
 								prim.uv_scale = [(*width as f32).recip(), (*height as f32).recip()];
 								prim.material = mat.1;
@@ -582,9 +974,16 @@ impl<'a> Mesh<'a> {
 							let i1 = prim.verts.len() as u16;
 							prim.verts.extend([p1, p2, p3]);
 							for [u, v] in tri.uvs {
-								prim.uvs.push([u * prim.uv_scale[0], v * prim.uv_scale[1]]);
+								let uv = [u * prim.uv_scale[0], v * prim.uv_scale[1]];
+								for (axis, value) in uv.into_iter().enumerate() {
+									if !(-UV_EPSILON..=1.0 + UV_EPSILON).contains(&value) {
+										repeats[texture_index][axis] = true;
+									}
+								}
+								prim.uvs.push(uv);
 							}
-							prim.indices.extend([i1, i1 + 2, i1 + 1]); // swizzle indices
+							prim.indices.extend(settings.triangle([i1, i1 + 2, i1 + 1])); // swizzle indices
+							prim.ids.extend([tri.id() as u16; 3]);
 
 							continue;
 						}
@@ -615,21 +1014,19 @@ impl<'a> Mesh<'a> {
 						// todo use shiny index
 						prim = &mut shiny_prim;
 						if prim.material.is_none() {
-							if shiny_mat.is_none() {
-								shiny_mat = Some(gltf.create_shiny_material("Shiny".to_owned()));
+							if water_mat.is_none() {
+								water_mat = Some(Surface::Water.create_gltf_material(gltf));
 							}
-							prim.material = shiny_mat;
+							prim.material = water_mat;
 						}
 					}
 					Pen::Translucent(translucent_index) => {
 						prim = &mut translucent_prim;
 						if prim.material.is_none() {
-							if translucent_mat.is_none() {
-								translucent_mat = Some(
-									gltf.create_translucent_material("Translucent".to_owned()),
-								);
+							if glass_mat.is_none() {
+								glass_mat = Some(Surface::Glass.create_gltf_material(gltf));
 							}
-							prim.material = translucent_mat;
+							prim.material = glass_mat;
 						}
 						colour = Some(
 							translucent_colours
@@ -638,7 +1035,7 @@ impl<'a> Mesh<'a> {
 						);
 					}
 					Pen::Texture(_) => unreachable!(),
-					Pen::Unknown(_n) => {
+					Pen::Unknown(_n) | Pen::UnknownMti(_n) => {
 						// todo
 						//eprintln!("unknown mesh material {n} in {name}");
 						continue;
@@ -647,7 +1044,8 @@ impl<'a> Mesh<'a> {
 
 				let i1 = prim.verts.len() as u16;
 				prim.verts.extend([p1, p2, p3]);
-				prim.indices.extend([i1, i1 + 2, i1 + 1]); // swizzle indices
+				prim.indices.extend(settings.triangle([i1, i1 + 2, i1 + 1])); // swizzle indices
+				prim.ids.extend([tri.id() as u16; 3]);
 				if let Some(colour) = colour {
 					prim.colours.extend([colour, colour, colour]);
 				}
@@ -655,7 +1053,10 @@ impl<'a> Mesh<'a> {
 
 			// finished populating primitives, create mesh
 
+			let outline_mesh = (settings.outline_nodes && lines_prim.material.is_some())
+				.then(|| format!("{name}_Outline"));
 			let mesh = gltf.create_mesh(name);
+			let outline_mesh = outline_mesh.map(|outline_name| gltf.create_mesh(outline_name));
 			for prim in
 				prims
 					.iter()
@@ -666,47 +1067,387 @@ impl<'a> Mesh<'a> {
 				}
 				assert!(!prim.verts.is_empty());
 
+				let is_lines = std::ptr::eq(prim, &lines_prim);
+				let prim_mesh = if is_lines {
+					outline_mesh.unwrap_or(mesh)
+				} else {
+					mesh
+				};
 				let prim_id =
-					gltf.add_mesh_primitive(mesh, &prim.verts, &prim.indices, prim.material);
+					gltf.add_mesh_primitive(prim_mesh, &prim.verts, &prim.indices, prim.material);
 				// these are no-ops if unused
 				gltf.add_primitive_uvs(prim_id, &prim.uvs);
 				gltf.add_primitive_colours(prim_id, &prim.colours);
+				if prim.ids.iter().any(|&id| id != 0) {
+					gltf.add_primitive_triangle_ids(prim_id, &prim.ids);
+				}
 
-				if std::ptr::eq(prim, &lines_prim) {
+				if is_lines {
 					gltf.set_primitive_mode(prim_id, gltf::PrimitiveMode::Lines);
+					// map each segment back to the triangle it outlines
+					let (triangles, (flags, edges)): (Vec<_>, (Vec<_>, Vec<_>)) = line_sources
+						.iter()
+						.map(|&(tri, flags, edge)| (tri, (format!("{flags:08X}"), edge)))
+						.unzip();
+					gltf.set_primitive_extras(prim_id, "outline_triangles", triangles);
+					gltf.set_primitive_extras(prim_id, "outline_flags", flags);
+					gltf.set_primitive_extras(prim_id, "outline_edges", edges);
 				}
 			}
 
-			mesh
+			(mesh, outline_mesh)
 		};
 
 		let target = target.unwrap_or_else(|| gltf.create_node(name.to_owned(), None));
-		match &self.mesh_data {
-			MeshType::Single(geo) => {
-				let submesh = create_submesh(gltf, name.to_owned(), geo);
-				gltf.set_node_mesh(target, submesh);
+		self.add_geo_to_gltf(gltf, name, target, create_submesh);
+
+		// clamp textures that don't need to repeat so their edges don't blend with the other side
+		for ((_, material), repeat) in materials.iter().zip(&repeats) {
+			if let Some(material) = *material {
+				let wrap = repeat.map(|repeat| {
+					if repeat {
+						gltf::WrapType::Repeat
+					} else {
+						gltf::WrapType::Clamp
+					}
+				});
+				gltf.set_material_wrap(material, wrap);
 			}
+		}
+
+		target
+	}
+
+	/// Creates nodes for each submesh (and their LODs) as well as the reference points
+	fn add_geo_to_gltf(
+		&self, gltf: &mut gltf::Gltf, name: &str, target: gltf::NodeIndex,
+		mut create_submesh: impl FnMut(&mut gltf::Gltf, String, &MeshGeo) -> SubmeshMeshes,
+	) {
+		// outlines exported separately go on a child node so they can be toggled
+		let add_outline = |gltf: &mut gltf::Gltf,
+		                   node: gltf::NodeIndex,
+		                   name: &str,
+		                   outline: Option<gltf::MeshIndex>| {
+			if let Some(outline) = outline {
+				let outline_node =
+					gltf.create_child_node(node, format!("{name}_Outline"), Some(outline));
+				gltf.set_node_extras(outline_node, "outline", true);
+			}
+		};
+		let lod_levels = gltf.export_settings().lod_levels;
+		let reflections = gltf.export_settings().reflections;
+		let mut add_geo = |gltf: &mut gltf::Gltf,
+		                   node: gltf::NodeIndex,
+		                   name: String,
+		                   geo: &MeshGeo,
+		                   origin: Option<Vec3>| {
+			let (mesh, outline) = create_submesh(gltf, name.clone(), geo);
+			gltf.set_node_mesh(node, mesh);
+			add_outline(gltf, node, &name, outline);
+			if let Some(origin) = origin {
+				gltf.set_node_position(node, origin);
+			}
+
+			let mut lods = Vec::new();
+			let mut num_tris = geo.tris.len();
+			for level in 1..=lod_levels {
+				let lod_geo = geo.decimate_lod(level);
+				// stop once the mesh can't get any simpler
+				if lod_geo.tris.is_empty() || lod_geo.tris.len() >= num_tris {
+					break;
+				}
+				num_tris = lod_geo.tris.len();
+				let lod_name = format!("{name}_LOD{level}");
+				let (lod_mesh, lod_outline) = create_submesh(gltf, lod_name.clone(), &lod_geo);
+				let lod_node = gltf.create_node(lod_name.clone(), Some(lod_mesh));
+				add_outline(gltf, lod_node, &lod_name, lod_outline);
+				if let Some(origin) = origin {
+					gltf.set_node_position(lod_node, origin);
+				}
+				lods.push(lod_node);
+			}
+			if !lods.is_empty() {
+				gltf.set_node_lods(node, &lods);
+			}
+
+			if reflections && let Some(reflection) = geo.reflect() {
+				let reflection_name = format!("{name}_Reflection");
+				let (reflection_mesh, reflection_outline) =
+					create_submesh(gltf, reflection_name.clone(), &reflection);
+				let reflection_node =
+					gltf.create_child_node(node, reflection_name.clone(), Some(reflection_mesh));
+				gltf.set_node_extras(reflection_node, "reflection", true);
+				add_outline(gltf, reflection_node, &reflection_name, reflection_outline);
+			}
+		};
+
+		match &self.mesh_data {
+			MeshType::Single(geo) => add_geo(gltf, target, name.to_owned(), geo, None),
 			MeshType::Multimesh { submeshes, .. } => {
-				for sub in submeshes {
-					let submesh = create_submesh(gltf, sub.name.to_string(), &sub.mesh_data);
-					let sub_node =
-						gltf.create_child_node(target, sub.name.to_string(), Some(submesh));
-					gltf.set_node_position(sub_node, sub.origin);
+				for (index, sub) in submeshes.iter().enumerate() {
+					let usages = self
+						.part_usages
+						.get(&index)
+						.map(Vec::as_slice)
+						.unwrap_or_default();
+					// add the name the scripts use if it's different (e.g. numbered level parts)
+					let sub_name = match usages.first() {
+						Some(usage) if !usage.part.as_str().eq_ignore_ascii_case(&sub.name) => {
+							format!("{} {}", sub.name, usage.part)
+						}
+						_ => sub.name.to_string(),
+					};
+					let sub_node = gltf.create_child_node(target, sub_name.clone(), None);
+					add_geo(gltf, sub_node, sub_name, &sub.mesh_data, Some(sub.origin));
+					if !usages.is_empty() {
+						let mut part_names: Vec<&str> =
+							usages.iter().map(|usage| usage.part.as_str()).collect();
+						part_names.sort_unstable();
+						part_names.dedup();
+						gltf.set_node_extras(sub_node, "part_id", sub.name.as_ref());
+						gltf.set_node_extras(sub_node, "part_names", part_names);
+						let usages: Vec<serde_json::Value> = usages
+							.iter()
+							.map(|usage| {
+								serde_json::json!({
+									"part": usage.part,
+									"action": usage.action,
+									"script": format!("{:06X}", usage.script_offset),
+								})
+							})
+							.collect();
+						gltf.set_node_extras(sub_node, "usages", usages);
+					}
 				}
 			}
 		}
 
 		if !self.reference_points.is_empty() {
-			gltf.create_points_nodes(
-				"Reference Points".into(),
-				&self.reference_points,
-				Some(target),
-			);
+			// empties named after how the scripts use them
+			let container = gltf.create_child_node(target, "Reference Points".into(), None);
+			for (index, &point) in self.reference_points.iter().enumerate() {
+				let usages = self
+					.reference_point_usages
+					.get(&index)
+					.map(Vec::as_slice)
+					.unwrap_or_default();
+				let node_name = match usages.first() {
+					Some(usage) if usage.target.is_empty() => format!("{index} {}", usage.action),
+					Some(usage) => format!("{index} {} {}", usage.action, usage.target),
+					None => index.to_string(),
+				};
+				let node = gltf.create_child_node(container, node_name, None);
+				gltf.set_node_position(node, point);
+				if !usages.is_empty() {
+					let usages: Vec<serde_json::Value> = usages
+						.iter()
+						.map(|usage| {
+							serde_json::json!({
+								"action": usage.action,
+								"target": usage.target,
+								"script": format!("{:06X}", usage.script_offset),
+							})
+						})
+						.collect();
+					gltf.set_node_extras(node, "usages", usages);
+				}
+			}
 		}
+	}
 
-		target
+	/// Saves the untextured geometry as an OBJ file, one object per submesh
+	pub fn save_obj_as(&self, name: &str, output: &mut OutputWriter) {
+		let mut obj = obj::Obj::new(name.to_owned(), *output.export_settings());
+		for (sub_name, geo, origin) in self.geos(name) {
+			obj.start_object(sub_name.to_owned());
+			let verts: Vec<usize> = geo
+				.verts
+				.iter()
+				.map(|&vert| obj.add_vertex(vert + origin, None))
+				.collect();
+			for tri in &geo.tris {
+				let [i1, i2, i3] = tri.indices.map(|i| verts[i as usize]);
+				obj.add_face([i1, i3, i2], None); // swizzle indices
+			}
+		}
+		let (obj, _) = obj.render();
+		output.write(name, "obj", obj);
 	}
 
+	/// Saves the mesh as an OBJ file with an MTL file referencing the exported textures.
+	///
+	/// Flat and translucent colours are stored as vertex colours, and outlines as lines.
+	/// LODs and reference points are only exported to GLTF.
+	pub fn save_textured_obj_as(
+		&self, name: &str, output: &mut OutputWriter, textures: &mut impl TextureHolder<'a>,
+	) {
+		let mut obj = obj::Obj::new(name.to_owned(), *output.export_settings());
+		obj.set_material_library(output.relative_uri(name, "obj", &format!("{name}.mtl")));
+		let materials: Vec<TextureResult> = self
+			.materials
+			.iter()
+			.map(|mat| textures.lookup(mat))
+			.collect();
+		let palette = textures.get_palette();
+		let palette_colour = |index: u8| -> [u8; 3] {
+			let index = index as usize;
+			palette[index * 3..index * 3 + 3].try_into().unwrap()
+		};
+		let mut translucent_colours: Option<[[u8; 4]; 4]> = None;
+		const WHITE: Option<[u8; 3]> = Some([255; 3]);
+
+		for (sub_name, geo, origin) in self.geos(name) {
+			obj.start_object(sub_name.to_owned());
+			for tri in &geo.tris {
+				let flags = tri.flags;
+				if flags & TRIFLAG_HIDDEN != 0 {
+					continue;
+				}
+				let indices @ [i1, i2, i3] = tri.indices.map(|n| n as usize);
+				let points = indices.map(|i| geo.verts[i] + origin);
+				let mut tri_mat = tri.material;
+
+				// outlines
+				if flags & TRIFLAG_OUTLINE_MASK > TRIFLAG_DRAW_OUTLINE {
+					let colour = match tri_mat {
+						Pen::Translucent(index) => {
+							let [r, g, b, _] = translucent_colours
+								.get_or_insert_with(|| textures.get_translucent_colours())[index as usize];
+							[r, g, b]
+						}
+						Pen::Colour(index) => palette_colour(index),
+						_ => palette_colour(1), // fallback (unused)
+					};
+					if !obj.has_material("Outline") {
+						obj.add_material("Outline".to_owned(), &["Kd 1 1 1".to_owned()]);
+					}
+					obj.use_material("Outline");
+					for (edge, [a, b]) in [
+						(TRIFLAG_OUTLINE_12, [0, 1]),
+						(TRIFLAG_OUTLINE_13, [0, 2]),
+						(TRIFLAG_OUTLINE_23, [1, 2]),
+					] {
+						if flags & edge != 0 {
+							let v1 = obj.add_vertex(points[a], Some(colour));
+							let v2 = obj.add_vertex(points[b], Some(colour));
+							obj.add_line(v1, v2);
+						}
+					}
+				}
+
+				// filter partial degenerates (after outlines)
+				if i1 == i2 || i1 == i3 || i2 == i3 {
+					continue;
+				}
+
+				// try textured
+				if let Pen::Texture(texture_index) = tri_mat {
+					let texture_index = texture_index as usize;
+					let material_name = self.materials[texture_index];
+					match &materials[texture_index] {
+						TextureResult::None => tri_mat = Pen::Colour(0xFF), // missing textures are white in-game
+						TextureResult::Pen(pen) => tri_mat = *pen,
+						result @ (TextureResult::SaveRef {
+							width,
+							height,
+							masked,
+							..
+						}
+						| TextureResult::SaveEmbed {
+							texture: Texture { width, height, .. },
+							masked,
+							..
+						}) => {
+							if !obj.has_material(material_name) {
+								let path = match result {
+									TextureResult::SaveRef { path, .. } => path.clone(),
+									TextureResult::SaveEmbed { texture, .. } => {
+										texture.save_as(material_name, output, Some(palette));
+										format!("{material_name}.png")
+									}
+									_ => unreachable!(),
+								};
+								let path = output.relative_uri(name, "mtl", &path);
+								let mut properties =
+									vec!["Kd 1 1 1".to_owned(), format!("map_Kd {path}")];
+								if *masked {
+									properties.push(format!("map_d {path}"));
+								}
+								obj.add_material(material_name.to_owned(), &properties);
+							}
+							obj.use_material(material_name);
+							let verts = points.map(|p| obj.add_vertex(p, WHITE));
+							let uv_scale = [(*width as f32).recip(), (*height as f32).recip()];
+							let uvs = tri
+								.uvs
+								.map(|[u, v]| obj.add_uv([u * uv_scale[0], v * uv_scale[1]]));
+							obj.add_face(
+								[verts[0], verts[2], verts[1]],
+								Some([uvs[0], uvs[2], uvs[1]]),
+							); // swizzle indices
+							continue;
+						}
+					}
+				}
+
+				// not textured
+				let (material, properties, colour) = match tri_mat {
+					Pen::Colour(index) => (
+						"Colour".to_owned(),
+						vec!["Kd 1 1 1".to_owned()],
+						Some(palette_colour(index)),
+					),
+					Pen::Shiny(_shiny_index) => (
+						Surface::Water.name().to_owned(),
+						vec![
+							"Kd 1 1 1".to_owned(),
+							"Ks 1 1 1".to_owned(),
+							"Ns 500".to_owned(),
+						],
+						WHITE,
+					),
+					Pen::Translucent(index) => {
+						let [r, g, b, a] = translucent_colours
+							.get_or_insert_with(|| textures.get_translucent_colours())[index as usize];
+						let [kr, kg, kb, d] = [r, g, b, a].map(|c| c as f32 / 255.0);
+						(
+							format!("{}{index}", Surface::Glass.name()),
+							vec![format!("Kd {kr} {kg} {kb}"), format!("d {d}")],
+							Some([r, g, b]),
+						)
+					}
+					Pen::Texture(_) => unreachable!(),
+					Pen::Unknown(_n) | Pen::UnknownMti(_n) => continue,
+				};
+				if !obj.has_material(&material) {
+					obj.add_material(material.clone(), &properties);
+				}
+				obj.use_material(&material);
+				let verts = points.map(|p| obj.add_vertex(p, colour));
+				obj.add_face([verts[0], verts[2], verts[1]], None); // swizzle indices
+			}
+		}
+
+		let (obj, mtl) = obj.render();
+		output.write(name, "obj", obj);
+		if let Some(mtl) = mtl {
+			output.write(name, "mtl", mtl);
+		}
+	}
+
+	/// Each submesh (or the whole mesh) with its name and origin
+	fn geos<'b>(&'b self, name: &'b str) -> Vec<(&'b str, &'b MeshGeo, Vec3)> {
+		match &self.mesh_data {
+			MeshType::Single(geo) => vec![(name, geo, Vec3::default())],
+			MeshType::Multimesh { submeshes, .. } => submeshes
+				.iter()
+				.map(|sub| (sub.name.as_ref(), &sub.mesh_data, sub.origin))
+				.collect(),
+		}
+	}
+}
+
+impl<'a> Mesh<'a> {
 	pub fn get_used_colours(&self, textures: &impl TextureHolder<'a>) -> ColourMap {
 		let mut result = ColourMap::new();
 		for mat in &self.materials {
@@ -750,6 +1491,9 @@ impl ColourMap {
 	pub fn push(&mut self, index: u8) {
 		self.0[(index >> 6) as usize] |= 1 << (index & 63);
 	}
+	pub fn contains(&self, index: u8) -> bool {
+		self.0[(index >> 6) as usize] & (1 << (index & 63)) != 0
+	}
 
 	pub fn compare(&self, pal1: &[u8], pal2: &[u8]) -> bool {
 		debug_assert_eq!(pal1.len(), 256 * 3);
@@ -769,6 +1513,27 @@ impl ColourMap {
 		}
 		true
 	}
+
+	/// Mean squared RGB difference of the used colours between two palettes
+	pub fn error(&self, pal1: &[u8], pal2: &[u8]) -> u32 {
+		debug_assert_eq!(pal1.len(), 256 * 3);
+		debug_assert_eq!(pal2.len(), 256 * 3);
+
+		let mut total = 0;
+		let mut count = 0;
+		for (index, (c1, c2)) in pal1.chunks_exact(3).zip(pal2.chunks_exact(3)).enumerate() {
+			if self.0[index >> 6] & (1 << (index & 63)) == 0 {
+				continue;
+			}
+			total += c1
+				.iter()
+				.zip(c2)
+				.map(|(&a, &b)| (a as i32 - b as i32).pow(2) as u32)
+				.sum::<u32>();
+			count += 1;
+		}
+		total.checked_div(count).unwrap_or(0)
+	}
 }
 impl Extend<u8> for ColourMap {
 	fn extend<Iter: IntoIterator<Item = u8>>(&mut self, iter: Iter) {
@@ -800,9 +1565,577 @@ pub enum TextureResult<'a> {
 		height: u16,
 		path: String,
 		masked: bool,
+		/// From the MTI material, if it came from one
+		flags: Option<MtiFlags>,
 	},
 	SaveEmbed {
 		texture: Texture<'a>,
 		masked: bool,
+		flags: Option<MtiFlags>,
 	},
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_mesh_stats() {
+		let tri = |flags| MeshTri {
+			indices: [0, 1, 2],
+			material: Pen::Texture(0),
+			uvs: Default::default(),
+			flags,
+		};
+		let submesh = |name: &'static str, flags: &[u32]| Submesh {
+			mesh_data: MeshGeo {
+				verts: vec![Vec3::default(); 3],
+				tris: flags.iter().copied().map(tri).collect(),
+				bbox: Default::default(),
+			},
+			name: name.into(),
+			origin: Vec3::default(),
+		};
+		let mesh = Mesh {
+			materials: vec!["ROCK", "SKY"],
+			mesh_data: MeshType::Multimesh {
+				submeshes: vec![submesh("A", &[0, 0x01_00_00_00]), submesh("B", &[0])],
+				bbox: [Vec3::new(-1.0, 0.0, 2.0), Vec3::new(1.0, 3.0, 2.5)],
+			},
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		let stats = mesh.stats();
+		assert_eq!((stats.submeshes, stats.verts, stats.tris), (2, 6, 3));
+		assert_eq!(stats.size, Vec3::new(2.0, 3.0, 0.5));
+		assert_eq!(
+			MeshStats::csv([("ROCKS", stats)]),
+			"name,submeshes,verts,tris,width,height,depth,materials,flags\n\
+			ROCKS,2,6,3,2,3,0.5,ROCK SKY,00000000:2 01000000:1\n"
+		);
+	}
+
+	#[test]
+	fn test_bsp_split() {
+		let tri = |indices, material, flags| MeshTri {
+			indices,
+			material,
+			uvs: Default::default(),
+			flags,
+		};
+		let verts = (0..4).map(|i| Vec3::new(i as f32, 0.0, 0.0)).collect();
+		let geo = MeshGeo {
+			verts,
+			tris: vec![
+				tri([0, 1, 2], Pen::Texture(0), 0),
+				tri([1, 2, 3], Pen::Colour(3), 0x01_00_00_00),
+				tri([0, 2, 3], Pen::Texture(0), 0x01_00_00_00),
+			],
+			bbox: Default::default(),
+		};
+		let mesh = Mesh {
+			materials: vec!["ROCK"],
+			mesh_data: geo.split_by_id(),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		assert!(mesh.with_split(MeshSplit::ById).is_none());
+
+		let flat = mesh.with_split(MeshSplit::Flat).unwrap();
+		let MeshType::Single(flat) = &flat.mesh_data else {
+			panic!("flat mesh has submeshes");
+		};
+		assert_eq!((flat.verts.len(), flat.tris.len()), (4, 3));
+
+		let by_material = mesh.with_split(MeshSplit::ByMaterial).unwrap();
+		let MeshType::Multimesh { submeshes, .. } = &by_material.mesh_data else {
+			panic!("mesh wasn't split");
+		};
+		let split: Vec<_> = submeshes
+			.iter()
+			.map(|sub| {
+				(
+					sub.name.as_ref(),
+					sub.mesh_data.tris.len(),
+					sub.mesh_data.verts.len(),
+				)
+			})
+			.collect();
+		assert_eq!(split, [("ROCK", 2, 4), ("Colour(3)", 1, 3)]);
+	}
+
+	#[test]
+	fn test_reflect() {
+		let tri = |indices, material| MeshTri {
+			indices,
+			material,
+			uvs: [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+			flags: 0,
+		};
+		let geo = MeshGeo {
+			verts: vec![
+				Vec3::new(0.0, 1.0, 0.0),
+				Vec3::new(1.0, 1.0, 0.0),
+				Vec3::new(0.0, 1.0, 1.0),
+				Vec3::new(0.0, 3.0, 0.0),
+				Vec3::new(0.0, -2.0, 0.0),
+			],
+			tris: vec![
+				tri([0, 1, 2], Pen::Shiny(0)),
+				tri([0, 1, 3], Pen::Colour(1)),
+				tri([0, 1, 4], Pen::Colour(2)),
+			],
+			bbox: Default::default(),
+		};
+		assert_eq!(geo.reflection_height(), Some(1.0));
+		// only the wall above the water is mirrored, with its winding flipped
+		let reflection = geo.reflect().unwrap();
+		assert_eq!(
+			reflection.verts,
+			[
+				Vec3::new(0.0, 1.0, 0.0),
+				Vec3::new(1.0, 1.0, 0.0),
+				Vec3::new(0.0, -1.0, 0.0)
+			]
+		);
+		assert_eq!(reflection.tris.len(), 1);
+		assert_eq!(reflection.tris[0].indices, [0, 2, 1]);
+		assert_eq!(reflection.tris[0].uvs[1], [0.0, 1.0]);
+		assert_eq!(reflection.tris[0].material, Pen::Colour(1));
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_texture_wrap() {
+		let tri = |texture, scale| MeshTri {
+			indices: [0, 1, 2],
+			material: Pen::Texture(texture),
+			uvs: [[0.0, 0.0], [scale, 0.0], [0.0, 4.0]],
+			flags: 0,
+		};
+		let mesh = Mesh {
+			materials: vec!["DECAL", "TILES"],
+			mesh_data: MeshType::Multimesh {
+				submeshes: vec![Submesh {
+					mesh_data: MeshGeo {
+						verts: vec![
+							Vec3::new(0.0, 0.0, 0.0),
+							Vec3::new(1.0, 0.0, 0.0),
+							Vec3::new(0.0, 1.0, 0.0),
+						],
+						tris: vec![tri(0, 4.0), tri(1, 8.0)],
+						bbox: Default::default(),
+					},
+					name: "A".into(),
+					origin: Vec3::default(),
+				}],
+				bbox: Default::default(),
+			},
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		let settings = crate::ExportSettings {
+			nearest_filter: true,
+			..Default::default()
+		};
+		let mut gltf = gltf::Gltf::with_settings("TEST".into(), settings);
+		mesh.add_to_gltf_textured(&mut gltf, "TEST", None, &mut SavedTextures, &test_output());
+		let json: serde_json::Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		// the decal fits in its texture so it's clamped, the tiles only repeat along u
+		assert_eq!(
+			json["samplers"],
+			serde_json::json!([
+				{"magFilter": 9728, "minFilter": 9728, "wrapS": 33071, "wrapT": 33071},
+				{"magFilter": 9728, "minFilter": 9728, "wrapS": 10497, "wrapT": 33071},
+			])
+		);
+		let samplers: Vec<_> = json["textures"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|texture| texture["sampler"].as_u64().unwrap())
+			.collect();
+		assert_eq!(samplers, [0, 1]);
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_texture_uris() {
+		let dir = std::path::Path::new("output/test_texture_uris");
+		let _ = std::fs::remove_dir_all(dir);
+		let mut output = OutputWriter::new_in_dir(dir).push_dir("Meshes");
+		let mut textures = output.push_dir("Textures");
+		// an earlier texture whose name only differs in case
+		textures.write("GUNT", "png", []);
+
+		let tri = |texture| MeshTri {
+			indices: [0, 1, 2],
+			material: Pen::Texture(texture),
+			uvs: Default::default(),
+			flags: 0,
+		};
+		let mesh = Mesh {
+			materials: vec!["gunt", "A|B"],
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: vec![Vec3::default(); 3],
+				tris: vec![tri(0), tri(1)],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		mesh.save_textured_as("ROOM", &mut output, &mut SavedTextures);
+		let mut obj_output = output.clone().with_export_settings(crate::ExportSettings {
+			mesh_format: MeshFormat::Obj,
+			..Default::default()
+		});
+		mesh.save_textured_as("ROOM", &mut obj_output, &mut SavedTextures);
+		for name in mesh.materials.iter() {
+			textures.write(name, "png", []);
+		}
+
+		let gltf = std::fs::read_to_string(dir.join("Meshes/ROOM.gltf")).unwrap();
+		let json: serde_json::Value = serde_json::from_str(&gltf).unwrap();
+		let uris: Vec<_> = json["images"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|image| image["uri"].as_str().unwrap())
+			.collect();
+		assert_eq!(uris, ["Textures/gunt_2.png", "Textures/A_B.png"]);
+		for uri in uris {
+			assert!(dir.join("Meshes").join(uri).exists(), "{uri} wasn't saved");
+		}
+		let mtl = std::fs::read_to_string(dir.join("Meshes/ROOM.mtl")).unwrap();
+		assert!(mtl.contains("map_Kd Textures/gunt_2.png\n"), "{mtl}");
+		assert!(mtl.contains("map_Kd Textures/A_B.png\n"), "{mtl}");
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_texture_uris_layout() {
+		let mesh = Mesh {
+			materials: vec!["WALL"],
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: vec![Vec3::default(); 3],
+				tris: vec![MeshTri {
+					indices: [0, 1, 2],
+					material: Pen::Texture(0),
+					uvs: Default::default(),
+					flags: 0,
+				}],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		let uri = |layout: &str| {
+			let session = crate::ExportSession::new("output/test_texture_uris_layout");
+			session.set_layout(Some(crate::OutputLayout::parse(layout)));
+			session.begin_dry_run();
+			let output = session
+				.writer()
+				.asset_dir("TRAVERSE/LEVEL3")
+				.push_dir("Meshes");
+			let mut gltf = gltf::Gltf::new("ROOM".into());
+			mesh.add_to_gltf_textured(&mut gltf, "ROOM", None, &mut SavedTextures, &test_output());
+			gltf.map_image_uris(|uri| output.relative_uri("ROOM", "gltf", uri));
+			let json: serde_json::Value = serde_json::from_str(&gltf.render_json()).unwrap();
+			json["images"][0]["uri"].as_str().unwrap().to_owned()
+		};
+		assert_eq!(
+			uri("{game_mode}/{level}/{asset_type}/{name}"),
+			"Textures/WALL.png"
+		);
+		assert_eq!(
+			uri("{asset_type}/{game_mode}_{level}_{name}"),
+			"Textures/TRAVERSE_LEVEL3_WALL.png"
+		);
+		assert_eq!(
+			uri("{asset_type}/{level}/{name}"),
+			"../Textures/LEVEL3/WALL.png"
+		);
+		assert_eq!(uri("{level}/{name}"), "WALL.png");
+	}
+
+	/// Somewhere for embedded textures to be encoded, nothing is written
+	#[cfg(feature = "export")]
+	fn test_output() -> OutputWriter {
+		OutputWriter::new_in_dir("output")
+	}
+
+	/// Textures saved next to the meshes using them
+	#[cfg(feature = "export")]
+	struct SavedTextures;
+	#[cfg(feature = "export")]
+	impl<'a> TextureHolder<'a> for SavedTextures {
+		fn lookup(&mut self, name: &str) -> TextureResult<'a> {
+			TextureResult::SaveRef {
+				width: 4,
+				height: 4,
+				path: format!("Textures/{name}.png"),
+				masked: false,
+				flags: None,
+			}
+		}
+		fn get_used_colours(&self, _name: &str, _colours: &mut ColourMap) {}
+		fn get_palette(&self) -> &[u8] {
+			&[]
+		}
+		fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
+			[[0; 4]; 4]
+		}
+	}
+
+	/// Textures for meshes that don't use any
+	#[cfg(feature = "export")]
+	struct Textures;
+	#[cfg(feature = "export")]
+	impl<'a> TextureHolder<'a> for Textures {
+		fn lookup(&mut self, _name: &str) -> TextureResult<'a> {
+			TextureResult::None
+		}
+		fn get_used_colours(&self, _name: &str, _colours: &mut ColourMap) {}
+		fn get_palette(&self) -> &[u8] {
+			&[]
+		}
+		fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
+			[[255; 4]; 4]
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_triangle_ids() {
+		let tri = |indices, flags| MeshTri {
+			indices,
+			material: Pen::Translucent(0),
+			uvs: Default::default(),
+			flags,
+		};
+		let mesh = Mesh {
+			materials: Vec::new(),
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: (0..4).map(|i| Vec3::new(i as f32, i as f32, 0.0)).collect(),
+				tris: vec![tri([0, 1, 2], 0), tri([1, 2, 3], 0x02_00_00_00)],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		let render = |textured| {
+			let mut gltf = gltf::Gltf::new("TEST".into());
+			if textured {
+				mesh.add_to_gltf_textured(&mut gltf, "TEST", None, &mut Textures, &test_output());
+			} else {
+				mesh.add_to_gltf(&mut gltf, "TEST", None);
+			}
+			serde_json::from_str::<serde_json::Value>(&gltf.render_json()).unwrap()
+		};
+
+		let json = render(true);
+		let prim = &json["meshes"][0]["primitives"][0];
+		let accessor =
+			&json["accessors"][prim["attributes"]["_TRIANGLE_ID"].as_u64().unwrap() as usize];
+		assert_eq!(accessor["count"], 6);
+		assert_eq!(accessor["componentType"], 5123);
+		assert_eq!(accessor["max"], serde_json::json!([2.0]));
+
+		let json = render(false);
+		let prim = &json["meshes"][0]["primitives"][0];
+		assert!(prim["attributes"].get("_TRIANGLE_ID").is_none());
+		assert_eq!(prim["extras"]["triangle_ids"], serde_json::json!([0, 2]));
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_mti_flags_extra() {
+		/// Textures from an MTI, with the given flags
+		struct MtiTextures(Option<MtiFlags>);
+		impl<'a> TextureHolder<'a> for MtiTextures {
+			fn lookup(&mut self, name: &str) -> TextureResult<'a> {
+				TextureResult::SaveRef {
+					width: 4,
+					height: 4,
+					path: format!("Textures/{name}.png"),
+					masked: false,
+					flags: self.0,
+				}
+			}
+			fn get_used_colours(&self, _name: &str, _colours: &mut ColourMap) {}
+			fn get_palette(&self) -> &[u8] {
+				&[]
+			}
+			fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
+				[[0; 4]; 4]
+			}
+		}
+
+		let mesh = Mesh {
+			materials: vec!["WATER"],
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: vec![Vec3::default(); 3],
+				tris: vec![MeshTri {
+					indices: [0, 1, 2],
+					material: Pen::Texture(0),
+					uvs: Default::default(),
+					flags: 0,
+				}],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		let render = |flags| {
+			let mut gltf = gltf::Gltf::new("TEST".into());
+			mesh.add_to_gltf_textured(
+				&mut gltf,
+				"TEST",
+				None,
+				&mut MtiTextures(flags),
+				&test_output(),
+			);
+			let json: serde_json::Value = serde_json::from_str(&gltf.render_json()).unwrap();
+			json["materials"][0]["extras"].clone()
+		};
+		let flags = MtiFlags::from_bits(MtiFlags::ANIMATED.bits() | 0x20);
+		assert_eq!(
+			render(Some(flags)),
+			serde_json::json!({ "mti_flags": ["animated", "0x20"] })
+		);
+		// textures that aren't from an MTI have no flags to list
+		assert_eq!(render(None), serde_json::Value::Null);
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_surfaces() {
+		let outline = TRIFLAG_DRAW_OUTLINE | TRIFLAG_OUTLINE_12;
+		let tri = |material, flags| MeshTri {
+			indices: [0, 1, 2],
+			material,
+			uvs: Default::default(),
+			flags,
+		};
+		let mesh = Mesh {
+			materials: Vec::new(),
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: vec![
+					Vec3::new(0.0, 0.0, 0.0),
+					Vec3::new(1.0, 0.0, 0.0),
+					Vec3::new(0.0, 1.0, 0.0),
+				],
+				tris: vec![tri(Pen::Shiny(0), 0), tri(Pen::Translucent(1), outline)],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		assert_eq!(Surface::of(Pen::Shiny(0)), Some(Surface::Water));
+		assert_eq!(Surface::of(Pen::Translucent(1)), Some(Surface::Glass));
+		assert_eq!(Surface::of(Pen::Colour(3)), None);
+
+		let mut gltf = gltf::Gltf::new("TEST".into());
+		mesh.add_to_gltf_textured(&mut gltf, "TEST", None, &mut Textures, &test_output());
+		let json: serde_json::Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		let materials: Vec<_> = json["materials"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|mat| (mat["name"].as_str().unwrap(), &mat["extras"]["surface"]))
+			.collect();
+		// the outline keeps its own material so it isn't tagged as glass
+		assert_eq!(
+			materials,
+			[
+				("Water", &serde_json::json!("water")),
+				("Translucent", &serde_json::Value::Null),
+				("Glass", &serde_json::json!("glass")),
+			]
+		);
+
+		let dir = std::path::Path::new("output/test_surfaces");
+		let _ = std::fs::remove_dir_all(dir);
+		let mut output =
+			OutputWriter::new_in_dir(dir).with_export_settings(crate::ExportSettings {
+				mesh_format: MeshFormat::Obj,
+				..Default::default()
+			});
+		mesh.save_textured_as("TEST", &mut output, &mut Textures);
+		let mtl = std::fs::read_to_string(dir.join("TEST.mtl")).unwrap();
+		assert!(mtl.contains("newmtl Water\n"), "{mtl}");
+		assert!(mtl.contains("newmtl Glass1\n"), "{mtl}");
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_outline_nodes() {
+		let flags = TRIFLAG_DRAW_OUTLINE | TRIFLAG_OUTLINE_12 | TRIFLAG_OUTLINE_23;
+		let mesh = Mesh {
+			materials: Vec::new(),
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: vec![
+					Vec3::new(0.0, 0.0, 0.0),
+					Vec3::new(1.0, 0.0, 0.0),
+					Vec3::new(0.0, 1.0, 0.0),
+				],
+				tris: vec![MeshTri {
+					indices: [0, 1, 2],
+					material: Pen::Translucent(0),
+					uvs: [[0.0; 2]; 3],
+					flags,
+				}],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+
+		let render = |outline_nodes| {
+			let settings = crate::ExportSettings {
+				outline_nodes,
+				..Default::default()
+			};
+			let mut gltf = gltf::Gltf::with_settings("TEST".into(), settings);
+			mesh.add_to_gltf_textured(&mut gltf, "TEST", None, &mut Textures, &test_output());
+			serde_json::from_str::<serde_json::Value>(&gltf.render_json()).unwrap()
+		};
+
+		let json = render(false);
+		assert_eq!(json["meshes"].as_array().unwrap().len(), 1);
+		let lines = &json["meshes"][0]["primitives"][1];
+		assert_eq!(lines["mode"], 1);
+		assert_eq!(
+			lines["extras"],
+			serde_json::json!({
+				"outline_triangles": [0, 0],
+				"outline_flags": [format!("{flags:08X}"), format!("{flags:08X}")],
+				"outline_edges": ["12", "23"],
+			})
+		);
+
+		let json = render(true);
+		assert_eq!(json["meshes"][0]["primitives"].as_array().unwrap().len(), 1);
+		assert_eq!(json["meshes"][1]["name"], "TEST_Outline");
+		assert_eq!(json["meshes"][1]["primitives"][0]["mode"], 1);
+		let outline_node = json["nodes"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.find(|node| node["name"] == "TEST_Outline")
+			.unwrap();
+		assert_eq!(outline_node["mesh"], 1);
+		assert_eq!(outline_node["extras"]["outline"], true);
+	}
+}
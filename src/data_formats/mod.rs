@@ -1,9 +1,17 @@
 pub mod animation;
 pub mod bsp;
 pub mod cmi_bytecode;
+pub mod cmi_trace;
 pub mod image_formats;
+pub mod masking;
 pub mod mesh;
+pub mod palette;
+#[cfg(feature = "export")]
+pub mod palette_cycle;
 mod pen;
+mod simplify;
+#[cfg(feature = "export")]
+pub mod sound_bank;
 pub mod spline;
 mod texture;
 mod wav;
@@ -14,4 +22,6 @@ pub use mesh::{Mesh, TextureHolder, TextureResult};
 pub use pen::Pen;
 pub use spline::Spline;
 pub use texture::Texture;
+#[cfg(feature = "export")]
+pub use texture::{Dither, PngImportOptions};
 pub use wav::Wav;
@@ -0,0 +1,424 @@
+//! Helpers for building the colour palettes the game actually renders with.
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::data_formats::Texture;
+use crate::data_formats::mesh::ColourMap;
+
+/// Index of the first palette entry that can be replaced by each arena.
+pub const ARENA_PALETTE_START: usize = 4 * 16;
+
+/// Which palette a colour in a [PaletteStack] comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteLayer {
+	System,
+	Arena,
+	Level,
+}
+
+/// The palettes the game layers on top of each other to render an arena with.
+///
+/// * the first 64 colours ([ARENA_PALETTE_START]) come from the system palette,
+///   which is stored in `mdkfont.fti` (see [Fti::system_palette](crate::file_formats::Fti::system_palette))
+/// * the next `num_free_pixels` colours come from the arena's MTO palette
+/// * everything else comes from the level's DTI palette
+///
+/// Levels have their own copy of the system colours, but a couple of them are wrong,
+/// so they're only used when there's no system palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteStack<'a> {
+	/// All 256 colours of the level's DTI palette
+	pub level: &'a [u8],
+	/// The first 64 colours, if the FTI is available
+	pub system: Option<&'a [u8]>,
+	pub arena: &'a [u8],
+	/// The number of arena colours used, from the DTI
+	pub num_free_pixels: usize,
+}
+
+impl<'a> PaletteStack<'a> {
+	/// Just the level palette (with its own system colours) until the other layers are added
+	pub fn new(level: &'a [u8]) -> Self {
+		assert_eq!(level.len(), 256 * 3, "invalid dti palette size");
+		Self {
+			level,
+			system: None,
+			arena: &[],
+			num_free_pixels: 0,
+		}
+	}
+	#[must_use]
+	pub fn with_system(mut self, system: Option<&'a [u8]>) -> Self {
+		if let Some(system) = system {
+			assert_eq!(
+				system.len(),
+				ARENA_PALETTE_START * 3,
+				"invalid system palette size"
+			);
+		}
+		self.system = system;
+		self
+	}
+	#[must_use]
+	pub fn with_arena(mut self, arena: &'a [u8], num_free_pixels: usize) -> Self {
+		assert!(
+			arena.len() >= num_free_pixels * 3,
+			"arena palette is missing colours"
+		);
+		self.arena = arena;
+		self.num_free_pixels = num_free_pixels;
+		self
+	}
+
+	/// Which palette colour `index` comes from
+	pub fn layer(&self, index: usize) -> PaletteLayer {
+		if index < ARENA_PALETTE_START {
+			if self.system.is_some() {
+				PaletteLayer::System
+			} else {
+				PaletteLayer::Level
+			}
+		} else if index < ARENA_PALETTE_START + self.num_free_pixels {
+			PaletteLayer::Arena
+		} else {
+			PaletteLayer::Level
+		}
+	}
+
+	/// The full 256 colour palette the game renders with
+	pub fn compose(&self) -> Vec<u8> {
+		let start = ARENA_PALETTE_START * 3;
+		let num_free_bytes = self.num_free_pixels * 3;
+
+		let mut palette = self.level.to_vec();
+		if let Some(system) = self.system {
+			palette[..start].copy_from_slice(system);
+		}
+		palette[start..start + num_free_bytes].copy_from_slice(&self.arena[..num_free_bytes]);
+		palette
+	}
+}
+
+/// Builds the full in-game palette for an arena, see [PaletteStack]
+#[deprecated = "use PaletteStack, which also allows a missing system palette"]
+pub fn compose_arena_palette(
+	sys_pal: &[u8], dti_pal: &[u8], arena_pal: &[u8], num_free_pixels: usize,
+) -> Vec<u8> {
+	PaletteStack::new(dti_pal)
+		.with_system(Some(sys_pal))
+		.with_arena(arena_pal, num_free_pixels)
+		.compose()
+}
+
+/// Why [PaletteResolver] picked a palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteReason {
+	/// There was only one to pick from
+	Only,
+	/// The palette's name starts the same as the texture's
+	Name,
+	/// The palette has the most colours for the indices the texture uses
+	Coverage,
+}
+
+/// Picks which palette to show a texture with, for files that don't say which one their textures use.
+///
+/// Candidates are usually the file's own palettes followed by the ones in its companion files.
+#[derive(Default)]
+pub struct PaletteResolver<'a> {
+	/// Name, source file, full palette and the number of colours it really had
+	candidates: Vec<(&'a str, &'a str, Cow<'a, [u8]>, usize)>,
+	report: String,
+}
+
+impl<'a> PaletteResolver<'a> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a candidate palette from `source`.
+	/// Partial palettes (e.g. the 64 colour `SYS_PAL`) are padded with black.
+	pub fn add(&mut self, name: &'a str, source: &'a str, palette: &'a [u8]) {
+		assert!(
+			palette.len() <= 256 * 3 && palette.len().is_multiple_of(3),
+			"invalid palette {name}"
+		);
+		let num_colours = palette.len() / 3;
+		let palette = if palette.len() == 256 * 3 {
+			Cow::Borrowed(palette)
+		} else {
+			let mut padded = palette.to_vec();
+			padded.resize(256 * 3, 0);
+			Cow::Owned(padded)
+		};
+		self.candidates.push((name, source, palette, num_colours));
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.candidates.is_empty()
+	}
+
+	/// Picks a palette for a texture (or all the frames of an animation), returning its name and colours
+	pub fn resolve(&mut self, name: &str, frames: &[Texture]) -> Option<(&str, &[u8])> {
+		let (index, reason) = self.pick(name, frames)?;
+		let (pal_name, source, palette, _) = &self.candidates[index];
+		if source.is_empty() {
+			writeln!(self.report, "{name:12}\t{pal_name}\t{reason:?}").unwrap();
+		} else {
+			writeln!(self.report, "{name:12}\t{source}:{pal_name}\t{reason:?}").unwrap();
+		}
+		Some((pal_name, palette))
+	}
+
+	fn pick(&self, name: &str, frames: &[Texture]) -> Option<(usize, PaletteReason)> {
+		match self.candidates.len() {
+			0 => return None,
+			1 => return Some((0, PaletteReason::Only)),
+			_ => {}
+		}
+
+		// by name, e.g. OPTIONS and OPTPAL
+		const MIN_PREFIX: usize = 3;
+		let prefix_len = |pal_name: &str| {
+			name.bytes()
+				.zip(pal_name.bytes())
+				.take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+				.count()
+		};
+		let best_prefix = self
+			.candidates
+			.iter()
+			.map(|(pal_name, ..)| prefix_len(pal_name))
+			.max()
+			.unwrap();
+		if best_prefix >= MIN_PREFIX {
+			let mut matches = (self.candidates.iter().enumerate())
+				.filter(|(_, (pal_name, ..))| prefix_len(pal_name) == best_prefix);
+			if let (Some((index, _)), None) = (matches.next(), matches.next()) {
+				return Some((index, PaletteReason::Name));
+			}
+		}
+
+		// by colour coverage: the right palette has colours for every index the texture uses,
+		// and usually maps them to more distinct colours than the wrong ones
+		let colours = ColourMap::from_frames(frames);
+		let score = |(_, _, palette, num_colours): &(&str, &str, Cow<[u8]>, usize)| {
+			let mut covered = 0;
+			let mut distinct: Vec<&[u8]> = Vec::new();
+			for index in (1..=255u8).filter(|&i| colours.contains(i)) {
+				if (index as usize) < *num_colours {
+					covered += 1;
+				}
+				let rgb = &palette[index as usize * 3..index as usize * 3 + 3];
+				if !distinct.contains(&rgb) {
+					distinct.push(rgb);
+				}
+			}
+			(covered, distinct.len())
+		};
+		// max_by_key returns the last maximum, prefer the first
+		let index = (self.candidates.iter().enumerate().rev())
+			.max_by_key(|(_, candidate)| score(candidate))
+			.unwrap()
+			.0;
+		Some((index, PaletteReason::Coverage))
+	}
+
+	/// Which palette each texture was given and why
+	pub fn report(&self) -> &str {
+		&self.report
+	}
+}
+
+/// Converts a palette byte (which is sRGB) to a linear 0-1 value, as GLTF expects for colours
+pub fn srgb_to_linear(value: u8) -> f32 {
+	let value = value as f32 / 255.0;
+	if value <= 0.04045 {
+		value / 12.92
+	} else {
+		((value + 0.055) / 1.055).powf(2.4)
+	}
+}
+/// The inverse of [srgb_to_linear], rounded to the nearest palette byte
+pub fn linear_to_srgb(value: f32) -> u8 {
+	let value = value.clamp(0.0, 1.0);
+	let value = if value <= 0.0031308 {
+		value * 12.92
+	} else {
+		1.055 * value.powf(1.0 / 2.4) - 0.055
+	};
+	(value * 255.0).round() as u8
+}
+
+/// Converts an RGB palette into a GIMP palette
+pub fn to_gpl(name: &str, palette: &[u8]) -> String {
+	let mut result = format!("GIMP Palette\nName: {name}\nColumns: 16\n#\n");
+	for (index, [r, g, b]) in palette.as_chunks::<3>().0.iter().enumerate() {
+		writeln!(result, "{r:3} {g:3} {b:3}\tIndex {index}").unwrap();
+	}
+	result
+}
+
+/// Converts an RGB palette into a Photoshop colour table,
+/// always 256 colours followed by the number of colours actually used
+pub fn to_act(palette: &[u8]) -> Vec<u8> {
+	let num_colours = (palette.len() / 3).min(256);
+	let mut result = vec![0; 256 * 3];
+	result[..num_colours * 3].copy_from_slice(&palette[..num_colours * 3]);
+	result.extend((num_colours as u16).to_be_bytes());
+	result.extend(0xFFFFu16.to_be_bytes()); // no transparent colour
+	result
+}
+
+/// Converts an RGB palette into an Adobe Swatch Exchange file, with every colour in a single group
+pub fn to_ase(name: &str, palette: &[u8]) -> Vec<u8> {
+	// names are null terminated utf-16, prefixed with their length in characters
+	fn ase_name(name: &str) -> Vec<u8> {
+		let chars: Vec<u16> = name.encode_utf16().chain([0]).collect();
+		let mut result = (chars.len() as u16).to_be_bytes().to_vec();
+		result.extend(chars.iter().flat_map(|c| c.to_be_bytes()));
+		result
+	}
+	fn block(result: &mut Vec<u8>, kind: u16, data: &[u8]) {
+		result.extend(kind.to_be_bytes());
+		result.extend((data.len() as u32).to_be_bytes());
+		result.extend(data);
+	}
+	const GROUP_START: u16 = 0xC001;
+	const GROUP_END: u16 = 0xC002;
+	const COLOUR: u16 = 0x0001;
+	const COLOUR_TYPE_NORMAL: u16 = 2;
+
+	let colours = palette.as_chunks::<3>().0;
+	let mut result = b"ASEF".to_vec();
+	result.extend([0, 1, 0, 0]); // version 1.0
+	result.extend((colours.len() as u32 + 2).to_be_bytes());
+
+	block(&mut result, GROUP_START, &ase_name(name));
+	for (index, colour) in colours.iter().enumerate() {
+		let mut data = ase_name(&index.to_string());
+		data.extend(b"RGB ");
+		for c in colour {
+			data.extend((*c as f32 / 255.0).to_be_bytes());
+		}
+		data.extend(COLOUR_TYPE_NORMAL.to_be_bytes());
+		block(&mut result, COLOUR, &data);
+	}
+	block(&mut result, GROUP_END, &[]);
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_swatches() {
+		let palette = [0, 0, 0, 255, 128, 1];
+		assert_eq!(
+			to_gpl("PAL", &palette),
+			"GIMP Palette\nName: PAL\nColumns: 16\n#\n  0   0   0\tIndex 0\n255 128   1\tIndex 1\n"
+		);
+
+		let act = to_act(&palette);
+		assert_eq!(act.len(), 256 * 3 + 4);
+		assert_eq!(act[3..6], [255, 128, 1]);
+		assert_eq!(act[256 * 3..], [0, 2, 0xFF, 0xFF]);
+
+		let ase = to_ase("PAL", &palette);
+		assert_eq!(ase[..12], *b"ASEF\0\x01\0\0\0\0\0\x04");
+		// group start, 2 colours (with 1 character names), group end
+		let colour_size = 6 + (2 + 4) + 4 + 12 + 2;
+		assert_eq!(ase.len(), 12 + (6 + 2 + 8) + colour_size * 2 + 6);
+		assert_eq!(ase[ase.len() - 6..], [0xC0, 0x02, 0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn test_srgb() {
+		assert_eq!(srgb_to_linear(0), 0.0);
+		assert_eq!(srgb_to_linear(255), 1.0);
+		assert!((srgb_to_linear(128) - 0.2158605).abs() < 1e-6);
+		for value in 0..=255 {
+			assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+		}
+	}
+
+	#[test]
+	fn test_palette_resolver() {
+		let grey: Vec<u8> = (0..256 * 3).map(|i| (i / 3) as u8).collect();
+		let flat = [7; 256 * 3];
+		let sys_pal = [9; 64 * 3];
+		let low = Texture::new(2, 1, vec![1, 2]);
+		let high = Texture::new(2, 1, vec![100, 200]);
+
+		let mut resolver = PaletteResolver::new();
+		assert!(
+			resolver
+				.resolve("LOGO", std::slice::from_ref(&low))
+				.is_none()
+		);
+		resolver.add("FLATPAL", "A.BNI", &flat);
+		assert_eq!(
+			resolver.pick("LOGO", std::slice::from_ref(&low)),
+			Some((0, PaletteReason::Only))
+		);
+
+		resolver.add("SYS_PAL", "mdkfont.fti", &sys_pal);
+		resolver.add("GREYPAL", "B.BNI", &grey);
+		assert_eq!(
+			resolver.pick("GREYSCALE", std::slice::from_ref(&high)),
+			Some((2, PaletteReason::Name))
+		);
+		// the flat palette maps everything to one colour
+		assert_eq!(
+			resolver.pick("LOGO", std::slice::from_ref(&low)),
+			Some((2, PaletteReason::Coverage))
+		);
+		// the system palette doesn't have colours this high
+		let (name, palette) = resolver.resolve("LOGO", &[high]).unwrap();
+		assert_eq!((name, palette.len()), ("GREYPAL", 256 * 3));
+		assert_eq!(resolver.report(), "LOGO        \tB.BNI:GREYPAL\tCoverage\n");
+	}
+
+	#[test]
+	fn test_palette_stack() {
+		let sys_pal = [1; 64 * 3];
+		let dti_pal = [2; 256 * 3];
+		let arena_pal = [3; 336];
+
+		let stack = PaletteStack::new(&dti_pal)
+			.with_system(Some(&sys_pal))
+			.with_arena(&arena_pal, 32);
+		let palette = stack.compose();
+		assert_eq!(palette.len(), 256 * 3);
+		assert!(palette[..64 * 3].iter().all(|c| *c == 1), "system colours");
+		assert!(
+			palette[64 * 3..96 * 3].iter().all(|c| *c == 3),
+			"arena colours"
+		);
+		assert!(palette[96 * 3..].iter().all(|c| *c == 2), "dti colours");
+
+		assert_eq!(stack.layer(0), PaletteLayer::System);
+		assert_eq!(stack.layer(95), PaletteLayer::Arena);
+		assert_eq!(stack.layer(96), PaletteLayer::Level);
+
+		let stack = PaletteStack::new(&dti_pal).with_arena(&arena_pal, 0);
+		assert!(stack.compose().iter().all(|c| *c == 2), "no free colours");
+		assert_eq!(stack.layer(0), PaletteLayer::Level);
+	}
+
+	#[test]
+	#[allow(deprecated)]
+	fn test_compose_arena_palette() {
+		let sys_pal = [1; 64 * 3];
+		let dti_pal = [2; 256 * 3];
+		let arena_pal = [3; 336];
+		let stack = PaletteStack::new(&dti_pal)
+			.with_system(Some(&sys_pal))
+			.with_arena(&arena_pal, 32);
+		assert_eq!(
+			compose_arena_palette(&sys_pal, &dti_pal, &arena_pal, 32),
+			stack.compose()
+		);
+	}
+}
@@ -0,0 +1,309 @@
+//! Baking palette cycling effects (water, lava etc.) into animated textures.
+//!
+//! The engine animates some surfaces by rotating a range of palette entries.
+//! Which MTI flags (if any) select this isn't known yet, so the ranges are read from
+//! a json object of `"NAME": { "start": 16, "count": 8, "fps": 12 }` instead.
+//! See `texture_flags.txt` in the texture output for the raw flags of each material.
+//!
+//! The playback speed of animated textures isn't known either (it might be one of the MTI's
+//! unknown per-material values), so it can be overridden per texture with [TextureFps].
+use std::collections::HashMap;
+
+use crate::OutputWriter;
+use crate::data_formats::Texture;
+
+/// A range of palette entries that rotates by one entry per frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub struct PaletteCycle {
+	pub start: u8,
+	pub count: u8,
+	pub fps: u16,
+	/// Whether colours move down the range instead of up
+	#[serde(default)]
+	pub reverse: bool,
+}
+
+impl PaletteCycle {
+	fn contains(&self, colour: u8) -> bool {
+		(self.start as usize..self.start as usize + self.count as usize)
+			.contains(&(colour as usize))
+	}
+
+	/// The colour a pixel shows after the palette has been rotated `step` times
+	pub fn cycle_colour(&self, colour: u8, step: usize) -> u8 {
+		if !self.contains(colour) {
+			return colour;
+		}
+		let count = self.count as usize;
+		let index = colour as usize - self.start as usize;
+		let index = if self.reverse {
+			(index + count - step % count) % count
+		} else {
+			(index + step) % count
+		};
+		(self.start as usize + index) as u8
+	}
+
+	/// Bakes every step of the cycle into the frames of an (already animated) texture.
+	///
+	/// Rotating the palette is done by remapping the pixels instead,
+	/// so the result still uses the original palette.
+	pub fn bake(&self, frames: &[Texture]) -> Vec<Texture<'static>> {
+		assert!(self.count != 0, "empty palette cycle");
+		assert!(
+			self.start as usize + self.count as usize <= 256,
+			"palette cycle out of range"
+		);
+		let num_frames = lcm(frames.len(), self.count as usize);
+		(0..num_frames)
+			.map(|step| {
+				let frame = &frames[step % frames.len()];
+				let pixels: Vec<u8> = frame
+					.pixels
+					.iter()
+					.map(|&p| self.cycle_colour(p, step))
+					.collect();
+				Texture {
+					pixels: pixels.into(),
+					..*frame
+				}
+			})
+			.collect()
+	}
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+	let (mut x, mut y) = (a, b);
+	while y != 0 {
+		(x, y) = (y, x % y);
+	}
+	a / x * b
+}
+
+/// Per-texture palette cycles, loaded from a json file
+#[derive(Default, serde::Deserialize)]
+#[serde(transparent)]
+pub struct PaletteCycles {
+	cycles: HashMap<String, PaletteCycle>,
+	/// Playback speeds for textures without a palette cycle
+	#[serde(skip)]
+	pub fps: TextureFps,
+}
+
+impl PaletteCycles {
+	pub fn load(path: impl AsRef<std::path::Path>) -> Self {
+		let path = path.as_ref();
+		let data = std::fs::read(path).unwrap_or_else(|e| {
+			panic!("failed to read palette cycle file {}: {e}", path.display())
+		});
+		let result: Self = serde_json::from_slice(&data).unwrap_or_else(|e| {
+			panic!("failed to parse palette cycle file {}: {e}", path.display())
+		});
+		for (name, cycle) in &result.cycles {
+			if cycle.fps == 0 {
+				panic!("palette cycle {name} has no fps in {}", path.display());
+			}
+			if cycle.count == 0 {
+				panic!("palette cycle {name} is empty in {}", path.display());
+			}
+			if cycle.start as usize + cycle.count as usize > 256 {
+				panic!(
+					"palette cycle {name} ({}+{}) runs past the end of the palette in {}",
+					cycle.start,
+					cycle.count,
+					path.display()
+				);
+			}
+		}
+		result
+	}
+
+	/// The cycle for a texture, if it uses any of the cycled colours
+	pub fn get(&self, name: &str, frames: &[Texture]) -> Option<&PaletteCycle> {
+		self.cycles.get(name).filter(|cycle| {
+			frames
+				.iter()
+				.any(|frame| frame.pixels.iter().any(|&p| cycle.contains(p)))
+		})
+	}
+
+	/// Saves a texture as an animated png like [Texture::save_animated],
+	/// baking in its palette cycle if it has one.
+	///
+	/// `fps` is only used if the texture has no cycle or speed override.
+	pub fn save_animated(
+		&self, frames: &[Texture], name: &str, fps: u16, output: &mut OutputWriter,
+		palette: Option<&[u8]>,
+	) {
+		match self.get(name, frames) {
+			Some(cycle) => {
+				Texture::save_animated(&cycle.bake(frames), name, cycle.fps, output, palette)
+			}
+			None => {
+				let fps = self.fps.get(name).unwrap_or(fps);
+				Texture::save_animated(frames, name, fps, output, palette)
+			}
+		}
+	}
+}
+
+/// Per-texture playback speeds, loaded from a json object of `"NAME": fps`
+#[derive(Default, serde::Deserialize)]
+#[serde(transparent)]
+pub struct TextureFps(HashMap<String, u16>);
+
+impl TextureFps {
+	pub fn load(path: impl AsRef<std::path::Path>) -> Self {
+		let path = path.as_ref();
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read texture fps file {}: {e}", path.display()));
+		let result: Self = serde_json::from_slice(&data)
+			.unwrap_or_else(|e| panic!("failed to parse texture fps file {}: {e}", path.display()));
+		if let Some((name, _)) = result.0.iter().find(|(_, fps)| **fps == 0) {
+			panic!("texture {name} has no fps in {}", path.display());
+		}
+		result
+	}
+
+	pub fn get(&self, name: &str) -> Option<u16> {
+		self.0.get(name).copied()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bake() {
+		let cycle = PaletteCycle {
+			start: 4,
+			count: 3,
+			fps: 10,
+			reverse: false,
+		};
+		let frames = [
+			Texture::new(2, 1, vec![4, 1]),
+			Texture::new(2, 1, vec![6, 2]),
+		];
+		let baked = cycle.bake(&frames);
+		let pixels: Vec<&[u8]> = baked.iter().map(|f| &f.pixels[..]).collect();
+		assert_eq!(pixels, [[4, 1], [4, 2], [6, 1], [6, 2], [5, 1], [5, 2]]);
+
+		let reverse = PaletteCycle {
+			reverse: true,
+			..cycle
+		};
+		let steps: Vec<u8> = (0..4).map(|step| reverse.cycle_colour(4, step)).collect();
+		assert_eq!(steps, [4, 6, 5, 4]);
+
+		let cycles = PaletteCycles {
+			cycles: HashMap::from([("WATER".to_owned(), cycle)]),
+			fps: TextureFps(HashMap::from([("LAVA".to_owned(), 8)])),
+		};
+		assert!(cycles.get("WATER", &frames).is_some());
+		assert!(
+			cycles
+				.get("WATER", &[Texture::new(1, 1, vec![7])])
+				.is_none()
+		);
+		assert!(cycles.get("LAVA", &frames).is_none());
+		assert_eq!(cycles.fps.get("LAVA"), Some(8));
+		assert_eq!(cycles.fps.get("WATER"), None);
+
+		let loaded: PaletteCycles =
+			serde_json::from_str(r#"{ "WATER": { "start": 4, "count": 3, "fps": 10 } }"#).unwrap();
+		assert_eq!(loaded.cycles["WATER"], cycle);
+	}
+
+	#[test]
+	fn test_texture_fps() {
+		let dir = std::path::Path::new("output/test_texture_fps");
+		let _ = std::fs::remove_dir_all(dir);
+		std::fs::create_dir_all(dir).unwrap();
+		let fps_path = dir.join("fps.json");
+		std::fs::write(&fps_path, r#"{ "LAVA": 8, "WATER": 20 }"#).unwrap();
+		let cycles = PaletteCycles {
+			cycles: HashMap::from([(
+				"WATER".to_owned(),
+				PaletteCycle {
+					start: 4,
+					count: 3,
+					fps: 10,
+					reverse: false,
+				},
+			)]),
+			fps: TextureFps::load(&fps_path),
+		};
+
+		let frames = [
+			Texture::new(2, 1, vec![4, 1]),
+			Texture::new(2, 1, vec![6, 2]),
+		];
+		let mut output = OutputWriter::new_in_dir(dir);
+		for name in ["LAVA", "SLIME", "WATER"] {
+			cycles.save_animated(&frames, name, 12, &mut output, None);
+		}
+		let fps = |name: &str| {
+			let file = std::fs::File::open(dir.join(name).with_extension("png")).unwrap();
+			let reader = png::Decoder::new(file).read_info().unwrap();
+			let control = reader.info().frame_control.unwrap();
+			control.delay_den / control.delay_num
+		};
+		// overridden, the default, and a palette cycle keeping its own speed
+		assert_eq!([fps("LAVA"), fps("SLIME"), fps("WATER")], [8, 12, 10]);
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	fn load_cycles(name: &str, json: &str) -> PaletteCycles {
+		let path = std::path::Path::new("output").join(name);
+		std::fs::create_dir_all("output").unwrap();
+		std::fs::write(&path, json).unwrap();
+		let result = std::panic::catch_unwind(|| PaletteCycles::load(&path));
+		std::fs::remove_file(&path).unwrap();
+		result.unwrap_or_else(|e| std::panic::resume_unwind(e))
+	}
+
+	#[test]
+	#[should_panic = "palette cycle WATER has no fps"]
+	fn test_cycle_fps_zero() {
+		load_cycles(
+			"test_cycle_fps_zero.json",
+			r#"{ "WATER": { "start": 4, "count": 3, "fps": 0 } }"#,
+		);
+	}
+
+	#[test]
+	#[should_panic = "palette cycle WATER is empty"]
+	fn test_cycle_empty() {
+		load_cycles(
+			"test_cycle_empty.json",
+			r#"{ "WATER": { "start": 4, "count": 0, "fps": 10 } }"#,
+		);
+	}
+
+	#[test]
+	#[should_panic = "palette cycle LAVA (250+8) runs past the end of the palette"]
+	fn test_cycle_out_of_range() {
+		let cycles = load_cycles(
+			"test_cycle_in_range.json",
+			r#"{ "LAVA": { "start": 248, "count": 8, "fps": 10 } }"#,
+		);
+		assert_eq!(cycles.cycles["LAVA"].start, 248);
+		load_cycles(
+			"test_cycle_out_of_range.json",
+			r#"{ "LAVA": { "start": 250, "count": 8, "fps": 10 } }"#,
+		);
+	}
+
+	#[test]
+	#[should_panic = "texture LAVA has no fps"]
+	fn test_texture_fps_zero() {
+		let path = std::path::Path::new("output/test_texture_fps_zero.json");
+		std::fs::create_dir_all("output").unwrap();
+		std::fs::write(path, r#"{ "LAVA": 0 }"#).unwrap();
+		let result = std::panic::catch_unwind(|| TextureFps::load(path));
+		std::fs::remove_file(path).unwrap();
+		std::panic::resume_unwind(result.err().unwrap());
+	}
+}
@@ -1,10 +1,21 @@
+/// A triangle material, stored as a single signed index in meshes
+/// (and as a pen value in MTI files, see [Pen::from_mti_value]).
+///
+/// | mesh index     | pen                  |
+/// |----------------|----------------------|
+/// | 0..=255        | `Texture(index)`     |
+/// | -255..=-1      | `Colour(-index)`     |
+/// | -1010..=-990   | `Shiny(-990 - index)` |
+/// | -1027..=-1024  | `Translucent(-1024 - index)` |
+/// | anything else  | `Unknown(index)`     |
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Pen {
 	Texture(u8),     // index into mesh material array
 	Colour(u8),      // index into palette
 	Translucent(u8), // index into dti translucent_colours
 	Shiny(u8), // value contains the 'angle' of the shiny material (y-offset of the reflected texture)
-	Unknown(i32), // todo
+	Unknown(i32), // mesh index outside any known range
+	UnknownMti(i32), // negative MTI pen value, which doesn't correspond to any mesh index
 }
 impl Pen {
 	pub fn new(index: i32) -> Pen {
@@ -16,4 +27,90 @@ impl Pen {
 			_ => Pen::Unknown(index), // todo
 		}
 	}
+
+	/// The mesh index of this pen, the inverse of [Pen::new].
+	///
+	/// Returns `None` for pens that can only come from MTI files (`Colour(0)` and `UnknownMti`)
+	/// or whose value is out of range for their kind.
+	pub fn to_index(self) -> Option<i32> {
+		let index = match self {
+			Pen::Texture(n) => n as i32,
+			Pen::Colour(0) => return None,
+			Pen::Colour(n) => -(n as i32),
+			Pen::Shiny(n) => -990 - n as i32,
+			Pen::Translucent(n) => -1024 - n as i32,
+			Pen::Unknown(index) => index,
+			Pen::UnknownMti(_) => return None,
+		};
+		(Pen::new(index) == self).then_some(index)
+	}
+
+	/// Converts a pen value from an MTI file, which are positive mesh indices of non-texture pens
+	pub fn from_mti_value(value: i32) -> Pen {
+		match value {
+			0 => Pen::Colour(0),
+			1.. => Pen::new(-value),     // negate to match mesh tri values
+			_ => Pen::UnknownMti(value), // todo negative?
+		}
+	}
+
+	/// The MTI pen value of this pen, the inverse of [Pen::from_mti_value].
+	///
+	/// Returns `None` for pens that can't be stored in MTI files, like textures.
+	pub fn to_mti_value(self) -> Option<i32> {
+		match self {
+			Pen::Colour(0) => Some(0),
+			Pen::UnknownMti(value) if value < 0 => Some(value),
+			Pen::UnknownMti(_) => None,
+			pen => pen
+				.to_index()
+				.filter(|index| *index < 0)
+				.and_then(i32::checked_neg),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_index_round_trip() {
+		for index in -2000..=2000 {
+			let pen = Pen::new(index);
+			assert_eq!(pen.to_index(), Some(index), "{pen:?}");
+			let expected = match index {
+				0..=255 => Pen::Texture(index as u8),
+				-255..=-1 => Pen::Colour(-index as u8),
+				-1010..=-990 => Pen::Shiny((-990 - index) as u8),
+				-1027..=-1024 => Pen::Translucent((-1024 - index) as u8),
+				_ => Pen::Unknown(index),
+			};
+			assert_eq!(pen, expected);
+		}
+		for extreme in [i32::MIN, i32::MAX] {
+			assert_eq!(Pen::new(extreme).to_index(), Some(extreme));
+		}
+
+		// mti only or out of range
+		assert_eq!(Pen::Colour(0).to_index(), None);
+		assert_eq!(Pen::UnknownMti(-5).to_index(), None);
+		assert_eq!(Pen::Shiny(21).to_index(), None);
+		assert_eq!(Pen::Translucent(4).to_index(), None);
+		assert_eq!(Pen::Unknown(5).to_index(), None);
+	}
+
+	#[test]
+	fn test_mti_round_trip() {
+		for value in -2000..=2000 {
+			let pen = Pen::from_mti_value(value);
+			assert_eq!(pen.to_mti_value(), Some(value), "{pen:?}");
+		}
+		assert_eq!(Pen::from_mti_value(3), Pen::Colour(3));
+		assert_eq!(Pen::from_mti_value(995), Pen::Shiny(5));
+		assert_eq!(Pen::from_mti_value(1025), Pen::Translucent(1));
+		assert_eq!(Pen::from_mti_value(-3), Pen::UnknownMti(-3));
+		assert_eq!(Pen::Texture(3).to_mti_value(), None);
+		assert_eq!(Pen::Unknown(i32::MIN).to_mti_value(), None);
+	}
 }
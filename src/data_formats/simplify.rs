@@ -0,0 +1,468 @@
+//! Mesh simplification for generating LODs, by quadric error edge collapse (Garland & Heckbert).
+//!
+//! Each vertex keeps the sum of the squared distances to the planes of its triangles,
+//! and the edge whose collapse adds the least to that error is collapsed first.
+//! Open edges and edges between materials get extra planes at right angles to their triangles
+//! so the outline of the mesh and of each material stays in place.
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::Vec3;
+use crate::data_formats::mesh::{MeshGeo, MeshTri};
+
+/// How much more moving an open or material edge costs than moving a surface
+const SEAM_WEIGHT: f64 = 100.0;
+
+type Vec3d = [f64; 3];
+
+fn to_f64(v: Vec3) -> Vec3d {
+	v.to_array().map(f64::from)
+}
+fn sub(a: Vec3d, b: Vec3d) -> Vec3d {
+	[a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+fn cross(a: Vec3d, b: Vec3d) -> Vec3d {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+fn dot(a: Vec3d, b: Vec3d) -> f64 {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Sum of squared distances to a set of planes, the upper half of the 4x4 matrix `p pᵀ`
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+	/// The plane with the given (non-zero) normal through `point`, scaled by `weight`
+	fn plane(normal: Vec3d, point: Vec3d, weight: f64) -> Self {
+		let length = dot(normal, normal).sqrt();
+		let [a, b, c] = normal.map(|n| n / length);
+		let d = -dot([a, b, c], point);
+		Quadric([
+			a * a,
+			a * b,
+			a * c,
+			a * d,
+			b * b,
+			b * c,
+			b * d,
+			c * c,
+			c * d,
+			d * d,
+		])
+		.scale(weight)
+	}
+
+	fn scale(self, weight: f64) -> Self {
+		Quadric(self.0.map(|n| n * weight))
+	}
+
+	fn add(self, other: Quadric) -> Self {
+		Quadric(std::array::from_fn(|i| self.0[i] + other.0[i]))
+	}
+
+	fn error(&self, point: Vec3) -> f64 {
+		let [x, y, z] = to_f64(point);
+		let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.0;
+		aa * x * x
+			+ bb * y * y
+			+ cc * z * z
+			+ 2.0 * (ab * x * y + ac * x * z + bc * y * z)
+			+ 2.0 * (ad * x + bd * y + cd * z)
+			+ dd
+	}
+}
+
+/// A possible edge collapse, only valid while neither vertex has changed since
+struct Collapse {
+	cost: f64,
+	keep: u16,
+	remove: u16,
+	versions: [u32; 2],
+	target: Vec3,
+}
+
+impl PartialEq for Collapse {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+impl Eq for Collapse {}
+impl PartialOrd for Collapse {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl Ord for Collapse {
+	// cheapest first
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.cost.total_cmp(&self.cost)
+	}
+}
+
+struct Simplifier<'a> {
+	tris: &'a [MeshTri],
+	verts: Vec<Vec3>,
+	quadrics: Vec<Quadric>,
+	versions: Vec<u32>,
+	removed: Vec<bool>,
+	indices: Vec<[u16; 3]>,
+	alive: Vec<bool>,
+	vert_tris: Vec<Vec<usize>>,
+	heap: BinaryHeap<Collapse>,
+}
+
+impl Simplifier<'_> {
+	fn normal(&self, [i1, i2, i3]: [u16; 3]) -> Vec3d {
+		let [p1, p2, p3] = [i1, i2, i3].map(|i| to_f64(self.verts[i as usize]));
+		cross(sub(p2, p1), sub(p3, p1))
+	}
+
+	/// Queues collapsing the edge between two vertices at whichever of the two ends
+	/// or the midpoint adds the least error
+	fn push(&mut self, keep: u16, remove: u16) {
+		let quadric = self.quadrics[keep as usize].add(self.quadrics[remove as usize]);
+		let [a, b] = [keep, remove].map(|i| self.verts[i as usize]);
+		let (cost, target) = [a, b, (a + b) * 0.5]
+			.into_iter()
+			.map(|target| (quadric.error(target), target))
+			.min_by(|x, y| x.0.total_cmp(&y.0))
+			.unwrap();
+		self.heap.push(Collapse {
+			cost,
+			keep,
+			remove,
+			versions: [self.versions[keep as usize], self.versions[remove as usize]],
+			target,
+		});
+	}
+
+	/// Whether collapsing the edge would leave no triangles at all
+	fn empties(&self, keep: u16, remove: u16, num_tris: usize) -> bool {
+		let lost = self.vert_tris[keep as usize]
+			.iter()
+			.filter(|&&t| self.alive[t] && self.indices[t].contains(&remove))
+			.count();
+		lost == num_tris
+	}
+
+	/// Whether moving both vertices of the edge to `target` would turn any triangle around
+	fn flips(&self, keep: u16, remove: u16, target: Vec3) -> bool {
+		let old_verts = [keep, remove];
+		old_verts
+			.iter()
+			.flat_map(|&v| &self.vert_tris[v as usize])
+			.filter(|&&t| self.alive[t])
+			.map(|&t| self.indices[t])
+			.filter(|tri| !(tri.contains(&keep) && tri.contains(&remove)))
+			.any(|tri| {
+				let old_normal = self.normal(tri);
+				let moved = tri.map(|i| {
+					if old_verts.contains(&i) {
+						to_f64(target)
+					} else {
+						to_f64(self.verts[i as usize])
+					}
+				});
+				let new_normal = cross(sub(moved[1], moved[0]), sub(moved[2], moved[0]));
+				dot(old_normal, old_normal) > 0.0 && dot(old_normal, new_normal) <= 0.0
+			})
+	}
+
+	/// Collapses `remove` into `keep`, returning how many triangles were lost
+	fn collapse(&mut self, keep: u16, remove: u16, target: Vec3) -> usize {
+		let (k, r) = (keep as usize, remove as usize);
+		self.verts[k] = target;
+		self.quadrics[k] = self.quadrics[k].add(self.quadrics[r]);
+		self.versions[k] += 1;
+		self.removed[r] = true;
+
+		let mut lost = 0;
+		for t in std::mem::take(&mut self.vert_tris[r]) {
+			if !self.alive[t] {
+				continue;
+			}
+			let tri = &mut self.indices[t];
+			for index in tri.iter_mut() {
+				if *index == remove {
+					*index = keep;
+				}
+			}
+			let [i1, i2, i3] = *tri;
+			if i1 == i2 || i1 == i3 || i2 == i3 {
+				self.alive[t] = false;
+				lost += 1;
+			} else {
+				self.vert_tris[k].push(t);
+			}
+		}
+		let alive = &self.alive;
+		self.vert_tris[k].retain(|&t| alive[t]);
+		self.vert_tris[k].sort_unstable();
+		self.vert_tris[k].dedup();
+
+		let mut neighbours: Vec<u16> = self.vert_tris[k]
+			.iter()
+			.flat_map(|&t| self.indices[t])
+			.filter(|&i| i != keep)
+			.collect();
+		neighbours.sort_unstable();
+		neighbours.dedup();
+		for neighbour in neighbours {
+			self.push(keep, neighbour);
+		}
+		lost
+	}
+}
+
+impl MeshGeo {
+	/// Simplifies the mesh by collapsing the edges that change its shape the least
+	/// until it has at most `target_tris` triangles, or until every collapse left
+	/// would turn a triangle around or remove the last of them.
+	///
+	/// Triangles keep their material, uvs and flags, and any which end up with no area
+	/// (or duplicate another triangle) are dropped.
+	pub fn decimate(&self, target_tris: usize) -> MeshGeo {
+		let indices: Vec<[u16; 3]> = self.tris.iter().map(|tri| tri.indices).collect();
+		let mut simplifier = Simplifier {
+			tris: &self.tris,
+			verts: self.verts.clone(),
+			quadrics: vec![Quadric::default(); self.verts.len()],
+			versions: vec![0; self.verts.len()],
+			removed: vec![false; self.verts.len()],
+			alive: indices
+				.iter()
+				.map(|&[i1, i2, i3]| i1 != i2 && i1 != i3 && i2 != i3)
+				.collect(),
+			indices,
+			vert_tris: vec![Vec::new(); self.verts.len()],
+			heap: BinaryHeap::new(),
+		};
+
+		// every triangle's plane, weighted by its area
+		let mut edges = HashMap::<[u16; 2], Vec<usize>>::new();
+		for (t, &tri) in simplifier.indices.iter().enumerate() {
+			if !simplifier.alive[t] {
+				continue;
+			}
+			let normal = simplifier.normal(tri);
+			let area = dot(normal, normal).sqrt() * 0.5;
+			for (i, &index) in tri.iter().enumerate() {
+				simplifier.vert_tris[index as usize].push(t);
+				if area > 0.0 {
+					let point = to_f64(simplifier.verts[index as usize]);
+					let quadric = &mut simplifier.quadrics[index as usize];
+					*quadric = quadric.add(Quadric::plane(normal, point, area));
+				}
+				let mut edge = [index, tri[(i + 1) % 3]];
+				edge.sort_unstable();
+				edges.entry(edge).or_default().push(t);
+			}
+		}
+
+		// keep open edges and material boundaries from moving sideways
+		for (&[a, b], edge_tris) in &edges {
+			let seam = match edge_tris[..] {
+				[t1, t2] => simplifier.tris[t1].material != simplifier.tris[t2].material,
+				_ => true,
+			};
+			if !seam {
+				continue;
+			}
+			let [pa, pb] = [a, b].map(|i| to_f64(simplifier.verts[i as usize]));
+			let edge = sub(pb, pa);
+			for &t in edge_tris {
+				let normal = cross(edge, simplifier.normal(simplifier.indices[t]));
+				if dot(normal, normal) > 0.0 {
+					let quadric = Quadric::plane(normal, pa, dot(edge, edge) * SEAM_WEIGHT);
+					for i in [a, b] {
+						let q = &mut simplifier.quadrics[i as usize];
+						*q = q.add(quadric);
+					}
+				}
+			}
+		}
+
+		for &[a, b] in edges.keys() {
+			simplifier.push(a, b);
+		}
+
+		let mut num_tris = simplifier.alive.iter().filter(|alive| **alive).count();
+		while num_tris > target_tris {
+			let Some(Collapse {
+				keep,
+				remove,
+				versions,
+				target,
+				..
+			}) = simplifier.heap.pop()
+			else {
+				break;
+			};
+			if simplifier.removed[keep as usize]
+				|| simplifier.removed[remove as usize]
+				|| versions
+					!= [
+						simplifier.versions[keep as usize],
+						simplifier.versions[remove as usize],
+					] || simplifier.empties(keep, remove, num_tris)
+				|| simplifier.flips(keep, remove, target)
+			{
+				continue;
+			}
+			num_tris -= simplifier.collapse(keep, remove, target);
+		}
+
+		// drop the unused vertices and any duplicate triangles
+		let mut remap = vec![u16::MAX; self.verts.len()];
+		let mut verts = Vec::new();
+		let mut seen = HashSet::new();
+		let mut tris = Vec::new();
+		for (t, tri) in self.tris.iter().enumerate() {
+			if !simplifier.alive[t] {
+				continue;
+			}
+			let mut key = simplifier.indices[t];
+			key.sort_unstable();
+			if !seen.insert(key) {
+				continue;
+			}
+			let indices = simplifier.indices[t].map(|i| {
+				let index = &mut remap[i as usize];
+				if *index == u16::MAX {
+					verts.push(simplifier.verts[i as usize]);
+					*index = (verts.len() - 1) as u16;
+				}
+				*index
+			});
+			tris.push(MeshTri {
+				indices,
+				..tri.clone()
+			});
+		}
+
+		MeshGeo {
+			verts,
+			tris,
+			bbox: self.bbox,
+		}
+	}
+
+	/// Simplified version of the mesh for the given level of detail (1 being the highest),
+	/// with half as many triangles as the level before it
+	pub fn decimate_lod(&self, level: u32) -> MeshGeo {
+		self.decimate(self.tris.len() >> level.min(usize::BITS - 1))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data_formats::Pen;
+
+	/// A flat square of `size` by `size` quads, textured by `material(x)` for each column
+	fn grid(size: u16, material: impl Fn(u16) -> Pen) -> MeshGeo {
+		let verts: Vec<Vec3> = (0..=size)
+			.flat_map(|z| (0..=size).map(move |x| Vec3::new(x as f32, 0.0, z as f32)))
+			.collect();
+		let index = |x: u16, z: u16| z * (size + 1) + x;
+		let tri = |indices, x| MeshTri {
+			indices,
+			material: material(x),
+			uvs: Default::default(),
+			flags: 0,
+		};
+		let tris = (0..size)
+			.flat_map(|z| (0..size).map(move |x| (x, z)))
+			.flat_map(|(x, z)| {
+				let [a, b, c, d] = [
+					index(x, z),
+					index(x + 1, z),
+					index(x, z + 1),
+					index(x + 1, z + 1),
+				];
+				[tri([a, c, b], x), tri([b, c, d], x)]
+			})
+			.collect();
+		let bbox = Vec3::calculate_bbox(&verts);
+		MeshGeo { verts, tris, bbox }
+	}
+
+	#[test]
+	fn test_decimate() {
+		let geo = grid(8, |_| Pen::Texture(0));
+		assert_eq!(geo.tris.len(), 128);
+		let lod = geo.decimate(32);
+		assert!(
+			!lod.tris.is_empty() && lod.tris.len() <= 32,
+			"{}",
+			lod.tris.len()
+		);
+		// still the same flat square
+		assert!(lod.verts.iter().all(|v| v.y == 0.0));
+		assert_eq!(Vec3::calculate_bbox(&lod.verts), geo.bbox);
+		assert!(lod.verts.len() < geo.verts.len());
+		assert!(lod.tris.iter().all(|tri| tri.material == Pen::Texture(0)));
+
+		let lod1 = geo.decimate_lod(1);
+		assert!(
+			lod1.tris.len() <= 64 && lod1.tris.len() > 32,
+			"{}",
+			lod1.tris.len()
+		);
+		let unchanged = geo.decimate(geo.tris.len());
+		assert_eq!(
+			(unchanged.verts.len(), unchanged.tris.len()),
+			(geo.verts.len(), geo.tris.len())
+		);
+	}
+
+	#[test]
+	fn test_decimate_degenerate() {
+		let mut geo = grid(1, |_| Pen::Texture(0));
+		let tri = geo.tris[0].clone();
+		geo.tris.push(MeshTri {
+			indices: [0, 0, 1],
+			..tri.clone()
+		});
+		geo.tris.push(MeshTri {
+			indices: [1, 0, 2],
+			..tri
+		});
+		let lod = geo.decimate(usize::MAX);
+		assert_eq!((lod.verts.len(), lod.tris.len()), (4, 2));
+
+		let lod = grid(8, |_| Pen::Texture(0)).decimate(0);
+		assert!(!lod.tris.is_empty());
+		for tri in &lod.tris {
+			let [i1, i2, i3] = tri.indices;
+			assert!(i1 != i2 && i1 != i3 && i2 != i3);
+		}
+	}
+
+	#[test]
+	fn test_decimate_materials() {
+		let material = |x| Pen::Texture(if x < 4 { 0 } else { 1 });
+		let lod = grid(8, material).decimate(16);
+		assert!(lod.tris.len() <= 16, "{}", lod.tris.len());
+		for texture in [0, 1] {
+			let tris: Vec<&MeshTri> = lod
+				.tris
+				.iter()
+				.filter(|tri| tri.material == Pen::Texture(texture))
+				.collect();
+			assert!(!tris.is_empty(), "lost texture {texture}");
+			// the boundary between the two materials stays where it was
+			for tri in tris {
+				for i in tri.indices {
+					let x = lod.verts[i as usize].x;
+					assert!(if texture == 0 { x <= 4.0 } else { x >= 4.0 }, "{x}");
+				}
+			}
+		}
+	}
+}
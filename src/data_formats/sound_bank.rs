@@ -0,0 +1,224 @@
+//! Cue sheets listing every sound an arena can play, so a level's soundscape can be auditioned
+//! without digging through the scripts.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::data_formats::Wav;
+use crate::data_formats::cmi_bytecode::CmiSoundRef;
+use crate::{ExportSettings, OutputWriter};
+
+/// Matches the sound names scripts use to the sounds a level actually has.
+///
+/// Names without an exact match are matched ignoring case, then to a sound whose name has an extra
+/// prefix or is cut short (or longer), picking the one closest in length.
+pub struct SoundAliases<'a> {
+	names: Vec<&'a str>,
+	/// Every name looked up without an exact match, and what it resolved to
+	lookups: BTreeMap<&'a str, Option<&'a str>>,
+}
+
+impl<'a> SoundAliases<'a> {
+	/// The shortest name that can be matched by prefix or truncation
+	const MIN_PARTIAL_LEN: usize = 4;
+
+	pub fn new(names: impl IntoIterator<Item = &'a str>) -> Self {
+		let mut names: Vec<&str> = names.into_iter().collect();
+		names.sort_unstable();
+		names.dedup();
+		Self {
+			names,
+			lookups: BTreeMap::new(),
+		}
+	}
+
+	/// The sound `name` refers to, if any
+	pub fn resolve(&mut self, name: &'a str) -> Option<&'a str> {
+		if self.names.binary_search(&name).is_ok() {
+			return Some(name);
+		}
+		*self
+			.lookups
+			.entry(name)
+			.or_insert_with(|| Self::find(&self.names, name))
+	}
+
+	fn find(names: &[&'a str], name: &str) -> Option<&'a str> {
+		if let Some(found) = names.iter().find(|other| other.eq_ignore_ascii_case(name)) {
+			return Some(found);
+		}
+		let name = name.to_ascii_uppercase();
+		names
+			.iter()
+			.copied()
+			.filter(|other| {
+				let other = other.to_ascii_uppercase();
+				let (short, long) = if other.len() < name.len() {
+					(&other, &name)
+				} else {
+					(&name, &other)
+				};
+				short.len() >= Self::MIN_PARTIAL_LEN
+					&& (long.starts_with(short.as_str()) || long.ends_with(short.as_str()))
+			})
+			.min_by_key(|other| other.len().abs_diff(name.len()))
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.lookups.is_empty()
+	}
+
+	/// Each name that wasn't exact, with the sound it was matched to or `(missing)`
+	pub fn report(&self) -> String {
+		let mut result = String::from("reference\tsound\n");
+		for (name, found) in &self.lookups {
+			writeln!(result, "{name}\t{}", found.unwrap_or("(missing)")).unwrap();
+		}
+		result
+	}
+}
+
+/// All the sounds used by a single arena, saved as json next to the exported wav files
+#[derive(Serialize)]
+pub struct SoundBank<'a> {
+	pub arena: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub song: Option<SoundBankEntry<'a>>,
+	pub sounds: Vec<SoundBankEntry<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct SoundBankEntry<'a> {
+	pub name: &'a str,
+	/// Path of the wav file, relative to the sound bank
+	pub file: String,
+	pub channels: u16,
+	pub sample_rate: u32,
+	pub bits_per_sample: u16,
+	pub duration_secs: f32,
+	/// Entities whose scripts play this sound
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub entities: Vec<&'a str>,
+	/// Fixed positions the sound is played at
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub positions: Vec<[f32; 3]>,
+	/// Every distinct way the sound is played
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub emitters: Vec<SoundBankEmitter<'a>>,
+}
+
+/// Where an entity plays a sound from, see [CmiSoundRef]
+#[derive(Serialize, PartialEq)]
+pub struct SoundBankEmitter<'a> {
+	pub entity: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub position: Option<[f32; 3]>,
+	/// Index of the entity's mesh reference point
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub point: Option<u8>,
+	pub sound_type: u8,
+}
+
+impl<'a> SoundBankEntry<'a> {
+	pub fn new(name: &'a str, file: String, wav: &Wav) -> Self {
+		Self {
+			name,
+			file,
+			channels: wav.num_channels,
+			sample_rate: wav.samples_per_second,
+			bits_per_sample: wav.bits_per_sample,
+			duration_secs: wav.duration_secs,
+			entities: Vec::new(),
+			positions: Vec::new(),
+			emitters: Vec::new(),
+		}
+	}
+}
+
+impl<'a> SoundBank<'a> {
+	pub fn new(arena: &'a str) -> Self {
+		Self {
+			arena,
+			song: None,
+			sounds: Vec::new(),
+		}
+	}
+
+	/// Adds a sound if it isn't already in the bank, returning its entry
+	pub fn add_sound(&mut self, name: &'a str, file: String, wav: &Wav) -> &mut SoundBankEntry<'a> {
+		let index = match self.sounds.iter().position(|entry| entry.name == name) {
+			Some(index) => index,
+			None => {
+				self.sounds.push(SoundBankEntry::new(name, file, wav));
+				self.sounds.len() - 1
+			}
+		};
+		&mut self.sounds[index]
+	}
+
+	/// Records that `entity` plays `sound`, with its position in export coordinates
+	pub fn add_usage(&mut self, entity: &'a str, sound: &CmiSoundRef, settings: &ExportSettings) {
+		let Some(entry) = self
+			.sounds
+			.iter_mut()
+			.find(|entry| entry.name == sound.name)
+		else {
+			return;
+		};
+		if !entry.entities.contains(&entity) {
+			entry.entities.push(entity);
+		}
+		let position = sound
+			.position
+			.map(|p| settings.transform_point(p).to_array());
+		if let Some(position) = position
+			&& !entry.positions.contains(&position)
+		{
+			entry.positions.push(position);
+		}
+		let emitter = SoundBankEmitter {
+			entity,
+			position,
+			point: sound.point,
+			sound_type: sound.sound_type,
+		};
+		if !entry.emitters.contains(&emitter) {
+			entry.emitters.push(emitter);
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.song.is_none() && self.sounds.is_empty()
+	}
+
+	pub fn save(&self, output: &mut OutputWriter) {
+		let json = serde_json::to_string_pretty(self).unwrap();
+		output.write(self.arena, "json", json);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sound_aliases() {
+		let mut aliases = SoundAliases::new(["BEEP", "GUNTFIRE", "L3_DOOROPEN", "ZAP"]);
+		assert_eq!(aliases.resolve("BEEP"), Some("BEEP"));
+		assert_eq!(aliases.resolve("beep"), Some("BEEP"));
+		assert_eq!(aliases.resolve("GUNTFIRE2"), Some("GUNTFIRE"));
+		assert_eq!(aliases.resolve("DOOROPEN"), Some("L3_DOOROPEN"));
+		assert_eq!(aliases.resolve("ZAPPER"), None);
+		assert_eq!(aliases.resolve("HUM"), None);
+		assert_eq!(
+			aliases.report(),
+			"reference\tsound\n\
+			DOOROPEN\tL3_DOOROPEN\n\
+			GUNTFIRE2\tGUNTFIRE\n\
+			HUM\t(missing)\n\
+			ZAPPER\t(missing)\n\
+			beep\tBEEP\n"
+		);
+	}
+}
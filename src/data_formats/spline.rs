@@ -1,4 +1,7 @@
-use crate::{OutputWriter, Reader, Vec3};
+#[cfg(feature = "export")]
+use crate::{OutputWriter, gltf};
+use crate::{Reader, Vec3};
+#[cfg(feature = "export")]
 use std::fmt::Write;
 
 /// 3D Spline type used for CMI paths
@@ -6,6 +9,7 @@ pub struct Spline {
 	pub points: Vec<SplinePoint>,
 }
 
+/// A point along a spline, in the game's coordinates (swizzled when exported)
 pub struct SplinePoint {
 	pub t: i32,
 	pub pos1: Vec3,
@@ -34,8 +38,10 @@ impl Spline {
 		Spline { points }
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save_as(&self, name: &str, output: &mut OutputWriter) {
 		// todo transform to actual bezier curves
+		let settings = output.export_settings();
 		let mut data = String::from("time\tpos1\tpos2\tpos3\n");
 		for SplinePoint {
 			t,
@@ -44,8 +50,69 @@ impl Spline {
 			pos3,
 		} in &self.points
 		{
+			let [pos1, pos2, pos3] =
+				[pos1, pos2, pos3].map(|pos| settings.transform_point(pos.swizzle()));
 			writeln!(&mut data, "{t}\t{pos1}\t{pos2}\t{pos3}").unwrap();
 		}
 		output.write(name, "tsv", data);
 	}
+
+	#[cfg(feature = "export")]
+	/// Adds the path as a line strip through the first position of each point
+	pub fn add_to_gltf(
+		&self, gltf: &mut gltf::Gltf, name: String, parent: gltf::NodeIndex,
+	) -> gltf::NodeIndex {
+		let points: Vec<Vec3> = self
+			.points
+			.iter()
+			.map(|point| point.pos1.swizzle())
+			.collect();
+		let indices: Vec<u16> = (0..points.len() as u16).collect();
+		let mesh = gltf.create_mesh(name.clone());
+		let prim = gltf.add_mesh_primitive(mesh, &points, &indices, None);
+		gltf.set_primitive_mode(prim, gltf::PrimitiveMode::LineStrip);
+		gltf.create_child_node(parent, name, Some(mesh))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::ByteBuilder;
+
+	#[test]
+	fn test_spline() {
+		let mut data = ByteBuilder::new();
+		data.u32(2);
+		for t in 0..2 {
+			data.i32(t)
+				.vec3(Vec3::new(1.0, 2.0, 3.0))
+				.vec3(Vec3::new(4.0, 5.0, 6.0))
+				.vec3(Vec3::new(7.0, 8.0, 9.0));
+		}
+		let data = data.finish();
+		let spline = Spline::parse(&mut Reader::new(&data));
+		// parsing keeps the game's coordinates
+		assert_eq!(spline.points.len(), 2);
+		assert_eq!(spline.points[1].t, 1);
+		assert_eq!(spline.points[1].pos1, Vec3::new(1.0, 2.0, 3.0));
+
+		#[cfg(feature = "export")]
+		{
+			let dir = std::path::Path::new("output/test_spline");
+			let _ = std::fs::remove_dir_all(dir);
+			let mut output = OutputWriter::new_in_dir(dir);
+			spline.save_as("PATH", &mut output);
+			let tsv = std::fs::read_to_string(dir.join("PATH.tsv")).unwrap();
+			let expected = Vec3::new(1.0, 2.0, 3.0).swizzle();
+			assert!(
+				tsv.lines()
+					.nth(1)
+					.unwrap()
+					.starts_with(&format!("0\t{expected}\t")),
+				"{tsv}"
+			);
+			std::fs::remove_dir_all(dir).unwrap();
+		}
+	}
 }
@@ -1,7 +1,31 @@
 use std::borrow::Cow;
+#[cfg(feature = "export")]
+use std::path::Path;
 
+#[cfg(feature = "export")]
 use crate::OutputWriter;
 
+/// How [Texture::from_png] spreads the error of mapping colours to the palette
+#[cfg(feature = "export")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+	/// Nearest colour only
+	#[default]
+	None,
+	FloydSteinberg,
+}
+
+#[cfg(feature = "export")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PngImportOptions {
+	pub dither: Dither,
+	/// Palette index for transparent pixels (alpha below half), which opaque pixels never use.
+	///
+	/// Usually 0, see [masking](crate::data_formats::masking).
+	/// If `None`, transparency is ignored.
+	pub transparent_index: Option<u8>,
+}
+
 /// 2D Textures
 #[derive(Default, Clone, PartialEq, Eq)]
 pub struct Texture<'a> {
@@ -27,9 +51,105 @@ impl<'a> Texture<'a> {
 		}
 	}
 
-	pub fn create_png(&self, palette: Option<&[u8]>) -> Vec<u8> {
-		let _ = palette;
-		todo!()
+	/// Creates a copy of the texture that borrows its pixels instead of cloning them
+	pub fn to_borrowed(&self) -> Texture<'_> {
+		Texture {
+			width: self.width,
+			height: self.height,
+			pixels: Cow::Borrowed(&self.pixels),
+			position: self.position,
+		}
+	}
+	/// Copies the pixels so the texture no longer borrows the file it was parsed from
+	pub fn into_owned(self) -> Texture<'static> {
+		Texture {
+			pixels: Cow::Owned(self.pixels.into_owned()),
+			..self
+		}
+	}
+}
+
+#[cfg(feature = "export")]
+impl Texture<'_> {
+	/// Loads an 8-bit indexed PNG, using its colour indices as-is.
+	///
+	/// 8-bit grayscale PNGs (like [Self::create_png] makes without a palette) use their values as indices.
+	pub fn from_indexed_png(data: &[u8]) -> Texture<'static> {
+		let decoder = png::Decoder::new(data);
+		let mut reader = decoder.read_info().expect("failed to read png");
+		let info = reader.info();
+		assert!(
+			matches!(
+				info.color_type,
+				png::ColorType::Indexed | png::ColorType::Grayscale
+			) && info.bit_depth == png::BitDepth::Eight,
+			"png must be 8-bit indexed or grayscale, not {:?} {:?}",
+			info.bit_depth,
+			info.color_type
+		);
+		let width = u16::try_from(info.width).expect("png too large");
+		let height = u16::try_from(info.height).expect("png too large");
+		let mut pixels = vec![0; reader.output_buffer_size()];
+		let frame = reader
+			.next_frame(&mut pixels)
+			.expect("failed to decode png");
+		pixels.truncate(frame.buffer_size());
+		Texture::new(width, height, pixels)
+	}
+
+	/// Loads a PNG of any colour type and maps it onto a 256 colour palette
+	pub fn from_png(path: &Path, palette: &[u8], options: PngImportOptions) -> Texture<'static> {
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+		Self::from_png_data(&data, palette, options)
+	}
+	pub fn from_png_data(
+		data: &[u8], palette: &[u8], options: PngImportOptions,
+	) -> Texture<'static> {
+		assert_eq!(palette.len(), 256 * 3, "invalid palette");
+		let mut decoder = png::Decoder::new(data);
+		decoder.set_transformations(png::Transformations::normalize_to_color8());
+		let mut reader = decoder.read_info().expect("failed to read png");
+		let mut buffer = vec![0; reader.output_buffer_size()];
+		let frame = reader
+			.next_frame(&mut buffer)
+			.expect("failed to decode png");
+		let width = u16::try_from(frame.width).expect("png too large");
+		let height = u16::try_from(frame.height).expect("png too large");
+		let buffer = &buffer[..frame.buffer_size()];
+		let rgba: Vec<[u8; 4]> = match frame.color_type {
+			png::ColorType::Rgba => buffer.as_chunks().0.to_vec(),
+			png::ColorType::Rgb => buffer
+				.as_chunks()
+				.0
+				.iter()
+				.map(|&[r, g, b]| [r, g, b, 255])
+				.collect(),
+			png::ColorType::GrayscaleAlpha => buffer
+				.as_chunks()
+				.0
+				.iter()
+				.map(|&[l, a]| [l, l, l, a])
+				.collect(),
+			png::ColorType::Grayscale => buffer.iter().map(|&l| [l, l, l, 255]).collect(),
+			png::ColorType::Indexed => unreachable!("indexed pngs are expanded"),
+		};
+		Texture::new(
+			width,
+			height,
+			quantize(&rgba, width as usize, palette, options),
+		)
+	}
+
+	/// Encodes the texture as an in-memory png, exactly as [Self::save_as] would save it
+	pub fn create_png(&self, name: &str, output: &OutputWriter, palette: Option<&[u8]>) -> Vec<u8> {
+		output.create_png(
+			name,
+			self.width as u32,
+			self.height as u32,
+			self.pixels.as_ref(),
+			palette,
+		)
 	}
 
 	pub fn save_as(&self, name: &str, output: &mut OutputWriter, palette: Option<&[u8]>) {
@@ -54,7 +174,7 @@ impl<'a> Texture<'a> {
 	}
 
 	/// Takes a sequence of animation frames with possible position offsets
-	/// and arranges them into an animated png.
+	/// and arranges them into an animated png and/or gif, depending on the export settings.
 	fn save_animated_inner(
 		frames: &[Self], name: &str, fps: u16, output: &mut OutputWriter, palette: Option<&[u8]>,
 		palette_rgba: bool,
@@ -87,6 +207,42 @@ impl<'a> Texture<'a> {
 		let width = (max_x + offset_x) as usize;
 		let height = (max_y + offset_y) as usize;
 
+		let canvases: Vec<Cow<[u8]>> = frames
+			.iter()
+			.map(|frame| {
+				if simple {
+					return Cow::Borrowed(frame.pixels.as_ref());
+				}
+				let mut buffer = vec![0; width * height];
+				let offset_x = (offset_x - (frame.position.0 as isize)) as usize;
+				for (dest, src) in buffer
+					.chunks_exact_mut(width)
+					.skip((offset_y - frame.position.1 as isize) as usize)
+					.zip(frame.pixels.chunks_exact(frame.width as usize))
+				{
+					dest[offset_x..offset_x + src.len()].copy_from_slice(src);
+				}
+				Cow::Owned(buffer)
+			})
+			.collect();
+
+		let format = output.export_settings().anim_format;
+		if format.gif() {
+			save_gif(
+				&canvases,
+				width,
+				height,
+				name,
+				fps,
+				output,
+				palette,
+				palette_rgba,
+			);
+		}
+		if !format.png() {
+			return;
+		}
+
 		let mut encoder = output.start_animated_png_inner(
 			name,
 			width as u32,
@@ -97,25 +253,328 @@ impl<'a> Texture<'a> {
 			palette_rgba,
 		);
 
-		if simple {
-			for frame in frames {
-				encoder.write_image_data(&frame.pixels).unwrap();
+		// only the first frame is stored in full, the rest just store the rectangle that changed
+		encoder.write_image_data(&canvases[0]).unwrap();
+		for pair in canvases.windows(2) {
+			// unchanged frames still need at least one pixel
+			let (x, y, w, h) = changed_rect(&pair[0], &pair[1], width).unwrap_or((0, 0, 1, 1));
+			encoder.reset_frame_position().unwrap();
+			encoder.set_frame_dimension(w as u32, h as u32).unwrap();
+			encoder.set_frame_position(x as u32, y as u32).unwrap();
+			encoder
+				.write_image_data(&crop(&pair[1], width, (x, y, w, h)))
+				.unwrap();
+		}
+		encoder.finish().expect("failed to write png file");
+	}
+}
+
+/// Maps RGBA pixels to the nearest palette colours
+#[cfg(feature = "export")]
+fn quantize(
+	pixels: &[[u8; 4]], width: usize, palette: &[u8], options: PngImportOptions,
+) -> Vec<u8> {
+	let colours: Vec<[i32; 3]> = palette
+		.as_chunks::<3>()
+		.0
+		.iter()
+		.map(|c| c.map(i32::from))
+		.collect();
+	let nearest = |[r, g, b]: [i32; 3]| -> u8 {
+		let (index, _) = colours
+			.iter()
+			.enumerate()
+			.filter(|(i, _)| options.transparent_index != Some(*i as u8))
+			.min_by_key(|(_, [pr, pg, pb])| (r - pr).pow(2) + (g - pg).pow(2) + (b - pb).pow(2))
+			.unwrap();
+		index as u8
+	};
+
+	// accumulated dithering error of the current and next rows
+	let mut errors = vec![[0; 3]; width * 2 + 2];
+	let mut result = Vec::with_capacity(pixels.len());
+	for row in pixels.chunks(width) {
+		let (current, next) = errors.split_at_mut(width + 1);
+		for (x, &[r, g, b, a]) in row.iter().enumerate() {
+			if let Some(index) = options.transparent_index
+				&& a < 128
+			{
+				result.push(index);
+				continue;
 			}
-		} else {
-			let mut buffer = vec![0; width * height];
-			for frame in frames {
-				buffer.fill(0);
-				let offset_x = (offset_x - (frame.position.0 as isize)) as usize;
-				for (dest, src) in buffer
-					.chunks_exact_mut(width)
-					.skip((offset_y - frame.position.1 as isize) as usize)
-					.zip(frame.pixels.chunks_exact(frame.width as usize))
-				{
-					dest[offset_x..offset_x + src.len()].copy_from_slice(src);
+			let target = match options.dither {
+				Dither::None => [r, g, b].map(i32::from),
+				Dither::FloydSteinberg => {
+					let [er, eg, eb] = current[x].map(|e| e / 16);
+					[r as i32 + er, g as i32 + eg, b as i32 + eb].map(|c| c.clamp(0, 255))
+				}
+			};
+			let index = nearest(target);
+			result.push(index);
+
+			if options.dither == Dither::FloydSteinberg {
+				let colour = colours[index as usize];
+				let error = [0, 1, 2].map(|i| target[i] - colour[i]);
+				let spread = |errors: &mut [[i32; 3]], x: usize, weight: i32| {
+					if let Some(e) = errors.get_mut(x) {
+						for i in 0..3 {
+							e[i] += error[i] * weight;
+						}
+					}
+				};
+				spread(current, x + 1, 7);
+				if x > 0 {
+					spread(next, x - 1, 3);
 				}
-				encoder.write_image_data(&buffer).unwrap();
+				spread(next, x, 5);
+				spread(next, x + 1, 1);
 			}
 		}
-		encoder.finish().expect("failed to write png file");
+		// move on to the next row
+		errors.copy_within(width + 1.., 0);
+		errors[width + 1..].fill([0; 3]);
+	}
+	result
+}
+
+/// Saves composited animation frames as a looping gif with the exact palette.
+///
+/// Like the animated png, frames after the first only store the rectangle that changed,
+/// unless pixels turn transparent (which gifs can't draw over the previous frame),
+/// in which case every frame is stored in full and cleared before the next one.
+#[cfg(feature = "export")]
+#[allow(clippy::too_many_arguments)]
+fn save_gif(
+	canvases: &[Cow<[u8]>], width: usize, height: usize, name: &str, fps: u16,
+	output: &mut OutputWriter, palette: Option<&[u8]>, palette_rgba: bool,
+) {
+	use crate::gif::{Dispose, GifFrame, write_gif};
+
+	let grey: Vec<u8>;
+	let (rgb, transparent) = match palette {
+		None => {
+			grey = (0..=255).flat_map(|i| [i, i, i]).collect();
+			(grey.as_slice(), None)
+		}
+		Some(palette) if palette_rgba => {
+			// rgbrgbrgb...aaa, and gifs only have one transparent colour
+			let (rgb, alpha) = palette.split_at(palette.len() / 4 * 3);
+			(rgb, alpha.iter().position(|&a| a < 128).map(|i| i as u8))
+		}
+		Some(palette) => (palette, Some(0)),
+	};
+
+	let clears = transparent.is_some_and(|transparent| {
+		canvases.windows(2).any(|pair| {
+			pair[0]
+				.iter()
+				.zip(pair[1].iter())
+				.any(|(&a, &b)| a != transparent && b == transparent)
+		})
+	});
+	// spread the rounding of each delay so the whole animation keeps time
+	let centis = |frame: usize| ((frame * 200 + fps as usize) / (fps as usize * 2)) as u16;
+	let full = (0, 0, width, height);
+	let rects: Vec<_> = (0..canvases.len())
+		.map(|i| {
+			if i == 0 || clears {
+				full
+			} else {
+				changed_rect(&canvases[i - 1], &canvases[i], width).unwrap_or((0, 0, 1, 1))
+			}
+		})
+		.collect();
+	let pixels: Vec<Cow<[u8]>> = canvases
+		.iter()
+		.zip(&rects)
+		.map(|(canvas, &rect)| {
+			if rect == full {
+				Cow::Borrowed(canvas.as_ref())
+			} else {
+				Cow::Owned(crop(canvas, width, rect))
+			}
+		})
+		.collect();
+	let gif_frames: Vec<GifFrame> = pixels
+		.iter()
+		.zip(&rects)
+		.enumerate()
+		.map(|(i, (pixels, &(x, y, w, h)))| GifFrame {
+			x: x as u16,
+			y: y as u16,
+			width: w as u16,
+			height: h as u16,
+			pixels,
+			delay: centis(i + 1) - centis(i),
+			dispose: if clears {
+				Dispose::Clear
+			} else {
+				Dispose::Keep
+			},
+		})
+		.collect();
+	let data = write_gif(width as u16, height as u16, rgb, transparent, &gif_frames);
+	output.write(name, "gif", data);
+}
+
+/// Copies the rectangle `(x, y, width, height)` out of an image `stride` pixels wide
+#[cfg(feature = "export")]
+fn crop(pixels: &[u8], stride: usize, (x, y, w, h): (usize, usize, usize, usize)) -> Vec<u8> {
+	pixels
+		.chunks_exact(stride)
+		.skip(y)
+		.take(h)
+		.flat_map(|row| &row[x..x + w])
+		.copied()
+		.collect()
+}
+
+#[cfg(feature = "export")]
+/// Finds the bounding rectangle `(x, y, width, height)` of all the pixels that differ between two frames.
+fn changed_rect(prev: &[u8], next: &[u8], width: usize) -> Option<(usize, usize, usize, usize)> {
+	debug_assert_eq!(prev.len(), next.len());
+	let mut min_x = usize::MAX;
+	let mut min_y = usize::MAX;
+	let mut max_x = 0;
+	let mut max_y = 0;
+	for (y, (row1, row2)) in prev
+		.chunks_exact(width)
+		.zip(next.chunks_exact(width))
+		.enumerate()
+	{
+		let Some(first) = row1.iter().zip(row2).position(|(a, b)| a != b) else {
+			continue;
+		};
+		let last = row1.iter().zip(row2).rposition(|(a, b)| a != b).unwrap();
+		min_x = min_x.min(first);
+		max_x = max_x.max(last);
+		min_y = min_y.min(y);
+		max_y = y;
+	}
+	(min_y != usize::MAX).then(|| (min_x, min_y, max_x + 1 - min_x, max_y + 1 - min_y))
+}
+
+#[cfg(all(test, feature = "export"))]
+mod tests {
+	use super::*;
+	use crate::{ExportSettings, PngColour};
+
+	fn rgba_png(width: u32, height: u32, pixels: &[[u8; 4]]) -> Vec<u8> {
+		let mut data = Vec::new();
+		let mut encoder = png::Encoder::new(&mut data, width, height);
+		encoder.set_color(png::ColorType::Rgba);
+		encoder.set_depth(png::BitDepth::Eight);
+		let mut writer = encoder.write_header().unwrap();
+		writer.write_image_data(pixels.as_flattened()).unwrap();
+		writer.finish().unwrap();
+		data
+	}
+
+	#[test]
+	fn test_from_png() {
+		// black, white and grey, with everything else a garish pink
+		let mut palette = [255, 0, 255].repeat(256);
+		palette[..9].copy_from_slice(&[0, 0, 0, 255, 255, 255, 128, 128, 128]);
+
+		let data = rgba_png(
+			4,
+			1,
+			&[
+				[250, 250, 250, 255],
+				[0, 0, 0, 0],
+				[120, 130, 125, 255],
+				[5, 0, 0, 255],
+			],
+		);
+		let texture = Texture::from_png_data(&data, &palette, PngImportOptions::default());
+		assert_eq!((texture.width, texture.height), (4, 1));
+		assert_eq!(&texture.pixels[..], [1, 0, 2, 0]);
+
+		// opaque black can't use the transparent index
+		let options = PngImportOptions {
+			transparent_index: Some(0),
+			..Default::default()
+		};
+		let texture = Texture::from_png_data(&data, &palette, options);
+		assert_eq!(&texture.pixels[..], [1, 0, 2, 2]);
+
+		// a flat 25% grey dithers into a mix of black and grey
+		let data = rgba_png(8, 8, &[[64, 64, 64, 255]; 64]);
+		let options = PngImportOptions {
+			dither: Dither::FloydSteinberg,
+			..Default::default()
+		};
+		let texture = Texture::from_png_data(&data, &palette, options);
+		let greys = texture.pixels.iter().filter(|&&p| p == 2).count();
+		assert!(texture.pixels.iter().all(|&p| p == 0 || p == 2));
+		assert!((24..=40).contains(&greys), "{greys} grey pixels");
+	}
+
+	#[test]
+	fn test_create_png() {
+		let mut palette = [255, 0, 255].repeat(256);
+		palette[..9].copy_from_slice(&[0, 0, 0, 255, 255, 255, 128, 128, 128]);
+		let texture = Texture::new(2, 2, vec![0, 1, 2, 1]);
+
+		let dir = std::path::Path::new("output/test_create_png");
+		let _ = std::fs::remove_dir_all(dir);
+		let mut output = OutputWriter::new_in_dir(dir);
+		output.set_source_file("LEVEL3.MTI");
+		let output = output.with_palette_name("ARENA1");
+		let data = texture.create_png("WALL", &output, Some(&palette));
+		let options = PngImportOptions {
+			transparent_index: Some(0),
+			..Default::default()
+		};
+		let decoded = Texture::from_png_data(&data, &palette, options);
+		assert_eq!((decoded.width, decoded.height), (2, 2));
+		assert_eq!(decoded.pixels, texture.pixels);
+
+		// the same as saving it, provenance included
+		texture.save_as("WALL", &mut output.clone(), Some(&palette));
+		output.session().finish_writes();
+		assert_eq!(std::fs::read(dir.join("WALL.png")).unwrap(), data);
+		std::fs::remove_dir_all(dir).unwrap();
+		let reader = png::Decoder::new(&data[..]).read_info().unwrap();
+		let text: Vec<(&str, &str)> = reader
+			.info()
+			.uncompressed_latin1_text
+			.iter()
+			.map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+			.collect();
+		assert_eq!(text[0], ("Title", "WALL"));
+		assert_eq!(text[2..], [("Source", "LEVEL3.MTI"), ("Palette", "ARENA1")]);
+
+		let settings = ExportSettings {
+			png_colour: PngColour::Rgba,
+			..Default::default()
+		};
+		let data = texture.create_png(
+			"WALL",
+			&output.with_export_settings(settings),
+			Some(&palette),
+		);
+		let reader = png::Decoder::new(&data[..]).read_info().unwrap();
+		assert_eq!(reader.info().color_type, png::ColorType::Rgba);
+	}
+
+	#[test]
+	fn test_changed_rect() {
+		let frame: Vec<u8> = (0..12).collect();
+		assert_eq!(changed_rect(&frame, &frame, 4), None);
+
+		let mut single = frame.clone();
+		single[6] = 0xFF;
+		assert_eq!(changed_rect(&frame, &single, 4), Some((2, 1, 1, 1)));
+
+		let mut corner = frame.clone();
+		corner[11] = 0xFF;
+		assert_eq!(changed_rect(&frame, &corner, 4), Some((3, 2, 1, 1)));
+
+		// spans from the first change to the last, in both directions
+		corner[4] = 0xFF;
+		assert_eq!(changed_rect(&frame, &corner, 4), Some((0, 1, 4, 2)));
+		corner[2] = 0xFF;
+		assert_eq!(changed_rect(&frame, &corner, 4), Some((0, 0, 4, 3)));
 	}
 }
@@ -1,8 +1,14 @@
-use crate::{OutputWriter, Reader};
+use std::io;
+
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+use crate::{Endian, Reader};
 
 /// Simple WAV file container.
 pub struct Wav<'a> {
 	pub file_data: &'a [u8],
+	/// Contents of the data chunk
+	pub samples: &'a [u8],
 	pub flags: u32, // flags from SNI and MTO
 
 	pub num_channels: u16,
@@ -14,6 +20,7 @@ pub struct Wav<'a> {
 impl<'a> Wav<'a> {
 	pub fn try_parse(base_reader: &mut Reader<'a>) -> Option<Wav<'a>> {
 		let mut reader = base_reader.rebased();
+		reader.set_endian(Endian::Little); // riff headers are always little endian
 
 		if reader.try_slice(4) != Some(b"RIFF") {
 			return None;
@@ -43,7 +50,7 @@ impl<'a> Wav<'a> {
 		let _bytes_per_sec = reader.u32();
 		let bytes_per_sample = reader.u16() as usize; // for all channels
 		let bits_per_sample = reader.u16(); // for invidial channel sample
-		if bits_per_sample % 8 != 0 {
+		if !bits_per_sample.is_multiple_of(8) {
 			return None;
 		}
 		reader.skip(header_size - 16); // skip extra header data
@@ -57,6 +64,9 @@ impl<'a> Wav<'a> {
 			}
 		};
 
+		if bytes_per_sample == 0 {
+			return None;
+		}
 		let num_samples = samples.len() / bytes_per_sample;
 		let duration_secs = num_samples as f32 / samples_per_second as f32;
 
@@ -65,6 +75,7 @@ impl<'a> Wav<'a> {
 
 		Some(Wav {
 			file_data,
+			samples,
 			flags: 0,
 			num_channels,
 			samples_per_second,
@@ -77,8 +88,62 @@ impl<'a> Wav<'a> {
 		Self::try_parse(reader).expect("failed to parse wav file")
 	}
 
+	#[cfg(feature = "export")]
+	/// Saves the wav file, unless an identical sound is already shared (see [crate::SoundIndex])
 	pub fn save_as(&self, name: &str, output: &mut OutputWriter) {
-		output.write(name, "wav", self.file_data)
+		crate::sound_index::save_sound(self, name, output);
+	}
+
+	/// Size of one sample across all channels
+	pub fn block_align(&self) -> usize {
+		self.num_channels as usize * self.bits_per_sample as usize / 8
+	}
+
+	/// Writes the samples of consecutive chunks of one sound as a single wav file,
+	/// without buffering the combined samples.
+	///
+	/// Fails with [io::ErrorKind::InvalidData] if there are no chunks,
+	/// or they don't share the same format or split a sample.
+	pub fn write_stitched(chunks: &[&Wav], mut writer: impl io::Write) -> io::Result<()> {
+		let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+		let Some(first) = chunks.first() else {
+			return Err(invalid("no chunks to stitch".into()));
+		};
+		let mut data_size = 0;
+		for (index, chunk) in chunks.iter().enumerate() {
+			if chunk.num_channels != first.num_channels
+				|| chunk.samples_per_second != first.samples_per_second
+				|| chunk.bits_per_sample != first.bits_per_sample
+			{
+				return Err(invalid(format!("chunk {index} has a different format")));
+			}
+			if !chunk.samples.len().is_multiple_of(first.block_align()) {
+				return Err(invalid(format!(
+					"chunk {index} ends partway through a sample"
+				)));
+			}
+			data_size += chunk.samples.len();
+		}
+		let data_size =
+			u32::try_from(data_size).map_err(|_| invalid("stitched sound is too large".into()))?;
+		let block_align = first.block_align() as u16;
+
+		writer.write_all(b"RIFF")?;
+		writer.write_all(&(36 + data_size).to_le_bytes())?;
+		writer.write_all(b"WAVEfmt ")?;
+		writer.write_all(&16u32.to_le_bytes())?;
+		writer.write_all(&1u16.to_le_bytes())?; // pcm
+		writer.write_all(&first.num_channels.to_le_bytes())?;
+		writer.write_all(&first.samples_per_second.to_le_bytes())?;
+		writer.write_all(&(first.samples_per_second * block_align as u32).to_le_bytes())?;
+		writer.write_all(&block_align.to_le_bytes())?;
+		writer.write_all(&first.bits_per_sample.to_le_bytes())?;
+		writer.write_all(b"data")?;
+		writer.write_all(&data_size.to_le_bytes())?;
+		for chunk in chunks {
+			writer.write_all(chunk.samples)?;
+		}
+		Ok(())
 	}
 
 	pub fn create_report_tsv(sounds: &[(&str, Self)]) -> String {
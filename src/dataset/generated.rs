@@ -0,0 +1,5705 @@
+pub use root::*;
+
+const _: () = ::planus::check_version_compatibility("planus-1.3.0");
+
+/// The root namespace
+///
+/// Generated from these locations:
+/// * File `schema/level.fbs`
+#[no_implicit_prelude]
+#[allow(clippy::needless_lifetimes)]
+mod root {
+    /// The namespace `mdk`
+    ///
+    /// Generated from these locations:
+    /// * File `schema/level.fbs`
+    pub mod mdk {
+        /// The namespace `mdk.dataset`
+        ///
+        /// Generated from these locations:
+        /// * File `schema/level.fbs`
+        pub mod dataset {
+            /// The struct `Vec3` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Struct `Vec3` in the file `schema/level.fbs:13`
+            #[derive(
+                Copy,
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Default,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Vec3 {
+                /// The field `x` in the struct `Vec3`
+                pub x: f32,
+
+                /// The field `y` in the struct `Vec3`
+                pub y: f32,
+
+                /// The field `z` in the struct `Vec3`
+                pub z: f32,
+            }
+
+            /// # Safety
+            /// The Planus compiler correctly calculates `ALIGNMENT` and `SIZE`.
+            unsafe impl ::planus::Primitive for Vec3 {
+                const ALIGNMENT: usize = 4;
+                const SIZE: usize = 12;
+            }
+
+            #[allow(clippy::identity_op)]
+            impl ::planus::WriteAsPrimitive<Vec3> for Vec3 {
+                #[inline]
+                fn write<const N: usize>(
+                    &self,
+                    cursor: ::planus::Cursor<'_, N>,
+                    buffer_position: u32,
+                ) {
+                    let (cur, cursor) = cursor.split::<4, 8>();
+                    self.x.write(cur, buffer_position - 0);
+                    let (cur, cursor) = cursor.split::<4, 4>();
+                    self.y.write(cur, buffer_position - 4);
+                    let (cur, cursor) = cursor.split::<4, 0>();
+                    self.z.write(cur, buffer_position - 8);
+                    cursor.finish([]);
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Vec3> for Vec3 {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Vec3> {
+                    unsafe {
+                        builder.write_with(12, 3, |buffer_position, bytes| {
+                            let bytes = bytes.as_mut_ptr();
+
+                            ::planus::WriteAsPrimitive::write(
+                                self,
+                                ::planus::Cursor::new(
+                                    &mut *(bytes as *mut [::core::mem::MaybeUninit<u8>; 12]),
+                                ),
+                                buffer_position,
+                            );
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<Vec3> for Vec3 {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self {
+                    *self
+                }
+            }
+
+            impl ::planus::WriteAsOptional<Vec3> for Vec3 {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<Self> {
+                    ::core::option::Option::Some(*self)
+                }
+            }
+
+            /// Reference to a deserialized [Vec3].
+            #[derive(Copy, Clone)]
+            pub struct Vec3Ref<'a>(::planus::ArrayWithStartOffset<'a, 12>);
+
+            impl<'a> Vec3Ref<'a> {
+                /// Getter for the [`x` field](Vec3#structfield.x).
+                pub fn x(&self) -> f32 {
+                    let buffer = self.0.advance_as_array::<4>(0).unwrap();
+
+                    f32::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`y` field](Vec3#structfield.y).
+                pub fn y(&self) -> f32 {
+                    let buffer = self.0.advance_as_array::<4>(4).unwrap();
+
+                    f32::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`z` field](Vec3#structfield.z).
+                pub fn z(&self) -> f32 {
+                    let buffer = self.0.advance_as_array::<4>(8).unwrap();
+
+                    f32::from_le_bytes(*buffer.as_array())
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for Vec3Ref<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("Vec3Ref");
+                    f.field("x", &self.x());
+                    f.field("y", &self.y());
+                    f.field("z", &self.z());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::From<::planus::ArrayWithStartOffset<'a, 12>> for Vec3Ref<'a> {
+                fn from(array: ::planus::ArrayWithStartOffset<'a, 12>) -> Self {
+                    Self(array)
+                }
+            }
+
+            impl<'a> ::core::convert::From<Vec3Ref<'a>> for Vec3 {
+                #[allow(unreachable_code)]
+                fn from(value: Vec3Ref<'a>) -> Self {
+                    Self {
+                        x: value.x(),
+                        y: value.y(),
+                        z: value.z(),
+                    }
+                }
+            }
+
+            impl<'a, 'b> ::core::cmp::PartialEq<Vec3Ref<'a>> for Vec3Ref<'b> {
+                fn eq(&self, other: &Vec3Ref<'_>) -> bool {
+                    self.x() == other.x() && self.y() == other.y() && self.z() == other.z()
+                }
+            }
+
+            impl<'a, 'b> ::core::cmp::PartialOrd<Vec3Ref<'a>> for Vec3Ref<'b> {
+                fn partial_cmp(
+                    &self,
+                    other: &Vec3Ref<'_>,
+                ) -> ::core::option::Option<::core::cmp::Ordering> {
+                    match self.x().partial_cmp(&other.x()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.y().partial_cmp(&other.y()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    self.z().partial_cmp(&other.z())
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for Vec3Ref<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    let buffer = buffer.advance_as_array::<12>(offset)?;
+                    ::core::result::Result::Ok(Self(buffer))
+                }
+            }
+
+            impl<'a> ::planus::VectorRead<'a> for Vec3Ref<'a> {
+                const STRIDE: usize = 12;
+
+                #[inline]
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> Self {
+                    Self(unsafe { buffer.unchecked_advance_as_array(offset) })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<Vec3> for Vec3 {
+                const STRIDE: usize = 12;
+
+                type Value = Vec3;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self::Value {
+                    *self
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[Vec3],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 12];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (12 * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            /// The struct `Vec2` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Struct `Vec2` in the file `schema/level.fbs:19`
+            #[derive(
+                Copy,
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Default,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Vec2 {
+                /// The field `x` in the struct `Vec2`
+                pub x: f32,
+
+                /// The field `y` in the struct `Vec2`
+                pub y: f32,
+            }
+
+            /// # Safety
+            /// The Planus compiler correctly calculates `ALIGNMENT` and `SIZE`.
+            unsafe impl ::planus::Primitive for Vec2 {
+                const ALIGNMENT: usize = 4;
+                const SIZE: usize = 8;
+            }
+
+            #[allow(clippy::identity_op)]
+            impl ::planus::WriteAsPrimitive<Vec2> for Vec2 {
+                #[inline]
+                fn write<const N: usize>(
+                    &self,
+                    cursor: ::planus::Cursor<'_, N>,
+                    buffer_position: u32,
+                ) {
+                    let (cur, cursor) = cursor.split::<4, 4>();
+                    self.x.write(cur, buffer_position - 0);
+                    let (cur, cursor) = cursor.split::<4, 0>();
+                    self.y.write(cur, buffer_position - 4);
+                    cursor.finish([]);
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Vec2> for Vec2 {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Vec2> {
+                    unsafe {
+                        builder.write_with(8, 3, |buffer_position, bytes| {
+                            let bytes = bytes.as_mut_ptr();
+
+                            ::planus::WriteAsPrimitive::write(
+                                self,
+                                ::planus::Cursor::new(
+                                    &mut *(bytes as *mut [::core::mem::MaybeUninit<u8>; 8]),
+                                ),
+                                buffer_position,
+                            );
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<Vec2> for Vec2 {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self {
+                    *self
+                }
+            }
+
+            impl ::planus::WriteAsOptional<Vec2> for Vec2 {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<Self> {
+                    ::core::option::Option::Some(*self)
+                }
+            }
+
+            /// Reference to a deserialized [Vec2].
+            #[derive(Copy, Clone)]
+            pub struct Vec2Ref<'a>(::planus::ArrayWithStartOffset<'a, 8>);
+
+            impl<'a> Vec2Ref<'a> {
+                /// Getter for the [`x` field](Vec2#structfield.x).
+                pub fn x(&self) -> f32 {
+                    let buffer = self.0.advance_as_array::<4>(0).unwrap();
+
+                    f32::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`y` field](Vec2#structfield.y).
+                pub fn y(&self) -> f32 {
+                    let buffer = self.0.advance_as_array::<4>(4).unwrap();
+
+                    f32::from_le_bytes(*buffer.as_array())
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for Vec2Ref<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("Vec2Ref");
+                    f.field("x", &self.x());
+                    f.field("y", &self.y());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::From<::planus::ArrayWithStartOffset<'a, 8>> for Vec2Ref<'a> {
+                fn from(array: ::planus::ArrayWithStartOffset<'a, 8>) -> Self {
+                    Self(array)
+                }
+            }
+
+            impl<'a> ::core::convert::From<Vec2Ref<'a>> for Vec2 {
+                #[allow(unreachable_code)]
+                fn from(value: Vec2Ref<'a>) -> Self {
+                    Self {
+                        x: value.x(),
+                        y: value.y(),
+                    }
+                }
+            }
+
+            impl<'a, 'b> ::core::cmp::PartialEq<Vec2Ref<'a>> for Vec2Ref<'b> {
+                fn eq(&self, other: &Vec2Ref<'_>) -> bool {
+                    self.x() == other.x() && self.y() == other.y()
+                }
+            }
+
+            impl<'a, 'b> ::core::cmp::PartialOrd<Vec2Ref<'a>> for Vec2Ref<'b> {
+                fn partial_cmp(
+                    &self,
+                    other: &Vec2Ref<'_>,
+                ) -> ::core::option::Option<::core::cmp::Ordering> {
+                    match self.x().partial_cmp(&other.x()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    self.y().partial_cmp(&other.y())
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for Vec2Ref<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    let buffer = buffer.advance_as_array::<8>(offset)?;
+                    ::core::result::Result::Ok(Self(buffer))
+                }
+            }
+
+            impl<'a> ::planus::VectorRead<'a> for Vec2Ref<'a> {
+                const STRIDE: usize = 8;
+
+                #[inline]
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> Self {
+                    Self(unsafe { buffer.unchecked_advance_as_array(offset) })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<Vec2> for Vec2 {
+                const STRIDE: usize = 8;
+
+                type Value = Vec2;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self::Value {
+                    *self
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[Vec2],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 8];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (8 * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            /// The struct `Triangle` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Struct `Triangle` in the file `schema/level.fbs:24`
+            #[derive(
+                Copy,
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Default,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Triangle {
+                /// The field `a` in the struct `Triangle`
+                pub a: u16,
+
+                /// The field `b` in the struct `Triangle`
+                pub b: u16,
+
+                /// The field `c` in the struct `Triangle`
+                pub c: u16,
+
+                /// The field `material` in the struct `Triangle`
+                pub material: i32,
+
+                /// The field `uv_a` in the struct `Triangle`
+                pub uv_a: self::Vec2,
+
+                /// The field `uv_b` in the struct `Triangle`
+                pub uv_b: self::Vec2,
+
+                /// The field `uv_c` in the struct `Triangle`
+                pub uv_c: self::Vec2,
+
+                /// The field `flags` in the struct `Triangle`
+                pub flags: u32,
+            }
+
+            /// # Safety
+            /// The Planus compiler correctly calculates `ALIGNMENT` and `SIZE`.
+            unsafe impl ::planus::Primitive for Triangle {
+                const ALIGNMENT: usize = 4;
+                const SIZE: usize = 40;
+            }
+
+            #[allow(clippy::identity_op)]
+            impl ::planus::WriteAsPrimitive<Triangle> for Triangle {
+                #[inline]
+                fn write<const N: usize>(
+                    &self,
+                    cursor: ::planus::Cursor<'_, N>,
+                    buffer_position: u32,
+                ) {
+                    let (cur, cursor) = cursor.split::<2, 38>();
+                    self.a.write(cur, buffer_position - 0);
+                    let (cur, cursor) = cursor.split::<2, 36>();
+                    self.b.write(cur, buffer_position - 2);
+                    let (cur, cursor) = cursor.split::<2, 34>();
+                    self.c.write(cur, buffer_position - 4);
+                    let cursor = cursor.write::<2, 32>([0; 2]);
+                    let (cur, cursor) = cursor.split::<4, 28>();
+                    self.material.write(cur, buffer_position - 8);
+                    let (cur, cursor) = cursor.split::<8, 20>();
+                    self.uv_a.write(cur, buffer_position - 12);
+                    let (cur, cursor) = cursor.split::<8, 12>();
+                    self.uv_b.write(cur, buffer_position - 20);
+                    let (cur, cursor) = cursor.split::<8, 4>();
+                    self.uv_c.write(cur, buffer_position - 28);
+                    let (cur, cursor) = cursor.split::<4, 0>();
+                    self.flags.write(cur, buffer_position - 36);
+                    cursor.finish([]);
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Triangle> for Triangle {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Triangle> {
+                    unsafe {
+                        builder.write_with(40, 3, |buffer_position, bytes| {
+                            let bytes = bytes.as_mut_ptr();
+
+                            ::planus::WriteAsPrimitive::write(
+                                self,
+                                ::planus::Cursor::new(
+                                    &mut *(bytes as *mut [::core::mem::MaybeUninit<u8>; 40]),
+                                ),
+                                buffer_position,
+                            );
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<Triangle> for Triangle {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self {
+                    *self
+                }
+            }
+
+            impl ::planus::WriteAsOptional<Triangle> for Triangle {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<Self> {
+                    ::core::option::Option::Some(*self)
+                }
+            }
+
+            /// Reference to a deserialized [Triangle].
+            #[derive(Copy, Clone)]
+            pub struct TriangleRef<'a>(::planus::ArrayWithStartOffset<'a, 40>);
+
+            impl<'a> TriangleRef<'a> {
+                /// Getter for the [`a` field](Triangle#structfield.a).
+                pub fn a(&self) -> u16 {
+                    let buffer = self.0.advance_as_array::<2>(0).unwrap();
+
+                    u16::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`b` field](Triangle#structfield.b).
+                pub fn b(&self) -> u16 {
+                    let buffer = self.0.advance_as_array::<2>(2).unwrap();
+
+                    u16::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`c` field](Triangle#structfield.c).
+                pub fn c(&self) -> u16 {
+                    let buffer = self.0.advance_as_array::<2>(4).unwrap();
+
+                    u16::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`material` field](Triangle#structfield.material).
+                pub fn material(&self) -> i32 {
+                    let buffer = self.0.advance_as_array::<4>(8).unwrap();
+
+                    i32::from_le_bytes(*buffer.as_array())
+                }
+
+                /// Getter for the [`uv_a` field](Triangle#structfield.uv_a).
+                pub fn uv_a(&self) -> self::Vec2Ref<'a> {
+                    let buffer = self.0.advance_as_array::<8>(12).unwrap();
+
+                    ::core::convert::From::from(buffer)
+                }
+
+                /// Getter for the [`uv_b` field](Triangle#structfield.uv_b).
+                pub fn uv_b(&self) -> self::Vec2Ref<'a> {
+                    let buffer = self.0.advance_as_array::<8>(20).unwrap();
+
+                    ::core::convert::From::from(buffer)
+                }
+
+                /// Getter for the [`uv_c` field](Triangle#structfield.uv_c).
+                pub fn uv_c(&self) -> self::Vec2Ref<'a> {
+                    let buffer = self.0.advance_as_array::<8>(28).unwrap();
+
+                    ::core::convert::From::from(buffer)
+                }
+
+                /// Getter for the [`flags` field](Triangle#structfield.flags).
+                pub fn flags(&self) -> u32 {
+                    let buffer = self.0.advance_as_array::<4>(36).unwrap();
+
+                    u32::from_le_bytes(*buffer.as_array())
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for TriangleRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("TriangleRef");
+                    f.field("a", &self.a());
+                    f.field("b", &self.b());
+                    f.field("c", &self.c());
+                    f.field("material", &self.material());
+                    f.field("uv_a", &self.uv_a());
+                    f.field("uv_b", &self.uv_b());
+                    f.field("uv_c", &self.uv_c());
+                    f.field("flags", &self.flags());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::From<::planus::ArrayWithStartOffset<'a, 40>> for TriangleRef<'a> {
+                fn from(array: ::planus::ArrayWithStartOffset<'a, 40>) -> Self {
+                    Self(array)
+                }
+            }
+
+            impl<'a> ::core::convert::From<TriangleRef<'a>> for Triangle {
+                #[allow(unreachable_code)]
+                fn from(value: TriangleRef<'a>) -> Self {
+                    Self {
+                        a: value.a(),
+                        b: value.b(),
+                        c: value.c(),
+                        material: value.material(),
+                        uv_a: ::core::convert::From::from(value.uv_a()),
+                        uv_b: ::core::convert::From::from(value.uv_b()),
+                        uv_c: ::core::convert::From::from(value.uv_c()),
+                        flags: value.flags(),
+                    }
+                }
+            }
+
+            impl<'a, 'b> ::core::cmp::PartialEq<TriangleRef<'a>> for TriangleRef<'b> {
+                fn eq(&self, other: &TriangleRef<'_>) -> bool {
+                    self.a() == other.a()
+                        && self.b() == other.b()
+                        && self.c() == other.c()
+                        && self.material() == other.material()
+                        && self.uv_a() == other.uv_a()
+                        && self.uv_b() == other.uv_b()
+                        && self.uv_c() == other.uv_c()
+                        && self.flags() == other.flags()
+                }
+            }
+
+            impl<'a, 'b> ::core::cmp::PartialOrd<TriangleRef<'a>> for TriangleRef<'b> {
+                fn partial_cmp(
+                    &self,
+                    other: &TriangleRef<'_>,
+                ) -> ::core::option::Option<::core::cmp::Ordering> {
+                    match self.a().partial_cmp(&other.a()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.b().partial_cmp(&other.b()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.c().partial_cmp(&other.c()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.material().partial_cmp(&other.material()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.uv_a().partial_cmp(&other.uv_a()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.uv_b().partial_cmp(&other.uv_b()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    match self.uv_c().partial_cmp(&other.uv_c()) {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => (),
+                        o => return o,
+                    }
+
+                    self.flags().partial_cmp(&other.flags())
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for TriangleRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    let buffer = buffer.advance_as_array::<40>(offset)?;
+                    ::core::result::Result::Ok(Self(buffer))
+                }
+            }
+
+            impl<'a> ::planus::VectorRead<'a> for TriangleRef<'a> {
+                const STRIDE: usize = 40;
+
+                #[inline]
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> Self {
+                    Self(unsafe { buffer.unchecked_advance_as_array(offset) })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<Triangle> for Triangle {
+                const STRIDE: usize = 40;
+
+                type Value = Triangle;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self::Value {
+                    *self
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[Triangle],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 40];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (40 * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            /// The table `Palette` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Palette` in the file `schema/level.fbs:37`
+            #[derive(
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Palette {
+                /// The field `arena` in the table `Palette`
+                pub arena: ::planus::alloc::string::String,
+                /// The field `colours` in the table `Palette`
+                pub colours: ::planus::alloc::vec::Vec<u8>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Palette {
+                fn default() -> Self {
+                    Self {
+                        arena: ::core::default::Default::default(),
+                        colours: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Palette {
+                /// Creates a [PaletteBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> PaletteBuilder<()> {
+                    PaletteBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_arena: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_colours: impl ::planus::WriteAs<::planus::Offset<[u8]>>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_arena = field_arena.prepare(builder);
+                    let prepared_colours = field_colours.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<8> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    table_writer.write_entry::<::planus::Offset<[u8]>>(1);
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_arena);
+                            object_writer.write::<_, _, 4>(&prepared_colours);
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Palette>> for Palette {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Palette> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Palette>> for Palette {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Palette>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Palette> for Palette {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Palette> {
+                    Palette::create(builder, &self.arena, &self.colours)
+                }
+            }
+
+            /// Builder for serializing an instance of the [Palette] type.
+            ///
+            /// Can be created using the [Palette::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct PaletteBuilder<State>(State);
+
+            impl PaletteBuilder<()> {
+                /// Setter for the [`arena` field](Palette#structfield.arena).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn arena<T0>(self, value: T0) -> PaletteBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    PaletteBuilder((value,))
+                }
+            }
+
+            impl<T0> PaletteBuilder<(T0,)> {
+                /// Setter for the [`colours` field](Palette#structfield.colours).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn colours<T1>(self, value: T1) -> PaletteBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                {
+                    let (v0,) = self.0;
+                    PaletteBuilder((v0, value))
+                }
+            }
+
+            impl<T0, T1> PaletteBuilder<(T0, T1)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Palette].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Palette>
+                where
+                    Self: ::planus::WriteAsOffset<Palette>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                > ::planus::WriteAs<::planus::Offset<Palette>> for PaletteBuilder<(T0, T1)>
+            {
+                type Prepared = ::planus::Offset<Palette>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Palette> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Palette>>
+                for PaletteBuilder<(T0, T1)>
+            {
+                type Prepared = ::planus::Offset<Palette>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Palette>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                > ::planus::WriteAsOffset<Palette> for PaletteBuilder<(T0, T1)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Palette> {
+                    let (v0, v1) = &self.0;
+                    Palette::create(builder, v0, v1)
+                }
+            }
+
+            /// Reference to a deserialized [Palette].
+            #[derive(Copy, Clone)]
+            pub struct PaletteRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> PaletteRef<'a> {
+                /// Getter for the [`arena` field](Palette#structfield.arena).
+                #[inline]
+                pub fn arena(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "Palette", "arena")
+                }
+
+                /// Getter for the [`colours` field](Palette#structfield.colours).
+                #[inline]
+                pub fn colours(&self) -> ::planus::Result<&'a [u8]> {
+                    self.0.access_required(1, "Palette", "colours")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for PaletteRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("PaletteRef");
+                    f.field("arena", &self.arena());
+                    f.field("colours", &self.colours());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<PaletteRef<'a>> for Palette {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: PaletteRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        arena: ::core::convert::Into::into(value.arena()?),
+                        colours: value.colours()?.to_vec(),
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for PaletteRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for PaletteRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[PaletteRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Palette>> for Palette {
+                type Value = ::planus::Offset<Palette>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Palette>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for PaletteRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[PaletteRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Frame` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Frame` in the file `schema/level.fbs:43`
+            #[derive(
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Frame {
+                /// The field `width` in the table `Frame`
+                pub width: u16,
+                /// The field `height` in the table `Frame`
+                pub height: u16,
+                /// The field `x` in the table `Frame`
+                pub x: i16,
+                /// The field `y` in the table `Frame`
+                pub y: i16,
+                /// The field `pixels` in the table `Frame`
+                pub pixels: ::planus::alloc::vec::Vec<u8>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Frame {
+                fn default() -> Self {
+                    Self {
+                        width: 0,
+                        height: 0,
+                        x: 0,
+                        y: 0,
+                        pixels: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Frame {
+                /// Creates a [FrameBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> FrameBuilder<()> {
+                    FrameBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_width: impl ::planus::WriteAsDefault<u16, u16>,
+                    field_height: impl ::planus::WriteAsDefault<u16, u16>,
+                    field_x: impl ::planus::WriteAsDefault<i16, i16>,
+                    field_y: impl ::planus::WriteAsDefault<i16, i16>,
+                    field_pixels: impl ::planus::WriteAs<::planus::Offset<[u8]>>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_width = field_width.prepare(builder, &0);
+                    let prepared_height = field_height.prepare(builder, &0);
+                    let prepared_x = field_x.prepare(builder, &0);
+                    let prepared_y = field_y.prepare(builder, &0);
+                    let prepared_pixels = field_pixels.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<14> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<[u8]>>(4);
+                    if prepared_width.is_some() {
+                        table_writer.write_entry::<u16>(0);
+                    }
+                    if prepared_height.is_some() {
+                        table_writer.write_entry::<u16>(1);
+                    }
+                    if prepared_x.is_some() {
+                        table_writer.write_entry::<i16>(2);
+                    }
+                    if prepared_y.is_some() {
+                        table_writer.write_entry::<i16>(3);
+                    }
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_pixels);
+                            if let ::core::option::Option::Some(prepared_width) = prepared_width {
+                                object_writer.write::<_, _, 2>(&prepared_width);
+                            }
+                            if let ::core::option::Option::Some(prepared_height) = prepared_height {
+                                object_writer.write::<_, _, 2>(&prepared_height);
+                            }
+                            if let ::core::option::Option::Some(prepared_x) = prepared_x {
+                                object_writer.write::<_, _, 2>(&prepared_x);
+                            }
+                            if let ::core::option::Option::Some(prepared_y) = prepared_y {
+                                object_writer.write::<_, _, 2>(&prepared_y);
+                            }
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Frame>> for Frame {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Frame> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Frame>> for Frame {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Frame>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Frame> for Frame {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Frame> {
+                    Frame::create(
+                        builder,
+                        self.width,
+                        self.height,
+                        self.x,
+                        self.y,
+                        &self.pixels,
+                    )
+                }
+            }
+
+            /// Builder for serializing an instance of the [Frame] type.
+            ///
+            /// Can be created using the [Frame::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct FrameBuilder<State>(State);
+
+            impl FrameBuilder<()> {
+                /// Setter for the [`width` field](Frame#structfield.width).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn width<T0>(self, value: T0) -> FrameBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAsDefault<u16, u16>,
+                {
+                    FrameBuilder((value,))
+                }
+
+                /// Sets the [`width` field](Frame#structfield.width) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn width_as_default(self) -> FrameBuilder<(::planus::DefaultValue,)> {
+                    self.width(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0> FrameBuilder<(T0,)> {
+                /// Setter for the [`height` field](Frame#structfield.height).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn height<T1>(self, value: T1) -> FrameBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAsDefault<u16, u16>,
+                {
+                    let (v0,) = self.0;
+                    FrameBuilder((v0, value))
+                }
+
+                /// Sets the [`height` field](Frame#structfield.height) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn height_as_default(self) -> FrameBuilder<(T0, ::planus::DefaultValue)> {
+                    self.height(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1> FrameBuilder<(T0, T1)> {
+                /// Setter for the [`x` field](Frame#structfield.x).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn x<T2>(self, value: T2) -> FrameBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAsDefault<i16, i16>,
+                {
+                    let (v0, v1) = self.0;
+                    FrameBuilder((v0, v1, value))
+                }
+
+                /// Sets the [`x` field](Frame#structfield.x) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn x_as_default(self) -> FrameBuilder<(T0, T1, ::planus::DefaultValue)> {
+                    self.x(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1, T2> FrameBuilder<(T0, T1, T2)> {
+                /// Setter for the [`y` field](Frame#structfield.y).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn y<T3>(self, value: T3) -> FrameBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAsDefault<i16, i16>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    FrameBuilder((v0, v1, v2, value))
+                }
+
+                /// Sets the [`y` field](Frame#structfield.y) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn y_as_default(self) -> FrameBuilder<(T0, T1, T2, ::planus::DefaultValue)> {
+                    self.y(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1, T2, T3> FrameBuilder<(T0, T1, T2, T3)> {
+                /// Setter for the [`pixels` field](Frame#structfield.pixels).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn pixels<T4>(self, value: T4) -> FrameBuilder<(T0, T1, T2, T3, T4)>
+                where
+                    T4: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                {
+                    let (v0, v1, v2, v3) = self.0;
+                    FrameBuilder((v0, v1, v2, v3, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4> FrameBuilder<(T0, T1, T2, T3, T4)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Frame].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Frame>
+                where
+                    Self: ::planus::WriteAsOffset<Frame>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u16, u16>,
+                    T1: ::planus::WriteAsDefault<u16, u16>,
+                    T2: ::planus::WriteAsDefault<i16, i16>,
+                    T3: ::planus::WriteAsDefault<i16, i16>,
+                    T4: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                > ::planus::WriteAs<::planus::Offset<Frame>>
+                for FrameBuilder<(T0, T1, T2, T3, T4)>
+            {
+                type Prepared = ::planus::Offset<Frame>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Frame> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u16, u16>,
+                    T1: ::planus::WriteAsDefault<u16, u16>,
+                    T2: ::planus::WriteAsDefault<i16, i16>,
+                    T3: ::planus::WriteAsDefault<i16, i16>,
+                    T4: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Frame>>
+                for FrameBuilder<(T0, T1, T2, T3, T4)>
+            {
+                type Prepared = ::planus::Offset<Frame>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Frame>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u16, u16>,
+                    T1: ::planus::WriteAsDefault<u16, u16>,
+                    T2: ::planus::WriteAsDefault<i16, i16>,
+                    T3: ::planus::WriteAsDefault<i16, i16>,
+                    T4: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                > ::planus::WriteAsOffset<Frame> for FrameBuilder<(T0, T1, T2, T3, T4)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Frame> {
+                    let (v0, v1, v2, v3, v4) = &self.0;
+                    Frame::create(builder, v0, v1, v2, v3, v4)
+                }
+            }
+
+            /// Reference to a deserialized [Frame].
+            #[derive(Copy, Clone)]
+            pub struct FrameRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> FrameRef<'a> {
+                /// Getter for the [`width` field](Frame#structfield.width).
+                #[inline]
+                pub fn width(&self) -> ::planus::Result<u16> {
+                    ::core::result::Result::Ok(self.0.access(0, "Frame", "width")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`height` field](Frame#structfield.height).
+                #[inline]
+                pub fn height(&self) -> ::planus::Result<u16> {
+                    ::core::result::Result::Ok(self.0.access(1, "Frame", "height")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`x` field](Frame#structfield.x).
+                #[inline]
+                pub fn x(&self) -> ::planus::Result<i16> {
+                    ::core::result::Result::Ok(self.0.access(2, "Frame", "x")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`y` field](Frame#structfield.y).
+                #[inline]
+                pub fn y(&self) -> ::planus::Result<i16> {
+                    ::core::result::Result::Ok(self.0.access(3, "Frame", "y")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`pixels` field](Frame#structfield.pixels).
+                #[inline]
+                pub fn pixels(&self) -> ::planus::Result<&'a [u8]> {
+                    self.0.access_required(4, "Frame", "pixels")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for FrameRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("FrameRef");
+                    f.field("width", &self.width());
+                    f.field("height", &self.height());
+                    f.field("x", &self.x());
+                    f.field("y", &self.y());
+                    f.field("pixels", &self.pixels());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<FrameRef<'a>> for Frame {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: FrameRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        width: ::core::convert::TryInto::try_into(value.width()?)?,
+                        height: ::core::convert::TryInto::try_into(value.height()?)?,
+                        x: ::core::convert::TryInto::try_into(value.x()?)?,
+                        y: ::core::convert::TryInto::try_into(value.y()?)?,
+                        pixels: value.pixels()?.to_vec(),
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for FrameRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for FrameRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[FrameRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Frame>> for Frame {
+                type Value = ::planus::Offset<Frame>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Frame>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for FrameRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[FrameRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Texture` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Texture` in the file `schema/level.fbs:53`
+            #[derive(
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Texture {
+                /// The field `name` in the table `Texture`
+                pub name: ::planus::alloc::string::String,
+                /// The field `mti_flags` in the table `Texture`
+                pub mti_flags: ::core::option::Option<u32>,
+                /// The field `frames` in the table `Texture`
+                pub frames: ::planus::alloc::vec::Vec<self::Frame>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Texture {
+                fn default() -> Self {
+                    Self {
+                        name: ::core::default::Default::default(),
+                        mti_flags: ::core::default::Default::default(),
+                        frames: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Texture {
+                /// Creates a [TextureBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> TextureBuilder<()> {
+                    TextureBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_name: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_mti_flags: impl ::planus::WriteAsOptional<u32>,
+                    field_frames: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Frame>]>,
+                    >,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_mti_flags = field_mti_flags.prepare(builder);
+                    let prepared_frames = field_frames.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<10> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    if prepared_mti_flags.is_some() {
+                        table_writer.write_entry::<u32>(1);
+                    }
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::Frame>]>>(2);
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_name);
+                            if let ::core::option::Option::Some(prepared_mti_flags) =
+                                prepared_mti_flags
+                            {
+                                object_writer.write::<_, _, 4>(&prepared_mti_flags);
+                            }
+                            object_writer.write::<_, _, 4>(&prepared_frames);
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Texture>> for Texture {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Texture> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Texture>> for Texture {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Texture>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Texture> for Texture {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Texture> {
+                    Texture::create(builder, &self.name, self.mti_flags, &self.frames)
+                }
+            }
+
+            /// Builder for serializing an instance of the [Texture] type.
+            ///
+            /// Can be created using the [Texture::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct TextureBuilder<State>(State);
+
+            impl TextureBuilder<()> {
+                /// Setter for the [`name` field](Texture#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T0>(self, value: T0) -> TextureBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    TextureBuilder((value,))
+                }
+            }
+
+            impl<T0> TextureBuilder<(T0,)> {
+                /// Setter for the [`mti_flags` field](Texture#structfield.mti_flags).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn mti_flags<T1>(self, value: T1) -> TextureBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAsOptional<u32>,
+                {
+                    let (v0,) = self.0;
+                    TextureBuilder((v0, value))
+                }
+
+                /// Sets the [`mti_flags` field](Texture#structfield.mti_flags) to null.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn mti_flags_as_null(self) -> TextureBuilder<(T0, ())> {
+                    self.mti_flags(())
+                }
+            }
+
+            impl<T0, T1> TextureBuilder<(T0, T1)> {
+                /// Setter for the [`frames` field](Texture#structfield.frames).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn frames<T2>(self, value: T2) -> TextureBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Frame>]>>,
+                {
+                    let (v0, v1) = self.0;
+                    TextureBuilder((v0, v1, value))
+                }
+            }
+
+            impl<T0, T1, T2> TextureBuilder<(T0, T1, T2)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Texture].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Texture>
+                where
+                    Self: ::planus::WriteAsOffset<Texture>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsOptional<u32>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Frame>]>>,
+                > ::planus::WriteAs<::planus::Offset<Texture>> for TextureBuilder<(T0, T1, T2)>
+            {
+                type Prepared = ::planus::Offset<Texture>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Texture> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsOptional<u32>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Frame>]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Texture>>
+                for TextureBuilder<(T0, T1, T2)>
+            {
+                type Prepared = ::planus::Offset<Texture>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Texture>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsOptional<u32>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Frame>]>>,
+                > ::planus::WriteAsOffset<Texture> for TextureBuilder<(T0, T1, T2)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Texture> {
+                    let (v0, v1, v2) = &self.0;
+                    Texture::create(builder, v0, v1, v2)
+                }
+            }
+
+            /// Reference to a deserialized [Texture].
+            #[derive(Copy, Clone)]
+            pub struct TextureRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> TextureRef<'a> {
+                /// Getter for the [`name` field](Texture#structfield.name).
+                #[inline]
+                pub fn name(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "Texture", "name")
+                }
+
+                /// Getter for the [`mti_flags` field](Texture#structfield.mti_flags).
+                #[inline]
+                pub fn mti_flags(&self) -> ::planus::Result<::core::option::Option<u32>> {
+                    self.0.access(1, "Texture", "mti_flags")
+                }
+
+                /// Getter for the [`frames` field](Texture#structfield.frames).
+                #[inline]
+                pub fn frames(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::FrameRef<'a>>>>
+                {
+                    self.0.access_required(2, "Texture", "frames")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for TextureRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("TextureRef");
+                    f.field("name", &self.name());
+                    if let ::core::option::Option::Some(field_mti_flags) =
+                        self.mti_flags().transpose()
+                    {
+                        f.field("mti_flags", &field_mti_flags);
+                    }
+                    f.field("frames", &self.frames());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<TextureRef<'a>> for Texture {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: TextureRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        name: ::core::convert::Into::into(value.name()?),
+                        mti_flags: if let ::core::option::Option::Some(mti_flags) =
+                            value.mti_flags()?
+                        {
+                            ::core::option::Option::Some(::core::convert::TryInto::try_into(
+                                mti_flags,
+                            )?)
+                        } else {
+                            ::core::option::Option::None
+                        },
+                        frames: value.frames()?.to_vec_result()?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for TextureRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for TextureRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[TextureRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Texture>> for Texture {
+                type Value = ::planus::Offset<Texture>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Texture>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for TextureRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[TextureRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Pen` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Pen` in the file `schema/level.fbs:61`
+            #[derive(
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Pen {
+                /// The field `name` in the table `Pen`
+                pub name: ::planus::alloc::string::String,
+                /// The field `value` in the table `Pen`
+                pub value: i32,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Pen {
+                fn default() -> Self {
+                    Self {
+                        name: ::core::default::Default::default(),
+                        value: 0,
+                    }
+                }
+            }
+
+            impl Pen {
+                /// Creates a [PenBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> PenBuilder<()> {
+                    PenBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_name: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_value: impl ::planus::WriteAsDefault<i32, i32>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_value = field_value.prepare(builder, &0);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<8> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    if prepared_value.is_some() {
+                        table_writer.write_entry::<i32>(1);
+                    }
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_name);
+                            if let ::core::option::Option::Some(prepared_value) = prepared_value {
+                                object_writer.write::<_, _, 4>(&prepared_value);
+                            }
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Pen>> for Pen {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Pen> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Pen>> for Pen {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Pen>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Pen> for Pen {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Pen> {
+                    Pen::create(builder, &self.name, self.value)
+                }
+            }
+
+            /// Builder for serializing an instance of the [Pen] type.
+            ///
+            /// Can be created using the [Pen::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct PenBuilder<State>(State);
+
+            impl PenBuilder<()> {
+                /// Setter for the [`name` field](Pen#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T0>(self, value: T0) -> PenBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    PenBuilder((value,))
+                }
+            }
+
+            impl<T0> PenBuilder<(T0,)> {
+                /// Setter for the [`value` field](Pen#structfield.value).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn value<T1>(self, value: T1) -> PenBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                {
+                    let (v0,) = self.0;
+                    PenBuilder((v0, value))
+                }
+
+                /// Sets the [`value` field](Pen#structfield.value) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn value_as_default(self) -> PenBuilder<(T0, ::planus::DefaultValue)> {
+                    self.value(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1> PenBuilder<(T0, T1)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Pen].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Pen>
+                where
+                    Self: ::planus::WriteAsOffset<Pen>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                > ::planus::WriteAs<::planus::Offset<Pen>> for PenBuilder<(T0, T1)>
+            {
+                type Prepared = ::planus::Offset<Pen>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Pen> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                > ::planus::WriteAsOptional<::planus::Offset<Pen>> for PenBuilder<(T0, T1)>
+            {
+                type Prepared = ::planus::Offset<Pen>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Pen>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                > ::planus::WriteAsOffset<Pen> for PenBuilder<(T0, T1)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Pen> {
+                    let (v0, v1) = &self.0;
+                    Pen::create(builder, v0, v1)
+                }
+            }
+
+            /// Reference to a deserialized [Pen].
+            #[derive(Copy, Clone)]
+            pub struct PenRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> PenRef<'a> {
+                /// Getter for the [`name` field](Pen#structfield.name).
+                #[inline]
+                pub fn name(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "Pen", "name")
+                }
+
+                /// Getter for the [`value` field](Pen#structfield.value).
+                #[inline]
+                pub fn value(&self) -> ::planus::Result<i32> {
+                    ::core::result::Result::Ok(self.0.access(1, "Pen", "value")?.unwrap_or(0))
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for PenRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("PenRef");
+                    f.field("name", &self.name());
+                    f.field("value", &self.value());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<PenRef<'a>> for Pen {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: PenRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        name: ::core::convert::Into::into(value.name()?),
+                        value: ::core::convert::TryInto::try_into(value.value()?)?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for PenRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for PenRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location("[PenRef]", "get", buffer.offset_from_start)
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Pen>> for Pen {
+                type Value = ::planus::Offset<Pen>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Pen>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for PenRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[PenRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `MeshPart` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `MeshPart` in the file `schema/level.fbs:67`
+            #[derive(
+                Clone, Debug, PartialEq, PartialOrd, ::serde::Serialize, ::serde::Deserialize,
+            )]
+            pub struct MeshPart {
+                /// The field `name` in the table `MeshPart`
+                pub name: ::planus::alloc::string::String,
+                /// The field `origin` in the table `MeshPart`
+                pub origin: self::Vec3,
+                /// The field `vertices` in the table `MeshPart`
+                pub vertices: ::planus::alloc::vec::Vec<self::Vec3>,
+                /// The field `triangles` in the table `MeshPart`
+                pub triangles: ::planus::alloc::vec::Vec<self::Triangle>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for MeshPart {
+                fn default() -> Self {
+                    Self {
+                        name: ::core::default::Default::default(),
+                        origin: ::core::default::Default::default(),
+                        vertices: ::core::default::Default::default(),
+                        triangles: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl MeshPart {
+                /// Creates a [MeshPartBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> MeshPartBuilder<()> {
+                    MeshPartBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_name: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_origin: impl ::planus::WriteAs<self::Vec3>,
+                    field_vertices: impl ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                    field_triangles: impl ::planus::WriteAs<::planus::Offset<[self::Triangle]>>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_origin = field_origin.prepare(builder);
+                    let prepared_vertices = field_vertices.prepare(builder);
+                    let prepared_triangles = field_triangles.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<12> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    table_writer.write_entry::<self::Vec3>(1);
+                    table_writer.write_entry::<::planus::Offset<[self::Vec3]>>(2);
+                    table_writer.write_entry::<::planus::Offset<[self::Triangle]>>(3);
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_name);
+                            object_writer.write::<_, _, 12>(&prepared_origin);
+                            object_writer.write::<_, _, 4>(&prepared_vertices);
+                            object_writer.write::<_, _, 4>(&prepared_triangles);
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<MeshPart>> for MeshPart {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<MeshPart> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<MeshPart>> for MeshPart {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<MeshPart>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<MeshPart> for MeshPart {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<MeshPart> {
+                    MeshPart::create(
+                        builder,
+                        &self.name,
+                        self.origin,
+                        &self.vertices,
+                        &self.triangles,
+                    )
+                }
+            }
+
+            /// Builder for serializing an instance of the [MeshPart] type.
+            ///
+            /// Can be created using the [MeshPart::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct MeshPartBuilder<State>(State);
+
+            impl MeshPartBuilder<()> {
+                /// Setter for the [`name` field](MeshPart#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T0>(self, value: T0) -> MeshPartBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    MeshPartBuilder((value,))
+                }
+            }
+
+            impl<T0> MeshPartBuilder<(T0,)> {
+                /// Setter for the [`origin` field](MeshPart#structfield.origin).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn origin<T1>(self, value: T1) -> MeshPartBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAs<self::Vec3>,
+                {
+                    let (v0,) = self.0;
+                    MeshPartBuilder((v0, value))
+                }
+            }
+
+            impl<T0, T1> MeshPartBuilder<(T0, T1)> {
+                /// Setter for the [`vertices` field](MeshPart#structfield.vertices).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn vertices<T2>(self, value: T2) -> MeshPartBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                {
+                    let (v0, v1) = self.0;
+                    MeshPartBuilder((v0, v1, value))
+                }
+            }
+
+            impl<T0, T1, T2> MeshPartBuilder<(T0, T1, T2)> {
+                /// Setter for the [`triangles` field](MeshPart#structfield.triangles).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn triangles<T3>(self, value: T3) -> MeshPartBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Triangle]>>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    MeshPartBuilder((v0, v1, v2, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3> MeshPartBuilder<(T0, T1, T2, T3)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [MeshPart].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<MeshPart>
+                where
+                    Self: ::planus::WriteAsOffset<MeshPart>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<self::Vec3>,
+                    T2: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Triangle]>>,
+                > ::planus::WriteAs<::planus::Offset<MeshPart>>
+                for MeshPartBuilder<(T0, T1, T2, T3)>
+            {
+                type Prepared = ::planus::Offset<MeshPart>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<MeshPart> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<self::Vec3>,
+                    T2: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Triangle]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<MeshPart>>
+                for MeshPartBuilder<(T0, T1, T2, T3)>
+            {
+                type Prepared = ::planus::Offset<MeshPart>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<MeshPart>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<self::Vec3>,
+                    T2: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Triangle]>>,
+                > ::planus::WriteAsOffset<MeshPart> for MeshPartBuilder<(T0, T1, T2, T3)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<MeshPart> {
+                    let (v0, v1, v2, v3) = &self.0;
+                    MeshPart::create(builder, v0, v1, v2, v3)
+                }
+            }
+
+            /// Reference to a deserialized [MeshPart].
+            #[derive(Copy, Clone)]
+            pub struct MeshPartRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> MeshPartRef<'a> {
+                /// Getter for the [`name` field](MeshPart#structfield.name).
+                #[inline]
+                pub fn name(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "MeshPart", "name")
+                }
+
+                /// Getter for the [`origin` field](MeshPart#structfield.origin).
+                #[inline]
+                pub fn origin(&self) -> ::planus::Result<self::Vec3Ref<'a>> {
+                    self.0.access_required(1, "MeshPart", "origin")
+                }
+
+                /// Getter for the [`vertices` field](MeshPart#structfield.vertices).
+                #[inline]
+                pub fn vertices(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, self::Vec3Ref<'a>>> {
+                    self.0.access_required(2, "MeshPart", "vertices")
+                }
+
+                /// Getter for the [`triangles` field](MeshPart#structfield.triangles).
+                #[inline]
+                pub fn triangles(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, self::TriangleRef<'a>>> {
+                    self.0.access_required(3, "MeshPart", "triangles")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for MeshPartRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("MeshPartRef");
+                    f.field("name", &self.name());
+                    f.field("origin", &self.origin());
+                    f.field("vertices", &self.vertices());
+                    f.field("triangles", &self.triangles());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<MeshPartRef<'a>> for MeshPart {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: MeshPartRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        name: ::core::convert::Into::into(value.name()?),
+                        origin: ::core::convert::Into::into(value.origin()?),
+                        vertices: value.vertices()?.to_vec()?,
+                        triangles: value.triangles()?.to_vec()?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for MeshPartRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for MeshPartRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[MeshPartRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<MeshPart>> for MeshPart {
+                type Value = ::planus::Offset<MeshPart>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<MeshPart>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for MeshPartRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[MeshPartRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Mesh` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Mesh` in the file `schema/level.fbs:75`
+            #[derive(
+                Clone, Debug, PartialEq, PartialOrd, ::serde::Serialize, ::serde::Deserialize,
+            )]
+            pub struct Mesh {
+                /// The field `name` in the table `Mesh`
+                pub name: ::planus::alloc::string::String,
+                /// The field `materials` in the table `Mesh`
+                pub materials: ::planus::alloc::vec::Vec<::planus::alloc::string::String>,
+                /// The field `parts` in the table `Mesh`
+                pub parts: ::planus::alloc::vec::Vec<self::MeshPart>,
+                /// The field `reference_points` in the table `Mesh`
+                pub reference_points: ::planus::alloc::vec::Vec<self::Vec3>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Mesh {
+                fn default() -> Self {
+                    Self {
+                        name: ::core::default::Default::default(),
+                        materials: ::core::default::Default::default(),
+                        parts: ::core::default::Default::default(),
+                        reference_points: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Mesh {
+                /// Creates a [MeshBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> MeshBuilder<()> {
+                    MeshBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_name: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_materials: impl ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    field_parts: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::MeshPart>]>,
+                    >,
+                    field_reference_points: impl ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_materials = field_materials.prepare(builder);
+                    let prepared_parts = field_parts.prepare(builder);
+                    let prepared_reference_points = field_reference_points.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<12> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    table_writer.write_entry::<::planus::Offset<[::planus::Offset<str>]>>(1);
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::MeshPart>]>>(2);
+                    table_writer.write_entry::<::planus::Offset<[self::Vec3]>>(3);
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_name);
+                            object_writer.write::<_, _, 4>(&prepared_materials);
+                            object_writer.write::<_, _, 4>(&prepared_parts);
+                            object_writer.write::<_, _, 4>(&prepared_reference_points);
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Mesh>> for Mesh {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Mesh> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Mesh>> for Mesh {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Mesh>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Mesh> for Mesh {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Mesh> {
+                    Mesh::create(
+                        builder,
+                        &self.name,
+                        &self.materials,
+                        &self.parts,
+                        &self.reference_points,
+                    )
+                }
+            }
+
+            /// Builder for serializing an instance of the [Mesh] type.
+            ///
+            /// Can be created using the [Mesh::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct MeshBuilder<State>(State);
+
+            impl MeshBuilder<()> {
+                /// Setter for the [`name` field](Mesh#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T0>(self, value: T0) -> MeshBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    MeshBuilder((value,))
+                }
+            }
+
+            impl<T0> MeshBuilder<(T0,)> {
+                /// Setter for the [`materials` field](Mesh#structfield.materials).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn materials<T1>(self, value: T1) -> MeshBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                {
+                    let (v0,) = self.0;
+                    MeshBuilder((v0, value))
+                }
+            }
+
+            impl<T0, T1> MeshBuilder<(T0, T1)> {
+                /// Setter for the [`parts` field](Mesh#structfield.parts).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn parts<T2>(self, value: T2) -> MeshBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::MeshPart>]>>,
+                {
+                    let (v0, v1) = self.0;
+                    MeshBuilder((v0, v1, value))
+                }
+            }
+
+            impl<T0, T1, T2> MeshBuilder<(T0, T1, T2)> {
+                /// Setter for the [`reference_points` field](Mesh#structfield.reference_points).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn reference_points<T3>(self, value: T3) -> MeshBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    MeshBuilder((v0, v1, v2, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3> MeshBuilder<(T0, T1, T2, T3)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Mesh].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Mesh>
+                where
+                    Self: ::planus::WriteAsOffset<Mesh>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::MeshPart>]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                > ::planus::WriteAs<::planus::Offset<Mesh>> for MeshBuilder<(T0, T1, T2, T3)>
+            {
+                type Prepared = ::planus::Offset<Mesh>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Mesh> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::MeshPart>]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Mesh>>
+                for MeshBuilder<(T0, T1, T2, T3)>
+            {
+                type Prepared = ::planus::Offset<Mesh>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Mesh>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::MeshPart>]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[self::Vec3]>>,
+                > ::planus::WriteAsOffset<Mesh> for MeshBuilder<(T0, T1, T2, T3)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Mesh> {
+                    let (v0, v1, v2, v3) = &self.0;
+                    Mesh::create(builder, v0, v1, v2, v3)
+                }
+            }
+
+            /// Reference to a deserialized [Mesh].
+            #[derive(Copy, Clone)]
+            pub struct MeshRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> MeshRef<'a> {
+                /// Getter for the [`name` field](Mesh#structfield.name).
+                #[inline]
+                pub fn name(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "Mesh", "name")
+                }
+
+                /// Getter for the [`materials` field](Mesh#structfield.materials).
+                #[inline]
+                pub fn materials(
+                    &self,
+                ) -> ::planus::Result<
+                    ::planus::Vector<'a, ::planus::Result<&'a ::core::primitive::str>>,
+                > {
+                    self.0.access_required(1, "Mesh", "materials")
+                }
+
+                /// Getter for the [`parts` field](Mesh#structfield.parts).
+                #[inline]
+                pub fn parts(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::MeshPartRef<'a>>>>
+                {
+                    self.0.access_required(2, "Mesh", "parts")
+                }
+
+                /// Getter for the [`reference_points` field](Mesh#structfield.reference_points).
+                #[inline]
+                pub fn reference_points(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, self::Vec3Ref<'a>>> {
+                    self.0.access_required(3, "Mesh", "reference_points")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for MeshRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("MeshRef");
+                    f.field("name", &self.name());
+                    f.field("materials", &self.materials());
+                    f.field("parts", &self.parts());
+                    f.field("reference_points", &self.reference_points());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<MeshRef<'a>> for Mesh {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: MeshRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        name: ::core::convert::Into::into(value.name()?),
+                        materials: value.materials()?.to_vec_result()?,
+                        parts: value.parts()?.to_vec_result()?,
+                        reference_points: value.reference_points()?.to_vec()?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for MeshRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for MeshRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location("[MeshRef]", "get", buffer.offset_from_start)
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Mesh>> for Mesh {
+                type Value = ::planus::Offset<Mesh>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Mesh>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for MeshRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[MeshRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The enum `FlowKind` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Enum `FlowKind` in the file `schema/level.fbs:83`
+            #[derive(
+                Copy,
+                Clone,
+                Debug,
+                PartialEq,
+                Eq,
+                PartialOrd,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            #[repr(u8)]
+            pub enum FlowKind {
+                /// The variant `Call` in the enum `FlowKind`
+                Call = 0,
+
+                /// The variant `Goto` in the enum `FlowKind`
+                Goto = 1,
+
+                /// The variant `Return` in the enum `FlowKind`
+                Return = 2,
+
+                /// The variant `AlwaysReturn` in the enum `FlowKind`
+                AlwaysReturn = 3,
+
+                /// The variant `Block` in the enum `FlowKind`
+                Block = 4,
+            }
+
+            impl FlowKind {
+                /// Array containing all valid variants of FlowKind
+                pub const ENUM_VALUES: [Self; 5] = [
+                    Self::Call,
+                    Self::Goto,
+                    Self::Return,
+                    Self::AlwaysReturn,
+                    Self::Block,
+                ];
+            }
+
+            impl ::core::convert::TryFrom<u8> for FlowKind {
+                type Error = ::planus::errors::UnknownEnumTagKind;
+                #[inline]
+                fn try_from(
+                    value: u8,
+                ) -> ::core::result::Result<Self, ::planus::errors::UnknownEnumTagKind>
+                {
+                    #[allow(clippy::match_single_binding)]
+                    match value {
+                        0 => ::core::result::Result::Ok(FlowKind::Call),
+                        1 => ::core::result::Result::Ok(FlowKind::Goto),
+                        2 => ::core::result::Result::Ok(FlowKind::Return),
+                        3 => ::core::result::Result::Ok(FlowKind::AlwaysReturn),
+                        4 => ::core::result::Result::Ok(FlowKind::Block),
+
+                        _ => ::core::result::Result::Err(::planus::errors::UnknownEnumTagKind {
+                            tag: value as i128,
+                        }),
+                    }
+                }
+            }
+
+            impl ::core::convert::From<FlowKind> for u8 {
+                #[inline]
+                fn from(value: FlowKind) -> Self {
+                    value as u8
+                }
+            }
+
+            /// # Safety
+            /// The Planus compiler correctly calculates `ALIGNMENT` and `SIZE`.
+            unsafe impl ::planus::Primitive for FlowKind {
+                const ALIGNMENT: usize = 1;
+                const SIZE: usize = 1;
+            }
+
+            impl ::planus::WriteAsPrimitive<FlowKind> for FlowKind {
+                #[inline]
+                fn write<const N: usize>(
+                    &self,
+                    cursor: ::planus::Cursor<'_, N>,
+                    buffer_position: u32,
+                ) {
+                    (*self as u8).write(cursor, buffer_position);
+                }
+            }
+
+            impl ::planus::WriteAs<FlowKind> for FlowKind {
+                type Prepared = Self;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> FlowKind {
+                    *self
+                }
+            }
+
+            impl ::planus::WriteAsDefault<FlowKind, FlowKind> for FlowKind {
+                type Prepared = Self;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                    default: &FlowKind,
+                ) -> ::core::option::Option<FlowKind> {
+                    if self == default {
+                        ::core::option::Option::None
+                    } else {
+                        ::core::option::Option::Some(*self)
+                    }
+                }
+            }
+
+            impl ::planus::WriteAsOptional<FlowKind> for FlowKind {
+                type Prepared = Self;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<FlowKind> {
+                    ::core::option::Option::Some(*self)
+                }
+            }
+
+            impl<'buf> ::planus::TableRead<'buf> for FlowKind {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'buf>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    let n: u8 = ::planus::TableRead::from_buffer(buffer, offset)?;
+                    ::core::result::Result::Ok(::core::convert::TryInto::try_into(n)?)
+                }
+            }
+
+            impl<'buf> ::planus::VectorReadInner<'buf> for FlowKind {
+                type Error = ::planus::errors::UnknownEnumTag;
+                const STRIDE: usize = 1;
+                #[inline]
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'buf>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::UnknownEnumTag>
+                {
+                    let value = unsafe { *buffer.buffer.get_unchecked(offset) };
+                    let value: ::core::result::Result<Self, _> =
+                        ::core::convert::TryInto::try_into(value);
+                    value.map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "FlowKind",
+                            "VectorRead::from_buffer",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<FlowKind> for FlowKind {
+                const STRIDE: usize = 1;
+
+                type Value = Self;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self {
+                    *self
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[Self],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 1];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - i as u32,
+                        );
+                    }
+                }
+            }
+
+            /// The struct `Flow` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Struct `Flow` in the file `schema/level.fbs:96`
+            #[derive(
+                Copy,
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Flow {
+                /// The field `kind` in the struct `Flow`
+                pub kind: self::FlowKind,
+
+                /// The field `target` in the struct `Flow`
+                pub target: u32,
+            }
+
+            /// # Safety
+            /// The Planus compiler correctly calculates `ALIGNMENT` and `SIZE`.
+            unsafe impl ::planus::Primitive for Flow {
+                const ALIGNMENT: usize = 4;
+                const SIZE: usize = 8;
+            }
+
+            #[allow(clippy::identity_op)]
+            impl ::planus::WriteAsPrimitive<Flow> for Flow {
+                #[inline]
+                fn write<const N: usize>(
+                    &self,
+                    cursor: ::planus::Cursor<'_, N>,
+                    buffer_position: u32,
+                ) {
+                    let (cur, cursor) = cursor.split::<1, 7>();
+                    self.kind.write(cur, buffer_position - 0);
+                    let cursor = cursor.write::<3, 4>([0; 3]);
+                    let (cur, cursor) = cursor.split::<4, 0>();
+                    self.target.write(cur, buffer_position - 4);
+                    cursor.finish([]);
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Flow> for Flow {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Flow> {
+                    unsafe {
+                        builder.write_with(8, 3, |buffer_position, bytes| {
+                            let bytes = bytes.as_mut_ptr();
+
+                            ::planus::WriteAsPrimitive::write(
+                                self,
+                                ::planus::Cursor::new(
+                                    &mut *(bytes as *mut [::core::mem::MaybeUninit<u8>; 8]),
+                                ),
+                                buffer_position,
+                            );
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<Flow> for Flow {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self {
+                    *self
+                }
+            }
+
+            impl ::planus::WriteAsOptional<Flow> for Flow {
+                type Prepared = Self;
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<Self> {
+                    ::core::option::Option::Some(*self)
+                }
+            }
+
+            /// Reference to a deserialized [Flow].
+            #[derive(Copy, Clone)]
+            pub struct FlowRef<'a>(::planus::ArrayWithStartOffset<'a, 8>);
+
+            impl<'a> FlowRef<'a> {
+                /// Getter for the [`kind` field](Flow#structfield.kind).
+                pub fn kind(
+                    &self,
+                ) -> ::core::result::Result<self::FlowKind, ::planus::errors::UnknownEnumTag>
+                {
+                    let buffer = self.0.advance_as_array::<1>(0).unwrap();
+
+                    let value: ::core::result::Result<self::FlowKind, _> =
+                        ::core::convert::TryInto::try_into(u8::from_le_bytes(*buffer.as_array()));
+                    value.map_err(|e| {
+                        e.with_error_location("FlowRef", "kind", buffer.offset_from_start)
+                    })
+                }
+
+                /// Getter for the [`target` field](Flow#structfield.target).
+                pub fn target(&self) -> u32 {
+                    let buffer = self.0.advance_as_array::<4>(4).unwrap();
+
+                    u32::from_le_bytes(*buffer.as_array())
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for FlowRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("FlowRef");
+                    f.field("kind", &self.kind());
+                    f.field("target", &self.target());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::From<::planus::ArrayWithStartOffset<'a, 8>> for FlowRef<'a> {
+                fn from(array: ::planus::ArrayWithStartOffset<'a, 8>) -> Self {
+                    Self(array)
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<FlowRef<'a>> for Flow {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: FlowRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        kind: value.kind()?,
+                        target: value.target(),
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for FlowRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    let buffer = buffer.advance_as_array::<8>(offset)?;
+                    ::core::result::Result::Ok(Self(buffer))
+                }
+            }
+
+            impl<'a> ::planus::VectorRead<'a> for FlowRef<'a> {
+                const STRIDE: usize = 8;
+
+                #[inline]
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> Self {
+                    Self(unsafe { buffer.unchecked_advance_as_array(offset) })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<Flow> for Flow {
+                const STRIDE: usize = 8;
+
+                type Value = Flow;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self::Value {
+                    *self
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[Flow],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 8];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (8 * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            /// The table `Instruction` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Instruction` in the file `schema/level.fbs:102`
+            #[derive(
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Instruction {
+                /// The field `offset` in the table `Instruction`
+                pub offset: u32,
+                /// The field `size` in the table `Instruction`
+                pub size: u32,
+                /// The field `opcode` in the table `Instruction`
+                pub opcode: u8,
+                /// The field `name` in the table `Instruction`
+                pub name: ::planus::alloc::string::String,
+                /// The field `flow` in the table `Instruction`
+                pub flow: ::planus::alloc::vec::Vec<self::Flow>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Instruction {
+                fn default() -> Self {
+                    Self {
+                        offset: 0,
+                        size: 0,
+                        opcode: 0,
+                        name: ::core::default::Default::default(),
+                        flow: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Instruction {
+                /// Creates a [InstructionBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> InstructionBuilder<()> {
+                    InstructionBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_offset: impl ::planus::WriteAsDefault<u32, u32>,
+                    field_size: impl ::planus::WriteAsDefault<u32, u32>,
+                    field_opcode: impl ::planus::WriteAsDefault<u8, u8>,
+                    field_name: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_flow: impl ::planus::WriteAs<::planus::Offset<[self::Flow]>>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_offset = field_offset.prepare(builder, &0);
+                    let prepared_size = field_size.prepare(builder, &0);
+                    let prepared_opcode = field_opcode.prepare(builder, &0);
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_flow = field_flow.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<14> =
+                        ::core::default::Default::default();
+                    if prepared_offset.is_some() {
+                        table_writer.write_entry::<u32>(0);
+                    }
+                    if prepared_size.is_some() {
+                        table_writer.write_entry::<u32>(1);
+                    }
+                    table_writer.write_entry::<::planus::Offset<str>>(3);
+                    table_writer.write_entry::<::planus::Offset<[self::Flow]>>(4);
+                    if prepared_opcode.is_some() {
+                        table_writer.write_entry::<u8>(2);
+                    }
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            if let ::core::option::Option::Some(prepared_offset) = prepared_offset {
+                                object_writer.write::<_, _, 4>(&prepared_offset);
+                            }
+                            if let ::core::option::Option::Some(prepared_size) = prepared_size {
+                                object_writer.write::<_, _, 4>(&prepared_size);
+                            }
+                            object_writer.write::<_, _, 4>(&prepared_name);
+                            object_writer.write::<_, _, 4>(&prepared_flow);
+                            if let ::core::option::Option::Some(prepared_opcode) = prepared_opcode {
+                                object_writer.write::<_, _, 1>(&prepared_opcode);
+                            }
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Instruction>> for Instruction {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::planus::Offset<Instruction> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Instruction>> for Instruction {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Instruction>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Instruction> for Instruction {
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::planus::Offset<Instruction> {
+                    Instruction::create(
+                        builder,
+                        self.offset,
+                        self.size,
+                        self.opcode,
+                        &self.name,
+                        &self.flow,
+                    )
+                }
+            }
+
+            /// Builder for serializing an instance of the [Instruction] type.
+            ///
+            /// Can be created using the [Instruction::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct InstructionBuilder<State>(State);
+
+            impl InstructionBuilder<()> {
+                /// Setter for the [`offset` field](Instruction#structfield.offset).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn offset<T0>(self, value: T0) -> InstructionBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                {
+                    InstructionBuilder((value,))
+                }
+
+                /// Sets the [`offset` field](Instruction#structfield.offset) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn offset_as_default(self) -> InstructionBuilder<(::planus::DefaultValue,)> {
+                    self.offset(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0> InstructionBuilder<(T0,)> {
+                /// Setter for the [`size` field](Instruction#structfield.size).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn size<T1>(self, value: T1) -> InstructionBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAsDefault<u32, u32>,
+                {
+                    let (v0,) = self.0;
+                    InstructionBuilder((v0, value))
+                }
+
+                /// Sets the [`size` field](Instruction#structfield.size) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn size_as_default(self) -> InstructionBuilder<(T0, ::planus::DefaultValue)> {
+                    self.size(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1> InstructionBuilder<(T0, T1)> {
+                /// Setter for the [`opcode` field](Instruction#structfield.opcode).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn opcode<T2>(self, value: T2) -> InstructionBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAsDefault<u8, u8>,
+                {
+                    let (v0, v1) = self.0;
+                    InstructionBuilder((v0, v1, value))
+                }
+
+                /// Sets the [`opcode` field](Instruction#structfield.opcode) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn opcode_as_default(
+                    self,
+                ) -> InstructionBuilder<(T0, T1, ::planus::DefaultValue)> {
+                    self.opcode(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1, T2> InstructionBuilder<(T0, T1, T2)> {
+                /// Setter for the [`name` field](Instruction#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T3>(self, value: T3) -> InstructionBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    InstructionBuilder((v0, v1, v2, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3> InstructionBuilder<(T0, T1, T2, T3)> {
+                /// Setter for the [`flow` field](Instruction#structfield.flow).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn flow<T4>(self, value: T4) -> InstructionBuilder<(T0, T1, T2, T3, T4)>
+                where
+                    T4: ::planus::WriteAs<::planus::Offset<[self::Flow]>>,
+                {
+                    let (v0, v1, v2, v3) = self.0;
+                    InstructionBuilder((v0, v1, v2, v3, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4> InstructionBuilder<(T0, T1, T2, T3, T4)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Instruction].
+                #[inline]
+                pub fn finish(
+                    self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::planus::Offset<Instruction>
+                where
+                    Self: ::planus::WriteAsOffset<Instruction>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAsDefault<u32, u32>,
+                    T2: ::planus::WriteAsDefault<u8, u8>,
+                    T3: ::planus::WriteAs<::planus::Offset<str>>,
+                    T4: ::planus::WriteAs<::planus::Offset<[self::Flow]>>,
+                > ::planus::WriteAs<::planus::Offset<Instruction>>
+                for InstructionBuilder<(T0, T1, T2, T3, T4)>
+            {
+                type Prepared = ::planus::Offset<Instruction>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::planus::Offset<Instruction> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAsDefault<u32, u32>,
+                    T2: ::planus::WriteAsDefault<u8, u8>,
+                    T3: ::planus::WriteAs<::planus::Offset<str>>,
+                    T4: ::planus::WriteAs<::planus::Offset<[self::Flow]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Instruction>>
+                for InstructionBuilder<(T0, T1, T2, T3, T4)>
+            {
+                type Prepared = ::planus::Offset<Instruction>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Instruction>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAsDefault<u32, u32>,
+                    T2: ::planus::WriteAsDefault<u8, u8>,
+                    T3: ::planus::WriteAs<::planus::Offset<str>>,
+                    T4: ::planus::WriteAs<::planus::Offset<[self::Flow]>>,
+                > ::planus::WriteAsOffset<Instruction>
+                for InstructionBuilder<(T0, T1, T2, T3, T4)>
+            {
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::planus::Offset<Instruction> {
+                    let (v0, v1, v2, v3, v4) = &self.0;
+                    Instruction::create(builder, v0, v1, v2, v3, v4)
+                }
+            }
+
+            /// Reference to a deserialized [Instruction].
+            #[derive(Copy, Clone)]
+            pub struct InstructionRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> InstructionRef<'a> {
+                /// Getter for the [`offset` field](Instruction#structfield.offset).
+                #[inline]
+                pub fn offset(&self) -> ::planus::Result<u32> {
+                    ::core::result::Result::Ok(
+                        self.0.access(0, "Instruction", "offset")?.unwrap_or(0),
+                    )
+                }
+
+                /// Getter for the [`size` field](Instruction#structfield.size).
+                #[inline]
+                pub fn size(&self) -> ::planus::Result<u32> {
+                    ::core::result::Result::Ok(
+                        self.0.access(1, "Instruction", "size")?.unwrap_or(0),
+                    )
+                }
+
+                /// Getter for the [`opcode` field](Instruction#structfield.opcode).
+                #[inline]
+                pub fn opcode(&self) -> ::planus::Result<u8> {
+                    ::core::result::Result::Ok(
+                        self.0.access(2, "Instruction", "opcode")?.unwrap_or(0),
+                    )
+                }
+
+                /// Getter for the [`name` field](Instruction#structfield.name).
+                #[inline]
+                pub fn name(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(3, "Instruction", "name")
+                }
+
+                /// Getter for the [`flow` field](Instruction#structfield.flow).
+                #[inline]
+                pub fn flow(&self) -> ::planus::Result<::planus::Vector<'a, self::FlowRef<'a>>> {
+                    self.0.access_required(4, "Instruction", "flow")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for InstructionRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("InstructionRef");
+                    f.field("offset", &self.offset());
+                    f.field("size", &self.size());
+                    f.field("opcode", &self.opcode());
+                    f.field("name", &self.name());
+                    f.field("flow", &self.flow());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<InstructionRef<'a>> for Instruction {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: InstructionRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        offset: ::core::convert::TryInto::try_into(value.offset()?)?,
+                        size: ::core::convert::TryInto::try_into(value.size()?)?,
+                        opcode: ::core::convert::TryInto::try_into(value.opcode()?)?,
+                        name: ::core::convert::Into::into(value.name()?),
+                        flow: value.flow()?.to_vec()?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for InstructionRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for InstructionRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[InstructionRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Instruction>> for Instruction {
+                type Value = ::planus::Offset<Instruction>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Instruction>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for InstructionRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[InstructionRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Script` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Script` in the file `schema/level.fbs:113`
+            #[derive(
+                Clone,
+                Debug,
+                PartialEq,
+                PartialOrd,
+                Eq,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            pub struct Script {
+                /// The field `offset` in the table `Script`
+                pub offset: u32,
+                /// The field `entities` in the table `Script`
+                pub entities: ::planus::alloc::vec::Vec<::planus::alloc::string::String>,
+                /// The field `instructions` in the table `Script`
+                pub instructions: ::planus::alloc::vec::Vec<self::Instruction>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Script {
+                fn default() -> Self {
+                    Self {
+                        offset: 0,
+                        entities: ::core::default::Default::default(),
+                        instructions: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Script {
+                /// Creates a [ScriptBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> ScriptBuilder<()> {
+                    ScriptBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_offset: impl ::planus::WriteAsDefault<u32, u32>,
+                    field_entities: impl ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    field_instructions: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Instruction>]>,
+                    >,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_offset = field_offset.prepare(builder, &0);
+                    let prepared_entities = field_entities.prepare(builder);
+                    let prepared_instructions = field_instructions.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<10> =
+                        ::core::default::Default::default();
+                    if prepared_offset.is_some() {
+                        table_writer.write_entry::<u32>(0);
+                    }
+                    table_writer.write_entry::<::planus::Offset<[::planus::Offset<str>]>>(1);
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::Instruction>]>>(2);
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            if let ::core::option::Option::Some(prepared_offset) = prepared_offset {
+                                object_writer.write::<_, _, 4>(&prepared_offset);
+                            }
+                            object_writer.write::<_, _, 4>(&prepared_entities);
+                            object_writer.write::<_, _, 4>(&prepared_instructions);
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Script>> for Script {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Script> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Script>> for Script {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Script>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Script> for Script {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Script> {
+                    Script::create(builder, self.offset, &self.entities, &self.instructions)
+                }
+            }
+
+            /// Builder for serializing an instance of the [Script] type.
+            ///
+            /// Can be created using the [Script::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct ScriptBuilder<State>(State);
+
+            impl ScriptBuilder<()> {
+                /// Setter for the [`offset` field](Script#structfield.offset).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn offset<T0>(self, value: T0) -> ScriptBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                {
+                    ScriptBuilder((value,))
+                }
+
+                /// Sets the [`offset` field](Script#structfield.offset) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn offset_as_default(self) -> ScriptBuilder<(::planus::DefaultValue,)> {
+                    self.offset(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0> ScriptBuilder<(T0,)> {
+                /// Setter for the [`entities` field](Script#structfield.entities).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn entities<T1>(self, value: T1) -> ScriptBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                {
+                    let (v0,) = self.0;
+                    ScriptBuilder((v0, value))
+                }
+            }
+
+            impl<T0, T1> ScriptBuilder<(T0, T1)> {
+                /// Setter for the [`instructions` field](Script#structfield.instructions).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn instructions<T2>(self, value: T2) -> ScriptBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Instruction>]>>,
+                {
+                    let (v0, v1) = self.0;
+                    ScriptBuilder((v0, v1, value))
+                }
+            }
+
+            impl<T0, T1, T2> ScriptBuilder<(T0, T1, T2)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Script].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Script>
+                where
+                    Self: ::planus::WriteAsOffset<Script>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Instruction>]>>,
+                > ::planus::WriteAs<::planus::Offset<Script>> for ScriptBuilder<(T0, T1, T2)>
+            {
+                type Prepared = ::planus::Offset<Script>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Script> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Instruction>]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Script>>
+                for ScriptBuilder<(T0, T1, T2)>
+            {
+                type Prepared = ::planus::Offset<Script>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Script>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAs<::planus::Offset<[::planus::Offset<str>]>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Instruction>]>>,
+                > ::planus::WriteAsOffset<Script> for ScriptBuilder<(T0, T1, T2)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Script> {
+                    let (v0, v1, v2) = &self.0;
+                    Script::create(builder, v0, v1, v2)
+                }
+            }
+
+            /// Reference to a deserialized [Script].
+            #[derive(Copy, Clone)]
+            pub struct ScriptRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> ScriptRef<'a> {
+                /// Getter for the [`offset` field](Script#structfield.offset).
+                #[inline]
+                pub fn offset(&self) -> ::planus::Result<u32> {
+                    ::core::result::Result::Ok(self.0.access(0, "Script", "offset")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`entities` field](Script#structfield.entities).
+                #[inline]
+                pub fn entities(
+                    &self,
+                ) -> ::planus::Result<
+                    ::planus::Vector<'a, ::planus::Result<&'a ::core::primitive::str>>,
+                > {
+                    self.0.access_required(1, "Script", "entities")
+                }
+
+                /// Getter for the [`instructions` field](Script#structfield.instructions).
+                #[inline]
+                pub fn instructions(
+                    &self,
+                ) -> ::planus::Result<
+                    ::planus::Vector<'a, ::planus::Result<self::InstructionRef<'a>>>,
+                > {
+                    self.0.access_required(2, "Script", "instructions")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for ScriptRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("ScriptRef");
+                    f.field("offset", &self.offset());
+                    f.field("entities", &self.entities());
+                    f.field("instructions", &self.instructions());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<ScriptRef<'a>> for Script {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: ScriptRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        offset: ::core::convert::TryInto::try_into(value.offset()?)?,
+                        entities: value.entities()?.to_vec_result()?,
+                        instructions: value.instructions()?.to_vec_result()?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for ScriptRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for ScriptRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[ScriptRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Script>> for Script {
+                type Value = ::planus::Offset<Script>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Script>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for ScriptRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[ScriptRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The enum `ZoneKind` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Enum `ZoneKind` in the file `schema/level.fbs:121`
+            #[derive(
+                Copy,
+                Clone,
+                Debug,
+                PartialEq,
+                Eq,
+                PartialOrd,
+                Ord,
+                Hash,
+                ::serde::Serialize,
+                ::serde::Deserialize,
+            )]
+            #[repr(u8)]
+            pub enum ZoneKind {
+                /// The variant `ArenaShowZone` in the enum `ZoneKind`
+                ArenaShowZone = 0,
+
+                /// The variant `Hotgen` in the enum `ZoneKind`
+                Hotgen = 1,
+
+                /// The variant `ArenaActivateZone` in the enum `ZoneKind`
+                ArenaActivateZone = 2,
+
+                /// The variant `Hotpick` in the enum `ZoneKind`
+                Hotpick = 3,
+
+                /// The variant `HidingSpot` in the enum `ZoneKind`
+                HidingSpot = 4,
+
+                /// The variant `ArenaConnectZone` in the enum `ZoneKind`
+                ArenaConnectZone = 5,
+
+                /// The variant `Fan` in the enum `ZoneKind`
+                Fan = 6,
+
+                /// The variant `JumpPoint` in the enum `ZoneKind`
+                JumpPoint = 7,
+
+                /// The variant `Slidething` in the enum `ZoneKind`
+                Slidething = 8,
+            }
+
+            impl ZoneKind {
+                /// Array containing all valid variants of ZoneKind
+                pub const ENUM_VALUES: [Self; 9] = [
+                    Self::ArenaShowZone,
+                    Self::Hotgen,
+                    Self::ArenaActivateZone,
+                    Self::Hotpick,
+                    Self::HidingSpot,
+                    Self::ArenaConnectZone,
+                    Self::Fan,
+                    Self::JumpPoint,
+                    Self::Slidething,
+                ];
+            }
+
+            impl ::core::convert::TryFrom<u8> for ZoneKind {
+                type Error = ::planus::errors::UnknownEnumTagKind;
+                #[inline]
+                fn try_from(
+                    value: u8,
+                ) -> ::core::result::Result<Self, ::planus::errors::UnknownEnumTagKind>
+                {
+                    #[allow(clippy::match_single_binding)]
+                    match value {
+                        0 => ::core::result::Result::Ok(ZoneKind::ArenaShowZone),
+                        1 => ::core::result::Result::Ok(ZoneKind::Hotgen),
+                        2 => ::core::result::Result::Ok(ZoneKind::ArenaActivateZone),
+                        3 => ::core::result::Result::Ok(ZoneKind::Hotpick),
+                        4 => ::core::result::Result::Ok(ZoneKind::HidingSpot),
+                        5 => ::core::result::Result::Ok(ZoneKind::ArenaConnectZone),
+                        6 => ::core::result::Result::Ok(ZoneKind::Fan),
+                        7 => ::core::result::Result::Ok(ZoneKind::JumpPoint),
+                        8 => ::core::result::Result::Ok(ZoneKind::Slidething),
+
+                        _ => ::core::result::Result::Err(::planus::errors::UnknownEnumTagKind {
+                            tag: value as i128,
+                        }),
+                    }
+                }
+            }
+
+            impl ::core::convert::From<ZoneKind> for u8 {
+                #[inline]
+                fn from(value: ZoneKind) -> Self {
+                    value as u8
+                }
+            }
+
+            /// # Safety
+            /// The Planus compiler correctly calculates `ALIGNMENT` and `SIZE`.
+            unsafe impl ::planus::Primitive for ZoneKind {
+                const ALIGNMENT: usize = 1;
+                const SIZE: usize = 1;
+            }
+
+            impl ::planus::WriteAsPrimitive<ZoneKind> for ZoneKind {
+                #[inline]
+                fn write<const N: usize>(
+                    &self,
+                    cursor: ::planus::Cursor<'_, N>,
+                    buffer_position: u32,
+                ) {
+                    (*self as u8).write(cursor, buffer_position);
+                }
+            }
+
+            impl ::planus::WriteAs<ZoneKind> for ZoneKind {
+                type Prepared = Self;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> ZoneKind {
+                    *self
+                }
+            }
+
+            impl ::planus::WriteAsDefault<ZoneKind, ZoneKind> for ZoneKind {
+                type Prepared = Self;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                    default: &ZoneKind,
+                ) -> ::core::option::Option<ZoneKind> {
+                    if self == default {
+                        ::core::option::Option::None
+                    } else {
+                        ::core::option::Option::Some(*self)
+                    }
+                }
+            }
+
+            impl ::planus::WriteAsOptional<ZoneKind> for ZoneKind {
+                type Prepared = Self;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    _builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<ZoneKind> {
+                    ::core::option::Option::Some(*self)
+                }
+            }
+
+            impl<'buf> ::planus::TableRead<'buf> for ZoneKind {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'buf>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    let n: u8 = ::planus::TableRead::from_buffer(buffer, offset)?;
+                    ::core::result::Result::Ok(::core::convert::TryInto::try_into(n)?)
+                }
+            }
+
+            impl<'buf> ::planus::VectorReadInner<'buf> for ZoneKind {
+                type Error = ::planus::errors::UnknownEnumTag;
+                const STRIDE: usize = 1;
+                #[inline]
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'buf>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::UnknownEnumTag>
+                {
+                    let value = unsafe { *buffer.buffer.get_unchecked(offset) };
+                    let value: ::core::result::Result<Self, _> =
+                        ::core::convert::TryInto::try_into(value);
+                    value.map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "ZoneKind",
+                            "VectorRead::from_buffer",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<ZoneKind> for ZoneKind {
+                const STRIDE: usize = 1;
+
+                type Value = Self;
+
+                #[inline]
+                fn prepare(&self, _builder: &mut ::planus::Builder) -> Self {
+                    *self
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[Self],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 1];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - i as u32,
+                        );
+                    }
+                }
+            }
+
+            /// The table `Zone` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Zone` in the file `schema/level.fbs:134`
+            #[derive(
+                Clone, Debug, PartialEq, PartialOrd, ::serde::Serialize, ::serde::Deserialize,
+            )]
+            pub struct Zone {
+                /// The field `arena` in the table `Zone`
+                pub arena: ::planus::alloc::string::String,
+                /// The field `id` in the table `Zone`
+                pub id: i32,
+                /// The field `kind` in the table `Zone`
+                pub kind: self::ZoneKind,
+                /// The field `min` in the table `Zone`
+                pub min: self::Vec3,
+                /// The field `max` in the table `Zone`
+                pub max: self::Vec3,
+                /// The field `name` in the table `Zone`
+                pub name: ::core::option::Option<::planus::alloc::string::String>,
+                /// The field `value` in the table `Zone`
+                pub value: ::core::option::Option<i32>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Zone {
+                fn default() -> Self {
+                    Self {
+                        arena: ::core::default::Default::default(),
+                        id: 0,
+                        kind: self::ZoneKind::ArenaShowZone,
+                        min: ::core::default::Default::default(),
+                        max: ::core::default::Default::default(),
+                        name: ::core::default::Default::default(),
+                        value: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Zone {
+                /// Creates a [ZoneBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> ZoneBuilder<()> {
+                    ZoneBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_arena: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_id: impl ::planus::WriteAsDefault<i32, i32>,
+                    field_kind: impl ::planus::WriteAsDefault<self::ZoneKind, self::ZoneKind>,
+                    field_min: impl ::planus::WriteAs<self::Vec3>,
+                    field_max: impl ::planus::WriteAs<self::Vec3>,
+                    field_name: impl ::planus::WriteAsOptional<::planus::Offset<::core::primitive::str>>,
+                    field_value: impl ::planus::WriteAsOptional<i32>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_arena = field_arena.prepare(builder);
+                    let prepared_id = field_id.prepare(builder, &0);
+                    let prepared_kind = field_kind.prepare(builder, &self::ZoneKind::ArenaShowZone);
+                    let prepared_min = field_min.prepare(builder);
+                    let prepared_max = field_max.prepare(builder);
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_value = field_value.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<18> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    if prepared_id.is_some() {
+                        table_writer.write_entry::<i32>(1);
+                    }
+                    table_writer.write_entry::<self::Vec3>(3);
+                    table_writer.write_entry::<self::Vec3>(4);
+                    if prepared_name.is_some() {
+                        table_writer.write_entry::<::planus::Offset<str>>(5);
+                    }
+                    if prepared_value.is_some() {
+                        table_writer.write_entry::<i32>(6);
+                    }
+                    if prepared_kind.is_some() {
+                        table_writer.write_entry::<self::ZoneKind>(2);
+                    }
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_arena);
+                            if let ::core::option::Option::Some(prepared_id) = prepared_id {
+                                object_writer.write::<_, _, 4>(&prepared_id);
+                            }
+                            object_writer.write::<_, _, 12>(&prepared_min);
+                            object_writer.write::<_, _, 12>(&prepared_max);
+                            if let ::core::option::Option::Some(prepared_name) = prepared_name {
+                                object_writer.write::<_, _, 4>(&prepared_name);
+                            }
+                            if let ::core::option::Option::Some(prepared_value) = prepared_value {
+                                object_writer.write::<_, _, 4>(&prepared_value);
+                            }
+                            if let ::core::option::Option::Some(prepared_kind) = prepared_kind {
+                                object_writer.write::<_, _, 1>(&prepared_kind);
+                            }
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Zone>> for Zone {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Zone> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Zone>> for Zone {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Zone>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Zone> for Zone {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Zone> {
+                    Zone::create(
+                        builder,
+                        &self.arena,
+                        self.id,
+                        self.kind,
+                        self.min,
+                        self.max,
+                        &self.name,
+                        self.value,
+                    )
+                }
+            }
+
+            /// Builder for serializing an instance of the [Zone] type.
+            ///
+            /// Can be created using the [Zone::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct ZoneBuilder<State>(State);
+
+            impl ZoneBuilder<()> {
+                /// Setter for the [`arena` field](Zone#structfield.arena).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn arena<T0>(self, value: T0) -> ZoneBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    ZoneBuilder((value,))
+                }
+            }
+
+            impl<T0> ZoneBuilder<(T0,)> {
+                /// Setter for the [`id` field](Zone#structfield.id).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn id<T1>(self, value: T1) -> ZoneBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                {
+                    let (v0,) = self.0;
+                    ZoneBuilder((v0, value))
+                }
+
+                /// Sets the [`id` field](Zone#structfield.id) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn id_as_default(self) -> ZoneBuilder<(T0, ::planus::DefaultValue)> {
+                    self.id(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1> ZoneBuilder<(T0, T1)> {
+                /// Setter for the [`kind` field](Zone#structfield.kind).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn kind<T2>(self, value: T2) -> ZoneBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAsDefault<self::ZoneKind, self::ZoneKind>,
+                {
+                    let (v0, v1) = self.0;
+                    ZoneBuilder((v0, v1, value))
+                }
+
+                /// Sets the [`kind` field](Zone#structfield.kind) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn kind_as_default(self) -> ZoneBuilder<(T0, T1, ::planus::DefaultValue)> {
+                    self.kind(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1, T2> ZoneBuilder<(T0, T1, T2)> {
+                /// Setter for the [`min` field](Zone#structfield.min).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn min<T3>(self, value: T3) -> ZoneBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAs<self::Vec3>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    ZoneBuilder((v0, v1, v2, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3> ZoneBuilder<(T0, T1, T2, T3)> {
+                /// Setter for the [`max` field](Zone#structfield.max).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn max<T4>(self, value: T4) -> ZoneBuilder<(T0, T1, T2, T3, T4)>
+                where
+                    T4: ::planus::WriteAs<self::Vec3>,
+                {
+                    let (v0, v1, v2, v3) = self.0;
+                    ZoneBuilder((v0, v1, v2, v3, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4> ZoneBuilder<(T0, T1, T2, T3, T4)> {
+                /// Setter for the [`name` field](Zone#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T5>(self, value: T5) -> ZoneBuilder<(T0, T1, T2, T3, T4, T5)>
+                where
+                    T5: ::planus::WriteAsOptional<::planus::Offset<::core::primitive::str>>,
+                {
+                    let (v0, v1, v2, v3, v4) = self.0;
+                    ZoneBuilder((v0, v1, v2, v3, v4, value))
+                }
+
+                /// Sets the [`name` field](Zone#structfield.name) to null.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name_as_null(self) -> ZoneBuilder<(T0, T1, T2, T3, T4, ())> {
+                    self.name(())
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5> ZoneBuilder<(T0, T1, T2, T3, T4, T5)> {
+                /// Setter for the [`value` field](Zone#structfield.value).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn value<T6>(self, value: T6) -> ZoneBuilder<(T0, T1, T2, T3, T4, T5, T6)>
+                where
+                    T6: ::planus::WriteAsOptional<i32>,
+                {
+                    let (v0, v1, v2, v3, v4, v5) = self.0;
+                    ZoneBuilder((v0, v1, v2, v3, v4, v5, value))
+                }
+
+                /// Sets the [`value` field](Zone#structfield.value) to null.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn value_as_null(self) -> ZoneBuilder<(T0, T1, T2, T3, T4, T5, ())> {
+                    self.value(())
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5, T6> ZoneBuilder<(T0, T1, T2, T3, T4, T5, T6)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Zone].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Zone>
+                where
+                    Self: ::planus::WriteAsOffset<Zone>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                    T2: ::planus::WriteAsDefault<self::ZoneKind, self::ZoneKind>,
+                    T3: ::planus::WriteAs<self::Vec3>,
+                    T4: ::planus::WriteAs<self::Vec3>,
+                    T5: ::planus::WriteAsOptional<::planus::Offset<::core::primitive::str>>,
+                    T6: ::planus::WriteAsOptional<i32>,
+                > ::planus::WriteAs<::planus::Offset<Zone>>
+                for ZoneBuilder<(T0, T1, T2, T3, T4, T5, T6)>
+            {
+                type Prepared = ::planus::Offset<Zone>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Zone> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                    T2: ::planus::WriteAsDefault<self::ZoneKind, self::ZoneKind>,
+                    T3: ::planus::WriteAs<self::Vec3>,
+                    T4: ::planus::WriteAs<self::Vec3>,
+                    T5: ::planus::WriteAsOptional<::planus::Offset<::core::primitive::str>>,
+                    T6: ::planus::WriteAsOptional<i32>,
+                > ::planus::WriteAsOptional<::planus::Offset<Zone>>
+                for ZoneBuilder<(T0, T1, T2, T3, T4, T5, T6)>
+            {
+                type Prepared = ::planus::Offset<Zone>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Zone>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                    T2: ::planus::WriteAsDefault<self::ZoneKind, self::ZoneKind>,
+                    T3: ::planus::WriteAs<self::Vec3>,
+                    T4: ::planus::WriteAs<self::Vec3>,
+                    T5: ::planus::WriteAsOptional<::planus::Offset<::core::primitive::str>>,
+                    T6: ::planus::WriteAsOptional<i32>,
+                > ::planus::WriteAsOffset<Zone> for ZoneBuilder<(T0, T1, T2, T3, T4, T5, T6)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Zone> {
+                    let (v0, v1, v2, v3, v4, v5, v6) = &self.0;
+                    Zone::create(builder, v0, v1, v2, v3, v4, v5, v6)
+                }
+            }
+
+            /// Reference to a deserialized [Zone].
+            #[derive(Copy, Clone)]
+            pub struct ZoneRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> ZoneRef<'a> {
+                /// Getter for the [`arena` field](Zone#structfield.arena).
+                #[inline]
+                pub fn arena(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "Zone", "arena")
+                }
+
+                /// Getter for the [`id` field](Zone#structfield.id).
+                #[inline]
+                pub fn id(&self) -> ::planus::Result<i32> {
+                    ::core::result::Result::Ok(self.0.access(1, "Zone", "id")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`kind` field](Zone#structfield.kind).
+                #[inline]
+                pub fn kind(&self) -> ::planus::Result<self::ZoneKind> {
+                    ::core::result::Result::Ok(
+                        self.0
+                            .access(2, "Zone", "kind")?
+                            .unwrap_or(self::ZoneKind::ArenaShowZone),
+                    )
+                }
+
+                /// Getter for the [`min` field](Zone#structfield.min).
+                #[inline]
+                pub fn min(&self) -> ::planus::Result<self::Vec3Ref<'a>> {
+                    self.0.access_required(3, "Zone", "min")
+                }
+
+                /// Getter for the [`max` field](Zone#structfield.max).
+                #[inline]
+                pub fn max(&self) -> ::planus::Result<self::Vec3Ref<'a>> {
+                    self.0.access_required(4, "Zone", "max")
+                }
+
+                /// Getter for the [`name` field](Zone#structfield.name).
+                #[inline]
+                pub fn name(
+                    &self,
+                ) -> ::planus::Result<::core::option::Option<&'a ::core::primitive::str>>
+                {
+                    self.0.access(5, "Zone", "name")
+                }
+
+                /// Getter for the [`value` field](Zone#structfield.value).
+                #[inline]
+                pub fn value(&self) -> ::planus::Result<::core::option::Option<i32>> {
+                    self.0.access(6, "Zone", "value")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for ZoneRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("ZoneRef");
+                    f.field("arena", &self.arena());
+                    f.field("id", &self.id());
+                    f.field("kind", &self.kind());
+                    f.field("min", &self.min());
+                    f.field("max", &self.max());
+                    if let ::core::option::Option::Some(field_name) = self.name().transpose() {
+                        f.field("name", &field_name);
+                    }
+                    if let ::core::option::Option::Some(field_value) = self.value().transpose() {
+                        f.field("value", &field_value);
+                    }
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<ZoneRef<'a>> for Zone {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: ZoneRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        arena: ::core::convert::Into::into(value.arena()?),
+                        id: ::core::convert::TryInto::try_into(value.id()?)?,
+                        kind: ::core::convert::TryInto::try_into(value.kind()?)?,
+                        min: ::core::convert::Into::into(value.min()?),
+                        max: ::core::convert::Into::into(value.max()?),
+                        name: value.name()?.map(::core::convert::Into::into),
+                        value: if let ::core::option::Option::Some(value) = value.value()? {
+                            ::core::option::Option::Some(::core::convert::TryInto::try_into(value)?)
+                        } else {
+                            ::core::option::Option::None
+                        },
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for ZoneRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for ZoneRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location("[ZoneRef]", "get", buffer.offset_from_start)
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Zone>> for Zone {
+                type Value = ::planus::Offset<Zone>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Zone>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for ZoneRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[ZoneRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Teleport` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Teleport` in the file `schema/level.fbs:146`
+            #[derive(
+                Clone, Debug, PartialEq, PartialOrd, ::serde::Serialize, ::serde::Deserialize,
+            )]
+            pub struct Teleport {
+                /// The field `arena` in the table `Teleport`
+                pub arena: ::planus::alloc::string::String,
+                /// The field `index` in the table `Teleport`
+                pub index: i32,
+                /// The field `pos` in the table `Teleport`
+                pub pos: self::Vec3,
+                /// The field `angle` in the table `Teleport`
+                pub angle: f32,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Teleport {
+                fn default() -> Self {
+                    Self {
+                        arena: ::core::default::Default::default(),
+                        index: 0,
+                        pos: ::core::default::Default::default(),
+                        angle: 0.0,
+                    }
+                }
+            }
+
+            impl Teleport {
+                /// Creates a [TeleportBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> TeleportBuilder<()> {
+                    TeleportBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_arena: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_index: impl ::planus::WriteAsDefault<i32, i32>,
+                    field_pos: impl ::planus::WriteAs<self::Vec3>,
+                    field_angle: impl ::planus::WriteAsDefault<f32, f32>,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_arena = field_arena.prepare(builder);
+                    let prepared_index = field_index.prepare(builder, &0);
+                    let prepared_pos = field_pos.prepare(builder);
+                    let prepared_angle = field_angle.prepare(builder, &0.0);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<12> =
+                        ::core::default::Default::default();
+                    table_writer.write_entry::<::planus::Offset<str>>(0);
+                    if prepared_index.is_some() {
+                        table_writer.write_entry::<i32>(1);
+                    }
+                    table_writer.write_entry::<self::Vec3>(2);
+                    if prepared_angle.is_some() {
+                        table_writer.write_entry::<f32>(3);
+                    }
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            object_writer.write::<_, _, 4>(&prepared_arena);
+                            if let ::core::option::Option::Some(prepared_index) = prepared_index {
+                                object_writer.write::<_, _, 4>(&prepared_index);
+                            }
+                            object_writer.write::<_, _, 12>(&prepared_pos);
+                            if let ::core::option::Option::Some(prepared_angle) = prepared_angle {
+                                object_writer.write::<_, _, 4>(&prepared_angle);
+                            }
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Teleport>> for Teleport {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Teleport> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Teleport>> for Teleport {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Teleport>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Teleport> for Teleport {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Teleport> {
+                    Teleport::create(builder, &self.arena, self.index, self.pos, self.angle)
+                }
+            }
+
+            /// Builder for serializing an instance of the [Teleport] type.
+            ///
+            /// Can be created using the [Teleport::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct TeleportBuilder<State>(State);
+
+            impl TeleportBuilder<()> {
+                /// Setter for the [`arena` field](Teleport#structfield.arena).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn arena<T0>(self, value: T0) -> TeleportBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    TeleportBuilder((value,))
+                }
+            }
+
+            impl<T0> TeleportBuilder<(T0,)> {
+                /// Setter for the [`index` field](Teleport#structfield.index).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn index<T1>(self, value: T1) -> TeleportBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                {
+                    let (v0,) = self.0;
+                    TeleportBuilder((v0, value))
+                }
+
+                /// Sets the [`index` field](Teleport#structfield.index) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn index_as_default(self) -> TeleportBuilder<(T0, ::planus::DefaultValue)> {
+                    self.index(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1> TeleportBuilder<(T0, T1)> {
+                /// Setter for the [`pos` field](Teleport#structfield.pos).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn pos<T2>(self, value: T2) -> TeleportBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAs<self::Vec3>,
+                {
+                    let (v0, v1) = self.0;
+                    TeleportBuilder((v0, v1, value))
+                }
+            }
+
+            impl<T0, T1, T2> TeleportBuilder<(T0, T1, T2)> {
+                /// Setter for the [`angle` field](Teleport#structfield.angle).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn angle<T3>(self, value: T3) -> TeleportBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAsDefault<f32, f32>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    TeleportBuilder((v0, v1, v2, value))
+                }
+
+                /// Sets the [`angle` field](Teleport#structfield.angle) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn angle_as_default(
+                    self,
+                ) -> TeleportBuilder<(T0, T1, T2, ::planus::DefaultValue)> {
+                    self.angle(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0, T1, T2, T3> TeleportBuilder<(T0, T1, T2, T3)> {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Teleport].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Teleport>
+                where
+                    Self: ::planus::WriteAsOffset<Teleport>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                    T2: ::planus::WriteAs<self::Vec3>,
+                    T3: ::planus::WriteAsDefault<f32, f32>,
+                > ::planus::WriteAs<::planus::Offset<Teleport>>
+                for TeleportBuilder<(T0, T1, T2, T3)>
+            {
+                type Prepared = ::planus::Offset<Teleport>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Teleport> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                    T2: ::planus::WriteAs<self::Vec3>,
+                    T3: ::planus::WriteAsDefault<f32, f32>,
+                > ::planus::WriteAsOptional<::planus::Offset<Teleport>>
+                for TeleportBuilder<(T0, T1, T2, T3)>
+            {
+                type Prepared = ::planus::Offset<Teleport>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Teleport>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAs<::planus::Offset<str>>,
+                    T1: ::planus::WriteAsDefault<i32, i32>,
+                    T2: ::planus::WriteAs<self::Vec3>,
+                    T3: ::planus::WriteAsDefault<f32, f32>,
+                > ::planus::WriteAsOffset<Teleport> for TeleportBuilder<(T0, T1, T2, T3)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Teleport> {
+                    let (v0, v1, v2, v3) = &self.0;
+                    Teleport::create(builder, v0, v1, v2, v3)
+                }
+            }
+
+            /// Reference to a deserialized [Teleport].
+            #[derive(Copy, Clone)]
+            pub struct TeleportRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> TeleportRef<'a> {
+                /// Getter for the [`arena` field](Teleport#structfield.arena).
+                #[inline]
+                pub fn arena(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(0, "Teleport", "arena")
+                }
+
+                /// Getter for the [`index` field](Teleport#structfield.index).
+                #[inline]
+                pub fn index(&self) -> ::planus::Result<i32> {
+                    ::core::result::Result::Ok(self.0.access(1, "Teleport", "index")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`pos` field](Teleport#structfield.pos).
+                #[inline]
+                pub fn pos(&self) -> ::planus::Result<self::Vec3Ref<'a>> {
+                    self.0.access_required(2, "Teleport", "pos")
+                }
+
+                /// Getter for the [`angle` field](Teleport#structfield.angle).
+                #[inline]
+                pub fn angle(&self) -> ::planus::Result<f32> {
+                    ::core::result::Result::Ok(
+                        self.0.access(3, "Teleport", "angle")?.unwrap_or(0.0),
+                    )
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for TeleportRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("TeleportRef");
+                    f.field("arena", &self.arena());
+                    f.field("index", &self.index());
+                    f.field("pos", &self.pos());
+                    f.field("angle", &self.angle());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<TeleportRef<'a>> for Teleport {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: TeleportRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        arena: ::core::convert::Into::into(value.arena()?),
+                        index: ::core::convert::TryInto::try_into(value.index()?)?,
+                        pos: ::core::convert::Into::into(value.pos()?),
+                        angle: ::core::convert::TryInto::try_into(value.angle()?)?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for TeleportRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for TeleportRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[TeleportRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Teleport>> for Teleport {
+                type Value = ::planus::Offset<Teleport>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Teleport>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for TeleportRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[TeleportRef]", "read_as_root", 0)
+                    })
+                }
+            }
+
+            /// The table `Level` in the namespace `mdk.dataset`
+            ///
+            /// Generated from these locations:
+            /// * Table `Level` in the file `schema/level.fbs:153`
+            #[derive(
+                Clone, Debug, PartialEq, PartialOrd, ::serde::Serialize, ::serde::Deserialize,
+            )]
+            pub struct Level {
+                /// The field `version` in the table `Level`
+                pub version: u32,
+                /// The field `name` in the table `Level`
+                pub name: ::planus::alloc::string::String,
+                /// The field `palettes` in the table `Level`
+                pub palettes: ::planus::alloc::vec::Vec<self::Palette>,
+                /// The field `textures` in the table `Level`
+                pub textures: ::planus::alloc::vec::Vec<self::Texture>,
+                /// The field `pens` in the table `Level`
+                pub pens: ::planus::alloc::vec::Vec<self::Pen>,
+                /// The field `meshes` in the table `Level`
+                pub meshes: ::planus::alloc::vec::Vec<self::Mesh>,
+                /// The field `script_data` in the table `Level`
+                pub script_data: ::planus::alloc::vec::Vec<u8>,
+                /// The field `scripts` in the table `Level`
+                pub scripts: ::planus::alloc::vec::Vec<self::Script>,
+                /// The field `zones` in the table `Level`
+                pub zones: ::planus::alloc::vec::Vec<self::Zone>,
+                /// The field `teleports` in the table `Level`
+                pub teleports: ::planus::alloc::vec::Vec<self::Teleport>,
+            }
+
+            #[allow(clippy::derivable_impls)]
+            impl ::core::default::Default for Level {
+                fn default() -> Self {
+                    Self {
+                        version: 0,
+                        name: ::core::default::Default::default(),
+                        palettes: ::core::default::Default::default(),
+                        textures: ::core::default::Default::default(),
+                        pens: ::core::default::Default::default(),
+                        meshes: ::core::default::Default::default(),
+                        script_data: ::core::default::Default::default(),
+                        scripts: ::core::default::Default::default(),
+                        zones: ::core::default::Default::default(),
+                        teleports: ::core::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Level {
+                /// Creates a [LevelBuilder] for serializing an instance of this table.
+                #[inline]
+                pub fn builder() -> LevelBuilder<()> {
+                    LevelBuilder(())
+                }
+
+                #[allow(clippy::too_many_arguments)]
+                pub fn create(
+                    builder: &mut ::planus::Builder,
+                    field_version: impl ::planus::WriteAsDefault<u32, u32>,
+                    field_name: impl ::planus::WriteAs<::planus::Offset<str>>,
+                    field_palettes: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Palette>]>,
+                    >,
+                    field_textures: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Texture>]>,
+                    >,
+                    field_pens: impl ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Pen>]>>,
+                    field_meshes: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Mesh>]>,
+                    >,
+                    field_script_data: impl ::planus::WriteAs<::planus::Offset<[u8]>>,
+                    field_scripts: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Script>]>,
+                    >,
+                    field_zones: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Zone>]>,
+                    >,
+                    field_teleports: impl ::planus::WriteAs<
+                        ::planus::Offset<[::planus::Offset<self::Teleport>]>,
+                    >,
+                ) -> ::planus::Offset<Self> {
+                    let prepared_version = field_version.prepare(builder, &0);
+                    let prepared_name = field_name.prepare(builder);
+                    let prepared_palettes = field_palettes.prepare(builder);
+                    let prepared_textures = field_textures.prepare(builder);
+                    let prepared_pens = field_pens.prepare(builder);
+                    let prepared_meshes = field_meshes.prepare(builder);
+                    let prepared_script_data = field_script_data.prepare(builder);
+                    let prepared_scripts = field_scripts.prepare(builder);
+                    let prepared_zones = field_zones.prepare(builder);
+                    let prepared_teleports = field_teleports.prepare(builder);
+
+                    let mut table_writer: ::planus::table_writer::TableWriter<24> =
+                        ::core::default::Default::default();
+                    if prepared_version.is_some() {
+                        table_writer.write_entry::<u32>(0);
+                    }
+                    table_writer.write_entry::<::planus::Offset<str>>(1);
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::Palette>]>>(2);
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::Texture>]>>(3);
+                    table_writer.write_entry::<::planus::Offset<[::planus::Offset<self::Pen>]>>(4);
+                    table_writer.write_entry::<::planus::Offset<[::planus::Offset<self::Mesh>]>>(5);
+                    table_writer.write_entry::<::planus::Offset<[u8]>>(6);
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::Script>]>>(7);
+                    table_writer.write_entry::<::planus::Offset<[::planus::Offset<self::Zone>]>>(8);
+                    table_writer
+                        .write_entry::<::planus::Offset<[::planus::Offset<self::Teleport>]>>(9);
+
+                    unsafe {
+                        table_writer.finish(builder, |object_writer| {
+                            if let ::core::option::Option::Some(prepared_version) = prepared_version
+                            {
+                                object_writer.write::<_, _, 4>(&prepared_version);
+                            }
+                            object_writer.write::<_, _, 4>(&prepared_name);
+                            object_writer.write::<_, _, 4>(&prepared_palettes);
+                            object_writer.write::<_, _, 4>(&prepared_textures);
+                            object_writer.write::<_, _, 4>(&prepared_pens);
+                            object_writer.write::<_, _, 4>(&prepared_meshes);
+                            object_writer.write::<_, _, 4>(&prepared_script_data);
+                            object_writer.write::<_, _, 4>(&prepared_scripts);
+                            object_writer.write::<_, _, 4>(&prepared_zones);
+                            object_writer.write::<_, _, 4>(&prepared_teleports);
+                        });
+                    }
+                    builder.current_offset()
+                }
+            }
+
+            impl ::planus::WriteAs<::planus::Offset<Level>> for Level {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Level> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl ::planus::WriteAsOptional<::planus::Offset<Level>> for Level {
+                type Prepared = ::planus::Offset<Self>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Level>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl ::planus::WriteAsOffset<Level> for Level {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Level> {
+                    Level::create(
+                        builder,
+                        self.version,
+                        &self.name,
+                        &self.palettes,
+                        &self.textures,
+                        &self.pens,
+                        &self.meshes,
+                        &self.script_data,
+                        &self.scripts,
+                        &self.zones,
+                        &self.teleports,
+                    )
+                }
+            }
+
+            /// Builder for serializing an instance of the [Level] type.
+            ///
+            /// Can be created using the [Level::builder] method.
+            #[derive(Debug)]
+            #[must_use]
+            pub struct LevelBuilder<State>(State);
+
+            impl LevelBuilder<()> {
+                /// Setter for the [`version` field](Level#structfield.version).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn version<T0>(self, value: T0) -> LevelBuilder<(T0,)>
+                where
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                {
+                    LevelBuilder((value,))
+                }
+
+                /// Sets the [`version` field](Level#structfield.version) to the default value.
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn version_as_default(self) -> LevelBuilder<(::planus::DefaultValue,)> {
+                    self.version(::planus::DefaultValue)
+                }
+            }
+
+            impl<T0> LevelBuilder<(T0,)> {
+                /// Setter for the [`name` field](Level#structfield.name).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn name<T1>(self, value: T1) -> LevelBuilder<(T0, T1)>
+                where
+                    T1: ::planus::WriteAs<::planus::Offset<str>>,
+                {
+                    let (v0,) = self.0;
+                    LevelBuilder((v0, value))
+                }
+            }
+
+            impl<T0, T1> LevelBuilder<(T0, T1)> {
+                /// Setter for the [`palettes` field](Level#structfield.palettes).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn palettes<T2>(self, value: T2) -> LevelBuilder<(T0, T1, T2)>
+                where
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Palette>]>>,
+                {
+                    let (v0, v1) = self.0;
+                    LevelBuilder((v0, v1, value))
+                }
+            }
+
+            impl<T0, T1, T2> LevelBuilder<(T0, T1, T2)> {
+                /// Setter for the [`textures` field](Level#structfield.textures).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn textures<T3>(self, value: T3) -> LevelBuilder<(T0, T1, T2, T3)>
+                where
+                    T3: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Texture>]>>,
+                {
+                    let (v0, v1, v2) = self.0;
+                    LevelBuilder((v0, v1, v2, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3> LevelBuilder<(T0, T1, T2, T3)> {
+                /// Setter for the [`pens` field](Level#structfield.pens).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn pens<T4>(self, value: T4) -> LevelBuilder<(T0, T1, T2, T3, T4)>
+                where
+                    T4: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Pen>]>>,
+                {
+                    let (v0, v1, v2, v3) = self.0;
+                    LevelBuilder((v0, v1, v2, v3, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4> LevelBuilder<(T0, T1, T2, T3, T4)> {
+                /// Setter for the [`meshes` field](Level#structfield.meshes).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn meshes<T5>(self, value: T5) -> LevelBuilder<(T0, T1, T2, T3, T4, T5)>
+                where
+                    T5: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Mesh>]>>,
+                {
+                    let (v0, v1, v2, v3, v4) = self.0;
+                    LevelBuilder((v0, v1, v2, v3, v4, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5> LevelBuilder<(T0, T1, T2, T3, T4, T5)> {
+                /// Setter for the [`script_data` field](Level#structfield.script_data).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn script_data<T6>(
+                    self,
+                    value: T6,
+                ) -> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6)>
+                where
+                    T6: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                {
+                    let (v0, v1, v2, v3, v4, v5) = self.0;
+                    LevelBuilder((v0, v1, v2, v3, v4, v5, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5, T6> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6)> {
+                /// Setter for the [`scripts` field](Level#structfield.scripts).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn scripts<T7>(
+                    self,
+                    value: T7,
+                ) -> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7)>
+                where
+                    T7: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Script>]>>,
+                {
+                    let (v0, v1, v2, v3, v4, v5, v6) = self.0;
+                    LevelBuilder((v0, v1, v2, v3, v4, v5, v6, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5, T6, T7> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7)> {
+                /// Setter for the [`zones` field](Level#structfield.zones).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn zones<T8>(
+                    self,
+                    value: T8,
+                ) -> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8)>
+                where
+                    T8: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Zone>]>>,
+                {
+                    let (v0, v1, v2, v3, v4, v5, v6, v7) = self.0;
+                    LevelBuilder((v0, v1, v2, v3, v4, v5, v6, v7, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5, T6, T7, T8> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8)> {
+                /// Setter for the [`teleports` field](Level#structfield.teleports).
+                #[inline]
+                #[allow(clippy::type_complexity)]
+                pub fn teleports<T9>(
+                    self,
+                    value: T9,
+                ) -> LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)>
+                where
+                    T9: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Teleport>]>>,
+                {
+                    let (v0, v1, v2, v3, v4, v5, v6, v7, v8) = self.0;
+                    LevelBuilder((v0, v1, v2, v3, v4, v5, v6, v7, v8, value))
+                }
+            }
+
+            impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9>
+                LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)>
+            {
+                /// Finish writing the builder to get an [Offset](::planus::Offset) to a serialized [Level].
+                #[inline]
+                pub fn finish(self, builder: &mut ::planus::Builder) -> ::planus::Offset<Level>
+                where
+                    Self: ::planus::WriteAsOffset<Level>,
+                {
+                    ::planus::WriteAsOffset::prepare(&self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAs<::planus::Offset<str>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Palette>]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Texture>]>>,
+                    T4: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Pen>]>>,
+                    T5: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Mesh>]>>,
+                    T6: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                    T7: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Script>]>>,
+                    T8: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Zone>]>>,
+                    T9: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Teleport>]>>,
+                > ::planus::WriteAs<::planus::Offset<Level>>
+                for LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)>
+            {
+                type Prepared = ::planus::Offset<Level>;
+
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Level> {
+                    ::planus::WriteAsOffset::prepare(self, builder)
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAs<::planus::Offset<str>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Palette>]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Texture>]>>,
+                    T4: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Pen>]>>,
+                    T5: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Mesh>]>>,
+                    T6: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                    T7: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Script>]>>,
+                    T8: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Zone>]>>,
+                    T9: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Teleport>]>>,
+                > ::planus::WriteAsOptional<::planus::Offset<Level>>
+                for LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)>
+            {
+                type Prepared = ::planus::Offset<Level>;
+
+                #[inline]
+                fn prepare(
+                    &self,
+                    builder: &mut ::planus::Builder,
+                ) -> ::core::option::Option<::planus::Offset<Level>> {
+                    ::core::option::Option::Some(::planus::WriteAsOffset::prepare(self, builder))
+                }
+            }
+
+            impl<
+                    T0: ::planus::WriteAsDefault<u32, u32>,
+                    T1: ::planus::WriteAs<::planus::Offset<str>>,
+                    T2: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Palette>]>>,
+                    T3: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Texture>]>>,
+                    T4: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Pen>]>>,
+                    T5: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Mesh>]>>,
+                    T6: ::planus::WriteAs<::planus::Offset<[u8]>>,
+                    T7: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Script>]>>,
+                    T8: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Zone>]>>,
+                    T9: ::planus::WriteAs<::planus::Offset<[::planus::Offset<self::Teleport>]>>,
+                > ::planus::WriteAsOffset<Level>
+                for LevelBuilder<(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)>
+            {
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> ::planus::Offset<Level> {
+                    let (v0, v1, v2, v3, v4, v5, v6, v7, v8, v9) = &self.0;
+                    Level::create(builder, v0, v1, v2, v3, v4, v5, v6, v7, v8, v9)
+                }
+            }
+
+            /// Reference to a deserialized [Level].
+            #[derive(Copy, Clone)]
+            pub struct LevelRef<'a>(#[allow(dead_code)] ::planus::table_reader::Table<'a>);
+
+            impl<'a> LevelRef<'a> {
+                /// Getter for the [`version` field](Level#structfield.version).
+                #[inline]
+                pub fn version(&self) -> ::planus::Result<u32> {
+                    ::core::result::Result::Ok(self.0.access(0, "Level", "version")?.unwrap_or(0))
+                }
+
+                /// Getter for the [`name` field](Level#structfield.name).
+                #[inline]
+                pub fn name(&self) -> ::planus::Result<&'a ::core::primitive::str> {
+                    self.0.access_required(1, "Level", "name")
+                }
+
+                /// Getter for the [`palettes` field](Level#structfield.palettes).
+                #[inline]
+                pub fn palettes(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::PaletteRef<'a>>>>
+                {
+                    self.0.access_required(2, "Level", "palettes")
+                }
+
+                /// Getter for the [`textures` field](Level#structfield.textures).
+                #[inline]
+                pub fn textures(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::TextureRef<'a>>>>
+                {
+                    self.0.access_required(3, "Level", "textures")
+                }
+
+                /// Getter for the [`pens` field](Level#structfield.pens).
+                #[inline]
+                pub fn pens(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::PenRef<'a>>>>
+                {
+                    self.0.access_required(4, "Level", "pens")
+                }
+
+                /// Getter for the [`meshes` field](Level#structfield.meshes).
+                #[inline]
+                pub fn meshes(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::MeshRef<'a>>>>
+                {
+                    self.0.access_required(5, "Level", "meshes")
+                }
+
+                /// Getter for the [`script_data` field](Level#structfield.script_data).
+                #[inline]
+                pub fn script_data(&self) -> ::planus::Result<&'a [u8]> {
+                    self.0.access_required(6, "Level", "script_data")
+                }
+
+                /// Getter for the [`scripts` field](Level#structfield.scripts).
+                #[inline]
+                pub fn scripts(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::ScriptRef<'a>>>>
+                {
+                    self.0.access_required(7, "Level", "scripts")
+                }
+
+                /// Getter for the [`zones` field](Level#structfield.zones).
+                #[inline]
+                pub fn zones(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::ZoneRef<'a>>>>
+                {
+                    self.0.access_required(8, "Level", "zones")
+                }
+
+                /// Getter for the [`teleports` field](Level#structfield.teleports).
+                #[inline]
+                pub fn teleports(
+                    &self,
+                ) -> ::planus::Result<::planus::Vector<'a, ::planus::Result<self::TeleportRef<'a>>>>
+                {
+                    self.0.access_required(9, "Level", "teleports")
+                }
+            }
+
+            impl<'a> ::core::fmt::Debug for LevelRef<'a> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut f = f.debug_struct("LevelRef");
+                    f.field("version", &self.version());
+                    f.field("name", &self.name());
+                    f.field("palettes", &self.palettes());
+                    f.field("textures", &self.textures());
+                    f.field("pens", &self.pens());
+                    f.field("meshes", &self.meshes());
+                    f.field("script_data", &self.script_data());
+                    f.field("scripts", &self.scripts());
+                    f.field("zones", &self.zones());
+                    f.field("teleports", &self.teleports());
+                    f.finish()
+                }
+            }
+
+            impl<'a> ::core::convert::TryFrom<LevelRef<'a>> for Level {
+                type Error = ::planus::Error;
+
+                #[allow(unreachable_code)]
+                fn try_from(value: LevelRef<'a>) -> ::planus::Result<Self> {
+                    ::core::result::Result::Ok(Self {
+                        version: ::core::convert::TryInto::try_into(value.version()?)?,
+                        name: ::core::convert::Into::into(value.name()?),
+                        palettes: value.palettes()?.to_vec_result()?,
+                        textures: value.textures()?.to_vec_result()?,
+                        pens: value.pens()?.to_vec_result()?,
+                        meshes: value.meshes()?.to_vec_result()?,
+                        script_data: value.script_data()?.to_vec(),
+                        scripts: value.scripts()?.to_vec_result()?,
+                        zones: value.zones()?.to_vec_result()?,
+                        teleports: value.teleports()?.to_vec_result()?,
+                    })
+                }
+            }
+
+            impl<'a> ::planus::TableRead<'a> for LevelRef<'a> {
+                #[inline]
+                fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::core::result::Result<Self, ::planus::errors::ErrorKind> {
+                    ::core::result::Result::Ok(Self(::planus::table_reader::Table::from_buffer(
+                        buffer, offset,
+                    )?))
+                }
+            }
+
+            impl<'a> ::planus::VectorReadInner<'a> for LevelRef<'a> {
+                type Error = ::planus::Error;
+                const STRIDE: usize = 4;
+
+                unsafe fn from_buffer(
+                    buffer: ::planus::SliceWithStartOffset<'a>,
+                    offset: usize,
+                ) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(buffer, offset).map_err(|error_kind| {
+                        error_kind.with_error_location(
+                            "[LevelRef]",
+                            "get",
+                            buffer.offset_from_start,
+                        )
+                    })
+                }
+            }
+
+            /// # Safety
+            /// The planus compiler generates implementations that initialize
+            /// the bytes in `write_values`.
+            unsafe impl ::planus::VectorWrite<::planus::Offset<Level>> for Level {
+                type Value = ::planus::Offset<Level>;
+                const STRIDE: usize = 4;
+                #[inline]
+                fn prepare(&self, builder: &mut ::planus::Builder) -> Self::Value {
+                    ::planus::WriteAs::prepare(self, builder)
+                }
+
+                #[inline]
+                unsafe fn write_values(
+                    values: &[::planus::Offset<Level>],
+                    bytes: *mut ::core::mem::MaybeUninit<u8>,
+                    buffer_position: u32,
+                ) {
+                    let bytes = bytes as *mut [::core::mem::MaybeUninit<u8>; 4];
+                    for (i, v) in ::core::iter::Iterator::enumerate(values.iter()) {
+                        ::planus::WriteAsPrimitive::write(
+                            v,
+                            ::planus::Cursor::new(unsafe { &mut *bytes.add(i) }),
+                            buffer_position - (Self::STRIDE * i) as u32,
+                        );
+                    }
+                }
+            }
+
+            impl<'a> ::planus::ReadAsRoot<'a> for LevelRef<'a> {
+                fn read_as_root(slice: &'a [u8]) -> ::planus::Result<Self> {
+                    ::planus::TableRead::from_buffer(
+                        ::planus::SliceWithStartOffset {
+                            buffer: slice,
+                            offset_from_start: 0,
+                        },
+                        0,
+                    )
+                    .map_err(|error_kind| {
+                        error_kind.with_error_location("[LevelRef]", "read_as_root", 0)
+                    })
+                }
+            }
+        }
+    }
+}
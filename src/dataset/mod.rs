@@ -0,0 +1,371 @@
+//! A whole TRAVERSE level in one FlatBuffers file (`Level.mdkd`, saved with `--dataset`),
+//! for tools that want the parsed data without the thousands of files the export makes.
+//!
+//! The schema is `schema/level.fbs`, so readers for other languages can be generated with `flatc`.
+//! The Rust types in this module are generated from it with [planus](https://docs.rs/planus):
+//! the owned types like [Level] are used for writing, and the `*Ref` types like [LevelRef]
+//! read straight from the file without copying it (see [read_dataset]).
+//!
+//! Textures are stored as palette indices along with every arena's palette,
+//! and positions are in the same (Y up) space as the exported meshes.
+
+use std::io;
+
+use planus::ReadAsRoot;
+
+use crate::data_formats;
+use crate::data_formats::cmi_bytecode::CmiFlow;
+use crate::data_formats::mesh::{MeshGeo, MeshType};
+use crate::file_formats::mti::MtiFlags;
+use crate::file_formats::{Cmi, Dti, DtiEntityData};
+
+#[rustfmt::skip]
+mod generated;
+pub use generated::mdk::dataset::*;
+
+/// The FlatBuffers file identifier of datasets
+pub const DATASET_IDENTIFIER: [u8; 4] = *b"MDKL";
+/// Stored in [Level::version], and only bumped when a field is removed or changes meaning
+pub const DATASET_VERSION: u32 = 1;
+
+/// Reads a dataset file without copying it.
+///
+/// Fails with [io::ErrorKind::InvalidData] if the data isn't a dataset or is from a newer version.
+/// Use `Level::try_from` on the result to copy it into the owned types.
+pub fn read_dataset(data: &[u8]) -> io::Result<LevelRef<'_>> {
+	let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+	if data.get(4..8) != Some(&DATASET_IDENTIFIER[..]) {
+		return Err(invalid("not a level dataset".into()));
+	}
+	let level =
+		LevelRef::read_as_root(data).map_err(|e| invalid(format!("invalid dataset: {e}")))?;
+	let version = level
+		.version()
+		.map_err(|e| invalid(format!("invalid dataset: {e}")))?;
+	if version > DATASET_VERSION {
+		return Err(invalid(format!("unsupported dataset version {version}")));
+	}
+	Ok(level)
+}
+
+/// Collects a level's assets into a [Level]
+pub struct DatasetBuilder {
+	level: Level,
+}
+
+impl DatasetBuilder {
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			level: Level {
+				version: DATASET_VERSION,
+				name: name.into(),
+				..Default::default()
+			},
+		}
+	}
+
+	pub fn add_palette(&mut self, arena: &str, palette: &[u8]) {
+		assert_eq!(palette.len(), 0x300, "invalid palette for {arena}");
+		self.level.palettes.push(Palette {
+			arena: arena.to_owned(),
+			colours: palette.to_vec(),
+		});
+	}
+
+	pub fn add_texture(
+		&mut self, name: &str, frames: &[data_formats::Texture], mti_flags: Option<MtiFlags>,
+	) {
+		let frames = frames
+			.iter()
+			.map(|frame| Frame {
+				width: frame.width,
+				height: frame.height,
+				x: frame.position.0,
+				y: frame.position.1,
+				pixels: frame.pixels.to_vec(),
+			})
+			.collect();
+		self.level.textures.push(Texture {
+			name: name.to_owned(),
+			mti_flags: mti_flags.map(MtiFlags::bits),
+			frames,
+		});
+	}
+
+	pub fn add_pen(&mut self, name: &str, pen: data_formats::Pen) {
+		let value = pen
+			.to_mti_value()
+			.unwrap_or_else(|| panic!("pen {name} ({pen:?}) has no mti value"));
+		self.level.pens.push(Pen {
+			name: name.to_owned(),
+			value,
+		});
+	}
+
+	pub fn add_mesh(&mut self, name: &str, mesh: &data_formats::Mesh) {
+		let parts = match &mesh.mesh_data {
+			MeshType::Single(geo) => vec![mesh_part(String::new(), crate::Vec3::default(), geo)],
+			MeshType::Multimesh { submeshes, .. } => submeshes
+				.iter()
+				.map(|sub| mesh_part(sub.name.to_string(), sub.origin, &sub.mesh_data))
+				.collect(),
+		};
+		self.level.meshes.push(Mesh {
+			name: name.to_owned(),
+			materials: mesh.materials.iter().map(|mat| mat.to_string()).collect(),
+			parts,
+			reference_points: mesh.reference_points.iter().copied().map(vec3).collect(),
+		});
+	}
+
+	/// Adds every script in the CMI, with the entities that run them
+	pub fn add_scripts(&mut self, cmi: &Cmi) {
+		let mut scripts: Vec<_> = cmi.scripts.iter().collect();
+		scripts.sort_unstable_by_key(|(offset, _)| **offset);
+		if let Some((_, script)) = scripts.first() {
+			self.level.script_data = script.data.to_vec();
+		}
+		for (&offset, script) in scripts {
+			let mut entities: Vec<String> = cmi
+				.entities
+				.iter()
+				.filter(|(_, entity)| entity.scripts.contains(&offset))
+				.map(|(name, _)| name.to_string())
+				.collect();
+			entities.sort_unstable();
+			let instructions = script
+				.instructions
+				.iter()
+				.map(|inst| Instruction {
+					offset: inst.offset,
+					size: inst.size,
+					opcode: inst.opcode,
+					name: inst.name.clone(),
+					flow: inst.flow.iter().map(flow).collect(),
+				})
+				.collect();
+			self.level.scripts.push(Script {
+				offset,
+				entities,
+				instructions,
+			});
+		}
+	}
+
+	/// Adds the DTI's zones and teleports
+	pub fn add_zones(&mut self, dti: &Dti) {
+		for arena in &dti.arenas {
+			for entity in &arena.entities {
+				let (kind, name, value) = match entity.data {
+					DtiEntityData::ArenaShowZone => (ZoneKind::ArenaShowZone, None, None),
+					DtiEntityData::Hotgen { name, value } => {
+						(ZoneKind::Hotgen, Some(name), Some(value))
+					}
+					DtiEntityData::ArenaActivateZone => (ZoneKind::ArenaActivateZone, None, None),
+					DtiEntityData::Hotpick(name) => (ZoneKind::Hotpick, Some(name), None),
+					DtiEntityData::HidingSpot => (ZoneKind::HidingSpot, None, None),
+					DtiEntityData::ArenaConnectZone(value) => {
+						(ZoneKind::ArenaConnectZone, None, Some(value))
+					}
+					DtiEntityData::Fan => (ZoneKind::Fan, None, None),
+					DtiEntityData::JumpPoint => (ZoneKind::JumpPoint, None, None),
+					DtiEntityData::Slidething => (ZoneKind::Slidething, None, None),
+				};
+				let [min, max] = entity.bbox.map(|p| p.swizzle());
+				let [min, max] = crate::Vec3::calculate_bbox(&[min, max]);
+				self.level.zones.push(Zone {
+					arena: arena.name.to_owned(),
+					id: entity.id,
+					kind,
+					min: vec3(min),
+					max: vec3(max),
+					name: name.map(str::to_owned),
+					value,
+				});
+			}
+			for teleport in &arena.teleports {
+				self.level.teleports.push(Teleport {
+					arena: arena.name.to_owned(),
+					index: teleport.index,
+					pos: vec3(teleport.pos.swizzle()),
+					angle: teleport.angle,
+				});
+			}
+		}
+	}
+
+	/// Writes the dataset file
+	pub fn build(&self) -> Vec<u8> {
+		// planus puts file identifiers before the root offset instead of after it, so add it here.
+		// Moving everything along 4 bytes keeps it aligned, since nothing in the schema needs 8
+		let mut builder = planus::Builder::new();
+		let data = builder.finish(&self.level, None);
+		let root = u32::from_le_bytes(data[..4].try_into().unwrap()) + 4;
+		let mut result = Vec::with_capacity(data.len() + 4);
+		result.extend(root.to_le_bytes());
+		result.extend(DATASET_IDENTIFIER);
+		result.extend(&data[4..]);
+		result
+	}
+}
+
+fn vec3(v: crate::Vec3) -> Vec3 {
+	Vec3 {
+		x: v.x,
+		y: v.y,
+		z: v.z,
+	}
+}
+
+fn mesh_part(name: String, origin: crate::Vec3, geo: &MeshGeo) -> MeshPart {
+	let triangles = geo
+		.tris
+		.iter()
+		.map(|tri| {
+			let material = tri
+				.material
+				.to_index()
+				.unwrap_or_else(|| panic!("{:?} is not a mesh material", tri.material));
+			let [a, b, c] = tri.indices;
+			let [uv_a, uv_b, uv_c] = tri.uvs.map(|[x, y]| Vec2 { x, y });
+			Triangle {
+				a,
+				b,
+				c,
+				material,
+				uv_a,
+				uv_b,
+				uv_c,
+				flags: tri.flags,
+			}
+		})
+		.collect();
+	MeshPart {
+		name,
+		origin: vec3(origin),
+		vertices: geo.verts.iter().copied().map(vec3).collect(),
+		triangles,
+	}
+}
+
+fn flow(flow: &CmiFlow) -> Flow {
+	let (kind, target) = match *flow {
+		CmiFlow::Call(target) => (FlowKind::Call, target),
+		CmiFlow::Goto(target) => (FlowKind::Goto, target),
+		CmiFlow::Return => (FlowKind::Return, 0),
+		CmiFlow::AlwaysReturn => (FlowKind::AlwaysReturn, 0),
+		CmiFlow::Block(target) => (FlowKind::Block, target),
+	};
+	Flow { kind, target }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Reader;
+	use crate::data_formats::Pen as MeshPen;
+	use crate::file_formats::Mto;
+	use crate::test_support::{
+		CmiBuilder, DtiBuilder, DtiZone, DtiZoneExtra, EMPTY_SCRIPT, MtiBuilder, MtoBuilder, mesh,
+	};
+
+	#[test]
+	fn test_round_trip() {
+		let pal: Vec<u8> = (0..0x300).map(|i| i as u8).collect();
+		let dti = DtiBuilder::new("LEVEL3.DTI")
+			.arena("ARENA1", 0.0)
+			.zone(DtiZone {
+				kind: 2,
+				id: 11,
+				value: 7,
+				min: crate::Vec3::new(5.0, 6.0, 7.0),
+				extra: DtiZoneExtra::Name("GUNT"),
+			})
+			.teleport(0, crate::Vec3::new(8.0, 9.0, 10.0), 1.5)
+			.palette(&pal)
+			.build();
+		let dti = Dti::parse(Reader::new(&dti));
+		let mto = MtoBuilder::new("LEVEL3O.MTO")
+			.arena("ARENA1", &[0; 336], MtiBuilder::new("ARENA1.MTI").build())
+			.mesh("DOOR", mesh(&["WALL"]))
+			.build();
+		let mto = Mto::parse(Reader::new(&mto));
+		let cmi = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "SONG1", EMPTY_SCRIPT)
+			.build();
+		let cmi = Cmi::parse(Reader::new(&cmi));
+
+		let mut builder = DatasetBuilder::new("LEVEL3");
+		builder.add_palette("ARENA1", dti.pal);
+		builder.add_texture(
+			"WALL",
+			&[data_formats::Texture::new(2, 1, &[3, 4][..])],
+			Some(MtiFlags::ANIMATED),
+		);
+		builder.add_pen("SHINY", MeshPen::from_mti_value(993));
+		builder.add_mesh("DOOR", &mto.arenas[0].meshes[0].1);
+		builder.add_scripts(&cmi);
+		builder.add_zones(&dti);
+		let data = builder.build();
+
+		let level = Level::try_from(read_dataset(&data).unwrap()).unwrap();
+		assert_eq!(level.version, DATASET_VERSION);
+		assert_eq!(level.name, "LEVEL3");
+
+		assert_eq!(level.palettes[0].arena, "ARENA1");
+		assert_eq!(level.palettes[0].colours, pal);
+
+		let texture = &level.textures[0];
+		assert_eq!(texture.mti_flags, Some(MtiFlags::ANIMATED.bits()));
+		assert_eq!((texture.frames[0].width, texture.frames[0].height), (2, 1));
+		assert_eq!(texture.frames[0].pixels, [3, 4]);
+
+		assert_eq!(level.pens[0].value, 993);
+		assert_eq!(
+			MeshPen::from_mti_value(level.pens[0].value),
+			MeshPen::Shiny(3)
+		);
+
+		let mesh = &level.meshes[0];
+		assert_eq!(mesh.materials, ["WALL"]);
+		let part = &mesh.parts[0];
+		let verts: Vec<[f32; 3]> = part.vertices.iter().map(|v| [v.x, v.y, v.z]).collect();
+		assert_eq!(verts, [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]]);
+		let tri = &part.triangles[0];
+		assert_eq!((tri.a, tri.b, tri.c, tri.material), (0, 1, 2, 0));
+
+		let script = &level.scripts[0];
+		assert_eq!(script.entities, ["ARENA1"]);
+		for inst in &script.instructions {
+			assert_eq!(level.script_data[inst.offset as usize], inst.opcode);
+		}
+		let end = script.instructions.last().unwrap();
+		assert_eq!(end.name, "Return");
+		assert_eq!(end.flow[0].kind, FlowKind::AlwaysReturn);
+
+		let zone = &level.zones[0];
+		assert_eq!((zone.kind, zone.id), (ZoneKind::Hotgen, 11));
+		assert_eq!((zone.name.as_deref(), zone.value), (Some("GUNT"), Some(7)));
+		assert_eq!([zone.min.x, zone.min.y, zone.min.z], [5.0, 7.0, -6.0]);
+		let pos = level.teleports[0].pos;
+		assert_eq!([pos.x, pos.y, pos.z], [8.0, 10.0, -9.0]);
+	}
+
+	#[test]
+	fn test_invalid() {
+		let mut data = DatasetBuilder::new("LEVEL3").build();
+		let error = |data: &[u8]| read_dataset(data).unwrap_err().to_string();
+
+		assert_eq!(error(&[]), "not a level dataset");
+		assert_eq!(error(b"12345678"), "not a level dataset");
+		data[..4].fill(0xFF);
+		assert!(error(&data).starts_with("invalid dataset:"));
+
+		let mut builder = DatasetBuilder::new("LEVEL3");
+		builder.level.version = DATASET_VERSION + 1;
+		assert_eq!(
+			error(&builder.build()),
+			format!("unsupported dataset version {}", DATASET_VERSION + 1)
+		);
+	}
+}
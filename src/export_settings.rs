@@ -0,0 +1,330 @@
+use crate::{Vec3, Vec4};
+
+/// Which axis points up in exported files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UpAxis {
+	/// GLTF, Unity, Maya
+	#[default]
+	Y,
+	/// Blender, Unreal, 3ds Max (and the game itself)
+	Z,
+}
+
+/// Which direction front-facing triangles wind in exported files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+	#[default]
+	CounterClockwise,
+	Clockwise,
+}
+
+impl UpAxis {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"y" | "Y" => UpAxis::Y,
+			"z" | "Z" => UpAxis::Z,
+			_ => return None,
+		})
+	}
+}
+impl Winding {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"ccw" => Winding::CounterClockwise,
+			"cw" => Winding::Clockwise,
+			_ => return None,
+		})
+	}
+}
+
+/// File format used for meshes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+	#[default]
+	Gltf,
+	/// Wavefront OBJ with an MTL material library
+	Obj,
+}
+
+impl MeshFormat {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"gltf" => MeshFormat::Gltf,
+			"obj" => MeshFormat::Obj,
+			_ => return None,
+		})
+	}
+}
+
+/// How level geometry is split into submeshes when exported
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MeshSplit {
+	/// One mesh, e.g. for collision
+	Flat,
+	/// By triangle ID, the parts that scripts hide, show and blow off
+	#[default]
+	ById,
+	/// A submesh per material
+	ByMaterial,
+}
+
+impl MeshSplit {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"flat" => MeshSplit::Flat,
+			"id" => MeshSplit::ById,
+			"material" => MeshSplit::ByMaterial,
+			_ => return None,
+		})
+	}
+}
+
+/// How hard to compress saved PNGs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PngCompression {
+	/// Much quicker to write, but larger files
+	Fast,
+	#[default]
+	Default,
+	Best,
+}
+
+/// Which row filter saved PNGs use before compressing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PngFilter {
+	/// Usually best for indexed images
+	None,
+	/// The encoder's default
+	#[default]
+	Sub,
+	Up,
+	Avg,
+	Paeth,
+	/// Picks the best filter for each row, which is slow
+	Adaptive,
+}
+
+impl PngFilter {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"none" => PngFilter::None,
+			"sub" => PngFilter::Sub,
+			"up" => PngFilter::Up,
+			"avg" => PngFilter::Avg,
+			"paeth" => PngFilter::Paeth,
+			"adaptive" => PngFilter::Adaptive,
+			_ => return None,
+		})
+	}
+}
+impl PngCompression {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"fast" => PngCompression::Fast,
+			"default" => PngCompression::Default,
+			"best" => PngCompression::Best,
+			_ => return None,
+		})
+	}
+}
+
+/// How colours are stored in saved (still) PNGs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PngColour {
+	/// The game's palette indices, with index 0 transparent
+	#[default]
+	Indexed,
+	/// Expanded to RGBA, for tools that don't handle indexed images
+	Rgba,
+}
+
+impl PngColour {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"indexed" => PngColour::Indexed,
+			"rgba" => PngColour::Rgba,
+			_ => return None,
+		})
+	}
+}
+
+/// File formats 2D animations are saved in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AnimFormat {
+	/// Animated PNG
+	#[default]
+	Png,
+	/// GIF with the same palette, for sharing
+	Gif,
+	Both,
+}
+
+impl AnimFormat {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"png" => AnimFormat::Png,
+			"gif" => AnimFormat::Gif,
+			"both" => AnimFormat::Both,
+			_ => return None,
+		})
+	}
+	pub fn png(self) -> bool {
+		self != AnimFormat::Gif
+	}
+	pub fn gif(self) -> bool {
+		self != AnimFormat::Png
+	}
+}
+
+/// Swatch formats to save alongside palette PNGs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteFormats {
+	/// GIMP `.gpl`
+	pub gpl: bool,
+	/// Photoshop `.act`
+	pub act: bool,
+	/// Adobe Swatch Exchange `.ase` (also loaded by Aseprite)
+	pub ase: bool,
+}
+
+/// How exported 3D animations are shrunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimCompression {
+	/// Whether to drop channels that never change, setting the node to their value instead
+	pub prune: bool,
+	/// Keyframes that are within this of the previous one (on every axis) are dropped,
+	/// or `None` to keep every frame
+	pub tolerance: Option<f32>,
+	/// Whether to store rotations as normalised 16 bit integers instead of floats
+	pub quantize: bool,
+}
+
+impl Default for AnimCompression {
+	fn default() -> Self {
+		Self {
+			prune: true,
+			tolerance: Some(0.0),
+			quantize: false,
+		}
+	}
+}
+
+/// Coordinate conventions and options used when exporting 3D data.
+///
+/// Parsed data is always stored Y-up (see [Vec3::swizzle]) and converted on export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportSettings {
+	pub up_axis: UpAxis,
+	pub scale: f32,
+	pub winding: Winding,
+	pub mesh_format: MeshFormat,
+	pub bsp_split: MeshSplit,
+	/// Number of simplified meshes to generate alongside each mesh
+	pub lod_levels: u32,
+	/// Whether to export DTI zones as translucent boxes
+	pub zone_boxes: bool,
+	/// Number of frames in the preview sheet saved alongside each 3D animation, 0 for none
+	pub anim_preview_frames: usize,
+	/// How different (mean squared RGB error) two arena palettes can be
+	/// while still sharing a copy of a texture, 0 for exact matches only
+	pub palette_tolerance: u32,
+	pub palette_formats: PaletteFormats,
+	pub png_compression: PngCompression,
+	pub png_filter: PngFilter,
+	/// Animated PNGs are always indexed
+	pub png_colour: PngColour,
+	/// Whether to write vertex colours as the raw (sRGB) palette bytes like older exports did,
+	/// instead of converting them to the linear colours GLTF expects
+	pub srgb_vertex_colours: bool,
+	pub anim_compression: AnimCompression,
+	/// Whether to add a node for each sound scripts play to the spawn scene
+	pub sound_emitters: bool,
+	/// Whether to add a mirrored copy of the geometry above shiny floors, like the game's reflections
+	pub reflections: bool,
+	/// Whether mesh outlines go on their own child node (named `{submesh}_Outline`) so they can be
+	/// hidden, instead of being a lines primitive of the submesh's mesh
+	pub outline_nodes: bool,
+	/// Whether to save CMI scripts with each instruction's bytes in hex underneath it
+	pub script_hex: bool,
+	/// Whether to save a [UvOverlay](crate::uv_overlay::UvOverlay) of each mesh texture
+	pub uv_overlays: bool,
+	/// Whether to save each TRAVERSE level as a single [dataset](crate::dataset) file
+	pub level_dataset: bool,
+	/// Whether textures use nearest filtering (keeping the pixelated look) instead of linear
+	pub nearest_filter: bool,
+	pub anim_format: AnimFormat,
+	/// Whether 3D animations move each part's points with morph targets on a single node,
+	/// instead of a translation channel on a node per point
+	pub morph_targets: bool,
+	/// The frame rate 3D animations are played at before their speed is applied
+	/// (see [Animation::frame_rate](crate::data_formats::Animation::frame_rate))
+	pub anim_fps: f32,
+}
+
+impl Default for ExportSettings {
+	fn default() -> Self {
+		Self {
+			up_axis: UpAxis::Y,
+			scale: 1.0,
+			winding: Winding::CounterClockwise,
+			mesh_format: MeshFormat::Gltf,
+			bsp_split: MeshSplit::ById,
+			lod_levels: 0,
+			zone_boxes: false,
+			anim_preview_frames: 0,
+			palette_tolerance: 0,
+			palette_formats: PaletteFormats::default(),
+			png_compression: PngCompression::Default,
+			png_filter: PngFilter::Sub,
+			png_colour: PngColour::Indexed,
+			srgb_vertex_colours: false,
+			anim_compression: AnimCompression::default(),
+			sound_emitters: false,
+			reflections: false,
+			outline_nodes: false,
+			script_hex: false,
+			uv_overlays: false,
+			level_dataset: false,
+			nearest_filter: false,
+			anim_format: AnimFormat::Png,
+			morph_targets: false,
+			anim_fps: 30.0,
+		}
+	}
+}
+
+impl ExportSettings {
+	/// Converts a parsed (Y-up) point into the export coordinate system
+	pub fn transform_point(&self, point: Vec3) -> Vec3 {
+		let point = match self.up_axis {
+			UpAxis::Y => point,
+			UpAxis::Z => Vec3::new(point.x, -point.z, point.y),
+		};
+		point * self.scale
+	}
+	pub fn transform_points(&self, points: &[Vec3]) -> Vec<Vec3> {
+		points.iter().map(|&p| self.transform_point(p)).collect()
+	}
+	/// Converts a parsed (Y-up) rotation quaternion into the export coordinate system
+	pub fn transform_rotation(&self, [x, y, z, w]: Vec4) -> Vec4 {
+		match self.up_axis {
+			UpAxis::Y => [x, y, z, w],
+			UpAxis::Z => [x, -z, y, w],
+		}
+	}
+	/// Converts a parsed (Y-up) per-axis scale into the export coordinate system
+	pub fn transform_scale(&self, scale: Vec3) -> Vec3 {
+		match self.up_axis {
+			UpAxis::Y => scale,
+			UpAxis::Z => Vec3::new(scale.x, scale.z, scale.y),
+		}
+	}
+
+	/// Orders the indices of a counter-clockwise triangle to match the export winding
+	pub fn triangle<T>(&self, [i1, i2, i3]: [T; 3]) -> [T; 3] {
+		match self.winding {
+			Winding::CounterClockwise => [i1, i2, i3],
+			Winding::Clockwise => [i1, i3, i2],
+		}
+	}
+}
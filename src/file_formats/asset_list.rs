@@ -0,0 +1,317 @@
+//! Lists the named assets inside each game file, along with a short logical description
+//! (mesh sizes, texture dimensions, script instructions etc.) of each.
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::{Bni, Cmi, Dti, FileType, Fti, Lbb, Mti, Mto, Sni, mti::Material};
+use crate::Reader;
+use crate::data_formats::mesh::MeshType;
+use crate::data_formats::{Animation, Bsp, Mesh, Texture, Wav};
+
+/// Logical description of every asset in a file, keyed by asset path (eg. `Meshes/GUNTHING`).
+pub struct AssetList {
+	data_range: Range<usize>,
+	pub assets: BTreeMap<String, AssetInfo>,
+}
+
+pub struct AssetInfo {
+	pub kind: String,
+	pub name: String,
+	/// Offset of the asset's name in the file, if it was read directly from the file
+	pub offset: Option<usize>,
+	/// Most assets are a single line, scripts have one line per instruction
+	pub lines: Vec<String>,
+}
+
+impl AssetList {
+	fn new(data: &[u8]) -> Self {
+		Self {
+			data_range: data.as_ptr_range().start as usize..data.as_ptr_range().end as usize,
+			assets: BTreeMap::new(),
+		}
+	}
+	fn add(&mut self, kind: &str, name: &str, info: String) {
+		self.add_lines(kind, name, vec![info]);
+	}
+	fn add_lines(&mut self, kind: &str, name: &str, lines: Vec<String>) {
+		// names are borrowed straight from the file data where possible
+		let name_pos = name.as_ptr() as usize;
+		let offset = (!name.is_empty() && self.data_range.contains(&name_pos))
+			.then(|| name_pos - self.data_range.start);
+		let info = AssetInfo {
+			kind: kind.to_owned(),
+			name: name.to_owned(),
+			offset,
+			lines,
+		};
+		self.assets.insert(format!("{kind}/{name}"), info);
+	}
+}
+
+/// Finds all files under `root`, keyed by their (uppercased) relative path
+pub fn find_files(root: &Path) -> BTreeMap<String, PathBuf> {
+	fn visit(root: &Path, dir: &Path, files: &mut BTreeMap<String, PathBuf>) {
+		let entries = std::fs::read_dir(dir)
+			.unwrap_or_else(|e| panic!("failed to read directory {}: {e}", dir.display()));
+		for entry in entries {
+			let path = entry.unwrap().path();
+			if path.is_dir() {
+				visit(root, &path, files);
+			} else {
+				let relative = path.strip_prefix(root).unwrap();
+				let key = relative.to_string_lossy().replace('\\', "/").to_uppercase();
+				files.insert(key, path);
+			}
+		}
+	}
+	let mut files = BTreeMap::new();
+	visit(root, root, &mut files);
+	files
+}
+
+/// Lists every asset in a game file
+pub fn list_assets(file_type: FileType, data: &[u8]) -> AssetList {
+	let mut summary = AssetList::new(data);
+	let reader = Reader::new_detect_endian(data);
+	match file_type {
+		FileType::Bni => {
+			let bni = Bni::parse(reader);
+			for (name, wav) in &bni.sounds {
+				summary.add("Sounds", name, describe_wav(wav));
+			}
+			for (name, tex) in &bni.textures {
+				summary.add("Textures", name, describe_frames(std::slice::from_ref(tex)));
+			}
+			for (name, (palette, tex)) in &bni.coloured_textures {
+				let info = describe_frames(std::slice::from_ref(tex));
+				summary.add(
+					"Textures",
+					name,
+					format!("{info}, palette {}", hash(palette)),
+				);
+			}
+			for (name, frames) in &bni.animations_2d {
+				summary.add("Animations", name, describe_frames(frames));
+			}
+			for (name, anim) in &bni.animations_3d {
+				summary.add("Animations", name, describe_animation(anim));
+			}
+			for (name, mesh) in &bni.meshes {
+				summary.add("Meshes", name, describe_mesh(mesh));
+			}
+			for (name, palette) in &bni.palettes {
+				summary.add("Palettes", name, hash(palette));
+			}
+			for (name, lines) in &bni.strings {
+				summary.add("Strings", name, lines.join("\\n"));
+			}
+		}
+		FileType::Cmi => {
+			let cmi = Cmi::parse(reader);
+			for arena in &cmi.arenas {
+				let info = format!("song {}, entities {:?}", arena.song, arena.entities);
+				summary.add("Arenas", arena.name, info);
+			}
+			for (name, entity) in &cmi.entities {
+				let mesh = entity.mesh.as_ref().map(describe_mesh);
+				let info = format!(
+					"mesh {}, {} animations, {} splines, {} scripts, arenas {:?}",
+					mesh.as_deref().unwrap_or("none"),
+					entity.animations.len(),
+					entity.splines.len(),
+					entity.scripts.len(),
+					entity.arenas
+				);
+				summary.add("Entities", name, info);
+
+				// scripts are keyed by entity since their offsets move around between versions
+				for (i, offset) in entity.scripts.iter().enumerate() {
+					let script = &cmi.scripts[offset];
+					let lines = script
+						.instructions
+						.iter()
+						.map(|inst| format!("{:02X} {}", inst.opcode, inst.name))
+						.collect();
+					summary.add_lines("Scripts", &format!("{name}_{i}"), lines);
+				}
+			}
+			for (name, anim) in cmi.resolved_animations() {
+				summary.add("Animations", name, describe_animation(anim));
+			}
+		}
+		FileType::Dti => {
+			let dti = Dti::parse(reader);
+			summary.add(
+				"Player",
+				"Start",
+				format!(
+					"arena {} at {:?} angle {}",
+					dti.player_start_arena_index, dti.player_start_pos, dti.player_start_angle
+				),
+			);
+			summary.add(
+				"Textures",
+				"Sky",
+				describe_frames(std::slice::from_ref(&dti.skybox)),
+			);
+			if let Some(sky) = &dti.reflected_skybox {
+				summary.add(
+					"Textures",
+					"Reflection",
+					describe_frames(std::slice::from_ref(sky)),
+				);
+			}
+			summary.add("Palettes", "Palette", hash(dti.pal));
+			for arena in &dti.arenas {
+				for (i, entity) in arena.entities.iter().enumerate() {
+					let info = format!("{:?} {:?}", entity.data, entity.bbox);
+					summary.add(arena.name, &format!("Entity {i}"), info);
+				}
+				for (i, teleport) in arena.teleports.iter().enumerate() {
+					let info = format!("{:?} angle {}", teleport.pos, teleport.angle);
+					summary.add(arena.name, &format!("Teleport {i}"), info);
+				}
+			}
+		}
+		FileType::Fti => {
+			let fti = Fti::parse(reader);
+			summary.add(
+				"Textures",
+				"Arrow",
+				describe_frames(std::slice::from_ref(&fti.arrow)),
+			);
+			summary.add("Palettes", "Palette", hash(fti.palette));
+			if let Some(wav) = &fti.snd_push {
+				summary.add("Sounds", "SND_PUSH", describe_wav(wav));
+			}
+			summary.add("Fonts", "BIG", format!("{} letters", fti.font_big.len()));
+			summary.add("Fonts", "SML", format!("{} letters", fti.font_sml.len()));
+			summary.add("Fonts", "8", format!("{} letters", fti.font_8.len()));
+			for (name, string) in &fti.strings {
+				summary.add("Strings", name, string.replace('\n', "\\n"));
+			}
+		}
+		FileType::Lbb => {
+			let lbb = Lbb::parse(reader);
+			summary.add(
+				"Textures",
+				"Image",
+				describe_frames(std::slice::from_ref(&lbb.texture)),
+			);
+			summary.add("Palettes", "Palette", hash(lbb.palette));
+		}
+		FileType::Mti => summarise_mti(&Mti::parse(reader), "Materials", &mut summary),
+		FileType::Mto => {
+			let mto = Mto::parse(reader);
+			for arena in &mto.arenas {
+				let arena_dir = |kind: &str| format!("{}/{kind}", arena.name);
+				for (name, anim) in &arena.animations {
+					summary.add(&arena_dir("Animations"), name, describe_animation(anim));
+				}
+				for (name, mesh) in &arena.meshes {
+					summary.add(&arena_dir("Meshes"), name, describe_mesh(mesh));
+				}
+				for (name, wav) in &arena.sounds {
+					summary.add(&arena_dir("Sounds"), name, describe_wav(wav));
+				}
+				summary.add(arena.name, "Bsp", describe_bsp(&arena.bsp));
+				summary.add(arena.name, "Palette", hash(arena.palette));
+				summarise_mti(&arena.mti, &arena_dir("Materials"), &mut summary);
+			}
+		}
+		FileType::Sni => {
+			let sni = Sni::parse(reader);
+			for (name, wav) in &sni.sounds {
+				summary.add("Sounds", name, describe_wav(wav));
+			}
+			for (name, bsp) in &sni.bsps {
+				summary.add("Bsp", name, describe_bsp(bsp));
+			}
+			for (name, frames) in &sni.anims {
+				summary.add("Animations", name, describe_frames(frames));
+			}
+		}
+	}
+	summary
+}
+
+fn summarise_mti(mti: &Mti, kind: &str, summary: &mut AssetList) {
+	for (name, mat) in &mti.materials {
+		let mut info = match mat {
+			Material::Pen(pen) => format!("{pen:?}"),
+			Material::Texture(tex, _) => describe_frames(std::slice::from_ref(tex)),
+			Material::AnimatedTexture(frames, _) => describe_frames(frames),
+		};
+		if let Material::Texture(_, flags) | Material::AnimatedTexture(_, flags) = mat
+			&& flags.flags.unknown() != 0
+		{
+			info += &format!(", flags {}", flags.flags);
+		}
+		summary.add(kind, name, info);
+	}
+}
+
+/// Short stable hash, used to detect content changes without listing the content
+fn hash(data: &(impl Hash + ?Sized)) -> String {
+	let mut hasher = DefaultHasher::new();
+	data.hash(&mut hasher);
+	format!("#{:08X}", hasher.finish() as u32)
+}
+
+fn describe_frames(frames: &[Texture]) -> String {
+	let pixels: Vec<&[u8]> = frames.iter().map(|frame| &frame.pixels[..]).collect();
+	let first = &frames[0];
+	let size = format!("{}x{}", first.width, first.height);
+	if frames.len() == 1 {
+		format!("{size} {}", hash(&pixels))
+	} else {
+		format!("{} frames {size} {}", frames.len(), hash(&pixels))
+	}
+}
+
+fn describe_wav(wav: &Wav) -> String {
+	format!(
+		"{}ch {}Hz {}bit {:.2}s {}",
+		wav.num_channels,
+		wav.samples_per_second,
+		wav.bits_per_sample,
+		wav.duration_secs,
+		hash(wav.file_data)
+	)
+}
+
+fn describe_animation(anim: &Animation) -> String {
+	format!(
+		"{} parts, {} frames, speed {}",
+		anim.parts.len(),
+		anim.num_frames(),
+		anim.speed
+	)
+}
+
+fn describe_mesh(mesh: &Mesh) -> String {
+	let (num_submeshes, num_verts, num_tris) = match &mesh.mesh_data {
+		MeshType::Single(geo) => (1, geo.verts.len(), geo.tris.len()),
+		MeshType::Multimesh { submeshes, .. } => {
+			submeshes
+				.iter()
+				.fold((submeshes.len(), 0, 0), |(count, verts, tris), submesh| {
+					(
+						count,
+						verts + submesh.mesh_data.verts.len(),
+						tris + submesh.mesh_data.tris.len(),
+					)
+				})
+		}
+	};
+	format!(
+		"{num_submeshes} submeshes, {num_verts} verts, {num_tris} tris, materials {:?}",
+		mesh.materials
+	)
+}
+
+fn describe_bsp(bsp: &Bsp) -> String {
+	format!("{} planes, {}", bsp.planes.len(), describe_mesh(&bsp.mesh))
+}
@@ -1,7 +1,35 @@
-use crate::data_formats::{Animation, Mesh, Texture, Wav, image_formats};
-use crate::{OutputWriter, Reader};
+use std::borrow::Cow;
+#[cfg(feature = "export")]
+use std::path::Path;
+
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+#[cfg(feature = "export")]
+use crate::data_formats::PngImportOptions;
+use crate::data_formats::image_formats::{self, SniffedImage};
+#[cfg(feature = "export")]
+use crate::data_formats::palette::PaletteResolver;
+use crate::data_formats::{Animation, Mesh, Texture, Wav};
+use crate::file_formats::ChunkTable;
+use crate::{Reader, Warnings};
 
 /// BNI files hold random global data.
+///
+/// ```
+/// use mdk_parse::Reader;
+/// use mdk_parse::file_formats::Bni;
+///
+/// let bni = Bni::parse(Reader::new(include_bytes!("../../samples/SAMPLE.BNI")));
+/// let (name, texture) = &bni.textures[0];
+/// assert_eq!((*name, texture.width, texture.height), ("WALL", 2, 2));
+/// assert_eq!(bni.palettes[0].0, "PAL");
+///
+/// // save everything, with textures using the bni's palette
+/// # #[cfg(feature = "export")] {
+/// let mut output = mdk_parse::OutputWriter::new_in_dir(std::env::temp_dir().join("SAMPLE.BNI"));
+/// bni.save(&mut output, false);
+/// # }
+/// ```
 pub struct Bni<'a> {
 	pub sounds: Vec<(&'a str, Wav<'a>)>,
 	pub textures: Vec<(&'a str, Texture<'a>)>,
@@ -11,6 +39,8 @@ pub struct Bni<'a> {
 	pub meshes: Vec<(&'a str, Mesh<'a>)>,
 	pub palettes: Vec<(&'a str, &'a [u8])>,
 	pub strings: Vec<(&'a str, Vec<&'a str>)>,
+	/// Entries that didn't look like any known asset
+	pub warnings: Warnings<'a>,
 }
 
 impl<'a> Bni<'a> {
@@ -31,20 +61,13 @@ impl<'a> Bni<'a> {
 		let mut meshes = Vec::new();
 		let mut palettes = Vec::new();
 		let mut strings = Vec::new();
+		let mut warnings = Warnings::new(file_reader.buf());
 
-		let num_entries = file_reader.u32();
-		for entry_index in 0..num_entries {
-			let name = file_reader.str(12);
-			let offset = file_reader.u32() as usize;
-
-			let next_offset = if entry_index + 1 == num_entries {
-				file_reader.len()
-			} else {
-				file_reader.clone_at(file_reader.position() + 12).u32() as usize
-			};
-
-			// make a new reader that only points at the asset data
-			let reader = file_reader.resized(offset..next_offset);
+		let num_entries = file_reader.u32() as usize;
+		let table = ChunkTable::read(&mut file_reader, num_entries, 12);
+		for chunk in &table {
+			let name = chunk.name;
+			let reader = chunk.reader.clone();
 
 			// there's no way to tell what type each asset is, but thankfully
 			// we can just guess a bunch of kinds and it all works out
@@ -56,39 +79,26 @@ impl<'a> Bni<'a> {
 				continue;
 			}
 
-			// image
-			if let Some(texture) = image_formats::try_parse_basic_image(&mut reader.clone()) {
-				textures.push((name, texture));
-				continue;
-			}
-			if let Some(texture) = image_formats::try_parse_overlay_image(&mut reader.clone()) {
-				textures.push((name, texture));
-				continue;
-			}
-			if let Some(texture) = image_formats::try_parse_rle_image(&mut reader.clone()) {
-				textures.push((name, texture));
-				continue;
-			}
-			if let Some((pal, texture)) =
-				image_formats::try_parse_palette_image(&mut reader.clone())
-			{
-				coloured_textures.push((name, (pal, texture)));
-				continue;
-			}
-			if let Some(([lut1, lut2], texture)) =
-				image_formats::try_parse_crossfade_image(&mut reader.clone())
-			{
-				coloured_textures.push((name, (lut1, texture.clone())));
-				coloured_textures.push((name, (lut2, texture)));
-				continue;
-			}
-
-			// 2d animation
-			if let Some(mut anim) = image_formats::try_parse_animation(&mut reader.clone()) {
-				if anim.len() == 1 {
-					textures.push((name, anim.pop().unwrap()));
-				} else {
-					animations_2d.push((name, anim));
+			// images and 2d animations
+			if let Some(image) = image_formats::sniff_reader(&reader) {
+				match image {
+					SniffedImage::Basic(texture)
+					| SniffedImage::Overlay(texture)
+					| SniffedImage::Rle(texture) => textures.push((name, texture)),
+					SniffedImage::Palette(pal, texture) => {
+						coloured_textures.push((name, (pal, texture)))
+					}
+					SniffedImage::Crossfade([lut1, lut2], texture) => {
+						coloured_textures.push((name, (lut1, texture.clone())));
+						coloured_textures.push((name, (lut2, texture)));
+					}
+					SniffedImage::Animation(mut anim) => {
+						if anim.len() == 1 {
+							textures.push((name, anim.pop().unwrap()));
+						} else {
+							animations_2d.push((name, anim));
+						}
+					}
 				}
 				continue;
 			}
@@ -130,7 +140,7 @@ impl<'a> Bni<'a> {
 				continue;
 			}
 
-			eprintln!("unknown asset {name} ({} bytes)", reader.remaining_len());
+			warnings.push(format!("unknown asset {name}"), chunk.data());
 		}
 
 		Bni {
@@ -142,10 +152,25 @@ impl<'a> Bni<'a> {
 			meshes,
 			palettes,
 			strings,
+			warnings,
 		}
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter, flatten: bool) {
+		let mut palettes = PaletteResolver::new();
+		for (name, palette) in &self.palettes {
+			palettes.add(name, "", palette);
+		}
+		self.save_with_palettes(output, flatten, &mut palettes);
+	}
+
+	#[cfg(feature = "export")]
+	/// Saves everything, picking each texture's palette from `palettes`
+	/// (which should include this file's own palettes first)
+	pub fn save_with_palettes(
+		&self, output: &mut OutputWriter, flatten: bool, palettes: &mut PaletteResolver,
+	) {
 		fn save_items<T>(
 			folder_name: &str, output: &mut OutputWriter, flatten: bool, items: &[(&str, T)],
 			mut save_func: impl FnMut(&str, &T, &mut OutputWriter),
@@ -184,12 +209,6 @@ impl<'a> Bni<'a> {
 			}
 		}
 
-		let palette: Option<&[u8]> = self
-			.palettes
-			.first()
-			.map(|(_, pal)| *pal)
-			.filter(|_| self.palettes.len() == 1);
-
 		save_items(
 			"Sounds",
 			output,
@@ -203,7 +222,10 @@ impl<'a> Bni<'a> {
 			output,
 			flatten,
 			&self.textures,
-			|name, texture, output| texture.save_as(name, output, palette),
+			|name, texture, output| {
+				let palette = palettes.resolve(name, std::slice::from_ref(texture));
+				texture.save_as(name, output, palette.map(|(_, pal)| pal))
+			},
 		);
 
 		save_items(
@@ -221,7 +243,8 @@ impl<'a> Bni<'a> {
 			&self.animations_2d,
 			|name, frames, output| {
 				let fps = if name == "PICKUPS" { 2 } else { 30 }; // todo fps
-				Texture::save_animated(frames, name, fps, output, palette)
+				let palette = palettes.resolve(name, frames);
+				Texture::save_animated(frames, name, fps, output, palette.map(|(_, pal)| pal))
 			},
 		);
 
@@ -258,6 +281,212 @@ impl<'a> Bni<'a> {
 				output.write(name, "txt", strings.join("\n"));
 			},
 		);
+
+		self.warnings.save_as("Warnings", output);
+	}
+}
+
+/// The raw entries of a BNI file, for replacing assets and writing the file back.
+///
+/// Entries that aren't replaced are written back byte-for-byte.
+pub struct BniArchive<'a> {
+	/// Bytes between the entry table and the first entry
+	table_padding: &'a [u8],
+	/// What the entry offsets are aligned to (4 in the game's files, or 1 if they aren't),
+	/// which entries that change size are padded to
+	alignment: usize,
+	pub entries: Vec<(&'a str, Cow<'a, [u8]>)>,
+}
+
+impl<'a> BniArchive<'a> {
+	pub fn parse(mut file_reader: Reader<'a>) -> Self {
+		let filesize = file_reader.u32() as usize + 4;
+		assert_eq!(file_reader.len(), filesize, "filesize does not match");
+		file_reader.rebase();
+
+		let num_entries = file_reader.u32() as usize;
+		let table = ChunkTable::read(&mut file_reader, num_entries, 12);
+		assert!(table.is_sorted(), "bni entries out of order");
+
+		let table_end = file_reader.position();
+		let first_offset = table.chunks.first().map_or(table_end, |chunk| chunk.offset);
+		assert!(
+			first_offset >= table_end,
+			"bni entry overlaps the entry table"
+		);
+		let table_padding = file_reader.slice(first_offset - table_end);
+		let alignment = if table.iter().all(|chunk| chunk.offset.is_multiple_of(4)) {
+			4
+		} else {
+			1
+		};
+
+		let entries = table
+			.iter()
+			.map(|chunk| (chunk.name, Cow::Borrowed(chunk.data())))
+			.collect();
+
+		Self {
+			table_padding,
+			alignment,
+			entries,
+		}
+	}
+
+	pub fn get(&self, name: &str) -> Option<&[u8]> {
+		self.entries
+			.iter()
+			.find(|(entry_name, _)| *entry_name == name)
+			.map(|(_, data)| data.as_ref())
+	}
+
+	pub fn replace(&mut self, name: &str, data: Vec<u8>) {
+		let Some((_, entry)) = self.entries.iter_mut().find(|(n, _)| *n == name) else {
+			panic!("no entry named {name}");
+		};
+		*entry = Cow::Owned(data);
+	}
+
+	/// Whether an entry can be replaced by [Self::replace_texture]
+	pub fn can_replace_texture(&self, name: &str) -> bool {
+		self.get(name).is_some_and(|original| {
+			image_formats::try_parse_basic_image(&mut Reader::new(original)).is_some()
+				|| original.len() == 640 * 480
+		})
+	}
+
+	/// Replaces a texture, which has to be stored as a basic or raw image
+	pub fn replace_texture(&mut self, name: &str, texture: &Texture) {
+		let original = self
+			.get(name)
+			.unwrap_or_else(|| panic!("no entry named {name}"));
+		let data = if image_formats::try_parse_basic_image(&mut Reader::new(original)).is_some() {
+			image_formats::write_basic_image(texture)
+		} else if original.len() == 640 * 480 {
+			assert!(
+				texture.width == 640 && texture.height == 480,
+				"{name} must be 640x480"
+			);
+			texture.pixels.to_vec()
+		} else {
+			panic!("{name} isn't a basic or raw image, other kinds can't be replaced yet");
+		};
+		self.replace(name, data);
+	}
+
+	/// Replaces a string table, keeping the layout of the original
+	pub fn replace_strings(&mut self, name: &str, lines: &[&str]) {
+		let original = self
+			.get(name)
+			.unwrap_or_else(|| panic!("no entry named {name}"));
+		assert!(
+			try_parse_strings(&mut Reader::new(original)).is_some(),
+			"{name} isn't a string table"
+		);
+		let mut data = Vec::new();
+		if Reader::new(original).try_str(original.len()).is_some() {
+			// a single null-padded string
+			let [line] = lines else {
+				panic!("{name} only holds a single string");
+			};
+			data.extend_from_slice(line.as_bytes());
+			data.resize((line.len() + 1).next_multiple_of(4), 0);
+			assert!(data.len() <= 100, "string too long for {name}");
+		} else {
+			// fixed size strings, maybe followed by a null terminator
+			for line in lines {
+				assert!(line.len() <= 12, "{line} is longer than 12 characters");
+				data.extend_from_slice(line.as_bytes());
+				data.resize(data.len().next_multiple_of(12), 0);
+			}
+			if original.len() % 12 == 4 {
+				data.extend_from_slice(&[0; 4]);
+			}
+		}
+		self.replace(name, data);
+	}
+
+	/// The palette used when saving textures: the only palette-sized entry, if there's exactly one
+	pub fn palette(&self) -> Option<&[u8]> {
+		let mut palettes = self.entries.iter().filter(|(_, data)| data.len() == 0x300);
+		let (_, palette) = palettes.next()?;
+		palettes.next().is_none().then_some(&palette[..])
+	}
+
+	#[cfg(feature = "export")]
+	/// Replaces an entry with a PNG texture or a text file of strings.
+	///
+	/// 8-bit indexed and grayscale PNGs (as textures without a palette are saved) are used as-is,
+	/// anything else is mapped onto the BNI's palette.
+	pub fn inject_file(&mut self, name: &str, path: &Path, options: PngImportOptions) {
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+		let ext = path.extension().map(|ext| ext.to_ascii_lowercase());
+		match ext.as_ref().and_then(|ext| ext.to_str()) {
+			Some("png") => {
+				let info = png::Decoder::new(&data[..])
+					.read_info()
+					.expect("failed to read png");
+				let indexed = matches!(
+					info.info().color_type,
+					png::ColorType::Indexed | png::ColorType::Grayscale
+				) && info.info().bit_depth == png::BitDepth::Eight;
+				let texture = if indexed {
+					Texture::from_indexed_png(&data)
+				} else {
+					let palette = self.palette().unwrap_or_else(|| {
+						panic!(
+							"{} needs to be an indexed png, {name} has no single palette to map it to",
+							path.display()
+						)
+					});
+					Texture::from_png_data(&data, palette, options)
+				};
+				self.replace_texture(name, &texture);
+			}
+			Some("txt") => {
+				let text = String::from_utf8(data).expect("text isn't valid utf8");
+				let lines: Vec<&str> = text.lines().collect();
+				self.replace_strings(name, &lines);
+			}
+			_ => panic!("can't inject {}, expected a .png or .txt", path.display()),
+		}
+	}
+
+	pub fn write(&self) -> Vec<u8> {
+		let table_end = 4 + self.entries.len() * 16;
+		let mut data = Vec::new();
+		data.extend_from_slice(&[0; 4]); // filesize
+		data.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+
+		// every entry but the last is padded so the next one starts aligned
+		let num_padded = self.entries.len().saturating_sub(1);
+		let padded_len = |index: usize, entry: &[u8]| {
+			if index < num_padded {
+				entry.len().next_multiple_of(self.alignment)
+			} else {
+				entry.len()
+			}
+		};
+
+		let mut offset = table_end + self.table_padding.len();
+		for (index, (name, entry)) in self.entries.iter().enumerate() {
+			assert!(name.len() <= 12, "entry name {name} is too long");
+			let mut name_bytes = [0; 12];
+			name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+			data.extend_from_slice(&name_bytes);
+			data.extend_from_slice(&(offset as u32).to_le_bytes());
+			offset += padded_len(index, entry);
+		}
+		data.extend_from_slice(self.table_padding);
+		for (index, (_, entry)) in self.entries.iter().enumerate() {
+			data.extend_from_slice(entry);
+			data.resize(data.len() + padded_len(index, entry) - entry.len(), 0);
+		}
+
+		let filesize = (data.len() - 4) as u32;
+		data[..4].copy_from_slice(&filesize.to_le_bytes());
+		data
 	}
 }
 
@@ -282,3 +511,74 @@ fn try_parse_strings<'a>(reader: &mut Reader<'a>) -> Option<Vec<&'a str>> {
 
 	Some(result)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::BniBuilder;
+
+	#[test]
+	fn test_inject() {
+		let data = BniBuilder::new()
+			.entry(
+				"TEX",
+				&image_formats::write_basic_image(&Texture::new(2, 1, vec![1, 2])),
+			)
+			.entry("PAL", &[7; 0x300])
+			.entry(
+				"NAMES",
+				b"ABC\0\0\0\0\0\0\0\0\0DEF\0\0\0\0\0\0\0\0\0\0\0\0\0",
+			)
+			.build();
+
+		// untouched files are written back as-is
+		let archive = BniArchive::parse(Reader::new(&data));
+		assert_eq!(archive.write(), data);
+		assert_eq!(archive.palette(), Some(&[7; 0x300][..]));
+
+		let mut archive = BniArchive::parse(Reader::new(&data));
+		archive.replace_texture("TEX", &Texture::new(1, 3, vec![4, 5, 6]));
+		archive.replace_strings("NAMES", &["HELLO", "WORLD", "LONGER_NAME"]);
+		let data = archive.write();
+
+		let bni = Bni::parse(Reader::new(&data));
+		let (name, texture) = &bni.textures[0];
+		assert_eq!(*name, "TEX");
+		assert_eq!((texture.width, texture.height), (1, 3));
+		assert_eq!(&texture.pixels[..], [4, 5, 6]);
+		assert_eq!(bni.palettes, [("PAL", &[7; 0x300][..])]);
+		assert_eq!(
+			bni.strings,
+			[("NAMES", vec!["HELLO", "WORLD", "LONGER_NAME"])]
+		);
+	}
+
+	#[test]
+	fn test_replace_aligned() {
+		let texture = image_formats::write_basic_image(&Texture::new(2, 2, vec![1, 2, 3, 4]));
+		let data = BniBuilder::new()
+			.entry("RAW", &[9; 8])
+			.entry("TEX", &texture)
+			.entry("PAL", &[7; 0x300])
+			.build();
+		let archive = BniArchive::parse(Reader::new(&data));
+		assert_eq!(archive.alignment, 4);
+		assert_eq!(archive.write(), data);
+
+		// a shorter entry is padded so the ones after it stay aligned
+		let mut archive = BniArchive::parse(Reader::new(&data));
+		archive.replace("RAW", vec![5; 6]);
+		let data = archive.write();
+		let mut reader = Reader::new(&data[4..]);
+		let num_entries = reader.u32() as usize;
+		let table = ChunkTable::read(&mut reader, num_entries, 12);
+		assert!(table.iter().all(|chunk| chunk.offset % 4 == 0));
+
+		let archive = BniArchive::parse(Reader::new(&data));
+		assert_eq!(archive.get("RAW"), Some(&[5, 5, 5, 5, 5, 5, 0, 0][..]));
+		assert_eq!(archive.get("TEX"), Some(&texture[..]));
+		assert_eq!(archive.palette(), Some(&[7; 0x300][..]));
+		let bni = Bni::parse(Reader::new(&data));
+		assert_eq!(bni.textures[0].1.pixels[..], [1, 2, 3, 4]);
+	}
+}
@@ -0,0 +1,138 @@
+//! Remembers results computed from game files, so tools that look at the same files repeatedly
+//! (e.g. listing assets then searching them again) only parse each one once.
+//!
+//! Parsed files borrow from their data, so rather than the parsed files themselves
+//! the cache stores owned results computed from them (like [list_assets_cached]).
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::FileType;
+use super::asset_list::{AssetList, list_assets};
+
+/// Identifies a result by the contents of the file it came from and what was computed
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct CacheKey {
+	hash: u64,
+	len: usize,
+	kind: &'static str,
+	result_type: TypeId,
+}
+
+impl CacheKey {
+	fn new<T: 'static>(kind: &'static str, data: &[u8]) -> Self {
+		let mut hasher = DefaultHasher::new();
+		data.hash(&mut hasher);
+		Self {
+			hash: hasher.finish(),
+			len: data.len(),
+			kind,
+			result_type: TypeId::of::<T>(),
+		}
+	}
+}
+
+/// An opt-in cache of results keyed by file contents, safe to share between threads
+#[derive(Default)]
+pub struct ParseCache {
+	entries: Mutex<HashMap<CacheKey, Arc<dyn Any + Send + Sync>>>,
+	hits: AtomicUsize,
+	misses: AtomicUsize,
+}
+
+/// Totals from [ParseCache::stats]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseCacheStats {
+	pub entries: usize,
+	pub hits: usize,
+	pub misses: usize,
+}
+
+impl ParseCache {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the result of `compute` for `data`, computing it only the first time
+	/// the same `kind` of result is asked for from the same contents.
+	///
+	/// `compute` runs without the cache locked, so it can use the cache itself.
+	pub fn get_or_insert_with<T: Any + Send + Sync>(
+		&self, kind: &'static str, data: &[u8], compute: impl FnOnce(&[u8]) -> T,
+	) -> Arc<T> {
+		let key = CacheKey::new::<T>(kind, data);
+		if let Some(entry) = self.entries.lock().unwrap().get(&key) {
+			self.hits.fetch_add(1, Ordering::Relaxed);
+			return entry.clone().downcast().unwrap();
+		}
+		self.misses.fetch_add(1, Ordering::Relaxed);
+		let result = Arc::new(compute(data));
+		// another thread may have computed it in the meantime, keep whichever was first
+		let entry = self
+			.entries
+			.lock()
+			.unwrap()
+			.entry(key)
+			.or_insert(result)
+			.clone();
+		entry.downcast().unwrap()
+	}
+
+	pub fn stats(&self) -> ParseCacheStats {
+		ParseCacheStats {
+			entries: self.entries.lock().unwrap().len(),
+			hits: self.hits.load(Ordering::Relaxed),
+			misses: self.misses.load(Ordering::Relaxed),
+		}
+	}
+
+	/// Forgets every result
+	pub fn clear(&mut self) {
+		self.entries.get_mut().unwrap().clear();
+	}
+}
+
+/// Lists the assets in a game file, reusing the list from any earlier file with the same contents
+pub fn list_assets_cached(cache: &ParseCache, file_type: FileType, data: &[u8]) -> Arc<AssetList> {
+	let kind = match file_type {
+		FileType::Bni => "assets/bni",
+		FileType::Cmi => "assets/cmi",
+		FileType::Dti => "assets/dti",
+		FileType::Fti => "assets/fti",
+		FileType::Lbb => "assets/lbb",
+		FileType::Mti => "assets/mti",
+		FileType::Mto => "assets/mto",
+		FileType::Sni => "assets/sni",
+	};
+	cache.get_or_insert_with(kind, data, |data| list_assets(file_type, data))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_cache() {
+		let cache = ParseCache::new();
+		let data = [1u8, 2, 3];
+		let copy = data;
+		let len = cache.get_or_insert_with("len", &data, |data| data.len());
+		let cached = cache.get_or_insert_with("len", &copy, |_| unreachable!());
+		assert!(Arc::ptr_eq(&len, &cached));
+		// different kinds and types are cached separately
+		let sum = cache.get_or_insert_with("sum", &data, |data| data.iter().sum::<u8>() as usize);
+		assert_eq!((*len, *sum), (3, 6));
+		let text = cache.get_or_insert_with("len", &data, |data| format!("{}", data.len()));
+		assert_eq!(*text, "3");
+		assert_eq!(
+			cache.stats(),
+			ParseCacheStats {
+				entries: 3,
+				hits: 1,
+				misses: 3
+			}
+		);
+	}
+}
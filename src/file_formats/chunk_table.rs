@@ -0,0 +1,238 @@
+//! The tables of named entries at the start of the container formats (BNI, SNI, MTO, MTI).
+use std::ops::Range;
+
+use crate::Reader;
+
+/// A named range of a container's data
+#[derive(Clone)]
+pub struct Chunk<'a> {
+	pub name: &'a str,
+	/// Offset from the start of the container's data
+	pub offset: usize,
+	pub size: usize,
+	/// Reads just this chunk's data
+	pub reader: Reader<'a>,
+}
+
+impl<'a> Chunk<'a> {
+	pub fn range(&self) -> Range<usize> {
+		self.offset..self.offset + self.size
+	}
+	pub fn data(&self) -> &'a [u8] {
+		self.reader.buf()
+	}
+}
+
+/// A container's table of contents, in table order
+pub struct ChunkTable<'a> {
+	pub chunks: Vec<Chunk<'a>>,
+}
+
+impl<'a> ChunkTable<'a> {
+	/// Creates a table from `(name, offset, size)` entries into `data`.
+	///
+	/// Entries without a size run until the next chunk in the data (the table doesn't need to be sorted),
+	/// or the end of `data` for the last one.
+	pub fn new(
+		data: &Reader<'a>, entries: impl IntoIterator<Item = (&'a str, usize, Option<usize>)>,
+	) -> Self {
+		let entries: Vec<_> = entries.into_iter().collect();
+		let mut starts: Vec<usize> = entries.iter().map(|&(_, offset, _)| offset).collect();
+		starts.sort_unstable();
+		starts.dedup();
+
+		let chunks = entries
+			.into_iter()
+			.map(|(name, offset, size)| {
+				let size = size.unwrap_or_else(|| {
+					let next = starts.partition_point(|&start| start <= offset);
+					let end = starts.get(next).copied().unwrap_or(data.len());
+					end.saturating_sub(offset)
+				});
+				assert!(
+					offset
+						.checked_add(size)
+						.is_some_and(|end| end <= data.len()),
+					"chunk {name} ({offset}+{size}) is outside the file ({} bytes)",
+					data.len()
+				);
+				Chunk {
+					name,
+					offset,
+					size,
+					reader: data.resized(offset..offset + size),
+				}
+			})
+			.collect();
+		Self { chunks }
+	}
+
+	/// Reads `count` entries of a `name_len` byte name followed by a u32 offset,
+	/// where every chunk runs until the next entry's offset (or the end of the data for the last one).
+	///
+	/// Unlike [ChunkTable::new] this goes by table order like the game does,
+	/// so entries sharing an offset with the next one are empty and an unsorted table panics.
+	pub fn read(reader: &mut Reader<'a>, count: usize, name_len: usize) -> Self {
+		let entries: Vec<(&str, usize)> = (0..count)
+			.map(|_| (reader.str(name_len), reader.u32() as usize))
+			.collect();
+		let ends = entries
+			.iter()
+			.skip(1)
+			.map(|&(_, offset)| offset)
+			.chain([reader.len()]);
+		let sized: Vec<_> = entries
+			.iter()
+			.zip(ends)
+			.map(|(&(name, offset), end)| {
+				let size = end.checked_sub(offset).unwrap_or_else(|| {
+					panic!("chunk {name} ({offset}) starts after the next entry ({end})")
+				});
+				(name, offset, Some(size))
+			})
+			.collect();
+		Self::new(reader, sized)
+	}
+
+	pub fn len(&self) -> usize {
+		self.chunks.len()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.chunks.is_empty()
+	}
+	pub fn iter(&self) -> std::slice::Iter<'_, Chunk<'a>> {
+		self.chunks.iter()
+	}
+	pub fn get(&self, name: &str) -> Option<&Chunk<'a>> {
+		self.chunks.iter().find(|chunk| chunk.name == name)
+	}
+
+	/// Whether the chunks are stored in table order
+	pub fn is_sorted(&self) -> bool {
+		self.chunks.is_sorted_by_key(|chunk| chunk.offset)
+	}
+	/// The end of the last chunk in the data
+	pub fn data_end(&self) -> usize {
+		self.chunks
+			.iter()
+			.map(|chunk| chunk.offset + chunk.size)
+			.max()
+			.unwrap_or(0)
+	}
+
+	/// Every pair of chunks (as table indices) that share some of their data.
+	///
+	/// Entries for exactly the same data are aliases rather than overlaps.
+	pub fn overlaps(&self) -> Vec<(usize, usize)> {
+		let mut order: Vec<usize> = (0..self.chunks.len())
+			.filter(|&i| self.chunks[i].size != 0)
+			.collect();
+		order.sort_by_key(|&i| self.chunks[i].offset);
+
+		let mut result = Vec::new();
+		let mut open: Vec<usize> = Vec::new();
+		for i in order {
+			let chunk = &self.chunks[i];
+			open.retain(|&other| self.chunks[other].range().end > chunk.offset);
+			result.extend(
+				open.iter()
+					.filter(|&&other| self.chunks[other].range() != chunk.range())
+					.map(|&other| (other.min(i), other.max(i))),
+			);
+			open.push(i);
+		}
+		result
+	}
+	/// Panics if any chunks overlap
+	pub fn validate(&self) {
+		if let Some(&(a, b)) = self.overlaps().first() {
+			let (a, b) = (&self.chunks[a], &self.chunks[b]);
+			panic!(
+				"chunks {} ({:?}) and {} ({:?}) overlap",
+				a.name,
+				a.range(),
+				b.name,
+				b.range()
+			);
+		}
+	}
+}
+
+impl<'a, 'b> IntoIterator for &'b ChunkTable<'a> {
+	type Item = &'b Chunk<'a>;
+	type IntoIter = std::slice::Iter<'b, Chunk<'a>>;
+	fn into_iter(self) -> Self::IntoIter {
+		self.chunks.iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_chunk_table() {
+		let data = [0u8; 32];
+		let reader = Reader::new(&data);
+
+		// unsorted, with a shared offset and a sized entry
+		let table = ChunkTable::new(
+			&reader,
+			[
+				("C", 20, None),
+				("A", 4, None),
+				("B", 12, Some(4)),
+				("A2", 4, None),
+			],
+		);
+		let ranges: Vec<_> = table.iter().map(Chunk::range).collect();
+		assert_eq!(ranges, [20..32, 4..12, 12..16, 4..12]);
+		assert_eq!(table.get("C").unwrap().data().len(), 12);
+		assert!(!table.is_sorted());
+		assert_eq!(table.data_end(), 32);
+		assert!(table.overlaps().is_empty());
+
+		let table = ChunkTable::new(
+			&reader,
+			[("A", 0, Some(8)), ("B", 4, Some(8)), ("C", 8, None)],
+		);
+		assert_eq!(table.overlaps(), [(0, 1), (1, 2)]);
+		let table = ChunkTable::new(&reader, [("A", 0, Some(8)), ("B", 8, None)]);
+		table.validate();
+	}
+
+	#[test]
+	fn test_read() {
+		let mut data = Vec::new();
+		for (name, offset) in [("A", 40), ("A2", 40), ("B", 44)] {
+			data.extend(format!("{name:\0<4}").bytes());
+			data.extend((offset as u32).to_le_bytes());
+		}
+		data.resize(48, 0);
+		let mut reader = Reader::new(&data);
+		let table = ChunkTable::read(&mut reader, 3, 4);
+		assert_eq!(reader.position(), 24);
+		let ranges: Vec<_> = table.iter().map(Chunk::range).collect();
+		// the alias runs until the next entry, not the next offset
+		assert_eq!(ranges, [40..40, 40..44, 44..48]);
+	}
+
+	#[test]
+	#[should_panic = "chunk B (44) starts after the next entry (40)"]
+	fn test_read_unsorted() {
+		let mut data = Vec::new();
+		for (name, offset) in [("A", 24u32), ("B", 44), ("C", 40)] {
+			data.extend(format!("{name:\0<4}").bytes());
+			data.extend(offset.to_le_bytes());
+		}
+		data.resize(48, 0);
+		ChunkTable::read(&mut Reader::new(&data), 3, 4);
+	}
+
+	#[test]
+	#[should_panic = "outside the file"]
+	fn test_chunk_out_of_bounds() {
+		let data = [0u8; 8];
+		ChunkTable::new(&Reader::new(&data), [("A", 4, Some(8))]);
+	}
+}
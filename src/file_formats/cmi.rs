@@ -1,19 +1,50 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
 
-use crate::data_formats::cmi_bytecode::CmiCallOrigin;
+#[cfg(feature = "export")]
+use serde::Serialize;
+
+#[cfg(feature = "export")]
+use crate::data_formats::cmi_bytecode::CmiSpawn;
+use crate::data_formats::cmi_bytecode::{CmiCallOrigin, CmiSymbols};
+#[cfg(feature = "export")]
+use crate::data_formats::cmi_trace::CmiTracer;
+use crate::data_formats::mesh::{PartUsage, ReferencePointUsage};
 use crate::data_formats::{Animation, Mesh, Spline, cmi_bytecode};
-use crate::{OutputWriter, Reader};
+#[cfg(feature = "export")]
+use crate::{ExportSettings, MeshFormat, OutputWriter, Vec3, gltf};
+use crate::{Name, Reader, Warnings};
 
 /// CMI files contain all the gameplay data in the form of their custom scripting bytecode
 /// as well as some custom assets.
 ///
 /// See [cmi_bytecode]
+///
+/// ```
+/// use mdk_parse::Reader;
+/// use mdk_parse::file_formats::Cmi;
+///
+/// let cmi = Cmi::parse(Reader::new(include_bytes!("../../samples/SAMPLE.CMI")));
+/// assert_eq!(cmi.arenas[0].name, "ARENA1");
+/// assert_eq!(cmi.arenas[0].song, "SONG1");
+/// // every entity's scripts, decoded into a readable summary
+/// for &offset in &cmi.entities["GUNT"].scripts {
+///     println!("{}", cmi.scripts[&offset].summary);
+/// }
+///
+/// # #[cfg(feature = "export")] {
+/// let mut output = mdk_parse::OutputWriter::new_in_dir(std::env::temp_dir().join("SAMPLE.CMI"));
+/// cmi.save_scripts(&mut output);
+/// # }
+/// ```
 #[derive(Default)]
 pub struct Cmi<'a> {
 	pub filename: &'a str,
 	pub arenas: Vec<CmiArena<'a>>,
 	pub animations: HashMap<u32, Animation<'a>>,
+	/// Resolved name of each animation in `animations`
+	pub animation_names: HashMap<u32, String>,
 	pub splines: HashMap<u32, Spline>,
 	pub scripts: HashMap<u32, cmi_bytecode::CmiScript<'a>>,
 	pub entities: HashMap<&'a str, CmiEntity<'a>>,
@@ -36,8 +67,51 @@ pub struct CmiEntity<'a> {
 	pub arenas: Vec<&'a str>,
 }
 
+/// Which entities [Cmi::save_filtered] saves, to look at a few of them without saving everything
+#[cfg(feature = "export")]
+#[derive(Debug, Default, Clone)]
+pub struct CmiFilter {
+	/// Entity name globs (with `*` and `?`, ignoring case), empty for every entity
+	pub entities: Vec<String>,
+	/// Arena names (ignoring case), empty for every arena
+	pub arenas: Vec<String>,
+}
+
+#[cfg(feature = "export")]
+impl CmiFilter {
+	pub fn is_empty(&self) -> bool {
+		self.entities.is_empty() && self.arenas.is_empty()
+	}
+	/// Whether the entity's name matches one of the globs and it's in one of the arenas
+	pub fn matches(&self, name: &str, entity: &CmiEntity) -> bool {
+		let name = name.to_ascii_uppercase();
+		let name_matches = self.entities.is_empty()
+			|| self.entities.iter().any(|glob| {
+				super::glob_matches(glob.to_ascii_uppercase().as_bytes(), name.as_bytes())
+			});
+		name_matches
+			&& (self.arenas.is_empty()
+				|| entity.arenas.iter().any(|arena| self.matches_arena(arena)))
+	}
+	pub fn matches_arena(&self, arena: &str) -> bool {
+		self.arenas.is_empty() || self.arenas.iter().any(|a| a.eq_ignore_ascii_case(arena))
+	}
+}
+
+/// A script that follows a spline, see [Cmi::spline_users]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SplineUser<'a> {
+	pub entity: &'a str,
+	pub script_offset: u32,
+}
+
 impl<'a> Cmi<'a> {
-	pub fn parse(mut reader: Reader<'a>) -> Self {
+	pub fn parse(reader: Reader<'a>) -> Self {
+		Self::parse_with_symbols(reader, &CmiSymbols::default())
+	}
+
+	/// Parses the file, naming script variables and flags from `symbols` in the summaries
+	pub fn parse_with_symbols(mut reader: Reader<'a>, symbols: &CmiSymbols) -> Self {
 		let filesize = reader.u32() as usize;
 		assert_eq!(reader.len(), filesize + 4, "filesize does not match");
 		reader.rebase();
@@ -167,8 +241,10 @@ impl<'a> Cmi<'a> {
 		while let Some((target_offset, origin)) = scripts.pop() {
 			let script = result.scripts.entry(target_offset).or_insert_with(|| {
 				// new script
-				let script =
-					cmi_bytecode::CmiScript::parse(reader.clone_at(target_offset as usize));
+				let script = cmi_bytecode::CmiScript::parse_with_symbols(
+					reader.clone_at(target_offset as usize),
+					symbols,
+				);
 
 				scripts.extend(script.called_scripts.iter().map(|s| {
 					(
@@ -241,19 +317,313 @@ impl<'a> Cmi<'a> {
 
 			entity.scripts.sort_unstable();
 			entity.scripts.dedup();
+
+			// label mesh reference points with the scripts that use them
+			if let Some(mesh) = &mut entity.mesh {
+				for &script_offset in &entity.scripts {
+					for point_ref in &result.scripts[&script_offset].point_refs {
+						let index = point_ref.index as usize;
+						if index >= mesh.reference_points.len() {
+							continue;
+						}
+						let usage = ReferencePointUsage {
+							action: point_ref.action,
+							target: point_ref.target,
+							script_offset,
+						};
+						let usages = mesh.reference_point_usages.entry(index).or_default();
+						if !usages.contains(&usage) {
+							usages.push(usage);
+						}
+					}
+				}
+				label_mesh_parts(mesh, &result.scripts, &entity.scripts);
+			}
+		}
+
+		// name animations after the first entity that uses them so they're only exported once
+		let mut entity_names: Vec<&str> = result.entities.keys().copied().collect();
+		entity_names.sort_unstable();
+		for entity_name in entity_names {
+			for &anim_offset in &result.entities[entity_name].animations {
+				result
+					.animation_names
+					.entry(anim_offset)
+					.or_insert_with(|| format!("{entity_name}_{anim_offset:06X}"));
+			}
+		}
+
+		result
+	}
+
+	/// Labels the parts of a mesh stored outside the CMI (e.g. an arena's level geometry)
+	/// with the entity's scripts that use them
+	pub fn label_mesh_parts(&self, entity_name: &str, mesh: &mut Mesh) {
+		if let Some(entity) = self.entities.get(entity_name) {
+			label_mesh_parts(mesh, &self.scripts, &entity.scripts);
+		}
+	}
+
+	/// Renames any animations that are identical to one of the given named animations
+	/// (e.g. ones defined in the level's MTO).
+	pub fn resolve_animation_names<'n>(
+		&mut self, named: impl IntoIterator<Item = (&'n str, &'n Animation<'n>)>,
+	) {
+		for (name, named_anim) in named {
+			for (offset, anim) in &self.animations {
+				if anim == named_anim {
+					self.animation_names.insert(*offset, name.to_owned());
+				}
+			}
 		}
+	}
+
+	/// All animations by their resolved name
+	pub fn resolved_animations(&self) -> BTreeMap<&str, &Animation<'a>> {
+		self.animations
+			.iter()
+			.map(|(offset, anim)| (self.animation_names[offset].as_str(), anim))
+			.collect()
+	}
 
+	/// The entities and scripts that follow each spline
+	pub fn spline_users(&self) -> BTreeMap<u32, Vec<SplineUser<'a>>> {
+		let mut result: BTreeMap<u32, Vec<SplineUser>> = BTreeMap::new();
+		for (&entity, cmi_entity) in &self.entities {
+			for &script_offset in &cmi_entity.scripts {
+				for &path_offset in &self.scripts[&script_offset].path_offsets {
+					result.entry(path_offset).or_default().push(SplineUser {
+						entity,
+						script_offset,
+					});
+				}
+			}
+		}
+		for users in result.values_mut() {
+			users.sort_unstable();
+			users.dedup();
+		}
 		result
 	}
 
+	/// Names a spline after its offset and the entities that follow it
+	#[cfg(feature = "export")]
+	fn spline_filename(offset: u32, users: &[SplineUser]) -> String {
+		let mut name = format!("{offset:06X}");
+		let mut entities: Vec<&str> = users.iter().map(|user| user.entity).collect();
+		entities.dedup(); // users are sorted by entity
+		for entity in entities {
+			write!(name, " {entity}").unwrap();
+		}
+		name
+	}
+
+	/// Lists every spline with the scripts that follow it
+	pub fn spline_report(&self) -> String {
+		let mut report = String::from("Spline\tPoints\tUsed by\n");
+		for (offset, users) in self.spline_users() {
+			write!(
+				report,
+				"{offset:06X}\t{}\t",
+				self.splines[&offset].points.len()
+			)
+			.unwrap();
+			for (i, user) in users.iter().enumerate() {
+				if i != 0 {
+					report.push_str(", ");
+				}
+				write!(report, "{} ({:06X})", user.entity, user.script_offset).unwrap();
+			}
+			report.push('\n');
+		}
+		report
+	}
+
+	#[cfg(feature = "export")]
+	/// Saves an entity's mesh with the paths it follows embedded,
+	/// or just the mesh if meshes aren't exported to GLTF
+	fn save_entity_gltf(
+		&self, entity_name: &str, entity: &CmiEntity,
+		spline_users: &BTreeMap<u32, Vec<SplineUser>>, output: &mut OutputWriter,
+	) {
+		let settings = *output.export_settings();
+		if settings.mesh_format != MeshFormat::Gltf || entity.splines.is_empty() {
+			if let Some(mesh) = &entity.mesh {
+				mesh.save_as(entity_name, output);
+			}
+			return;
+		}
+
+		let mut gltf = gltf::Gltf::with_settings(entity_name.to_owned(), settings);
+		let root = gltf.get_root_node();
+		if let Some(mesh) = &entity.mesh {
+			mesh.add_to_gltf(&mut gltf, entity_name, Some(root));
+		}
+		let paths = gltf.create_base_node("Paths".to_owned(), None);
+		for spline_offset in &entity.splines {
+			let node = self.splines[spline_offset].add_to_gltf(
+				&mut gltf,
+				format!("{spline_offset:06X}"),
+				paths,
+			);
+			let scripts: Vec<String> = spline_users[spline_offset]
+				.iter()
+				.filter(|user| user.entity == entity_name)
+				.map(|user| format!("{:06X}", user.script_offset))
+				.collect();
+			gltf.set_node_extras(node, "scripts", scripts);
+		}
+		output.write(entity_name, "gltf", gltf.render_json().as_bytes());
+	}
+
+	#[cfg(feature = "export")]
+	/// Places the mesh of every entity spawned at a fixed position,
+	/// rotated by its spawn angle or the yaw its init script sets.
+	///
+	/// Each entity's mesh is only added once, and every spawn of it shares those meshes.
+	/// The angles are assumed to be degrees, which hasn't been checked against the game,
+	/// so they're kept in the extras as `angle` with `angle_units` saying so.
+	pub fn spawn_scene(&self, settings: ExportSettings) -> Option<gltf::Gltf> {
+		let spawns: BTreeMap<u32, &[CmiSpawn]> = self
+			.scripts
+			.iter()
+			.filter(|(_, script)| !script.spawns.is_empty())
+			.map(|(&offset, script)| (offset, &script.spawns[..]))
+			.collect();
+		if spawns.is_empty() {
+			return None;
+		}
+
+		let mut gltf = gltf::Gltf::with_settings(format!("{} Spawns", self.filename), settings);
+		// the first spawn's copy of each entity's mesh
+		let mut entity_meshes: HashMap<&str, gltf::NodeIndex> = HashMap::new();
+		for (script_offset, spawns) in spawns {
+			for spawn in spawns {
+				let node =
+					gltf.create_base_node(format!("{} ({script_offset:06X})", spawn.name), None);
+				gltf.set_node_position(node, spawn.position);
+				gltf.set_node_extras(node, "script", format!("{script_offset:06X}"));
+				if !spawn.arena.is_empty() {
+					gltf.set_node_extras(node, "arena", spawn.arena);
+				}
+
+				let init_yaw = self
+					.scripts
+					.get(&spawn.init_offset)
+					.and_then(|script| script.yaw)
+					.map(f32::from);
+				let angle = spawn.angle.or(init_yaw);
+				if let Some(angle) = angle {
+					// the game's yaw is around its z (up) axis, which is y once swizzled
+					let half = angle.to_radians() / 2.0;
+					gltf.set_node_rotation(node, [0.0, half.sin(), 0.0, half.cos()]);
+					gltf.set_node_extras(node, "angle", angle);
+					gltf.set_node_extras(node, "angle_units", "degrees (unverified)");
+				}
+
+				if let Some(mesh) = self.entities.get(spawn.name).and_then(|e| e.mesh.as_ref()) {
+					if let Some(&mesh_node) = entity_meshes.get(spawn.name) {
+						gltf.copy_node(mesh_node, node);
+					} else {
+						let mesh_node = mesh.add_to_gltf(&mut gltf, spawn.name, None);
+						gltf.set_node_parent(node, mesh_node);
+						entity_meshes.insert(spawn.name, mesh_node);
+					}
+				}
+				if settings.sound_emitters {
+					self.add_sound_emitters(&mut gltf, spawn.name, node);
+				}
+			}
+		}
+		if settings.sound_emitters {
+			// fixed position sounds aren't attached to anything
+			let mut emitters: Vec<(&str, Vec3)> = Vec::new();
+			for sound in self.scripts.values().flat_map(|script| &script.sounds) {
+				if let Some(position) = sound.position
+					&& !emitters.contains(&(sound.name, position))
+				{
+					emitters.push((sound.name, position));
+					let node = gltf.create_base_node(format!("Sound {}", sound.name), None);
+					gltf.set_node_position(node, position);
+					gltf.set_node_extras(node, "sound", sound.name);
+					gltf.set_node_extras(node, "sound_type", sound.sound_type);
+				}
+			}
+		}
+		Some(gltf)
+	}
+
+	#[cfg(feature = "export")]
+	/// Adds a node under a spawned entity for each sound its scripts play from the entity
+	/// or one of its mesh's reference points
+	fn add_sound_emitters(&self, gltf: &mut gltf::Gltf, entity_name: &str, node: gltf::NodeIndex) {
+		let Some(entity) = self.entities.get(entity_name) else {
+			return;
+		};
+		let mut emitters: Vec<(&str, Option<u8>)> = Vec::new();
+		for script_offset in &entity.scripts {
+			for sound in &self.scripts[script_offset].sounds {
+				if sound.position.is_some() || emitters.contains(&(sound.name, sound.point)) {
+					continue;
+				}
+				emitters.push((sound.name, sound.point));
+				let emitter = gltf.create_child_node(node, format!("Sound {}", sound.name), None);
+				if let Some(point) = sound.point {
+					let position = entity
+						.mesh
+						.as_ref()
+						.and_then(|mesh| mesh.reference_points.get(point as usize));
+					if let Some(&position) = position {
+						gltf.set_node_position(emitter, position);
+					}
+					gltf.set_node_extras(emitter, "point", point);
+				}
+				gltf.set_node_extras(emitter, "sound", sound.name);
+				gltf.set_node_extras(emitter, "sound_type", sound.sound_type);
+			}
+		}
+	}
+
+	#[cfg(feature = "export")]
+	/// Saves [Self::spawn_scene] as `Spawns.gltf`, if meshes are exported to GLTF
+	pub fn save_spawn_scene(&self, output: &mut OutputWriter) {
+		if output.export_settings().mesh_format == MeshFormat::Gltf
+			&& let Some(mut gltf) = self.spawn_scene(*output.export_settings())
+		{
+			output.write("Spawns", "gltf", gltf.render_json().as_bytes());
+		}
+	}
+
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter) {
+		self.save_filtered(output, &CmiFilter::default());
+	}
+
+	/// Like [Self::save], but only saves the arenas and entities matching the filter,
+	/// returning how many entities were saved.
+	///
+	/// The spline report and spawn scene cover the whole file, so they're only saved unfiltered.
+	#[cfg(feature = "export")]
+	pub fn save_filtered(&self, output: &mut OutputWriter, filter: &CmiFilter) -> usize {
+		let spline_users = self.spline_users();
+		if filter.is_empty() {
+			if !spline_users.is_empty() {
+				output.write("Spline Refs", "txt", self.spline_report());
+			}
+			self.save_spawn_scene(output);
+		}
+
 		let mut temp_filename = String::new();
 		let mut temp_data = String::new();
 		let mut temp_reason_list: Vec<&str> = Vec::new();
 		let mut temp_arena_list: Vec<&str> = Vec::new();
 
 		// arenas
-		for arena in &self.arenas {
+		for arena in self
+			.arenas
+			.iter()
+			.filter(|arena| filter.matches_arena(arena.name))
+		{
 			let mut output = output.push_dir(arena.name);
 			if !arena.song.is_empty() {
 				output.write("Song", "txt", arena.song);
@@ -270,21 +640,23 @@ impl<'a> Cmi<'a> {
 		}
 
 		// entities
+		let mut saved = 0;
 		for (&entity_name, entity) in self.entities.iter() {
+			if !filter.matches(entity_name, entity) {
+				continue;
+			}
+			saved += 1;
 			let mut output = output.push_dir(entity_name);
 
-			// save mesh
-			if let Some(mesh) = &entity.mesh {
-				mesh.save_as(entity_name, &mut output);
-			}
+			// save mesh and paths
+			self.save_entity_gltf(entity_name, entity, &spline_users, &mut output);
 
 			// save animations
 			if !entity.animations.is_empty() || !entity.animation_names.is_empty() {
 				let mut output = output.push_dir("Animations");
 				for anim_offset in &entity.animations {
-					temp_filename.clear();
-					write!(temp_filename, "{anim_offset:06X}").unwrap();
-					self.animations[anim_offset].save_as(&temp_filename, &mut output);
+					let name = &self.animation_names[anim_offset];
+					self.animations[anim_offset].save_as(name, &mut output);
 				}
 				if !entity.animation_names.is_empty() {
 					temp_data.clear();
@@ -300,9 +672,9 @@ impl<'a> Cmi<'a> {
 			if !entity.splines.is_empty() {
 				let mut output = output.push_dir("Splines");
 				for spline_offset in &entity.splines {
-					temp_filename.clear();
-					write!(temp_filename, "{spline_offset:06X}").unwrap();
-					self.splines[spline_offset].save_as(&temp_filename, &mut output);
+					let filename =
+						Self::spline_filename(*spline_offset, &spline_users[spline_offset]);
+					self.splines[spline_offset].save_as(&filename, &mut output);
 				}
 			}
 
@@ -379,8 +751,10 @@ impl<'a> Cmi<'a> {
 				}
 			}
 		}
+		saved
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save_scripts(&self, output: &mut OutputWriter) {
 		debug_assert!(self.validate_entity_references());
 
@@ -398,6 +772,11 @@ impl<'a> Cmi<'a> {
 		}
 		output.write("Songs", "txt", &temp_data);
 
+		let spline_users = self.spline_users();
+		if !spline_users.is_empty() {
+			output.write("Spline Refs", "txt", self.spline_report());
+		}
+
 		for (&entity_name, entity) in self.entities.iter() {
 			if entity.scripts.is_empty() {
 				assert!(entity.splines.is_empty());
@@ -473,21 +852,295 @@ impl<'a> Cmi<'a> {
 				}
 
 				temp_data.push('\n');
-				temp_data.push_str(&script.summary);
+				if output.export_settings().script_hex {
+					temp_data.push_str(&script.disassembly());
+				} else {
+					temp_data.push_str(&script.summary);
+				}
 
 				output.write(&temp_filename, "txt", &temp_data);
 
 				// save splines
 				for path_offset in &script.path_offsets {
-					temp_filename.clear();
-					write!(&mut temp_filename, "{path_offset:06X}").unwrap();
+					let filename = Self::spline_filename(*path_offset, &spline_users[path_offset]);
 					let spline = &self.splines[path_offset];
-					spline.save_as(&temp_filename, &mut output.push_dir("Splines"));
+					spline.save_as(&filename, &mut output.push_dir("Splines"));
+				}
+			}
+		}
+	}
+
+	#[cfg(feature = "export")]
+	/// Writes the arena/entity graph as `relationships.json`
+	pub fn save_relationships(&self, output: &mut OutputWriter) {
+		#[derive(Serialize)]
+		struct Relationships<'a> {
+			arenas: Vec<ArenaInfo<'a>>,
+			entities: Vec<EntityInfo<'a>>,
+		}
+		#[derive(Serialize)]
+		struct ArenaInfo<'a> {
+			name: &'a str,
+			#[serde(skip_serializing_if = "str::is_empty")]
+			song: &'a str,
+			entities: &'a [&'a str],
+		}
+		#[derive(Serialize)]
+		struct EntityInfo<'a> {
+			name: &'a str,
+			arenas: &'a [&'a str],
+			has_mesh: bool,
+			num_scripts: usize,
+			num_animations: usize,
+			num_splines: usize,
+		}
+
+		let arenas = self
+			.arenas
+			.iter()
+			.map(|arena| ArenaInfo {
+				name: arena.name,
+				song: arena.song,
+				entities: &arena.entities,
+			})
+			.collect();
+
+		let mut entities: Vec<EntityInfo> = self
+			.entities
+			.iter()
+			.map(|(&name, entity)| EntityInfo {
+				name,
+				arenas: &entity.arenas,
+				has_mesh: entity.mesh.is_some(),
+				num_scripts: entity.scripts.len(),
+				num_animations: entity.animations.len() + entity.animation_names.len(),
+				num_splines: entity.splines.len(),
+			})
+			.collect();
+		entities.sort_unstable_by_key(|entity| entity.name);
+
+		let json = serde_json::to_string_pretty(&Relationships { arenas, entities }).unwrap();
+		output.write("relationships", "json", json);
+	}
+
+	/// Every script's warnings in file order, scripts sharing a block only report it once
+	pub fn warnings(&self) -> Warnings<'a> {
+		let mut result = Warnings::default();
+		for script in self.scripts.values() {
+			result.list.extend(script.warnings.iter().cloned());
+		}
+		result
+			.list
+			.sort_by(|a, b| (a.offset, &a.message).cmp(&(b.offset, &b.message)));
+		result.list.dedup();
+		result
+	}
+
+	#[cfg(feature = "export")]
+	pub fn save_warnings(&self, output: &mut OutputWriter) {
+		self.warnings().save_as("Script Warnings", output);
+	}
+
+	#[cfg(feature = "export")]
+	/// Writes opcode frequencies, unknown opcodes, and unreached bytecode across all scripts
+	pub fn save_opcode_report(&self, output: &mut OutputWriter) {
+		output.write("Opcode Coverage", "txt", self.opcode_report());
+	}
+
+	#[cfg(feature = "export")]
+	/// Writes every path through each entity's scripts, one file per entity
+	pub fn save_traces(&self, output: &mut OutputWriter) {
+		let tracer = CmiTracer::default();
+		let mut temp_data = String::new();
+		for (&entity_name, entity) in self.entities.iter() {
+			temp_data.clear();
+			for script_offset in &entity.scripts {
+				let script = &self.scripts[script_offset];
+				writeln!(temp_data, "script {script_offset:06X}:").unwrap();
+				temp_data.push_str(&tracer.report(script));
+			}
+			if !temp_data.is_empty() {
+				output.write(entity_name, "txt", &temp_data);
+			}
+		}
+	}
+
+	#[cfg(feature = "export")]
+	pub fn save_script_symbols(&self, output: &mut OutputWriter) {
+		output.write("Script Symbols", "csv", self.script_symbols());
+	}
+
+	/// Labels for the scripts, instructions, animations and splines as `address,name,comment` csv,
+	/// for importing into a disassembler (e.g. Ghidra or IDA) with the CMI file loaded as raw data.
+	///
+	/// Addresses are offsets from the start of the file, so the file should be loaded at address 0.
+	pub fn script_symbols(&self) -> String {
+		// offsets are from the end of the size header
+		const HEADER_SIZE: u32 = 4;
+
+		let mut symbols: BTreeMap<u32, (String, String)> = BTreeMap::new();
+		for (&offset, script) in &self.scripts {
+			if offset == 0 {
+				continue;
+			}
+			let name = match script.call_origins.first() {
+				Some(origin) => format!("{}_{offset:06X}", origin.target_name),
+				None => format!("script_{offset:06X}"),
+			};
+			let mut comment = String::new();
+			for origin in &script.call_origins {
+				if !comment.is_empty() {
+					comment.push_str("; ");
 				}
+				write!(
+					comment,
+					"[{}] {}: {}",
+					origin.arena_name, origin.target_name, origin.reason
+				)
+				.unwrap();
 			}
+			symbols.insert(offset, (name, comment));
+
+			// blocks can be shared between scripts, and script labels take priority
+			for instruction in &script.instructions {
+				symbols.entry(instruction.offset).or_insert_with(|| {
+					let comment = format!("{:02X} {}", instruction.opcode, instruction.name);
+					(String::new(), comment)
+				});
+			}
+		}
+		for (&offset, name) in &self.animation_names {
+			symbols.insert(offset, (name.clone(), "animation".to_owned()));
+		}
+		for (offset, users) in self.spline_users() {
+			let mut entities: Vec<&str> = users.iter().map(|user| user.entity).collect();
+			entities.dedup();
+			let comment = format!("spline used by {}", entities.join(", "));
+			symbols.insert(offset, (format!("spline_{offset:06X}"), comment));
 		}
+
+		let mut result = String::from("address,name,comment\n");
+		for (offset, (name, comment)) in symbols {
+			writeln!(
+				result,
+				"0x{:08X},{},{}",
+				offset + HEADER_SIZE,
+				csv_field(&name),
+				csv_field(&comment)
+			)
+			.unwrap();
+		}
+		result
 	}
 
+	pub fn opcode_report(&self) -> String {
+		let mut script_offsets: Vec<u32> = self.scripts.keys().copied().collect();
+		script_offsets.sort_unstable();
+		let scripts = || {
+			script_offsets
+				.iter()
+				.map(|offset| (*offset, &self.scripts[offset]))
+		};
+
+		let mut counts = [0usize; 256];
+		let mut names: [Vec<&str>; 256] = std::array::from_fn(|_| Vec::new());
+		let mut num_instructions = 0;
+		for (_, script) in scripts() {
+			for instruction in &script.instructions {
+				num_instructions += 1;
+				counts[instruction.opcode as usize] += 1;
+				let names = &mut names[instruction.opcode as usize];
+				if !names.contains(&instruction.name.as_str()) {
+					names.push(&instruction.name);
+				}
+			}
+		}
+
+		let mut by_frequency: Vec<u8> = (0..=255).filter(|&op| counts[op as usize] != 0).collect();
+		by_frequency.sort_by_key(|&op| std::cmp::Reverse(counts[op as usize]));
+
+		let mut result = String::new();
+		writeln!(
+			result,
+			"{} scripts, {num_instructions} instructions, {} distinct opcodes\n",
+			self.scripts.len(),
+			by_frequency.len()
+		)
+		.unwrap();
+
+		result.push_str("Opcode frequencies:\n");
+		for &op in &by_frequency {
+			writeln!(
+				result,
+				"\t{op:02X}: {:6} {}",
+				counts[op as usize],
+				names[op as usize].join(" / ")
+			)
+			.unwrap();
+		}
+
+		result.push_str("\nPartially understood opcodes:\n");
+		for &op in &by_frequency {
+			let instruction = scripts()
+				.flat_map(|(_, script)| &script.instructions)
+				.find(|instruction| instruction.opcode == op)
+				.unwrap();
+			if instruction.is_partially_known() && !instruction.is_invalid() {
+				writeln!(
+					result,
+					"\t{op:02X}: {:6} {}",
+					counts[op as usize],
+					names[op as usize].join(" / ")
+				)
+				.unwrap();
+			}
+		}
+
+		result.push_str("\nBlocks quarantined at an invalid opcode:\n");
+		for (script_offset, script) in scripts() {
+			for block in &script.quarantined_blocks {
+				writeln!(
+					result,
+					"\t{:06X}..{:06X} (script {script_offset:06X})",
+					block.start, block.end
+				)
+				.unwrap();
+			}
+		}
+
+		result.push_str("\nUnreached bytecode within scripts:\n");
+		for (script_offset, script) in scripts() {
+			let mut ranges = script.block_ranges.clone();
+			ranges.sort_unstable_by_key(|range| range.start);
+			let mut end = None;
+			for range in ranges {
+				if let Some(end) = end
+					&& range.start > end
+				{
+					writeln!(
+						result,
+						"\t{end:06X}..{:06X} ({} bytes, script {script_offset:06X})",
+						range.start,
+						range.start - end
+					)
+					.unwrap();
+				}
+				end = Some(end.unwrap_or(0).max(range.end));
+			}
+		}
+
+		result.push_str("\nUnused opcodes:\n");
+		for (op, &count) in counts.iter().enumerate().take(0xFF).skip(1) {
+			if count == 0 {
+				write!(result, " {op:02X}").unwrap();
+			}
+		}
+		result.push('\n');
+
+		result
+	}
+
+	#[cfg(feature = "export")]
 	fn validate_entity_references(&self) -> bool {
 		for arena in &self.arenas {
 			let arena_name = arena.name;
@@ -514,3 +1167,342 @@ impl<'a> Cmi<'a> {
 		true
 	}
 }
+
+/// Records which submeshes the scripts hide, show or blow off
+fn label_mesh_parts(
+	mesh: &mut Mesh, scripts: &HashMap<u32, cmi_bytecode::CmiScript>, script_offsets: &[u32],
+) {
+	for &script_offset in script_offsets {
+		for part_ref in &scripts[&script_offset].part_refs {
+			mesh.add_part_usage(PartUsage {
+				part: Name::new(part_ref.name),
+				action: part_ref.action,
+				script_offset,
+			});
+		}
+	}
+}
+
+/// Quotes a csv field if it needs it
+fn csv_field(field: &str) -> Cow<'_, str> {
+	if field.contains([',', '"', '\n']) {
+		format!("\"{}\"", field.replace('"', "\"\"")).into()
+	} else {
+		field.into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Vec3;
+	use crate::data_formats::spline::SplinePoint;
+	use crate::test_support::{CmiBuilder, EMPTY_SCRIPT};
+
+	#[test]
+	fn test_parse() {
+		let data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "SONG1", EMPTY_SCRIPT)
+			.arena("ARENA2", "", EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 3, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.build();
+		let cmi = Cmi::parse(Reader::new(&data));
+
+		assert_eq!(cmi.filename, "LEVEL3.CMI");
+		let arenas: Vec<_> = cmi
+			.arenas
+			.iter()
+			.map(|a| (a.name, a.song, a.entities.clone()))
+			.collect();
+		assert_eq!(
+			arenas,
+			[
+				("ARENA1", "SONG1", vec!["ARENA1", "GUNT"]),
+				("ARENA2", "", vec!["ARENA2"]),
+			]
+		);
+
+		let gunt = &cmi.entities["GUNT"];
+		assert!(gunt.mesh.is_none());
+		assert_eq!(gunt.arenas, ["ARENA1"]);
+		assert_eq!(gunt.scripts.len(), 2);
+		assert_eq!(cmi.scripts.len(), 4);
+		for script in cmi.scripts.values() {
+			assert_eq!(script.instructions.len(), 2);
+		}
+
+		let symbols = cmi.script_symbols();
+		let mut lines = symbols.lines();
+		assert_eq!(lines.next(), Some("address,name,comment"));
+		let gunt_init = gunt.scripts[0];
+		assert!(lines.any(|line| line.starts_with(&format!(
+			"0x{:08X},GUNT_{gunt_init:06X},[ARENA1] GUNT: ",
+			gunt_init + 4
+		))));
+		assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+	}
+
+	#[test]
+	fn test_resolve_animation_names() {
+		let anim = |speed| Animation {
+			speed,
+			target_vectors: Vec::new(),
+			reference_points: Vec::new(),
+			parts: Vec::new(),
+		};
+		let [walk, run, jump] = [anim(1.0), anim(2.0), anim(3.0)];
+		let mut cmi = Cmi::default();
+		// the same animation can be stored more than once
+		for (offset, speed) in [(0x10, 1.0), (0x20, 2.0), (0x30, 1.0), (0x40, 4.0)] {
+			cmi.animations.insert(offset, anim(speed));
+			cmi.animation_names
+				.insert(offset, format!("GUNT_{offset:06X}"));
+		}
+
+		cmi.resolve_animation_names([("WALK", &walk), ("JUMP", &jump)]);
+		let names = |cmi: &Cmi| {
+			let mut names: Vec<(u32, String)> = cmi
+				.animation_names
+				.iter()
+				.map(|(offset, name)| (*offset, name.clone()))
+				.collect();
+			names.sort_unstable();
+			names
+		};
+		let expected = |second: &str| {
+			[
+				(0x10, "WALK".to_owned()),
+				(0x20, second.to_owned()),
+				(0x30, "WALK".to_owned()),
+				(0x40, "GUNT_000040".to_owned()),
+			]
+		};
+		// unmatched names (JUMP) aren't added, unmatched animations keep their own
+		assert_eq!(names(&cmi), expected("GUNT_000020"));
+		let resolved: Vec<&str> = cmi.resolved_animations().into_keys().collect();
+		assert_eq!(resolved, ["GUNT_000020", "GUNT_000040", "WALK"]);
+
+		// the last name given for an animation wins
+		cmi.resolve_animation_names([("SPRINT", &run), ("RUN", &run)]);
+		assert_eq!(names(&cmi), expected("RUN"));
+	}
+
+	#[test]
+	fn test_script_symbols() {
+		let data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "SONG1", EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 3, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.build();
+		let cmi = Cmi::parse(Reader::new(&data));
+		let symbols = cmi.script_symbols();
+		assert_eq!(
+			symbols,
+			"address,name,comment
+0x00000066,GUNT_000062,[ARENA1] GUNT: Init (id 3)
+0x00000067,,FD Return
+0x00000069,GUNT_000065,[ARENA1] GUNT: Setup
+0x0000006A,,FD Return
+0x0000006C,ARENA1_000068,[ARENA1] ARENA1: Setup
+0x0000006D,,FD Return
+"
+		);
+
+		// addresses point at their instruction in the file,
+		// and a script's label replaces the one for its first instruction
+		for line in symbols.lines().skip(1) {
+			let [address, name, comment] = line.splitn(3, ',').collect::<Vec<_>>()[..] else {
+				panic!("invalid line {line}");
+			};
+			let address = usize::from_str_radix(&address[2..], 16).unwrap();
+			let opcode = if name.is_empty() {
+				u8::from_str_radix(&comment[..2], 16).unwrap()
+			} else {
+				EMPTY_SCRIPT[0]
+			};
+			assert_eq!(data[address], opcode, "{line}");
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_filter() {
+		let data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "SONG1", EMPTY_SCRIPT)
+			.arena("ARENA2", "", EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 3, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.entity("ARENA2", "GUNTER", 4, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.entity("ARENA2", "SNIPER", 5, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.build();
+		let cmi = Cmi::parse(Reader::new(&data));
+		let matching = |filter: &CmiFilter| {
+			let mut names: Vec<&str> = cmi
+				.entities
+				.iter()
+				.filter(|(name, entity)| filter.matches(name, entity))
+				.map(|(name, _)| *name)
+				.collect();
+			names.sort_unstable();
+			names
+		};
+		let filter = |entities: &[&str], arenas: &[&str]| CmiFilter {
+			entities: entities.iter().map(|s| s.to_string()).collect(),
+			arenas: arenas.iter().map(|s| s.to_string()).collect(),
+		};
+
+		assert_eq!(matching(&filter(&["gunt*"], &[])), ["GUNT", "GUNTER"]);
+		assert_eq!(matching(&filter(&["GUNT*"], &["arena2"])), ["GUNTER"]);
+		assert_eq!(
+			matching(&filter(&["GUNT", "?NIPER"], &[])),
+			["GUNT", "SNIPER"]
+		);
+		assert_eq!(matching(&filter(&["GUNT"], &["ARENA2"])), [] as [&str; 0]);
+
+		let dir = std::path::Path::new("output/test_cmi_filter");
+		let _ = std::fs::remove_dir_all(dir);
+		let saved = cmi.save_filtered(
+			&mut OutputWriter::new_in_dir(dir),
+			&filter(&["SNIPER"], &["ARENA2"]),
+		);
+		assert_eq!(saved, 1);
+		assert!(dir.join("SNIPER").is_dir());
+		assert!(dir.join("ARENA2").is_dir());
+		assert!(!dir.join("GUNT").exists());
+		assert!(!dir.join("ARENA1").exists());
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_save_relationships() {
+		let data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "SONG1", EMPTY_SCRIPT)
+			.arena("ARENA2", "", EMPTY_SCRIPT)
+			.entity("ARENA2", "SNIPER", 5, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 3, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.build();
+		let mut cmi = Cmi::parse(Reader::new(&data));
+		let gunt = cmi.entities.get_mut("GUNT").unwrap();
+		// animations in the CMI and ones named from the MTO both count
+		gunt.animations = vec![0x10, 0x20];
+		gunt.animation_names = vec!["WALK"];
+		gunt.splines = vec![0x30];
+
+		let dir = std::path::Path::new("output/test_cmi_relationships");
+		let _ = std::fs::remove_dir_all(dir);
+		cmi.save_relationships(&mut OutputWriter::new_in_dir(dir));
+		let json = std::fs::read(dir.join("relationships.json")).unwrap();
+		std::fs::remove_dir_all(dir).unwrap();
+		let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+		assert_eq!(
+			json,
+			serde_json::json!({
+				"arenas": [
+					{"name": "ARENA1", "song": "SONG1", "entities": ["ARENA1", "GUNT"]},
+					{"name": "ARENA2", "entities": ["ARENA2", "SNIPER"]},
+				],
+				"entities": [
+					{
+						"name": "ARENA1",
+						"arenas": ["ARENA1"],
+						"has_mesh": false,
+						"num_scripts": 1,
+						"num_animations": 0,
+						"num_splines": 0,
+					},
+					{
+						"name": "ARENA2",
+						"arenas": ["ARENA2"],
+						"has_mesh": false,
+						"num_scripts": 1,
+						"num_animations": 0,
+						"num_splines": 0,
+					},
+					{
+						"name": "GUNT",
+						"arenas": ["ARENA1"],
+						"has_mesh": false,
+						"num_scripts": 2,
+						"num_animations": 3,
+						"num_splines": 1,
+					},
+					{
+						"name": "SNIPER",
+						"arenas": ["ARENA2"],
+						"has_mesh": false,
+						"num_scripts": 2,
+						"num_animations": 0,
+						"num_splines": 0,
+					},
+				],
+			})
+		);
+	}
+
+	#[test]
+	fn test_spline_users() {
+		let data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "", EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 3, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.entity("ARENA1", "SNIPER", 5, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.build();
+		let mut cmi = Cmi::parse(Reader::new(&data));
+		let point = |t| SplinePoint {
+			t,
+			pos1: Vec3::default(),
+			pos2: Vec3::default(),
+			pos3: Vec3::default(),
+		};
+		cmi.splines.insert(
+			0x40,
+			Spline {
+				points: vec![point(0), point(1), point(2)],
+			},
+		);
+		cmi.splines.insert(
+			0x80,
+			Spline {
+				points: vec![point(0), point(1)],
+			},
+		);
+		// both of GUNT's scripts and SNIPER's setup follow the first spline
+		let [gunt_init, gunt_setup] = cmi.entities["GUNT"].scripts[..] else {
+			panic!("expected two scripts");
+		};
+		let sniper_setup = cmi.entities["SNIPER"].scripts[1];
+		for (script, splines) in [
+			(gunt_init, vec![0x40, 0x80]),
+			(gunt_setup, vec![0x40]),
+			(sniper_setup, vec![0x40]),
+		] {
+			cmi.scripts.get_mut(&script).unwrap().path_offsets = splines;
+		}
+
+		let user = |entity, script_offset| SplineUser {
+			entity,
+			script_offset,
+		};
+		let users = cmi.spline_users();
+		assert_eq!(
+			users[&0x40],
+			[
+				user("GUNT", gunt_init),
+				user("GUNT", gunt_setup),
+				user("SNIPER", sniper_setup),
+			]
+		);
+		assert_eq!(users[&0x80], [user("GUNT", gunt_init)]);
+		assert_eq!(
+			cmi.spline_report(),
+			format!(
+				"Spline\tPoints\tUsed by\n\
+				000040\t3\tGUNT ({gunt_init:06X}), GUNT ({gunt_setup:06X}), SNIPER ({sniper_setup:06X})\n\
+				000080\t2\tGUNT ({gunt_init:06X})\n"
+			)
+		);
+		#[cfg(feature = "export")]
+		assert_eq!(
+			Cmi::spline_filename(0x40, &users[&0x40]),
+			"000040 GUNT SNIPER"
+		);
+	}
+}
@@ -0,0 +1,225 @@
+//! Finding the scripts in a CMI that use a sound, animation or entity,
+//! spawn an entity, or change a variable.
+use std::fmt::Write;
+
+use super::Cmi;
+use crate::data_formats::cmi_bytecode::{CmiScript, CmiSpawn, CmiVarWrite};
+
+/// What to look for with [Cmi::search], parsed from `NAME`, `spawn:NAME` or `var:TARGET:INDEX`
+/// (e.g. `var:Entity:3`). Names are case-insensitive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CmiQuery {
+	/// Any sound, animation or entity with this name
+	Name(String),
+	/// Spawns of this entity
+	Spawn(String),
+	/// Writes to a variable, by whose variables they are (`Global`, `Arena`, `Entity`...) and index
+	Var { target: String, index: u8 },
+}
+
+impl CmiQuery {
+	pub fn parse(query: &str) -> Self {
+		if let Some(name) = query.strip_prefix("spawn:") {
+			CmiQuery::Spawn(name.to_owned())
+		} else if let Some(var) = query.strip_prefix("var:") {
+			let (target, index) = var
+				.split_once(':')
+				.unwrap_or_else(|| panic!("variable query {query} should be var:TARGET:INDEX"));
+			CmiQuery::Var {
+				target: target.to_owned(),
+				index: index
+					.parse()
+					.unwrap_or_else(|e| panic!("invalid variable index {index}: {e}")),
+			}
+		} else {
+			CmiQuery::Name(query.to_owned())
+		}
+	}
+}
+
+/// A script found by a search, and what it does that matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct CmiMatch<'a> {
+	pub script_offset: u32,
+	/// The entities the script runs for
+	pub entities: Vec<&'a str>,
+	pub detail: String,
+}
+
+impl std::fmt::Display for CmiMatch<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{:06X}\t{}\t{}",
+			self.script_offset,
+			self.entities.join(", "),
+			self.detail
+		)
+	}
+}
+
+impl<'a> Cmi<'a> {
+	/// Scripts that play a sound, set an animation, or spawn, call or target an entity
+	/// named `name`, in file order
+	pub fn scripts_referencing(&self, name: &str) -> Vec<CmiMatch<'a>> {
+		self.find_in_scripts(|script, detail| {
+			let is = |other: &str| other.eq_ignore_ascii_case(name);
+			if script.sounds.iter().any(|sound| is(sound.name)) {
+				detail.push("sound");
+			}
+			let animations = script
+				.anim_offsets
+				.iter()
+				.map(|offset| self.animation_names.get(offset).map_or("", String::as_str));
+			if script.anim_names.iter().copied().chain(animations).any(is) {
+				detail.push("animation");
+			}
+			if script.spawns.iter().any(|spawn| is(spawn.name)) {
+				detail.push("spawn");
+			}
+			let calls = script.called_scripts.iter().map(|call| call.target_name);
+			let points = script.point_refs.iter().map(|point| point.target);
+			if calls.chain(points).any(is) {
+				detail.push("entity");
+			}
+		})
+	}
+
+	/// Every spawn of the entity `name`, with the offset of the script that spawns it
+	pub fn spawns_of(&self, name: &str) -> Vec<(u32, &CmiSpawn<'a>)> {
+		self.sorted_scripts()
+			.flat_map(|(offset, script)| {
+				script
+					.spawns
+					.iter()
+					.filter(|spawn| spawn.name.eq_ignore_ascii_case(name))
+					.map(move |spawn| (offset, spawn))
+			})
+			.collect()
+	}
+
+	/// Every write to `{target}_vars[{index}]`, with the offset of the script that writes it
+	pub fn var_writes(&self, target: &str, index: u8) -> Vec<(u32, &CmiVarWrite)> {
+		self.sorted_scripts()
+			.flat_map(|(offset, script)| {
+				script
+					.var_writes
+					.iter()
+					.filter(|write| {
+						write.index == index && write.target.eq_ignore_ascii_case(target)
+					})
+					.map(move |write| (offset, write))
+			})
+			.collect()
+	}
+
+	pub fn search(&self, query: &CmiQuery) -> Vec<CmiMatch<'a>> {
+		match query {
+			CmiQuery::Name(name) => self.scripts_referencing(name),
+			CmiQuery::Spawn(name) => self.find_in_scripts(|script, detail| {
+				for spawn in script
+					.spawns
+					.iter()
+					.filter(|spawn| spawn.name.eq_ignore_ascii_case(name))
+				{
+					let mut text = format!("{} at {}", spawn.name, spawn.position);
+					if !spawn.arena.is_empty() {
+						write!(text, " in {}", spawn.arena).unwrap();
+					}
+					detail.push(text);
+				}
+			}),
+			CmiQuery::Var { target, index } => self.find_in_scripts(|script, detail| {
+				for write in script.var_writes.iter().filter(|write| {
+					write.index == *index && write.target.eq_ignore_ascii_case(target)
+				}) {
+					detail.push(write.to_string());
+				}
+			}),
+		}
+	}
+
+	fn sorted_scripts(&self) -> impl Iterator<Item = (u32, &CmiScript<'a>)> {
+		let mut offsets: Vec<u32> = self.scripts.keys().copied().collect();
+		offsets.sort_unstable();
+		offsets
+			.into_iter()
+			.map(|offset| (offset, &self.scripts[&offset]))
+	}
+
+	/// Scripts that `find` describes as matching, joining the descriptions it gives
+	fn find_in_scripts<D: ToString>(
+		&self, mut find: impl FnMut(&CmiScript<'a>, &mut Vec<D>),
+	) -> Vec<CmiMatch<'a>> {
+		let mut result = Vec::new();
+		let mut detail = Vec::new();
+		for (script_offset, script) in self.sorted_scripts() {
+			detail.clear();
+			find(script, &mut detail);
+			if detail.is_empty() {
+				continue;
+			}
+			let mut entities: Vec<&str> = script
+				.call_origins
+				.iter()
+				.map(|origin| origin.target_name)
+				.collect();
+			entities.sort_unstable();
+			entities.dedup();
+			let detail: Vec<String> = detail.iter().map(ToString::to_string).collect();
+			result.push(CmiMatch {
+				script_offset,
+				entities,
+				detail: detail.join(", "),
+			});
+		}
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{ByteBuilder, CmiBuilder, EMPTY_SCRIPT};
+	use crate::{Reader, Vec3};
+
+	#[test]
+	fn test_search() {
+		let mut script = ByteBuilder::new();
+		script.u8(0x41).u8(2).u8(3).f32(1.0);
+		script.u8(0x6B).pascal_str("BEEP");
+		script.u8(0x56).vec3(Vec3::new(1.0, 2.0, 3.0));
+		script.pascal_str("ALIEN").u32(0);
+		script.u8(0xFD).u8(0xFF);
+		let data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "", EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 1, &script.finish(), EMPTY_SCRIPT)
+			.build();
+		let cmi = Cmi::parse(Reader::new(&data));
+		let init = cmi.entities["GUNT"].scripts[0];
+
+		let found = cmi.search(&CmiQuery::parse("beep"));
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[0].to_string(), format!("{init:06X}\tGUNT\tsound"));
+		assert_eq!(
+			cmi.search(&CmiQuery::parse("ALIEN"))[0].detail,
+			"spawn, entity"
+		);
+		assert!(cmi.search(&CmiQuery::parse("SONG1")).is_empty());
+
+		assert_eq!(cmi.spawns_of("alien").len(), 1);
+		assert_eq!(
+			cmi.search(&CmiQuery::parse("spawn:ALIEN"))[0].script_offset,
+			init
+		);
+
+		let writes = cmi.var_writes("entity", 3);
+		assert_eq!(writes.len(), 1);
+		assert_eq!(writes[0].0, init);
+		assert_eq!(
+			cmi.search(&CmiQuery::parse("var:Entity:3"))[0].detail,
+			"Entity_vars[3] = 1"
+		);
+		assert!(cmi.var_writes("Entity", 4).is_empty());
+	}
+}
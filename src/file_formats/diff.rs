@@ -0,0 +1,143 @@
+//! Compares the assets of two game versions at the level of parsed data
+//! (mesh sizes, texture dimensions, script instructions etc.) rather than raw bytes.
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use super::FileType;
+use super::asset_list::{find_files, list_assets};
+
+/// Compares every file under `old_root` and `new_root` and returns a report of what changed.
+pub fn diff_asset_roots(old_root: &Path, new_root: &Path) -> String {
+	let old_files = find_files(old_root);
+	let new_files = find_files(new_root);
+
+	let mut report = String::new();
+	let all_files: BTreeSet<&String> = old_files.keys().chain(new_files.keys()).collect();
+	for file in all_files {
+		match (old_files.get(file), new_files.get(file)) {
+			(Some(_), None) => writeln!(report, "- {file}\n").unwrap(),
+			(None, Some(_)) => writeln!(report, "+ {file}\n").unwrap(),
+			(Some(old_path), Some(new_path)) => {
+				let old_data = read(old_path);
+				let new_data = read(new_path);
+				if old_data != new_data {
+					diff_file(file, old_path, &old_data, &new_data, &mut report);
+				}
+			}
+			(None, None) => unreachable!(),
+		}
+	}
+	if report.is_empty() {
+		report.push_str("No differences\n");
+	}
+	report
+}
+
+fn read(path: &Path) -> Vec<u8> {
+	std::fs::read(path).unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()))
+}
+
+fn diff_file(file: &str, path: &Path, old_data: &[u8], new_data: &[u8], report: &mut String) {
+	let Some(file_type) = FileType::sniff(path, new_data) else {
+		writeln!(
+			report,
+			"~ {file}\n\t{} bytes -> {} bytes\n",
+			old_data.len(),
+			new_data.len()
+		)
+		.unwrap();
+		return;
+	};
+
+	let old_summary = list_assets(file_type, old_data).assets;
+	let new_summary = list_assets(file_type, new_data).assets;
+
+	writeln!(report, "~ {file}").unwrap();
+	let mut any_changes = false;
+	let all_assets: BTreeSet<&String> = old_summary.keys().chain(new_summary.keys()).collect();
+	for asset in all_assets {
+		match (
+			old_summary.get(asset).map(|info| &info.lines),
+			new_summary.get(asset).map(|info| &info.lines),
+		) {
+			(Some(old), None) => writeln!(report, "\t- {asset}: {}", old.join("; ")).unwrap(),
+			(None, Some(new)) => writeln!(report, "\t+ {asset}: {}", new.join("; ")).unwrap(),
+			(Some(old), Some(new)) if old == new => continue,
+			(Some(old), Some(new)) if old.len() == 1 && new.len() == 1 => {
+				writeln!(report, "\t~ {asset}: {} -> {}", old[0], new[0]).unwrap()
+			}
+			(Some(old), Some(new)) => {
+				writeln!(report, "\t~ {asset}").unwrap();
+				diff_lines(old, new, report);
+			}
+			(None, None) => unreachable!(),
+		}
+		any_changes = true;
+	}
+	if !any_changes {
+		writeln!(report, "\tno logical changes").unwrap();
+	}
+	report.push('\n');
+}
+
+/// Writes the lines that differ between `old` and `new`, ignoring any matching start and end
+fn diff_lines(old: &[String], new: &[String], report: &mut String) {
+	let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+	let suffix = old[prefix..]
+		.iter()
+		.rev()
+		.zip(new[prefix..].iter().rev())
+		.take_while(|(a, b)| a == b)
+		.count();
+	writeln!(report, "\t\tat line {prefix}:").unwrap();
+	for line in &old[prefix..old.len() - suffix] {
+		writeln!(report, "\t\t- {line}").unwrap();
+	}
+	for line in &new[prefix..new.len() - suffix] {
+		writeln!(report, "\t\t+ {line}").unwrap();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::MtiBuilder;
+
+	#[test]
+	fn test_diff_asset_roots() {
+		let dir = Path::new("output/test_diff_asset_roots");
+		let _ = std::fs::remove_dir_all(dir);
+		let [old_root, new_root] = ["old", "new"].map(|name| dir.join(name));
+		for root in [&old_root, &new_root] {
+			std::fs::create_dir_all(root.join("Level3")).unwrap();
+			std::fs::write(root.join("SAME.TXT"), "unchanged").unwrap();
+		}
+		std::fs::write(old_root.join("Removed.txt"), "old").unwrap();
+		std::fs::write(new_root.join("ADDED.TXT"), "new").unwrap();
+		std::fs::write(old_root.join("NOTES.TXT"), "short").unwrap();
+		std::fs::write(new_root.join("NOTES.TXT"), "a bit longer").unwrap();
+		let mti = |value| {
+			MtiBuilder::new("LEVEL3.MTI")
+				.pen("GLOW", value)
+				.texture("WALL", 2, 1, &[1, 2])
+		};
+		std::fs::write(old_root.join("Level3/LEVEL3.MTI"), mti(4).build()).unwrap();
+		let new_mti = mti(5).pen("DARK", 0).build();
+		std::fs::write(new_root.join("Level3/LEVEL3.MTI"), new_mti).unwrap();
+
+		let report = diff_asset_roots(&old_root, &new_root);
+		assert_eq!(
+			report,
+			"+ ADDED.TXT\n\n\
+			~ LEVEL3/LEVEL3.MTI\n\
+			\t+ Materials/DARK: Colour(0)\n\
+			\t~ Materials/GLOW: Colour(4) -> Colour(5)\n\n\
+			~ NOTES.TXT\n\
+			\t5 bytes -> 12 bytes\n\n\
+			- REMOVED.TXT\n\n"
+		);
+		assert_eq!(diff_asset_roots(&old_root, &old_root), "No differences\n");
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -1,6 +1,27 @@
-use crate::{OutputWriter, Reader, Vec3, data_formats::Texture};
+#[cfg(feature = "export")]
+use crate::{OutputWriter, gltf::Gltf};
+use serde::Serialize;
+
+use crate::{Reader, Vec3, data_formats::Texture};
 
 /// DTI files contain a lot of level metadata
+///
+/// ```
+/// use mdk_parse::Reader;
+/// use mdk_parse::file_formats::Dti;
+///
+/// let dti = Dti::parse(Reader::new(include_bytes!("../../samples/SAMPLE.DTI")));
+/// assert_eq!(dti.arenas[0].name, "ARENA1");
+/// assert_eq!(dti.arenas[0].entities.len(), 1);
+/// assert_eq!((dti.skybox.width, dti.skybox.height), (2, 1));
+/// assert_eq!(dti.pal.len(), 0x300);
+///
+/// // save the palette, skybox and level info
+/// # #[cfg(feature = "export")] {
+/// let mut output = mdk_parse::OutputWriter::new_in_dir(std::env::temp_dir().join("SAMPLE.DTI"));
+/// dti.save(&mut output);
+/// # }
+/// ```
 pub struct Dti<'a> {
 	pub filename: &'a str,
 
@@ -52,6 +73,60 @@ pub enum DtiEntityData<'a> {
 	JumpPoint,
 	Slidething,
 }
+impl DtiEntityData<'_> {
+	pub fn kind_name(&self) -> &'static str {
+		match self {
+			DtiEntityData::ArenaShowZone => "ArenaShowZone",
+			DtiEntityData::Hotgen { .. } => "Hotgen",
+			DtiEntityData::ArenaActivateZone => "ArenaActivateZone",
+			DtiEntityData::Hotpick(_) => "Hotpick",
+			DtiEntityData::HidingSpot => "HidingSpot",
+			DtiEntityData::ArenaConnectZone(_) => "ArenaConnectZone",
+			DtiEntityData::Fan => "Fan",
+			DtiEntityData::JumpPoint => "JumpPoint",
+			DtiEntityData::Slidething => "Slidething",
+		}
+	}
+	/// Colour used when exporting the zone as a box
+	#[cfg(feature = "export")]
+	fn debug_colour(&self) -> [f32; 4] {
+		let [r, g, b] = match self {
+			DtiEntityData::ArenaShowZone => [0.0, 0.0, 1.0],
+			DtiEntityData::ArenaActivateZone => [0.0, 1.0, 0.0],
+			DtiEntityData::ArenaConnectZone(_) => [1.0, 1.0, 0.0],
+			DtiEntityData::Fan => [0.0, 1.0, 1.0],
+			DtiEntityData::JumpPoint => [1.0, 0.0, 0.0],
+			_ => [0.5, 0.5, 0.5],
+		};
+		[r, g, b, 0.25]
+	}
+}
+
+/// The level's floor and ceiling colours as they look in one arena's palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DtiLighting<'a> {
+	pub arena: &'a str,
+	pub floor_colour: Option<[u8; 3]>,
+	pub ceiling_colour: Option<[u8; 3]>,
+	pub reflected_floor_colour: Option<[u8; 3]>,
+	pub reflected_ceiling_colour: Option<[u8; 3]>,
+}
+impl DtiLighting<'_> {
+	/// Average of the floor and ceiling colours (0-1 srgb), as a flat ambient colour
+	pub fn ambient(&self) -> Option<[f32; 3]> {
+		let colours: Vec<[u8; 3]> = [self.floor_colour, self.ceiling_colour]
+			.into_iter()
+			.flatten()
+			.collect();
+		if colours.is_empty() {
+			return None;
+		}
+		Some(std::array::from_fn(|i| {
+			colours.iter().map(|c| c[i] as f32).sum::<f32>() / (colours.len() as f32 * 255.0)
+		}))
+	}
+}
+
 #[derive(Debug)]
 pub struct Teleport {
 	pub index: i32,
@@ -59,8 +134,8 @@ pub struct Teleport {
 	pub angle: f32,
 }
 
-impl Dti<'_> {
-	pub fn parse(mut data: Reader) -> Dti {
+impl<'a> Dti<'a> {
+	pub fn parse(mut data: Reader<'a>) -> Dti<'a> {
 		let filesize = data.u32() + 4;
 		data.rebase();
 
@@ -275,12 +350,130 @@ impl Dti<'_> {
 		}
 	}
 
+	/// Looks up a floor/ceiling colour index, which is out of range when the level doesn't use it
+	pub fn palette_colour(palette: &[u8], colour_index: i32) -> Option<[u8; 3]> {
+		let offset = usize::try_from(colour_index).ok().filter(|&i| i < 256)? * 3;
+		palette.get(offset..offset + 3)?.try_into().ok()
+	}
+	/// The floor and ceiling colours for an arena using `palette`
+	pub fn lighting<'n>(&self, arena: &'n str, palette: &[u8]) -> DtiLighting<'n> {
+		let colour = |index| Self::palette_colour(palette, index);
+		let reflected = self.reflected_skybox.is_some();
+		DtiLighting {
+			arena,
+			floor_colour: colour(self.floor_colour),
+			ceiling_colour: colour(self.ceiling_colour),
+			reflected_floor_colour: colour(self.reflected_floor_colour).filter(|_| reflected),
+			reflected_ceiling_colour: colour(self.reflected_ceiling_colour).filter(|_| reflected),
+		}
+	}
+
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter) {
 		output.write_palette("palette", self.pal);
 		self.skybox.save_as("skybox", output, Some(self.pal));
 		self.save_info_as("info", output);
+		let lighting: Vec<DtiLighting> = self
+			.arenas
+			.iter()
+			.map(|arena| self.lighting(arena.name, self.pal))
+			.collect();
+		self.save_lighting_as("lighting", &lighting, output);
+		if output.export_settings().zone_boxes {
+			self.save_zones_as("zones", &lighting, output);
+		}
+	}
+
+	#[cfg(feature = "export")]
+	/// Saves each arena's floor and ceiling colours so they can be reproduced when importing
+	pub fn save_lighting_as(
+		&self, name: &str, lighting: &[DtiLighting], output: &mut OutputWriter,
+	) {
+		#[derive(Serialize)]
+		struct Lighting<'a> {
+			level: &'a str,
+			arenas: &'a [DtiLighting<'a>],
+		}
+		let json = serde_json::to_string_pretty(&Lighting {
+			level: self.filename,
+			arenas: lighting,
+		})
+		.unwrap();
+		output.write(name, "json", json.as_bytes());
 	}
 
+	#[cfg(feature = "export")]
+	/// Saves every entity with a bounding box as a translucent box, grouped by arena,
+	/// so they can be imported alongside the arena meshes.
+	///
+	/// Each arena node also gets its `lighting` in its extras,
+	/// and empty nodes for the player start and teleports in it (with their `index` and `angle`).
+	pub fn save_zones_as(&self, name: &str, lighting: &[DtiLighting], output: &mut OutputWriter) {
+		let mut gltf = Gltf::with_settings(name.to_owned(), *output.export_settings());
+		let zones_node = gltf.create_base_node("Zones".to_owned(), None);
+
+		let mut materials = Vec::new();
+		for (arena_index, arena) in self.arenas.iter().enumerate() {
+			let arena_node = gltf.create_child_node(zones_node, arena.name.to_owned(), None);
+			if arena_index == self.player_start_arena_index as usize {
+				let node_name = format!("{}_PlayerStart", arena.name);
+				let node = gltf.create_child_node(arena_node, node_name, None);
+				gltf.set_node_position(node, self.player_start_pos.swizzle());
+				gltf.set_node_extras(node, "angle", self.player_start_angle);
+			}
+			for teleport in &arena.teleports {
+				let node_name = format!("{}_Teleport{}", arena.name, teleport.index);
+				let node = gltf.create_child_node(arena_node, node_name, None);
+				gltf.set_node_position(node, teleport.pos.swizzle());
+				gltf.set_node_extras(node, "index", teleport.index);
+				gltf.set_node_extras(node, "angle", teleport.angle);
+			}
+			if let Some(lighting) = lighting.iter().find(|l| l.arena == arena.name) {
+				gltf.set_node_extras(
+					arena_node,
+					"lighting",
+					serde_json::to_value(lighting).unwrap(),
+				);
+				if let Some(ambient) = lighting.ambient() {
+					gltf.set_node_extras(arena_node, "ambient", ambient.to_vec());
+				}
+			}
+			for (entity_index, entity) in arena.entities.iter().enumerate() {
+				if entity.bbox[0] == entity.bbox[1] {
+					continue; // not a zone
+				}
+				let kind = entity.data.kind_name();
+				let material =
+					if let Some((_, material)) = materials.iter().find(|(name, _)| *name == kind) {
+						*material
+					} else {
+						let material = gltf.create_translucent_colour_material(
+							kind.to_owned(),
+							entity.data.debug_colour(),
+						);
+						materials.push((kind, material));
+						material
+					};
+
+				let [min, max] = entity.bbox;
+				let bbox = Vec3::calculate_bbox(&[min.swizzle(), max.swizzle()]);
+				let node_name = format!("{}_{entity_index}_{kind}", arena.name);
+				let mesh = gltf.create_box_mesh(node_name.clone(), bbox, Some(material));
+				let node = gltf.create_child_node(arena_node, node_name, Some(mesh));
+				gltf.set_node_extras(node, "id", entity.id);
+				gltf.set_node_extras(node, "kind", kind);
+				if let DtiEntityData::ArenaConnectZone(value)
+				| DtiEntityData::Hotgen { value, .. } = entity.data
+				{
+					gltf.set_node_extras(node, "value", value);
+				}
+			}
+		}
+
+		output.write(name, "gltf", gltf.render_json().as_bytes());
+	}
+
+	#[cfg(feature = "export")]
 	pub fn save_info_as(&self, info_filename: &str, output: &mut OutputWriter) {
 		use std::fmt::Write;
 		let mut info = format!(
@@ -299,13 +492,11 @@ impl Dti<'_> {
 		info.push('\n');
 
 		let mut print_colour = |label, colour_index| {
-			if !(0..=255).contains(&colour_index) {
+			let Some([r, g, b]) = Self::palette_colour(self.pal, colour_index) else {
 				writeln!(&mut info, "{label}: ({colour_index})").unwrap();
 				return;
-			}
-			let offset = colour_index as usize * 3;
-			let rgb = &self.pal[offset..offset + 3];
-			let value = u32::from_be_bytes([0, rgb[0], rgb[1], rgb[2]]);
+			};
+			let value = u32::from_be_bytes([0, r, g, b]);
 			writeln!(&mut info, "{label}: #{value:06X}").unwrap();
 		};
 		print_colour("floor colour", self.floor_colour);
@@ -351,3 +542,110 @@ impl Dti<'_> {
 		output.write(info_filename, "txt", info.as_bytes());
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{DtiBuilder, DtiZone, DtiZoneExtra};
+
+	#[test]
+	fn test_parse() {
+		let pal: Vec<u8> = (0..0x300).map(|i| i as u8).collect();
+		let data = DtiBuilder::new("LEVEL3.DTI")
+			.player_start(1, Vec3::new(1.0, 2.0, 3.0), 0.5)
+			.arena("ARENA1", 4.0)
+			.zone(DtiZone {
+				kind: 1,
+				id: 10,
+				value: 0,
+				min: Vec3::new(0.0, 0.0, 0.0),
+				extra: DtiZoneExtra::Max(Vec3::new(1.0, 1.0, 1.0)),
+			})
+			.arena("ARENA2", 5.0)
+			.zone(DtiZone {
+				kind: 2,
+				id: 11,
+				value: 7,
+				min: Vec3::new(5.0, 6.0, 7.0),
+				extra: DtiZoneExtra::Name("GUNT"),
+			})
+			.zone(DtiZone {
+				kind: 6,
+				id: 12,
+				value: 1,
+				min: Vec3::new(-1.0, -1.0, -1.0),
+				extra: DtiZoneExtra::Max(Vec3::new(0.0, 0.0, 0.0)),
+			})
+			.teleport(1, Vec3::new(8.0, 9.0, 10.0), 1.5)
+			.palette(&pal)
+			.skybox(2, 2, &[1, 2, 3, 4], Some(&[5, 6, 7, 8]))
+			.build();
+		let dti = Dti::parse(Reader::new(&data));
+
+		assert_eq!(dti.filename, "LEVEL3.DTI");
+		assert_eq!(dti.player_start_arena_index, 1);
+		assert_eq!(dti.player_start_pos, Vec3::new(1.0, 2.0, 3.0));
+		assert_eq!(dti.player_start_angle, 0.5);
+		assert_eq!(dti.pal, &pal[..]);
+		assert_eq!(&dti.skybox.pixels[..], [1, 2, 3, 4]);
+		assert_eq!(&dti.reflected_skybox.unwrap().pixels[..], [5, 6, 7, 8]);
+
+		let names: Vec<&str> = dti.arenas.iter().map(|a| a.name).collect();
+		assert_eq!(names, ["ARENA1", "ARENA2"]);
+		assert_eq!(dti.arenas[0].num, 4.0);
+		assert!(matches!(
+			dti.arenas[0].entities[0].data,
+			DtiEntityData::ArenaShowZone
+		));
+		assert_eq!(
+			dti.arenas[0].entities[0].bbox,
+			[Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)]
+		);
+		assert!(matches!(
+			dti.arenas[1].entities[0].data,
+			DtiEntityData::Hotgen {
+				name: "GUNT",
+				value: 7
+			}
+		));
+		assert_eq!(dti.arenas[1].entities[0].bbox[1], Vec3::new(5.0, 6.0, 7.0));
+		assert!(matches!(
+			dti.arenas[1].entities[1].data,
+			DtiEntityData::ArenaConnectZone(1)
+		));
+		assert_eq!(dti.arenas[1].teleports.len(), 1);
+		assert_eq!(dti.arenas[1].teleports[0].pos, Vec3::new(8.0, 9.0, 10.0));
+	}
+
+	#[test]
+	fn test_lighting() {
+		let pal: Vec<u8> = (0..0x300).map(|i| i as u8).collect();
+		let data = DtiBuilder::new("LEVEL4.DTI")
+			.colours(1, 2, 3, 300)
+			.arena("ARENA1", 0.0)
+			.palette(&pal)
+			.skybox(1, 1, &[0], Some(&[0]))
+			.build();
+		let dti = Dti::parse(Reader::new(&data));
+
+		let lighting = dti.lighting("ARENA1", dti.pal);
+		assert_eq!(
+			lighting,
+			DtiLighting {
+				arena: "ARENA1",
+				floor_colour: Some([6, 7, 8]),
+				ceiling_colour: Some([3, 4, 5]),
+				reflected_floor_colour: None,
+				reflected_ceiling_colour: Some([9, 10, 11]),
+			}
+		);
+		assert_eq!(
+			lighting.ambient(),
+			Some([4.5 / 255.0, 5.5 / 255.0, 6.5 / 255.0])
+		);
+
+		// the arena's own palette changes the colours
+		let dark = vec![0; 0x300];
+		assert_eq!(dti.lighting("ARENA1", &dark).floor_colour, Some([0, 0, 0]));
+	}
+}
@@ -0,0 +1,109 @@
+//! A bestiary of every CMI entity, gathered across levels.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::Cmi;
+
+/// What every level says about one entity
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EntityStat {
+	pub levels: BTreeSet<String>,
+	/// As `LEVEL/ARENA`
+	pub arenas: BTreeSet<String>,
+	pub has_mesh: bool,
+	/// The most animations it has in any one level
+	pub animations: usize,
+	/// The most scripts it has in any one level
+	pub scripts: usize,
+	/// Every health value any of its scripts set
+	pub health: BTreeSet<u16>,
+}
+
+/// Entities by name, see [EntityStats::add_level]
+#[derive(Debug, Default)]
+pub struct EntityStats {
+	pub entities: BTreeMap<String, EntityStat>,
+}
+
+impl EntityStats {
+	/// Adds every entity in `level`'s CMI
+	pub fn add_level(&mut self, level: &str, cmi: &Cmi) {
+		for (&name, entity) in &cmi.entities {
+			let stat = self.entities.entry(name.to_owned()).or_default();
+			stat.levels.insert(level.to_owned());
+			for arena in &entity.arenas {
+				stat.arenas.insert(format!("{level}/{arena}"));
+			}
+			stat.has_mesh |= entity.mesh.is_some();
+			stat.animations = stat
+				.animations
+				.max(entity.animations.len() + entity.animation_names.len());
+			stat.scripts = stat.scripts.max(entity.scripts.len());
+		}
+		for script in cmi.scripts.values() {
+			if script.health_values.is_empty() {
+				continue;
+			}
+			for origin in &script.call_origins {
+				if let Some(stat) = self.entities.get_mut(origin.target_name) {
+					stat.health.extend(&script.health_values);
+				}
+			}
+		}
+	}
+
+	pub fn csv(&self) -> String {
+		let join = |values: &BTreeSet<String>| values.iter().cloned().collect::<Vec<_>>().join(" ");
+		let mut result = String::from("name,levels,arenas,mesh,animations,scripts,health\n");
+		for (name, stat) in &self.entities {
+			let health: Vec<String> = stat.health.iter().map(u16::to_string).collect();
+			writeln!(
+				result,
+				"{name},{},{},{},{},{},{}",
+				join(&stat.levels),
+				join(&stat.arenas),
+				stat.has_mesh,
+				stat.animations,
+				stat.scripts,
+				health.join(" ")
+			)
+			.unwrap();
+		}
+		result
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Reader;
+	use crate::test_support::{ByteBuilder, CmiBuilder, EMPTY_SCRIPT};
+
+	#[test]
+	fn test_entity_stats() {
+		let mut script = ByteBuilder::new();
+		script.u8(0x10).u16(300).u8(0x10).u16(0).u8(0xFF);
+		let script = script.finish();
+		let level3 = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "", EMPTY_SCRIPT)
+			.entity("ARENA1", "GUNT", 1, &script, EMPTY_SCRIPT)
+			.build();
+		let level4 = CmiBuilder::new("LEVEL4.CMI")
+			.arena("ARENA2", "", EMPTY_SCRIPT)
+			.entity("ARENA2", "GUNT", 1, EMPTY_SCRIPT, EMPTY_SCRIPT)
+			.build();
+
+		let mut stats = EntityStats::default();
+		stats.add_level("LEVEL3", &Cmi::parse(Reader::new(&level3)));
+		stats.add_level("LEVEL4", &Cmi::parse(Reader::new(&level4)));
+
+		let gunt = &stats.entities["GUNT"];
+		assert_eq!(gunt.arenas.len(), 2);
+		assert_eq!(gunt.health.iter().copied().collect::<Vec<_>>(), [300]);
+		assert!(
+			stats
+				.csv()
+				.contains("\nGUNT,LEVEL3 LEVEL4,LEVEL3/ARENA1 LEVEL4/ARENA2,false,")
+		);
+	}
+}
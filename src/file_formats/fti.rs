@@ -1,11 +1,15 @@
 use std::borrow::Cow;
 
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+use crate::Reader;
+use crate::data_formats::palette::ARENA_PALETTE_START;
 use crate::data_formats::{Texture, Wav, image_formats::parse_animation};
-use crate::{OutputWriter, Reader};
 
 /// FTI files mostly contain fonts and strings, but also have a few other things.
 pub struct Fti<'a> {
 	pub arrow: Texture<'a>,
+	/// `SYS_PAL`, see [Fti::system_palette]
 	pub palette: &'a [u8],
 	pub snd_push: Option<Wav<'a>>,
 	pub font_big: Vec<FontLetter<&'a [u8]>>,
@@ -54,7 +58,7 @@ impl<'a> Fti<'a> {
 					snd_push = Some(Wav::parse(&mut reader));
 				}
 				"SYS_PAL" => {
-					palette = Some(reader.slice(64 * 3));
+					palette = Some(reader.slice(ARENA_PALETTE_START * 3));
 				}
 				"F8" => {
 					font_8 = Some(parse_small_font(&mut reader));
@@ -84,6 +88,13 @@ impl<'a> Fti<'a> {
 		}
 	}
 
+	/// The first 64 colours of every palette in the game (`SYS_PAL`, only in `mdkfont.fti`),
+	/// which replace the ones in each level's palette. See [PaletteStack](crate::data_formats::palette::PaletteStack) for how they're layered.
+	pub fn system_palette(&self) -> &'a [u8] {
+		self.palette
+	}
+
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter) {
 		output.write_png(
 			"ARROW",
@@ -198,6 +209,7 @@ fn parse_small_font(reader: &mut Reader) -> Vec<FontLetter<Vec<u8>>> {
 		.collect()
 }
 
+#[cfg(feature = "export")]
 fn save_font_as<Pixels: AsRef<[u8]>>(
 	name: &str, font: &[FontLetter<Pixels>], output: &mut OutputWriter, pal: &[u8],
 ) {
@@ -0,0 +1,165 @@
+//! A searchable index of every named asset in the game files.
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::asset_list::find_files;
+use super::{FileType, glob_matches};
+use super::{ParseCache, list_assets_cached};
+use crate::Name;
+use crate::data_formats::image_formats;
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct AssetIndex {
+	pub assets: Vec<IndexedAsset>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IndexedAsset {
+	pub name: String,
+	pub kind: Name,
+	/// Path of the game file, relative to the asset root
+	pub file: Name,
+	/// Offset of the asset's name in the game file, if known
+	#[serde(skip_serializing_if = "Option::is_none", default)]
+	pub offset: Option<usize>,
+	/// Contents of string assets, for full-text search
+	#[serde(skip_serializing_if = "Option::is_none", default)]
+	pub text: Option<String>,
+}
+
+/// A name query, either a glob (`GUNT*`, `?_LEV3`) or a regex (`/^GUNT_\d+$/`).
+///
+/// Globs match the whole name case-insensitively, regexes can match anywhere.
+pub enum AssetQuery {
+	Glob(String),
+	Regex(regex::Regex),
+}
+
+impl AssetQuery {
+	pub fn parse(query: &str) -> Self {
+		if let Some(pattern) = query.strip_prefix('/').and_then(|q| q.strip_suffix('/')) {
+			match regex::Regex::new(pattern) {
+				Ok(regex) => AssetQuery::Regex(regex),
+				Err(e) => panic!("invalid regex {pattern}: {e}"),
+			}
+		} else {
+			AssetQuery::Glob(query.to_ascii_uppercase())
+		}
+	}
+
+	pub fn matches(&self, text: &str) -> bool {
+		match self {
+			AssetQuery::Glob(glob) => {
+				glob_matches(glob.as_bytes(), text.to_ascii_uppercase().as_bytes())
+			}
+			AssetQuery::Regex(regex) => regex.is_match(text),
+		}
+	}
+	/// Like [Self::matches] but globs can match anywhere in the text
+	pub fn matches_text(&self, text: &str) -> bool {
+		match self {
+			AssetQuery::Glob(glob) => {
+				let text = text.to_ascii_uppercase();
+				(0..text.len())
+					.any(|start| glob_prefix_matches(glob.as_bytes(), &text.as_bytes()[start..]))
+			}
+			AssetQuery::Regex(regex) => regex.is_match(text),
+		}
+	}
+}
+
+/// Whether the glob matches the start of the text
+fn glob_prefix_matches(glob: &[u8], text: &[u8]) -> bool {
+	match glob.split_first() {
+		None => true,
+		Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_prefix_matches(rest, &text[skip..])),
+		Some((b'?', rest)) => !text.is_empty() && glob_prefix_matches(rest, &text[1..]),
+		Some((c, rest)) => text.first() == Some(c) && glob_prefix_matches(rest, &text[1..]),
+	}
+}
+
+impl AssetIndex {
+	/// Indexes every recognised game file under `root`
+	pub fn build(root: &Path) -> Self {
+		Self::build_with_cache(root, &ParseCache::new())
+	}
+	/// Like [Self::build], but only lists the assets of files that aren't in `cache` yet
+	pub fn build_with_cache(root: &Path, cache: &ParseCache) -> Self {
+		let mut assets = Vec::new();
+		for (file, path) in find_files(root) {
+			let data = std::fs::read(&path)
+				.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+			let Some(file_type) = FileType::sniff(&path, &data) else {
+				if let Some(image) = image_formats::sniff(&data) {
+					assets.push(IndexedAsset {
+						name: path.file_stem().unwrap().to_string_lossy().into_owned(),
+						kind: Name::new(&format!("Images/{}", image.kind())),
+						file: Name::new(&file),
+						offset: None,
+						text: None,
+					});
+				}
+				continue;
+			};
+			for info in list_assets_cached(cache, file_type, &data).assets.values() {
+				let text = (info.kind == "Strings").then(|| info.lines.join("\n"));
+				assets.push(IndexedAsset {
+					name: info.name.clone(),
+					kind: Name::new(&info.kind),
+					file: Name::new(&file),
+					offset: info.offset,
+					text,
+				});
+			}
+		}
+		Self { assets }
+	}
+
+	pub fn load(path: &Path) -> Self {
+		let data = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read index {}: {e}", path.display()));
+		serde_json::from_slice(&data)
+			.unwrap_or_else(|e| panic!("failed to parse index {}: {e}", path.display()))
+	}
+
+	pub fn to_json(&self) -> String {
+		serde_json::to_string_pretty(self).unwrap()
+	}
+
+	/// Finds every asset whose name (or string contents) matches the query
+	pub fn find(&self, query: &AssetQuery) -> impl Iterator<Item = &IndexedAsset> {
+		self.assets.iter().filter(|asset| {
+			query.matches(&asset.name)
+				|| asset
+					.text
+					.as_deref()
+					.is_some_and(|text| query.matches_text(text))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_query() {
+		let glob = AssetQuery::parse("gunt*");
+		assert!(glob.matches("GUNT_10"));
+		assert!(glob.matches("gunt"));
+		assert!(!glob.matches("A_GUNT"));
+
+		let glob = AssetQuery::parse("?_LEV*3");
+		assert!(glob.matches("A_LEVEL3"));
+		assert!(!glob.matches("AB_LEVEL3"));
+
+		let regex = AssetQuery::parse(r"/^GUNT_\d+$/");
+		assert!(regex.matches("GUNT_10"));
+		assert!(!regex.matches("GUNT_A"));
+
+		let text = AssetQuery::parse("gun?er");
+		assert!(text.matches_text("Hello Gunter!"));
+		assert!(!text.matches_text("Gun"));
+	}
+}
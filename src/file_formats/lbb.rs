@@ -1,4 +1,5 @@
-use crate::data_formats::{Texture, image_formats};
+use crate::data_formats::Texture;
+use crate::data_formats::image_formats;
 use crate::reader::Reader;
 
 /// LBB files are the loading images for each level.
@@ -9,7 +10,28 @@ pub struct Lbb<'a> {
 impl<'a> Lbb<'a> {
 	pub fn parse(mut reader: Reader<'a>) -> Self {
 		// no actual file structure here, just a raw palette image
-		let (palette, texture) = image_formats::try_parse_palette_image(&mut reader).unwrap();
+		// (not sniffed, since some palettes happen to look like the start of another image)
+		let (palette, texture) =
+			image_formats::try_parse_palette_image(&mut reader).expect("invalid lbb image");
 		Self { palette, texture }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data_formats::image_formats::{SniffedImage, sniff, write_basic_image};
+
+	#[test]
+	fn test_lbb() {
+		let texture = Texture::new(2, 1, vec![1, 2]);
+		// a palette that also reads as the header of a 2x385 image filling the rest of the file
+		let mut palette = [7; 0x300];
+		palette[..4].copy_from_slice(&[2, 0, 0x81, 1]);
+		let data = [&palette[..], &write_basic_image(&texture)].concat();
+		assert!(matches!(sniff(&data), Some(SniffedImage::Basic(_))));
+
+		let lbb = Lbb::parse(Reader::new(&data));
+		assert!(lbb.palette == palette && lbb.texture == texture);
+	}
+}
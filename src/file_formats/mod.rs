@@ -1,16 +1,266 @@
+mod asset_list;
 mod bni;
+mod cache;
+mod chunk_table;
 mod cmi;
+mod cmi_search;
+mod diff;
 mod dti;
+mod entity_stats;
 mod fti;
+#[cfg(feature = "cli")]
+mod index;
 mod lbb;
 pub mod mti;
 mod mto;
+#[cfg(feature = "export")]
+mod patch;
 mod sni;
-pub use bni::Bni;
+pub use asset_list::{AssetInfo, AssetList};
+pub use bni::{Bni, BniArchive};
+pub use cache::{ParseCache, ParseCacheStats, list_assets_cached};
+pub use chunk_table::{Chunk, ChunkTable};
 pub use cmi::Cmi;
-pub use dti::Dti;
+#[cfg(feature = "export")]
+pub use cmi::CmiFilter;
+pub use cmi_search::{CmiMatch, CmiQuery};
+pub use diff::diff_asset_roots;
+pub use dti::{Dti, DtiEntityData, DtiLighting};
+pub use entity_stats::{EntityStat, EntityStats};
 pub use fti::Fti;
+#[cfg(feature = "cli")]
+pub use index::{AssetIndex, AssetQuery};
 pub use lbb::Lbb;
 pub use mti::Mti;
 pub use mto::Mto;
+#[cfg(feature = "export")]
+pub use patch::{PatchedFile, apply_ips, build_patch, patch_report, write_ips};
 pub use sni::Sni;
+
+use std::path::Path;
+
+use crate::Reader;
+#[cfg(feature = "export")]
+use crate::coverage::{self, CoverageOutput};
+#[cfg(feature = "export")]
+use crate::data_formats::cmi_bytecode::CmiSymbols;
+#[cfg(feature = "export")]
+use crate::data_formats::image_formats;
+#[cfg(feature = "export")]
+use crate::{Endian, OutputWriter};
+
+/// Whether the glob (with `*` and `?` wildcards) matches all of the text
+#[cfg(feature = "export")]
+fn glob_matches(glob: &[u8], text: &[u8]) -> bool {
+	match glob.split_first() {
+		None => text.is_empty(),
+		Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_matches(rest, &text[skip..])),
+		Some((b'?', rest)) => !text.is_empty() && glob_matches(rest, &text[1..]),
+		Some((c, rest)) => text.first() == Some(c) && glob_matches(rest, &text[1..]),
+	}
+}
+
+/// The kinds of game files that can be extracted on their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+	Bni,
+	Cmi,
+	Dti,
+	Fti,
+	Lbb,
+	Mti,
+	Mto,
+	Sni,
+}
+
+impl FileType {
+	pub fn from_extension(ext: &str) -> Option<Self> {
+		Some(match ext.to_ascii_uppercase().as_str() {
+			"BNI" => Self::Bni,
+			"CMI" => Self::Cmi,
+			"DTI" => Self::Dti,
+			"FTI" => Self::Fti,
+			"LBB" => Self::Lbb,
+			"MTI" => Self::Mti,
+			"MTO" => Self::Mto,
+			"SNI" => Self::Sni,
+			_ => return None,
+		})
+	}
+
+	/// Works out the file type from its extension,
+	/// falling back to the filename embedded in the header (for CMI/DTI/MTI/MTO/SNI files).
+	pub fn sniff(path: &Path, data: &[u8]) -> Option<Self> {
+		if let Some(result) = path
+			.extension()
+			.and_then(|ext| ext.to_str())
+			.and_then(Self::from_extension)
+		{
+			return Some(result);
+		}
+
+		let mut reader = Reader::new_detect_endian(data);
+		reader.try_u32()?;
+		let embedded_name = reader.try_str(12)?;
+		let (_, ext) = embedded_name.rsplit_once('.')?;
+		Self::from_extension(ext)
+	}
+}
+
+#[cfg(feature = "export")]
+/// Parses any single game file and extracts everything in it to `output`.
+///
+/// If `coverage` is set, also writes a report of the bytes that weren't read.
+///
+/// Files that aren't a known type are saved as an image if they're one of the raw image layouts
+/// (see [image_formats::sniff]), otherwise this panics.
+pub fn extract_file(
+	path: &Path, output: &mut OutputWriter, symbols: &CmiSymbols, coverage: Option<CoverageOutput>,
+) {
+	let data = match std::fs::read(path) {
+		Ok(data) => data,
+		Err(e) => panic!("failed to read {}: {e}", path.display()),
+	};
+	output.set_source_file(path.display());
+
+	let Some(file_type) = FileType::sniff(path, &data) else {
+		// loose images (e.g. pulled out of a BNI by another tool)
+		let Some(image) = image_formats::sniff(&data) else {
+			panic!("unknown file type for {}", path.display());
+		};
+		println!("Found a {} image in {}", image.kind(), path.display());
+		let name = path.file_stem().unwrap().to_string_lossy();
+		image.save_as(&name, output, None);
+		return;
+	};
+
+	let Some(coverage_output) = coverage else {
+		extract_data(path, file_type, &data, output, symbols);
+		return;
+	};
+	let ((), coverage) = coverage::track(&data, || {
+		extract_data(path, file_type, &data, output, symbols)
+	});
+	let name = path.file_name().unwrap().to_string_lossy();
+	println!(
+		"Read {} of {} bytes of {name}",
+		coverage.read_len(),
+		coverage.len()
+	);
+	coverage.save(&name, &data, coverage_output, output);
+}
+
+#[cfg(feature = "export")]
+/// Like [extract_file] for a CMI file, but only saves the entities (and arenas) matching `filter`
+pub fn extract_cmi(
+	path: &Path, output: &mut OutputWriter, symbols: &CmiSymbols, filter: &CmiFilter,
+) {
+	let data = match std::fs::read(path) {
+		Ok(data) => data,
+		Err(e) => panic!("failed to read {}: {e}", path.display()),
+	};
+	output.set_source_file(path.display());
+	if FileType::sniff(path, &data) != Some(FileType::Cmi) {
+		panic!("{} isn't a CMI file", path.display());
+	}
+	let cmi = Cmi::parse_with_symbols(Reader::new_detect_endian(&data), symbols);
+	let saved = cmi.save_filtered(output, filter);
+	println!("Saved {saved} of {} entities", cmi.entities.len());
+}
+
+#[cfg(feature = "export")]
+fn extract_data(
+	path: &Path, file_type: FileType, data: &[u8], output: &mut OutputWriter, symbols: &CmiSymbols,
+) {
+	let reader = Reader::new_detect_endian(data);
+	if reader.endian() == Endian::Big {
+		println!("Detected big endian data in {}", path.display());
+	}
+	match file_type {
+		FileType::Bni => Bni::parse(reader).save(output, false),
+		FileType::Cmi => {
+			let cmi = Cmi::parse_with_symbols(reader, symbols);
+			cmi.save(output);
+			cmi.save_opcode_report(output);
+			cmi.save_script_symbols(output);
+			cmi.save_traces(&mut output.push_dir("Traces"));
+			cmi.save_warnings(output);
+			cmi.save_relationships(output);
+		}
+		FileType::Dti => Dti::parse(reader).save(output),
+		FileType::Fti => Fti::parse(reader).save(output),
+		FileType::Lbb => {
+			let lbb = Lbb::parse(reader);
+			let name = path.file_stem().unwrap().to_string_lossy();
+			lbb.texture.save_as(&name, output, Some(lbb.palette));
+		}
+		FileType::Mti => Mti::parse(reader).save(output, None),
+		FileType::Mto => Mto::parse(reader).save(output),
+		FileType::Sni => Sni::parse(reader).save(output),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{MtiBuilder, MtoBuilder, samples};
+
+	#[test]
+	fn test_sniff() {
+		let mut files = samples();
+		files.push((
+			"SAMPLE.MTO",
+			MtoBuilder::new("SAMPLE.MTO")
+				.arena("ARENA1", &[0; 336], MtiBuilder::new("ARENA1.MTI").build())
+				.build(),
+		));
+		for (name, data) in &files {
+			let expected = FileType::sniff(Path::new(name), &[]).unwrap();
+			// the extension wins if it's a known one
+			assert_eq!(FileType::sniff(Path::new(name), b"junk"), Some(expected));
+			let embedded = FileType::sniff(Path::new("SAMPLE"), data);
+			for path in ["SAMPLE.BIN", "dir.mti/SAMPLE", "SAMPLE."] {
+				assert_eq!(FileType::sniff(Path::new(path), data), embedded, "{path}");
+			}
+			if expected == FileType::Bni {
+				// no header to tell it from any other data
+				assert_eq!(embedded, None);
+			} else {
+				assert_eq!(embedded, Some(expected), "{name}");
+			}
+		}
+		assert_eq!(FileType::sniff(Path::new("SAMPLE.BIN"), &[1, 2, 3]), None);
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_extract_file_sniffed() {
+		let dir = Path::new("output/test_extract_file_sniffed");
+		let _ = std::fs::remove_dir_all(dir);
+		std::fs::create_dir_all(dir).unwrap();
+		let extract = |name: &str, data: &[u8], output_dir: &str| {
+			let path = dir.join(name);
+			std::fs::write(&path, data).unwrap();
+			let mut output = OutputWriter::new_in_dir(dir.join(output_dir));
+			extract_file(&path, &mut output, &CmiSymbols::default(), None);
+			asset_list::find_files(&dir.join(output_dir))
+				.into_keys()
+				.collect::<Vec<_>>()
+		};
+		for (name, data) in samples() {
+			if name.ends_with(".BNI") {
+				// only known by its extension, see test_sniff
+				continue;
+			}
+			let expected = extract(name, &data, &format!("{name} known"));
+			assert!(!expected.is_empty(), "{name}");
+			let stem = name.split_once('.').unwrap().0;
+			let bin = format!("{stem}.BIN");
+			let missing = format!("{name} missing/{stem}");
+			std::fs::create_dir_all(dir.join(&missing).parent().unwrap()).unwrap();
+			assert_eq!(extract(&bin, &data, &format!("{name} wrong")), expected);
+			assert_eq!(extract(&missing, &data, &format!("{name} none")), expected);
+		}
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
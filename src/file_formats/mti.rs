@@ -1,25 +1,152 @@
+use std::cell::OnceCell;
+use std::ops::Deref;
+
+#[cfg(feature = "export")]
+use crate::OutputWriter;
 use crate::data_formats::image_formats::{parse_basic_image, parse_overlay_animation};
 use crate::data_formats::{Pen, Texture};
-use crate::{OutputWriter, Reader};
+use crate::file_formats::ChunkTable;
+use crate::{Reader, Warnings};
 
 /// MTI files just store materials, containing both texture data
 /// and giving names to Pens (flat colours or engine materials)
+///
+/// ```
+/// use mdk_parse::Reader;
+/// use mdk_parse::file_formats::Mti;
+/// use mdk_parse::file_formats::mti::Material;
+///
+/// let mti = Mti::parse(Reader::new(include_bytes!("../../samples/SAMPLE.MTI")));
+/// for (name, material) in &mti.materials {
+///     match material {
+///         Material::Pen(pen) => println!("{name}: {pen:?}"),
+///         Material::Texture(texture, _) => println!("{name}: {}x{}", texture.width, texture.height),
+///         Material::AnimatedTexture(frames, _) => println!("{name}: {} frames", frames.len()),
+///     }
+/// }
+/// assert_eq!(mti.materials.len(), 3);
+///
+/// // MTIs don't have a palette, it comes from the level's other files
+/// # #[cfg(feature = "export")] {
+/// let palette: Vec<u8> = (0..=255).flat_map(|i| [i, i, i]).collect();
+/// let mut output = mdk_parse::OutputWriter::new_in_dir(std::env::temp_dir().join("SAMPLE.MTI"));
+/// mti.save(&mut output, Some(&palette));
+/// # }
+/// ```
 pub struct Mti<'a> {
 	pub filename: &'a str,
 	pub materials: Vec<(&'a str, Material<'a>)>,
+	pub warnings: Warnings<'a>,
 }
 
 pub enum Material<'a> {
 	Pen(Pen),
-	Texture(Texture<'a>, MaterialFlags),
-	AnimatedTexture(Vec<Texture<'a>>, MaterialFlags),
+	Texture(Lazy<'a, Texture<'a>>, MaterialFlags),
+	AnimatedTexture(Lazy<'a, Vec<Texture<'a>>>, MaterialFlags),
+}
+
+/// Texture data that's only decoded the first time it's used,
+/// so listing materials doesn't pay for decoding every texture
+pub struct Lazy<'a, T> {
+	reader: Reader<'a>,
+	decode: fn(&mut Reader<'a>) -> T,
+	value: OnceCell<T>,
+}
+
+impl<'a, T> Lazy<'a, T> {
+	fn new(reader: Reader<'a>, decode: fn(&mut Reader<'a>) -> T) -> Self {
+		Self {
+			reader,
+			decode,
+			value: OnceCell::new(),
+		}
+	}
+	/// Already decoded
+	fn decoded(reader: Reader<'a>, value: T) -> Self {
+		Self {
+			reader,
+			decode: |_| unreachable!(),
+			value: OnceCell::from(value),
+		}
+	}
+	/// The undecoded data, starting at the texture
+	pub fn data(&self) -> &'a [u8] {
+		self.reader.remaining_buf()
+	}
+	pub fn is_decoded(&self) -> bool {
+		self.value.get().is_some()
+	}
+}
+
+impl<T> Deref for Lazy<'_, T> {
+	type Target = T;
+	fn deref(&self) -> &T {
+		self.value
+			.get_or_init(|| (self.decode)(&mut self.reader.clone()))
+	}
 }
 
 /// Some metadata per material, no idea what most of it means
 pub struct MaterialFlags {
 	pub a: f32,
 	pub b: f32,
-	pub flags: u32,
+	pub flags: MtiFlags,
+}
+
+/// The flags on a texture material.
+///
+/// Only the bits picking how the texture is stored are understood so far,
+/// anything else is kept as [MtiFlags::unknown].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MtiFlags(u32);
+
+impl MtiFlags {
+	/// Frames stored one after another
+	pub const ANIMATED: Self = Self(1 << 16);
+	/// Frames stored as changes drawn over the first one
+	pub const OVERLAY: Self = Self(1 << 17);
+
+	const NAMES: [(Self, &'static str); 2] =
+		[(Self::ANIMATED, "animated"), (Self::OVERLAY, "overlay")];
+
+	pub const fn from_bits(bits: u32) -> Self {
+		Self(bits)
+	}
+	pub const fn bits(self) -> u32 {
+		self.0
+	}
+	pub const fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+	/// The bits without a name
+	pub fn unknown(self) -> u32 {
+		Self::NAMES
+			.iter()
+			.fold(self.0, |bits, (flag, _)| bits & !flag.0)
+	}
+	/// The name of each flag that's set, then any unknown bits in hex
+	pub fn names(self) -> Vec<String> {
+		let mut result: Vec<String> = Self::NAMES
+			.iter()
+			.filter(|(flag, _)| self.contains(*flag))
+			.map(|(_, name)| name.to_string())
+			.collect();
+		if self.unknown() != 0 {
+			result.push(format!("{:#x}", self.unknown()));
+		}
+		result
+	}
+}
+
+impl std::fmt::Display for MtiFlags {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let names = self.names();
+		if names.is_empty() {
+			f.write_str("none")
+		} else {
+			f.write_str(&names.join("|"))
+		}
+	}
 }
 
 impl<'a> Mti<'a> {
@@ -33,24 +160,24 @@ impl<'a> Mti<'a> {
 		assert_eq!(filesize, filesize2 + 8, "filesizes do not match");
 		let num_entries = reader.u32() as usize;
 
-		let mut materials: Vec<(&str, Material)> = Vec::with_capacity(num_entries);
-
+		enum Entry {
+			Pen(Pen),
+			Texture { a: f32, b: f32, flags: u32 },
+		}
+		let mut entries = Vec::with_capacity(num_entries);
+		let mut texture_offsets = Vec::new();
 		for _ in 0..num_entries {
 			let name = reader.str(8);
 			let flags = reader.u32();
 
 			if flags == 0xFFFFFFFF {
 				// pen
-				let pen_value = match reader.i32() {
-					0 => Pen::Colour(0),
-					n @ 1.. => Pen::new(-n), // negate to match mesh tri values
-					n => Pen::Unknown(n),    // todo negative?
-				};
+				let pen_value = Pen::from_mti_value(reader.i32());
 
 				let padding1 = reader.u32(); // padding
 				let padding2 = reader.u32();
 				assert!(padding1 == 0 && padding2 == 0);
-				materials.push((name, Material::Pen(pen_value)));
+				entries.push((name, Entry::Pen(pen_value)));
 				continue;
 			}
 
@@ -58,38 +185,49 @@ impl<'a> Mti<'a> {
 			let a = reader.f32(); // todo what is this
 			let b = reader.f32(); // todo what is this
 			let start_offset = reader.u32() as usize;
+			texture_offsets.push((name, start_offset, None));
+			entries.push((name, Entry::Texture { a, b, flags }));
+		}
 
-			const MAT_TYPE_IMAGE: u32 = 0;
-			const MAT_TYPE_AMIMATED: u32 = 1;
-			const MAT_TYPE_OVERLAY_IMAGE: u32 = 2;
-			let mat_type = (flags >> 16) & 3;
-			let flags_rest = flags & !0x30000;
-
-			let matflags = MaterialFlags {
-				a,
-				b,
-				flags: flags_rest,
+		let mut warnings = Warnings::new(reader.buf());
+		let table = ChunkTable::new(&reader, texture_offsets);
+		let mut textures = table.iter();
+		let mut materials: Vec<(&str, Material)> = Vec::with_capacity(num_entries);
+		for (name, entry) in entries {
+			let (a, b, flags) = match entry {
+				Entry::Pen(pen) => {
+					materials.push((name, Material::Pen(pen)));
+					continue;
+				}
+				Entry::Texture { a, b, flags } => (a, b, flags),
 			};
 
-			let mut entry_reader = reader.clone_at(start_offset);
-			let result = match mat_type {
-				MAT_TYPE_IMAGE => Material::Texture(parse_basic_image(&mut entry_reader), matflags),
-				MAT_TYPE_AMIMATED => {
-					let num_frames = entry_reader.u32();
-					let width = entry_reader.u16();
-					let height = entry_reader.u16();
-					let frame_size = width as usize * height as usize;
-					let frames = (0..num_frames)
-						.map(|_| Texture::new(width, height, entry_reader.slice(frame_size)))
-						.collect();
-					Material::AnimatedTexture(frames, matflags)
+			let flags = MtiFlags::from_bits(flags);
+			let matflags = MaterialFlags { a, b, flags };
+
+			let entry_reader = textures.next().unwrap().reader.clone();
+			assert!(
+				texture_fits(&entry_reader, flags.contains(MtiFlags::ANIMATED)),
+				"invalid texture data for {name}"
+			);
+			let result = match (
+				flags.contains(MtiFlags::ANIMATED),
+				flags.contains(MtiFlags::OVERLAY),
+			) {
+				(false, false) => {
+					Material::Texture(Lazy::new(entry_reader, parse_basic_image), matflags)
 				}
-				MAT_TYPE_OVERLAY_IMAGE => {
-					// this is only used for the M_COMM terminal thing that calls the bomber aircraft
-					let frames = parse_overlay_animation(&mut entry_reader);
-					Material::AnimatedTexture(frames, matflags)
+				(true, false) => Material::AnimatedTexture(
+					Lazy::new(entry_reader, parse_animated_texture),
+					matflags,
+				),
+				(false, true) => {
+					// this is only used for the M_COMM terminal thing that calls the bomber aircraft,
+					// and is decoded straight away to keep its warnings
+					let frames = parse_overlay_animation(&mut entry_reader.clone(), &mut warnings);
+					Material::AnimatedTexture(Lazy::decoded(entry_reader, frames), matflags)
 				}
-				_ => panic!("unknown mti material type on {name}"),
+				(true, true) => panic!("unknown mti material type on {name}"),
 			};
 
 			materials.push((name, result));
@@ -102,6 +240,7 @@ impl<'a> Mti<'a> {
 		Mti {
 			filename,
 			materials,
+			warnings,
 		}
 	}
 
@@ -109,6 +248,7 @@ impl<'a> Mti<'a> {
 		self.materials.is_empty()
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter, palette: Option<&[u8]>) {
 		for (name, material) in &self.materials {
 			match material {
@@ -124,6 +264,7 @@ impl<'a> Mti<'a> {
 		self.save_report(output);
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save_report(&self, output: &mut OutputWriter) {
 		use std::fmt::Write;
 		let mut pens_summary = String::from("name    \tvalue\n");
@@ -138,11 +279,11 @@ impl<'a> Mti<'a> {
 					writeln!(pens_summary, "{name:8}\t{pen:?}").unwrap()
 				}
 				Material::Texture(_, flags) | Material::AnimatedTexture(_, flags) => {
-					if flags.a != 0.0 || flags.b != 3.5 || flags.flags != 0 {
+					if flags.a != 0.0 || flags.b != 3.5 || flags.flags.unknown() != 0 {
 						has_flags = true;
 						writeln!(
 							flags_summary,
-							"{name:8}\t{:5}\t{:3}\t{:x}",
+							"{name:8}\t{:5}\t{:3}\t{}",
 							flags.a, flags.b, flags.flags
 						)
 						.unwrap();
@@ -157,5 +298,121 @@ impl<'a> Mti<'a> {
 		if has_flags {
 			output.write("texture_flags", "txt", &flags_summary);
 		}
+		self.warnings.save_as("warnings", output);
+	}
+}
+
+/// Whether a texture's pixels fit in its entry, checked while parsing
+/// so corrupt files fail there instead of the first time the texture is used
+fn texture_fits(reader: &Reader, animated: bool) -> bool {
+	let mut reader = reader.clone();
+	let num_frames = if animated { reader.try_u32() } else { Some(1) };
+	let (Some(num_frames), Some(width), Some(height)) =
+		(num_frames, reader.try_u16(), reader.try_u16())
+	else {
+		return false;
+	};
+	let size = num_frames as u64 * width as u64 * height as u64;
+	size <= reader.remaining_len() as u64
+}
+
+/// Frames stored one after another
+fn parse_animated_texture<'a>(reader: &mut Reader<'a>) -> Vec<Texture<'a>> {
+	let num_frames = reader.u32();
+	let width = reader.u16();
+	let height = reader.u16();
+	let frame_size = width as usize * height as usize;
+	(0..num_frames)
+		.map(|_| Texture::new(width, height, reader.slice(frame_size)))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::MtiBuilder;
+
+	#[test]
+	fn test_parse() {
+		let data = MtiBuilder::new("LEVEL3.MTI")
+			.pen("BLACK", 0)
+			.pen("SHINY", 3)
+			.texture("WALL", 2, 3, &[1, 2, 3, 4, 5, 6])
+			.flags(1.0, 2.0, 0x10)
+			.animated_texture("WATER", 1, 2, &[&[1, 2], &[3, 4]])
+			.build();
+		let mti = Mti::parse(Reader::new(&data));
+
+		assert_eq!(mti.filename, "LEVEL3.MTI");
+		let names: Vec<&str> = mti.materials.iter().map(|(name, _)| *name).collect();
+		assert_eq!(names, ["BLACK", "SHINY", "WALL", "WATER"]);
+
+		assert!(matches!(mti.materials[0].1, Material::Pen(Pen::Colour(0))));
+		assert!(matches!(mti.materials[1].1, Material::Pen(pen) if pen == Pen::new(-3)));
+		let Material::Texture(texture, flags) = &mti.materials[2].1 else {
+			panic!("expected a texture");
+		};
+		assert!(!texture.is_decoded());
+		assert_eq!(texture.data(), [2, 0, 3, 0, 1, 2, 3, 4, 5, 6]);
+		assert_eq!((texture.width, texture.height), (2, 3));
+		assert!(texture.is_decoded());
+		assert_eq!(&texture.pixels[..], [1, 2, 3, 4, 5, 6]);
+		assert_eq!((flags.a, flags.b, flags.flags.bits()), (1.0, 2.0, 0x10));
+		assert_eq!(flags.flags.to_string(), "0x10");
+		let Material::AnimatedTexture(frames, flags) = &mti.materials[3].1 else {
+			panic!("expected an animated texture");
+		};
+		assert!(flags.flags.contains(MtiFlags::ANIMATED));
+		assert_eq!(flags.flags.names(), ["animated"]);
+		let frames: Vec<&[u8]> = frames.iter().map(|f| &f.pixels[..]).collect();
+		assert_eq!(frames, [[1, 2], [3, 4]]);
+	}
+
+	#[test]
+	fn test_flags() {
+		let flags = MtiFlags::from_bits(MtiFlags::OVERLAY.bits() | 0x104);
+		assert!(flags.contains(MtiFlags::OVERLAY));
+		assert!(!flags.contains(MtiFlags::ANIMATED));
+		assert_eq!(flags.unknown(), 0x104);
+		assert_eq!(flags.names(), ["overlay", "0x104"]);
+		assert_eq!(flags.to_string(), "overlay|0x104");
+		assert_eq!(MtiFlags::default().to_string(), "none");
+		assert_eq!(MtiFlags::ANIMATED.unknown(), 0);
+	}
+
+	#[test]
+	#[cfg(feature = "export")]
+	fn test_report() {
+		let data = MtiBuilder::new("LEVEL3.MTI")
+			.pen("BLACK", 0)
+			.texture("WALL", 1, 1, &[1])
+			.animated_texture("WATER", 1, 1, &[&[1], &[2]])
+			.flags(0.0, 3.5, 0x20)
+			.build();
+		let mti = Mti::parse(Reader::new(&data));
+		let dir = std::path::Path::new("output/test_mti_report");
+		let _ = std::fs::remove_dir_all(dir);
+		mti.save_report(&mut OutputWriter::new_in_dir(dir));
+
+		// only materials with unusual values are listed
+		let report = std::fs::read_to_string(dir.join("texture_flags.txt")).unwrap();
+		assert_eq!(
+			report,
+			"name    \ta    \tb  \tflags\nWATER   \t    0\t3.5\tanimated|0x20\n"
+		);
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	#[should_panic = "invalid texture data for WATER"]
+	fn test_corrupt_texture() {
+		let mut data = MtiBuilder::new("LEVEL3.MTI")
+			.texture("WALL", 1, 1, &[1])
+			.animated_texture("WATER", 1, 2, &[&[1, 2], &[3, 4]])
+			.build();
+		// claim more frames than there are, which fails when parsing rather than on first use
+		let frames = data.len() - 12 - 8 - 4;
+		data[frames] = 200;
+		Mti::parse(Reader::new(&data));
 	}
 }
@@ -1,6 +1,8 @@
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+use crate::Reader;
 use crate::data_formats::{Animation, Bsp, Mesh, Wav};
-use crate::file_formats::Mti;
-use crate::{OutputWriter, Reader};
+use crate::file_formats::{ChunkTable, Mti};
 
 /// MTO files contain per-arena assets
 pub struct Mto<'a> {
@@ -30,22 +32,31 @@ impl<'a> Mto<'a> {
 
 		let mut arenas = Vec::with_capacity(num_arenas);
 
-		for _ in 0..num_arenas {
-			let arena_name = reader.str(8);
-			let arena_offset = reader.u32() as usize;
+		let arena_entries: Vec<_> = (0..num_arenas)
+			.map(|_| {
+				let name = reader.str(8);
+				let offset = reader.u32() as usize;
+				// each arena starts with its size
+				let size = reader.clone_at(offset).u32() as usize + 4;
+				(name, offset, Some(size))
+			})
+			.collect();
+		let arena_table = ChunkTable::new(&reader, arena_entries);
+		arena_table.validate();
 
-			let mut arena_reader = reader.resized(arena_offset..);
-			let asset_filesize = arena_reader.u32() as usize;
-			arena_reader.rebase_length(asset_filesize);
+		for arena in &arena_table {
+			let arena_name = arena.name;
+			let mut arena_reader = arena.reader.clone_at(4);
+			arena_reader.rebase();
 
 			let assets_offset = arena_reader.u32() as usize;
 			let pal_offset = arena_reader.u32() as usize;
 			let bsp_offset = arena_reader.u32() as usize;
 			let matfile_offset = arena_reader.position();
 
-			let mut animations;
-			let mut meshes;
-			let mut sounds;
+			let animations;
+			let meshes;
+			let sounds;
 			{
 				// parse assets
 				arena_reader.set_position(assets_offset);
@@ -56,38 +67,46 @@ impl<'a> Mto<'a> {
 				let num_meshes = assets_reader.u32() as usize;
 				let num_sounds = assets_reader.u32() as usize;
 
-				animations = Vec::with_capacity(num_animations);
-				meshes = Vec::with_capacity(num_meshes);
-				sounds = Vec::with_capacity(num_sounds);
-
-				for _ in 0..num_animations {
-					let name = assets_reader.str(8);
-					let offset = assets_reader.u32() as usize;
-
-					let anim = Animation::parse(&mut assets_reader.resized(offset..));
-					animations.push((name, anim));
-				}
-				for _ in 0..num_meshes {
-					let name = assets_reader.str(8);
-					let offset = assets_reader.u32() as usize;
-
-					let mut mesh_reader = assets_reader.resized(offset..);
-					let is_multimesh = mesh_reader.u32();
-					assert!(is_multimesh <= 1, "invalid multimesh value");
-					let mesh = Mesh::parse(&mut mesh_reader, is_multimesh != 0);
-					meshes.push((name, mesh));
+				// one table for everything, so unsized entries end where the next asset starts
+				let mut entries = Vec::with_capacity(num_animations + num_meshes + num_sounds);
+				for _ in 0..num_animations + num_meshes {
+					entries.push((assets_reader.str(8), assets_reader.u32() as usize, None));
 				}
+				let mut sound_flags = Vec::with_capacity(num_sounds);
 				for _ in 0..num_sounds {
 					let name = assets_reader.str(12);
-					let sound_flags = assets_reader.u32(); // todo
-					let sound_offset = assets_reader.u32() as usize;
-					let sound_length = assets_reader.u32() as usize;
-					let mut sound_reader =
-						assets_reader.resized(sound_offset..sound_offset + sound_length);
-					let mut wav = Wav::parse(&mut sound_reader);
-					wav.flags = sound_flags;
-					sounds.push((name, wav));
+					sound_flags.push(assets_reader.u32()); // todo
+					let offset = assets_reader.u32() as usize;
+					let length = assets_reader.u32() as usize;
+					entries.push((name, offset, Some(length)));
 				}
+				let table = ChunkTable::new(&assets_reader, entries);
+				table.validate();
+				let (anim_chunks, rest) = table.chunks.split_at(num_animations);
+				let (mesh_chunks, sound_chunks) = rest.split_at(num_meshes);
+
+				animations = anim_chunks
+					.iter()
+					.map(|chunk| (chunk.name, Animation::parse(&mut chunk.reader.clone())))
+					.collect();
+				meshes = mesh_chunks
+					.iter()
+					.map(|chunk| {
+						let mut mesh_reader = chunk.reader.clone();
+						let is_multimesh = mesh_reader.u32();
+						assert!(is_multimesh <= 1, "invalid multimesh value");
+						(chunk.name, Mesh::parse(&mut mesh_reader, is_multimesh != 0))
+					})
+					.collect();
+				sounds = sound_chunks
+					.iter()
+					.zip(sound_flags)
+					.map(|(chunk, flags)| {
+						let mut wav = Wav::parse(&mut chunk.reader.clone());
+						wav.flags = flags;
+						(chunk.name, wav)
+					})
+					.collect();
 			}
 
 			// parse palette
@@ -121,6 +140,7 @@ impl<'a> Mto<'a> {
 		Mto { filename, arenas }
 	}
 
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter) {
 		for arena in &self.arenas {
 			let mut output = output.push_dir(arena.name);
@@ -159,3 +179,43 @@ impl<'a> Mto<'a> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{MtiBuilder, MtoBuilder, mesh};
+
+	#[test]
+	fn test_parse() {
+		let palette: Vec<u8> = (0..336).map(|i| i as u8).collect();
+		let data = MtoBuilder::new("LEVEL3O.MTO")
+			.arena("ARENA1", &palette, MtiBuilder::new("ARENA1.MTI").build())
+			.mesh("DOOR", mesh(&["WALL", "FLOOR"]))
+			// odd sized mti to test the bsp alignment
+			.arena(
+				"ARENA2",
+				&[0; 336],
+				MtiBuilder::new("ARENA2.MTI")
+					.texture("WALL", 1, 1, &[1])
+					.build(),
+			)
+			.build();
+		let mto = Mto::parse(Reader::new(&data));
+		assert_eq!(mto.filename, "LEVEL3O.MTO");
+		let names: Vec<_> = mto.arenas.iter().map(|arena| arena.name).collect();
+		assert_eq!(names, ["ARENA1", "ARENA2"]);
+
+		let [arena1, arena2] = &mto.arenas[..] else {
+			panic!("expected two arenas");
+		};
+		assert_eq!(arena1.palette, palette);
+		let [(name, door)] = &arena1.meshes[..] else {
+			panic!("expected one mesh");
+		};
+		assert_eq!(*name, "DOOR");
+		assert_eq!(door.materials, ["WALL", "FLOOR"]);
+		assert!(arena2.meshes.is_empty());
+		assert_eq!(arena2.mti.filename, "ARENA2.MTI");
+		assert_eq!(arena2.mti.materials.len(), 1);
+	}
+}
@@ -0,0 +1,256 @@
+//! Builds mods from edited extracted assets, as repacked game files plus IPS patches
+//! against the originals for distributing them.
+//!
+//! The edited tree is laid out like `--extract` output, in a folder named after each file's path
+//! under the game folder (e.g. `TRAVERSE/TRAVSPRT.BNI/Textures/ARROW.png`).
+//! Files that weren't edited can be left in, only entries whose bytes change end up in the patch.
+//! So far only BNI textures and strings can be repacked.
+use std::fmt::Write;
+use std::path::Path;
+
+use super::asset_list::find_files;
+use super::{BniArchive, FileType};
+use crate::Reader;
+use crate::data_formats::PngImportOptions;
+
+/// IPS offsets are 24 bits
+const IPS_MAX_LEN: usize = 1 << 24;
+/// Record sizes are 16 bits
+const IPS_MAX_RECORD: usize = 0xFFFF;
+/// Offset and size, so matching runs shorter than this are cheaper to include in a record
+const IPS_RECORD_HEADER: usize = 5;
+/// A record at this offset would read as the end of the patch
+const IPS_EOF: usize = 0x454F46;
+
+/// A game file with some of its entries replaced
+pub struct PatchedFile {
+	/// Path under the game folder, uppercase
+	pub path: String,
+	/// Entries that changed
+	pub entries: Vec<String>,
+	/// The whole repacked file
+	pub data: Vec<u8>,
+	/// The changes as an IPS patch, if the file is small enough for one
+	pub ips: Option<Vec<u8>>,
+}
+
+/// Repacks every game file under `game_root` that has edited entries under `edited_root`
+pub fn build_patch(
+	game_root: &Path, edited_root: &Path, options: PngImportOptions,
+) -> Vec<PatchedFile> {
+	let game_files = find_files(game_root);
+	let edited_files = find_files(edited_root);
+
+	let mut result = Vec::new();
+	for (file, path) in &game_files {
+		if FileType::from_extension(file.rsplit('.').next().unwrap()) != Some(FileType::Bni) {
+			continue;
+		}
+		let prefix = format!("{file}/");
+		let edits: Vec<_> = edited_files
+			.range(prefix.clone()..)
+			.take_while(|(key, _)| key.starts_with(&prefix))
+			.collect();
+		if edits.is_empty() {
+			continue;
+		}
+
+		let original = std::fs::read(path)
+			.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+		let mut archive = BniArchive::parse(Reader::new(&original));
+		for (key, edited_path) in edits {
+			let (folder, name) = key[prefix.len()..].split_once('/').unwrap_or_default();
+			let (stem, ext) = name.rsplit_once('.').unwrap_or_default();
+			let Some(&(entry, _)) = archive
+				.entries
+				.iter()
+				.find(|(entry, _)| entry.eq_ignore_ascii_case(stem))
+			else {
+				continue;
+			};
+			let replaceable = match (folder, ext) {
+				("TEXTURES", "PNG") => archive.can_replace_texture(entry),
+				("STRINGS", "TXT") => true,
+				_ => false,
+			};
+			if replaceable {
+				archive.inject_file(entry, edited_path, options);
+			}
+		}
+
+		let unchanged = BniArchive::parse(Reader::new(&original));
+		let entries: Vec<String> = archive
+			.entries
+			.iter()
+			.zip(&unchanged.entries)
+			.filter(|((_, data), (_, original))| data != original)
+			.map(|((name, _), _)| name.to_string())
+			.collect();
+		if entries.is_empty() {
+			continue;
+		}
+		let data = archive.write();
+		result.push(PatchedFile {
+			path: file.clone(),
+			entries,
+			ips: write_ips(&original, &data),
+			data,
+		});
+	}
+	result
+}
+
+/// Each patched file followed by its changed entries
+pub fn patch_report(files: &[PatchedFile]) -> String {
+	let mut result = String::new();
+	for file in files {
+		writeln!(result, "{}", file.path).unwrap();
+		for entry in &file.entries {
+			writeln!(result, "\t{entry}").unwrap();
+		}
+	}
+	if result.is_empty() {
+		result.push_str("No changes\n");
+	}
+	result
+}
+
+/// An IPS patch turning `original` into `modified`, or `None` if `modified` is too large for one
+pub fn write_ips(original: &[u8], modified: &[u8]) -> Option<Vec<u8>> {
+	if modified.len() > IPS_MAX_LEN {
+		return None;
+	}
+	let differs = |i: usize| original.get(i) != Some(&modified[i]);
+	let mut result = b"PATCH".to_vec();
+	let mut pos = 0;
+	while pos < modified.len() {
+		if !differs(pos) {
+			pos += 1;
+			continue;
+		}
+		let start = if pos == IPS_EOF { pos - 1 } else { pos };
+		let mut end = pos + 1;
+		loop {
+			while end < modified.len() && end - start < IPS_MAX_RECORD && differs(end) {
+				end += 1;
+			}
+			// bridge short matching runs rather than starting a new record
+			let gap = (end..modified.len())
+				.take(IPS_RECORD_HEADER)
+				.take_while(|&i| !differs(i))
+				.count();
+			let next = end + gap;
+			if gap < IPS_RECORD_HEADER && next < modified.len() && next - start < IPS_MAX_RECORD {
+				end = next;
+			} else {
+				break;
+			}
+		}
+		result.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+		result.extend_from_slice(&((end - start) as u16).to_be_bytes());
+		result.extend_from_slice(&modified[start..end]);
+		pos = end;
+	}
+	result.extend_from_slice(b"EOF");
+	if modified.len() < original.len() {
+		// truncation extension
+		result.extend_from_slice(&(modified.len() as u32).to_be_bytes()[1..]);
+	}
+	Some(result)
+}
+
+/// Applies an IPS patch, the inverse of [write_ips]
+pub fn apply_ips(original: &[u8], patch: &[u8]) -> Vec<u8> {
+	let mut reader = Reader::new(patch);
+	let be24 = |reader: &mut Reader| {
+		let [a, b, c] = reader.get::<[u8; 3]>();
+		u32::from_be_bytes([0, a, b, c]) as usize
+	};
+	assert_eq!(reader.slice(5), b"PATCH", "not an ips patch");
+	let mut result = original.to_vec();
+	loop {
+		if reader.remaining_buf().starts_with(b"EOF") {
+			reader.skip(3);
+			break;
+		}
+		let offset = be24(&mut reader);
+		let size = u16::from_be_bytes(reader.get()) as usize;
+		let (size, data) = if size == 0 {
+			// run length encoded
+			let size = u16::from_be_bytes(reader.get()) as usize;
+			(size, vec![reader.u8(); size])
+		} else {
+			(size, reader.slice(size).to_vec())
+		};
+		if result.len() < offset + size {
+			result.resize(offset + size, 0);
+		}
+		result[offset..offset + size].copy_from_slice(&data);
+	}
+	if reader.remaining_len() == 3 {
+		result.truncate(be24(&mut reader));
+	}
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_ips() {
+		let original: Vec<u8> = (0..=255).collect();
+		let mut modified = original.clone();
+		modified[10] = 0;
+		modified[13] = 0; // close enough to share a record
+		modified[100] = 0;
+		modified.extend_from_slice(&[1, 2, 3]);
+		let patch = write_ips(&original, &modified).unwrap();
+		assert_eq!(patch.len(), 5 + (5 + 4) + (5 + 1) + (5 + 3) + 3);
+		assert_eq!(apply_ips(&original, &patch), modified);
+
+		let truncated = &original[..200];
+		let patch = write_ips(&original, truncated).unwrap();
+		assert_eq!(patch, b"PATCHEOF\x00\x00\xC8");
+		assert_eq!(apply_ips(&original, &patch), truncated);
+	}
+
+	#[test]
+	fn test_build_patch() {
+		use crate::data_formats::Texture;
+		use crate::test_support::samples;
+		use std::fs;
+
+		let dir = Path::new("output/test_build_patch");
+		let _ = fs::remove_dir_all(dir);
+		let (_, bni) = samples()
+			.into_iter()
+			.find(|(name, _)| *name == "SAMPLE.BNI")
+			.unwrap();
+		fs::create_dir_all(dir.join("game/MISC")).unwrap();
+		fs::write(dir.join("game/MISC/SAMPLE.BNI"), &bni).unwrap();
+		let textures = dir.join("edited/MISC/SAMPLE.BNI/Textures");
+		fs::create_dir_all(&textures).unwrap();
+		let palette: Vec<u8> = (0..=255u8).flat_map(|i| [i, i, i]).collect();
+		let options = PngImportOptions::default();
+		let output = crate::OutputWriter::new_in_dir(dir);
+
+		// an unedited texture isn't patched
+		let wall = Texture::new(2, 2, vec![1, 2, 3, 4]).create_png("WALL", &output, Some(&palette));
+		fs::write(textures.join("WALL.png"), wall).unwrap();
+		let files = build_patch(&dir.join("game"), &dir.join("edited"), options);
+		assert!(files.is_empty());
+
+		let wall = Texture::new(2, 2, vec![4, 3, 2, 1]).create_png("WALL", &output, Some(&palette));
+		fs::write(textures.join("WALL.png"), wall).unwrap();
+		let files = build_patch(&dir.join("game"), &dir.join("edited"), options);
+		assert_eq!(files.len(), 1);
+		assert_eq!(files[0].path, "MISC/SAMPLE.BNI");
+		assert_eq!(files[0].entries, ["WALL"]);
+		let ips = files[0].ips.as_ref().unwrap();
+		assert_eq!(apply_ips(&bni, ips), files[0].data);
+		assert_eq!(patch_report(&files), "MISC/SAMPLE.BNI\n\tWALL\n");
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
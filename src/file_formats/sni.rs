@@ -1,13 +1,48 @@
+use std::io;
+
+#[cfg(feature = "export")]
+use crate::OutputWriter;
+use crate::Reader;
 use crate::data_formats::{Bsp, Texture, Wav, image_formats::parse_animation};
-use crate::{OutputWriter, Reader};
+use crate::file_formats::ChunkTable;
 
 /// SNI files primarily contain sounds, but they also contain BSP data for the inter-arena corridors
 /// as well as some 2d player animations
+///
+/// ```
+/// use mdk_parse::Reader;
+/// use mdk_parse::file_formats::Sni;
+///
+/// let sni = Sni::parse(Reader::new(include_bytes!("../../samples/SAMPLE.SNI")));
+/// let (name, wav) = &sni.sounds[0];
+/// assert_eq!(*name, "BEEP");
+/// assert_eq!((wav.num_channels, wav.samples_per_second), (1, 11025));
+///
+/// // save the sounds as wav files
+/// # #[cfg(feature = "export")] {
+/// let mut output = mdk_parse::OutputWriter::new_in_dir(std::env::temp_dir().join("SAMPLE.SNI"));
+/// sni.save(&mut output);
+/// # }
+/// ```
 pub struct Sni<'a> {
 	pub filename: &'a str,
 	pub sounds: Vec<(&'a str, Wav<'a>)>,
 	pub bsps: Vec<(&'a str, Bsp<'a>)>,
 	pub anims: Vec<(&'a str, Vec<Texture<'a>>)>,
+	/// Layout of every entry, in file order
+	pub entries: Vec<SniEntry<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SniEntry<'a> {
+	pub name: &'a str,
+	/// 0 for bsps, -1 for animations, sound flags otherwise
+	pub entry_type: u32,
+	/// Offset from the start of the file data (after the leading filesize)
+	pub offset: usize,
+	pub size: usize,
+	/// Whether the size was read from the entry data rather than the entry table
+	pub implied_size: bool,
 }
 
 impl<'a> Sni<'a> {
@@ -24,35 +59,49 @@ impl<'a> Sni<'a> {
 		let mut sounds = Vec::new();
 		let mut bsps = Vec::new();
 		let mut anims = Vec::new();
+		let mut entries = Vec::with_capacity(num_entries as usize);
 
-		let mut last_end = 0;
 		for _ in 0..num_entries {
-			let entry_name = reader.str(12);
+			let name = reader.str(12);
 			let entry_type = reader.u32();
-			let entry_offset = reader.u32() as usize;
-			let mut entry_size = reader.u32() as usize;
-			if entry_size == 0xFFFFFFFF {
-				entry_size = reader.clone_at(entry_offset).u32() as usize + 4;
+			let offset = reader.u32() as usize;
+			let mut size = reader.u32() as usize;
+			let implied_size = size == 0xFFFFFFFF;
+			if implied_size {
+				size = reader.clone_at(offset).u32() as usize + 4;
 			}
+			entries.push(SniEntry {
+				name,
+				entry_type,
+				offset,
+				size,
+				implied_size,
+			});
+		}
+		let table = ChunkTable::new(
+			&reader,
+			entries
+				.iter()
+				.map(|entry| (entry.name, entry.offset, Some(entry.size))),
+		);
+		table.validate();
 
-			last_end = last_end.max(entry_offset + entry_size);
-
-			let mut entry_reader = reader.resized(entry_offset..entry_offset + entry_size);
-
-			if entry_type == u32::MAX {
+		for (entry, chunk) in entries.iter().zip(&table) {
+			let mut entry_reader = chunk.reader.clone();
+			if entry.entry_type == u32::MAX {
 				let anim = parse_animation(&mut entry_reader);
-				anims.push((entry_name, anim));
-			} else if entry_type == 0 {
+				anims.push((entry.name, anim));
+			} else if entry.entry_type == 0 {
 				let bsp = Bsp::parse(&mut entry_reader);
-				bsps.push((entry_name, bsp));
+				bsps.push((entry.name, bsp));
 			} else {
 				let mut wav = Wav::parse(&mut entry_reader);
-				wav.flags = entry_type;
-				sounds.push((entry_name, wav));
+				wav.flags = entry.entry_type;
+				sounds.push((entry.name, wav));
 			}
 		}
 
-		last_end = last_end.max(reader.position()).next_multiple_of(4);
+		let last_end = table.data_end().max(reader.position()).next_multiple_of(4);
 		reader.set_position(last_end);
 		let filename2 = reader.str(12);
 		assert_eq!(filename, filename2, "incorrect sni footer");
@@ -62,9 +111,36 @@ impl<'a> Sni<'a> {
 			sounds,
 			bsps,
 			anims,
+			entries,
 		}
 	}
 
+	/// Combines sounds that are stored as consecutive chunks of one stream into a single wav file.
+	///
+	/// Fails with [io::ErrorKind::NotFound] if any of the sounds are missing,
+	/// or [io::ErrorKind::InvalidData] if they don't share the same format.
+	pub fn stitch_sounds(&self, names: &[&str]) -> io::Result<Vec<u8>> {
+		let chunks = names
+			.iter()
+			.map(|name| {
+				self.sounds
+					.iter()
+					.find(|(sound_name, _)| sound_name == name)
+					.map(|(_, wav)| wav)
+					.ok_or_else(|| {
+						io::Error::new(
+							io::ErrorKind::NotFound,
+							format!("missing sound chunk {name}"),
+						)
+					})
+			})
+			.collect::<io::Result<Vec<&Wav>>>()?;
+		let mut result = Vec::new();
+		Wav::write_stitched(&chunks, &mut result)?;
+		Ok(result)
+	}
+
+	#[cfg(feature = "export")]
 	pub fn save(&self, output: &mut OutputWriter) {
 		for (name, sound) in &self.sounds {
 			sound.save_as(name, output);
@@ -87,3 +163,92 @@ impl<'a> Sni<'a> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{SniBuilder, wav};
+
+	#[test]
+	fn test_parse() {
+		let data = SniBuilder::new("LEVEL3.SNI")
+			.sound("BANG", 0x10001, wav(1, 11025, 8, 11025))
+			// odd length to test the footer alignment
+			.sound("BEEP", 0x20002, wav(2, 22050, 16, 5))
+			.sound("CLICK", 1, wav(1, 8000, 8, 3))
+			.build();
+		let sni = Sni::parse(Reader::new(&data));
+
+		assert_eq!(sni.filename, "LEVEL3.SNI");
+		assert!(sni.bsps.is_empty() && sni.anims.is_empty());
+		let sounds: Vec<_> = sni
+			.sounds
+			.iter()
+			.map(|(name, wav)| {
+				(
+					*name,
+					wav.flags,
+					wav.num_channels,
+					wav.samples_per_second,
+					wav.bits_per_sample,
+					wav.duration_secs,
+				)
+			})
+			.collect();
+		assert_eq!(
+			sounds,
+			[
+				("BANG", 0x10001, 1, 11025, 8, 1.0),
+				("BEEP", 0x20002, 2, 22050, 16, 5.0 / 22050.0),
+				("CLICK", 1, 1, 8000, 8, 3.0 / 8000.0),
+			]
+		);
+		assert_eq!(sni.sounds[2].1.file_data, wav(1, 8000, 8, 3));
+	}
+
+	#[test]
+	fn test_stitch() {
+		let mut chunk1 = wav(2, 22050, 16, 3);
+		chunk1[44..].copy_from_slice(&[1; 12]);
+		let mut chunk2 = wav(2, 22050, 16, 2);
+		chunk2[44..].copy_from_slice(&[2; 8]);
+		let data = SniBuilder::new("STREAM.SNI")
+			.sound("MUSIC_0", 1, chunk1)
+			.sound("MUSIC_1", 1, chunk2)
+			.sound("OTHER", 1, wav(1, 11025, 8, 4))
+			.build();
+		let sni = Sni::parse(Reader::new(&data));
+
+		let layout: Vec<_> = sni.entries.iter().map(|e| (e.name, e.size)).collect();
+		assert_eq!(
+			layout,
+			[("MUSIC_0", 44 + 12), ("MUSIC_1", 44 + 8), ("OTHER", 44 + 4)]
+		);
+		assert_eq!(sni.entries[1].offset, sni.entries[0].offset + 44 + 12);
+		assert!(sni.entries.iter().all(|e| !e.implied_size));
+
+		let stitched = sni.stitch_sounds(&["MUSIC_0", "MUSIC_1"]).unwrap();
+		let mut expected = wav(2, 22050, 16, 5);
+		expected[44..].copy_from_slice(&[[1; 12].as_slice(), &[2; 8]].concat());
+		assert_eq!(stitched, expected);
+
+		let stitched = Wav::parse(&mut Reader::new(&stitched));
+		assert_eq!(stitched.duration_secs, 5.0 / 22050.0);
+	}
+
+	#[test]
+	fn test_stitch_mismatched() {
+		let data = SniBuilder::new("STREAM.SNI")
+			.sound("A", 1, wav(2, 22050, 16, 3))
+			.sound("B", 1, wav(1, 22050, 16, 3))
+			.build();
+		let sni = Sni::parse(Reader::new(&data));
+		let error = sni.stitch_sounds(&["A", "B"]).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+		assert_eq!(error.to_string(), "chunk 1 has a different format");
+		let error = sni.stitch_sounds(&["A", "C"]).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::NotFound);
+		let error = sni.stitch_sounds(&[]).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+	}
+}
@@ -0,0 +1,51 @@
+//! Entry points for the fuzz targets in `fuzz/`, only built with `--cfg fuzzing` (which `cargo fuzz` sets).
+//!
+//! The `try_` parsers return `None` on malformed data, so any panic from them is a bug.
+//! The whole-file parsers still assert their way through the data, so for now a panic from them
+//! only matters if it isn't one of their own asserts (out of bounds reads, overflows, huge allocations).
+use crate::Reader;
+use crate::data_formats::{Animation, Mesh, Wav, image_formats};
+use crate::file_formats::{Bni, Cmi, Dti, Fti, Lbb, Mti, Mto, Sni};
+
+fn reader(data: &[u8]) -> Reader<'_> {
+	Reader::new_detect_endian(data)
+}
+
+pub fn parse_bni_bytes(data: &[u8]) {
+	Bni::parse(reader(data));
+}
+pub fn parse_cmi_bytes(data: &[u8]) {
+	Cmi::parse(reader(data));
+}
+pub fn parse_dti_bytes(data: &[u8]) {
+	Dti::parse(reader(data));
+}
+pub fn parse_fti_bytes(data: &[u8]) {
+	Fti::parse(reader(data));
+}
+pub fn parse_lbb_bytes(data: &[u8]) {
+	Lbb::parse(reader(data));
+}
+pub fn parse_mti_bytes(data: &[u8]) {
+	Mti::parse(reader(data));
+}
+pub fn parse_mto_bytes(data: &[u8]) {
+	Mto::parse(reader(data));
+}
+pub fn parse_sni_bytes(data: &[u8]) {
+	Sni::parse(reader(data));
+}
+
+/// Runs every `try_` parser over the data
+pub fn try_parse_bytes(data: &[u8]) {
+	let _ = Wav::try_parse(&mut reader(data));
+	let _ = Animation::try_parse(&mut reader(data));
+	let _ = Mesh::try_parse(&mut reader(data), false);
+	let _ = Mesh::try_parse(&mut reader(data), true);
+	let _ = image_formats::try_parse_animation(&mut reader(data));
+	let _ = image_formats::try_parse_basic_image(&mut reader(data));
+	let _ = image_formats::try_parse_palette_image(&mut reader(data));
+	let _ = image_formats::try_parse_overlay_image(&mut reader(data));
+	let _ = image_formats::try_parse_rle_image(&mut reader(data));
+	let _ = image_formats::try_parse_crossfade_image(&mut reader(data));
+}
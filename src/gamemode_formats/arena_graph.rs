@@ -0,0 +1,199 @@
+//! Which arenas of a level lead to which, for mapping and routing.
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::OutputWriter;
+use crate::file_formats::{Cmi, Dti, DtiEntityData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum ArenaLinkKind {
+	/// A DTI `ArenaConnectZone`, whose value is taken to be the index of the arena it leads to
+	ConnectZone,
+	/// A script's `Show arena` instruction
+	ShowArena,
+	/// A script's `Load arena` instruction
+	LoadArena,
+	/// A corridor mesh `C{arena}`, taken to lead on to the next arena in the DTI
+	Corridor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ArenaLink<'a> {
+	pub from: &'a str,
+	pub to: &'a str,
+	pub kind: ArenaLinkKind,
+	/// The zone id, the entity running the script, or the corridor mesh
+	pub via: String,
+}
+
+/// The arenas of a level (in DTI order) and the links between them
+#[derive(Debug, Serialize)]
+pub struct ArenaGraph<'a> {
+	pub arenas: Vec<&'a str>,
+	pub links: Vec<ArenaLink<'a>>,
+}
+
+impl<'a> ArenaGraph<'a> {
+	/// `corridors` are the names of the level's corridor meshes (the BSPs in its `_O.SNI`)
+	pub fn build(dti: &Dti<'a>, cmi: &Cmi<'a>, corridors: &[&'a str]) -> Self {
+		let arenas: Vec<&str> = dti.arenas.iter().map(|arena| arena.name).collect();
+		let find = |name: &str| {
+			arenas
+				.iter()
+				.copied()
+				.find(|a| a.eq_ignore_ascii_case(name))
+		};
+		let mut links = Vec::new();
+
+		for arena in &dti.arenas {
+			for entity in &arena.entities {
+				if let DtiEntityData::ArenaConnectZone(value) = entity.data
+					&& let Some(&to) = usize::try_from(value).ok().and_then(|i| arenas.get(i))
+				{
+					links.push(ArenaLink {
+						from: arena.name,
+						to,
+						kind: ArenaLinkKind::ConnectZone,
+						via: format!("zone {}", entity.id),
+					});
+				}
+			}
+		}
+
+		for script in cmi.scripts.values() {
+			for arena_ref in &script.arena_refs {
+				let Some(to) = find(arena_ref.name) else {
+					continue;
+				};
+				let kind = if arena_ref.action == "Load" {
+					ArenaLinkKind::LoadArena
+				} else {
+					ArenaLinkKind::ShowArena
+				};
+				for origin in &script.call_origins {
+					let from = cmi
+						.entities
+						.get(origin.target_name)
+						.map_or(std::slice::from_ref(&origin.arena_name), |entity| {
+							entity.arenas.as_slice()
+						});
+					for from in from.iter().filter_map(|name| find(name)) {
+						links.push(ArenaLink {
+							from,
+							to,
+							kind,
+							via: origin.target_name.to_owned(),
+						});
+					}
+				}
+			}
+		}
+
+		for &corridor in corridors {
+			let Some(index) = arenas
+				.iter()
+				.position(|arena| arena.eq_ignore_ascii_case(&corridor[1..]))
+			else {
+				continue;
+			};
+			if let Some(&to) = arenas.get(index + 1) {
+				links.push(ArenaLink {
+					from: arenas[index],
+					to,
+					kind: ArenaLinkKind::Corridor,
+					via: corridor.to_owned(),
+				});
+			}
+		}
+
+		links.retain(|link| link.from != link.to);
+		links.sort_unstable();
+		links.dedup();
+		Self { arenas, links }
+	}
+
+	pub fn to_dot(&self) -> String {
+		let mut result = String::from("digraph arenas {\n");
+		for arena in &self.arenas {
+			writeln!(result, "\t\"{arena}\";").unwrap();
+		}
+		for link in &self.links {
+			let style = match link.kind {
+				ArenaLinkKind::ConnectZone => "solid",
+				ArenaLinkKind::ShowArena => "dotted",
+				ArenaLinkKind::LoadArena => "dashed",
+				ArenaLinkKind::Corridor => "bold",
+			};
+			writeln!(
+				result,
+				"\t\"{}\" -> \"{}\" [label=\"{}\", style={style}];",
+				link.from, link.to, link.via
+			)
+			.unwrap();
+		}
+		result.push_str("}\n");
+		result
+	}
+
+	/// Writes `{name}.json` and `{name}.dot`
+	pub fn save_as(&self, name: &str, output: &mut OutputWriter) {
+		output.write(name, "json", serde_json::to_string_pretty(self).unwrap());
+		output.write(name, "dot", self.to_dot());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{
+		ByteBuilder, CmiBuilder, DtiBuilder, DtiZone, DtiZoneExtra, EMPTY_SCRIPT,
+	};
+	use crate::{Reader, Vec3};
+
+	#[test]
+	fn test_arena_graph() {
+		let zone = DtiZone {
+			kind: 6,
+			id: 12,
+			value: 2,
+			min: Vec3::new(0.0, 0.0, 0.0),
+			extra: DtiZoneExtra::Max(Vec3::new(1.0, 1.0, 1.0)),
+		};
+		let dti_data = DtiBuilder::new("LEVEL3.DTI")
+			.arena("ARENA1", 1.0)
+			.zone(zone)
+			.arena("ARENA2", 1.0)
+			.arena("ARENA3", 1.0)
+			.build();
+		let dti = Dti::parse(Reader::new(&dti_data));
+
+		let mut script = ByteBuilder::new();
+		script.u8(0xDF).pascal_str("ARENA2").u8(0xFF);
+		let cmi_data = CmiBuilder::new("LEVEL3.CMI")
+			.arena("ARENA1", "", EMPTY_SCRIPT)
+			.entity("ARENA1", "DOOR", 1, &script.finish(), EMPTY_SCRIPT)
+			.build();
+		let cmi = Cmi::parse(Reader::new(&cmi_data));
+
+		let graph = ArenaGraph::build(&dti, &cmi, &["CARENA2", "CARENA3"]);
+		let links: Vec<_> = graph
+			.links
+			.iter()
+			.map(|link| (link.from, link.to, link.kind, link.via.as_str()))
+			.collect();
+		assert_eq!(
+			links,
+			[
+				("ARENA1", "ARENA2", ArenaLinkKind::LoadArena, "DOOR"),
+				("ARENA1", "ARENA3", ArenaLinkKind::ConnectZone, "zone 12"),
+				("ARENA2", "ARENA3", ArenaLinkKind::Corridor, "CARENA2"),
+			]
+		);
+		assert!(
+			graph
+				.to_dot()
+				.contains("\"ARENA2\" -> \"ARENA3\" [label=\"CARENA2\", style=bold];")
+		);
+	}
+}
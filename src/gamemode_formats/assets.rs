@@ -0,0 +1,313 @@
+//! Loading game files while tolerating missing ones,
+//! so demo versions and partial installs still export everything they have.
+//!
+//! Releases lay their files out differently (nested folders, lowercase names),
+//! so [AssetFiles::detect] finds the game folders first and looks up every path case-insensitively.
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// The game folders, which every game path starts with
+const GAME_DIRS: [&str; 4] = ["TRAVERSE", "STREAM", "FALL3D", "MISC"];
+/// How many folders deep the game folders can be (e.g. `assets/GOG/MDK/TRAVERSE`)
+const MAX_NESTING: usize = 3;
+/// The traverse levels in the full game
+const TRAVERSE_LEVELS: std::ops::RangeInclusive<usize> = 3..=8;
+
+/// Keeps track of which game files were found or missing
+#[derive(Default)]
+pub struct AssetFiles {
+	found: Vec<PathBuf>,
+	missing: Vec<PathBuf>,
+	/// Every game file and folder by its uppercase path under the game folders, once detected
+	paths: Option<BTreeMap<String, PathBuf>>,
+	layout: Option<GameLayout>,
+}
+
+/// Where the game folders were found, and which release they look like
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameLayout {
+	/// The folder containing the game folders
+	pub root: PathBuf,
+	/// The game folders that were found, in their usual order
+	pub game_dirs: Vec<&'static str>,
+	/// The traverse levels that have a folder
+	pub levels: Vec<usize>,
+	/// Files only found under an alternate name
+	pub aliased: usize,
+	pub version: GameVersion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameVersion {
+	/// Every game folder and traverse level
+	Full,
+	/// A demo or an incomplete install
+	Partial,
+}
+
+impl std::fmt::Display for GameLayout {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let version = match self.version {
+			GameVersion::Full => "full game",
+			GameVersion::Partial => "partial game (demo or incomplete install)",
+		};
+		write!(
+			f,
+			"Detected {version} in {} ({}",
+			self.root.display(),
+			self.game_dirs.join(", ")
+		)?;
+		if !self.levels.is_empty() {
+			let levels: Vec<String> = self.levels.iter().map(usize::to_string).collect();
+			write!(f, "; traverse levels {}", levels.join(", "))?;
+		}
+		if self.aliased != 0 {
+			write!(f, "; {} files under alternate names", self.aliased)?;
+		}
+		f.write_str(")")
+	}
+}
+
+/// Alternate names for game files in some releases, loaded from a json object of
+/// `"alternate path": "usual path"`, with paths relative to the game folders (e.g. `MISC/MDKFONT.FTI`)
+#[derive(Default, serde::Deserialize)]
+#[serde(transparent)]
+pub struct AssetAliases(HashMap<String, String>);
+
+impl AssetAliases {
+	pub fn load(path: impl AsRef<Path>) -> Self {
+		let path = path.as_ref();
+		let data = std::fs::read(path).unwrap_or_else(|e| {
+			panic!("failed to read asset aliases file {}: {e}", path.display())
+		});
+		serde_json::from_slice(&data).unwrap_or_else(|e| {
+			panic!("failed to parse asset aliases file {}: {e}", path.display())
+		})
+	}
+}
+
+impl AssetFiles {
+	/// Finds the game folders anywhere under `root`, in any case.
+	///
+	/// Game paths are then read relative to them (so `assets/MISC/mdkfont.fti` could read `root/mdk/misc/MDKFONT.FTI`).
+	/// If there are no game folders then paths are read as they are.
+	pub fn detect(root: &Path, aliases: &AssetAliases) -> Self {
+		let Some(game_root) = find_game_root(root) else {
+			return Self::default();
+		};
+		let mut paths = BTreeMap::new();
+		let mut game_dirs = Vec::new();
+		for entry in read_dir(&game_root) {
+			let name = entry.file_name().to_string_lossy().to_ascii_uppercase();
+			let path = entry.path();
+			if GAME_DIRS.contains(&name.as_str()) && path.is_dir() {
+				index_files(&path, &name, &mut paths);
+				paths.insert(name, path);
+			}
+		}
+		for dir in GAME_DIRS {
+			if paths.contains_key(dir) {
+				game_dirs.push(dir);
+			}
+		}
+
+		let mut aliased = 0;
+		for (alternate, usual) in &aliases.0 {
+			let usual = normalise(usual);
+			if !paths.contains_key(&usual)
+				&& let Some(path) = paths.get(&normalise(alternate)).cloned()
+			{
+				paths.insert(usual, path);
+				aliased += 1;
+			}
+		}
+
+		let levels: Vec<usize> = TRAVERSE_LEVELS
+			.filter(|level| paths.contains_key(&format!("TRAVERSE/LEVEL{level}")))
+			.collect();
+		let version =
+			if game_dirs.len() == GAME_DIRS.len() && levels.len() == TRAVERSE_LEVELS.count() {
+				GameVersion::Full
+			} else {
+				GameVersion::Partial
+			};
+		Self {
+			paths: Some(paths),
+			layout: Some(GameLayout {
+				root: game_root,
+				game_dirs,
+				levels,
+				aliased,
+				version,
+			}),
+			..Default::default()
+		}
+	}
+
+	/// Where the game folders were found, if they were detected
+	pub fn layout(&self) -> Option<&GameLayout> {
+		self.layout.as_ref()
+	}
+
+	/// The real location of a game path (starting with `assets/`), or `None` if it isn't there
+	fn resolve(&self, path: &Path) -> Option<PathBuf> {
+		let Some(paths) = &self.paths else {
+			return Some(path.to_owned());
+		};
+		let relative = path.strip_prefix("assets").unwrap_or(path);
+		paths.get(&normalise(&relative.to_string_lossy())).cloned()
+	}
+
+	/// Reads a game file, returning `None` (with a warning) if it doesn't exist
+	pub fn read(&mut self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+		let path = path.as_ref();
+		let result = self
+			.resolve(path)
+			.map(|real_path| std::fs::read(&real_path).map_err(|e| (real_path, e)));
+		match result {
+			Some(Ok(data)) => {
+				self.found.push(path.to_owned());
+				Some(data)
+			}
+			None => self.skip(path),
+			Some(Err((_, e))) if e.kind() == std::io::ErrorKind::NotFound => self.skip(path),
+			Some(Err((real_path, e))) => panic!("failed to read {}: {e}", real_path.display()),
+		}
+	}
+
+	/// Like [Self::read] but for directories, returning `None` if it doesn't exist
+	pub fn read_dir(&mut self, path: impl AsRef<Path>) -> Option<std::fs::ReadDir> {
+		let path = path.as_ref();
+		let result = self
+			.resolve(path)
+			.map(|real_path| std::fs::read_dir(&real_path).map_err(|e| (real_path, e)));
+		match result {
+			Some(Ok(dir)) => Some(dir),
+			None => self.skip(path),
+			Some(Err((_, e))) if e.kind() == std::io::ErrorKind::NotFound => self.skip(path),
+			Some(Err((real_path, e))) => panic!("failed to read {}: {e}", real_path.display()),
+		}
+	}
+
+	fn skip<T>(&mut self, path: &Path) -> Option<T> {
+		eprintln!("  {} is missing, skipping", path.display());
+		self.missing.push(path.to_owned());
+		None
+	}
+
+	pub fn print_summary(&self) {
+		println!(
+			"Found {} game files, {} missing",
+			self.found.len(),
+			self.missing.len()
+		);
+		for path in &self.missing {
+			println!("  missing {}", path.display());
+		}
+	}
+}
+
+/// The shallowest folder under `root` (including itself) with the most game folders in it
+fn find_game_root(root: &Path) -> Option<PathBuf> {
+	let mut best: Option<(usize, PathBuf)> = None;
+	let mut dirs = vec![root.to_owned()];
+	for _ in 0..=MAX_NESTING {
+		let mut next = Vec::new();
+		for dir in dirs {
+			let Ok(entries) = std::fs::read_dir(&dir) else {
+				continue;
+			};
+			let mut count = 0;
+			for entry in entries {
+				let entry = entry.unwrap();
+				if !entry.path().is_dir() {
+					continue;
+				}
+				let name = entry.file_name().to_string_lossy().to_ascii_uppercase();
+				if GAME_DIRS.contains(&name.as_str()) {
+					count += 1;
+				} else {
+					next.push(entry.path());
+				}
+			}
+			if count > best.as_ref().map_or(0, |(best, _)| *best) {
+				best = Some((count, dir));
+			}
+		}
+		next.sort_unstable();
+		dirs = next;
+	}
+	best.map(|(_, dir)| dir)
+}
+
+/// Adds everything under `dir` to `paths`, keyed by `prefix` and their uppercase relative path
+fn index_files(dir: &Path, prefix: &str, paths: &mut BTreeMap<String, PathBuf>) {
+	for entry in read_dir(dir) {
+		let path = entry.path();
+		let key = format!(
+			"{prefix}/{}",
+			entry.file_name().to_string_lossy().to_ascii_uppercase()
+		);
+		if path.is_dir() {
+			index_files(&path, &key, paths);
+		}
+		paths.insert(key, path);
+	}
+}
+
+fn read_dir(dir: &Path) -> impl Iterator<Item = std::fs::DirEntry> {
+	std::fs::read_dir(dir)
+		.unwrap_or_else(|e| panic!("failed to read directory {}: {e}", dir.display()))
+		.map(|entry| entry.unwrap())
+}
+
+/// Uppercase with forward slashes and no trailing slash, to match the keys of [AssetFiles::paths]
+fn normalise(path: &str) -> String {
+	path.replace('\\', "/")
+		.trim_matches('/')
+		.to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	#[test]
+	fn test_detect() {
+		let dir = Path::new("output/test_detect_assets");
+		let _ = fs::remove_dir_all(dir);
+		let game = dir.join("GOG/mdk");
+		fs::create_dir_all(game.join("traverse/level3")).unwrap();
+		fs::create_dir_all(game.join("Misc/flic")).unwrap();
+		fs::create_dir_all(dir.join("extras/stream")).unwrap();
+		fs::write(game.join("traverse/level3/Level3.cmi"), [1]).unwrap();
+		fs::write(game.join("Misc/MDKFONT.FTI"), [2]).unwrap();
+		fs::write(game.join("Misc/FONT2.FTI"), [3]).unwrap();
+
+		let aliases: AssetAliases =
+			serde_json::from_str(r#"{"MISC/FONT2.FTI": "MISC/STATS.FTI"}"#).unwrap();
+		let mut files = AssetFiles::detect(dir, &aliases);
+		assert_eq!(
+			files.layout(),
+			Some(&GameLayout {
+				root: game.clone(),
+				game_dirs: vec!["TRAVERSE", "MISC"],
+				levels: vec![3],
+				aliased: 1,
+				version: GameVersion::Partial,
+			})
+		);
+		assert_eq!(
+			files.read("assets/TRAVERSE/LEVEL3/LEVEL3.CMI"),
+			Some(vec![1])
+		);
+		assert_eq!(files.read("assets/MISC/mdkfont.fti"), Some(vec![2]));
+		assert_eq!(files.read("assets/MISC/STATS.FTI"), Some(vec![3]));
+		assert!(files.read_dir("assets/MISC/FLIC").is_some());
+		assert_eq!(files.read("assets/STREAM/STREAM.BNI"), None);
+		assert_eq!(files.missing, [Path::new("assets/STREAM/STREAM.BNI")]);
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -1,17 +1,18 @@
 //! Exports the assets from FALL3D (the skydiving section at the start of each level)
-use crate::Reader;
-use crate::data_formats::mesh::ColourMap;
-use crate::data_formats::{Texture, TextureHolder, TextureResult};
+use super::ExtractOptions;
+use super::assets::AssetFiles;
+use super::pipeline::{MaterialTextures, Pipeline};
+use crate::data_formats::Texture;
 use crate::file_formats::mti::Material;
 use crate::file_formats::{Bni, Mti, Sni};
-use crate::output_writer::OutputWriter;
+use crate::{OutputWriter, Reader};
 use std::fmt::Write;
 
 /// combines flare and zoom images into an animation
 fn combine_animation_frames(bni: &mut Bni) {
 	let mut flare = Vec::new();
 	let mut zoom = Vec::new();
-	bni.textures.retain(|(name, tex)| {
+	bni.textures.retain_mut(|(name, tex)| {
 		if let Some(flare_num) = name.strip_prefix("FLARE") {
 			let flare_index: usize = flare_num
 				.parse::<usize>()
@@ -21,14 +22,14 @@ fn combine_animation_frames(bni: &mut Bni) {
 			if flare_index <= flare.len() {
 				flare.resize_with(flare_index + 1, Default::default);
 			}
-			flare[flare_index] = tex.clone();
+			flare[flare_index] = std::mem::take(tex);
 			false
 		} else if let Some(zoom_num) = name.strip_prefix("ZOOM") {
 			let zoom_index: usize = zoom_num.parse::<usize>().expect("bad flare suffix");
 			if zoom_index <= zoom.len() {
 				zoom.resize_with(zoom_index + 1, Default::default);
 			}
-			zoom[zoom_index] = tex.clone();
+			zoom[zoom_index] = std::mem::take(tex);
 			false
 		} else {
 			true
@@ -73,22 +74,38 @@ static ZOOM_PAL: [u8; NUM_ZOOM_PAL_ENTRIES * 4] = const {
 };
 const NUM_ZOOM_PAL_ENTRIES: usize = 17;
 
-pub fn parse_fall3d(save_sounds: bool, save_textures: bool, save_meshes: bool) {
-	let output = OutputWriter::new("assets/FALL3D", true);
+pub fn parse_fall3d(options: &ExtractOptions, output: &OutputWriter, files: &mut AssetFiles) {
+	let ExtractOptions {
+		save_sounds,
+		save_textures,
+		save_meshes,
+		masking,
+		cycles,
+		..
+	} = *options;
+	let pipeline = Pipeline::new(
+		output.asset_dir("FALL3D"),
+		save_sounds,
+		save_textures,
+		save_meshes,
+	);
+	let output = &pipeline.output;
 	let shared_output = output.push_dir("Shared");
 
-	if save_sounds {
-		let sni = std::fs::read("assets/FALL3D/FALL3D.SNI").unwrap();
+	if save_sounds && let Some(sni) = files.read("assets/FALL3D/FALL3D.SNI") {
 		let sni = Sni::parse(Reader::new(&sni));
-		let mut output = shared_output.push_dir("Sounds");
-		for (name, sound) in &sni.sounds {
-			sound.save_as(name, &mut output);
-		}
+		pipeline.save_sounds(
+			&shared_output,
+			"Sounds",
+			sni.sounds.iter().map(|(n, s)| (*n, s)),
+		);
 		assert!(sni.anims.is_empty());
 		assert!(sni.bsps.is_empty());
 	}
 
-	let bni = std::fs::read("assets/FALL3D/FALL3D.BNI").unwrap();
+	let Some(bni) = files.read("assets/FALL3D/FALL3D.BNI") else {
+		return;
+	};
 	let mut bni = Bni::parse(Reader::new(&bni));
 
 	if save_textures {
@@ -122,12 +139,14 @@ pub fn parse_fall3d(save_sounds: bool, save_textures: bool, save_meshes: bool) {
 	for level_index in 1..=5 {
 		temp_filename.clear();
 		write!(temp_filename, "assets/FALL3D/FALL3D_{level_index}.MTI").unwrap();
-		let mti = std::fs::read(&temp_filename).unwrap();
+		let Some(mti) = files.read(&temp_filename) else {
+			continue;
+		};
 		let mti = Mti::parse(Reader::new(&mti));
 
 		temp_filename.clear();
 		write!(temp_filename, "LEVEL{level_index}").unwrap();
-		let mut output = output.push_dir(&temp_filename);
+		let mut output = output.push_level_dir(&temp_filename);
 
 		temp_filename.clear();
 		write!(temp_filename, "FALLPU_{level_index}").unwrap();
@@ -157,72 +176,15 @@ pub fn parse_fall3d(save_sounds: bool, save_textures: bool, save_meshes: bool) {
 			output.write_palette(&temp_filename, palette);
 		}
 
-		struct Textures<'a> {
-			palette: &'a [u8],
-			materials: &'a [(&'a str, Material<'a>)],
-		}
-		impl<'a> TextureHolder<'a> for Textures<'a> {
-			fn lookup(&mut self, name: &str) -> TextureResult<'a> {
-				for (mat_name, mat) in self.materials {
-					if *mat_name == name {
-						return match mat {
-							Material::Pen(pen) => TextureResult::Pen(*pen),
-							Material::Texture(tex, _) => TextureResult::SaveRef {
-								width: tex.width,
-								height: tex.height,
-								path: format!("Textures/{name}.png"),
-								masked: tex.pixels.iter().any(|p| *p == 0),
-							},
-							Material::AnimatedTexture(frames, _) => TextureResult::SaveRef {
-								width: frames[0].width,
-								height: frames[0].height,
-								path: format!("Textures/{name}.png"),
-								masked: frames
-									.iter()
-									.any(|frame| frame.pixels.iter().any(|p| *p == 0)),
-							},
-						};
-					}
-				}
-				//eprintln!("failed to find fall3d material {name}"); // todo
-				TextureResult::None
-			}
-			fn get_used_colours(&self, _name: &str, _colours: &mut ColourMap) {
-				eprintln!("not getting used colours in fall3d");
-			}
-			fn get_palette(&self) -> &[u8] {
-				self.palette
-			}
-			fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
-				eprintln!("getting unknown stream translucent colours!");
-				[[0; 4]; 4]
-			}
-		}
-		let mut textures = if save_textures {
-			Textures {
-				palette,
-				materials: &mti.materials,
-			}
-		} else {
-			Textures {
-				palette,
-				materials: &[],
-			}
-		};
-
-		if save_meshes {
-			let mut output = output.push_dir("Meshes");
-			for (name, mesh) in &bni.meshes {
-				mesh.save_textured_as(name, &mut output, &mut textures);
-			}
+		let materials: &[_] = if save_textures { &mti.materials } else { &[] };
+		let mut textures = MaterialTextures::new(materials, &[], palette, masking, cycles);
 
-			if !bni.animations_3d.is_empty() {
-				let mut output = output.push_dir("Animations");
-				for (name, anim) in &bni.animations_3d {
-					anim.save_as(name, &mut output);
-				}
-			}
-		}
+		pipeline.save_meshes(&output, "Meshes", &bni.meshes, &mut textures);
+		pipeline.save_animations(
+			&output,
+			"Meshes/Animations",
+			bni.animations_3d.iter().map(|(n, a)| (*n, a)),
+		);
 
 		if save_textures {
 			let mut output = output.push_dir("Meshes/Textures");
@@ -233,15 +195,22 @@ pub fn parse_fall3d(save_sounds: bool, save_textures: bool, save_meshes: bool) {
 				match material {
 					Material::Pen(_pen) => (),
 					Material::Texture(tex, _flags) if name.starts_with(&temp_filename) => {
-						temp_anim.push(tex.clone());
+						temp_anim.push(tex.to_borrowed());
 					}
-					Material::Texture(tex, _flags) => tex.save_as(name, &mut output, Some(palette)),
+					Material::Texture(tex, _flags) => cycles.save_animated(
+						std::slice::from_ref(tex),
+						name,
+						24,
+						&mut output,
+						Some(palette),
+					),
 					Material::AnimatedTexture(frames, _flags) => {
-						Texture::save_animated(frames, name, 24, &mut output, Some(palette))
+						cycles.save_animated(frames, name, 24, &mut output, Some(palette))
 					}
 				}
 			}
 			Texture::save_animated(&temp_anim, &temp_filename, 12, &mut output, Some(palette));
+			textures.mask_report.save(&mut output);
 		}
 	}
 }
@@ -2,66 +2,124 @@
 use std::path::Path;
 use std::process::Stdio;
 
+use super::assets::AssetFiles;
 use crate::data_formats::mesh::ColourMap;
+use crate::data_formats::palette::PaletteResolver;
 use crate::data_formats::{TextureHolder, TextureResult};
 use crate::file_formats::mti::Material;
 use crate::file_formats::{Bni, Fti, Lbb, Mti, Sni};
 use crate::output_writer::OutputWriter;
 use crate::reader::Reader;
 
-pub fn parse_misc(save_videos: bool) {
-	let mut output = OutputWriter::new("assets/MISC", true);
-
-	export_simple(&output, "FINISH.BNI", |reader, output| {
-		Bni::parse(reader).save(output, true);
-	});
-	export_simple(&output, "OPTIONS.BNI", |reader, output| {
-		Bni::parse(reader).save(output, true)
-	});
-	export_simple(&output, "mdkfont.fti", |reader, output| {
-		Fti::parse(reader).save(output)
-	});
-	export_simple(&output, "UINSTALL.FTI", |reader, output| {
-		Fti::parse(reader).save(output)
-	});
-	export_simple(&output, "MDKSOUND.SNI", |reader, output| {
+pub fn parse_misc(save_videos: bool, output: &OutputWriter, files: &mut AssetFiles) {
+	let mut output = output.asset_dir("MISC");
+
+	// the system palettes in the font files are also used by some BNI images
+	let fti_files: Vec<(&str, Vec<u8>)> = ["mdkfont.fti", "UINSTALL.FTI"]
+		.into_iter()
+		.filter_map(|filename| Some((filename, load_misc_file(files, filename)?)))
+		.collect();
+	let ftis: Vec<(&str, Fti)> = fti_files
+		.iter()
+		.map(|(filename, data)| (*filename, Fti::parse(Reader::new(data))))
+		.collect();
+	for (filename, fti) in &ftis {
+		let mut output = output.push_dir(filename);
+		output.set_source_file(Path::new("assets/MISC").join(filename).display());
+		fti.save(&mut output);
+	}
+	let sys_palettes: Vec<(&str, &[u8])> = ftis
+		.iter()
+		.map(|(filename, fti)| (*filename, fti.palette))
+		.collect();
+	export_bnis(
+		&output,
+		files,
+		&["FINISH.BNI", "OPTIONS.BNI"],
+		&sys_palettes,
+	);
+	export_simple(&output, files, "MDKSOUND.SNI", |reader, output| {
 		Sni::parse(reader).save(output)
 	});
 
-	export_stats(&output);
+	export_stats(&output, files);
 
 	// export LBBs (load images)
 	for i in 3..=8 {
-		let lbb = load_misc_file(&format!("LOAD_{i}.LBB"));
+		let Some(lbb) = load_misc_file(files, &format!("LOAD_{i}.LBB")) else {
+			continue;
+		};
 		let lbb = Lbb::parse(Reader::new(&lbb));
+		output.set_source_file(format!("assets/MISC/LOAD_{i}.LBB"));
 		lbb.texture
 			.save_as(&format!("LOAD_{i}.png"), &mut output, Some(lbb.palette));
 	}
 
-	if save_videos {
+	if save_videos && let Some(video_dir) = files.read_dir("assets/MISC/FLIC") {
 		let mut video_output = output.push_dir("FLIC");
-		for dirent in std::fs::read_dir("assets/MISC/FLIC").unwrap().flatten() {
+		for dirent in video_dir.flatten() {
 			export_video(&dirent.path(), &mut video_output);
 		}
 	}
 }
 
-fn load_misc_file(filename: &str) -> Vec<u8> {
-	let path = Path::new("assets/MISC").join(filename);
-	match std::fs::read(&path) {
-		Ok(data) => data,
-		Err(e) => panic!("failed to read {}: {e}", path.display()),
-	}
+fn load_misc_file(files: &mut AssetFiles, filename: &str) -> Option<Vec<u8>> {
+	files.read(Path::new("assets/MISC").join(filename))
 }
 
 fn export_simple(
-	output: &OutputWriter, filename: &str, func: impl FnOnce(Reader, &mut OutputWriter),
+	output: &OutputWriter, files: &mut AssetFiles, filename: &str,
+	func: impl FnOnce(Reader, &mut OutputWriter),
 ) {
-	let data = load_misc_file(filename);
-	func(Reader::new(&data), &mut output.push_dir(filename));
+	let Some(data) = load_misc_file(files, filename) else {
+		return;
+	};
+	let mut output = output.push_dir(filename);
+	output.set_source_file(Path::new("assets/MISC").join(filename).display());
+	func(Reader::new(&data), &mut output);
 }
 
-fn export_stats(output: &OutputWriter) {
+/// Exports BNIs that share their palettes with each other.
+///
+/// Not every image's palette is in the same file, so each image picks from its own file's palettes,
+/// then the other files' and the system palettes (`(source filename, palette)`).
+fn export_bnis(
+	output: &OutputWriter, files: &mut AssetFiles, filenames: &[&str],
+	sys_palettes: &[(&str, &[u8])],
+) {
+	let bni_files: Vec<(&str, Vec<u8>)> = filenames
+		.iter()
+		.filter_map(|&filename| Some((filename, load_misc_file(files, filename)?)))
+		.collect();
+	let bnis: Vec<(&str, Bni)> = bni_files
+		.iter()
+		.map(|(filename, data)| (*filename, Bni::parse(Reader::new(data))))
+		.collect();
+
+	for (filename, bni) in &bnis {
+		let mut palettes = PaletteResolver::new();
+		// own palettes first
+		let mut sources: Vec<&(&str, Bni)> = bnis.iter().filter(|(f, _)| f == filename).collect();
+		sources.extend(bnis.iter().filter(|(f, _)| f != filename));
+		for (source, other) in sources {
+			for (name, palette) in &other.palettes {
+				palettes.add(name, source, palette);
+			}
+		}
+		for (source, palette) in sys_palettes {
+			palettes.add("SYS_PAL", source, palette);
+		}
+
+		let mut output = output.push_dir(filename);
+		output.set_source_file(Path::new("assets/MISC").join(filename).display());
+		bni.save_with_palettes(&mut output, true, &mut palettes);
+		if !palettes.report().is_empty() {
+			output.write("Palettes", "txt", palettes.report());
+		}
+	}
+}
+
+fn export_stats(output: &OutputWriter, files: &mut AssetFiles) {
 	struct MiscTextureHolder<'a> {
 		palette: &'a [u8],
 		materials: &'a [(&'a str, Material<'a>)],
@@ -77,11 +135,12 @@ fn export_stats(output: &OutputWriter) {
 			};
 			match mat {
 				Material::Pen(pen) => TextureResult::Pen(*pen),
-				Material::Texture(tex, _) => TextureResult::SaveRef {
+				Material::Texture(tex, flags) => TextureResult::SaveRef {
 					width: tex.width,
 					height: tex.height,
 					path: format!("Textures/{name}.png"),
 					masked: false,
+					flags: Some(flags.flags),
 				},
 				Material::AnimatedTexture(..) => unreachable!(),
 			}
@@ -97,9 +156,12 @@ fn export_stats(output: &OutputWriter) {
 		}
 	}
 
-	let stats_bni = load_misc_file("STATS.BNI");
+	let stats_bni = load_misc_file(files, "STATS.BNI");
+	let stats_mti = load_misc_file(files, "STATS.MTI");
+	let (Some(stats_bni), Some(stats_mti)) = (stats_bni, stats_mti) else {
+		return;
+	};
 	let mut stats_bni = Bni::parse(Reader::new(&stats_bni));
-	let stats_mti = load_misc_file("STATS.MTI");
 	let stats_mti = Mti::parse(Reader::new(&stats_mti));
 
 	let mut stats_output = output.push_dir("STATS");
@@ -147,11 +209,18 @@ fn export_video(input_path: &Path, output: &mut OutputWriter) {
 		return;
 	}
 	println!("  Converting {filename}...");
-	let output_path = output.set_output_path(file_stem, "mp4");
+	let output_path = output.set_output_path(file_stem, "mp4").to_owned();
+	if output.is_dry_run() {
+		output.record_dry_run(&output_path);
+		return;
+	}
+	if !output.can_write(&output_path) {
+		return;
+	}
 
 	let result = std::process::Command::new("ffmpeg")
 		.args(["-y", "-loglevel", "error", "-i"])
-		.args([input_path, output_path])
+		.args([input_path, &output_path])
 		.stdin(Stdio::null())
 		.status();
 
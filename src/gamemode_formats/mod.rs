@@ -1,9 +1,33 @@
+mod arena_graph;
+mod assets;
 mod fall3d;
 mod misc;
+pub mod pipeline;
+mod single_mesh;
 mod stream;
 mod traverse;
 
+pub use arena_graph::{ArenaGraph, ArenaLink, ArenaLinkKind};
+pub use assets::{AssetAliases, AssetFiles, GameLayout, GameVersion};
 pub use fall3d::parse_fall3d;
 pub use misc::parse_misc;
+pub use single_mesh::extract_mesh;
+#[cfg(feature = "viewer")]
+pub use single_mesh::preview_mesh;
 pub use stream::parse_stream;
 pub use traverse::parse_traverse;
+
+use crate::data_formats::masking::MaskSettings;
+use crate::data_formats::palette_cycle::PaletteCycles;
+
+/// Which assets to save from each game mode, and the settings they're saved with
+#[derive(Clone, Copy)]
+pub struct ExtractOptions<'a> {
+	pub save_sounds: bool,
+	/// Per-arena sound banks (only traverse has arenas)
+	pub save_sound_banks: bool,
+	pub save_textures: bool,
+	pub save_meshes: bool,
+	pub masking: &'a MaskSettings,
+	pub cycles: &'a PaletteCycles,
+}
@@ -0,0 +1,451 @@
+//! Shared export stages used by each game mode.
+//!
+//! Each game mode collects its assets from whatever files it uses,
+//! resolves the palette(s) they should be saved with,
+//! then hands them off to these stages to be saved.
+//!
+//! TRAVERSE only uses the sound and animation stages. Its meshes and textures can be
+//! saved once per arena palette, so it picks their palettes and saves them itself.
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::OutputWriter;
+use crate::data_formats::masking::{MaskReport, MaskSettings};
+use crate::data_formats::mesh::ColourMap;
+use crate::data_formats::palette_cycle::PaletteCycles;
+use crate::data_formats::{Animation, Mesh, Pen, Texture, TextureHolder, TextureResult, Wav};
+use crate::file_formats::mti::{Material, MtiFlags};
+
+/// Which asset types to save, along with the output location and settings
+pub struct Pipeline {
+	pub output: OutputWriter,
+	pub save_sounds: bool,
+	pub save_textures: bool,
+	pub save_meshes: bool,
+}
+
+impl Pipeline {
+	/// Creates a pipeline exporting to `output`
+	pub fn new(
+		output: OutputWriter, save_sounds: bool, save_textures: bool, save_meshes: bool,
+	) -> Self {
+		Self {
+			output,
+			save_sounds,
+			save_textures,
+			save_meshes,
+		}
+	}
+
+	/// Saves sounds into `output/dir`
+	pub fn save_sounds<'s>(
+		&self, output: &OutputWriter, dir: &str,
+		sounds: impl IntoIterator<Item = (&'s str, &'s Wav<'s>)>,
+	) {
+		if !self.save_sounds {
+			return;
+		}
+		let mut output = output.push_dir(dir);
+		for (name, sound) in sounds {
+			sound.save_as(name, &mut output);
+		}
+	}
+
+	/// Saves meshes into `output/dir`, looking up their materials from `textures`.
+	///
+	/// The materials are still looked up when meshes aren't being saved,
+	/// so that `textures` knows which ones are used.
+	pub fn save_meshes<'a>(
+		&self, output: &OutputWriter, dir: &str, meshes: &[(&str, Mesh<'a>)],
+		textures: &mut impl TextureHolder<'a>,
+	) {
+		if !self.save_meshes {
+			for (_, mesh) in meshes {
+				for name in &mesh.materials {
+					textures.lookup(name);
+				}
+			}
+			return;
+		}
+		let mut output = output.push_dir(dir);
+		for (name, mesh) in meshes {
+			mesh.save_textured_as(name, &mut output, textures);
+		}
+	}
+
+	/// Saves 3D animations into `output/dir`
+	pub fn save_animations<'s>(
+		&self, output: &OutputWriter, dir: &str,
+		animations: impl IntoIterator<Item = (&'s str, &'s Animation<'s>)>,
+	) {
+		let mut animations = animations.into_iter().peekable();
+		if !self.save_meshes || animations.peek().is_none() {
+			return;
+		}
+		let mut output = output.push_dir(dir);
+		for (name, anim) in animations {
+			anim.save_as(name, &mut output);
+		}
+	}
+}
+
+/// Looks up mesh materials from MTI materials (then BNI textures), all sharing one palette.
+///
+/// Keeps track of which textures were used so they can be saved next to the meshes.
+pub struct MaterialTextures<'a> {
+	pub materials: &'a [(&'a str, Material<'a>)],
+	pub textures: &'a [(&'a str, Texture<'a>)],
+	pub palette: &'a [u8],
+	pub translucent_colours: Option<[[u8; 4]; 4]>,
+	pub used_textures: Vec<&'a str>,
+	pub masking: &'a MaskSettings,
+	pub mask_report: MaskReport,
+	pub cycles: &'a PaletteCycles,
+}
+
+impl<'a> MaterialTextures<'a> {
+	pub fn new(
+		materials: &'a [(&'a str, Material<'a>)], textures: &'a [(&'a str, Texture<'a>)],
+		palette: &'a [u8], masking: &'a MaskSettings, cycles: &'a PaletteCycles,
+	) -> Self {
+		Self {
+			materials,
+			textures,
+			palette,
+			translucent_colours: None,
+			used_textures: Vec::new(),
+			masking,
+			mask_report: MaskReport::default(),
+			cycles,
+		}
+	}
+
+	pub fn is_used(&self, name: &str) -> bool {
+		self.used_textures.contains(&name)
+	}
+
+	/// Saves every material and texture, putting the ones used by meshes in `mesh_output`
+	/// and everything else (including a summary of pens) in `other_output`.
+	/// The masking decisions for the used textures are saved with them,
+	/// and textures with a palette cycle are baked into animations.
+	pub fn save(&self, mesh_output: &mut OutputWriter, other_output: &mut OutputWriter, fps: u16) {
+		let palette = Some(self.palette);
+		let mut pens = String::from("Name    \tValue\n");
+		for (name, mat) in self.materials {
+			let output = if self.is_used(name) {
+				&mut *mesh_output
+			} else {
+				&mut *other_output
+			};
+			match mat {
+				Material::Pen(pen) => writeln!(pens, "{name:8}\t{pen:?}").unwrap(),
+				Material::Texture(tex, _) => {
+					let frames = std::slice::from_ref(&**tex);
+					self.cycles
+						.save_animated(frames, name, fps, output, palette)
+				}
+				Material::AnimatedTexture(frames, _) => self
+					.cycles
+					.save_animated(frames, name, fps, output, palette),
+			}
+		}
+		for (name, tex) in self.textures {
+			let output = if self.is_used(name) {
+				&mut *mesh_output
+			} else {
+				&mut *other_output
+			};
+			let frames = std::slice::from_ref(tex);
+			self.cycles
+				.save_animated(frames, name, fps, output, palette);
+		}
+		other_output.write("Pens", "txt", &pens);
+		self.mask_report.save(mesh_output);
+	}
+}
+
+impl<'a> TextureHolder<'a> for MaterialTextures<'a> {
+	fn lookup(&mut self, name: &str) -> TextureResult<'a> {
+		let name = &name[..name.len().min(8)]; // truncated material names
+
+		let mut found: Option<(&'a str, &'a [Texture<'a>], Option<MtiFlags>)> = None;
+		if let Some((mat_name, mat)) = self.materials.iter().find(|(n, _)| *n == name) {
+			match mat {
+				Material::Pen(pen) => return TextureResult::Pen(*pen),
+				Material::Texture(tex, flags) => {
+					found = Some((mat_name, std::slice::from_ref(tex), Some(flags.flags)))
+				}
+				Material::AnimatedTexture(frames, flags) => {
+					found = Some((mat_name, frames, Some(flags.flags)))
+				}
+			}
+		} else if let Some((tex_name, tex)) = self.textures.iter().find(|(n, _)| *n == name) {
+			found = Some((tex_name, std::slice::from_ref(tex), None));
+		}
+
+		let Some((name, frames, flags)) = found else {
+			return TextureResult::None;
+		};
+
+		let width = frames[0].width;
+		let height = frames[0].height;
+		assert!(
+			frames[1..]
+				.iter()
+				.all(|t| t.width == width && t.height == height),
+			"mismatched texture dimensions!"
+		);
+
+		if !self.used_textures.contains(&name) {
+			self.used_textures.push(name);
+		}
+		let (masked, reason) = self.masking.is_masked(name, frames);
+		self.mask_report.record(name, (masked, reason));
+		TextureResult::SaveRef {
+			width,
+			height,
+			path: format!("Textures/{name}.png"),
+			masked,
+			flags,
+		}
+	}
+	fn get_used_colours(&self, name: &str, colours: &mut ColourMap) {
+		if let Some((_, mat)) = self.materials.iter().find(|(n, _)| *n == name) {
+			match mat {
+				Material::Pen(Pen::Colour(p)) => colours.push(*p),
+				Material::Texture(tex, _) => colours.extend(tex.pixels.iter()),
+				Material::AnimatedTexture(frames, _) => {
+					for frame in frames.iter() {
+						colours.extend(frame.pixels.iter());
+					}
+				}
+				_ => {}
+			}
+		} else if let Some((_, tex)) = self.textures.iter().find(|(n, _)| *n == name) {
+			colours.extend(tex.pixels.iter());
+		}
+	}
+	fn get_palette(&self) -> &[u8] {
+		self.palette
+	}
+	fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
+		self.translucent_colours.unwrap_or_else(|| {
+			eprintln!("getting unknown translucent colours!");
+			[[0; 4]; 4]
+		})
+	}
+}
+
+macro_rules! asset_id {
+	($(#[$meta:meta])* $name:ident) => {
+		$(#[$meta])*
+		///
+		/// Only [AssetDb] hands these out, and it never removes assets,
+		/// so the asset it names is always in the db that made it.
+		/// IDs aren't tied to their db though, so using one with a different db panics if it's missing there.
+		#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+		pub struct $name<'a>(&'a str);
+
+		impl<'a> $name<'a> {
+			pub fn name(self) -> &'a str {
+				self.0
+			}
+		}
+
+		impl std::fmt::Display for $name<'_> {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				f.write_str(self.0)
+			}
+		}
+	};
+}
+
+asset_id!(
+	/// A texture (or animated texture) in an [AssetDb]
+	TextureId
+);
+asset_id!(
+	/// A mesh in an [AssetDb]
+	MeshId
+);
+asset_id!(
+	/// A sound in an [AssetDb]
+	SoundId
+);
+asset_id!(
+	/// An arena (or corridor) with its own palette in an [AssetDb]
+	ArenaId
+);
+
+const FOREIGN_ID: &str = "asset id from another AssetDb";
+
+/// Every asset a level's files provide, by name.
+///
+/// Names from the level data are checked once with the `*_id` functions,
+/// after which the typed IDs can be used to fetch assets without any more lookups failing.
+/// Adding an asset with a name that's already there replaces it, keeping existing IDs valid.
+#[derive(Default)]
+pub struct AssetDb<'a> {
+	sounds: HashMap<&'a str, &'a Wav<'a>>,
+	meshes: HashMap<&'a str, &'a Mesh<'a>>,
+	textures: HashMap<&'a str, (&'a [Texture<'a>], Option<MtiFlags>)>,
+	pens: HashMap<&'a str, Pen>,
+	palettes: HashMap<&'a str, Vec<u8>>,
+}
+
+impl<'a> AssetDb<'a> {
+	/// Adds a sound, replacing any with the same name
+	pub fn add_sound(&mut self, name: &'a str, sound: &'a Wav<'a>) -> SoundId<'a> {
+		self.sounds.insert(name, sound);
+		SoundId(name)
+	}
+	pub fn sound_id(&self, name: &str) -> Option<SoundId<'a>> {
+		self.sounds
+			.get_key_value(name)
+			.map(|(name, _)| SoundId(name))
+	}
+	pub fn sound(&self, id: SoundId) -> &'a Wav<'a> {
+		self.sounds.get(id.0).expect(FOREIGN_ID)
+	}
+	pub fn sounds(&self) -> impl Iterator<Item = (SoundId<'a>, &'a Wav<'a>)> + '_ {
+		self.sounds
+			.iter()
+			.map(|(name, sound)| (SoundId(name), *sound))
+	}
+
+	/// Adds a mesh, returning the one it replaced
+	pub fn add_mesh(&mut self, name: &'a str, mesh: &'a Mesh<'a>) -> Option<&'a Mesh<'a>> {
+		self.meshes.insert(name, mesh)
+	}
+	pub fn mesh_id(&self, name: &str) -> Option<MeshId<'a>> {
+		self.meshes
+			.get_key_value(name)
+			.map(|(name, _)| MeshId(name))
+	}
+	pub fn mesh(&self, id: MeshId) -> &'a Mesh<'a> {
+		self.meshes.get(id.0).expect(FOREIGN_ID)
+	}
+	pub fn meshes(&self) -> impl Iterator<Item = (MeshId<'a>, &'a Mesh<'a>)> + '_ {
+		self.meshes.iter().map(|(name, mesh)| (MeshId(name), *mesh))
+	}
+
+	/// Adds a texture without MTI flags, replacing any with the same name
+	pub fn add_texture(&mut self, name: &'a str, frames: &'a [Texture<'a>]) -> TextureId<'a> {
+		self.textures.insert(name, (frames, None));
+		TextureId(name)
+	}
+	/// Adds an MTI material as either a pen or a texture with its flags
+	pub fn add_material(&mut self, name: &'a str, material: &'a Material<'a>) {
+		match material {
+			Material::Pen(pen) => {
+				self.pens.insert(name, *pen);
+			}
+			Material::Texture(tex, flags) => {
+				self.textures
+					.insert(name, (std::slice::from_ref(tex), Some(flags.flags)));
+			}
+			Material::AnimatedTexture(frames, flags) => {
+				self.textures.insert(name, (frames, Some(flags.flags)));
+			}
+		}
+	}
+	pub fn texture_id(&self, name: &str) -> Option<TextureId<'a>> {
+		self.textures
+			.get_key_value(name)
+			.map(|(name, _)| TextureId(name))
+	}
+	pub fn texture(&self, id: TextureId) -> &'a [Texture<'a>] {
+		self.textures.get(id.0).expect(FOREIGN_ID).0
+	}
+	/// Flags of textures that came from MTI materials
+	pub fn texture_flags(&self, id: TextureId) -> Option<MtiFlags> {
+		self.textures.get(id.0).expect(FOREIGN_ID).1
+	}
+	pub fn textures(&self) -> impl Iterator<Item = (TextureId<'a>, &'a [Texture<'a>])> + '_ {
+		self.textures
+			.iter()
+			.map(|(name, (frames, _))| (TextureId(name), *frames))
+	}
+	pub fn pen(&self, name: &str) -> Option<Pen> {
+		self.pens.get(name).copied()
+	}
+	pub fn pens(&self) -> impl Iterator<Item = (&'a str, Pen)> + '_ {
+		self.pens.iter().map(|(name, pen)| (*name, *pen))
+	}
+
+	/// Adds an arena with the palette its assets are saved with
+	pub fn add_arena(&mut self, name: &'a str, palette: Vec<u8>) -> ArenaId<'a> {
+		self.palettes.insert(name, palette);
+		ArenaId(name)
+	}
+	pub fn arena_id(&self, name: &str) -> Option<ArenaId<'a>> {
+		self.palettes
+			.get_key_value(name)
+			.map(|(name, _)| ArenaId(name))
+	}
+	pub fn palette(&self, id: ArenaId) -> &[u8] {
+		self.palettes.get(id.0).expect(FOREIGN_ID)
+	}
+	pub fn arenas(&self) -> impl Iterator<Item = ArenaId<'a>> + '_ {
+		self.palettes.keys().map(|name| ArenaId(name))
+	}
+	/// Every arena's palette, for keeping after the assets are gone
+	pub fn into_palettes(self) -> impl Iterator<Item = (String, Vec<u8>)> {
+		self.palettes
+			.into_iter()
+			.map(|(name, palette)| (name.to_owned(), palette))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_asset_db() {
+		let pixels = [1u8; 4];
+		let tex = Texture::new(2, 2, &pixels[..]);
+		let materials = [Material::Pen(Pen::Colour(7))];
+
+		let mut db = AssetDb::default();
+		let id = db.add_texture("WALL", std::slice::from_ref(&tex));
+		db.add_material("FLOOR", &materials[0]);
+		let arena = db.add_arena("ARENA1", vec![0; 768]);
+
+		assert_eq!(db.texture_id("WALL"), Some(id));
+		assert_eq!(db.texture(id).len(), 1);
+		assert_eq!(db.texture_flags(id), None);
+		assert_eq!(db.texture_id("FLOOR"), None);
+		assert!(matches!(db.pen("FLOOR"), Some(Pen::Colour(7))));
+		assert_eq!(db.arena_id("ARENA1"), Some(arena));
+		assert_eq!(db.palette(arena).len(), 768);
+		assert_eq!(db.sound_id("WALL"), None);
+		assert_eq!(arena.to_string(), "ARENA1");
+	}
+
+	#[test]
+	fn test_asset_db_replace() {
+		let pixels = [1u8; 4];
+		let tex = Texture::new(2, 2, &pixels[..]);
+		let materials = [Material::Pen(Pen::Colour(7))];
+
+		let mut db = AssetDb::default();
+		let id = db.add_texture("WALL", std::slice::from_ref(&tex));
+		let frames = [tex.clone(), tex.clone()];
+		assert_eq!(db.add_texture("WALL", &frames), id);
+		assert_eq!(db.texture(id).len(), 2);
+
+		// a pen with the same name doesn't remove the texture
+		db.add_material("WALL", &materials[0]);
+		assert_eq!(db.texture(id).len(), 2);
+		assert!(db.pen("WALL").is_some());
+	}
+
+	#[test]
+	#[should_panic = "asset id from another AssetDb"]
+	fn test_asset_db_foreign_id() {
+		let mut other = AssetDb::default();
+		let arena = other.add_arena("ARENA1", vec![0; 768]);
+		AssetDb::default().palette(arena);
+	}
+}
@@ -0,0 +1,458 @@
+//! Extracts a single mesh on its own, with its textures embedded.
+//!
+//! Meshes don't store their own palette and most of their textures live in other files,
+//! so these are found in the files next to the mesh's (the same ones the game mode would load).
+use std::path::{Path, PathBuf};
+
+use crate::data_formats::cmi_bytecode::CmiSymbols;
+use crate::data_formats::masking::MaskSettings;
+use crate::data_formats::mesh::ColourMap;
+use crate::data_formats::palette::PaletteStack;
+use crate::data_formats::{Mesh, Pen, Texture, TextureHolder, TextureResult};
+use crate::file_formats::mti::{Material, MtiFlags};
+use crate::file_formats::{Bni, Cmi, Dti, FileType, Fti, Mti, Mto, Sni};
+#[cfg(feature = "viewer")]
+use crate::preview::Preview;
+use crate::{OutputWriter, Reader};
+
+/// Saves the mesh `mesh_name` from the BNI, MTO, SNI or CMI file at `path` as a self-contained mesh.
+///
+/// Panics if the mesh or the files it needs can't be found.
+pub fn extract_mesh(
+	path: &Path, mesh_name: &str, symbols: &CmiSymbols, masking: &MaskSettings,
+	output: &mut OutputWriter,
+) {
+	output.set_source_file(path.display());
+	with_mesh(path, mesh_name, symbols, masking, |mesh, textures| {
+		mesh.save_textured_as(mesh_name, output, textures)
+	});
+}
+
+/// Saves a [Preview] of the mesh `mesh_name`, found the same way as [extract_mesh]
+#[cfg(feature = "viewer")]
+pub fn preview_mesh(
+	path: &Path, mesh_name: &str, symbols: &CmiSymbols, masking: &MaskSettings, preview: &Preview,
+	output: &mut OutputWriter,
+) {
+	output.set_source_file(path.display());
+	with_mesh(path, mesh_name, symbols, masking, |mesh, textures| {
+		preview.save_as(mesh, mesh_name, textures, output)
+	});
+}
+
+/// Finds the mesh and everything it needs to be drawn, then hands them to `func`
+fn with_mesh(
+	path: &Path, mesh_name: &str, symbols: &CmiSymbols, masking: &MaskSettings,
+	func: impl for<'a> FnOnce(&Mesh<'a>, &mut EmbeddedTextures<'a>),
+) {
+	let data = read(path);
+	let Some(file_type) = FileType::sniff(path, &data) else {
+		panic!("unknown file type for {}", path.display());
+	};
+
+	match file_type {
+		FileType::Bni => with_bni_mesh(path, &data, mesh_name, masking, func),
+		FileType::Mto | FileType::Sni | FileType::Cmi => {
+			with_level_mesh(path, file_type, &data, mesh_name, symbols, masking, func)
+		}
+		file_type => panic!("{file_type:?} files don't contain meshes"),
+	}
+}
+
+/// STREAM and FALL3D meshes, using the BNI's palette and the MTIs next to it
+fn with_bni_mesh(
+	path: &Path, data: &[u8], mesh_name: &str, masking: &MaskSettings,
+	func: impl for<'a> FnOnce(&Mesh<'a>, &mut EmbeddedTextures<'a>),
+) {
+	let bni = Bni::parse(Reader::new(data));
+	let mesh = find_mesh(&bni.meshes, mesh_name, path);
+
+	let mti_files = read_siblings(path, FileType::Mti);
+	let mtis: Vec<(&Path, Mti)> = mti_files
+		.iter()
+		.map(|(path, data)| (path.as_path(), Mti::parse(Reader::new(data))))
+		.collect();
+
+	// FALL3D has a material file per level, each with the same names,
+	// so use the first one that has any of the mesh's materials and its matching palette
+	let mti = mtis.iter().find(|(_, mti)| {
+		mesh.materials
+			.iter()
+			.any(|name| mti.materials.iter().any(|(n, _)| n == name))
+	});
+	let mti_name = mti.map(|(path, _)| file_stem(path)).unwrap_or_default();
+	let level_number = trailing_digits(&mti_name);
+	let palette = bni
+		.palettes
+		.iter()
+		.find(|(name, _)| !level_number.is_empty() && trailing_digits(name) == level_number)
+		.or(bni.palettes.first())
+		.unwrap_or_else(|| panic!("{} has no palette", path.display()))
+		.1;
+
+	let mut textures = EmbeddedTextures {
+		materials: mti
+			.map(|(_, mti)| mti.materials.as_slice())
+			.into_iter()
+			.collect(),
+		textures: bni
+			.textures
+			.iter()
+			.map(|(name, tex)| (*name, std::slice::from_ref(tex)))
+			.collect(),
+		palette,
+		translucent_colours: [[0; 4]; 4],
+		masking,
+	};
+	func(mesh, &mut textures);
+}
+
+/// TRAVERSE meshes, using the palette of the arena they're in
+fn with_level_mesh(
+	path: &Path, file_type: FileType, data: &[u8], mesh_name: &str, symbols: &CmiSymbols,
+	masking: &MaskSettings, func: impl for<'a> FnOnce(&Mesh<'a>, &mut EmbeddedTextures<'a>),
+) {
+	let dti_file = read_sibling(path, FileType::Dti);
+	let dti = Dti::parse(Reader::new(&dti_file));
+	let sys_pal_file = find_sys_palette(path);
+	let sys_pal = sys_pal_file
+		.as_deref()
+		.map(|data| Fti::parse(Reader::new(data)).system_palette());
+
+	let mto_file = (file_type != FileType::Mto).then(|| read_sibling(path, FileType::Mto));
+	let mto = Mto::parse(Reader::new(mto_file.as_deref().unwrap_or(data)));
+	let sni_files = read_siblings(path, FileType::Sni);
+	let mut snis: Vec<Sni> = sni_files
+		.iter()
+		.map(|(_, data)| Sni::parse(Reader::new(data)))
+		.collect();
+	if file_type == FileType::Sni {
+		snis.push(Sni::parse(Reader::new(data)));
+	}
+	let mti_files = read_siblings(path, FileType::Mti);
+	let mtis: Vec<Mti> = mti_files
+		.iter()
+		.map(|(_, data)| Mti::parse(Reader::new(data)))
+		.collect();
+
+	let cmi;
+	let (mesh, arena_name): (&Mesh, Option<&str>) = match file_type {
+		FileType::Mto => mto
+			.arenas
+			.iter()
+			.find_map(|arena| {
+				if arena.name == mesh_name {
+					return Some((&arena.bsp.mesh, Some(arena.name)));
+				}
+				let (_, mesh) = arena.meshes.iter().find(|(name, _)| *name == mesh_name)?;
+				Some((mesh, Some(arena.name)))
+			})
+			.unwrap_or_else(|| panic!("mesh {mesh_name} not found in {}", path.display())),
+		FileType::Sni => {
+			let sni = snis.last().unwrap();
+			let (name, bsp) = sni
+				.bsps
+				.iter()
+				.find(|(name, _)| *name == mesh_name)
+				.unwrap_or_else(|| panic!("mesh {mesh_name} not found in {}", path.display()));
+			// corridors are named after their arena
+			(&bsp.mesh, name.get(1..))
+		}
+		FileType::Cmi => {
+			cmi = Cmi::parse_with_symbols(Reader::new(data), symbols);
+			let entity = cmi
+				.entities
+				.get(mesh_name)
+				.filter(|entity| entity.mesh.is_some())
+				.unwrap_or_else(|| panic!("mesh {mesh_name} not found in {}", path.display()));
+			(
+				entity.mesh.as_ref().unwrap(),
+				entity.arenas.first().copied(),
+			)
+		}
+		_ => unreachable!(),
+	};
+
+	// meshes that aren't in an arena just use the first one
+	let arena = arena_name
+		.and_then(|name| mto.arenas.iter().find(|arena| arena.name == name))
+		.or(mto.arenas.first())
+		.expect("level has no arenas");
+	let palette = PaletteStack::new(dti.pal)
+		.with_system(sys_pal)
+		.with_arena(arena.palette, dti.num_pal_free_pixels as usize)
+		.compose();
+
+	let mut materials = vec![arena.mti.materials.as_slice()];
+	materials.extend(mtis.iter().map(|mti| mti.materials.as_slice()));
+	let mut textures = EmbeddedTextures {
+		materials,
+		textures: snis
+			.iter()
+			.flat_map(|sni| &sni.anims)
+			.map(|(name, frames)| (*name, frames.as_slice()))
+			.collect(),
+		palette: &palette,
+		translucent_colours: dti.translucent_colours,
+		masking,
+	};
+	func(mesh, &mut textures);
+}
+
+/// Looks up mesh materials from several files, embedding their textures in the mesh.
+///
+/// Animated textures only embed their first frame.
+struct EmbeddedTextures<'a> {
+	/// Searched in order, so the mesh's own materials should be first
+	materials: Vec<&'a [(&'a str, Material<'a>)]>,
+	textures: Vec<(&'a str, &'a [Texture<'a>])>,
+	palette: &'a [u8],
+	translucent_colours: [[u8; 4]; 4],
+	masking: &'a MaskSettings,
+}
+
+enum Found<'a> {
+	Pen(Pen),
+	/// With the flags of the material they came from
+	Frames(&'a [Texture<'a>], Option<MtiFlags>),
+}
+
+impl<'a> EmbeddedTextures<'a> {
+	fn find(&self, name: &str) -> Option<Found<'a>> {
+		// material names are sometimes truncated
+		let short_name = &name[..name.len().min(8)];
+		for name in [name, short_name] {
+			for materials in &self.materials {
+				if let Some((_, mat)) = materials.iter().find(|(n, _)| *n == name) {
+					return Some(match mat {
+						Material::Pen(pen) => Found::Pen(*pen),
+						Material::Texture(tex, flags) => {
+							Found::Frames(std::slice::from_ref(tex), Some(flags.flags))
+						}
+						Material::AnimatedTexture(frames, flags) => {
+							Found::Frames(frames, Some(flags.flags))
+						}
+					});
+				}
+			}
+			if let Some((_, frames)) = self.textures.iter().find(|(n, _)| *n == name) {
+				return Some(Found::Frames(frames, None));
+			}
+		}
+		None
+	}
+}
+
+impl<'a> TextureHolder<'a> for EmbeddedTextures<'a> {
+	fn lookup(&mut self, name: &str) -> TextureResult<'a> {
+		match self.find(name) {
+			None => TextureResult::None,
+			Some(Found::Pen(pen)) => TextureResult::Pen(pen),
+			Some(Found::Frames(frames, flags)) => {
+				let (masked, _) = self.masking.is_masked(name, frames);
+				TextureResult::SaveEmbed {
+					texture: frames[0].to_borrowed(),
+					masked,
+					flags,
+				}
+			}
+		}
+	}
+	fn get_used_colours(&self, name: &str, colours: &mut ColourMap) {
+		match self.find(name) {
+			Some(Found::Pen(Pen::Colour(n))) => colours.push(n),
+			Some(Found::Frames(frames, _)) => {
+				for frame in frames {
+					colours.extend(frame.pixels.iter());
+				}
+			}
+			_ => {}
+		}
+	}
+	fn get_palette(&self) -> &[u8] {
+		self.palette
+	}
+	fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
+		self.translucent_colours
+	}
+}
+
+fn find_mesh<'m, 'a>(meshes: &'m [(&str, Mesh<'a>)], name: &str, path: &Path) -> &'m Mesh<'a> {
+	meshes
+		.iter()
+		.find(|(n, _)| *n == name)
+		.map(|(_, mesh)| mesh)
+		.unwrap_or_else(|| panic!("mesh {name} not found in {}", path.display()))
+}
+
+fn read(path: &Path) -> Vec<u8> {
+	std::fs::read(path).unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()))
+}
+
+/// Reads every other file of a type in the same folder as `path`, sorted by filename
+fn read_siblings(path: &Path, file_type: FileType) -> Vec<(PathBuf, Vec<u8>)> {
+	let dir = path.parent().unwrap_or(Path::new("."));
+	let entries =
+		std::fs::read_dir(dir).unwrap_or_else(|e| panic!("failed to read {}: {e}", dir.display()));
+	let mut paths: Vec<PathBuf> = entries
+		.map(|entry| entry.unwrap().path())
+		.filter(|p| p.file_name() != path.file_name())
+		.filter(|p| {
+			p.extension()
+				.and_then(|ext| ext.to_str())
+				.and_then(FileType::from_extension)
+				== Some(file_type)
+		})
+		.collect();
+	paths.sort_unstable();
+	paths
+		.into_iter()
+		.map(|path| {
+			let data = read(&path);
+			(path, data)
+		})
+		.collect()
+}
+/// Reads the one file of a type next to `path`
+fn read_sibling(path: &Path, file_type: FileType) -> Vec<u8> {
+	let mut files = read_siblings(path, file_type);
+	match files.len() {
+		0 => panic!("no {file_type:?} file next to {}", path.display()),
+		1 => files.pop().unwrap().1,
+		_ => panic!(
+			"more than one {file_type:?} file next to {}",
+			path.display()
+		),
+	}
+}
+
+/// The base traverse palette is stored in the font file, look for it in any parent folder
+fn find_sys_palette(path: &Path) -> Option<Vec<u8>> {
+	path.ancestors().skip(1).find_map(|dir| {
+		["MISC/mdkfont.fti", "MISC/MDKFONT.FTI"]
+			.iter()
+			.find_map(|name| std::fs::read(dir.join(name)).ok())
+	})
+}
+
+fn file_stem(path: &Path) -> String {
+	path.file_stem()
+		.map(|stem| stem.to_string_lossy().into_owned())
+		.unwrap_or_default()
+}
+fn trailing_digits(name: &str) -> &str {
+	let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+	&name[prefix.len()..]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_support::{
+		BniBuilder, DtiBuilder, MtiBuilder, MtoBuilder, SniBuilder, mesh, wav,
+	};
+	use base64::{Engine, engine::general_purpose};
+	use std::fs;
+
+	fn extract(path: &Path, mesh_name: &str, out: &Path) {
+		extract_mesh(
+			path,
+			mesh_name,
+			&CmiSymbols::default(),
+			&MaskSettings::default(),
+			&mut OutputWriter::new_in_dir(out),
+		);
+	}
+
+	/// The name and first palette colour of each image embedded in a saved gltf
+	fn embedded_images(path: &Path) -> Vec<(String, Vec<u8>)> {
+		let gltf = fs::read_to_string(path).unwrap();
+		let json: serde_json::Value = serde_json::from_str(&gltf).unwrap();
+		json["images"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|image| {
+				let uri = image["uri"].as_str().unwrap();
+				let data = uri.strip_prefix("data:image/png;base64,").unwrap();
+				let png = general_purpose::STANDARD.decode(data).unwrap();
+				let reader = png::Decoder::new(&png[..]).read_info().unwrap();
+				let palette = reader.info().palette.as_ref().unwrap()[..3].to_vec();
+				(image["name"].as_str().unwrap().to_owned(), palette)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_bni_mesh() {
+		let dir = Path::new("output/test_single_mesh_bni");
+		let _ = fs::remove_dir_all(dir);
+		fs::create_dir_all(dir).unwrap();
+		let ship = mesh(&["WALL", "GUNT"]);
+		let bni = BniBuilder::new()
+			.entry("SHIP", &ship)
+			.entry("PAL1", &[7; 0x300])
+			.entry("PAL2", &[8; 0x300])
+			.build();
+		fs::write(dir.join("FALL3D.BNI"), bni).unwrap();
+		let other = MtiBuilder::new("LEVEL1.MTI").texture("OTHER", 1, 1, &[1]);
+		fs::write(dir.join("LEVEL1.MTI"), other.build()).unwrap();
+		let level = MtiBuilder::new("LEVEL2.MTI").texture("WALL", 2, 2, &[1, 2, 3, 4]);
+		fs::write(dir.join("LEVEL2.MTI"), level.build()).unwrap();
+
+		// the first mti with the mesh's materials picks the palette with its level number
+		let out = dir.join("out");
+		extract(&dir.join("FALL3D.BNI"), "SHIP", &out);
+		assert_eq!(
+			embedded_images(&out.join("SHIP.gltf")),
+			[("WALL".to_owned(), vec![8, 8, 8])]
+		);
+
+		// without a matching mti, the first palette is used
+		fs::remove_file(dir.join("LEVEL2.MTI")).unwrap();
+		let level = MtiBuilder::new("LEVEL9.MTI").texture("WALL", 1, 1, &[1]);
+		fs::write(dir.join("LEVEL9.MTI"), level.build()).unwrap();
+		extract(&dir.join("FALL3D.BNI"), "SHIP", &out);
+		assert_eq!(
+			embedded_images(&out.join("SHIP.gltf")),
+			[("WALL".to_owned(), vec![7, 7, 7])]
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn test_level_mesh() {
+		let dir = Path::new("output/test_single_mesh_level");
+		let _ = fs::remove_dir_all(dir);
+		fs::create_dir_all(dir).unwrap();
+		let dti = DtiBuilder::new("LEVEL3.DTI")
+			.arena("ARENA1", 1.0)
+			.arena("ARENA2", 2.0)
+			.palette(&[5; 0x300])
+			.build();
+		fs::write(dir.join("LEVEL3.DTI"), dti).unwrap();
+		let arena1 = MtiBuilder::new("ARENA1.MTI").texture("WALL", 1, 1, &[1]);
+		let arena2 = MtiBuilder::new("ARENA2.MTI")
+			.texture("WALL", 2, 1, &[1, 2])
+			.texture("FLOOR", 1, 1, &[3]);
+		let mto = MtoBuilder::new("LEVEL3O.MTO")
+			.arena("ARENA1", &[0; 336], arena1.build())
+			.arena("ARENA2", &[0; 336], arena2.build())
+			.mesh("DOOR", mesh(&["WALL", "FLOOR", "SHARED"]))
+			.build();
+		fs::write(dir.join("LEVEL3O.MTO"), mto).unwrap();
+		let shared = MtiBuilder::new("LEVEL3.MTI").texture("SHARED", 1, 1, &[4]);
+		fs::write(dir.join("LEVEL3.MTI"), shared.build()).unwrap();
+		let sni = SniBuilder::new("LEVEL3S.SNI").sound("DOOR", 1, wav(1, 11025, 8, 4));
+		fs::write(dir.join("LEVEL3S.SNI"), sni.build()).unwrap();
+
+		// materials come from the mesh's own arena first, then the level's mti
+		let out = dir.join("out");
+		extract(&dir.join("LEVEL3O.MTO"), "DOOR", &out);
+		let images = embedded_images(&out.join("DOOR.gltf"));
+		let names: Vec<_> = images.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(names, ["WALL", "FLOOR", "SHARED"]);
+		assert!(images.iter().all(|(_, palette)| palette == &[5, 5, 5]));
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -1,92 +1,114 @@
 //! Exports TRAVERSE assets (everything in-game)
-use std::collections::HashMap;
-
-use crate::data_formats::mesh::ColourMap;
-use crate::data_formats::{Mesh, Pen, Texture, TextureHolder, TextureResult, Wav};
-use crate::file_formats::{
-	Bni, Cmi, Dti, Fti, Mto, Sni,
-	mti::{Material, Mti},
-};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use super::assets::AssetFiles;
+use super::pipeline::{ArenaId, AssetDb, Pipeline, TextureId};
+use super::{ArenaGraph, ExtractOptions};
+use crate::data_formats::cmi_bytecode::{CmiSoundRef, CmiSymbols};
+use crate::data_formats::masking::{MaskReport, MaskSettings};
+use crate::data_formats::mesh::{ColourMap, MeshStats};
+use crate::data_formats::palette::PaletteStack;
+use crate::data_formats::sound_bank::{SoundAliases, SoundBank, SoundBankEntry};
+use crate::data_formats::{Pen, Texture, TextureHolder, TextureResult};
+use crate::dataset::DatasetBuilder;
+use crate::file_formats::mti::Material;
+use crate::file_formats::{Bni, Cmi, Dti, DtiLighting, EntityStats, Fti, Mti, Mto, Sni};
+use crate::uv_overlay::UvOverlay;
 use crate::{OutputWriter, Reader};
 
-pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool) {
-	// the base palette is loaded from the font file for some reason!
-	// this is required since a couple of levels have invalid colours in their versions
-	let sys_pal = {
-		let fti = std::fs::read("assets/MISC/mdkfont.fti").unwrap();
-		let fti = Fti::parse(Reader::new(&fti));
-		fti.palette.to_owned()
-	};
-
-	let trav_bni = std::fs::read("assets/TRAVERSE/TRAVSPRT.BNI").unwrap();
-	let trav_bni = Bni::parse(Reader::new(&trav_bni));
+pub fn parse_traverse(
+	options: &ExtractOptions, symbols: &CmiSymbols, output: &OutputWriter, files: &mut AssetFiles,
+) {
+	let ExtractOptions {
+		save_sounds,
+		save_sound_banks,
+		save_textures,
+		save_meshes,
+		masking,
+		cycles,
+	} = *options;
+	let pipeline = Pipeline::new(output.clone(), save_sounds, save_textures, save_meshes);
+	let output = &pipeline.output;
+	let settings = output.export_settings();
+	// see PaletteStack for why this is in the font file
+	let sys_pal = files
+		.read("assets/MISC/mdkfont.fti")
+		.map(|fti| Fti::parse(Reader::new(&fti)).system_palette().to_owned());
+
+	let trav_bni = files.read("assets/TRAVERSE/TRAVSPRT.BNI");
+	let trav_bni = trav_bni
+		.as_deref()
+		.map(|data| Bni::parse(Reader::new(data)));
 
 	let mut all_palettes: HashMap<String, Vec<u8>> = Default::default();
+	let mut entity_stats = EntityStats::default();
 
 	for level_index in 3usize..=8 {
 		println!("  Parsing traverse level {level_index}...");
-		let mut output = OutputWriter::new(format!("assets/TRAVERSE/LEVEL{level_index}"), true);
 
-		let read_file = |ext| {
-			std::fs::read(format!(
+		// load files
+		let level_files = [".CMI", ".DTI", "O.MTO", "S.MTI", "O.SNI", "S.SNI"].map(|ext| {
+			files.read(format!(
 				"assets/TRAVERSE/LEVEL{level_index}/LEVEL{level_index}{ext}"
 			))
-			.unwrap()
+		});
+		let [
+			Some(cmi),
+			Some(dti),
+			Some(mto),
+			Some(mti),
+			Some(sni_o),
+			Some(sni_s),
+		] = level_files
+		else {
+			println!("  Skipping traverse level {level_index} (missing files)");
+			continue;
 		};
 
-		// load files
-		let cmi = read_file(".CMI");
-		let mut cmi = Cmi::parse(Reader::new(&cmi));
-		let dti = read_file(".DTI");
+		let mut output = output.asset_dir(format!("TRAVERSE/LEVEL{level_index}"));
+
+		let mut cmi = Cmi::parse_with_symbols(Reader::new(&cmi), symbols);
 		let dti = Dti::parse(Reader::new(&dti));
-		let mto = read_file("O.MTO");
-		let mto = Mto::parse(Reader::new(&mto));
-		let mti = read_file("S.MTI");
+		let mut mto = Mto::parse(Reader::new(&mto));
+		cmi.resolve_animation_names(
+			mto.arenas
+				.iter()
+				.flat_map(|arena| arena.animations.iter().map(|(name, anim)| (*name, anim))),
+		);
+		entity_stats.add_level(&format!("LEVEL{level_index}"), &cmi);
 		let mti = Mti::parse(Reader::new(&mti));
-		let sni_o = read_file("O.SNI");
-		let sni_o = Sni::parse(Reader::new(&sni_o));
-		let sni_s = read_file("S.SNI");
+		let mut sni_o = Sni::parse(Reader::new(&sni_o));
 		let sni_s = Sni::parse(Reader::new(&sni_s));
 
-		// gather assets
+		// name the level geometry parts that scripts hide, show and blow off
+		for arena in &mut mto.arenas {
+			cmi.label_mesh_parts(arena.name, &mut arena.bsp.mesh);
+			if let Some(mesh) = arena.bsp.mesh.with_split(settings.bsp_split) {
+				arena.bsp.mesh = mesh;
+			}
+		}
+		for (corridor_name, bsp) in &mut sni_o.bsps {
+			cmi.label_mesh_parts(corridor_name, &mut bsp.mesh);
+			if let Some(mesh) = bsp.mesh.with_split(settings.bsp_split) {
+				bsp.mesh = mesh;
+			}
+		}
 
-		let mut all_sounds = HashMap::<&str, &Wav>::new();
-		let mut all_meshes = HashMap::<&str, &Mesh>::new();
-		let mut all_pens = HashMap::<&str, Pen>::new();
-		let mut all_textures = HashMap::<&str, &[Texture]>::new();
+		// gather assets
 
-		let mut palettes = HashMap::<String, Vec<u8>>::new();
+		let mut db = AssetDb::default();
 
 		if save_sounds {
-			all_sounds.extend(sni_o.sounds.iter().map(|(name, sound)| (*name, sound)));
-			all_sounds.extend(sni_s.sounds.iter().map(|(name, sound)| (*name, sound)));
+			for (name, sound) in sni_o.sounds.iter().chain(&sni_s.sounds) {
+				db.add_sound(name, sound);
+			}
+		}
+		for (name, frames) in sni_o.anims.iter().chain(&sni_s.anims) {
+			db.add_texture(name, frames);
 		}
-
-		all_textures.extend(
-			sni_o
-				.anims
-				.iter()
-				.map(|(name, frames)| (*name, frames.as_slice())),
-		);
-		all_textures.extend(
-			sni_s
-				.anims
-				.iter()
-				.map(|(name, frames)| (*name, frames.as_slice())),
-		);
-
 		for (name, mat) in mti.materials.iter() {
-			match mat {
-				Material::Pen(pen) => {
-					all_pens.insert(name, *pen);
-				}
-				Material::Texture(tex, _) => {
-					all_textures.insert(*name, std::slice::from_ref(tex));
-				}
-				Material::AnimatedTexture(frames, _) => {
-					all_textures.insert(name, frames.as_slice());
-				}
-			}
+			db.add_material(name, mat);
 		}
 
 		// add mto assets/arenas/palettes
@@ -99,7 +121,7 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 					.find(|cmi_arena| cmi_arena.name == arena.name)
 					.unwrap();
 				for (mesh_name, mesh) in &arena.meshes {
-					let dup = all_meshes.insert(mesh_name, mesh);
+					let dup = db.add_mesh(mesh_name, mesh);
 					assert!(dup.is_none_or(|m| mesh == m), "duplicate mesh {mesh_name}");
 					// register mesh as belonging to this arena
 					let entity_arenas = &mut cmi.entities.entry(mesh_name).or_default().arenas;
@@ -110,7 +132,7 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 						cmi_arena.entities.push(mesh_name);
 					}
 				}
-				let dup = all_meshes.insert(arena.name, &arena.bsp.mesh);
+				let dup = db.add_mesh(arena.name, &arena.bsp.mesh);
 				assert!(dup.is_none(), "duplicate arena mesh {}", arena.name);
 				assert!(
 					cmi.entities
@@ -124,29 +146,18 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 
 				// don't add arena sounds, do that later so we can organize them in folders
 
-				let num_free_palette_bytes = dti.num_pal_free_pixels as usize * 3;
-				let mut palette = dti.pal.to_vec();
-				palette[..192].copy_from_slice(&sys_pal);
-				palette[4 * 16 * 3..4 * 16 * 3 + num_free_palette_bytes]
-					.copy_from_slice(&arena.palette[..num_free_palette_bytes]);
+				let palette = PaletteStack::new(dti.pal)
+					.with_system(sys_pal.as_deref())
+					.with_arena(arena.palette, dti.num_pal_free_pixels as usize)
+					.compose();
 				if save_textures {
 					palette_output.write_palette(arena.name, &palette);
 				}
-				palettes.insert(arena.name.to_owned(), palette);
+				db.add_arena(arena.name, palette);
 
 				// add materials
 				for (name, mat) in arena.mti.materials.iter() {
-					match mat {
-						Material::Pen(pen) => {
-							all_pens.insert(name, *pen);
-						}
-						Material::Texture(tex, _) => {
-							all_textures.insert(*name, std::slice::from_ref(tex));
-						}
-						Material::AnimatedTexture(frames, _) => {
-							all_textures.insert(name, frames.as_slice());
-						}
-					}
+					db.add_material(name, mat);
 				}
 			}
 		}
@@ -159,11 +170,11 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 			let entity = cmi.entities.entry(corridor_name).or_default();
 			if entity.arenas.contains(corridor_name) {
 				// referenced in cmi, add a new palette
-				let pal = &palettes[arena_name];
-				palettes.insert(corridor_name.to_string(), pal.clone());
+				let arena = db.arena_id(arena_name).unwrap();
+				db.add_arena(corridor_name, db.palette(arena).to_vec());
 			} else {
 				// not referenced anywhere, add to parent arena
-				let dup = all_meshes.insert(*corridor_name, &bsp.mesh);
+				let dup = db.add_mesh(corridor_name, &bsp.mesh);
 				assert!(dup.is_none(), "duplicate corridor mesh {corridor_name}");
 				entity.arenas.push(arena_name);
 				cmi.arenas
@@ -181,59 +192,135 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 			let Some(ref mesh) = entity.mesh else {
 				continue;
 			};
-			let dup = all_meshes.insert(name, mesh);
+			let dup = db.add_mesh(name, mesh);
 			assert!(dup.is_none(), "duplicate cmi mesh {name}");
 		}
 
 		// save level info
 		dti.save_info_as("Level Info", &mut output);
+		let mesh_stats = db.meshes().map(|(id, mesh)| (id.name(), mesh.stats()));
+		output.write("Mesh Stats", "csv", MeshStats::csv(mesh_stats));
+		let lighting: Vec<DtiLighting> = dti
+			.arenas
+			.iter()
+			.map(|arena| {
+				let palette = db.arena_id(arena.name).map_or(dti.pal, |id| db.palette(id));
+				dti.lighting(arena.name, palette)
+			})
+			.collect();
+		dti.save_lighting_as("Lighting", &lighting, &mut output);
+		if settings.zone_boxes {
+			dti.save_zones_as("Zones", &lighting, &mut output);
+		}
+		if settings.level_dataset {
+			save_dataset(&format!("LEVEL{level_index}"), &db, &cmi, &dti, &mut output);
+		}
+		let corridors: Vec<&str> = sni_o.bsps.iter().map(|(name, _)| *name).collect();
+		ArenaGraph::build(&dti, &cmi, &corridors).save_as("Arena Graph", &mut output);
 
 		// save scripts
 		cmi.save_scripts(&mut output.push_dir("Scripts"));
+		cmi.save_traces(&mut output.push_dir("Traces"));
+		cmi.save_opcode_report(&mut output);
+		cmi.save_script_symbols(&mut output);
+		cmi.save_warnings(&mut output);
+		cmi.save_relationships(&mut output);
+		if save_meshes {
+			cmi.save_spawn_scene(&mut output);
+		}
 
 		// save sounds
 		if save_sounds {
-			let output = output.push_dir("Sounds");
+			let arena_sounds = mto.arenas.iter().flat_map(|arena| &arena.sounds);
+			let mut aliases = SoundAliases::new(
+				db.sounds()
+					.map(|(id, _)| id.name())
+					.chain(arena_sounds.map(|(name, _)| *name)),
+			);
+			for script in cmi.scripts.values() {
+				for sound in &script.sounds {
+					aliases.resolve(sound.name);
+				}
+			}
+
+			let mut output = output.push_dir("Sounds");
 			for arena in &mto.arenas {
 				let song = cmi
 					.arenas
 					.iter()
-					.find(|a| a.name == arena.name)
-					.and_then(|a| all_sounds.get(a.song).map(|song| (a.song, *song)));
+					.find(|a| a.name == arena.name && !a.song.is_empty())
+					.and_then(|a| aliases.resolve(a.song))
+					.and_then(|name| db.sound_id(name))
+					.map(|id| (id.name(), db.sound(id)));
 
 				if song.is_none() && arena.sounds.is_empty() {
 					continue;
 				}
-				let mut arena_output = output.push_dir(arena.name);
-				if let Some((song_name, song)) = song {
-					song.save_as(song_name, &mut arena_output);
-				}
+				let sounds = arena.sounds.iter().map(|(name, sound)| (*name, sound));
+				pipeline.save_sounds(&output, arena.name, song.into_iter().chain(sounds));
+
+				if save_sound_banks {
+					let mut bank = SoundBank::new(arena.name);
+					if let Some((song_name, song)) = song {
+						let file = format!("{}/{song_name}.wav", arena.name);
+						bank.song = Some(SoundBankEntry::new(song_name, file, song));
+					}
+					for (name, sound) in &arena.sounds {
+						bank.add_sound(name, format!("{}/{name}.wav", arena.name), sound);
+					}
 
-				for (name, sound) in &arena.sounds {
-					sound.save_as(name, &mut arena_output);
+					// add everything played by the arena's entities
+					let entities = cmi.arenas.iter().filter(|a| a.name == arena.name);
+					for &entity_name in entities.flat_map(|a| &a.entities) {
+						let Some(entity) = cmi.entities.get(entity_name) else {
+							continue;
+						};
+						for sound in entity.scripts.iter().flat_map(|s| &cmi.scripts[s].sounds) {
+							let sound = CmiSoundRef {
+								name: aliases.resolve(sound.name).unwrap_or(sound.name),
+								..*sound
+							};
+							if let Some(id) = db.sound_id(sound.name) {
+								let file = format!("Shared/{id}.wav");
+								bank.add_sound(id.name(), file, db.sound(id));
+							}
+							bank.add_usage(entity_name, &sound, settings);
+						}
+					}
+					bank.save(&mut output);
 				}
 			}
 
-			let mut shared_output = output.push_dir("Shared");
-			for (name, sound) in all_sounds.iter() {
-				sound.save_as(name, &mut shared_output);
+			let shared_sounds = db.sounds().map(|(id, sound)| (id.name(), sound));
+			pipeline.save_sounds(&output, "Shared", shared_sounds);
+			if !aliases.is_empty() {
+				output.write("Sound Aliases", "tsv", aliases.report());
 			}
 		}
 
-		let mut used_textures = HashMap::<&str, Vec<(&str, &str)>>::new();
+		let mut used_textures = HashMap::<TextureId, Vec<(ArenaId, ArenaId)>>::new();
+		let mut texture_usage = TextureUsageReport::new(&db);
+		for (name, mat) in mti.materials.iter() {
+			texture_usage.add_material(name, mat, "MTI");
+		}
+		for arena in &mto.arenas {
+			for (name, mat) in arena.mti.materials.iter() {
+				texture_usage.add_material(name, mat, arena.name);
+			}
+		}
+		let mut palette_matcher = PaletteMatcher::new(&db, settings.palette_tolerance);
 
 		// save meshes/textures
 		{
 			let mut output = output.push_dir("Meshes");
-			let mut anim_output = output.push_dir("Animations");
 
 			// most of the following nonsense is just deduplicating textures used by meshes
 
 			// gather materials
-			for (&name, mesh) in all_meshes.iter() {
+			for (name, mesh) in db.meshes() {
 				let mesh_arenas = cmi
 					.entities
-					.get(name)
+					.get(name.name())
 					.map(|entity| entity.arenas.as_slice())
 					.filter(|a| !a.is_empty());
 
@@ -242,19 +329,18 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 				}
 
 				for &tex_name in mesh.materials.iter() {
-					if all_textures.contains_key(tex_name) {
-						let used = used_textures.entry(tex_name).or_default();
+					if let Some(tex) = db.texture_id(tex_name) {
+						let used = used_textures.entry(tex).or_default();
 						if let Some(mesh_arenas) = mesh_arenas {
-							used.extend(mesh_arenas.iter().map(|&arena| (arena, arena)));
+							used.extend(mesh_arenas.iter().map(|&arena| {
+								let arena =
+									db.arena_id(arena).expect("entity arena has no palette");
+								(arena, arena)
+							}));
 						} else {
-							used.extend(
-								palettes
-									.keys()
-									.map(String::as_str)
-									.map(|arena| (arena, arena)),
-							);
+							used.extend(db.arenas().map(|arena| (arena, arena)));
 						}
-					} else if !all_pens.contains_key(tex_name) {
+					} else if db.pen(tex_name).is_none() {
 						// the ramp to the boss room in LEVEL3 (really level2) is missing a texture
 					}
 				}
@@ -262,33 +348,42 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 
 			// save mesh textures
 			{
-				let mut output = output.push_dir("Textures"); // inside mesh folder
+				let output = output.push_dir("Textures"); // inside mesh folder
 				for (&name, arenas) in used_textures.iter_mut() {
-					let tex = all_textures[name];
-					let num_unique = filter_textures(tex, &palettes, arenas);
+					let tex = db.texture(name);
+					let num_unique = palette_matcher.filter_textures(name, tex, arenas);
+					if num_unique == 1 {
+						texture_usage.add_palette(name, arenas[0].0.name());
+					} else {
+						for &(src, dest) in arenas.iter() {
+							if src == dest {
+								texture_usage.add_palette(name, src.name());
+							}
+						}
+					}
 
 					if !save_textures {
 						continue;
 					}
 
 					if num_unique == 1 {
-						Texture::save_animated(
+						cycles.save_animated(
 							tex,
-							name,
+							name.name(),
 							24,
-							&mut output,
-							Some(&palettes[arenas[0].0]),
+							&mut output.with_palette_name(arenas[0].0.name()),
+							Some(db.palette(arenas[0].0)),
 						);
 					} else {
 						//println!("level {level_index} splitting mesh texture {name}");
 						for &(arena_src, arena_dest) in arenas.iter() {
 							if arena_src == arena_dest {
-								Texture::save_animated(
+								cycles.save_animated(
 									tex,
 									&format!("{name}_{arena_src}"),
 									24,
-									&mut output,
-									Some(&palettes[arena_src]),
+									&mut output.with_palette_name(arena_src.name()),
+									Some(db.palette(arena_src)),
 								);
 							}
 						}
@@ -296,17 +391,60 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 				}
 			}
 
+			// debug images of the uvs sampling each texture, in each palette it was saved with
+			if settings.uv_overlays {
+				let mut output = output.push_dir("UV Overlays");
+				let mut legend = String::from("overlay\tmesh\tcolour\ttriangles\toutside\n");
+				let mut meshes: Vec<_> = db.meshes().collect();
+				meshes.sort_unstable_by_key(|(id, _)| *id);
+				let mut textures: Vec<_> = used_textures.iter().collect();
+				textures.sort_unstable_by_key(|(id, _)| **id);
+				for (&id, arenas) in textures {
+					let mut overlay = UvOverlay::new(&db.texture(id)[0]);
+					for &(mesh_id, mesh) in &meshes {
+						let material = mesh
+							.materials
+							.iter()
+							.position(|&mat| db.texture_id(mat) == Some(id));
+						if let Some(material) = material {
+							overlay.add_mesh(mesh_id.name(), mesh, material);
+						}
+					}
+					if overlay.is_empty() {
+						continue;
+					}
+					// named like the saved textures
+					let is_unique = arenas[1..].iter().all(|(src, dest)| src != dest);
+					for &(src, dest) in arenas {
+						if is_unique {
+							overlay.save_as(id.name(), db.palette(src), &mut output);
+							overlay.write_legend(id.name(), &mut legend);
+							break;
+						}
+						if src == dest {
+							let name = format!("{id}_{src}");
+							overlay.save_as(&name, db.palette(src), &mut output);
+							overlay.write_legend(&name, &mut legend);
+						}
+					}
+				}
+				output.write("UV Overlays", "tsv", legend);
+			}
+
 			struct TravTextureLookup<'a> {
 				translucent_colours: [[u8; 4]; 4],
-				pens: &'a HashMap<&'a str, Pen>,
-				textures: &'a HashMap<&'a str, &'a [Texture<'a>]>,
-				texture_arenas: &'a HashMap<&'a str, Vec<(&'a str, &'a str)>>,
+				db: &'a AssetDb<'a>,
+				texture_arenas: &'a HashMap<TextureId<'a>, Vec<(ArenaId<'a>, ArenaId<'a>)>>,
 				palette: &'a [u8],
-				current_arena: &'a str,
+				current_arena: Option<ArenaId<'a>>,
+				masking: &'a MaskSettings,
+				mask_report: MaskReport,
 			}
 			impl<'a> TextureHolder<'a> for TravTextureLookup<'a> {
 				fn lookup(&mut self, name: &str) -> TextureResult<'a> {
-					if let Some(arenas) = self.texture_arenas.get(name) {
+					if let Some(id) = self.db.texture_id(name)
+						&& let Some(arenas) = self.texture_arenas.get(&id)
+					{
 						let is_unique = arenas[1..].iter().all(|(src, dest)| src != dest);
 
 						let path = if is_unique {
@@ -314,12 +452,12 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 						} else {
 							let dest_arena = arenas
 								.iter()
-								.find(|(src, _)| *src == self.current_arena)
+								.find(|(src, _)| Some(*src) == self.current_arena)
 								.unwrap()
 								.1;
 							format!("Textures/{name}_{dest_arena}.png")
 						};
-						let tex = self.textures[name];
+						let tex = self.db.texture(id);
 						let width = tex[0].width;
 						let height = tex[0].height;
 						assert!(
@@ -327,30 +465,30 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 								.iter()
 								.all(|t| t.width == width && t.height == height)
 						);
-						let masked = tex
-							.iter()
-							.any(|frames| frames.pixels.iter().any(|p| *p == 0));
+						let (masked, reason) = self.masking.is_masked(name, tex);
+						self.mask_report.record(name, (masked, reason));
 						return TextureResult::SaveRef {
 							width,
 							height,
 							path,
 							masked,
+							flags: self.db.texture_flags(id),
 						};
 					}
-					if let Some(pen) = self.pens.get(name) {
-						return TextureResult::Pen(*pen);
+					if let Some(pen) = self.db.pen(name) {
+						return TextureResult::Pen(pen);
 					}
 
 					// missing
 					TextureResult::None
 				}
 				fn get_used_colours(&self, name: &str, colours: &mut ColourMap) {
-					if let Some(tex) = self.textures.get(name) {
-						for frame in *tex {
+					if let Some(id) = self.db.texture_id(name) {
+						for frame in self.db.texture(id) {
 							colours.extend(frame.pixels.iter());
 						}
-					} else if let Some(Pen::Colour(n)) = self.pens.get(name) {
-						colours.push(*n);
+					} else if let Some(Pen::Colour(n)) = self.db.pen(name) {
+						colours.push(n);
 					}
 				}
 				fn get_palette(&self) -> &[u8] {
@@ -364,29 +502,37 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 
 			let mut textures = TravTextureLookup {
 				translucent_colours: dti.translucent_colours,
-				pens: &all_pens,
-				textures: &all_textures,
+				db: &db,
 				texture_arenas: &used_textures,
 				palette: &[],
-				current_arena: "",
+				current_arena: None,
+				masking,
+				mask_report: MaskReport::default(),
 			};
 
 			// save meshes
 			let mut mesh_arenas = Vec::new();
 			if save_meshes {
-				for (&name, &mesh) in all_meshes.iter() {
+				for (id, mesh) in db.meshes() {
+					let name = id.name();
 					mesh_arenas.clear();
 
 					if let Some(cmi_entity) = cmi.entities.get(name) {
-						mesh_arenas.extend(cmi_entity.arenas.iter().map(|arena| (*arena, *arena)));
+						mesh_arenas.extend(cmi_entity.arenas.iter().map(|arena| {
+							let arena = db.arena_id(arena).expect("entity arena has no palette");
+							(arena, arena)
+						}));
 					}
 
 					if mesh_arenas.is_empty() {
 						for matname in &mesh.materials {
-							let Some(tex_arenas) = used_textures.get(matname) else {
+							let Some(tex_arenas) = db
+								.texture_id(matname)
+								.and_then(|tex| used_textures.get(&tex))
+							else {
 								continue;
 							};
-							for (src, dest) in tex_arenas {
+							for &(src, dest) in tex_arenas {
 								if src == dest {
 									mesh_arenas.push((src, src));
 								}
@@ -397,29 +543,36 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 						// shouldn't happen, textures are already split by arena
 						// (maybe if the mesh is only flat coloured and different between arenas)
 						//println!("level {level_index} mesh {name} cant find arenas");
-						mesh_arenas.extend(mto.arenas.iter().map(|arena| (arena.name, arena.name)));
+						mesh_arenas.extend(mto.arenas.iter().map(|arena| {
+							let arena = db.arena_id(arena.name).unwrap();
+							(arena, arena)
+						}));
 					}
 
 					mesh_arenas.sort_unstable();
 					mesh_arenas.dedup();
 
 					let used_colours = mesh.get_used_colours(&textures);
-					let num_unique_arenas =
-						filter_colours(used_colours, &palettes, &mut mesh_arenas);
+					let num_unique_arenas = palette_matcher.filter_colours(
+						"mesh",
+						name,
+						used_colours,
+						&mut mesh_arenas,
+					);
 
 					if num_unique_arenas == 1 {
-						textures.current_arena = mesh_arenas[0].0;
-						textures.palette = &palettes[textures.current_arena];
+						textures.current_arena = Some(mesh_arenas[0].0);
+						textures.palette = db.palette(mesh_arenas[0].0);
 						mesh.save_textured_as(name, &mut output, &mut textures);
 					} else {
 						// save multiple meshes with the different textures
 						//println!("level {level_index} splitting mesh {name}");
-						for (src, dest) in &mesh_arenas {
+						for &(src, dest) in &mesh_arenas {
 							if src != dest {
 								continue;
 							}
-							textures.current_arena = src;
-							textures.palette = &palettes[textures.current_arena];
+							textures.current_arena = Some(src);
+							textures.palette = db.palette(src);
 							mesh.save_textured_as(
 								&format!("{name}_{src}"),
 								&mut output,
@@ -429,40 +582,45 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 					}
 				}
 
+				textures.mask_report.save(&mut output);
+
 				// save 3d animations
 				// todo save animations inside meshes
 
-				// save mto animations
-				for arena in &mto.arenas {
-					for (name, anim) in &arena.animations {
-						anim.save_as(name, &mut anim_output);
-					}
-				}
-				// save unnamed cmi animations
-				for (mesh_name, mesh) in cmi.entities.iter() {
-					for anim_offset in &mesh.animations {
-						let anim = &cmi.animations[anim_offset];
-						anim.save_as(&format!("{mesh_name}_{anim_offset:08X}"), &mut anim_output);
-					}
-				}
+				// save mto animations, then cmi animations that weren't resolved to mto ones
+				let mto_anims: Vec<_> = mto
+					.arenas
+					.iter()
+					.flat_map(|arena| arena.animations.iter().map(|(name, anim)| (*name, anim)))
+					.collect();
+				let cmi_anims = cmi
+					.resolved_animations()
+					.into_iter()
+					.filter(|(name, _)| !mto_anims.iter().any(|(n, _)| n == name));
+				pipeline.save_animations(
+					&output,
+					"Animations",
+					mto_anims.iter().copied().chain(cmi_anims),
+				);
 			} // end save_meshes
 		} // end save_meshes/textures
 
 		// save unused/other textures
 		if save_textures {
-			let mut temp_arenas: Vec<(&str, &str)> = Vec::new();
+			let mut temp_arenas: Vec<(ArenaId, ArenaId)> = Vec::new();
 			let mut tex_output = output.push_dir("Textures");
 			let mut anim_output = output.push_dir("Animations");
 
 			mti.save_report(&mut tex_output);
 
-			dti.skybox.save_as("Sky", &mut tex_output, Some(dti.pal));
+			let mut sky_output = tex_output.with_palette_name("DTI");
+			dti.skybox.save_as("Sky", &mut sky_output, Some(dti.pal));
 			if let Some(sky) = &dti.reflected_skybox {
-				sky.save_as("Reflection", &mut tex_output, Some(dti.pal));
+				sky.save_as("Reflection", &mut sky_output, Some(dti.pal));
 			}
 
-			for (&name, tex) in all_textures.iter() {
-				if used_textures.contains_key(name) {
+			for (name, tex) in db.textures() {
+				if used_textures.contains_key(&name) {
 					continue;
 				}
 				// find all arenas that use this texture so we can split its palettes
@@ -471,8 +629,9 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 				// find source mtos and use their palette
 				'outer: for arena in &mto.arenas {
 					for (mti_name, _) in &arena.mti.materials {
-						if name == *mti_name {
-							temp_arenas.push((arena.name, arena.name));
+						if name.name() == *mti_name {
+							let arena = db.arena_id(arena.name).unwrap();
+							temp_arenas.push((arena, arena));
 							break 'outer;
 						}
 					}
@@ -481,13 +640,9 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 				if temp_arenas.is_empty() {
 					// try all palettes
 					//println!("level {level_index} texture {name} can't find arena");
-					temp_arenas.extend(
-						palettes
-							.keys()
-							.map(|arena| (arena.as_str(), arena.as_str())),
-					);
+					temp_arenas.extend(db.arenas().map(|arena| (arena, arena)));
 					temp_arenas.sort_unstable();
-					filter_textures(tex, &palettes, &mut temp_arenas);
+					palette_matcher.filter_textures(name, tex, &mut temp_arenas);
 					temp_arenas.retain(|(a, b)| a == b);
 				}
 
@@ -497,50 +652,58 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 					&mut anim_output
 				};
 
+				for &(arena, _) in &temp_arenas {
+					texture_usage.add_palette(name, arena.name());
+				}
+
 				let unique_pal = temp_arenas.len() == 1;
 				let fps = 24;
 				if unique_pal {
-					Texture::save_animated(
+					let arena = temp_arenas[0].0;
+					cycles.save_animated(
 						tex,
-						name,
+						name.name(),
 						fps,
-						output,
-						Some(&palettes[temp_arenas[0].0]),
+						&mut output.with_palette_name(arena.name()),
+						Some(db.palette(arena)),
 					);
 				} else {
 					// save all copies
 					//println!("level {level_index} splitting other texture {name}");
 					for &(arena, _) in &temp_arenas {
-						Texture::save_animated(
+						cycles.save_animated(
 							tex,
 							&format!("{name}_{arena}"),
 							fps,
-							output,
-							Some(&palettes[arena]),
+							&mut output.with_palette_name(arena.name()),
+							Some(db.palette(arena)),
 						);
 					}
 				}
 			}
 		}
 
-		all_palettes.extend(palettes);
+		palette_matcher.save_report(&mut output);
+		texture_usage.save(&mut output);
+		all_palettes.extend(db.into_palettes());
 	}
 
 	// finished exporting each level, now export stuff from the shared files
 
-	assert!(trav_bni.strings.is_empty());
-	assert!(trav_bni.sounds.is_empty());
-	assert!(trav_bni.coloured_textures.is_empty());
-	assert!(trav_bni.palettes.is_empty());
+	if !entity_stats.entities.is_empty() {
+		output
+			.asset_dir("TRAVERSE")
+			.write("Entity Stats", "csv", entity_stats.csv());
+	}
 
-	let shared_output = OutputWriter::new("assets/TRAVERSE/Shared/", true);
-	if save_sounds {
-		let trav_sni = std::fs::read("assets/TRAVERSE/TRAVERSE.SNI").unwrap();
+	let shared_output = output.asset_dir("TRAVERSE/Shared");
+	if save_sounds && let Some(trav_sni) = files.read("assets/TRAVERSE/TRAVERSE.SNI") {
 		let trav_sni = Sni::parse(Reader::new(&trav_sni));
-		let mut output = shared_output.push_dir("Sounds");
-		for (name, sound) in &trav_sni.sounds {
-			sound.save_as(name, &mut output);
-		}
+		pipeline.save_sounds(
+			&shared_output,
+			"Sounds",
+			trav_sni.sounds.iter().map(|(name, sound)| (*name, sound)),
+		);
 		assert!(
 			trav_sni.anims.is_empty(),
 			"traverse.sni contained unexpected animations"
@@ -551,9 +714,23 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 		);
 	}
 
-	if save_textures {
-		let mut tex_output = shared_output.push_dir("Textures");
-		let mut anim_output = shared_output.push_dir("Animations");
+	let Some(trav_bni) = trav_bni else {
+		return;
+	};
+	assert!(trav_bni.strings.is_empty());
+	assert!(trav_bni.sounds.is_empty());
+	assert!(trav_bni.coloured_textures.is_empty());
+	assert!(trav_bni.palettes.is_empty());
+
+	if save_textures && let Some(sys_pal) = &sys_pal {
+		let mut tex_output = shared_output
+			.push_dir("Textures")
+			.with_palette_name("SYS_PAL");
+		let mut anim_output = shared_output
+			.push_dir("Animations")
+			.with_palette_name("SYS_PAL");
+		tex_output.set_source_file("assets/TRAVERSE/TRAVSPRT.BNI");
+		anim_output.set_source_file("assets/TRAVERSE/TRAVSPRT.BNI");
 
 		for (name, frames) in trav_bni
 			.textures
@@ -568,76 +745,459 @@ pub fn parse_traverse(save_sounds: bool, save_textures: bool, save_meshes: bool)
 			if name == "PICKUPS" {
 				// all pickups except the last (gunter snack / bones) use the same colours,
 				// so we can just use that last palette to make sure they all export correctly.
-				let pal = all_palettes["GUNT_10"].as_slice();
+				let Some(pal) = all_palettes.get("GUNT_10") else {
+					continue; // level missing
+				};
+				let mut pickup_output = tex_output.with_palette_name("GUNT_10");
 				for (i, tex) in frames.iter().enumerate() {
 					// save as separate images instead of an animation
-					tex.save_as(&format!("PICKUPS_{i}"), &mut tex_output, Some(pal));
+					tex.save_as(&format!("PICKUPS_{i}"), &mut pickup_output, Some(pal));
 				}
 				continue;
 			}
 
 			if frames.len() == 1 {
-				frames[0].save_as(name, &mut tex_output, Some(&sys_pal));
+				frames[0].save_as(name, &mut tex_output, Some(sys_pal));
 			} else {
-				Texture::save_animated(frames, name, 24, &mut anim_output, Some(&sys_pal))
+				Texture::save_animated(frames, name, 24, &mut anim_output, Some(sys_pal))
 			};
 		}
 	}
 
-	if save_meshes {
-		// save animations
-		// todo move into individual level meshes
-		let mut anim_output = shared_output.push_dir("Meshes/Animations");
-		for (name, anim) in &trav_bni.animations_3d {
-			anim.save_as(name, &mut anim_output);
-		}
+	// todo move into individual level meshes
+	pipeline.save_animations(
+		&shared_output,
+		"Meshes/Animations",
+		trav_bni
+			.animations_3d
+			.iter()
+			.map(|(name, anim)| (*name, anim)),
+	);
+}
+
+/// Saves everything the level's files provide as one [dataset](crate::dataset) file, sorted by name
+fn save_dataset(name: &str, db: &AssetDb, cmi: &Cmi, dti: &Dti, output: &mut OutputWriter) {
+	let mut dataset = DatasetBuilder::new(name);
+	let mut arenas: Vec<_> = db.arenas().collect();
+	arenas.sort_unstable_by_key(|arena| arena.name());
+	for arena in arenas {
+		dataset.add_palette(arena.name(), db.palette(arena));
+	}
+	let mut textures: Vec<_> = db.textures().collect();
+	textures.sort_unstable_by_key(|(id, _)| id.name());
+	for (id, frames) in textures {
+		dataset.add_texture(id.name(), frames, db.texture_flags(id));
+	}
+	let mut pens: Vec<_> = db.pens().collect();
+	pens.sort_unstable_by_key(|(name, _)| *name);
+	for (name, pen) in pens {
+		dataset.add_pen(name, pen);
 	}
+	let mut meshes: Vec<_> = db.meshes().collect();
+	meshes.sort_unstable_by_key(|(id, _)| id.name());
+	for (id, mesh) in meshes {
+		dataset.add_mesh(id.name(), mesh);
+	}
+	dataset.add_scripts(cmi);
+	dataset.add_zones(dti);
+	output.write("Level", "mdkd", dataset.build());
+}
+
+/// Where each of a level's textures is used, and which arena palettes it was exported with
+struct TextureUsageReport<'a>(BTreeMap<&'a str, TextureUsage<'a>>);
+
+#[derive(serde::Serialize)]
+struct TextureUsage<'a> {
+	width: u16,
+	height: u16,
+	frames: usize,
+	/// Whether any mesh uses the texture
+	used: bool,
+	/// Meshes with the texture as a material
+	meshes: Vec<&'a str>,
+	/// Material files defining the texture (`MTI` for the level's, otherwise the arena's MTO)
+	materials: Vec<&'a str>,
+	palettes: Vec<&'a str>,
 }
 
-/// Determines how many unique palettes a texture uses
-fn filter_textures<'a>(
-	frames: &[Texture], palettes: &HashMap<String, Vec<u8>>, arenas: &mut Vec<(&'a str, &'a str)>,
-) -> usize {
-	if arenas.len() == 1 {
-		return 1;
+impl<'a> TextureUsageReport<'a> {
+	/// Lists every texture in `db` with the meshes that use it
+	fn new(db: &AssetDb<'a>) -> Self {
+		let usages = db
+			.textures()
+			.map(|(id, frames)| {
+				let usage = TextureUsage {
+					width: frames[0].width,
+					height: frames[0].height,
+					frames: frames.len(),
+					used: false,
+					meshes: Vec::new(),
+					materials: Vec::new(),
+					palettes: Vec::new(),
+				};
+				(id.name(), usage)
+			})
+			.collect();
+		let mut report = Self(usages);
+		for (name, mesh) in db.meshes() {
+			for tex_name in &mesh.materials {
+				if let Some(tex) = db.texture_id(tex_name) {
+					report.add_mesh(tex, name.name());
+				}
+			}
+		}
+		report
+	}
+	fn add_mesh(&mut self, texture: TextureId, mesh: &'a str) {
+		let usage = self.0.get_mut(texture.name()).unwrap();
+		usage.used = true;
+		if !usage.meshes.contains(&mesh) {
+			usage.meshes.push(mesh);
+		}
+	}
+	/// Records a material file defining the texture, ignoring pens
+	fn add_material(&mut self, name: &str, material: &Material, source: &'a str) {
+		if matches!(material, Material::Pen(_)) {
+			return;
+		}
+		let materials = &mut self.0.get_mut(name).unwrap().materials;
+		if !materials.contains(&source) {
+			materials.push(source);
+		}
+	}
+	fn add_palette(&mut self, texture: TextureId, arena: &'a str) {
+		let palettes = &mut self.0.get_mut(texture.name()).unwrap().palettes;
+		if !palettes.contains(&arena) {
+			palettes.push(arena);
+		}
+	}
+
+	/// Saves the report as JSON and CSV (with space separated lists)
+	fn save(&mut self, output: &mut OutputWriter) {
+		let mut csv = String::from("name,width,height,frames,used,meshes,materials,palettes\n");
+		for (name, usage) in &mut self.0 {
+			usage.meshes.sort_unstable();
+			usage.palettes.sort_unstable();
+			writeln!(
+				csv,
+				"{name},{},{},{},{},{},{},{}",
+				usage.width,
+				usage.height,
+				usage.frames,
+				usage.used,
+				usage.meshes.join(" "),
+				usage.materials.join(" "),
+				usage.palettes.join(" ")
+			)
+			.unwrap();
+		}
+		output.write("Texture Usage", "csv", csv);
+		output.write(
+			"Texture Usage",
+			"json",
+			serde_json::to_string_pretty(&self.0).unwrap(),
+		);
 	}
-	let colour_map = ColourMap::from_frames(frames);
-	filter_colours(colour_map, palettes, arenas)
 }
-fn filter_colours<'a>(
-	colour_map: ColourMap, palettes: &HashMap<String, Vec<u8>>,
-	arenas: &mut Vec<(&'a str, &'a str)>,
-) -> usize {
-	if arenas.len() == 1 {
-		return 1;
+
+/// Works out which arena palettes can share a single copy of a texture or mesh.
+struct PaletteMatcher<'p> {
+	db: &'p AssetDb<'p>,
+	/// Maximum mean squared RGB error for palettes to be considered the same (0 = exact)
+	tolerance: u32,
+	/// Approximate or ambiguous matches, for the report
+	report: String,
+	/// Everything saved more than once, for the split report
+	splits: Vec<PaletteSplit>,
+}
+
+/// A texture or mesh that's saved as a copy per palette (named `{asset}_{arena}`)
+#[derive(serde::Serialize)]
+struct PaletteSplit {
+	asset: String,
+	/// `texture` or `mesh`
+	kind: &'static str,
+	/// The first arena's palette, which the others are compared against
+	canonical: String,
+	/// Which arena's copy each arena uses
+	arenas: BTreeMap<String, String>,
+	/// The colour indices the asset uses that differ from the canonical palette, for each other copy
+	differing_colours: BTreeMap<String, Vec<u8>>,
+}
+
+impl<'p> PaletteMatcher<'p> {
+	fn new(db: &'p AssetDb<'p>, tolerance: u32) -> Self {
+		Self {
+			db,
+			tolerance,
+			report: String::from("name    	arena   	matched 	error	other candidates\n"),
+			splits: Vec::new(),
+		}
 	}
 
-	for arena in arenas.iter_mut() {
-		debug_assert_eq!(arena.0, arena.1);
-		arena.1 = arena.0;
+	/// Determines how many unique palettes a texture uses
+	fn filter_textures<'a>(
+		&mut self, name: TextureId, frames: &[Texture],
+		arenas: &mut Vec<(ArenaId<'a>, ArenaId<'a>)>,
+	) -> usize {
+		if arenas.len() == 1 {
+			return 1;
+		}
+		let colour_map = ColourMap::from_frames(frames);
+		self.filter_colours("texture", name.name(), colour_map, arenas)
 	}
-	arenas.sort_unstable_by(|arena1, arena2| {
-		let c1 = arena1.0.as_bytes()[0] == b'C';
-		let c2 = arena2.0.as_bytes()[0] == b'C';
-		c1.cmp(&c2).then(arena1.0.cmp(arena2.0))
-	});
-	arenas.dedup();
-
-	let mut num_unique = arenas.len();
-	for i in 1..arenas.len() {
-		let arena1 = arenas[i].0;
-		let pal1 = &palettes[arena1];
-		for (arena2_src, arena2_dest) in &arenas[0..i] {
-			if arena2_src != arena2_dest {
+
+	/// Points each arena at the first arena with matching colours (or itself if unique).
+	///
+	/// With a tolerance, each arena is pointed at the closest match instead.
+	fn filter_colours<'a>(
+		&mut self, kind: &'static str, name: &str, colour_map: ColourMap,
+		arenas: &mut Vec<(ArenaId<'a>, ArenaId<'a>)>,
+	) -> usize {
+		if arenas.len() == 1 {
+			return 1;
+		}
+
+		for arena in arenas.iter_mut() {
+			debug_assert_eq!(arena.0, arena.1);
+			arena.1 = arena.0;
+		}
+		arenas.sort_unstable_by(|arena1, arena2| {
+			let c1 = arena1.0.name().starts_with('C');
+			let c2 = arena2.0.name().starts_with('C');
+			c1.cmp(&c2).then(arena1.0.cmp(&arena2.0))
+		});
+		arenas.dedup();
+
+		let mut num_unique = arenas.len();
+		let mut candidates = Vec::new();
+		for i in 1..arenas.len() {
+			let arena1 = arenas[i].0;
+			let pal1 = self.db.palette(arena1);
+
+			if self.tolerance == 0 {
+				for (arena2_src, arena2_dest) in &arenas[0..i] {
+					if arena2_src != arena2_dest {
+						continue;
+					}
+					let pal2 = self.db.palette(*arena2_src);
+					if colour_map.compare(pal1, pal2) {
+						arenas[i].1 = *arena2_src;
+						num_unique -= 1;
+						break;
+					}
+				}
 				continue;
 			}
-			let pal2 = &palettes[*arena2_src];
-			if colour_map.compare(pal1, pal2) {
-				arenas[i].1 = *arena2_src;
-				num_unique -= 1;
-				break;
+
+			candidates.clear();
+			for (arena2_src, arena2_dest) in &arenas[0..i] {
+				if arena2_src != arena2_dest {
+					continue;
+				}
+				let error = colour_map.error(pal1, self.db.palette(*arena2_src));
+				if error <= self.tolerance {
+					candidates.push((error, *arena2_src));
+				}
+			}
+			candidates.sort_by_key(|(error, _)| *error);
+			let Some(&(best_error, best_arena)) = candidates.first() else {
+				continue;
+			};
+			arenas[i].1 = best_arena;
+			num_unique -= 1;
+
+			let ambiguous = candidates[1..]
+				.iter()
+				.any(|(error, _)| *error == best_error);
+			if best_error != 0 || ambiguous {
+				write!(
+					self.report,
+					"{name:8}\t{arena1:8}\t{best_arena:8}\t{best_error}\t"
+				)
+				.unwrap();
+				for (error, arena) in &candidates[1..] {
+					write!(self.report, " {arena} ({error})").unwrap();
+				}
+				self.report.push('\n');
 			}
 		}
+		if num_unique > 1 {
+			self.add_split(kind, name, &colour_map, arenas);
+		}
+		num_unique
+	}
+
+	fn add_split(
+		&mut self, kind: &'static str, name: &str, colour_map: &ColourMap,
+		arenas: &[(ArenaId, ArenaId)],
+	) {
+		let canonical = arenas[0].0;
+		let canonical_pal = self.db.palette(canonical);
+		let differing_colours = arenas
+			.iter()
+			.filter(|(src, dest)| src == dest && *src != canonical)
+			.map(|(arena, _)| {
+				let pal = self.db.palette(*arena);
+				let colours = (0..=255u8)
+					.filter(|&index| {
+						let i = index as usize * 3;
+						colour_map.contains(index) && pal[i..i + 3] != canonical_pal[i..i + 3]
+					})
+					.collect();
+				(arena.name().to_owned(), colours)
+			})
+			.collect();
+		self.splits.push(PaletteSplit {
+			asset: name.to_owned(),
+			kind,
+			canonical: canonical.name().to_owned(),
+			arenas: arenas
+				.iter()
+				.map(|(src, dest)| (src.name().to_owned(), dest.name().to_owned()))
+				.collect(),
+			differing_colours,
+		});
+	}
+
+	fn save_report(&mut self, output: &mut OutputWriter) {
+		if self.tolerance != 0 {
+			output.write("Palette Matches", "txt", &self.report);
+		}
+		self.splits
+			.sort_unstable_by(|a, b| (a.kind, &a.asset).cmp(&(b.kind, &b.asset)));
+		output.write(
+			"Palette Splits",
+			"json",
+			serde_json::to_string_pretty(&self.splits).unwrap(),
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data_formats::Mesh;
+	use crate::test_support::{MtiBuilder, mesh};
+
+	#[test]
+	fn test_palette_splits() {
+		let palette = |changes: &[(usize, u8)]| {
+			let mut palette = vec![0; 256 * 3];
+			for &(index, value) in changes {
+				palette[index * 3] = value;
+			}
+			palette
+		};
+		let mut db = AssetDb::default();
+		let a1 = db.add_arena("A1", palette(&[]));
+		let a2 = db.add_arena("A2", palette(&[(5, 10)]));
+		let a3 = db.add_arena("A3", palette(&[(2, 10), (5, 10)]));
+		let mut matcher = PaletteMatcher::new(&db, 0);
+
+		let colours = || ColourMap::from_pixels(&[1, 2]);
+		let mut arenas = vec![(a1, a1), (a2, a2)];
+		assert_eq!(
+			matcher.filter_colours("mesh", "SAME", colours(), &mut arenas),
+			1
+		);
+		let mut arenas = vec![(a3, a3), (a2, a2), (a1, a1)];
+		assert_eq!(
+			matcher.filter_colours("texture", "ROCK", colours(), &mut arenas),
+			2
+		);
+
+		let [split] = matcher.splits.as_slice() else {
+			panic!("expected one split");
+		};
+		assert_eq!(
+			serde_json::to_value(split).unwrap(),
+			serde_json::json!({
+				"asset": "ROCK",
+				"kind": "texture",
+				"canonical": "A1",
+				"arenas": {"A1": "A1", "A2": "A1", "A3": "A3"},
+				"differing_colours": {"A3": [2]},
+			})
+		);
+	}
+
+	#[test]
+	fn test_palette_tolerance() {
+		let palette = |red: u8| {
+			let mut palette = vec![0; 256 * 3];
+			palette[3] = red;
+			palette
+		};
+		let mut db = AssetDb::default();
+		let a1 = db.add_arena("A1", palette(0));
+		let a2 = db.add_arena("A2", palette(20));
+		// as close to A1 as to A2
+		let a3 = db.add_arena("A3", palette(10));
+		let mut matcher = PaletteMatcher::new(&db, 200);
+
+		let mut arenas = vec![(a1, a1), (a2, a2), (a3, a3)];
+		assert_eq!(
+			matcher.filter_colours("texture", "ROCK", ColourMap::from_pixels(&[1]), &mut arenas),
+			2
+		);
+		// ties go to the first arena, and are reported with the other candidates
+		assert_eq!(arenas, [(a1, a1), (a2, a2), (a3, a1)]);
+		let mut lines = matcher.report.lines().skip(1);
+		assert_eq!(lines.next(), Some("ROCK    \tA3\tA1\t100\t A2 (100)"));
+		assert_eq!(lines.next(), None);
+	}
+
+	#[test]
+	fn test_texture_usage() {
+		let mti_data = MtiBuilder::new("LEVEL3.MTI")
+			.pen("RED", 4)
+			.texture("WALL", 2, 2, &[1, 2, 3, 4])
+			.animated_texture("FLOOR", 1, 1, &[&[1], &[2]])
+			.build();
+		let mti = Mti::parse(Reader::new(&mti_data));
+		let spare = [Texture::new(3, 1, vec![1, 2, 3])];
+		let mesh_data = [mesh(&["WALL", "FLOOR", "RED"]), mesh(&["WALL", "MISSING"])];
+		let [door, crate_mesh] = mesh_data
+			.each_ref()
+			.map(|data| Mesh::try_parse(&mut Reader::new(data), false).unwrap());
+
+		let mut db = AssetDb::default();
+		for (name, material) in &mti.materials {
+			db.add_material(name, material);
+		}
+		db.add_texture("SPARE", &spare);
+		db.add_mesh("DOOR", &door);
+		db.add_mesh("CRATE", &crate_mesh);
+		let arena = db.add_arena("ARENA1", vec![0; 0x300]);
+
+		let mut report = TextureUsageReport::new(&db);
+		for (name, material) in &mti.materials {
+			report.add_material(name, material, "MTI");
+		}
+		report.add_material("FLOOR", &mti.materials[2].1, arena.name());
+		let wall = db.texture_id("WALL").unwrap();
+		report.add_palette(wall, arena.name());
+		report.add_palette(wall, arena.name());
+
+		let dir = std::path::Path::new("output/test_texture_usage");
+		let _ = std::fs::remove_dir_all(dir);
+		report.save(&mut OutputWriter::new_in_dir(dir));
+		let csv = std::fs::read_to_string(dir.join("Texture Usage.csv")).unwrap();
+		std::fs::remove_dir_all(dir).unwrap();
+		assert_eq!(
+			csv,
+			"name,width,height,frames,used,meshes,materials,palettes\n\
+			FLOOR,1,1,2,true,DOOR,MTI ARENA1,\n\
+			SPARE,3,1,1,false,,,\n\
+			WALL,2,2,1,true,CRATE DOOR,MTI,ARENA1\n"
+		);
+		let unused: Vec<&str> = report
+			.0
+			.iter()
+			.filter(|(_, usage)| !usage.used)
+			.map(|(name, _)| *name)
+			.collect();
+		assert_eq!(unused, ["SPARE"]);
 	}
-	num_unique
 }
@@ -0,0 +1,229 @@
+//! A writer for [GIF](https://www.w3.org/Graphics/GIF/spec-gif89a.txt) animations,
+//! which are handier than animated PNGs for sharing.
+//!
+//! Frames are already palette indices, so the palette is written as it is without quantizing.
+use std::collections::HashMap;
+
+/// Every palette index fits in 8 bits
+const MIN_CODE_SIZE: u8 = 8;
+/// Codes are at most 12 bits
+const MAX_CODE_SIZE: u8 = 12;
+const CLEAR_CODE: u16 = 1 << MIN_CODE_SIZE;
+const END_CODE: u16 = CLEAR_CODE + 1;
+/// Data sub-blocks are at most this long
+const MAX_BLOCK: usize = 255;
+
+/// What happens to a frame's rectangle before the next frame is drawn over it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dispose {
+	Keep = 1,
+	/// Back to transparent
+	Clear = 2,
+}
+
+pub struct GifFrame<'a> {
+	pub x: u16,
+	pub y: u16,
+	pub width: u16,
+	pub height: u16,
+	pub pixels: &'a [u8],
+	/// In hundredths of a second
+	pub delay: u16,
+	pub dispose: Dispose,
+}
+
+/// Encodes a looping GIF with an rgb palette of up to 256 colours
+pub fn write_gif(
+	width: u16, height: u16, palette: &[u8], transparent: Option<u8>, frames: &[GifFrame],
+) -> Vec<u8> {
+	assert_eq!(palette.len() % 3, 0);
+	assert!(palette.len() <= 256 * 3, "too many colours for a gif");
+
+	let mut result = b"GIF89a".to_vec();
+	result.extend_from_slice(&width.to_le_bytes());
+	result.extend_from_slice(&height.to_le_bytes());
+	// global 8 bit colour table of 256 entries
+	result.extend_from_slice(&[0xF7, transparent.unwrap_or(0), 0]);
+	result.extend_from_slice(palette);
+	result.resize(result.len() + 256 * 3 - palette.len(), 0);
+
+	if frames.len() > 1 {
+		// loop forever
+		result.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+	}
+
+	for frame in frames {
+		assert_eq!(
+			frame.width as usize * frame.height as usize,
+			frame.pixels.len(),
+			"mismatched frame dimensions"
+		);
+		assert!(frame.x + frame.width <= width && frame.y + frame.height <= height);
+
+		// graphic control extension
+		let packed = ((frame.dispose as u8) << 2) | transparent.is_some() as u8;
+		result.extend_from_slice(&[0x21, 0xF9, 4, packed]);
+		result.extend_from_slice(&frame.delay.to_le_bytes());
+		result.extend_from_slice(&[transparent.unwrap_or(0), 0]);
+
+		// image descriptor
+		result.push(0x2C);
+		for value in [frame.x, frame.y, frame.width, frame.height] {
+			result.extend_from_slice(&value.to_le_bytes());
+		}
+		result.push(0);
+
+		result.push(MIN_CODE_SIZE);
+		for block in compress(frame.pixels).chunks(MAX_BLOCK) {
+			result.push(block.len() as u8);
+			result.extend_from_slice(block);
+		}
+		result.push(0);
+	}
+
+	result.push(0x3B);
+	result
+}
+
+/// Packs codes starting from the lowest bit
+#[derive(Default)]
+struct BitWriter {
+	data: Vec<u8>,
+	buffer: u32,
+	bits: u8,
+}
+
+impl BitWriter {
+	fn write(&mut self, code: u16, size: u8) {
+		self.buffer |= (code as u32) << self.bits;
+		self.bits += size;
+		while self.bits >= 8 {
+			self.data.push(self.buffer as u8);
+			self.buffer >>= 8;
+			self.bits -= 8;
+		}
+	}
+	fn finish(mut self) -> Vec<u8> {
+		if self.bits != 0 {
+			self.data.push(self.buffer as u8);
+		}
+		self.data
+	}
+}
+
+/// LZW compresses palette indices, starting over whenever the code table fills up
+fn compress(pixels: &[u8]) -> Vec<u8> {
+	let mut writer = BitWriter::default();
+	let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+	let mut code_size = MIN_CODE_SIZE + 1;
+	let mut next_code = END_CODE + 1;
+	writer.write(CLEAR_CODE, code_size);
+
+	let Some((&first, rest)) = pixels.split_first() else {
+		writer.write(END_CODE, code_size);
+		return writer.finish();
+	};
+	let mut prefix = first as u16;
+	for &pixel in rest {
+		if let Some(&code) = table.get(&(prefix, pixel)) {
+			prefix = code;
+			continue;
+		}
+		writer.write(prefix, code_size);
+		if next_code < 1 << MAX_CODE_SIZE {
+			table.insert((prefix, pixel), next_code);
+			next_code += 1;
+			if next_code > 1 << code_size && code_size < MAX_CODE_SIZE {
+				code_size += 1;
+			}
+		} else {
+			writer.write(CLEAR_CODE, code_size);
+			table.clear();
+			code_size = MIN_CODE_SIZE + 1;
+			next_code = END_CODE + 1;
+		}
+		prefix = pixel as u16;
+	}
+	writer.write(prefix, code_size);
+	writer.write(END_CODE, code_size);
+	writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A plain LZW decoder following the spec, to check the encoder against
+	fn decompress(data: &[u8]) -> Vec<u8> {
+		let mut result = Vec::new();
+		let mut table: Vec<Vec<u8>> = Vec::new();
+		let mut code_size = MIN_CODE_SIZE + 1;
+		let mut prev: Option<Vec<u8>> = None;
+		let (mut buffer, mut bits, mut pos) = (0u32, 0u8, 0);
+		loop {
+			while bits < code_size {
+				buffer |= (data[pos] as u32) << bits;
+				pos += 1;
+				bits += 8;
+			}
+			let code = (buffer & ((1 << code_size) - 1)) as u16;
+			buffer >>= code_size;
+			bits -= code_size;
+
+			if code == CLEAR_CODE {
+				table = (0..=255).map(|i| vec![i]).chain([vec![], vec![]]).collect();
+				code_size = MIN_CODE_SIZE + 1;
+				prev = None;
+				continue;
+			}
+			if code == END_CODE {
+				return result;
+			}
+			let entry = match (table.get(code as usize), &prev) {
+				(Some(entry), _) => entry.clone(),
+				(None, Some(prev)) => [prev.as_slice(), &prev[..1]].concat(),
+				(None, None) => panic!("bad code {code}"),
+			};
+			if let Some(prev) = prev
+				&& table.len() < 1 << MAX_CODE_SIZE
+			{
+				table.push([prev.as_slice(), &entry[..1]].concat());
+				if table.len() == 1 << code_size && code_size < MAX_CODE_SIZE {
+					code_size += 1;
+				}
+			}
+			result.extend_from_slice(&entry);
+			prev = Some(entry);
+		}
+	}
+
+	#[test]
+	fn test_gif() {
+		// noisy enough to fill the code table a few times over
+		let mut seed = 1u32;
+		let pixels: Vec<u8> = (0..100_000)
+			.map(|i| {
+				seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+				if i % 3 == 0 { 7 } else { (seed >> 24) as u8 }
+			})
+			.collect();
+		for pixels in [&pixels[..], &[], &[5; 1000]] {
+			assert_eq!(decompress(&compress(pixels)), pixels);
+		}
+
+		let frame = GifFrame {
+			x: 0,
+			y: 0,
+			width: 2,
+			height: 1,
+			pixels: &[0, 1],
+			delay: 8,
+			dispose: Dispose::Keep,
+		};
+		let gif = write_gif(2, 1, &[0, 0, 0, 255, 0, 0], Some(0), &[frame]);
+		assert!(gif.starts_with(b"GIF89a\x02\x00\x01\x00\xF7\x00\x00\x00\x00\x00\xFF\x00\x00"));
+		let gce = 13 + 256 * 3;
+		assert_eq!(gif[gce..gce + 8], [0x21, 0xF9, 4, 0x05, 8, 0, 0, 0]);
+		assert_eq!(gif.last(), Some(&0x3B));
+	}
+}
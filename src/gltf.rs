@@ -3,7 +3,8 @@
 use serde::{Serialize, Serializer};
 use std::mem;
 
-use crate::{Vec2, Vec3};
+use crate::data_formats::palette;
+use crate::{ExportSettings, Vec2, Vec3, Vec4};
 
 #[derive(Serialize)]
 struct Asset {
@@ -31,6 +32,15 @@ struct Primitive {
 	material: Option<MaterialIndex>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	mode: Option<PrimitiveMode>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	targets: Vec<MorphTarget>,
+	#[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+	extras: serde_json::Map<String, serde_json::Value>,
+}
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct MorphTarget {
+	position: AccessorIndex,
 }
 #[derive(Serialize)]
 #[serde(rename_all = "UPPERCASE")]
@@ -40,6 +50,8 @@ struct Attributes {
 	texcoord_0: Option<AccessorIndex>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	color_0: Option<AccessorIndex>,
+	#[serde(rename = "_TRIANGLE_ID", skip_serializing_if = "Option::is_none")]
+	triangle_id: Option<AccessorIndex>,
 }
 
 #[derive(Serialize, Clone, Copy, Eq, PartialEq)]
@@ -66,6 +78,8 @@ struct Material {
 	pbr_metallic_roughness: PbrMetallicRoughness,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	alpha_mode: Option<AlphaMode>,
+	#[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+	extras: serde_json::Map<String, serde_json::Value>,
 }
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -102,9 +116,9 @@ struct Image {
 	name: String,
 }
 
-#[derive(Serialize, Default, Clone)]
+#[derive(Serialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
 #[serde(into = "usize")]
-enum FilterType {
+pub enum FilterType {
 	#[default]
 	Linear = 9729,
 	Nearest = 9728,
@@ -114,9 +128,9 @@ impl From<FilterType> for usize {
 		value as usize
 	}
 }
-#[derive(Serialize, Default, Clone)]
+#[derive(Serialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
 #[serde(into = "usize")]
-enum WrapType {
+pub enum WrapType {
 	#[default]
 	Repeat = 10497,
 	Clamp = 33071,
@@ -127,7 +141,7 @@ impl From<WrapType> for usize {
 	}
 }
 
-#[derive(Default, Serialize)]
+#[derive(Default, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 struct Sampler {
 	mag_filter: FilterType,
@@ -140,6 +154,8 @@ struct Sampler {
 #[serde(rename_all = "camelCase")]
 struct Accessor {
 	buffer_view: BufferViewIndex,
+	#[serde(skip_serializing_if = "is_zero")]
+	byte_offset: usize,
 	component_type: AccessorComponentType,
 	#[serde(skip_serializing_if = "is_false")]
 	normalized: bool,
@@ -154,6 +170,27 @@ struct Accessor {
 fn is_false(value: &bool) -> bool {
 	!value
 }
+fn is_zero(value: &usize) -> bool {
+	*value == 0
+}
+impl Accessor {
+	fn element_size(&self) -> usize {
+		let component_size = match self.component_type {
+			AccessorComponentType::SignedByte | AccessorComponentType::UnsignedByte => 1,
+			AccessorComponentType::SignedShort | AccessorComponentType::UnsignedShort => 2,
+			AccessorComponentType::UnsignedInt | AccessorComponentType::Float => 4,
+		};
+		let components = match self.element_type {
+			AccessorType::Scalar => 1,
+			AccessorType::Vec2 => 2,
+			AccessorType::Vec3 => 3,
+			AccessorType::Vec4 | AccessorType::Mat2 => 4,
+			AccessorType::Mat3 => 9,
+			AccessorType::Mat4 => 16,
+		};
+		component_size * components
+	}
+}
 
 #[derive(Serialize, Clone)]
 #[serde(into = "usize")]
@@ -243,22 +280,15 @@ struct BufferView {
 	#[serde(skip_serializing_if = "Option::is_none")]
 	target: Option<usize>,
 	byte_offset: usize,
-	//pub byte_stride : Option<usize>
+	#[serde(skip_serializing_if = "Option::is_none")]
+	byte_stride: Option<usize>,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct Scene {
 	name: String,
-	nodes: [NodeIndex; 1],
-}
-impl Default for Scene {
-	fn default() -> Self {
-		Self {
-			name: Default::default(),
-			nodes: [NodeIndex(0)],
-		}
-	}
+	nodes: Vec<NodeIndex>,
 }
 
 #[derive(Serialize)]
@@ -269,10 +299,16 @@ struct Node {
 	mesh: Option<MeshIndex>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	translation: Option<Vec3>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	rotation: Option<Vec4>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	scale: Option<Vec3>,
 	#[serde(skip_serializing_if = "Vec::is_empty")]
 	children: Vec<NodeIndex>,
 	#[serde(skip_serializing_if = "serde_json::Map::is_empty")]
 	extras: serde_json::Map<String, serde_json::Value>,
+	#[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+	extensions: serde_json::Map<String, serde_json::Value>,
 
 	#[serde(skip)]
 	parent: Option<NodeIndex>,
@@ -315,6 +351,8 @@ struct Animation {
 	name: String,
 	channels: Vec<AnimationChannel>,
 	samplers: Vec<AnimationSampler>,
+	#[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+	extras: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
@@ -337,13 +375,17 @@ struct ImageIndex(usize);
 struct TextureIndex(usize);
 #[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
 pub struct AnimationIndex(usize);
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SceneIndex(usize);
 
 #[derive(Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Gltf {
 	asset: Asset,
-	scene: usize,
-	scenes: [Scene; 1],
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	extensions_used: Vec<&'static str>,
+	scene: SceneIndex,
+	scenes: Vec<Scene>,
 	#[serde(skip_serializing_if = "Vec::is_empty")]
 	nodes: Vec<Node>,
 	#[serde(skip_serializing_if = "Vec::is_empty")]
@@ -367,6 +409,8 @@ pub struct Gltf {
 
 	#[serde(skip)]
 	debug_cube: Option<MeshIndex>,
+	#[serde(skip)]
+	settings: ExportSettings,
 }
 
 enum PrimitiveTarget {
@@ -379,42 +423,91 @@ enum PrimitiveTarget {
 impl Gltf {
 	pub fn new(name: String) -> Self {
 		Gltf {
-			scenes: [Scene {
+			scenes: vec![Scene {
 				name: name.clone(),
-				nodes: [NodeIndex(0)],
+				nodes: vec![NodeIndex(0)],
 			}],
 			nodes: vec![Node {
 				name,
 				mesh: None,
 				translation: None,
+				rotation: None,
+				scale: None,
 				children: Vec::new(),
 				parent: None,
 				extras: Default::default(),
+				extensions: Default::default(),
 			}],
 			..Default::default()
 		}
 	}
 
+	/// Creates a GLTF which converts all positions with the given settings
+	pub fn with_settings(name: String, settings: ExportSettings) -> Self {
+		Gltf {
+			settings,
+			..Self::new(name)
+		}
+	}
+	pub fn export_settings(&self) -> &ExportSettings {
+		&self.settings
+	}
+
+	/// The root node of the first scene, which every GLTF starts with
 	pub fn get_root_node(&self) -> NodeIndex {
 		NodeIndex(0)
 	}
 
+	/// Adds an empty scene, see [Gltf::add_scene_node]
+	pub fn create_scene(&mut self, name: String) -> SceneIndex {
+		self.scenes.push(Scene {
+			name,
+			nodes: Vec::new(),
+		});
+		SceneIndex(self.scenes.len() - 1)
+	}
+	/// Adds a node (which mustn't have a parent) to the top level of a scene.
+	/// Nodes can be in more than one scene.
+	pub fn add_scene_node(&mut self, scene: SceneIndex, node: NodeIndex) {
+		assert!(
+			self.nodes[node.0].parent.is_none(),
+			"scene nodes can't have a parent"
+		);
+		let nodes = &mut self.scenes[scene.0].nodes;
+		if !nodes.contains(&node) {
+			nodes.push(node);
+		}
+	}
+	/// Sets which scene viewers show first (the first scene by default)
+	pub fn set_default_scene(&mut self, scene: SceneIndex) {
+		assert!(scene.0 < self.scenes.len(), "invalid scene");
+		self.scene = scene;
+	}
+
 	#[must_use]
 	pub fn create_colour_material(&mut self, name: String, colour: [f32; 4]) -> MaterialIndex {
 		self.materials.push(Material {
 			name,
 			pbr_metallic_roughness: PbrMetallicRoughness::BaseColorFactor(colour),
 			alpha_mode: None,
+			extras: Default::default(),
 		});
 		MaterialIndex(self.materials.len() - 1)
 	}
 
 	#[must_use]
 	pub fn create_translucent_material(&mut self, name: String) -> MaterialIndex {
+		self.create_translucent_colour_material(name, [1.0; 4])
+	}
+	#[must_use]
+	pub fn create_translucent_colour_material(
+		&mut self, name: String, colour: [f32; 4],
+	) -> MaterialIndex {
 		self.materials.push(Material {
 			name,
-			pbr_metallic_roughness: PbrMetallicRoughness::BaseColorFactor([1.0; 4]),
+			pbr_metallic_roughness: PbrMetallicRoughness::BaseColorFactor(colour),
 			alpha_mode: Some(AlphaMode::Blend),
+			extras: Default::default(),
 		});
 		MaterialIndex(self.materials.len() - 1)
 	}
@@ -424,6 +517,7 @@ impl Gltf {
 			name,
 			pbr_metallic_roughness: PbrMetallicRoughness::RoughnessFactor(0.0),
 			alpha_mode: None,
+			extras: Default::default(),
 		});
 		MaterialIndex(self.materials.len() - 1)
 	}
@@ -438,16 +532,13 @@ impl Gltf {
 			uri: relative_filename,
 		});
 		let texture_index = TextureIndex(self.textures.len());
+		let sampler = self.get_sampler([WrapType::Repeat; 2]);
 		self.textures.push(Texture {
 			name: name.clone(),
-			sampler: 0,
+			sampler,
 			source: image_index,
 		});
 
-		if self.samplers.is_empty() {
-			self.samplers.push(Default::default());
-		}
-
 		let material_index = MaterialIndex(self.materials.len());
 		self.materials.push(Material {
 			name,
@@ -455,10 +546,62 @@ impl Gltf {
 				index: texture_index,
 			}),
 			alpha_mode: alpha_mode.filter(|mode| !matches!(mode, AlphaMode::Opaque)),
+			extras: Default::default(),
 		});
 		material_index
 	}
 
+	/// Sets how a texture material wraps along u and v, removing any sampler it leaves unused
+	pub fn set_material_wrap(&mut self, material: MaterialIndex, wrap: [WrapType; 2]) {
+		let PbrMetallicRoughness::BaseColorTexture(info) =
+			&self.materials[material.0].pbr_metallic_roughness
+		else {
+			return;
+		};
+		let texture = info.index.0;
+		let old_sampler = self.textures[texture].sampler;
+		self.textures[texture].sampler = self.get_sampler(wrap);
+		if self.textures.iter().all(|tex| tex.sampler != old_sampler) {
+			self.samplers.remove(old_sampler);
+			for tex in &mut self.textures {
+				if tex.sampler > old_sampler {
+					tex.sampler -= 1;
+				}
+			}
+		}
+	}
+
+	/// Finds or creates a sampler with the given wrapping and the filter from the export settings
+	fn get_sampler(&mut self, [wrap_s, wrap_t]: [WrapType; 2]) -> usize {
+		let filter = if self.settings.nearest_filter {
+			FilterType::Nearest
+		} else {
+			FilterType::Linear
+		};
+		let sampler = Sampler {
+			mag_filter: filter,
+			min_filter: filter,
+			wrap_s,
+			wrap_t,
+		};
+		match self.samplers.iter().position(|s| *s == sampler) {
+			Some(index) => index,
+			None => {
+				self.samplers.push(sampler);
+				self.samplers.len() - 1
+			}
+		}
+	}
+
+	/// Changes the uri of every image saved outside the glTF (rather than embedded in it)
+	pub fn map_image_uris(&mut self, mut f: impl FnMut(&str) -> String) {
+		for image in &mut self.images {
+			if !image.uri.starts_with("data:") {
+				image.uri = f(&image.uri);
+			}
+		}
+	}
+
 	#[must_use]
 	pub fn create_texture_material_embedded(
 		&mut self, name: String, data: &[u8], alpha_mode: Option<AlphaMode>,
@@ -473,9 +616,12 @@ impl Gltf {
 			name,
 			mesh,
 			translation: None,
+			rotation: None,
+			scale: None,
 			children: Vec::new(),
 			parent: None,
 			extras: Default::default(),
+			extensions: Default::default(),
 		});
 		result
 	}
@@ -486,6 +632,29 @@ impl Gltf {
 		self.set_node_parent(parent, child_node);
 		child_node
 	}
+	/// Copies a node and everything under it into `parent`, sharing their meshes
+	pub fn copy_node(&mut self, node: NodeIndex, parent: NodeIndex) -> NodeIndex {
+		let source = &self.nodes[node.0];
+		let copy = Node {
+			name: source.name.clone(),
+			mesh: source.mesh,
+			translation: source.translation,
+			rotation: source.rotation,
+			scale: source.scale,
+			children: Vec::new(),
+			extras: source.extras.clone(),
+			extensions: source.extensions.clone(),
+			parent: None,
+		};
+		let children = source.children.clone();
+		let result = NodeIndex(self.nodes.len());
+		self.nodes.push(copy);
+		self.set_node_parent(parent, result);
+		for child in children {
+			self.copy_node(child, result);
+		}
+		result
+	}
 	pub fn get_node_name_mut(&mut self, node: NodeIndex) -> &mut String {
 		&mut self.nodes[node.0].name
 	}
@@ -505,17 +674,48 @@ impl Gltf {
 		self.nodes[node.0].mesh = Some(mesh);
 	}
 	pub fn set_node_position(&mut self, node: NodeIndex, position: Vec3) {
-		self.nodes[node.0].translation = Some(position);
+		self.nodes[node.0].translation = Some(self.settings.transform_point(position));
+	}
+	/// Sets a node's rotation from a (parsed, Y-up) unit quaternion
+	pub fn set_node_rotation(&mut self, node: NodeIndex, rotation: Vec4) {
+		self.nodes[node.0].rotation = Some(self.settings.transform_rotation(rotation));
+	}
+	/// Sets a node's scale along each (parsed, Y-up) axis
+	pub fn set_node_scale(&mut self, node: NodeIndex, scale: Vec3) {
+		self.nodes[node.0].scale = Some(self.settings.transform_scale(scale));
 	}
 	pub fn get_node_mesh(&self, node: NodeIndex) -> Option<MeshIndex> {
 		self.nodes[node.0].mesh
 	}
+	pub fn set_material_extras(
+		&mut self, material: MaterialIndex, name: impl Into<String>,
+		value: impl Into<serde_json::Value>,
+	) {
+		self.materials[material.0]
+			.extras
+			.insert(name.into(), value.into());
+	}
+
 	pub fn set_node_extras(
 		&mut self, node: NodeIndex, name: impl Into<String>, value: impl Into<serde_json::Value>,
 	) {
 		self.nodes[node.0].extras.insert(name.into(), value.into());
 	}
 
+	/// Sets lower detail versions of a node using `MSFT_lod`, highest detail first.
+	///
+	/// The LOD nodes should not be part of the scene hierarchy.
+	pub fn set_node_lods(&mut self, node: NodeIndex, lods: &[NodeIndex]) {
+		const EXTENSION: &str = "MSFT_lod";
+		if !self.extensions_used.contains(&EXTENSION) {
+			self.extensions_used.push(EXTENSION);
+		}
+		let ids: Vec<usize> = lods.iter().map(|lod| lod.0).collect();
+		self.nodes[node.0]
+			.extensions
+			.insert(EXTENSION.to_owned(), serde_json::json!({ "ids": ids }));
+	}
+
 	pub fn create_base_node(&mut self, name: String, mesh: Option<MeshIndex>) -> NodeIndex {
 		self.create_child_node(self.get_root_node(), name, mesh)
 	}
@@ -557,6 +757,7 @@ impl Gltf {
 			buffer: buffer_index,
 			byte_length: data_u8.len(),
 			byte_offset: 0,
+			byte_stride: None,
 			target,
 		});
 
@@ -565,6 +766,7 @@ impl Gltf {
 		let accessor_index = AccessorIndex(self.accessors.len());
 		self.accessors.push(Accessor {
 			buffer_view: view_index,
+			byte_offset: 0,
 			component_type: T::COMPONENT_TYPE,
 			normalized: T::NORMALIZED,
 			count: data.len(),
@@ -580,7 +782,8 @@ impl Gltf {
 		&mut self, mesh: MeshIndex, positions: &[Vec3], indices: &[u16],
 		material: Option<MaterialIndex>,
 	) -> PrimitiveIndex {
-		let position = self.add_primitive_data(positions, PrimitiveTarget::Vertices);
+		let positions = self.settings.transform_points(positions);
+		let position = self.add_primitive_data(&positions, PrimitiveTarget::Vertices);
 		let indices = self.add_primitive_data(indices, PrimitiveTarget::Indices);
 
 		let primitives = &mut self.meshes[mesh.0].primitives;
@@ -590,10 +793,13 @@ impl Gltf {
 				position,
 				texcoord_0: None,
 				color_0: None,
+				triangle_id: None,
 			},
 			indices,
 			material,
 			mode: None,
+			targets: Vec::new(),
+			extras: Default::default(),
 		});
 
 		PrimitiveIndex(mesh, primitive_index)
@@ -601,6 +807,25 @@ impl Gltf {
 	pub fn set_primitive_mode(&mut self, primitive: PrimitiveIndex, mode: PrimitiveMode) {
 		self.meshes[primitive.0.0].primitives[primitive.1].mode = Some(mode);
 	}
+	pub fn set_primitive_extras(
+		&mut self, primitive: PrimitiveIndex, name: impl Into<String>,
+		value: impl Into<serde_json::Value>,
+	) {
+		self.meshes[primitive.0.0].primitives[primitive.1]
+			.extras
+			.insert(name.into(), value.into());
+	}
+
+	/// Adds a morph target moving each vertex by `displacements`, returning its index
+	pub fn add_primitive_morph_target(
+		&mut self, primitive: PrimitiveIndex, displacements: &[Vec3],
+	) -> usize {
+		let displacements = self.settings.transform_points(displacements);
+		let position = self.add_primitive_data(&displacements, PrimitiveTarget::Vertices);
+		let targets = &mut self.meshes[primitive.0.0].primitives[primitive.1].targets;
+		targets.push(MorphTarget { position });
+		targets.len() - 1
+	}
 
 	pub fn add_primitive_uvs(&mut self, primitive: PrimitiveIndex, uvs: &[Vec2]) {
 		if uvs.is_empty() {
@@ -611,16 +836,37 @@ impl Gltf {
 			.attributes
 			.texcoord_0 = Some(uvs);
 	}
+	/// Adds vertex colours from palette colours (sRGB, with linear alpha)
 	pub fn add_primitive_colours(&mut self, primitive: PrimitiveIndex, colours: &[[u8; 4]]) {
 		if colours.is_empty() {
 			return;
 		}
-		let colours = self.add_primitive_data(colours, PrimitiveTarget::Vertices);
+		let colours = if self.settings.srgb_vertex_colours {
+			self.add_primitive_data(colours, PrimitiveTarget::Vertices)
+		} else {
+			// bytes would lose too much precision in dark colours
+			let colours: Vec<[f32; 4]> = colours
+				.iter()
+				.map(|&[r, g, b, a]| {
+					let [r, g, b] = [r, g, b].map(palette::srgb_to_linear);
+					[r, g, b, a as f32 / 255.0]
+				})
+				.collect();
+			self.add_primitive_data(&colours, PrimitiveTarget::Vertices)
+		};
 		self.meshes[primitive.0.0].primitives[primitive.1]
 			.attributes
 			.color_0 = Some(colours);
 	}
 
+	/// Adds the BSP id of the triangle each vertex belongs to as a custom `_TRIANGLE_ID` attribute
+	pub fn add_primitive_triangle_ids(&mut self, primitive: PrimitiveIndex, ids: &[u16]) {
+		let ids = self.add_primitive_data(ids, PrimitiveTarget::Vertices);
+		self.meshes[primitive.0.0].primitives[primitive.1]
+			.attributes
+			.triangle_id = Some(ids);
+	}
+
 	pub fn create_mesh_from_primitive(
 		&mut self, name: String, positions: &[Vec3], indices: &[u16], uvs: Option<&[Vec2]>,
 		material: Option<MaterialIndex>,
@@ -639,9 +885,18 @@ impl Gltf {
 			name,
 			channels: Vec::new(),
 			samplers: Vec::new(),
+			extras: Default::default(),
 		});
 		result
 	}
+	pub fn set_animation_extras(
+		&mut self, animation: AnimationIndex, name: impl Into<String>,
+		value: impl Into<serde_json::Value>,
+	) {
+		self.animations[animation.0]
+			.extras
+			.insert(name.into(), value.into());
+	}
 
 	pub fn create_animation_timestamps(&mut self, num_frames: usize, fps: f32) -> AccessorIndex {
 		let period = fps.recip();
@@ -659,8 +914,63 @@ impl Gltf {
 		&mut self, animation: AnimationIndex, node: NodeIndex, timestamps: AccessorIndex,
 		path: &[Vec3], interpolation: Option<AnimationInterpolationMode>,
 	) {
-		let data = self.add_primitive_data(path, PrimitiveTarget::AnimationData);
-
+		let path = self.settings.transform_points(path);
+		let data = self.add_primitive_data(&path, PrimitiveTarget::AnimationData);
+		self.add_animation_channel(
+			animation,
+			node,
+			timestamps,
+			data,
+			interpolation,
+			AnimationChannelTargetPath::Translation,
+		);
+	}
+	pub fn add_animation_rotation(
+		&mut self, animation: AnimationIndex, node: NodeIndex, timestamps: AccessorIndex,
+		rotations: &[Vec4], interpolation: Option<AnimationInterpolationMode>,
+	) {
+		let rotations: Vec<Vec4> = rotations
+			.iter()
+			.map(|&q| self.settings.transform_rotation(q))
+			.collect();
+		let data = if self.settings.anim_compression.quantize {
+			let rotations: Vec<[Normalized<i16>; 4]> = rotations
+				.iter()
+				.map(|q| q.map(|n| Normalized((n * i16::MAX as f32).round() as i16)))
+				.collect();
+			self.add_primitive_data(&rotations, PrimitiveTarget::AnimationData)
+		} else {
+			self.add_primitive_data(&rotations, PrimitiveTarget::AnimationData)
+		};
+		self.add_animation_channel(
+			animation,
+			node,
+			timestamps,
+			data,
+			interpolation,
+			AnimationChannelTargetPath::Rotation,
+		);
+	}
+	/// Adds morph target weights, with `weights` holding every target's weight for each timestamp
+	pub fn add_animation_weights(
+		&mut self, animation: AnimationIndex, node: NodeIndex, timestamps: AccessorIndex,
+		weights: &[f32], interpolation: Option<AnimationInterpolationMode>,
+	) {
+		let data = self.add_primitive_data(weights, PrimitiveTarget::AnimationData);
+		self.add_animation_channel(
+			animation,
+			node,
+			timestamps,
+			data,
+			interpolation,
+			AnimationChannelTargetPath::Weights,
+		);
+	}
+	fn add_animation_channel(
+		&mut self, animation: AnimationIndex, node: NodeIndex, timestamps: AccessorIndex,
+		data: AccessorIndex, interpolation: Option<AnimationInterpolationMode>,
+		path: AnimationChannelTargetPath,
+	) {
 		let anim = &mut self.animations[animation.0];
 		let sampler_index = anim.samplers.len();
 		anim.samplers.push(AnimationSampler {
@@ -670,42 +980,109 @@ impl Gltf {
 		});
 		anim.channels.push(AnimationChannel {
 			sampler: sampler_index,
-			target: AnimationChannelTarget {
-				node,
-				path: AnimationChannelTargetPath::Translation,
-			},
+			target: AnimationChannelTarget { node, path },
 		});
 	}
 
+	/// Repacks all the data into a single buffer, interleaving each primitive's vertex attributes
+	/// into one buffer view (with a stride) and giving every other accessor its own view.
+	///
+	/// [Gltf::render_json] does this, and doing it again doesn't change anything.
 	pub fn combine_buffers(&mut self) {
-		// todo dont merge buffers of different types?
-		for view in &mut self.buffer_views {
-			let buffer_index = view.buffer.0;
-			if buffer_index == 0 {
+		// the attributes of each primitive that can share a view
+		let mut groups: Vec<Vec<AccessorIndex>> = Vec::new();
+		let mut group_of: Vec<Option<usize>> = vec![None; self.accessors.len()];
+		for primitive in self.meshes.iter().flat_map(|mesh| &mesh.primitives) {
+			let attributes = &primitive.attributes;
+			let group: Vec<AccessorIndex> = [Some(attributes.position)]
+				.into_iter()
+				.chain([
+					attributes.texcoord_0,
+					attributes.color_0,
+					attributes.triangle_id,
+				])
+				.flatten()
+				.collect();
+			let count = self.accessors[group[0].0].count;
+			if group.len() < 2
+				|| group.iter().any(|accessor| {
+					group_of[accessor.0].is_some() || self.accessors[accessor.0].count != count
+				}) {
 				continue;
 			}
+			for accessor in &group {
+				group_of[accessor.0] = Some(groups.len());
+			}
+			groups.push(group);
+		}
 
-			let mut src: Buffer = mem::take(&mut self.buffers[buffer_index]);
-			assert_eq!(view.byte_offset, 0);
-			assert!(src.byte_length != 0 && src.byte_length == src.uri.len());
-
-			let dest = &mut self.buffers[0];
-
-			while dest.byte_length % 4 != 0 {
-				dest.uri.push(0);
-				dest.byte_length += 1;
+		let old_buffers = mem::take(&mut self.buffers);
+		let old_views = mem::take(&mut self.buffer_views);
+		let mut data = Vec::new();
+		let mut done = vec![false; self.accessors.len()];
+		for index in 0..self.accessors.len() {
+			if done[index] {
+				continue;
+			}
+			let members = match group_of[index] {
+				Some(group) => groups[group].as_slice(),
+				None => &[AccessorIndex(index)],
+			};
+
+			// vertex attributes have to be aligned to 4 bytes
+			let mut offsets = Vec::with_capacity(members.len());
+			let mut stride = 0;
+			for accessor in members {
+				offsets.push(stride);
+				stride = (stride + self.accessors[accessor.0].element_size()).next_multiple_of(4);
+			}
+			if members.len() == 1 {
+				stride = self.accessors[index].element_size();
 			}
 
-			view.buffer.0 = 0;
-			view.byte_offset = dest.byte_length;
+			data.resize(data.len().next_multiple_of(4), 0);
+			let start = data.len();
+			let old_view = &old_views[self.accessors[index].buffer_view.0];
+			let target = old_view.target;
+			for element in 0..self.accessors[index].count {
+				for (accessor, offset) in members.iter().zip(&offsets) {
+					let accessor = &self.accessors[accessor.0];
+					let size = accessor.element_size();
+					let view = &old_views[accessor.buffer_view.0];
+					let from = view.byte_offset
+						+ accessor.byte_offset
+						+ element * view.byte_stride.unwrap_or(size);
+					data.resize(start + element * stride + offset, 0);
+					data.extend_from_slice(&old_buffers[view.buffer.0].uri[from..from + size]);
+				}
+				data.resize(start + (element + 1) * stride, 0);
+			}
 
-			dest.byte_length += src.byte_length;
-			dest.uri.append(&mut src.uri);
+			let view = BufferViewIndex(self.buffer_views.len());
+			self.buffer_views.push(BufferView {
+				buffer: BufferIndex(0),
+				byte_length: data.len() - start,
+				target,
+				byte_offset: start,
+				byte_stride: (members.len() > 1).then_some(stride),
+			});
+			for (member, &offset) in members.iter().zip(&offsets) {
+				let accessor = &mut self.accessors[member.0];
+				accessor.buffer_view = view;
+				accessor.byte_offset = offset;
+				done[member.0] = true;
+			}
+		}
+		if !data.is_empty() {
+			self.buffers.push(Buffer {
+				byte_length: data.len(),
+				uri: data,
+			});
 		}
-		self.buffers.truncate(1);
 	}
 
 	pub fn render_json(&mut self) -> String {
+		self.combine_buffers();
 		serde_json::to_string(self).unwrap()
 	}
 
@@ -715,6 +1092,12 @@ impl Gltf {
 		};
 
 		let (cube_verts, cube_indices) = make_cube(0.5);
+		let cube_indices: Vec<u16> = cube_indices
+			.as_chunks()
+			.0
+			.iter()
+			.flat_map(|&tri| self.settings.triangle(tri))
+			.collect();
 		let cube_material = self.create_colour_material("Debug".to_owned(), [1.0, 0.0, 1.0, 1.0]);
 
 		let result = self.create_mesh_from_primitive(
@@ -728,6 +1111,23 @@ impl Gltf {
 		result
 	}
 
+	/// Creates a box mesh covering `bbox`
+	pub fn create_box_mesh(
+		&mut self, name: String, bbox: [Vec3; 2], material: Option<MaterialIndex>,
+	) -> MeshIndex {
+		let (cube_verts, cube_indices) = make_unit_cube();
+		let [min, max] = bbox;
+		let size = max - min;
+		let verts = cube_verts.map(|vert| min + (vert + Vec3::new_splat(0.5)) * size);
+		let indices: Vec<u16> = cube_indices
+			.as_chunks()
+			.0
+			.iter()
+			.flat_map(|&tri| self.settings.triangle(tri))
+			.collect();
+		self.create_mesh_from_primitive(name, &verts, &indices, None, material)
+	}
+
 	pub fn create_points_nodes(
 		&mut self, name: String, points: &[Vec3], parent: Option<NodeIndex>,
 	) -> NodeIndex {
@@ -885,6 +1285,24 @@ impl BufferData for f32 {
 	}
 }
 
+/// Integers stored as normalised -1 to 1 (or 0 to 1) values
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[repr(transparent)]
+pub struct Normalized<T>(pub T);
+impl BufferData for Normalized<i16> {
+	const COMPONENT_TYPE: AccessorComponentType = AccessorComponentType::SignedShort;
+	const NORMALIZED: bool = true;
+	type InnerType = Self;
+	fn to_array(&self) -> &[Self] {
+		std::slice::from_ref(self)
+	}
+}
+impl From<Normalized<i16>> for f64 {
+	fn from(value: Normalized<i16>) -> Self {
+		value.0.into()
+	}
+}
+
 impl<T: BufferData + Into<f64>> BufferData for [T; 2] {
 	const COMPONENT_TYPE: AccessorComponentType = T::COMPONENT_TYPE;
 	const NORMALIZED: bool = T::NORMALIZED;
@@ -939,3 +1357,28 @@ impl BufferData for Vec3 {
 		self.as_slice()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_node_transforms() {
+		let settings = crate::ExportSettings {
+			up_axis: crate::UpAxis::Z,
+			scale: 2.0,
+			..Default::default()
+		};
+		let mut gltf = Gltf::with_settings("test".into(), settings);
+		let node = gltf.create_child_node(gltf.get_root_node(), "spawn".into(), None);
+		gltf.set_node_position(node, Vec3::new(1.0, 2.0, 3.0));
+		gltf.set_node_rotation(node, [0.0, 1.0, 0.0, 0.0]);
+		gltf.set_node_scale(node, Vec3::new(1.0, 2.0, 3.0));
+		let json: serde_json::Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		let node = &json["nodes"][1];
+		assert_eq!(node["translation"], serde_json::json!([2.0, -6.0, 4.0]));
+		assert_eq!(node["rotation"], serde_json::json!([0.0, -0.0, 1.0, 0.0]));
+		// scales are relative, so aren't affected by the export scale
+		assert_eq!(node["scale"], serde_json::json!([1.0, 3.0, 2.0]));
+	}
+}
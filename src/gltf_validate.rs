@@ -0,0 +1,932 @@
+//! Checks exported GLTF files for the problems picky viewers reject, and optionally repairs them.
+//!
+//! This works on the written JSON rather than a [Gltf](crate::gltf::Gltf),
+//! so it also catches anything that went wrong on the way to disk.
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use base64::{Engine, engine::general_purpose};
+use serde_json::Value;
+
+/// A problem in a GLTF file
+pub struct GltfIssue {
+	/// Where the problem is, e.g. `accessors[3]`
+	pub location: String,
+	pub message: String,
+	pub repaired: bool,
+}
+
+impl std::fmt::Display for GltfIssue {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}: {}", self.location, self.message)?;
+		if self.repaired {
+			f.write_str(" (repaired)")?;
+		}
+		Ok(())
+	}
+}
+
+/// Every issue found in the GLTF files under a directory
+#[derive(Default)]
+pub struct GltfReport {
+	pub files: usize,
+	pub repaired_files: usize,
+	pub issues: Vec<(PathBuf, GltfIssue)>,
+}
+
+impl GltfReport {
+	/// One line per issue, grouped by file
+	pub fn details(&self) -> String {
+		let mut result = String::new();
+		let mut last_path = None;
+		for (path, issue) in &self.issues {
+			if last_path != Some(path) {
+				if last_path.is_some() {
+					result.push('\n');
+				}
+				writeln!(result, "{}", path.display()).unwrap();
+				last_path = Some(path);
+			}
+			writeln!(result, "\t{issue}").unwrap();
+		}
+		result
+	}
+}
+
+impl std::fmt::Display for GltfReport {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let mut files: Vec<&Path> = self.issues.iter().map(|(path, _)| path.as_path()).collect();
+		files.dedup();
+		write!(
+			f,
+			"Checked {} gltf files, found {} issues in {} files",
+			self.files,
+			self.issues.len(),
+			files.len()
+		)?;
+		if self.repaired_files != 0 {
+			write!(f, ", repaired {} files", self.repaired_files)?;
+		}
+		Ok(())
+	}
+}
+
+/// Checks every `.gltf` file under `root`, rewriting the ones with problems if `repair` is set
+pub fn validate_dir(root: &Path, repair: bool) -> GltfReport {
+	fn visit(dir: &Path, files: &mut Vec<PathBuf>) {
+		let entries = std::fs::read_dir(dir)
+			.unwrap_or_else(|e| panic!("failed to read directory {}: {e}", dir.display()));
+		for entry in entries {
+			let path = entry.unwrap().path();
+			if path.is_dir() {
+				visit(&path, files);
+			} else if path
+				.extension()
+				.is_some_and(|ext| ext.eq_ignore_ascii_case("gltf"))
+			{
+				files.push(path);
+			}
+		}
+	}
+	let mut files = Vec::new();
+	visit(root, &mut files);
+	files.sort();
+
+	let mut report = GltfReport {
+		files: files.len(),
+		..Default::default()
+	};
+	for path in files {
+		let issues = validate_file(&path, repair);
+		if issues.iter().any(|issue| issue.repaired) {
+			report.repaired_files += 1;
+		}
+		report
+			.issues
+			.extend(issues.into_iter().map(|issue| (path.clone(), issue)));
+	}
+	report
+}
+
+/// Checks a single `.gltf` file, rewriting it if `repair` is set and anything was repaired
+pub fn validate_file(path: &Path, repair: bool) -> Vec<GltfIssue> {
+	let data =
+		std::fs::read(path).unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+	let mut gltf: Value = match serde_json::from_slice(&data) {
+		Ok(gltf) => gltf,
+		Err(e) => {
+			return vec![GltfIssue {
+				location: String::new(),
+				message: format!("invalid json: {e}"),
+				repaired: false,
+			}];
+		}
+	};
+	let issues = validate_gltf(&mut gltf, repair);
+	if issues.iter().any(|issue| issue.repaired) {
+		std::fs::write(path, serde_json::to_string(&gltf).unwrap())
+			.unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+	}
+	issues
+}
+
+/// Checks a parsed GLTF file, fixing what it can in place if `repair` is set:
+///
+/// * wrong buffer lengths and accessor min/max values are corrected
+/// * primitives with missing attributes, mismatched attribute counts or out of range indices are removed,
+///   along with any meshes left without primitives
+/// * unused accessors, buffer views and buffers are removed
+///
+/// Anything else (e.g. accessors reading past the end of their buffer) is only reported.
+pub fn validate_gltf(gltf: &mut Value, repair: bool) -> Vec<GltfIssue> {
+	let mut issues = Issues {
+		list: Vec::new(),
+		repair,
+	};
+
+	// buffers
+	let mut buffers: Vec<Option<Vec<u8>>> = Vec::new();
+	for (i, buffer) in array_mut(gltf, "buffers").iter_mut().enumerate() {
+		let location = format!("buffers[{i}]");
+		let byte_length = index(buffer, "byteLength");
+		let data = match buffer.get("uri").and_then(Value::as_str) {
+			Some(uri) if uri.starts_with("data:") => match decode_uri(uri) {
+				Some(data) => Some(data),
+				None => {
+					issues.report(&location, "invalid data uri".into());
+					None
+				}
+			},
+			_ => None, // external data isn't checked
+		};
+		if let Some(data) = &data
+			&& byte_length != Some(data.len())
+		{
+			let message = format!(
+				"byteLength is {byte_length:?} but the data is {} bytes",
+				data.len()
+			);
+			if issues.repair(&location, message) {
+				buffer["byteLength"] = data.len().into();
+			}
+		}
+		if index(buffer, "byteLength") == Some(0) {
+			issues.report(&location, "empty buffer".into());
+		}
+		buffers.push(data);
+	}
+
+	// buffer views, `None` if they can't be read
+	let buffer_lengths: Vec<Option<usize>> = array(gltf, "buffers")
+		.iter()
+		.map(|buffer| index(buffer, "byteLength"))
+		.collect();
+	let mut views: Vec<Option<&[u8]>> = Vec::new();
+	for (i, view) in array(gltf, "bufferViews").iter().enumerate() {
+		let location = format!("bufferViews[{i}]");
+		let buffer = index(view, "buffer");
+		let offset = index(view, "byteOffset").unwrap_or(0);
+		let length = index(view, "byteLength").unwrap_or(0);
+		let Some(&buffer_length) = buffer.and_then(|buffer| buffer_lengths.get(buffer)) else {
+			issues.report(&location, format!("invalid buffer {buffer:?}"));
+			views.push(None);
+			continue;
+		};
+		if buffer_length.is_none_or(|buffer_length| offset + length > buffer_length) {
+			issues.report(
+				&location,
+				format!(
+					"bytes {offset}..{} are outside buffer {} ({buffer_length:?} bytes)",
+					offset + length,
+					buffer.unwrap()
+				),
+			);
+			views.push(None);
+			continue;
+		}
+		views.push(
+			buffers[buffer.unwrap()]
+				.as_deref()
+				.and_then(|data| data.get(offset..offset + length)),
+		);
+	}
+
+	// accessors
+	let mut accessors: Vec<Option<AccessorData>> = Vec::new();
+	for (i, accessor) in array(gltf, "accessors").iter().enumerate() {
+		let location = format!("accessors[{i}]");
+		let data = read_accessor(accessor, &views, array(gltf, "bufferViews"));
+		let data = data.unwrap_or_else(|message| {
+			issues.report(&location, message);
+			None
+		});
+		if let Some(data) = &data
+			&& data.values.iter().any(|value| !value.is_finite())
+		{
+			issues.report(&location, "contains nan or infinite values".into());
+		}
+		accessors.push(data);
+	}
+	let mut minmax_fixes = Vec::new();
+	let required_minmax = required_minmax(gltf, accessors.len());
+	for (i, (accessor, data)) in array(gltf, "accessors").iter().zip(&accessors).enumerate() {
+		let Some(data) = data else {
+			continue;
+		};
+		let location = format!("accessors[{i}]");
+		let (min, max) = data.minmax();
+		let (old_min, old_max) = (accessor.get("min"), accessor.get("max"));
+		let message = if old_min.is_none() && old_max.is_none() {
+			if !required_minmax[i] {
+				continue;
+			}
+			"missing min/max".into()
+		} else if !minmax_matches(old_min, &min) || !minmax_matches(old_max, &max) {
+			format!(
+				"min/max are {}/{} but the data is {min:?}/{max:?}",
+				old_min.unwrap_or(&Value::Null),
+				old_max.unwrap_or(&Value::Null)
+			)
+		} else {
+			continue;
+		};
+		if issues.repair(&location, message) {
+			minmax_fixes.push((i, min, max));
+		}
+	}
+	for (i, min, max) in minmax_fixes {
+		let accessor = &mut array_mut(gltf, "accessors")[i];
+		accessor["min"] = min.into();
+		accessor["max"] = max.into();
+	}
+
+	// primitives
+	let num_materials = array(gltf, "materials").len();
+	for (m, mesh) in array_mut(gltf, "meshes").iter_mut().enumerate() {
+		let Some(primitives) = mesh.get_mut("primitives").and_then(Value::as_array_mut) else {
+			issues.repair(&format!("meshes[{m}]"), "no primitives".into());
+			continue;
+		};
+		let mut p = 0;
+		primitives.retain_mut(|primitive| {
+			let location = format!("meshes[{m}].primitives[{p}]");
+			p += 1;
+			if let Some(material) = primitive.get("material")
+				&& material
+					.as_u64()
+					.is_none_or(|material| material as usize >= num_materials)
+				&& issues.repair(&location, format!("invalid material {material}"))
+			{
+				primitive.as_object_mut().unwrap().remove("material");
+			}
+			match check_primitive(primitive, &accessors) {
+				Ok(()) => true,
+				Err(message) => !issues.repair(&location, message),
+			}
+		});
+		if primitives.is_empty() {
+			issues.repair(&format!("meshes[{m}]"), "no primitives".into());
+		}
+	}
+
+	// empty meshes
+	let empty_meshes: Vec<bool> = array(gltf, "meshes")
+		.iter()
+		.map(|mesh| array(mesh, "primitives").is_empty())
+		.collect();
+	if repair && empty_meshes.contains(&true) {
+		for node in array_mut(gltf, "nodes") {
+			if index(node, "mesh").is_some_and(|mesh| empty_meshes.get(mesh) == Some(&true)) {
+				node.as_object_mut().unwrap().remove("mesh");
+			}
+		}
+		let keep: Vec<bool> = empty_meshes.iter().map(|&empty| !empty).collect();
+		remove_unused(gltf, "meshes", &keep);
+	}
+
+	// nodes and animations
+	let num_nodes = array(gltf, "nodes").len();
+	let num_meshes = array(gltf, "meshes").len();
+	let num_accessors = accessors.len();
+	for (i, node) in array_mut(gltf, "nodes").iter_mut().enumerate() {
+		let location = format!("nodes[{i}]");
+		if let Some(mesh) = node.get("mesh")
+			&& mesh.as_u64().is_none_or(|mesh| mesh as usize >= num_meshes)
+			&& issues.repair(&location, format!("invalid mesh {mesh}"))
+		{
+			node.as_object_mut().unwrap().remove("mesh");
+		}
+		for child in array(node, "children") {
+			if child
+				.as_u64()
+				.is_none_or(|child| child as usize >= num_nodes)
+			{
+				issues.report(&location, format!("invalid child {child}"));
+			}
+		}
+	}
+	for scene in array(gltf, "scenes") {
+		for node in array(scene, "nodes") {
+			if node.as_u64().is_none_or(|node| node as usize >= num_nodes) {
+				issues.report("scenes", format!("invalid node {node}"));
+			}
+		}
+	}
+	if let Some(scene) = gltf.get("scene")
+		&& scene
+			.as_u64()
+			.is_none_or(|scene| scene as usize >= array(gltf, "scenes").len())
+	{
+		issues.report("scene", format!("invalid default scene {scene}"));
+	}
+	for (a, animation) in array(gltf, "animations").iter().enumerate() {
+		let samplers = array(animation, "samplers");
+		for (s, sampler) in samplers.iter().enumerate() {
+			let location = format!("animations[{a}].samplers[{s}]");
+			let input = index(sampler, "input").filter(|&input| input < num_accessors);
+			let output = index(sampler, "output").filter(|&output| output < num_accessors);
+			let (Some(input), Some(output)) = (input, output) else {
+				issues.report(&location, "invalid input or output".into());
+				continue;
+			};
+			let input_count = index(&array(gltf, "accessors")[input], "count");
+			let output_count = index(&array(gltf, "accessors")[output], "count");
+			let is_cubic =
+				sampler.get("interpolation").and_then(Value::as_str) == Some("CUBICSPLINE");
+			// outputs can have several values per keyframe (e.g. morph target weights)
+			if !is_cubic
+				&& let (Some(input_count), Some(output_count)) = (input_count, output_count)
+				&& (output_count < input_count || !output_count.is_multiple_of(input_count.max(1)))
+			{
+				issues.report(
+					&location,
+					format!("{input_count} keyframes but {output_count} values"),
+				);
+			}
+		}
+		for (c, channel) in array(animation, "channels").iter().enumerate() {
+			let location = format!("animations[{a}].channels[{c}]");
+			if index(channel, "sampler").is_none_or(|sampler| sampler >= samplers.len()) {
+				issues.report(&location, "invalid sampler".into());
+			}
+			let node = channel
+				.get("target")
+				.and_then(|target| index(target, "node"));
+			if node.is_none_or(|node| node >= num_nodes) {
+				issues.report(&location, "invalid target node".into());
+			}
+		}
+	}
+
+	// unused data
+	let mut used_accessors = vec![false; num_accessors];
+	for reference in references(gltf, "accessors") {
+		if let Some(used) = reference
+			.as_u64()
+			.and_then(|i| used_accessors.get_mut(i as usize))
+		{
+			*used = true;
+		}
+	}
+	let mut used_views = vec![false; views.len()];
+	let image_views = array(gltf, "images")
+		.iter()
+		.filter_map(|image| index(image, "bufferView"));
+	let accessor_views = array(gltf, "accessors")
+		.iter()
+		.zip(&used_accessors)
+		.filter(|(_, used)| **used)
+		.filter_map(|(accessor, _)| index(accessor, "bufferView"));
+	for view in image_views.chain(accessor_views) {
+		if let Some(used) = used_views.get_mut(view) {
+			*used = true;
+		}
+	}
+	let mut used_buffers = vec![false; buffers.len()];
+	for (view, _) in array(gltf, "bufferViews")
+		.iter()
+		.zip(&used_views)
+		.filter(|(_, used)| **used)
+	{
+		if let Some(used) = index(view, "buffer").and_then(|buffer| used_buffers.get_mut(buffer)) {
+			*used = true;
+		}
+	}
+	let mut remove = Vec::new();
+	for (key, used) in [
+		("accessors", used_accessors),
+		("bufferViews", used_views),
+		("buffers", used_buffers),
+	] {
+		let mut any_removed = false;
+		for (i, _) in used.iter().enumerate().filter(|(_, used)| !**used) {
+			any_removed |= issues.repair(&format!("{key}[{i}]"), "unused".into());
+		}
+		if any_removed {
+			remove.push((key, used));
+		}
+	}
+	for (key, used) in remove {
+		remove_unused(gltf, key, &used);
+	}
+
+	issues.list
+}
+
+struct Issues {
+	list: Vec<GltfIssue>,
+	repair: bool,
+}
+impl Issues {
+	fn report(&mut self, location: &str, message: String) {
+		self.list.push(GltfIssue {
+			location: location.to_owned(),
+			message,
+			repaired: false,
+		});
+	}
+	/// Reports a problem that can be repaired, returning whether it should be
+	fn repair(&mut self, location: &str, message: String) -> bool {
+		self.list.push(GltfIssue {
+			location: location.to_owned(),
+			message,
+			repaired: self.repair,
+		});
+		self.repair
+	}
+}
+
+/// An accessor's values converted to floats, `components` per element
+struct AccessorData {
+	components: usize,
+	values: Vec<f64>,
+	component_type: u64,
+}
+impl AccessorData {
+	fn count(&self) -> usize {
+		self.values.len() / self.components
+	}
+	fn minmax(&self) -> (Vec<f64>, Vec<f64>) {
+		let mut min = vec![f64::INFINITY; self.components];
+		let mut max = vec![f64::NEG_INFINITY; self.components];
+		for element in self.values.chunks_exact(self.components) {
+			for (i, &value) in element.iter().enumerate() {
+				min[i] = min[i].min(value);
+				max[i] = max[i].max(value);
+			}
+		}
+		(min, max)
+	}
+}
+
+/// Reads an accessor's values, `None` if they aren't stored in this file
+fn read_accessor(
+	accessor: &Value, views: &[Option<&[u8]>], view_info: &[Value],
+) -> Result<Option<AccessorData>, String> {
+	let component_type = accessor
+		.get("componentType")
+		.and_then(Value::as_u64)
+		.unwrap_or(0);
+	let component_size = match component_type {
+		5120 | 5121 => 1,
+		5122 | 5123 => 2,
+		5125 | 5126 => 4,
+		_ => return Err(format!("invalid componentType {component_type}")),
+	};
+	let element_type = accessor.get("type").and_then(Value::as_str).unwrap_or("");
+	let components = match element_type {
+		"SCALAR" => 1,
+		"VEC2" => 2,
+		"VEC3" => 3,
+		"VEC4" | "MAT2" => 4,
+		"MAT3" => 9,
+		"MAT4" => 16,
+		_ => return Err(format!("invalid type {element_type:?}")),
+	};
+	let count = index(accessor, "count").unwrap_or(0);
+	if count == 0 {
+		return Err("count is 0".into());
+	}
+	let Some(view) = index(accessor, "bufferView") else {
+		return Ok(None); // all zeros
+	};
+	let Some(data) = views.get(view) else {
+		return Err(format!("invalid bufferView {view}"));
+	};
+	let Some(data) = data else {
+		return Ok(None);
+	};
+
+	let element_size = component_size * components;
+	let offset = index(accessor, "byteOffset").unwrap_or(0);
+	let view_offset = index(&view_info[view], "byteOffset").unwrap_or(0);
+	let stride = index(&view_info[view], "byteStride").unwrap_or(element_size);
+	if !(view_offset + offset).is_multiple_of(component_size) {
+		return Err(format!(
+			"data at offset {} isn't aligned to {component_size} bytes",
+			view_offset + offset
+		));
+	}
+	let end = offset + stride * (count - 1) + element_size;
+	if end > data.len() {
+		return Err(format!(
+			"reads bytes {offset}..{end} of bufferView {view} ({} bytes)",
+			data.len()
+		));
+	}
+
+	let mut values = Vec::with_capacity(count * components);
+	for element in 0..count {
+		let start = offset + element * stride;
+		for component in data[start..start + element_size].chunks_exact(component_size) {
+			values.push(match component_type {
+				5120 => component[0] as i8 as f64,
+				5121 => component[0] as f64,
+				5122 => i16::from_le_bytes([component[0], component[1]]) as f64,
+				5123 => u16::from_le_bytes([component[0], component[1]]) as f64,
+				5125 => u32::from_le_bytes(component.try_into().unwrap()) as f64,
+				_ => f32::from_le_bytes(component.try_into().unwrap()) as f64,
+			});
+		}
+	}
+	Ok(Some(AccessorData {
+		components,
+		values,
+		component_type,
+	}))
+}
+
+/// Which accessors must have min and max values: positions and animation times
+fn required_minmax(gltf: &Value, num_accessors: usize) -> Vec<bool> {
+	let positions = array(gltf, "meshes")
+		.iter()
+		.flat_map(|mesh| array(mesh, "primitives"))
+		.filter_map(|primitive| primitive.get("attributes"))
+		.filter_map(|attributes| index(attributes, "POSITION"));
+	let times = array(gltf, "animations")
+		.iter()
+		.flat_map(|animation| array(animation, "samplers"))
+		.filter_map(|sampler| index(sampler, "input"));
+	let mut result = vec![false; num_accessors];
+	for accessor in positions.chain(times) {
+		if let Some(required) = result.get_mut(accessor) {
+			*required = true;
+		}
+	}
+	result
+}
+
+fn minmax_matches(value: Option<&Value>, expected: &[f64]) -> bool {
+	let Some(values) = value.and_then(Value::as_array) else {
+		return false;
+	};
+	values.len() == expected.len()
+		&& values.iter().zip(expected).all(|(value, &expected)| {
+			value.as_f64().is_some_and(|value| {
+				// values might have been rounded to f32 by other tools
+				let tolerance = f32::EPSILON as f64 * value.abs().max(expected.abs()).max(1.0);
+				(value - expected).abs() <= tolerance
+			})
+		})
+}
+
+/// Returns why a primitive can't be drawn, if it can't
+fn check_primitive(primitive: &Value, accessors: &[Option<AccessorData>]) -> Result<(), String> {
+	let attributes = primitive
+		.get("attributes")
+		.and_then(Value::as_object)
+		.ok_or("missing attributes")?;
+	if !attributes.contains_key("POSITION") {
+		return Err("missing POSITION attribute".into());
+	}
+	let mut vertex_count = None;
+	for (name, accessor) in attributes {
+		let accessor = accessor.as_u64().map(|accessor| accessor as usize);
+		let Some(data) = accessor.and_then(|accessor| accessors.get(accessor)) else {
+			return Err(format!("{name} has invalid accessor {accessor:?}"));
+		};
+		let Some(data) = data else {
+			continue;
+		};
+		match vertex_count {
+			None => vertex_count = Some(data.count()),
+			Some(count) if count != data.count() => {
+				return Err(format!(
+					"{name} has {} elements but other attributes have {count}",
+					data.count()
+				));
+			}
+			Some(_) => {}
+		}
+	}
+
+	let Some(indices) = primitive.get("indices") else {
+		return Ok(());
+	};
+	let accessor = indices.as_u64().map(|accessor| accessor as usize);
+	let Some(data) = accessor.and_then(|accessor| accessors.get(accessor)) else {
+		return Err(format!("invalid indices accessor {indices}"));
+	};
+	if let Some(data) = data {
+		if data.components != 1 || !matches!(data.component_type, 5121 | 5123 | 5125) {
+			return Err("indices must be unsigned scalars".into());
+		}
+		if let Some(vertex_count) = vertex_count
+			&& let Some(&index) = data
+				.values
+				.iter()
+				.find(|&&index| index as usize >= vertex_count)
+		{
+			return Err(format!(
+				"index {index} is out of range of {vertex_count} vertices"
+			));
+		}
+	}
+	Ok(())
+}
+
+/// Every index into the `target` array (e.g. `"accessors"`) that the rest of the file uses
+fn references<'a>(gltf: &'a mut Value, target: &str) -> Vec<&'a mut Value> {
+	fn fields(value: &mut Value) -> impl Iterator<Item = (&String, &mut Value)> {
+		value.as_object_mut().into_iter().flatten()
+	}
+	let mut result = Vec::new();
+	for (key, items) in fields(gltf) {
+		for item in items.as_array_mut().into_iter().flatten() {
+			match (target, key.as_str()) {
+				("accessors", "meshes") => {
+					for primitive in array_mut(item, "primitives") {
+						for (key, value) in fields(primitive) {
+							match key.as_str() {
+								"indices" => result.push(value),
+								"attributes" => result.extend(fields(value).map(|(_, v)| v)),
+								"targets" => {
+									for target in value.as_array_mut().into_iter().flatten() {
+										result.extend(fields(target).map(|(_, v)| v));
+									}
+								}
+								_ => {}
+							}
+						}
+					}
+				}
+				("accessors", "animations") => {
+					for sampler in array_mut(item, "samplers") {
+						for (key, value) in fields(sampler) {
+							if key == "input" || key == "output" {
+								result.push(value);
+							}
+						}
+					}
+				}
+				("accessors", "skins") => result.extend(item.get_mut("inverseBindMatrices")),
+				("bufferViews", "accessors" | "images") => {
+					result.extend(item.get_mut("bufferView"))
+				}
+				("buffers", "bufferViews") => result.extend(item.get_mut("buffer")),
+				("meshes", "nodes") => result.extend(item.get_mut("mesh")),
+				_ => {}
+			}
+		}
+	}
+	result
+}
+
+/// Removes the entries of `key` that aren't kept, and updates every reference to the rest
+fn remove_unused(gltf: &mut Value, key: &str, keep: &[bool]) {
+	let mut next = 0;
+	let new_indices: Vec<Option<usize>> = keep
+		.iter()
+		.map(|&keep| {
+			next += keep as usize;
+			keep.then(|| next - 1)
+		})
+		.collect();
+	let items = gltf.as_object_mut().unwrap();
+	let entries = items.get_mut(key).and_then(Value::as_array_mut).unwrap();
+	let mut i = 0;
+	entries.retain(|_| {
+		i += 1;
+		keep[i - 1]
+	});
+	if entries.is_empty() {
+		items.remove(key); // arrays can't be empty
+	}
+	for reference in references(gltf, key) {
+		let new_index = reference
+			.as_u64()
+			.and_then(|index| new_indices.get(index as usize).copied().flatten());
+		if let Some(new_index) = new_index {
+			*reference = new_index.into();
+		}
+	}
+}
+
+fn decode_uri(uri: &str) -> Option<Vec<u8>> {
+	let (_, data) = uri.split_once(";base64,")?;
+	general_purpose::STANDARD.decode(data).ok()
+}
+
+fn index(value: &Value, key: &str) -> Option<usize> {
+	value.get(key)?.as_u64().map(|value| value as usize)
+}
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+	value
+		.get(key)
+		.and_then(Value::as_array)
+		.map_or(&[], Vec::as_slice)
+}
+fn array_mut<'a>(value: &'a mut Value, key: &str) -> &'a mut [Value] {
+	value
+		.get_mut(key)
+		.and_then(Value::as_array_mut)
+		.map_or(&mut [], Vec::as_mut_slice)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Vec3;
+	use crate::gltf::Gltf;
+
+	#[test]
+	fn test_validate_gltf() {
+		let mut gltf = Gltf::new("test".into());
+		let positions = [
+			Vec3::new(0.0, 0.0, 0.0),
+			Vec3::new(1.0, 0.0, 0.0),
+			Vec3::new(0.0, 2.0, 0.0),
+		];
+		let mesh =
+			gltf.create_mesh_from_primitive("tri".into(), &positions, &[0, 1, 2], None, None);
+		gltf.create_child_node(gltf.get_root_node(), "tri".into(), Some(mesh));
+		let mut json: Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		let valid = json.clone();
+		assert!(validate_gltf(&mut json, true).is_empty());
+
+		let mut combined = Gltf::new("test".into());
+		combined.create_mesh_from_primitive("tri".into(), &positions, &[0, 1, 2], None, None);
+		combined.combine_buffers();
+		let mut combined: Value = serde_json::from_str(&combined.render_json()).unwrap();
+		assert!(validate_gltf(&mut combined, false).is_empty());
+
+		// wrong bounds, an unused buffer, a bad buffer length, and a primitive using positions as indices
+		json["accessors"][0]["max"] = serde_json::json!([9.0, 9.0, 9.0]);
+		json["buffers"]
+			.as_array_mut()
+			.unwrap()
+			.push(serde_json::json!({
+				"uri": "data:application/octet-stream;base64,AAAAAA==",
+				"byteLength": 4,
+			}));
+		json["buffers"][0]["byteLength"] = 43.into();
+		json["meshes"][0]["primitives"]
+			.as_array_mut()
+			.unwrap()
+			.push(serde_json::json!({"attributes": {"POSITION": 0}, "indices": 0}));
+
+		let broken = json.clone();
+		let issues = validate_gltf(&mut json, false);
+		assert_eq!(json, broken);
+		let locations: Vec<&str> = issues.iter().map(|issue| issue.location.as_str()).collect();
+		assert_eq!(
+			locations,
+			[
+				"buffers[0]",
+				"accessors[0]",
+				"meshes[0].primitives[1]",
+				"buffers[1]"
+			]
+		);
+		assert!(issues.iter().all(|issue| !issue.repaired));
+
+		let issues = validate_gltf(&mut json, true);
+		assert_eq!(issues.len(), 4);
+		assert!(issues.iter().all(|issue| issue.repaired));
+		assert_eq!(json, valid);
+	}
+
+	#[test]
+	fn test_interleaved_buffers() {
+		let mut gltf = Gltf::new("test".into());
+		let mesh = gltf.create_mesh("tri".into());
+		let positions = [
+			Vec3::new(0.0, 0.0, 0.0),
+			Vec3::new(1.0, 0.0, 0.0),
+			Vec3::new(0.0, 2.0, 0.0),
+		];
+		let prim = gltf.add_mesh_primitive(mesh, &positions, &[0, 1, 2], None);
+		gltf.add_primitive_uvs(prim, &[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+		gltf.add_primitive_colours(prim, &[[255, 0, 0, 255]; 3]);
+		gltf.create_child_node(gltf.get_root_node(), "tri".into(), Some(mesh));
+
+		let rendered = gltf.render_json();
+		assert_eq!(gltf.render_json(), rendered);
+		let mut json: Value = serde_json::from_str(&rendered).unwrap();
+		assert_eq!(json["buffers"].as_array().unwrap().len(), 1);
+		// positions, uvs and colours share a view, the indices get their own
+		assert_eq!(json["bufferViews"].as_array().unwrap().len(), 2);
+		assert_eq!(json["bufferViews"][0]["byteStride"], 12 + 8 + 16);
+		let offsets: Vec<u64> = json["accessors"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.map(|accessor| accessor["byteOffset"].as_u64().unwrap_or(0))
+			.collect();
+		assert_eq!(offsets, [0, 0, 12, 12 + 8]);
+		assert!(validate_gltf(&mut json, false).is_empty());
+	}
+
+	#[test]
+	fn test_scenes() {
+		let mut gltf = Gltf::new("level".into());
+		let arena = gltf.create_node("ARENA2".into(), None);
+		let scene = gltf.create_scene("ARENA2".into());
+		gltf.add_scene_node(scene, arena);
+		gltf.set_default_scene(scene);
+		let mut json: Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		assert_eq!(json["scene"], 1);
+		assert_eq!(
+			json["scenes"],
+			serde_json::json!([
+				{"name": "level", "nodes": [0]},
+				{"name": "ARENA2", "nodes": [1]},
+			])
+		);
+		assert!(validate_gltf(&mut json, false).is_empty());
+
+		json["scene"] = 2.into();
+		let issues = validate_gltf(&mut json, false);
+		assert_eq!(issues.len(), 1);
+		assert_eq!(issues[0].location, "scene");
+	}
+
+	#[test]
+	fn test_copy_node() {
+		let mut gltf = Gltf::new("test".into());
+		let positions = [
+			Vec3::new(0.0, 0.0, 0.0),
+			Vec3::new(1.0, 0.0, 0.0),
+			Vec3::new(0.0, 2.0, 0.0),
+		];
+		let mesh =
+			gltf.create_mesh_from_primitive("tri".into(), &positions, &[0, 1, 2], None, None);
+		let spawn1 = gltf.create_child_node(gltf.get_root_node(), "spawn1".into(), None);
+		let spawn2 = gltf.create_child_node(gltf.get_root_node(), "spawn2".into(), None);
+		let tri = gltf.create_child_node(spawn1, "tri".into(), Some(mesh));
+		gltf.set_node_position(tri, Vec3::new(1.0, 0.0, 0.0));
+		gltf.create_child_node(tri, "tri_Outline".into(), Some(mesh));
+		gltf.copy_node(tri, spawn2);
+
+		let mut json: Value = serde_json::from_str(&gltf.render_json()).unwrap();
+		assert_eq!(json["meshes"].as_array().unwrap().len(), 1);
+		let nodes = json["nodes"].as_array().unwrap();
+		assert_eq!(nodes[2]["children"], serde_json::json!([5]));
+		assert_eq!(nodes[5]["name"], "tri");
+		assert_eq!(nodes[5]["mesh"], 0);
+		assert_eq!(nodes[5]["translation"], serde_json::json!([1.0, 0.0, 0.0]));
+		assert_eq!(nodes[5]["children"], serde_json::json!([6]));
+		assert_eq!(nodes[6]["name"], "tri_Outline");
+		assert!(validate_gltf(&mut json, false).is_empty());
+	}
+
+	#[test]
+	fn test_remove_unused() {
+		let mut json = serde_json::json!({
+			"meshes": [{"primitives": [{"attributes": {"POSITION": 1}, "indices": 2}]}],
+			"accessors": [
+				{"bufferView": 0, "componentType": 5126, "count": 1, "type": "SCALAR"},
+				{"bufferView": 1, "componentType": 5126, "count": 1, "type": "VEC3", "min": [0.0, 0.0, 0.0], "max": [0.0, 0.0, 0.0]},
+				{"bufferView": 2, "componentType": 5123, "count": 1, "type": "SCALAR"},
+			],
+			"bufferViews": [
+				{"buffer": 0, "byteLength": 4},
+				{"buffer": 1, "byteLength": 12},
+				{"buffer": 1, "byteOffset": 12, "byteLength": 2},
+			],
+			"buffers": [
+				{"uri": "data:application/octet-stream;base64,AAAAAA==", "byteLength": 4},
+				{"uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAAA=", "byteLength": 14},
+			],
+		});
+		let issues = validate_gltf(&mut json, true);
+		let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+		assert_eq!(
+			issues,
+			[
+				"accessors[0]: unused (repaired)",
+				"bufferViews[0]: unused (repaired)",
+				"buffers[0]: unused (repaired)"
+			]
+		);
+		assert_eq!(
+			json["meshes"][0]["primitives"][0]["attributes"]["POSITION"],
+			0
+		);
+		assert_eq!(json["meshes"][0]["primitives"][0]["indices"], 1);
+		assert_eq!(json["accessors"][1]["bufferView"], 1);
+		assert_eq!(json["bufferViews"][1]["buffer"], 0);
+		assert_eq!(json["buffers"].as_array().unwrap().len(), 1);
+		assert!(validate_gltf(&mut json, false).is_empty());
+	}
+}
@@ -1,11 +1,54 @@
+#[cfg(feature = "cli")]
+pub mod config;
+pub mod coverage;
 pub mod data_formats;
+#[cfg(feature = "dataset")]
+pub mod dataset;
+mod export_settings;
 pub mod file_formats;
+#[cfg(fuzzing)]
+pub mod fuzzing;
+#[cfg(feature = "export")]
 pub mod gamemode_formats;
+#[cfg(feature = "export")]
+pub mod gif;
+#[cfg(feature = "export")]
 pub mod gltf;
+#[cfg(feature = "export")]
+pub mod gltf_validate;
+mod name_table;
+#[cfg(feature = "export")]
+pub mod obj;
+#[cfg(feature = "export")]
 mod output_writer;
+#[cfg(feature = "viewer")]
+pub mod preview;
 mod reader;
+#[cfg(feature = "export")]
+mod shared_assets;
+#[cfg(feature = "export")]
+mod sound_index;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "export")]
+pub mod uv_overlay;
 mod vectors;
+mod warnings;
 
-pub use output_writer::OutputWriter;
-pub use reader::Reader;
+pub use export_settings::{
+	AnimCompression, AnimFormat, ExportSettings, MeshFormat, MeshSplit, PaletteFormats, PngColour,
+	PngCompression, PngFilter, UpAxis, Winding,
+};
+pub use name_table::Name;
+#[cfg(feature = "export")]
+pub use output_writer::{
+	DryRunFile, DryRunReport, ExportSession, OutputLayout, OutputWriter, OverwritePolicy,
+	WriteStats, sanitize_name,
+};
+pub use reader::{Endian, Reader};
+#[cfg(feature = "export")]
+pub use shared_assets::{SharedAssets, SharedAssetsStats, SharedRules};
+#[cfg(feature = "export")]
+pub use sound_index::{SoundIndex, SoundIndexStats};
 pub use vectors::{Vec2, Vec3, Vec4};
+pub use warnings::{Warning, Warnings};
@@ -1,24 +1,667 @@
-use mdk_parse::gamemode_formats;
+use std::path::PathBuf;
+
+use mdk_parse::config::Config;
+use mdk_parse::coverage::CoverageOutput;
+use mdk_parse::data_formats::cmi_bytecode::{CmiOpcodes, CmiSymbols};
+use mdk_parse::data_formats::masking::MaskSettings;
+use mdk_parse::data_formats::palette_cycle::{PaletteCycles, TextureFps};
+use mdk_parse::data_formats::{Dither, PngImportOptions};
+use mdk_parse::gamemode_formats::{AssetAliases, AssetFiles};
+use mdk_parse::gltf_validate;
+use mdk_parse::{
+	AnimFormat, ExportSession, ExportSettings, MeshFormat, MeshSplit, OutputLayout,
+	OverwritePolicy, PngColour, PngCompression, PngFilter, SharedAssets, SharedRules, SoundIndex,
+	UpAxis, Winding, file_formats, gamemode_formats,
+};
 
 fn main() {
 	let start_time = std::time::Instant::now();
 
-	let save_sounds = true;
-	let save_textures = true;
-	let save_meshes = true;
-	let save_videos = true;
+	let mut save_sounds = true;
+	let mut save_textures = true;
+	let mut save_meshes = true;
+	let mut save_videos = true;
+	let mut save_sound_banks = false;
+	let mut dedup_sounds = false;
+	let mut shared_rules: Option<SharedRules> = None;
+
+	let mut symbols = CmiSymbols::default();
+	let mut masking = MaskSettings::default();
+	let mut cycles = PaletteCycles::default();
+	let mut aliases = AssetAliases::default();
+	let mut settings = ExportSettings::default();
+	let mut extract_path: Option<PathBuf> = None;
+	let mut extract_mesh: Option<(PathBuf, String)> = None;
+	#[cfg(feature = "viewer")]
+	let mut preview_mesh: Option<(PathBuf, String)> = None;
+	let mut coverage: Option<CoverageOutput> = None;
+	let mut output_dir: Option<PathBuf> = None;
+	let mut layout: Option<OutputLayout> = None;
+	let mut overwrite = OverwritePolicy::default();
+	let mut normalise_names = false;
+	let mut assets_dir = PathBuf::from("assets");
+	let mut diff_roots: Option<(PathBuf, PathBuf)> = None;
+	let mut patch_roots: Option<(PathBuf, PathBuf)> = None;
+	let mut build_index = false;
+	let mut find_query: Option<String> = None;
+	let mut cmi_grep: Option<(PathBuf, String)> = None;
+	let mut cmi_filter = file_formats::CmiFilter::default();
+	let mut dry_run = false;
+	// Some(repair)
+	let mut validate_gltf: Option<bool> = None;
+	let mut write_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+	let mut inject: Option<(PathBuf, String, PathBuf)> = None;
+	let mut stitch: Option<(PathBuf, String)> = None;
+	let mut png_import = PngImportOptions {
+		transparent_index: Some(0),
+		..Default::default()
+	};
+
+	let args: Vec<String> = std::env::args().skip(1).collect();
+
+	// load the config first so flags override it
+	let config_path = match args.iter().position(|arg| arg == "--config") {
+		Some(index) => Some(PathBuf::from(
+			args.get(index + 1).expect("--config requires a path"),
+		)),
+		None => Some(PathBuf::from(Config::FILE_NAME)).filter(|path| path.exists()),
+	};
+	if let Some(path) = config_path {
+		println!("Using config {}", path.display());
+		let config = Config::load(&path);
+		config.apply(&mut settings);
+		save_sounds = config.formats.sounds;
+		save_textures = config.formats.textures;
+		save_meshes = config.formats.meshes;
+		save_videos = config.formats.videos;
+		save_sound_banks = config.formats.sound_banks;
+		if let Some(path) = &config.symbols {
+			symbols = CmiSymbols::load(path);
+		}
+		if let Some(path) = &config.opcodes {
+			symbols.opcodes = CmiOpcodes::load(path);
+		}
+		if let Some(path) = &config.export.texture_fps {
+			cycles.fps = TextureFps::load(path);
+		}
+		if let Some(template) = &config.layout {
+			layout = Some(OutputLayout::parse(template));
+		}
+		if let Some(path) = config.assets {
+			assets_dir = path;
+		}
+		output_dir = config.output;
+	}
+
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		let mut value = |name: &str| {
+			args.next()
+				.unwrap_or_else(|| panic!("{arg} requires a {name}"))
+		};
+		match arg.as_str() {
+			"--config" => {
+				value("path"); // already loaded
+			}
+			"--symbols" => {
+				let opcodes = std::mem::take(&mut symbols.opcodes);
+				symbols = CmiSymbols::load(value("path"));
+				symbols.opcodes = opcodes;
+			}
+			"--opcodes" => {
+				symbols.opcodes = CmiOpcodes::load(value("path"));
+			}
+			"--masks" => {
+				masking = MaskSettings::load(value("path"));
+			}
+			"--palette-cycles" => {
+				let fps = std::mem::take(&mut cycles.fps);
+				cycles = PaletteCycles::load(value("path"));
+				cycles.fps = fps;
+			}
+			"--texture-fps" => {
+				cycles.fps = TextureFps::load(value("path"));
+			}
+			"--asset-aliases" => {
+				aliases = AssetAliases::load(value("path"));
+			}
+			"--sound-banks" => {
+				save_sound_banks = true;
+			}
+			"--dedup-sounds" => {
+				dedup_sounds = true;
+			}
+			"--promote-shared" => {
+				shared_rules.get_or_insert_default();
+			}
+			"--shared-rules" => {
+				shared_rules = Some(SharedRules::load(value("path")));
+			}
+			"--sound-emitters" => {
+				settings.sound_emitters = true;
+			}
+			"--script-hex" => {
+				settings.script_hex = true;
+			}
+			"--nearest-filter" => {
+				settings.nearest_filter = true;
+			}
+			"--reflections" => {
+				settings.reflections = true;
+			}
+			"--outline-nodes" => {
+				settings.outline_nodes = true;
+			}
+			"--uv-overlays" => {
+				settings.uv_overlays = true;
+			}
+			"--dataset" => {
+				settings.level_dataset = true;
+			}
+			"--zones" => {
+				settings.zone_boxes = true;
+			}
+			"--anim-previews" => {
+				settings.anim_preview_frames =
+					value("frame count").parse().expect("invalid frame count");
+			}
+			"--png-compression" => {
+				let level = value("fast/default/best");
+				settings.png_compression = PngCompression::parse(&level)
+					.unwrap_or_else(|| panic!("unknown png compression {level}"));
+			}
+			"--png-filter" => {
+				let filter = value("none/sub/up/avg/paeth/adaptive");
+				settings.png_filter = PngFilter::parse(&filter)
+					.unwrap_or_else(|| panic!("unknown png filter {filter}"));
+			}
+			"--png-colour" => {
+				let colour = value("indexed/rgba");
+				settings.png_colour = PngColour::parse(&colour)
+					.unwrap_or_else(|| panic!("unknown png colour {colour}"));
+			}
+			"--anim-format" => {
+				let format = value("png/gif/both");
+				settings.anim_format = AnimFormat::parse(&format)
+					.unwrap_or_else(|| panic!("unknown animation format {format}"));
+			}
+			"--anim-fps" => {
+				settings.anim_fps = value("fps").parse().expect("invalid fps");
+			}
+			"--morph-targets" => {
+				settings.morph_targets = true;
+			}
+			"--fast-png" => {
+				settings.png_compression = PngCompression::Fast;
+			}
+			"--palette-tolerance" => {
+				settings.palette_tolerance = value("error").parse().expect("invalid tolerance");
+			}
+			"--palette-formats" => {
+				for format in value("list of gpl/act/ase").split(',') {
+					match format {
+						"gpl" => settings.palette_formats.gpl = true,
+						"act" => settings.palette_formats.act = true,
+						"ase" => settings.palette_formats.ase = true,
+						format => panic!("invalid palette format {format}"),
+					}
+				}
+			}
+			"--lods" => {
+				settings.lod_levels = value("count").parse().expect("invalid lod count");
+			}
+			"--up" => {
+				let axis = value("axis");
+				settings.up_axis =
+					UpAxis::parse(&axis).unwrap_or_else(|| panic!("invalid up axis {axis}"));
+			}
+			"--scale" => {
+				settings.scale = value("factor").parse().expect("invalid scale");
+			}
+			"--winding" => {
+				let order = value("order");
+				settings.winding = Winding::parse(&order)
+					.unwrap_or_else(|| panic!("invalid winding order {order}"));
+			}
+			"--layout" => {
+				layout = Some(OutputLayout::parse(&value("template")));
+			}
+			"--overwrite" => {
+				let policy = value("overwrite/error/skip/backup");
+				overwrite = OverwritePolicy::parse(&policy)
+					.unwrap_or_else(|| panic!("unknown overwrite policy {policy}"));
+			}
+			"--normalise-names" => {
+				normalise_names = true;
+			}
+			"--srgb-vertex-colours" => {
+				settings.srgb_vertex_colours = true;
+			}
+			"--anim-tolerance" => {
+				settings.anim_compression.tolerance =
+					Some(value("distance").parse().expect("invalid tolerance"));
+			}
+			"--keep-all-keyframes" => {
+				settings.anim_compression.prune = false;
+				settings.anim_compression.tolerance = None;
+			}
+			"--quantize-anims" => {
+				settings.anim_compression.quantize = true;
+			}
+			"--bsp-split" => {
+				let split = value("flat/id/material");
+				settings.bsp_split =
+					MeshSplit::parse(&split).unwrap_or_else(|| panic!("unknown bsp split {split}"));
+			}
+			"--obj" => {
+				settings.mesh_format = MeshFormat::Obj;
+			}
+			"--extract" => {
+				extract_path = Some(value("path").into());
+			}
+			"--entity" => {
+				cmi_filter.entities.push(value("entity glob"));
+			}
+			"--arena" => {
+				cmi_filter.arenas.push(value("arena name"));
+			}
+			"--extract-mesh" => {
+				let path = value("path").into();
+				extract_mesh = Some((path, value("mesh name")));
+			}
+			#[cfg(feature = "viewer")]
+			"--preview" => {
+				let path = value("path").into();
+				preview_mesh = Some((path, value("mesh name")));
+			}
+			"--coverage" => {
+				coverage = Some(CoverageOutput::Text);
+			}
+			"--coverage-html" => {
+				coverage = Some(CoverageOutput::Html);
+			}
+			"--diff" => {
+				let old_root = value("old asset path").into();
+				diff_roots = Some((old_root, value("new asset path").into()));
+			}
+			"--patch" => {
+				let game_root = value("game asset path").into();
+				patch_roots = Some((game_root, value("edited extract path").into()));
+			}
+			"--index" => {
+				build_index = true;
+			}
+			"--find" => {
+				find_query = Some(value("query"));
+			}
+			"--cmi-grep" => {
+				let path = value("cmi path").into();
+				cmi_grep = Some((path, value("query")));
+			}
+			"--inject" => {
+				let bni_path = value("bni path").into();
+				let entry = value("entry name");
+				inject = Some((bni_path, entry, value("replacement png/txt").into()));
+			}
+			"--stitch" => {
+				let path = value("sni path").into();
+				stitch = Some((path, value("comma separated sound names")));
+			}
+			"--dither" => {
+				png_import.dither = Dither::FloydSteinberg;
+			}
+			"--write-threads" => {
+				write_threads = value("thread count").parse().expect("invalid thread count");
+			}
+			"--validate-gltf" => {
+				validate_gltf.get_or_insert(false);
+			}
+			"--repair-gltf" => {
+				validate_gltf = Some(true);
+			}
+			"--dry-run" => {
+				dry_run = true;
+			}
+			"--output" => {
+				output_dir = Some(value("path").into());
+			}
+			"--assets" => {
+				assets_dir = value("path").into();
+			}
+			_ => panic!("unknown argument {arg}"),
+		}
+	}
+
+	// every file written uses the same naming and layout
+	let start_session = |root: PathBuf| {
+		let session = ExportSession::new(root);
+		session.set_layout(layout.clone());
+		session.set_overwrite_policy(overwrite);
+		session.set_normalise_names(normalise_names);
+		session
+	};
+	// extractions can also be dry runs or write in the background
+	let start_export = |root: PathBuf| {
+		let session = start_session(root);
+		if dry_run {
+			println!("Dry run, nothing will be written");
+			session.begin_dry_run();
+		} else if write_threads != 0 {
+			session.begin_background_writes(write_threads);
+		}
+		session
+	};
+
+	if let Some((old_root, new_root)) = diff_roots {
+		// compare two versions of the game
+		println!(
+			"Comparing {} to {}...",
+			old_root.display(),
+			new_root.display()
+		);
+		let report = file_formats::diff_asset_roots(&old_root, &new_root);
+		let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output/diff"));
+		start_session(output_dir)
+			.writer()
+			.write("Asset Diff", "txt", report.as_bytes());
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	if let Some((game_root, edited_root)) = patch_roots {
+		// repack edited assets into a mod
+		println!(
+			"Patching {} with {}...",
+			game_root.display(),
+			edited_root.display()
+		);
+		let files = file_formats::build_patch(&game_root, &edited_root, png_import);
+		let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output/patch"));
+		let mut output = start_session(output_dir).writer();
+		for file in &files {
+			let (name, ext) = file.path.rsplit_once('.').unwrap();
+			output.write(name, ext, &file.data);
+			match &file.ips {
+				Some(ips) => output.write(name, "ips", ips),
+				None => println!("  {} is too large for an ips patch", file.path),
+			}
+		}
+		output.write("Patch", "txt", file_formats::patch_report(&files));
+		println!(
+			"Patched {} files in {:.2?}",
+			files.len(),
+			start_time.elapsed()
+		);
+		return;
+	}
+
+	if let Some((bni_path, entry, replacement)) = inject {
+		// replace an asset and write out a new bni
+		println!(
+			"Injecting {} into {}...",
+			replacement.display(),
+			bni_path.display()
+		);
+		let data = std::fs::read(&bni_path)
+			.unwrap_or_else(|e| panic!("failed to read {}: {e}", bni_path.display()));
+		let mut archive = file_formats::BniArchive::parse(mdk_parse::Reader::new(&data));
+		archive.inject_file(&entry, &replacement, png_import);
+		let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output"));
+		let name = bni_path
+			.file_stem()
+			.expect("invalid bni path")
+			.to_string_lossy();
+		let ext = bni_path.extension().unwrap_or_default().to_string_lossy();
+		start_session(output_dir)
+			.writer()
+			.write(&name, &ext, archive.write());
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	if let Some((sni_path, names)) = stitch {
+		// join the chunks of a streamed sound into one wav
+		let names: Vec<&str> = names.split(',').collect();
+		println!(
+			"Stitching {} from {}...",
+			names.join(", "),
+			sni_path.display()
+		);
+		let data = std::fs::read(&sni_path)
+			.unwrap_or_else(|e| panic!("failed to read {}: {e}", sni_path.display()));
+		let sni = file_formats::Sni::parse(mdk_parse::Reader::new(&data));
+		match sni.stitch_sounds(&names) {
+			Ok(wav) => {
+				let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output"));
+				start_session(output_dir)
+					.writer()
+					.write(names[0], "wav", wav);
+			}
+			Err(e) => {
+				eprintln!("Couldn't stitch {}: {e}", sni_path.display());
+				std::process::exit(1);
+			}
+		}
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	if let Some((path, query)) = cmi_grep {
+		// search a cmi's scripts
+		let data = std::fs::read(&path)
+			.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+		let cmi = file_formats::Cmi::parse_with_symbols(mdk_parse::Reader::new(&data), &symbols);
+		for found in cmi.search(&file_formats::CmiQuery::parse(&query)) {
+			println!("{found}");
+		}
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	if build_index || find_query.is_some() {
+		// index asset names, then search them
+		let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output"));
+		let index_path = output_dir.join("asset_index.json");
+		let index = if build_index || !index_path.exists() {
+			println!("Indexing assets...");
+			let index = file_formats::AssetIndex::build(&assets_dir);
+			start_session(output_dir)
+				.writer()
+				.write("asset_index", "json", index.to_json());
+			index
+		} else {
+			file_formats::AssetIndex::load(&index_path)
+		};
+		if let Some(query) = find_query {
+			let query = file_formats::AssetQuery::parse(&query);
+			for asset in index.find(&query) {
+				match asset.offset {
+					Some(offset) => {
+						println!("{}\t{}\t{}@{offset:X}", asset.kind, asset.name, asset.file)
+					}
+					None => println!("{}\t{}\t{}", asset.kind, asset.name, asset.file),
+				}
+			}
+		}
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	if let Some(path) = extract_path {
+		// extract a single file
+		let output_dir = output_dir.unwrap_or_else(|| {
+			PathBuf::from("output").join(path.file_name().expect("invalid extract path"))
+		});
+		println!(
+			"Extracting {} to {}...",
+			path.display(),
+			output_dir.display()
+		);
+		let session = start_export(output_dir);
+		let mut output = session.writer().with_export_settings(settings);
+		if cmi_filter.is_empty() {
+			file_formats::extract_file(&path, &mut output, &symbols, coverage);
+		} else {
+			file_formats::extract_cmi(&path, &mut output, &symbols, &cmi_filter);
+		}
+		finish_export(&session, dry_run, validate_gltf);
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	#[cfg(feature = "viewer")]
+	if let Some((path, mesh_name)) = preview_mesh {
+		// render a single mesh to a turning animated png, found the same way as --extract-mesh
+		let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output/Preview"));
+		println!(
+			"Previewing {mesh_name} from {} in {}...",
+			path.display(),
+			output_dir.display()
+		);
+		let session = start_export(output_dir);
+		let mut output = session.writer().with_export_settings(settings);
+		let preview = mdk_parse::preview::Preview::default();
+		gamemode_formats::preview_mesh(
+			&path,
+			&mesh_name,
+			&symbols,
+			&masking,
+			&preview,
+			&mut output,
+		);
+		finish_export(&session, dry_run, validate_gltf);
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	if let Some((path, mesh_name)) = extract_mesh {
+		// extract a single mesh, finding its textures and palette from the files next to it
+		let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("output").join(&mesh_name));
+		println!(
+			"Extracting {mesh_name} from {} to {}...",
+			path.display(),
+			output_dir.display()
+		);
+		let session = start_export(output_dir);
+		let mut output = session.writer().with_export_settings(settings);
+		gamemode_formats::extract_mesh(&path, &mesh_name, &symbols, &masking, &mut output);
+		finish_export(&session, dry_run, validate_gltf);
+		println!("Done in {:.2?}", start_time.elapsed());
+		return;
+	}
+
+	let mut files = AssetFiles::detect(&assets_dir, &aliases);
+	match files.layout() {
+		Some(layout) => println!("{layout}"),
+		None => println!("No game folders found in {}", assets_dir.display()),
+	}
+	let session = start_export(output_dir.unwrap_or_else(|| PathBuf::from("output")));
+	let output = session.writer().with_export_settings(settings);
+	if dedup_sounds {
+		SoundIndex::begin(&session);
+	}
+	if let Some(rules) = shared_rules.clone() {
+		SharedAssets::begin(&session, rules);
+	}
+
+	let options = gamemode_formats::ExtractOptions {
+		save_sounds,
+		save_sound_banks,
+		save_textures,
+		save_meshes,
+		masking: &masking,
+		cycles: &cycles,
+	};
 
 	println!("Parsing traverse data...");
-	gamemode_formats::parse_traverse(save_sounds, save_textures, save_meshes);
+	gamemode_formats::parse_traverse(&options, &symbols, &output, &mut files);
 
 	println!("Parsing stream data...");
-	gamemode_formats::parse_stream(save_sounds, save_textures, save_meshes);
+	gamemode_formats::parse_stream(&options, &output, &mut files);
 
 	println!("Parsing fall3d data...");
-	gamemode_formats::parse_fall3d(save_sounds, save_textures, save_meshes);
+	gamemode_formats::parse_fall3d(&options, &output, &mut files);
 
 	println!("Parsing misc data...");
-	gamemode_formats::parse_misc(save_videos);
+	gamemode_formats::parse_misc(save_videos, &output, &mut files);
+
+	files.print_summary();
+	save_rename_report(&session);
+	print_skipped_files(&session);
+	if dry_run {
+		if dedup_sounds {
+			println!("{}", SoundIndex::finish(&session));
+		}
+		if shared_rules.is_some() {
+			println!("{}", SharedAssets::finish(&session));
+		}
+		print!("{}", session.finish_dry_run().summary());
+	} else {
+		println!("{}", session.finish_writes());
+		if dedup_sounds {
+			println!("{}", SoundIndex::finish(&session));
+		}
+		if shared_rules.is_some() {
+			println!("{}", SharedAssets::finish(&session));
+		}
+		if let Some(repair) = validate_gltf {
+			check_gltfs(&session, repair);
+		}
+	}
 
 	println!("Done in {:.2?}", start_time.elapsed());
 }
+
+/// Reports on a single file or mesh extraction once everything's been extracted
+fn finish_export(session: &ExportSession, dry_run: bool, validate_gltf: Option<bool>) {
+	save_rename_report(session);
+	print_skipped_files(session);
+	if dry_run {
+		print!("{}", session.finish_dry_run().summary());
+	} else {
+		println!("{}", session.finish_writes());
+		if let Some(repair) = validate_gltf {
+			check_gltfs(session, repair);
+		}
+	}
+}
+
+/// Saves the list of files that had to be renamed to be written, if there were any
+fn save_rename_report(session: &ExportSession) {
+	let report = session.rename_report();
+	if !report.is_empty() {
+		println!(
+			"Renamed {} files, see Renamed Files.txt",
+			report.lines().count()
+		);
+		session.writer().write("Renamed Files", "txt", report);
+	}
+}
+
+/// Lists the existing files that were kept instead of being overwritten, if there were any
+fn print_skipped_files(session: &ExportSession) {
+	const MAX_LISTED: usize = 20;
+	let skipped = session.skipped_files();
+	if skipped.is_empty() {
+		return;
+	}
+	println!("Kept {} existing files:", skipped.len());
+	for path in skipped.iter().take(MAX_LISTED) {
+		println!("  {}", path.display());
+	}
+	if skipped.len() > MAX_LISTED {
+		println!("  ...and {} more", skipped.len() - MAX_LISTED);
+	}
+}
+
+/// Checks every exported gltf, saving a list of any problems next to them
+fn check_gltfs(session: &ExportSession, repair: bool) {
+	println!("Checking gltf files...");
+	let report = gltf_validate::validate_dir(session.root(), repair);
+	println!("{report}");
+	if !report.issues.is_empty() {
+		session
+			.writer()
+			.write("GLTF Issues", "txt", report.details());
+	}
+}
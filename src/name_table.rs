@@ -0,0 +1,124 @@
+//! Interned asset names, so parsed assets don't have to borrow the file they came from.
+//!
+//! Names are never freed, which is fine for the few thousand distinct names in the game files.
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Every interned name, ids are indices into `names`
+#[derive(Default)]
+struct NameTable {
+	names: Vec<&'static str>,
+	ids: HashMap<&'static str, u32>,
+}
+
+static NAMES: LazyLock<RwLock<NameTable>> = LazyLock::new(Default::default);
+
+/// An interned name that can outlive its source buffer and be sent across threads.
+///
+/// Comparing and hashing only look at the id, ordering is alphabetical.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Name(u32);
+
+impl Name {
+	pub fn new(name: &str) -> Self {
+		if let Some(&id) = NAMES.read().unwrap().ids.get(name) {
+			return Name(id);
+		}
+		let mut table = NAMES.write().unwrap();
+		// another thread might have added it in the meantime
+		if let Some(&id) = table.ids.get(name) {
+			return Name(id);
+		}
+		let id = u32::try_from(table.names.len()).expect("too many names");
+		let name: &'static str = Box::leak(name.into());
+		table.names.push(name);
+		table.ids.insert(name, id);
+		Name(id)
+	}
+
+	/// Ids are handed out in order and are stable for the rest of the run
+	pub fn id(self) -> u32 {
+		self.0
+	}
+	pub fn as_str(self) -> &'static str {
+		NAMES.read().unwrap().names[self.0 as usize]
+	}
+}
+
+impl From<&str> for Name {
+	fn from(name: &str) -> Self {
+		Name::new(name)
+	}
+}
+impl PartialEq<str> for Name {
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == other
+	}
+}
+impl PartialEq<&str> for Name {
+	fn eq(&self, other: &&str) -> bool {
+		self.as_str() == *other
+	}
+}
+impl PartialOrd for Name {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl Ord for Name {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		if self == other {
+			std::cmp::Ordering::Equal
+		} else {
+			self.as_str().cmp(other.as_str())
+		}
+	}
+}
+impl std::fmt::Display for Name {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+impl std::fmt::Debug for Name {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self.as_str())
+	}
+}
+
+impl serde::Serialize for Name {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.as_str())
+	}
+}
+impl<'de> serde::Deserialize<'de> for Name {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let name = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+		Ok(Name::new(&name))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_intern() {
+		let buffer = String::from("GUNT_1 ARENA_2");
+		let a = Name::new(&buffer[..6]);
+		let b = Name::new(&buffer[7..]);
+		drop(buffer);
+
+		assert_eq!(a, Name::new("GUNT_1"));
+		assert_ne!(a, b);
+		assert_eq!(a.as_str(), "GUNT_1");
+		assert!(b < a);
+		assert_eq!(
+			std::thread::spawn(move || b.to_string()).join().unwrap(),
+			"ARENA_2"
+		);
+
+		let json = serde_json::to_string(&[a, b]).unwrap();
+		assert_eq!(json, r#"["GUNT_1","ARENA_2"]"#);
+		assert_eq!(serde_json::from_str::<[Name; 2]>(&json).unwrap(), [a, b]);
+	}
+}
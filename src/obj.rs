@@ -0,0 +1,203 @@
+//! A writer for [Wavefront OBJ](https://paulbourke.net/dataformats/obj/) models
+//! and their MTL material libraries.
+use std::fmt::Write;
+
+use crate::{ExportSettings, Vec2, Vec3};
+
+/// Faces of one object, grouped by material so each `usemtl` is only written once
+struct Object {
+	name: String,
+	groups: Vec<(Option<String>, String)>,
+}
+
+pub struct Obj {
+	name: String,
+	settings: ExportSettings,
+	verts: String,
+	uvs: String,
+	num_verts: usize,
+	num_uvs: usize,
+	objects: Vec<Object>,
+	material_names: Vec<String>,
+	materials: String,
+	/// Where the MTL file is, relative to the OBJ file
+	material_library: String,
+	current_material: Option<String>,
+}
+
+impl Obj {
+	pub fn new(name: String, settings: ExportSettings) -> Self {
+		Self {
+			material_library: format!("{name}.mtl"),
+			name,
+			settings,
+			verts: String::new(),
+			uvs: String::new(),
+			num_verts: 0,
+			num_uvs: 0,
+			objects: Vec::new(),
+			material_names: Vec::new(),
+			materials: String::new(),
+			current_material: None,
+		}
+	}
+
+	pub fn export_settings(&self) -> &ExportSettings {
+		&self.settings
+	}
+	/// Sets the `mtllib` path, if the MTL file isn't saved as `{name}.mtl` next to the OBJ file
+	pub fn set_material_library(&mut self, uri: String) {
+		self.material_library = uri;
+	}
+
+	/// Starts a new `o` object, which the following faces and lines are added to
+	pub fn start_object(&mut self, name: String) {
+		self.objects.push(Object {
+			name,
+			groups: Vec::new(),
+		});
+		self.current_material = None;
+	}
+
+	pub fn has_material(&self, name: &str) -> bool {
+		self.material_names.iter().any(|n| n == name)
+	}
+	/// Adds a material to the library, `properties` are MTL statements like `Kd 1 1 1`
+	pub fn add_material(&mut self, name: String, properties: &[String]) {
+		assert!(!self.has_material(&name), "duplicate material {name}");
+		writeln!(self.materials, "newmtl {name}").unwrap();
+		for property in properties {
+			writeln!(self.materials, "{property}").unwrap();
+		}
+		self.materials.push('\n');
+		self.material_names.push(name);
+	}
+	/// Sets the material of the following faces and lines
+	pub fn use_material(&mut self, name: &str) {
+		self.current_material = Some(name.to_owned());
+	}
+
+	/// Adds a (parsed, Y-up) vertex, with an optional colour using the common `v x y z r g b` extension.
+	///
+	/// Returns the 1-based OBJ index of the vertex.
+	pub fn add_vertex(&mut self, point: Vec3, colour: Option<[u8; 3]>) -> usize {
+		// adding zero turns -0 into 0
+		let [x, y, z] = self
+			.settings
+			.transform_point(point)
+			.to_array()
+			.map(|n| n + 0.0);
+		write!(self.verts, "v {x} {y} {z}").unwrap();
+		if let Some([r, g, b]) = colour {
+			let [r, g, b] = [r, g, b].map(|c| c as f32 / 255.0);
+			write!(self.verts, " {r} {g} {b}").unwrap();
+		}
+		self.verts.push('\n');
+		self.num_verts += 1;
+		self.num_verts
+	}
+	/// Adds a normalised texture coordinate (with the origin in the top left, like GLTF)
+	pub fn add_uv(&mut self, [u, v]: Vec2) -> usize {
+		writeln!(self.uvs, "vt {u} {}", 1.0 - v).unwrap();
+		self.num_uvs += 1;
+		self.num_uvs
+	}
+
+	fn current_group(&mut self) -> &mut String {
+		if self.objects.is_empty() {
+			self.start_object(self.name.clone());
+		}
+		let material = &self.current_material;
+		let groups = &mut self.objects.last_mut().unwrap().groups;
+		let index = match groups.iter().position(|(mat, _)| mat == material) {
+			Some(index) => index,
+			None => {
+				groups.push((material.clone(), String::new()));
+				groups.len() - 1
+			}
+		};
+		&mut groups[index].1
+	}
+
+	/// Adds a counter-clockwise triangle, reordered to match the export winding
+	pub fn add_face(&mut self, verts: [usize; 3], uvs: Option<[usize; 3]>) {
+		let settings = self.settings;
+		let group = self.current_group();
+		group.push('f');
+		match uvs {
+			Some(uvs) => {
+				let [(v1, t1), (v2, t2), (v3, t3)] =
+					settings.triangle([0, 1, 2].map(|i| (verts[i], uvs[i])));
+				write!(group, " {v1}/{t1} {v2}/{t2} {v3}/{t3}").unwrap();
+			}
+			None => {
+				let [v1, v2, v3] = settings.triangle(verts);
+				write!(group, " {v1} {v2} {v3}").unwrap();
+			}
+		}
+		group.push('\n');
+	}
+	pub fn add_line(&mut self, v1: usize, v2: usize) {
+		writeln!(self.current_group(), "l {v1} {v2}").unwrap();
+	}
+
+	/// Renders the OBJ file, and the MTL file if any materials were added
+	pub fn render(&self) -> (String, Option<String>) {
+		let mut obj = format!("# {}\n", self.name);
+		if !self.material_names.is_empty() {
+			writeln!(obj, "mtllib {}", self.material_library).unwrap();
+		}
+		obj.push_str(&self.verts);
+		obj.push_str(&self.uvs);
+		for object in &self.objects {
+			writeln!(obj, "o {}", object.name).unwrap();
+			for (material, faces) in &object.groups {
+				if let Some(material) = material {
+					writeln!(obj, "usemtl {material}").unwrap();
+				}
+				obj.push_str(faces);
+			}
+		}
+
+		let mtl = (!self.material_names.is_empty())
+			.then(|| format!("# {}\n\n{}", self.name, self.materials));
+		(obj, mtl)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{UpAxis, Winding};
+
+	#[test]
+	fn test_render() {
+		let settings = ExportSettings {
+			up_axis: UpAxis::Z,
+			winding: Winding::Clockwise,
+			..Default::default()
+		};
+		let mut obj = Obj::new("TEST".to_owned(), settings);
+		obj.start_object("PART".to_owned());
+		let verts = [[0.0, 1.0, 2.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]
+			.map(|p| obj.add_vertex(Vec3::from(p), Some([255, 0, 0])));
+		obj.add_material("Colour".to_owned(), &["Kd 1 1 1".to_owned()]);
+		obj.use_material("Colour");
+		obj.add_face(verts, None);
+		obj.use_material("Lines");
+		obj.add_line(verts[0], verts[1]);
+		obj.use_material("Colour");
+		let uvs = [[0.0, 0.0], [1.0, 0.0], [0.0, 0.25]].map(|uv| obj.add_uv(uv));
+		obj.add_face(verts, Some(uvs));
+
+		let (obj, mtl) = obj.render();
+		assert_eq!(
+			obj,
+			"# TEST\nmtllib TEST.mtl\n\
+			v 0 -2 1 1 0 0\nv 1 0 0 1 0 0\nv 0 -1 0 1 0 0\n\
+			vt 0 1\nvt 1 1\nvt 0 0.75\n\
+			o PART\nusemtl Colour\nf 1 3 2\nf 1/1 3/3 2/2\nusemtl Lines\nl 1 2\n"
+		);
+		assert_eq!(mtl.unwrap(), "# TEST\n\nnewmtl Colour\nKd 1 1 1\n\n");
+	}
+}
@@ -1,81 +1,497 @@
 use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap},
+	fmt::Write as _,
 	fs,
-	io::BufWriter,
+	io::{self, BufWriter, Write},
 	path::{Path, PathBuf},
+	sync::{
+		Arc, Mutex, RwLock,
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		mpsc,
+	},
+	thread::JoinHandle,
+	time::{Duration, Instant},
 };
 
+use crate::data_formats::palette;
+use crate::shared_assets;
+use crate::{ExportSettings, PngColour, PngCompression, PngFilter, SharedAssets, SoundIndex};
+
+/// One export, with where it goes and everything its [OutputWriter]s share:
+/// how files are named and laid out, the names already used, and how files are written.
+///
+/// Cloning gives another handle to the same export.
+#[derive(Clone)]
+pub struct ExportSession(pub(crate) Arc<Session>);
+
+pub(crate) struct Session {
+	root: PathBuf,
+	layout: RwLock<Option<OutputLayout>>,
+	normalise_names: AtomicBool,
+	paths: Mutex<OutputPaths>,
+	existing: Mutex<ExistingFiles>,
+	/// Files recorded instead of written while a dry run is active
+	dry_run: Mutex<Option<Vec<DryRunFile>>>,
+	pool: Mutex<Option<WritePool>>,
+	totals: Arc<WriteTotals>,
+	/// Sounds saved so far, if sounds are being deduplicated
+	pub(crate) sound_index: Mutex<Option<SoundIndex>>,
+	/// Textures and glTFs saved so far, if shared assets are being promoted
+	pub(crate) shared_assets: Mutex<Option<SharedAssets>>,
+}
+
+impl ExportSession {
+	/// Starts an export into the `root` folder
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self(Arc::new(Session {
+			root: root.into(),
+			layout: RwLock::new(None),
+			normalise_names: AtomicBool::new(false),
+			paths: Mutex::default(),
+			existing: Mutex::default(),
+			dry_run: Mutex::new(None),
+			pool: Mutex::new(None),
+			totals: Arc::default(),
+			sound_index: Mutex::new(None),
+			shared_assets: Mutex::new(None),
+		}))
+	}
+
+	pub fn root(&self) -> &Path {
+		&self.0.root
+	}
+
+	/// Creates an output writer that points to the root folder
+	pub fn writer(&self) -> OutputWriter {
+		let mut path = self.0.root.clone();
+		self.create_dir(&path);
+		path.push("_");
+		OutputWriter {
+			session: self.clone(),
+			path,
+			game_mode: String::new(),
+			level: Vec::new(),
+			asset_type: Vec::new(),
+			settings: ExportSettings::default(),
+			source_file: None,
+			palette_name: None,
+		}
+	}
+
+	/// Every file whose name had to be changed, as `requested -> actual` lines
+	pub fn rename_report(&self) -> String {
+		let mut result = String::new();
+		for (requested, actual) in &self.0.paths.lock().unwrap().renames {
+			writeln!(result, "{} -> {}", requested.display(), actual.display()).unwrap();
+		}
+		result
+	}
+
+	/// Sets the layout used by every writer, `None` for the default
+	/// (which matches `{game_mode}/{level}/{asset_type}/{name}`)
+	pub fn set_layout(&self, layout: Option<OutputLayout>) {
+		*self.0.layout.write().unwrap() = layout;
+	}
+	fn has_layout(&self) -> bool {
+		self.0.layout.read().unwrap().is_some()
+	}
+
+	/// Sets what every writer does with files that already exist, see [OverwritePolicy]
+	pub fn set_overwrite_policy(&self, policy: OverwritePolicy) {
+		self.0.existing.lock().unwrap().policy = policy;
+	}
+	/// Every existing file that wasn't written because of [OverwritePolicy::Skip]
+	pub fn skipped_files(&self) -> Vec<PathBuf> {
+		self.0.existing.lock().unwrap().skipped.clone()
+	}
+
+	/// Sets whether every writer lowercases file and folder names and replaces spaces with underscores,
+	/// rather than keeping the game's all caps names. References between output files
+	/// (like texture paths in GLTFs) are normalised the same way.
+	pub fn set_normalise_names(&self, normalise: bool) {
+		self.0.normalise_names.store(normalise, Ordering::Relaxed);
+	}
+	/// A file name or relative path as it's written, see [ExportSession::set_normalise_names]
+	fn output_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+		if self.0.normalise_names.load(Ordering::Relaxed) {
+			normalise_name(name)
+		} else {
+			Cow::Borrowed(name)
+		}
+	}
+
+	/// Hands finished files to `threads` background threads to write, for every writer
+	pub fn begin_background_writes(&self, threads: usize) {
+		let mut pool = self.0.pool.lock().unwrap();
+		assert!(pool.is_none(), "background writes already started");
+		*pool = Some(WritePool::new(threads, self.0.totals.clone()));
+	}
+	/// Waits for any background writes to finish, returning totals for everything written so far
+	pub fn finish_writes(&self) -> WriteStats {
+		let start = Instant::now();
+		let pool = self.0.pool.lock().unwrap().take();
+		if let Some(pool) = pool {
+			pool.finish();
+		}
+		let totals = &self.0.totals;
+		WriteStats {
+			files: totals.files.load(Ordering::Relaxed),
+			bytes: totals.bytes.load(Ordering::Relaxed),
+			write_time: Duration::from_nanos(totals.nanos.load(Ordering::Relaxed)),
+			wait_time: start.elapsed(),
+		}
+	}
+
+	/// Starts recording output files instead of writing them, for every writer
+	pub fn begin_dry_run(&self) {
+		*self.0.dry_run.lock().unwrap() = Some(Vec::new());
+	}
+	/// Stops the dry run, returning every file that would have been written
+	pub fn finish_dry_run(&self) -> DryRunReport {
+		let files = self
+			.0
+			.dry_run
+			.lock()
+			.unwrap()
+			.take()
+			.expect("no dry run in progress");
+		DryRunReport { files }
+	}
+	pub fn is_dry_run(&self) -> bool {
+		self.0.dry_run.lock().unwrap().is_some()
+	}
+	fn record_dry_run(&self, path: &Path, size: Option<u64>) {
+		if let Some(files) = self.0.dry_run.lock().unwrap().as_mut() {
+			files.push(DryRunFile {
+				path: path.to_owned(),
+				size,
+			});
+		}
+	}
+
+	fn create_dir(&self, path: &Path) {
+		if !self.is_dry_run() {
+			fs::create_dir_all(path).unwrap();
+		}
+	}
+
+	/// Whether a file can be written under the [OverwritePolicy], backing up the existing one if needed
+	fn can_write(&self, path: &Path) -> bool {
+		let mut existing = self.0.existing.lock().unwrap();
+		let policy = existing.policy;
+		if policy == OverwritePolicy::Overwrite {
+			return true;
+		}
+		if let Some(&decision) = existing.decided.get(path) {
+			return decision;
+		}
+		let decision = policy.apply(path);
+		if !decision {
+			existing.skipped.push(path.to_owned());
+		}
+		existing.decided.insert(path.to_owned(), decision);
+		decision
+	}
+
+	/// Writes a whole file, in the background if [ExportSession::begin_background_writes] was called
+	fn write_file(&self, path: &Path, data: Vec<u8>) {
+		if !self.can_write(path) {
+			return;
+		}
+		if let Some(pool) = self.0.pool.lock().unwrap().as_ref() {
+			pool.submit(path.to_owned(), data);
+			return;
+		}
+		let start = Instant::now();
+		if let Err(e) = fs::write(path, &data) {
+			panic!("failed to write file {}: {e}", path.display());
+		}
+		self.0.totals.record(data.len(), start.elapsed());
+	}
+}
+
 /// Helper struct to wrangle filenames, folder structures, and PNG stuff
 #[derive(Clone)]
 pub struct OutputWriter {
+	session: ExportSession,
 	path: PathBuf,
+	pub(crate) game_mode: String,
+	pub(crate) level: Vec<String>,
+	pub(crate) asset_type: Vec<String>,
+	settings: ExportSettings,
+	/// Recorded in saved PNGs
+	source_file: Option<String>,
+	palette_name: Option<String>,
 }
 impl OutputWriter {
-	/// Creates an output writer that points to the corresponding path in the Output folder
+	/// Creates an output writer that points directly to the given directory, as a new export
+	pub fn new_in_dir(dir: impl Into<PathBuf>) -> Self {
+		ExportSession::new(dir).writer()
+	}
+
+	/// Creates an output writer (with the same settings) that points to the folder in the export
+	/// matching a folder of game assets, relative to the assets folder
 	///
-	/// e.g. `assets/MISC` becomes `output/MISC`
-	pub fn new(path: impl AsRef<Path>, create_output_dir: bool) -> Self {
-		let mut output_path =
-			Path::new("output").join(path.as_ref().strip_prefix("assets").unwrap());
-		if create_output_dir {
-			fs::create_dir_all(&output_path).unwrap();
+	/// e.g. `MISC` becomes `{root}/MISC`
+	#[must_use]
+	pub fn asset_dir(&self, path: impl AsRef<Path>) -> Self {
+		let path = path.as_ref();
+		let dirs: Vec<String> = path
+			.iter()
+			.map(|dir| {
+				self.session
+					.output_name(&dir.to_string_lossy())
+					.into_owned()
+			})
+			.collect();
+		let mut output_path = self.session.0.root.clone();
+		output_path.extend(&dirs);
+		if !self.session.has_layout() {
+			self.session.create_dir(&output_path);
 		}
 		output_path.push("_");
-		OutputWriter { path: output_path }
+		let mut dirs = dirs.into_iter();
+		OutputWriter {
+			session: self.session.clone(),
+			path: output_path,
+			game_mode: dirs.next().unwrap_or_default(),
+			level: dirs.collect(),
+			asset_type: Vec::new(),
+			settings: self.settings,
+			source_file: Some(Path::new("assets").join(path).display().to_string()),
+			palette_name: None,
+		}
+	}
+
+	/// The export this writer is part of
+	pub fn session(&self) -> &ExportSession {
+		&self.session
 	}
 
+	/// Sets the conventions used for 3D exports from this writer (and any pushed dirs)
+	#[must_use]
+	pub fn with_export_settings(mut self, settings: ExportSettings) -> Self {
+		self.settings = settings;
+		self
+	}
+	pub fn export_settings(&self) -> &ExportSettings {
+		&self.settings
+	}
+
+	/// Sets the source file recorded in saved PNGs
+	pub fn set_source_file(&mut self, source_file: impl std::fmt::Display) {
+		self.source_file = Some(source_file.to_string());
+	}
+	/// Creates a writer that records the palette (or arena) name in the paletted PNGs it saves
+	///
+	/// Pushed folders don't keep the name, since their assets could use any palette.
+	#[must_use]
+	pub fn with_palette_name(&self, palette_name: &str) -> Self {
+		let mut result = self.clone();
+		result.palette_name = Some(palette_name.to_owned());
+		result
+	}
+
+	/// Provenance text chunks for saved PNGs
+	fn png_text(&self, asset_name: &str, palette: Option<&[u8]>) -> Vec<(&'static str, String)> {
+		let mut result = vec![
+			("Title", asset_name.to_owned()),
+			(
+				"Software",
+				format!("mdk-parse {}", env!("CARGO_PKG_VERSION")),
+			),
+		];
+		if let Some(source_file) = &self.source_file {
+			result.push(("Source", source_file.clone()));
+		}
+		if let Some(palette_name) = &self.palette_name
+			&& palette.is_some()
+		{
+			result.push(("Palette", palette_name.clone()));
+		}
+		result
+	}
+
+	/// Pushes a folder for a kind of asset (`{asset_type}` in an [OutputLayout])
 	#[must_use]
 	pub fn push_dir(&self, dir: &str) -> Self {
+		let dir = self.session.output_name(dir);
+		let mut result = self.push_dir_inner(&dir);
+		result.asset_type.extend(dir.split('/').map(str::to_owned));
+		result
+	}
+	/// Pushes a folder for a level (`{level}` in an [OutputLayout])
+	#[must_use]
+	pub fn push_level_dir(&self, dir: &str) -> Self {
+		let dir = self.session.output_name(dir);
+		let mut result = self.push_dir_inner(&dir);
+		result.level.extend(dir.split('/').map(str::to_owned));
+		result
+	}
+	fn push_dir_inner(&self, dir: &str) -> Self {
 		let mut result = self.clone();
+		result.palette_name = None;
 		result.path.set_file_name(dir);
-		fs::create_dir_all(&result.path).unwrap();
+		if !self.session.has_layout() {
+			self.session.create_dir(&result.path);
+		}
 		result.path.push("a");
 		result
 	}
 
+	/// Points the writer at the file for an asset, returning its path.
+	///
+	/// Names are made safe for every filesystem (see [sanitize_name]), and names that only differ
+	/// in case from an earlier file are numbered (see [ExportSession::rename_report]).
 	pub fn set_output_path(&mut self, asset_name: &str, ext: &str) -> &Path {
 		let ext = ext.trim_start_matches('.');
-		self.path.set_file_name(asset_name);
-		self.path.set_extension(ext);
+		let sanitized = sanitize_name(asset_name);
+		let name = self.session.output_name(&sanitized).into_owned();
+		self.path = self.asset_path(&name, ext);
+		let mut paths = self.session.0.paths.lock().unwrap();
+		let mut number = 1;
+		while !paths.claim(&self.path) {
+			number += 1;
+			self.path = self.asset_path(&format!("{name}_{number}"), ext);
+		}
+		// normalised names aren't worth reporting, they all change
+		if number != 1 || sanitized != asset_name {
+			let rename = (self.asset_path(asset_name, ext), self.path.clone());
+			if !paths.renames.contains(&rename) {
+				paths.renames.push(rename);
+			}
+		}
 		&self.path
 	}
+	/// The uri from the `from_ext` file saved as `from_name` to the file saved at `relative_path`
+	/// (e.g. `Textures/GUNT.png`, a file name under any pushed folders), following the same
+	/// name changes as [OutputWriter::set_output_path] and the [OutputLayout]
+	pub fn relative_uri(&self, from_name: &str, from_ext: &str, relative_path: &str) -> String {
+		let mut from = self.clone();
+		let from = from.set_output_path(from_name, from_ext);
+		let (dir, file) = relative_path
+			.rsplit_once('/')
+			.unwrap_or(("", relative_path));
+		let (name, ext) = file.rsplit_once('.').unwrap_or((file, ""));
+		let mut to = if dir.is_empty() {
+			self.clone()
+		} else {
+			self.push_dir(dir)
+		};
+		let to = to.set_output_path(name, ext);
+		shared_assets::relative_path(from.parent().unwrap(), to)
+	}
+	fn asset_path(&self, asset_name: &str, ext: &str) -> PathBuf {
+		let mut path = if let Some(layout) = self.session.0.layout.read().unwrap().as_ref() {
+			let path = layout.resolve(self, asset_name);
+			self.session.create_dir(path.parent().unwrap());
+			path
+		} else {
+			self.path.with_file_name(asset_name)
+		};
+		path.set_extension(ext);
+		path
+	}
+
+	/// Applies the [OverwritePolicy] to a file written by something else (e.g. ffmpeg),
+	/// returning whether it should be written
+	pub fn can_write(&self, path: &Path) -> bool {
+		self.session.can_write(path)
+	}
 
 	pub fn write(&mut self, asset_name: &str, ext: &str, data: impl AsRef<[u8]>) {
-		let path = self.set_output_path(asset_name, ext);
+		self.set_output_path(asset_name, ext);
+		let path = &self.path;
+		if ext == "gltf" {
+			shared_assets::record_document(&self.session, path);
+		}
 
-		if let Err(e) = fs::write(path, data) {
-			panic!("failed to write file {}: {e}", path.display());
-		};
+		if self.session.is_dry_run() {
+			self.session
+				.record_dry_run(path, Some(data.as_ref().len() as u64));
+		} else {
+			self.session.write_file(path, data.as_ref().into());
+		}
+	}
+
+	pub fn is_dry_run(&self) -> bool {
+		self.session.is_dry_run()
+	}
+	/// Records a file written by something else (e.g. ffmpeg) during a dry run
+	pub fn record_dry_run(&self, path: &Path) {
+		self.session.record_dry_run(path, None);
 	}
 
 	pub fn write_png(
 		&mut self, asset_name: &str, width: u32, height: u32, pixels: impl AsRef<[u8]>,
 		palette: Option<&[u8]>,
 	) {
-		save_png(
-			self.set_output_path(asset_name, "png"),
-			pixels.as_ref(),
+		let text = self.png_text(asset_name, palette);
+		let settings = self.settings;
+		let path = self.set_output_path(asset_name, "png").to_owned();
+		shared_assets::record_texture(self, &path, width, height, pixels.as_ref(), palette);
+		let image = PngImage {
+			pixels: pixels.as_ref(),
 			width,
 			height,
 			palette,
-			false,
-		)
+			palette_rgba: false,
+		};
+		save_png(&self.session, &path, &image, &text, &settings)
+	}
+	/// Encodes a png the same way as [Self::write_png], but returns it instead of writing it
+	/// (e.g. to embed in a glTF)
+	pub fn create_png(
+		&self, asset_name: &str, width: u32, height: u32, pixels: &[u8], palette: Option<&[u8]>,
+	) -> Vec<u8> {
+		let image = PngImage {
+			pixels,
+			width,
+			height,
+			palette,
+			palette_rgba: false,
+		};
+		let mut data = Vec::new();
+		let text = self.png_text(asset_name, palette);
+		encode_png(&mut data, &image, &text, &self.settings);
+		data
 	}
 	pub fn write_png_rgba(
 		&mut self, asset_name: &str, width: u32, height: u32, pixels: impl AsRef<[u8]>,
 		palette: &[u8],
 	) {
-		save_png(
-			self.set_output_path(asset_name, "png"),
-			pixels.as_ref(),
+		let text = self.png_text(asset_name, Some(palette));
+		let settings = self.settings;
+		let path = self.set_output_path(asset_name, "png").to_owned();
+		shared_assets::record_texture(self, &path, width, height, pixels.as_ref(), Some(palette));
+		let image = PngImage {
+			pixels: pixels.as_ref(),
 			width,
 			height,
-			Some(palette),
-			true,
-		)
+			palette: Some(palette),
+			palette_rgba: true,
+		};
+		save_png(&self.session, &path, &image, &text, &settings)
 	}
 
+	/// Saves a palette as a PNG, plus any swatch formats enabled in the export settings
 	pub fn write_palette(&mut self, asset_name: &str, pixels: impl AsRef<[u8]>) {
-		save_pal(self.set_output_path(asset_name, "png"), pixels.as_ref())
+		let pixels = pixels.as_ref();
+		self.set_output_path(asset_name, "png");
+		let file = OutputFile::create(&self.session, &self.path);
+		save_pal(file, pixels, &self.settings);
+
+		let formats = self.settings.palette_formats;
+		if formats.gpl {
+			self.write(asset_name, "gpl", palette::to_gpl(asset_name, pixels));
+		}
+		if formats.act {
+			self.write(asset_name, "act", palette::to_act(pixels));
+		}
+		if formats.ase {
+			self.write(asset_name, "ase", palette::to_ase(asset_name, pixels));
+		}
 	}
 
 	#[must_use]
@@ -106,70 +522,145 @@ impl OutputWriter {
 		&mut self, asset_name: &str, width: u32, height: u32, fps: u16, num_frames: u32,
 		palette: Option<&[u8]>, palette_rgba: bool,
 	) -> png::Writer<impl std::io::Write> {
-		let path = self.set_output_path(asset_name, "png");
-		let mut encoder = setup_png(path, width, height, palette, palette_rgba);
+		let text = self.png_text(asset_name, palette);
+		let settings = self.settings;
+		self.set_output_path(asset_name, "png");
+		let file = OutputFile::create(&self.session, &self.path);
+		let mut encoder = setup_png(file, width, height, palette, palette_rgba, &text, &settings);
 		if num_frames > 1 {
 			encoder.set_animated(num_frames, 0).unwrap();
 			encoder.set_sep_def_img(false).unwrap();
 			encoder.set_frame_delay(1, fps).unwrap();
+			// frames after the first only contain the changed region, so they replace what's underneath
+			encoder.set_blend_op(png::BlendOp::Source).unwrap();
+			encoder.set_dispose_op(png::DisposeOp::None).unwrap();
 		}
 		encoder.write_header().unwrap()
 	}
 }
 
+/// An image to save as a PNG, with pixels indexing the palette (or greyscale without one)
+struct PngImage<'a> {
+	pixels: &'a [u8],
+	width: u32,
+	height: u32,
+	palette: Option<&'a [u8]>,
+	/// Whether the palette is sorted as rgbrgbrgb...aaa instead of just rgb
+	palette_rgba: bool,
+}
+
 fn save_png(
-	path: &Path, data: &[u8], width: u32, height: u32, palette: Option<&[u8]>, palette_rgba: bool,
+	session: &ExportSession, path: &Path, image: &PngImage, text: &[(&str, String)],
+	settings: &ExportSettings,
 ) {
+	let file = OutputFile::create(session, path);
+	encode_png(file, image, text, settings);
+}
+fn encode_png(
+	file: impl Write, image: &PngImage, text: &[(&str, String)], settings: &ExportSettings,
+) {
+	let &PngImage {
+		pixels: data,
+		width,
+		height,
+		palette,
+		palette_rgba,
+	} = image;
 	debug_assert_eq!(
 		width as usize * height as usize,
 		data.len(),
 		"mismatched image dimensions"
 	);
 
+	if settings.png_colour == PngColour::Rgba
+		&& let Some(palette) = palette
+	{
+		let pixels = palette_to_rgba(data, palette, palette_rgba);
+		let mut encoder = setup_png(file, width, height, None, false, text, settings);
+		encoder.set_color(png::ColorType::Rgba);
+		let mut encoder = encoder.write_header().unwrap();
+		encoder.write_image_data(pixels.as_flattened()).unwrap();
+		encoder.finish().unwrap();
+		return;
+	}
+
 	let palette = match palette {
 		Some(pal) if !palette_rgba => {
 			// truncate unused colours for no reason
 			let max_index = data.iter().copied().max().unwrap() as usize;
 			let trimmed_pal = pal.get(..(max_index + 1) * 3);
-			debug_assert!(
-				trimmed_pal.is_some(),
-				"indexed pixel out of range in {}",
-				path.display()
-			);
+			debug_assert!(trimmed_pal.is_some(), "indexed pixel out of range");
 			trimmed_pal
 		}
 		_ => palette,
 	};
 
-	let mut encoder = setup_png(path, width, height, palette, palette_rgba)
+	let mut encoder = setup_png(file, width, height, palette, palette_rgba, text, settings)
 		.write_header()
 		.unwrap();
 	encoder.write_image_data(data).unwrap();
 	encoder.finish().unwrap();
 }
-fn save_pal(path: &Path, data: &[u8]) {
+/// Looks up the colour of each pixel, with index 0 of RGB palettes transparent like indexed PNGs
+fn palette_to_rgba(data: &[u8], palette: &[u8], palette_rgba: bool) -> Vec<[u8; 4]> {
+	let (rgb, alpha) = if palette_rgba {
+		palette.split_at(palette.len() / 4 * 3)
+	} else {
+		(palette, &[][..])
+	};
+	data.iter()
+		.map(|&index| {
+			let i = index as usize;
+			let a = match alpha.get(i) {
+				Some(&a) => a,
+				None if palette_rgba || index != 0 => 255,
+				None => 0,
+			};
+			[rgb[i * 3], rgb[i * 3 + 1], rgb[i * 3 + 2], a]
+		})
+		.collect()
+}
+fn save_pal(file: OutputFile, data: &[u8], settings: &ExportSettings) {
 	let width: u32 = 16;
-	assert!(data.len() % 24 == 0);
+	assert!(data.len().is_multiple_of(24));
 	let height = data.len() as u32 / (3 * width);
-	let mut encoder = png::Encoder::new(
-		BufWriter::new(fs::File::create(path).unwrap()),
-		width,
-		height,
-	);
+	let mut encoder = png_encoder(file, width, height, settings);
 	encoder.set_color(png::ColorType::Rgb);
 	let mut encoder = encoder.write_header().unwrap();
 	encoder.write_image_data(data).unwrap();
 	encoder.finish().unwrap();
 }
 
-fn setup_png<'a>(
-	path: &Path, width: u32, height: u32, palette: Option<&'a [u8]>, palette_rgba: bool,
-) -> png::Encoder<'a, impl std::io::Write> {
-	let mut encoder = png::Encoder::new(
-		BufWriter::new(fs::File::create(path).unwrap()),
-		width,
-		height,
-	);
+/// Creates an encoder using the compression and filter from the export settings
+fn png_encoder<'a, W: Write>(
+	file: W, width: u32, height: u32, settings: &ExportSettings,
+) -> png::Encoder<'a, W> {
+	let mut encoder = png::Encoder::new(file, width, height);
+	encoder.set_compression(match settings.png_compression {
+		PngCompression::Fast => png::Compression::Fast,
+		PngCompression::Default => png::Compression::Default,
+		PngCompression::Best => png::Compression::Best,
+	});
+	let (filter, adaptive) = match settings.png_filter {
+		PngFilter::None => (png::FilterType::NoFilter, false),
+		PngFilter::Sub => (png::FilterType::Sub, false),
+		PngFilter::Up => (png::FilterType::Up, false),
+		PngFilter::Avg => (png::FilterType::Avg, false),
+		PngFilter::Paeth => (png::FilterType::Paeth, false),
+		PngFilter::Adaptive => (png::FilterType::Sub, true),
+	};
+	encoder.set_filter(filter);
+	if adaptive {
+		encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+	}
+	encoder
+}
+
+fn setup_png<'a, W: Write>(
+	file: W, width: u32, height: u32, palette: Option<&'a [u8]>, palette_rgba: bool,
+	text: &[(&str, String)], settings: &ExportSettings,
+) -> png::Encoder<'a, W> {
+	let mut encoder = png_encoder(file, width, height, settings);
 	if let Some(palette) = palette {
 		encoder.set_color(png::ColorType::Indexed);
 		if !palette_rgba {
@@ -189,9 +680,507 @@ fn setup_png<'a>(
 		encoder.set_color(png::ColorType::Grayscale);
 	}
 
+	for (keyword, value) in text {
+		encoder
+			.add_text_chunk(keyword.to_string(), value.clone())
+			.unwrap();
+	}
+
 	encoder
 }
 
+/// A template for where output files go, relative to the output folder,
+/// e.g. `{asset_type}/{game_mode}_{level}/{name}` or just `{name}` for a flat layout.
+///
+/// | placeholder    | example             |
+/// |----------------|---------------------|
+/// | `{game_mode}`  | `TRAVERSE`          |
+/// | `{level}`      | `LEVEL3`            |
+/// | `{asset_type}` | `Meshes/Textures`   |
+/// | `{name}`       | `GUNT_10`           |
+///
+/// Segments that end up empty are skipped. Files in a flat layout can overwrite each other.
+/// References between files (like glTF texture uris) are relative to where the layout puts them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLayout {
+	segments: Vec<String>,
+}
+
+impl OutputLayout {
+	const PLACEHOLDERS: [&str; 4] = ["game_mode", "level", "asset_type", "name"];
+
+	pub fn parse(template: &str) -> Self {
+		let segments: Vec<String> = template
+			.split('/')
+			.filter(|s| !s.is_empty())
+			.map(str::to_owned)
+			.collect();
+		for segment in &segments {
+			let mut rest = segment.as_str();
+			while let Some(start) = rest.find('{') {
+				let Some(end) = rest[start..].find('}') else {
+					panic!("unclosed placeholder in layout {template}");
+				};
+				let placeholder = &rest[start + 1..start + end];
+				assert!(
+					Self::PLACEHOLDERS.contains(&placeholder),
+					"unknown placeholder {{{placeholder}}} in layout {template}"
+				);
+				rest = &rest[start + end..];
+			}
+		}
+		assert!(
+			segments
+				.last()
+				.is_some_and(|segment| segment.contains("{name}")),
+			"layout {template} must end with {{name}}"
+		);
+		Self { segments }
+	}
+
+	fn resolve(&self, writer: &OutputWriter, name: &str) -> PathBuf {
+		let level = writer.level.join("/");
+		let asset_type = writer.asset_type.join("/");
+		let mut path = writer.session.0.root.clone();
+		for segment in &self.segments {
+			let segment = segment
+				.replace("{game_mode}", &writer.game_mode)
+				.replace("{level}", &level)
+				.replace("{asset_type}", &asset_type)
+				.replace("{name}", name);
+			path.extend(segment.split('/').filter(|s| !s.is_empty()));
+		}
+		path
+	}
+}
+
+/// Makes an asset name (which comes straight from the game data) safe to use as a file name
+/// on every filesystem.
+///
+/// Characters Windows doesn't allow become `_`, as do trailing dots and spaces,
+/// and reserved device names (`CON`, `COM1` etc.) get a `_` prefix.
+/// Slashes are kept since some names include a folder.
+pub fn sanitize_name(name: &str) -> Cow<'_, str> {
+	const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+	fn is_reserved(part: &str) -> bool {
+		let stem = part.split('.').next().unwrap().to_ascii_uppercase();
+		RESERVED.contains(&stem.as_str())
+			|| ((stem.starts_with("COM") || stem.starts_with("LPT"))
+				&& stem.len() == 4
+				&& matches!(stem.as_bytes()[3], b'1'..=b'9'))
+	}
+	fn is_invalid(c: char) -> bool {
+		c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*')
+	}
+
+	let needs_change = name.contains(is_invalid)
+		|| name
+			.split('/')
+			.any(|part| part.is_empty() || part.ends_with(['.', ' ']) || is_reserved(part));
+	if !needs_change {
+		return Cow::Borrowed(name);
+	}
+	let parts: Vec<String> = name
+		.split('/')
+		.map(|part| {
+			let mut part: String = part
+				.chars()
+				.map(|c| if is_invalid(c) { '_' } else { c })
+				.collect();
+			let trimmed = part.trim_end_matches(['.', ' ']).len();
+			if trimmed != part.len() || part.is_empty() {
+				part.truncate(trimmed);
+				part.push('_');
+			}
+			if is_reserved(&part) {
+				part.insert(0, '_');
+			}
+			part
+		})
+		.collect();
+	Cow::Owned(parts.join("/"))
+}
+
+/// Every path written so far, to catch names that only differ in case
+/// (which are the same file on Windows and macOS)
+#[derive(Default)]
+struct OutputPaths {
+	/// Paths by their lowercase version
+	paths: BTreeMap<String, PathBuf>,
+	/// `(requested, actual)` paths of renamed files
+	renames: Vec<(PathBuf, PathBuf)>,
+}
+impl OutputPaths {
+	/// Whether `path` can be written, which is false if a different path only differs in case
+	fn claim(&mut self, path: &Path) -> bool {
+		let key = path.to_string_lossy().to_lowercase();
+		match self.paths.get(&key) {
+			Some(existing) => existing == path,
+			None => {
+				self.paths.insert(key, path.to_owned());
+				true
+			}
+		}
+	}
+}
+
+fn normalise_name(name: &str) -> Cow<'_, str> {
+	if name.contains(|c: char| c.is_uppercase() || c == ' ') {
+		Cow::Owned(name.to_lowercase().replace(' ', "_"))
+	} else {
+		Cow::Borrowed(name)
+	}
+}
+
+/// What to do when a file being written already exists, which is probably from an earlier export
+/// but might have been edited since
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverwritePolicy {
+	/// Replace it
+	#[default]
+	Overwrite,
+	/// Panic
+	Error,
+	/// Keep it and don't write the new one, see [ExportSession::skipped_files]
+	Skip,
+	/// Rename it to `{name}.bak` (or `.bak2` and so on if that exists too) first
+	Backup,
+}
+
+impl OverwritePolicy {
+	pub fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"overwrite" => OverwritePolicy::Overwrite,
+			"error" => OverwritePolicy::Error,
+			"skip" => OverwritePolicy::Skip,
+			"backup" => OverwritePolicy::Backup,
+			_ => return None,
+		})
+	}
+
+	/// Whether `path` can be written, backing up the file that's there if needed
+	fn apply(self, path: &Path) -> bool {
+		if !path.exists() {
+			return true;
+		}
+		match self {
+			OverwritePolicy::Overwrite => true,
+			OverwritePolicy::Error => panic!("{} already exists", path.display()),
+			OverwritePolicy::Skip => false,
+			OverwritePolicy::Backup => {
+				let backup = (1..)
+					.map(|i| {
+						let suffix = if i == 1 { String::new() } else { i.to_string() };
+						let mut backup = path.as_os_str().to_owned();
+						backup.push(format!(".bak{suffix}"));
+						PathBuf::from(backup)
+					})
+					.find(|backup| !backup.exists())
+					.unwrap();
+				if let Err(e) = fs::rename(path, &backup) {
+					panic!("failed to back up {}: {e}", path.display());
+				}
+				true
+			}
+		}
+	}
+}
+
+#[derive(Default)]
+struct ExistingFiles {
+	policy: OverwritePolicy,
+	/// Whether each path written so far can be written, so writing a file twice in one export
+	/// doesn't count as overwriting an existing one
+	decided: HashMap<PathBuf, bool>,
+	skipped: Vec<PathBuf>,
+}
+
+/// Totals for every file written
+#[derive(Default)]
+struct WriteTotals {
+	files: AtomicU64,
+	bytes: AtomicU64,
+	/// Time spent in whole-file writes, summed over every thread
+	nanos: AtomicU64,
+}
+impl WriteTotals {
+	fn record(&self, bytes: usize, time: Duration) {
+		self.files.fetch_add(1, Ordering::Relaxed);
+		self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+		self.nanos
+			.fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+	}
+}
+
+/// Totals from [ExportSession::finish_writes]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteStats {
+	pub files: u64,
+	pub bytes: u64,
+	/// Time spent writing whole files (not including streamed ones), summed over every thread
+	pub write_time: Duration,
+	/// Time spent waiting for background writes at the end
+	pub wait_time: Duration,
+}
+impl std::fmt::Display for WriteStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"Wrote {} files ({}), {:.2?} writing, {:.2?} waiting for background writes",
+			self.files,
+			format_size(self.bytes),
+			self.write_time,
+			self.wait_time
+		)
+	}
+}
+
+/// Threads writing queued files, so many small files don't hold up extraction
+struct WritePool {
+	sender: mpsc::SyncSender<(PathBuf, Vec<u8>)>,
+	/// Each thread returns the files it failed to write
+	threads: Vec<JoinHandle<Vec<String>>>,
+}
+impl WritePool {
+	const QUEUED_PER_THREAD: usize = 16;
+
+	fn new(num_threads: usize, totals: Arc<WriteTotals>) -> Self {
+		assert!(num_threads != 0, "no write threads");
+		// extraction waits once this many files are queued, so they can't all pile up in memory
+		let (sender, receiver) =
+			mpsc::sync_channel::<(PathBuf, Vec<u8>)>(num_threads * Self::QUEUED_PER_THREAD);
+		let receiver = Arc::new(Mutex::new(receiver));
+		let threads = (0..num_threads)
+			.map(|_| {
+				let receiver = receiver.clone();
+				let totals = totals.clone();
+				std::thread::spawn(move || {
+					let mut errors = Vec::new();
+					loop {
+						// only hold the lock while waiting for the next file
+						let Ok((path, data)) = receiver.lock().unwrap().recv() else {
+							break;
+						};
+						let start = Instant::now();
+						match fs::write(&path, &data) {
+							Ok(()) => totals.record(data.len(), start.elapsed()),
+							Err(e) => errors.push(format!("{}: {e}", path.display())),
+						}
+					}
+					errors
+				})
+			})
+			.collect();
+		Self { sender, threads }
+	}
+	fn submit(&self, path: PathBuf, data: Vec<u8>) {
+		self.sender
+			.send((path, data))
+			.expect("write threads stopped");
+	}
+	/// Waits for every queued file to be written
+	fn finish(self) {
+		drop(self.sender);
+		let errors: Vec<String> = self
+			.threads
+			.into_iter()
+			.flat_map(|thread| thread.join().expect("write thread panicked"))
+			.collect();
+		assert!(
+			errors.is_empty(),
+			"failed to write files:\n{}",
+			errors.join("\n")
+		);
+	}
+}
+
+/// Streamed output file, which just counts the bytes written during a dry run
+/// and is written in one go when writing in the background
+enum OutputFile {
+	File {
+		session: ExportSession,
+		file: BufWriter<fs::File>,
+	},
+	Buffered {
+		session: ExportSession,
+		path: PathBuf,
+		data: Vec<u8>,
+	},
+	DryRun {
+		session: ExportSession,
+		path: PathBuf,
+		size: u64,
+	},
+	/// An existing file being kept, see [OverwritePolicy::Skip]
+	Skipped,
+}
+impl OutputFile {
+	fn create(session: &ExportSession, path: &Path) -> Self {
+		let session = session.clone();
+		if session.is_dry_run() {
+			return OutputFile::DryRun {
+				session,
+				path: path.to_owned(),
+				size: 0,
+			};
+		}
+		if !session.can_write(path) {
+			return OutputFile::Skipped;
+		}
+		if session.0.pool.lock().unwrap().is_some() {
+			return OutputFile::Buffered {
+				session,
+				path: path.to_owned(),
+				data: Vec::new(),
+			};
+		}
+		match fs::File::create(path) {
+			Ok(file) => OutputFile::File {
+				session,
+				file: BufWriter::with_capacity(1 << 16, file),
+			},
+			Err(e) => panic!("failed to create file {}: {e}", path.display()),
+		}
+	}
+}
+impl Write for OutputFile {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match self {
+			OutputFile::File { file, .. } => file.write(buf),
+			OutputFile::Buffered { data, .. } => data.write(buf),
+			OutputFile::Skipped => Ok(buf.len()),
+			OutputFile::DryRun { size, .. } => {
+				*size += buf.len() as u64;
+				Ok(buf.len())
+			}
+		}
+	}
+	fn flush(&mut self) -> io::Result<()> {
+		match self {
+			OutputFile::File { file, .. } => file.flush(),
+			OutputFile::Buffered { .. } | OutputFile::DryRun { .. } | OutputFile::Skipped => Ok(()),
+		}
+	}
+}
+impl Drop for OutputFile {
+	fn drop(&mut self) {
+		match self {
+			OutputFile::File { session, file } => {
+				let bytes =
+					file.get_ref().metadata().map_or(0, |m| m.len()) + file.buffer().len() as u64;
+				session.0.totals.record(bytes as usize, Duration::ZERO);
+			}
+			OutputFile::Buffered {
+				session,
+				path,
+				data,
+			} => session.write_file(path, std::mem::take(data)),
+			OutputFile::DryRun {
+				session,
+				path,
+				size,
+			} => session.record_dry_run(path, Some(*size)),
+			OutputFile::Skipped => {}
+		}
+	}
+}
+
+/// A file that would have been written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunFile {
+	pub path: PathBuf,
+	/// `None` if the size isn't known without writing it (e.g. converted videos)
+	pub size: Option<u64>,
+}
+
+/// Everything that would have been written during a dry run
+pub struct DryRunReport {
+	pub files: Vec<DryRunFile>,
+}
+
+impl DryRunReport {
+	/// Total number of files and bytes in each directory (including subdirectories),
+	/// and in each file format
+	pub fn summary(&self) -> String {
+		#[derive(Default)]
+		struct Totals {
+			files: usize,
+			bytes: u64,
+		}
+		let mut dirs: BTreeMap<&Path, Totals> = BTreeMap::new();
+		let mut formats: BTreeMap<String, Totals> = BTreeMap::new();
+		for file in &self.files {
+			let bytes = file.size.unwrap_or(0);
+			for dir in file.path.ancestors().skip(1) {
+				if dir.as_os_str().is_empty() {
+					break;
+				}
+				let totals = dirs.entry(dir).or_default();
+				totals.files += 1;
+				totals.bytes += bytes;
+			}
+			let ext = file
+				.path
+				.extension()
+				.map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+				.unwrap_or_default();
+			let totals = formats.entry(ext).or_default();
+			totals.files += 1;
+			totals.bytes += bytes;
+		}
+
+		let total_bytes: u64 = self.files.iter().filter_map(|file| file.size).sum();
+		let mut summary = format!("{} files, {}\n", self.files.len(), format_size(total_bytes));
+		let num_unknown = self.files.iter().filter(|file| file.size.is_none()).count();
+		if num_unknown != 0 {
+			writeln!(summary, "(plus the size of {num_unknown} converted files)").unwrap();
+		}
+
+		summary.push('\n');
+		for (dir, totals) in &dirs {
+			let depth = dir.components().count() - 1;
+			let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy();
+			let name = name.trim_end_matches(['/', '\\']);
+			writeln!(
+				summary,
+				"{:indent$}{name}/\t{} files, {}",
+				"",
+				totals.files,
+				format_size(totals.bytes),
+				indent = depth * 2
+			)
+			.unwrap();
+		}
+
+		summary.push('\n');
+		for (ext, totals) in &formats {
+			writeln!(
+				summary,
+				"{ext:5}\t{} files, {}",
+				totals.files,
+				format_size(totals.bytes)
+			)
+			.unwrap();
+		}
+		summary
+	}
+}
+
+fn format_size(bytes: u64) -> String {
+	const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < UNITS.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+	if unit == 0 {
+		format!("{bytes} B")
+	} else {
+		format!("{size:.1} {}", UNITS[unit])
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -202,7 +1191,9 @@ mod tests {
 			"output test directory already exists before starting test"
 		);
 
-		let mut writer = OutputWriter::new("assets/test_no_dir/input_file.txt", false);
+		let session = ExportSession::new("output");
+		session.begin_dry_run();
+		let mut writer = session.writer().asset_dir("test_no_dir/input_file.txt");
 		assert_eq!(
 			writer.path,
 			Path::new("output/test_no_dir/input_file.txt/_"),
@@ -231,4 +1222,263 @@ mod tests {
 			"should not have created a directory"
 		);
 	}
+
+	#[test]
+	fn test_export_root() {
+		let dir = Path::new("output/test_export_root");
+		let _ = fs::remove_dir_all(dir);
+		let session = ExportSession::new(dir);
+		crate::SoundIndex::begin(&session);
+		let mut sounds = session
+			.writer()
+			.asset_dir("TRAVERSE/LEVEL3")
+			.push_dir("Sounds");
+		let data = crate::test_support::wav(1, 11025, 8, 10);
+		let wav = crate::data_formats::Wav::try_parse(&mut crate::Reader::new(&data)).unwrap();
+		wav.save_as("BEEP", &mut sounds);
+		session.finish_writes();
+		crate::SoundIndex::finish(&session);
+
+		// everything, including the shared files, goes in the root
+		assert!(dir.join("Shared/Sounds/BEEP.wav").exists());
+		assert!(dir.join("TRAVERSE/LEVEL3/Sounds/BEEP.wav").exists());
+		assert!(!Path::new("output/Shared/Sounds/BEEP.wav").exists());
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn test_sanitize_name() {
+		assert!(matches!(sanitize_name("GUNT_10"), Cow::Borrowed(_)));
+		assert_eq!(sanitize_name("Sounds/BEEP"), "Sounds/BEEP");
+		assert_eq!(sanitize_name("A:B?*"), "A_B__");
+		assert_eq!(sanitize_name("NAME. "), "NAME_");
+		assert_eq!(sanitize_name("con"), "_con");
+		assert_eq!(sanitize_name("Meshes/COM1.x"), "Meshes/_COM1.x");
+		assert_eq!(sanitize_name("COM10"), "COM10");
+		assert_eq!(sanitize_name(""), "_");
+	}
+
+	#[test]
+	fn test_normalise_name() {
+		assert!(matches!(normalise_name("gunt_10"), Cow::Borrowed(_)));
+		assert_eq!(
+			normalise_name("Textures/GUNT 10.png"),
+			"textures/gunt_10.png"
+		);
+	}
+
+	#[test]
+	fn test_case_collisions() {
+		let session = ExportSession::new("output");
+		session.begin_dry_run();
+		let mut writer = session.writer().asset_dir("test_case_collisions");
+		let dir = Path::new("output/test_case_collisions");
+		assert_eq!(writer.set_output_path("GUNT", "png"), dir.join("GUNT.png"));
+		assert_eq!(
+			writer.set_output_path("gunt", "png"),
+			dir.join("gunt_2.png")
+		);
+		// writing the same file again is fine
+		assert_eq!(writer.set_output_path("GUNT", "png"), dir.join("GUNT.png"));
+		assert_eq!(
+			writer.set_output_path("gunt", "png"),
+			dir.join("gunt_2.png")
+		);
+		assert_eq!(writer.set_output_path("A|B", "png"), dir.join("A_B.png"));
+
+		let report = session.rename_report();
+		let line = |from: &str, to: &str| {
+			format!(
+				"{} -> {}\n",
+				dir.join(from).display(),
+				dir.join(to).display()
+			)
+		};
+		assert_eq!(
+			report.matches(&line("gunt.png", "gunt_2.png")).count(),
+			1,
+			"{report}"
+		);
+		assert!(report.contains(&line("A|B.png", "A_B.png")), "{report}");
+
+		// other exports have their own names
+		let other = ExportSession::new("output");
+		other.begin_dry_run();
+		let mut writer = other.writer().asset_dir("test_case_collisions");
+		assert_eq!(writer.set_output_path("gunt", "png"), dir.join("gunt.png"));
+		assert!(other.rename_report().is_empty());
+	}
+
+	#[test]
+	fn test_overwrite_policy() {
+		let dir = Path::new("output/test_overwrite_policy");
+		let _ = fs::remove_dir_all(dir);
+		fs::create_dir_all(dir).unwrap();
+		let path = dir.join("GUNT.png");
+		assert!(OverwritePolicy::Error.apply(&path));
+
+		fs::write(&path, "edited").unwrap();
+		assert!(!OverwritePolicy::Skip.apply(&path));
+		assert!(OverwritePolicy::Backup.apply(&path));
+		assert!(!path.exists());
+		assert_eq!(fs::read(dir.join("GUNT.png.bak")).unwrap(), b"edited");
+
+		fs::write(&path, "edited again").unwrap();
+		assert!(OverwritePolicy::Backup.apply(&path));
+		assert_eq!(
+			fs::read(dir.join("GUNT.png.bak2")).unwrap(),
+			b"edited again"
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn test_png_colour() {
+		let dir = Path::new("output/test_png_colour");
+		let _ = fs::remove_dir_all(dir);
+		let palette = [0, 0, 0, 10, 20, 30];
+		let mut settings = ExportSettings::default();
+		for colour in [PngColour::Indexed, PngColour::Rgba] {
+			settings.png_colour = colour;
+			let mut output = OutputWriter::new_in_dir(dir).with_export_settings(settings);
+			output.write_png(&format!("{colour:?}"), 2, 1, [0, 1], Some(&palette));
+		}
+		let read = |name: &str| {
+			let decoder =
+				png::Decoder::new(io::BufReader::new(fs::File::open(dir.join(name)).unwrap()));
+			let mut reader = decoder.read_info().unwrap();
+			let mut pixels = vec![0; reader.output_buffer_size()];
+			reader.next_frame(&mut pixels).unwrap();
+			(reader.info().color_type, pixels)
+		};
+		assert_eq!(read("Indexed.png"), (png::ColorType::Indexed, vec![0, 1]));
+		assert_eq!(
+			read("Rgba.png"),
+			(png::ColorType::Rgba, vec![0, 0, 0, 0, 10, 20, 30, 255])
+		);
+		fs::remove_dir_all(dir).unwrap();
+
+		let rgba_palette = [1, 2, 3, 4, 5, 6, 128, 255];
+		assert_eq!(
+			palette_to_rgba(&[0, 1], &rgba_palette, true),
+			[[1, 2, 3, 128], [4, 5, 6, 255]]
+		);
+	}
+
+	#[test]
+	fn test_png_palette_name() {
+		let output = OutputWriter::new_in_dir("output/test_palette_name").asset_dir("LEVEL3");
+		let palette_name = |output: &OutputWriter, palette: Option<&[u8]>| {
+			output
+				.png_text("TEX", palette)
+				.into_iter()
+				.find(|(key, _)| *key == "Palette")
+				.map(|(_, value)| value)
+		};
+		let palette = [0; 768];
+		let arena_output = output.with_palette_name("ARENA1");
+		assert_eq!(
+			palette_name(&arena_output, Some(&palette)).as_deref(),
+			Some("ARENA1")
+		);
+		// the original writer and pushed folders don't keep it
+		assert_eq!(palette_name(&output, Some(&palette)), None);
+		assert_eq!(
+			palette_name(&arena_output.push_dir("Textures"), Some(&palette)),
+			None
+		);
+		// and images saved without a palette don't have one
+		assert_eq!(palette_name(&arena_output, None), None);
+		fs::remove_dir_all("output/test_palette_name").unwrap();
+	}
+
+	#[test]
+	fn test_layout() {
+		let session = ExportSession::new("output");
+		session.begin_dry_run();
+		let mut writer = session.writer().asset_dir("TRAVERSE/LEVEL3");
+		writer.asset_type = vec!["Meshes".to_owned(), "Textures".to_owned()];
+		let resolve = |writer: &OutputWriter, template: &str| {
+			OutputLayout::parse(template).resolve(writer, "GUNT")
+		};
+
+		assert_eq!(
+			resolve(&writer, "{game_mode}/{level}/{asset_type}/{name}"),
+			Path::new("output/TRAVERSE/LEVEL3/Meshes/Textures/GUNT")
+		);
+		assert_eq!(
+			resolve(&writer, "{asset_type}/{game_mode}_{level}_{name}"),
+			Path::new("output/Meshes/Textures/TRAVERSE_LEVEL3_GUNT")
+		);
+		assert_eq!(resolve(&writer, "{name}"), Path::new("output/GUNT"));
+
+		// empty segments are skipped
+		writer.level.clear();
+		writer.asset_type.clear();
+		assert_eq!(
+			resolve(&writer, "{game_mode}/{level}/{asset_type}/{name}"),
+			Path::new("output/TRAVERSE/GUNT")
+		);
+	}
+
+	#[test]
+	#[should_panic = "unknown placeholder {arena}"]
+	fn test_layout_unknown_placeholder() {
+		OutputLayout::parse("{arena}/{name}");
+	}
+
+	#[test]
+	fn test_write_pool() {
+		let dir = std::env::temp_dir().join(format!("mdk_parse_write_pool_{}", std::process::id()));
+		let dir = dir.as_path();
+		fs::create_dir_all(dir).unwrap();
+		let totals = Arc::default();
+		// more files than fit in the queue
+		let pool = WritePool::new(1, Arc::clone(&totals));
+		for i in 0..WritePool::QUEUED_PER_THREAD * 3 {
+			pool.submit(dir.join(format!("{i}.txt")), i.to_string().into_bytes());
+		}
+		pool.finish();
+		assert_eq!(
+			totals.files.load(Ordering::Relaxed),
+			WritePool::QUEUED_PER_THREAD as u64 * 3
+		);
+		for i in 0..WritePool::QUEUED_PER_THREAD * 3 {
+			assert_eq!(
+				fs::read_to_string(dir.join(format!("{i}.txt"))).unwrap(),
+				i.to_string()
+			);
+		}
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn test_dry_run_summary() {
+		let file = |path: &str, size| DryRunFile {
+			path: path.into(),
+			size,
+		};
+		let report = DryRunReport {
+			files: vec![
+				file("output/MISC/a.png", Some(1000)),
+				file("output/MISC/b.PNG", Some(2000)),
+				file("output/MISC/Videos/c.mp4", None),
+				file("output/STREAM/d.wav", Some(3 * 1024 * 1024)),
+			],
+		};
+		assert_eq!(
+			report.summary(),
+			"4 files, 3.0 MB\n\
+			(plus the size of 1 converted files)\n\
+			\n\
+			output/\t4 files, 3.0 MB\n  \
+			MISC/\t3 files, 2.9 KB\n    \
+			Videos/\t1 files, 0 B\n  \
+			STREAM/\t1 files, 3.0 MB\n\
+			\n\
+			mp4  \t1 files, 0 B\n\
+			png  \t2 files, 2.9 KB\n\
+			wav  \t1 files, 3.0 MB\n"
+		);
+	}
 }
@@ -0,0 +1,345 @@
+//! Renders meshes in software, for a quick look at them straight from the game files
+//! without exporting them and opening another program.
+//!
+//! Like the game it draws palette indices, so a preview is an animated PNG of the mesh turning around,
+//! lit by picking the nearest palette colours. Glass and shiny surfaces aren't drawn.
+use std::f32::consts::TAU;
+
+use crate::data_formats::mesh::MeshType;
+use crate::data_formats::{Mesh, Pen, Texture, TextureHolder, TextureResult};
+use crate::{OutputWriter, Vec2, Vec3};
+
+/// How many levels of light each colour is drawn with
+const SHADES: usize = 4;
+/// The darkest level of light
+const MIN_LIGHT: f32 = 0.4;
+/// Drawn behind the mesh, and saved as transparent
+const BACKGROUND: u8 = 0;
+
+/// How to draw a preview
+#[derive(Debug, Clone, Copy)]
+pub struct Preview {
+	pub width: u16,
+	pub height: u16,
+	/// Frames for one full turn
+	pub frames: usize,
+	pub fps: u16,
+	/// How far the camera looks down at the mesh, in degrees
+	pub pitch: f32,
+}
+
+impl Default for Preview {
+	fn default() -> Self {
+		Self {
+			width: 256,
+			height: 256,
+			frames: 36,
+			fps: 12,
+			pitch: 20.0,
+		}
+	}
+}
+
+/// A triangle ready to draw, in world space
+struct Tri<'t> {
+	points: [Vec3; 3],
+	uvs: [Vec2; 3],
+	fill: Fill<'t>,
+}
+
+enum Fill<'t> {
+	Colour(u8),
+	Texture {
+		texture: &'t Texture<'t>,
+		masked: bool,
+	},
+}
+
+impl Preview {
+	/// Renders `mesh` turning around once, one frame of palette indices at a time
+	pub fn render<'a>(
+		&self, mesh: &Mesh<'a>, textures: &mut impl TextureHolder<'a>,
+	) -> Vec<Texture<'static>> {
+		let materials: Vec<TextureResult> = mesh
+			.materials
+			.iter()
+			.map(|name| textures.lookup(name))
+			.collect();
+		let shades = shade_table(textures.get_palette());
+		let tris = world_tris(mesh, &materials);
+
+		let points: Vec<Vec3> = tris.iter().flat_map(|tri| tri.points).collect();
+		let [min, max] = Vec3::calculate_bbox(&points);
+		let centre = (min + max) * 0.5;
+		let radius = points
+			.iter()
+			.map(|&p| {
+				let d = p - centre;
+				(d.x * d.x + d.y * d.y + d.z * d.z).sqrt()
+			})
+			.fold(0.0, f32::max)
+			.max(f32::EPSILON);
+
+		(0..self.frames)
+			.map(|frame| {
+				let yaw = TAU * frame as f32 / self.frames as f32;
+				self.render_frame(&tris, &shades, centre, radius, yaw)
+			})
+			.collect()
+	}
+
+	/// Renders `mesh` and saves it as an animated PNG
+	pub fn save_as<'a>(
+		&self, mesh: &Mesh<'a>, name: &str, textures: &mut impl TextureHolder<'a>,
+		output: &mut OutputWriter,
+	) {
+		let frames = self.render(mesh, textures);
+		Texture::save_animated(
+			&frames,
+			name,
+			self.fps,
+			output,
+			Some(textures.get_palette()),
+		);
+	}
+
+	fn render_frame(
+		&self, tris: &[Tri], shades: &[[u8; SHADES]], centre: Vec3, radius: f32, yaw: f32,
+	) -> Texture<'static> {
+		let (width, height) = (self.width as usize, self.height as usize);
+		let mut pixels = vec![BACKGROUND; width * height];
+		let mut depth = vec![f32::NEG_INFINITY; width * height];
+		let scale = 0.45 * width.min(height) as f32 / radius;
+		let (sin_yaw, cos_yaw) = yaw.sin_cos();
+		let (sin_pitch, cos_pitch) = self.pitch.to_radians().sin_cos();
+		// to screen space, with z getting bigger towards the camera
+		let project = |p: Vec3| {
+			let p = p - centre;
+			let x = p.x * cos_yaw + p.z * sin_yaw;
+			let z = p.z * cos_yaw - p.x * sin_yaw;
+			let y = p.y * cos_pitch - z * sin_pitch;
+			let z = z * cos_pitch + p.y * sin_pitch;
+			Vec3::new(
+				width as f32 * 0.5 + x * scale,
+				height as f32 * 0.5 - y * scale,
+				z,
+			)
+		};
+		let light = normalise(Vec3::new(-0.4, -0.6, 0.7));
+
+		for tri in tris {
+			let [a, b, c] = tri.points.map(project);
+			let area = edge(a, b, c);
+			if area.abs() < f32::EPSILON {
+				continue;
+			}
+			let normal = normalise(cross(b - a, c - a));
+			let brightness = dot(normal, light).abs();
+			let shade = ((brightness * SHADES as f32) as usize).min(SHADES - 1);
+
+			let x0 = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+			let y0 = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+			let x1 = (a.x.max(b.x).max(c.x).ceil() as usize).min(width);
+			let y1 = (a.y.max(b.y).max(c.y).ceil() as usize).min(height);
+			for y in y0..y1 {
+				for x in x0..x1 {
+					let p = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+					let wa = edge(b, c, p) / area;
+					let wb = edge(c, a, p) / area;
+					let wc = 1.0 - wa - wb;
+					if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+						continue;
+					}
+					let index = y * width + x;
+					let z = a.z * wa + b.z * wb + c.z * wc;
+					if z <= depth[index] {
+						continue;
+					}
+					let colour = match tri.fill {
+						Fill::Colour(colour) => colour,
+						Fill::Texture { texture, masked } => {
+							let [ua, ub, uc] = tri.uvs;
+							let u = ua[0] * wa + ub[0] * wb + uc[0] * wc;
+							let v = ua[1] * wa + ub[1] * wb + uc[1] * wc;
+							let colour = sample(texture, u, v);
+							if masked && colour == 0 {
+								continue;
+							}
+							colour
+						}
+					};
+					depth[index] = z;
+					pixels[index] = shades[colour as usize][shade];
+				}
+			}
+		}
+		Texture::new(self.width, self.height, pixels)
+	}
+}
+
+/// Every visible triangle of the mesh with how it's filled
+fn world_tris<'t>(mesh: &Mesh, materials: &'t [TextureResult]) -> Vec<Tri<'t>> {
+	let geos = match &mesh.mesh_data {
+		MeshType::Single(geo) => vec![(geo, Vec3::default())],
+		MeshType::Multimesh { submeshes, .. } => submeshes
+			.iter()
+			.map(|sub| (&sub.mesh_data, sub.origin))
+			.collect(),
+	};
+	let mut result = Vec::new();
+	for (geo, origin) in geos {
+		for tri in &geo.tris {
+			if tri.is_hidden() {
+				continue;
+			}
+			let fill = match tri.material {
+				Pen::Colour(colour) => Fill::Colour(colour),
+				Pen::Texture(index) => match materials.get(index as usize) {
+					Some(TextureResult::Pen(Pen::Colour(colour))) => Fill::Colour(*colour),
+					Some(TextureResult::SaveEmbed {
+						texture, masked, ..
+					}) => Fill::Texture {
+						texture,
+						masked: *masked,
+					},
+					// missing textures are white in-game
+					Some(TextureResult::None | TextureResult::SaveRef { .. }) | None => {
+						Fill::Colour(0xFF)
+					}
+					Some(TextureResult::Pen(_)) => continue,
+				},
+				Pen::Translucent(_) | Pen::Shiny(_) | Pen::Unknown(_) | Pen::UnknownMti(_) => {
+					continue;
+				}
+			};
+			result.push(Tri {
+				points: tri.indices.map(|i| geo.verts[i as usize] + origin),
+				uvs: tri.uvs,
+				fill,
+			});
+		}
+	}
+	result
+}
+
+/// Each palette colour at every level of light, as the nearest palette colour
+fn shade_table(palette: &[u8]) -> Vec<[u8; SHADES]> {
+	let (palette, _) = palette.as_chunks::<3>();
+	// any colours missing from the palette are black, but never picked
+	let colours: Vec<[u8; 3]> = palette
+		.iter()
+		.copied()
+		.chain(std::iter::repeat([0; 3]))
+		.take(256)
+		.collect();
+	colours
+		.iter()
+		.map(|colour| {
+			std::array::from_fn(|shade| {
+				let light = MIN_LIGHT + (1.0 - MIN_LIGHT) * shade as f32 / (SHADES - 1) as f32;
+				let target = colour.map(|c| c as f32 * light);
+				// skipping the background colour
+				(1..palette.len().max(2))
+					.min_by_key(|&index| {
+						let [r, g, b] = colours[index].map(|c| c as f32);
+						let distance = (r - target[0]).powi(2)
+							+ (g - target[1]).powi(2)
+							+ (b - target[2]).powi(2);
+						distance as u32
+					})
+					.unwrap() as u8
+			})
+		})
+		.collect()
+}
+
+/// The texel at texture coordinates `u` and `v` (in pixels), repeating the texture
+fn sample(texture: &Texture, u: f32, v: f32) -> u8 {
+	let x = (u.floor() as i64).rem_euclid(texture.width as i64) as usize;
+	let y = (v.floor() as i64).rem_euclid(texture.height as i64) as usize;
+	texture.pixels[y * texture.width as usize + x]
+}
+
+/// Twice the signed area of the triangle `a b p` on screen
+fn edge(a: Vec3, b: Vec3, p: Vec3) -> f32 {
+	(b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+	Vec3::new(
+		a.y * b.z - a.z * b.y,
+		a.z * b.x - a.x * b.z,
+		a.x * b.y - a.y * b.x,
+	)
+}
+fn dot(a: Vec3, b: Vec3) -> f32 {
+	a.x * b.x + a.y * b.y + a.z * b.z
+}
+fn normalise(v: Vec3) -> Vec3 {
+	let length = dot(v, v).sqrt();
+	if length == 0.0 { v } else { v * length.recip() }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data_formats::mesh::{ColourMap, MeshGeo, MeshTri};
+	use std::collections::BTreeMap;
+
+	struct Palette(Vec<u8>);
+	impl<'a> TextureHolder<'a> for Palette {
+		fn lookup(&mut self, _name: &str) -> TextureResult<'a> {
+			TextureResult::None
+		}
+		fn get_used_colours(&self, _name: &str, _colours: &mut ColourMap) {}
+		fn get_palette(&self) -> &[u8] {
+			&self.0
+		}
+		fn get_translucent_colours(&self) -> [[u8; 4]; 4] {
+			[[0; 4]; 4]
+		}
+	}
+
+	#[test]
+	fn test_preview() {
+		// a red triangle facing the camera
+		let mesh = Mesh {
+			materials: Vec::new(),
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: vec![
+					Vec3::new(-1.0, -1.0, 0.0),
+					Vec3::new(1.0, -1.0, 0.0),
+					Vec3::new(0.0, 1.0, 0.0),
+				],
+				tris: vec![MeshTri {
+					indices: [0, 1, 2],
+					material: Pen::Colour(2),
+					uvs: Default::default(),
+					flags: 0,
+				}],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+		let mut palette = Palette(vec![0; 256 * 3]);
+		for (index, shade) in [(1, 100), (2, 255)] {
+			palette.0[index * 3] = shade;
+		}
+		let preview = Preview {
+			width: 16,
+			height: 16,
+			frames: 2,
+			pitch: 0.0,
+			..Default::default()
+		};
+		let frames = preview.render(&mesh, &mut palette);
+		assert_eq!(frames.len(), 2);
+		let pixels = &frames[0].pixels;
+		assert_eq!(pixels[0], BACKGROUND);
+		// lit enough to keep its colour
+		assert_eq!(pixels[10 * 16 + 8], 2);
+		// half a turn later it's seen from behind
+		assert_eq!(frames[1].pixels[10 * 16 + 8], 2);
+	}
+}
@@ -1,12 +1,45 @@
 use std::io;
 use std::io::Read;
 
+use crate::coverage;
 use crate::vectors::Vec3;
 
+/// Byte order of multi-byte values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+	/// PC release
+	#[default]
+	Little,
+	/// Byte-swapped console release
+	Big,
+}
+
+impl Endian {
+	/// Guesses the byte order of a game file from its leading filesize field.
+	///
+	/// Every container format starts with a u32 size that fits inside the file,
+	/// which is almost never true when read with the wrong byte order.
+	pub fn detect(buf: &[u8]) -> Endian {
+		let Some(&size_bytes) = buf.first_chunk::<4>() else {
+			return Endian::Little;
+		};
+		let fits = |size: u32| (size as usize) <= buf.len();
+		if !fits(u32::from_le_bytes(size_bytes)) && fits(u32::from_be_bytes(size_bytes)) {
+			Endian::Big
+		} else {
+			Endian::Little
+		}
+	}
+}
+
 /// Helper struct to parse values out of a byte stream.
 #[derive(Clone)]
 pub struct Reader<'buf> {
 	reader: io::Cursor<&'buf [u8]>,
+	endian: Endian,
+	/// See [Reader::set_lenient]
+	lenient: bool,
+	overran: bool,
 }
 
 #[allow(dead_code)]
@@ -14,7 +47,47 @@ impl<'buf> Reader<'buf> {
 	pub fn new(buf: &'buf [u8]) -> Reader<'buf> {
 		Reader {
 			reader: io::Cursor::new(buf),
+			endian: Endian::Little,
+			lenient: false,
+			overran: false,
+		}
+	}
+	/// Creates a reader using the byte order detected from the file header
+	pub fn new_detect_endian(buf: &'buf [u8]) -> Reader<'buf> {
+		Self::new(buf).with_endian(Endian::detect(buf))
+	}
+
+	#[must_use]
+	pub fn with_endian(mut self, endian: Endian) -> Self {
+		self.endian = endian;
+		self
+	}
+	pub fn set_endian(&mut self, endian: Endian) {
+		self.endian = endian;
+	}
+	pub fn endian(&self) -> Endian {
+		self.endian
+	}
+
+	/// Makes reads past the end return zeroes (or empty strings) instead of panicking,
+	/// for data that might be cut short. See [Reader::overran]
+	pub fn set_lenient(&mut self, lenient: bool) {
+		self.lenient = lenient;
+	}
+	/// Whether a lenient read went past the end (since [Reader::clear_overrun])
+	pub fn overran(&self) -> bool {
+		self.overran
+	}
+	pub fn clear_overrun(&mut self) {
+		self.overran = false;
+	}
+	/// Handles a lenient read past the end, returning whether it was lenient
+	fn overrun(&mut self) -> bool {
+		if self.lenient {
+			self.overran = true;
+			self.set_position(self.position().max(self.len()));
 		}
+		self.lenient
 	}
 
 	pub fn resize(&mut self, range: impl std::ops::RangeBounds<usize>) {
@@ -35,7 +108,7 @@ impl<'buf> Reader<'buf> {
 			std::ops::Bound::Excluded(&n) => n,
 			std::ops::Bound::Unbounded => self.len(),
 		};
-		Reader::new(&self.buf()[start..end])
+		Reader::new(&self.buf()[start..end]).with_endian(self.endian)
 	}
 	#[must_use]
 	pub fn resized_pos(&self, range: impl std::ops::RangeBounds<usize>, new_pos: usize) -> Self {
@@ -87,7 +160,7 @@ impl<'buf> Reader<'buf> {
 		self.buf().len()
 	}
 	pub fn remaining_len(&self) -> usize {
-		self.len() - self.position()
+		self.len().saturating_sub(self.position())
 	}
 	pub fn is_empty(&self) -> bool {
 		self.remaining_len() == 0
@@ -106,14 +179,22 @@ impl<'buf> Reader<'buf> {
 	pub fn try_get_unvalidated<T: Readable>(&mut self) -> Option<T> {
 		let mut buffer = T::new_buffer();
 		let buffer_bytes = T::buffer_as_mut(&mut buffer);
+		let start = self.position();
 		self.reader.read_exact(buffer_bytes).ok()?;
-		let result = T::convert_little(buffer);
+		coverage::mark(&self.buf()[start..self.position()]);
+		let result = match self.endian {
+			Endian::Little => T::convert_little(buffer),
+			Endian::Big => T::convert_big(buffer),
+		};
 		Some(result)
 	}
 	pub fn get<T: Readable + std::fmt::Debug>(&mut self) -> T {
 		let start = self.position();
 		let end = start + std::mem::size_of::<T::Buffer>();
 		let Some(result) = self.try_get_unvalidated::<T>() else {
+			if self.overrun() {
+				return T::convert_little(T::new_buffer());
+			}
 			panic!(
 				"failed to read bytes {start}..{end} (buffer size {})",
 				self.len()
@@ -128,6 +209,9 @@ impl<'buf> Reader<'buf> {
 		let start = self.position();
 		let end = start + std::mem::size_of::<T::Buffer>();
 		let Some(result) = self.try_get_unvalidated::<T>() else {
+			if self.overrun() {
+				return T::convert_little(T::new_buffer());
+			}
 			panic!(
 				"failed to read bytes {start}..{end} (buffer size {})",
 				self.len()
@@ -147,6 +231,9 @@ impl<'buf> Reader<'buf> {
 		}
 	}
 	pub fn skip(&mut self, len: usize) {
+		if self.remaining_len() < len && self.overrun() {
+			return;
+		}
 		let start_pos = self.position();
 		let ok = self.try_skip(len).is_some();
 		assert!(
@@ -168,6 +255,7 @@ impl<'buf> Reader<'buf> {
 		if self.buf()[pos..next_position].iter().any(|b| *b != 0) {
 			return None;
 		}
+		coverage::mark(&self.buf()[pos..next_position]);
 
 		self.set_position(next_position);
 		Some(())
@@ -177,12 +265,17 @@ impl<'buf> Reader<'buf> {
 	}
 
 	pub fn slice(&mut self, size: usize) -> &'buf [u8] {
+		if self.remaining_len() < size && self.overrun() {
+			return &[];
+		}
 		self.try_slice(size).expect("slice out of range")
 	}
 	pub fn try_slice(&mut self, size: usize) -> Option<&'buf [u8]> {
 		let pos = self.position();
 		self.try_skip(size)?;
-		Some(&self.buf()[pos..pos + size])
+		let result = &self.buf()[pos..pos + size];
+		coverage::mark(result);
+		Some(result)
 	}
 	pub fn remaining_slice(&mut self) -> &'buf [u8] {
 		self.slice(self.remaining_len())
@@ -190,6 +283,10 @@ impl<'buf> Reader<'buf> {
 
 	/// Reads a length-prefixed string
 	pub fn pascal_str(&mut self) -> &'buf str {
+		if self.lenient {
+			let length = self.u8();
+			return self.str(length as usize);
+		}
 		self.try_pascal_str().expect("invalid string")
 	}
 	pub fn try_pascal_str(&mut self) -> Option<&'buf str> {
@@ -199,6 +296,9 @@ impl<'buf> Reader<'buf> {
 
 	/// Reads a string from a fixed-size span of bytes
 	pub fn str(&mut self, size: usize) -> &'buf str {
+		if self.remaining_len() < size && self.overrun() {
+			return "";
+		}
 		self.try_str(size).expect("invalid string")
 	}
 	pub fn try_str(&mut self, size: usize) -> Option<&'buf str> {
@@ -437,3 +537,36 @@ impl<T: Readable, const N: usize> Readable for [T; N] {
 		self.iter().all(T::validate)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_endian() {
+		let mut data = vec![0; 16];
+		data[..4].copy_from_slice(&12u32.to_le_bytes());
+		assert_eq!(Endian::detect(&data), Endian::Little);
+		assert_eq!(Reader::new_detect_endian(&data).u32(), 12);
+
+		data[..4].copy_from_slice(&12u32.to_be_bytes());
+		assert_eq!(Endian::detect(&data), Endian::Big);
+		let mut reader = Reader::new_detect_endian(&data);
+		assert_eq!(reader.u32(), 12);
+		assert_eq!(reader.rebased().endian(), Endian::Big, "endian is kept");
+	}
+
+	#[test]
+	fn test_lenient() {
+		let data = [3, b'A', b'B', 0x34, 0x12];
+		let mut reader = Reader::new(&data);
+		reader.set_lenient(true);
+		assert_eq!(reader.pascal_str(), "AB4");
+		assert!(!reader.overran());
+		assert_eq!(reader.u16(), 0);
+		assert!(reader.overran());
+		reader.clear_overrun();
+		assert_eq!(reader.pascal_str(), "");
+		assert!(reader.overran());
+	}
+}
@@ -0,0 +1,419 @@
+//! Moves textures that several levels save identically into their game mode's `Shared` folder,
+//! pointing the glTFs that used the level copies at the shared one instead.
+//!
+//! While enabled for an [ExportSession], every texture and glTF it saves is recorded,
+//! then [SharedAssets::finish] moves the textures and rewrites the glTFs once all the writes are finished.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Component, Path, PathBuf};
+
+use crate::{ExportSession, OutputWriter};
+
+/// Which textures are promoted, loaded from a json object like
+/// `{"min_levels": 3, "asset_types": ["Meshes/Textures", "Textures"], "keep": ["SKY"]}`
+/// where anything left out keeps its default
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SharedRules {
+	/// How many levels of a game mode have to save a texture identically for it to be promoted
+	pub min_levels: usize,
+	/// The folders a texture can be saved in to be promoted (e.g. `Meshes/Textures`), or empty for any
+	pub asset_types: Vec<String>,
+	/// Names of textures that always stay in their levels
+	pub keep: Vec<String>,
+}
+
+impl Default for SharedRules {
+	fn default() -> Self {
+		Self {
+			min_levels: 2,
+			asset_types: vec!["Meshes/Textures".to_owned()],
+			keep: Vec::new(),
+		}
+	}
+}
+
+impl SharedRules {
+	pub fn load(path: impl AsRef<Path>) -> Self {
+		let path = path.as_ref();
+		let data = fs::read(path).unwrap_or_else(|e| {
+			panic!("failed to read shared asset rules {}: {e}", path.display())
+		});
+		let result: Self = serde_json::from_slice(&data).unwrap_or_else(|e| {
+			panic!("failed to parse shared asset rules {}: {e}", path.display())
+		});
+		assert!(result.min_levels >= 2, "min_levels must be at least 2");
+		result
+	}
+
+	fn applies_to(&self, asset_type: &str, name: &str) -> bool {
+		(self.asset_types.is_empty()
+			|| self
+				.asset_types
+				.iter()
+				.any(|dir| dir.trim_matches('/').eq_ignore_ascii_case(asset_type)))
+			&& !self.keep.iter().any(|keep| keep.eq_ignore_ascii_case(name))
+	}
+}
+
+/// Identifies a texture by where it's saved (apart from the level) and its contents
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct TextureKey {
+	game_mode: String,
+	asset_type: String,
+	/// Lowercase file name
+	name: String,
+	width: u32,
+	height: u32,
+	hash: u64,
+}
+
+/// Where a texture was saved
+struct TextureCopy {
+	level: String,
+	path: PathBuf,
+}
+
+/// Every texture and glTF saved so far
+pub struct SharedAssets {
+	rules: SharedRules,
+	textures: BTreeMap<TextureKey, Vec<TextureCopy>>,
+	documents: Vec<PathBuf>,
+}
+
+/// Totals from [SharedAssets::finish]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedAssetsStats {
+	pub textures: usize,
+	/// Level copies replaced by the shared textures
+	pub copies: usize,
+	/// glTFs pointed at the shared textures
+	pub documents: usize,
+}
+impl std::fmt::Display for SharedAssetsStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"Promoted {} shared textures from {} level copies, updating {} glTFs",
+			self.textures, self.copies, self.documents
+		)
+	}
+}
+
+/// A texture being promoted
+struct Promotion<'a> {
+	shared: PathBuf,
+	copies: &'a [TextureCopy],
+}
+
+impl SharedAssets {
+	fn new(rules: SharedRules) -> Self {
+		Self {
+			rules,
+			textures: BTreeMap::new(),
+			documents: Vec::new(),
+		}
+	}
+
+	/// Starts recording every texture and glTF saved, for every writer in the export
+	pub fn begin(session: &ExportSession, rules: SharedRules) {
+		let mut shared = session.0.shared_assets.lock().unwrap();
+		assert!(shared.is_none(), "shared asset promotion already started");
+		*shared = Some(SharedAssets::new(rules));
+	}
+
+	/// Stops recording, moving textures saved by enough levels to their game mode's `Shared` folder,
+	/// updating the glTFs that used them, and saving a list of what was moved for each game mode.
+	///
+	/// Call after [ExportSession::finish_writes] so the level copies exist.
+	/// Nothing is moved during a dry run.
+	pub fn finish(session: &ExportSession) -> SharedAssetsStats {
+		let shared = session
+			.0
+			.shared_assets
+			.lock()
+			.unwrap()
+			.take()
+			.expect("shared asset promotion not started");
+		let root = session.writer();
+		let promotions = shared.promotions(|game_mode, asset_type| {
+			let output = root.asset_dir(format!("{game_mode}/Shared"));
+			if asset_type.is_empty() {
+				output
+			} else {
+				output.push_dir(asset_type)
+			}
+		});
+		let documents = if session.is_dry_run() {
+			0
+		} else {
+			shared.apply(&promotions)
+		};
+
+		let mut reports: BTreeMap<&str, String> = BTreeMap::new();
+		for (key, promotion) in &promotions {
+			let report = reports.entry(&key.game_mode).or_default();
+			writeln!(report, "{}", promotion.shared.display()).unwrap();
+			for copy in promotion.copies {
+				writeln!(report, "\t{}", copy.path.display()).unwrap();
+			}
+		}
+		for (game_mode, report) in reports {
+			root.asset_dir(format!("{game_mode}/Shared"))
+				.write("Shared Textures", "txt", report);
+		}
+
+		SharedAssetsStats {
+			textures: promotions.len(),
+			copies: promotions.iter().map(|(_, p)| p.copies.len()).sum(),
+			documents,
+		}
+	}
+
+	fn add_texture(&mut self, output: &OutputWriter, path: &Path, key: (u32, u32, u64)) {
+		// the game modes' own shared folders aren't levels
+		if output.level.is_empty() || output.level[0].eq_ignore_ascii_case("Shared") {
+			return;
+		}
+		let asset_type = output.asset_type.join("/");
+		let Some(name) = path.file_stem().map(|name| name.to_string_lossy()) else {
+			return;
+		};
+		if !self.rules.applies_to(&asset_type, &name) {
+			return;
+		}
+		let key = TextureKey {
+			game_mode: output.game_mode.clone(),
+			asset_type,
+			name: name.to_ascii_lowercase(),
+			width: key.0,
+			height: key.1,
+			hash: key.2,
+		};
+		let copies = self.textures.entry(key).or_default();
+		if !copies.iter().any(|copy| copy.path == path) {
+			copies.push(TextureCopy {
+				level: output.level.join("/"),
+				path: path.to_owned(),
+			});
+		}
+	}
+
+	/// Every texture saved by enough levels, with where to move it made by `shared_output`
+	fn promotions(
+		&self, mut shared_output: impl FnMut(&str, &str) -> OutputWriter,
+	) -> Vec<(&TextureKey, Promotion<'_>)> {
+		let copy_paths: HashSet<&Path> = self
+			.textures
+			.values()
+			.flatten()
+			.map(|copy| copy.path.as_path())
+			.collect();
+		let mut names = HashSet::new();
+		let mut result = Vec::new();
+		for (key, copies) in &self.textures {
+			let levels: HashSet<&str> = copies.iter().map(|copy| copy.level.as_str()).collect();
+			if levels.len() < self.rules.min_levels {
+				continue;
+			}
+			// different textures can share a name
+			let name = copies[0].path.file_stem().unwrap().to_string_lossy();
+			let mut output = shared_output(&key.game_mode, &key.asset_type);
+			let mut shared_name = name.to_string();
+			let mut number = 1;
+			let shared = loop {
+				let path = output.set_output_path(&shared_name, "png").to_owned();
+				if !copy_paths.contains(path.as_path())
+					&& names.insert(path.to_string_lossy().to_ascii_lowercase())
+				{
+					break path;
+				}
+				number += 1;
+				shared_name = format!("{name}_{number}");
+			};
+			result.push((key, Promotion { shared, copies }));
+		}
+		result
+	}
+
+	/// Moves the promoted textures and updates the glTFs, returning how many glTFs changed
+	fn apply(&self, promotions: &[(&TextureKey, Promotion)]) -> usize {
+		let mut moved = HashMap::new();
+		for (_, promotion) in promotions {
+			let (first, rest) = promotion.copies.split_first().unwrap();
+			move_file(&first.path, &promotion.shared);
+			for copy in rest {
+				fs::remove_file(&copy.path)
+					.unwrap_or_else(|e| panic!("failed to remove {}: {e}", copy.path.display()));
+			}
+			for copy in promotion.copies {
+				moved.insert(normalise(&copy.path), promotion.shared.as_path());
+			}
+		}
+
+		let mut documents = HashSet::new();
+		let mut changed = 0;
+		for document in &self.documents {
+			if documents.insert(document) && rewrite_document(document, &moved) {
+				changed += 1;
+			}
+		}
+		changed
+	}
+}
+
+/// Records a saved texture to be promoted if shared assets are being promoted
+pub(crate) fn record_texture(
+	output: &OutputWriter, path: &Path, width: u32, height: u32, pixels: &[u8],
+	palette: Option<&[u8]>,
+) {
+	if let Some(shared) = output.session().0.shared_assets.lock().unwrap().as_mut() {
+		let mut hasher = DefaultHasher::new();
+		pixels.hash(&mut hasher);
+		palette.hash(&mut hasher);
+		shared.add_texture(output, path, (width, height, hasher.finish()));
+	}
+}
+
+/// Records a saved glTF to be updated if shared assets are being promoted
+pub(crate) fn record_document(session: &ExportSession, path: &Path) {
+	if let Some(shared) = session.0.shared_assets.lock().unwrap().as_mut() {
+		shared.documents.push(path.to_owned());
+	}
+}
+
+fn move_file(from: &Path, to: &Path) {
+	if from == to {
+		return;
+	}
+	if fs::rename(from, to).is_err() {
+		fs::copy(from, to).unwrap_or_else(|e| {
+			panic!("failed to copy {} to {}: {e}", from.display(), to.display())
+		});
+		fs::remove_file(from).unwrap();
+	}
+}
+
+/// Points any images in a glTF that were moved at their new location, returning whether it changed
+fn rewrite_document(path: &Path, moved: &HashMap<PathBuf, &Path>) -> bool {
+	let text = fs::read_to_string(path)
+		.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+	let json: serde_json::Value = serde_json::from_str(&text)
+		.unwrap_or_else(|e| panic!("failed to parse {}: {e}", path.display()));
+	let dir = path.parent().unwrap();
+	let mut result = text.clone();
+	for image in json["images"].as_array().into_iter().flatten() {
+		let Some(uri) = image["uri"].as_str() else {
+			continue;
+		};
+		let Some(shared) = moved.get(&normalise(&dir.join(uri))) else {
+			continue;
+		};
+		let old = format!("\"uri\":{}", serde_json::to_string(uri).unwrap());
+		let new = format!(
+			"\"uri\":{}",
+			serde_json::to_string(&relative_path(dir, shared)).unwrap()
+		);
+		result = result.replace(&old, &new);
+	}
+	if result == text {
+		return false;
+	}
+	fs::write(path, result).unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+	true
+}
+
+/// Removes `.` and `..` from a path without touching the filesystem
+fn normalise(path: &Path) -> PathBuf {
+	let mut result = PathBuf::new();
+	for component in path.components() {
+		match component {
+			Component::CurDir => {}
+			Component::ParentDir => {
+				if !result.pop() {
+					result.push("..");
+				}
+			}
+			component => result.push(component),
+		}
+	}
+	result
+}
+
+/// A uri from the folder `from` to the file `to`
+pub(crate) fn relative_path(from: &Path, to: &Path) -> String {
+	let from = normalise(from);
+	let to = normalise(to);
+	let from: Vec<_> = from.components().collect();
+	let to: Vec<_> = to.components().collect();
+	let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+	let mut parts = vec![String::from(".."); from.len() - common];
+	parts.extend(
+		to[common..]
+			.iter()
+			.map(|part| part.as_os_str().to_string_lossy().into_owned()),
+	);
+	parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_shared_assets() {
+		let dir = Path::new("output/test_shared_assets");
+		let _ = fs::remove_dir_all(dir);
+		let game = OutputWriter::new_in_dir(dir.join("TRAVERSE"));
+		let mut shared = SharedAssets::new(SharedRules::default());
+		let mut gltfs = Vec::new();
+		for (index, level) in ["LEVEL3", "LEVEL4", "LEVEL5"].into_iter().enumerate() {
+			let mut meshes = game.push_level_dir(level).push_dir("Meshes");
+			let mut textures = meshes.push_dir("Textures");
+			// the floor is different in every level
+			for (name, hash) in [("WALL", 1), ("FLOOR", 10 + index as u64)] {
+				let path = textures.set_output_path(name, "png").to_owned();
+				fs::write(&path, [hash as u8]).unwrap();
+				shared.add_texture(&textures, &path, (2, 2, hash));
+			}
+			let gltf = meshes.set_output_path("ROOM", "gltf").to_owned();
+			fs::write(
+				&gltf,
+				r#"{"images":[{"name":"WALL","uri":"Textures/WALL.png"},{"name":"FLOOR","uri":"Textures/FLOOR.png"}]}"#,
+			)
+			.unwrap();
+			shared.documents.push(gltf.clone());
+			gltfs.push(gltf);
+		}
+		// only the same texture in the same kind of folder counts
+		let mut other = game.push_level_dir("LEVEL6").push_dir("Textures");
+		let path = other.set_output_path("WALL", "png").to_owned();
+		shared.add_texture(&other, &path, (2, 2, 1));
+
+		let promotions = shared.promotions(|_, asset_type| {
+			OutputWriter::new_in_dir(dir.join("TRAVERSE/Shared")).push_dir(asset_type)
+		});
+		assert_eq!(promotions.len(), 1);
+		let wall = dir.join("TRAVERSE/Shared/Meshes/Textures/WALL.png");
+		assert_eq!(promotions[0].1.shared, wall);
+		assert_eq!(promotions[0].1.copies.len(), 3);
+		assert_eq!(shared.apply(&promotions), 3);
+
+		assert_eq!(fs::read(&wall).unwrap(), [1]);
+		assert!(
+			!dir.join("TRAVERSE/LEVEL4/Meshes/Textures/WALL.png")
+				.exists()
+		);
+		assert!(
+			dir.join("TRAVERSE/LEVEL4/Meshes/Textures/FLOOR.png")
+				.exists()
+		);
+		assert_eq!(
+			fs::read_to_string(&gltfs[1]).unwrap(),
+			r#"{"images":[{"name":"WALL","uri":"../../Shared/Meshes/Textures/WALL.png"},{"name":"FLOOR","uri":"Textures/FLOOR.png"}]}"#
+		);
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -0,0 +1,299 @@
+//! Writes each distinct sound once for the whole export, instead of once for every level using it.
+//!
+//! While enabled for an [ExportSession], the first copy of a sound is written to `Shared/Sounds`
+//! in the export, and everywhere it's saved to becomes a hard link to that file
+//! once all the writes are finished.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use crate::data_formats::Wav;
+use crate::{ExportSession, OutputWriter};
+
+/// Finds sounds that are probably the same from their format and samples, ignoring the rest of the wav file
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct SoundKey {
+	num_channels: u16,
+	samples_per_second: u32,
+	bits_per_sample: u16,
+	num_bytes: usize,
+	hash: u64,
+}
+
+impl SoundKey {
+	fn new(wav: &Wav) -> Self {
+		let mut hasher = DefaultHasher::new();
+		wav.samples.hash(&mut hasher);
+		Self {
+			num_channels: wav.num_channels,
+			samples_per_second: wav.samples_per_second,
+			bits_per_sample: wav.bits_per_sample,
+			num_bytes: wav.samples.len(),
+			hash: hasher.finish(),
+		}
+	}
+}
+
+/// A sound written to the shared folder
+struct SharedSound {
+	path: PathBuf,
+	/// Compared with sounds that have the same key, in case the hashes collide
+	samples: Vec<u8>,
+	/// Everywhere the sound was saved to, in save order
+	copies: Vec<PathBuf>,
+}
+
+/// Every distinct sound saved so far
+pub struct SoundIndex {
+	keys: HashMap<SoundKey, Vec<usize>>,
+	sounds: Vec<SharedSound>,
+	/// Lowercase names already used in the shared folder
+	names: HashSet<String>,
+}
+
+/// Totals from [SoundIndex::finish]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoundIndexStats {
+	pub sounds: usize,
+	pub copies: usize,
+	/// Copies that couldn't be hard linked, so were copied instead
+	pub unlinked: usize,
+}
+impl std::fmt::Display for SoundIndexStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"Shared {} distinct sounds between {} copies",
+			self.sounds, self.copies
+		)?;
+		if self.unlinked != 0 {
+			write!(f, " ({} copied instead of linked)", self.unlinked)?;
+		}
+		Ok(())
+	}
+}
+
+impl SoundIndex {
+	fn new() -> Self {
+		Self {
+			keys: HashMap::new(),
+			sounds: Vec::new(),
+			names: HashSet::new(),
+		}
+	}
+
+	/// Starts deduplicating every sound saved with [Wav::save_as], for every writer in the export
+	pub fn begin(session: &ExportSession) {
+		let mut index = session.0.sound_index.lock().unwrap();
+		assert!(index.is_none(), "sound deduplication already started");
+		*index = Some(SoundIndex::new());
+	}
+
+	/// Stops deduplicating, linking every copy to its shared file
+	/// and saving a list of the sounds that were saved more than once.
+	///
+	/// Call after [ExportSession::finish_writes] so the shared files exist.
+	/// Nothing is linked during a dry run.
+	pub fn finish(session: &ExportSession) -> SoundIndexStats {
+		let index = session
+			.0
+			.sound_index
+			.lock()
+			.unwrap()
+			.take()
+			.expect("sound deduplication not started");
+		let stats = if session.is_dry_run() {
+			index.stats(0)
+		} else {
+			index.link_copies()
+		};
+		let report = index.report();
+		if !report.is_empty() {
+			shared_output(&session.writer()).write("Sound Copies", "txt", report);
+		}
+		stats
+	}
+
+	fn add(&mut self, wav: &Wav, name: &str, output: &mut OutputWriter) {
+		let copy = output.set_output_path(name, "wav").to_owned();
+		let key = SoundKey::new(wav);
+		let existing = self.keys.get(&key).and_then(|indices| {
+			indices
+				.iter()
+				.copied()
+				.find(|&index| self.sounds[index].samples == wav.samples)
+		});
+		let index = match existing {
+			Some(index) => index,
+			None => {
+				// different sounds can share a name
+				let mut shared_name = name.to_owned();
+				let mut number = 1;
+				while !self.names.insert(shared_name.to_ascii_lowercase()) {
+					number += 1;
+					shared_name = format!("{name}_{number}");
+				}
+				let mut shared = shared_output(output);
+				let path = shared.set_output_path(&shared_name, "wav").to_owned();
+				shared.write(&shared_name, "wav", wav.file_data);
+				self.sounds.push(SharedSound {
+					path,
+					samples: wav.samples.to_owned(),
+					copies: Vec::new(),
+				});
+				self.keys
+					.entry(key)
+					.or_default()
+					.push(self.sounds.len() - 1);
+				self.sounds.len() - 1
+			}
+		};
+		let sound = &mut self.sounds[index];
+		if !sound.copies.contains(&copy) {
+			sound.copies.push(copy);
+		}
+	}
+
+	fn link_copies(&self) -> SoundIndexStats {
+		let mut unlinked = 0;
+		for sound in &self.sounds {
+			for copy in &sound.copies {
+				if !link(&sound.path, copy) {
+					unlinked += 1;
+				}
+			}
+		}
+		self.stats(unlinked)
+	}
+
+	fn stats(&self, unlinked: usize) -> SoundIndexStats {
+		SoundIndexStats {
+			sounds: self.sounds.len(),
+			copies: self.sounds.iter().map(|sound| sound.copies.len()).sum(),
+			unlinked,
+		}
+	}
+
+	/// Each shared file saved more than once, followed by its copies
+	fn report(&self) -> String {
+		let mut result = String::new();
+		for sound in self.sounds.iter().filter(|sound| sound.copies.len() > 1) {
+			writeln!(result, "{}", sound.path.display()).unwrap();
+			for copy in &sound.copies {
+				writeln!(result, "\t{}", copy.display()).unwrap();
+			}
+		}
+		result
+	}
+}
+
+/// Where the shared copies are written
+fn shared_output(output: &OutputWriter) -> OutputWriter {
+	output.asset_dir("Shared").push_dir("Sounds")
+}
+
+/// Saves `wav` as `name`, or leaves it to be linked to a shared copy if sounds are being deduplicated
+pub(crate) fn save_sound(wav: &Wav, name: &str, output: &mut OutputWriter) {
+	let session = output.session().clone();
+	match session.0.sound_index.lock().unwrap().as_mut() {
+		Some(index) => index.add(wav, name, output),
+		None => output.write(name, "wav", wav.file_data),
+	}
+}
+
+/// Hard links `copy` to `shared`, falling back to copying the file. Returns whether it was linked.
+fn link(shared: &Path, copy: &Path) -> bool {
+	// layouts can put the copy in the shared folder
+	if shared == copy {
+		return true;
+	}
+	// replace anything left from a previous export
+	let _ = fs::remove_file(copy);
+	if fs::hard_link(shared, copy).is_ok() {
+		return true;
+	}
+	if let Err(e) = fs::copy(shared, copy) {
+		panic!(
+			"failed to copy {} to {}: {e}",
+			shared.display(),
+			copy.display()
+		);
+	}
+	false
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Reader;
+	use crate::test_support::wav;
+
+	#[test]
+	fn test_sound_index() {
+		let dir = Path::new("output/test_sound_index");
+		let mut index = SoundIndex::new();
+		let root = OutputWriter::new_in_dir(dir);
+		let mut level1 = root.push_level_dir("LEVEL1");
+		let mut level2 = root.push_level_dir("LEVEL2");
+
+		let short = wav(1, 11025, 8, 10);
+		let long = wav(1, 11025, 8, 20);
+		let short = Wav::try_parse(&mut Reader::new(&short)).unwrap();
+		let long = Wav::try_parse(&mut Reader::new(&long)).unwrap();
+		index.add(&short, "BEEP", &mut level1);
+		index.add(&short, "BOOP", &mut level2);
+		index.add(&long, "BEEP", &mut level2);
+
+		assert_eq!(index.sounds.len(), 2);
+		assert_eq!(index.sounds[1].path, dir.join("Shared/Sounds/BEEP_2.wav"));
+		assert_eq!(
+			index.link_copies(),
+			SoundIndexStats {
+				sounds: 2,
+				copies: 3,
+				unlinked: 0
+			}
+		);
+		assert_eq!(
+			fs::read(dir.join("LEVEL2/BOOP.wav")).unwrap(),
+			short.file_data
+		);
+		assert_eq!(
+			fs::read(dir.join("LEVEL2/BEEP.wav")).unwrap(),
+			long.file_data
+		);
+		assert!(index.report().starts_with(&format!(
+			"{}\n\t{}\n",
+			dir.join("Shared/Sounds/BEEP.wav").display(),
+			dir.join("LEVEL1/BEEP.wav").display()
+		)));
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn test_sound_key_collision() {
+		let dir = Path::new("output/test_sound_key_collision");
+		let mut index = SoundIndex::new();
+		let mut output = OutputWriter::new_in_dir(dir);
+
+		let data = wav(1, 11025, 8, 10);
+		let beep = Wav::try_parse(&mut Reader::new(&data)).unwrap();
+		let mut other = Wav::try_parse(&mut Reader::new(&data)).unwrap();
+		let mut samples = beep.samples.to_owned();
+		samples[0] ^= 0xFF;
+		other.samples = &samples;
+		index.add(&beep, "BEEP", &mut output);
+		// pretend the hashes collide
+		let key = SoundKey::new(&beep);
+		index
+			.keys
+			.insert(SoundKey::new(&other), index.keys[&key].clone());
+
+		index.add(&other, "BOOP", &mut output);
+		assert_eq!(index.sounds.len(), 2);
+		assert_eq!(index.sounds[1].copies, [dir.join("BOOP.wav")]);
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
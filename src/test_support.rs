@@ -0,0 +1,737 @@
+//! Builders for small synthetic game files, so parsers can be tested without shipping game data.
+//!
+//! Every builder only writes the fields its parser checks, with everything else zeroed.
+use crate::Vec3;
+
+/// Little endian byte buffer with helpers for patching offsets in after the fact
+#[derive(Default)]
+pub struct ByteBuilder {
+	data: Vec<u8>,
+}
+
+impl ByteBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	pub fn position(&self) -> usize {
+		self.data.len()
+	}
+	pub fn finish(self) -> Vec<u8> {
+		self.data
+	}
+
+	pub fn u8(&mut self, value: u8) -> &mut Self {
+		self.bytes(&[value])
+	}
+	pub fn u16(&mut self, value: u16) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+	pub fn u32(&mut self, value: u32) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+	pub fn i32(&mut self, value: i32) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+	pub fn f32(&mut self, value: f32) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+	pub fn vec3(&mut self, value: Vec3) -> &mut Self {
+		self.f32(value.x).f32(value.y).f32(value.z)
+	}
+	pub fn bytes(&mut self, value: &[u8]) -> &mut Self {
+		self.data.extend_from_slice(value);
+		self
+	}
+
+	/// Writes a string into a fixed-size, null padded span
+	pub fn str(&mut self, value: &str, size: usize) -> &mut Self {
+		assert!(value.len() <= size, "{value} doesn't fit in {size} bytes");
+		self.bytes(value.as_bytes());
+		self.zeroes(size - value.len())
+	}
+	/// Writes a length-prefixed string
+	pub fn pascal_str(&mut self, value: &str) -> &mut Self {
+		self.u8(value.len().try_into().unwrap());
+		self.bytes(value.as_bytes())
+	}
+	pub fn zeroes(&mut self, count: usize) -> &mut Self {
+		self.data.resize(self.data.len() + count, 0);
+		self
+	}
+	pub fn align(&mut self, alignment: usize) -> &mut Self {
+		self.data
+			.resize(self.data.len().next_multiple_of(alignment), 0);
+		self
+	}
+
+	/// Reserves space for a u32 to be filled in later with [Self::patch_u32]
+	pub fn placeholder(&mut self) -> usize {
+		let pos = self.position();
+		self.u32(0);
+		pos
+	}
+	pub fn patch_u32(&mut self, pos: usize, value: u32) {
+		self.data[pos..pos + 4].copy_from_slice(&value.to_le_bytes());
+	}
+	/// Points a placeholder at the current position
+	pub fn patch_offset(&mut self, pos: usize) {
+		let offset = self.position() as u32;
+		self.patch_u32(pos, offset);
+	}
+}
+
+/// Starts the body shared by DTI/MTI/SNI/CMI files, a filename followed by a size.
+///
+/// Offsets in these files are relative to the start of the body, see [finish_file].
+fn file_body(filename: &str) -> ByteBuilder {
+	let mut body = ByteBuilder::new();
+	body.str(filename, 12);
+	body.placeholder();
+	body
+}
+
+/// Fills in the inner size and prepends the outer size of a file started with [file_body]
+fn finish_file(mut body: ByteBuilder) -> Vec<u8> {
+	let size = body.position() as u32;
+	body.patch_u32(12, size - 8);
+	let mut result = size.to_le_bytes().to_vec();
+	result.extend(body.finish());
+	result
+}
+
+/// A canonical PCM wav file with silent samples
+pub fn wav(num_channels: u16, sample_rate: u32, bits_per_sample: u16, num_samples: u32) -> Vec<u8> {
+	let bytes_per_sample = num_channels * bits_per_sample / 8;
+	let data_size = num_samples * bytes_per_sample as u32;
+	let mut wav = ByteBuilder::new();
+	wav.bytes(b"RIFF")
+		.u32(36 + data_size)
+		.bytes(b"WAVEfmt ")
+		.u32(16)
+		.u16(1)
+		.u16(num_channels)
+		.u32(sample_rate)
+		.u32(sample_rate * bytes_per_sample as u32)
+		.u16(bytes_per_sample)
+		.u16(bits_per_sample)
+		.bytes(b"data")
+		.u32(data_size)
+		.zeroes(data_size as usize);
+	wav.finish()
+}
+
+pub struct DtiZone<'a> {
+	pub kind: i32,
+	pub id: i32,
+	pub value: i32,
+	pub min: Vec3,
+	pub extra: DtiZoneExtra<'a>,
+}
+
+pub enum DtiZoneExtra<'a> {
+	/// Max corner of a box zone
+	Max(Vec3),
+	/// Name of a hotgen or hotpick
+	Name(&'a str),
+}
+
+/// Builds a DTI with a player start, arenas of zones, teleports, a palette and a skybox
+pub struct DtiBuilder<'a> {
+	filename: &'a str,
+	player_start: (u32, Vec3, f32),
+	/// Ceiling, floor, reflected ceiling and reflected floor colour indices
+	colours: [i32; 4],
+	arenas: Vec<(&'a str, f32, Vec<DtiZone<'a>>)>,
+	teleports: Vec<(i32, Vec3, f32)>,
+	pal: Vec<u8>,
+	sky: (u32, u32, Vec<u8>),
+	reflected_sky: Option<Vec<u8>>,
+}
+
+impl<'a> DtiBuilder<'a> {
+	pub fn new(filename: &'a str) -> Self {
+		Self {
+			filename,
+			player_start: (0, Vec3::default(), 0.0),
+			colours: [0; 4],
+			arenas: Vec::new(),
+			teleports: Vec::new(),
+			pal: vec![0; 0x300],
+			sky: (0, 0, Vec::new()),
+			reflected_sky: None,
+		}
+	}
+	pub fn player_start(mut self, arena_index: u32, pos: Vec3, angle: f32) -> Self {
+		self.player_start = (arena_index, pos, angle);
+		self
+	}
+	/// Sets the floor and ceiling colour indices, the reflected ones are only kept with a reflected skybox
+	pub fn colours(
+		mut self, ceiling: i32, floor: i32, reflected_ceiling: i32, reflected_floor: i32,
+	) -> Self {
+		self.colours = [ceiling, floor, reflected_ceiling, reflected_floor];
+		self
+	}
+	pub fn arena(mut self, name: &'a str, num: f32) -> Self {
+		self.arenas.push((name, num, Vec::new()));
+		self
+	}
+	/// Adds a zone to the last arena
+	pub fn zone(mut self, zone: DtiZone<'a>) -> Self {
+		self.arenas.last_mut().expect("no arena").2.push(zone);
+		self
+	}
+	/// Adds a teleport to the arena with the given index
+	pub fn teleport(mut self, arena_index: i32, pos: Vec3, angle: f32) -> Self {
+		self.teleports.push((arena_index, pos, angle));
+		self
+	}
+	pub fn palette(mut self, pal: &[u8]) -> Self {
+		assert_eq!(pal.len(), 0x300);
+		self.pal = pal.to_vec();
+		self
+	}
+	/// Sets the skybox pixels, rows are padded with the 4 extra pixels the game stores
+	pub fn skybox(
+		mut self, width: u32, height: u32, pixels: &[u8], reflected: Option<&[u8]>,
+	) -> Self {
+		self.sky = (width, height, pixels.to_vec());
+		self.reflected_sky = reflected.map(<[u8]>::to_vec);
+		self
+	}
+
+	pub fn build(&self) -> Vec<u8> {
+		let mut data = file_body(self.filename);
+		let player_offset = data.placeholder();
+		let teleports_offset = data.placeholder();
+		let entities_offset = data.placeholder();
+		let pal_offset = data.placeholder();
+		let skybox_offset = data.placeholder();
+
+		let (sky_width, sky_height, sky_pixels) = &self.sky;
+		data.patch_offset(player_offset);
+		let (arena_index, pos, angle) = self.player_start;
+		data.u32(arena_index).vec3(pos).f32(angle);
+		let [ceiling, floor, reflected_ceiling, reflected_floor] = self.colours;
+		data.i32(ceiling).i32(floor);
+		data.i32(0).i32(0).u32(*sky_width).u32(*sky_height);
+		data.i32(if self.reflected_sky.is_some() {
+			reflected_ceiling.max(0)
+		} else {
+			-1
+		});
+		data.i32(reflected_floor);
+		data.zeroes(4 * 4 * 4); // translucent colours
+
+		data.patch_offset(teleports_offset);
+		data.u32(self.teleports.len() as u32);
+		for (i, (arena_index, pos, angle)) in self.teleports.iter().enumerate() {
+			data.i32((i as i32 + 1) % 10)
+				.i32(*arena_index)
+				.vec3(*pos)
+				.f32(*angle);
+		}
+
+		data.patch_offset(entities_offset);
+		data.u32(self.arenas.len() as u32);
+		let arena_offsets: Vec<usize> = self
+			.arenas
+			.iter()
+			.map(|(name, num, _)| {
+				data.str(name, 8);
+				let offset = data.placeholder();
+				data.f32(*num);
+				offset
+			})
+			.collect();
+		for ((_, _, zones), offset) in self.arenas.iter().zip(arena_offsets) {
+			data.patch_offset(offset);
+			data.u32(zones.len() as u32);
+			for zone in zones {
+				data.i32(zone.kind)
+					.i32(zone.id)
+					.i32(zone.value)
+					.vec3(zone.min);
+				match zone.extra {
+					DtiZoneExtra::Max(max) => data.vec3(max),
+					DtiZoneExtra::Name(name) => data.str(name, 12),
+				};
+			}
+		}
+
+		data.patch_offset(pal_offset);
+		data.u32(0).bytes(&self.pal);
+
+		data.patch_offset(skybox_offset);
+		for pixels in std::iter::once(sky_pixels).chain(&self.reflected_sky) {
+			assert_eq!(pixels.len(), (sky_width * sky_height) as usize);
+			for row in pixels.chunks_exact((*sky_width as usize).max(1)) {
+				data.bytes(row).zeroes(4);
+			}
+		}
+
+		data.str(self.filename, 12);
+		finish_file(data)
+	}
+}
+
+enum MtiEntry {
+	Pen(i32),
+	Texture {
+		flags: u32,
+		a: f32,
+		b: f32,
+		data: Vec<u8>,
+	},
+}
+
+/// Builds an MTI of pens, textures and animated textures
+pub struct MtiBuilder<'a> {
+	filename: &'a str,
+	materials: Vec<(&'a str, MtiEntry)>,
+}
+
+impl<'a> MtiBuilder<'a> {
+	pub fn new(filename: &'a str) -> Self {
+		Self {
+			filename,
+			materials: Vec::new(),
+		}
+	}
+	pub fn pen(mut self, name: &'a str, value: i32) -> Self {
+		self.materials.push((name, MtiEntry::Pen(value)));
+		self
+	}
+	pub fn texture(mut self, name: &'a str, width: u16, height: u16, pixels: &[u8]) -> Self {
+		assert_eq!(pixels.len(), width as usize * height as usize);
+		let mut data = ByteBuilder::new();
+		data.u16(width).u16(height).bytes(pixels);
+		self.materials.push((
+			name,
+			MtiEntry::Texture {
+				flags: 0,
+				a: 0.0,
+				b: 3.5,
+				data: data.finish(),
+			},
+		));
+		self
+	}
+	pub fn animated_texture(
+		mut self, name: &'a str, width: u16, height: u16, frames: &[&[u8]],
+	) -> Self {
+		let mut data = ByteBuilder::new();
+		data.u32(frames.len() as u32).u16(width).u16(height);
+		for frame in frames {
+			assert_eq!(frame.len(), width as usize * height as usize);
+			data.bytes(frame);
+		}
+		self.materials.push((
+			name,
+			MtiEntry::Texture {
+				flags: 1 << 16,
+				a: 0.0,
+				b: 3.5,
+				data: data.finish(),
+			},
+		));
+		self
+	}
+	/// Sets the flags of the last texture
+	pub fn flags(mut self, a: f32, b: f32, flags: u32) -> Self {
+		match &mut self.materials.last_mut().expect("no material").1 {
+			MtiEntry::Texture {
+				flags: old_flags,
+				a: old_a,
+				b: old_b,
+				..
+			} => {
+				*old_flags = (*old_flags & 0x30000) | flags;
+				*old_a = a;
+				*old_b = b;
+			}
+			_ => panic!("only textures have flags"),
+		}
+		self
+	}
+
+	pub fn build(&self) -> Vec<u8> {
+		let mut data = file_body(self.filename);
+		data.u32(self.materials.len() as u32);
+		let mut offsets = Vec::new();
+		for (name, entry) in &self.materials {
+			data.str(name, 8);
+			match entry {
+				MtiEntry::Pen(value) => {
+					data.u32(u32::MAX).i32(*value).u32(0).u32(0);
+				}
+				MtiEntry::Texture {
+					flags,
+					a,
+					b,
+					data: texture,
+				} => {
+					data.u32(*flags).f32(*a).f32(*b);
+					offsets.push((data.placeholder(), texture));
+				}
+			}
+		}
+		for (offset, texture) in offsets {
+			data.patch_offset(offset);
+			data.bytes(texture);
+		}
+		data.str(self.filename, 12);
+		finish_file(data)
+	}
+}
+
+/// Builds an SNI of sounds
+pub struct SniBuilder<'a> {
+	filename: &'a str,
+	sounds: Vec<(&'a str, u32, Vec<u8>)>,
+}
+
+impl<'a> SniBuilder<'a> {
+	pub fn new(filename: &'a str) -> Self {
+		Self {
+			filename,
+			sounds: Vec::new(),
+		}
+	}
+	/// Adds a wav file (see [wav]) with nonzero flags
+	pub fn sound(mut self, name: &'a str, flags: u32, wav: Vec<u8>) -> Self {
+		assert_ne!(flags, 0, "sni entries with 0 flags are bsps");
+		self.sounds.push((name, flags, wav));
+		self
+	}
+
+	pub fn build(&self) -> Vec<u8> {
+		let mut data = file_body(self.filename);
+		data.u32(self.sounds.len() as u32);
+		let mut offsets = Vec::new();
+		for (name, flags, wav) in &self.sounds {
+			data.str(name, 12).u32(*flags);
+			offsets.push((data.placeholder(), wav));
+			data.u32(wav.len() as u32);
+		}
+		for (offset, wav) in offsets {
+			data.patch_offset(offset);
+			data.bytes(wav);
+		}
+		data.align(4);
+		data.str(self.filename, 12);
+		finish_file(data)
+	}
+}
+
+/// A single mesh with one triangle per material, as stored in BNIs and MTOs
+pub fn mesh(materials: &[&str]) -> Vec<u8> {
+	let mut data = ByteBuilder::new();
+	data.u32(materials.len() as u32);
+	for name in materials {
+		data.str(name, 16);
+	}
+	data.u32(3)
+		.vec3(Vec3::new(0.0, 0.0, 0.0))
+		.vec3(Vec3::new(1.0, 0.0, 0.0))
+		.vec3(Vec3::new(0.0, 1.0, 0.0));
+	data.u32(materials.len() as u32);
+	for i in 0..materials.len() {
+		data.u16(0).u16(1).u16(2).u16(i as u16);
+		data.zeroes(6 * 4).u32(0);
+	}
+	for value in [0.0, 1.0, 0.0, 1.0, 0.0, 0.0] {
+		data.f32(value);
+	}
+	data.u32(0); // reference points
+	data.finish()
+}
+
+/// Builds an MTO of arenas with meshes (see [mesh]), an empty bsp and their own MTI
+pub struct MtoBuilder<'a> {
+	filename: &'a str,
+	arenas: Vec<MtoArena<'a>>,
+}
+
+struct MtoArena<'a> {
+	name: &'a str,
+	palette: &'a [u8],
+	mti: Vec<u8>,
+	meshes: Vec<(&'a str, Vec<u8>)>,
+}
+
+impl<'a> MtoBuilder<'a> {
+	pub fn new(filename: &'a str) -> Self {
+		Self {
+			filename,
+			arenas: Vec::new(),
+		}
+	}
+	pub fn arena(mut self, name: &'a str, palette: &'a [u8], mti: Vec<u8>) -> Self {
+		assert_eq!(palette.len(), 336);
+		self.arenas.push(MtoArena {
+			name,
+			palette,
+			mti,
+			meshes: Vec::new(),
+		});
+		self
+	}
+	/// Adds a mesh to the last arena
+	pub fn mesh(mut self, name: &'a str, mesh: Vec<u8>) -> Self {
+		let arena = self.arenas.last_mut().expect("no arena");
+		arena.meshes.push((name, mesh));
+		self
+	}
+
+	pub fn build(&self) -> Vec<u8> {
+		let mut data = ByteBuilder::new();
+		data.placeholder();
+		data.str(self.filename, 12);
+		data.placeholder();
+		data.u32(self.arenas.len() as u32);
+		let mut arena_offsets = Vec::new();
+		for arena in &self.arenas {
+			data.str(arena.name, 8);
+			arena_offsets.push(data.placeholder());
+		}
+
+		for (offset, arena) in arena_offsets.into_iter().zip(&self.arenas) {
+			data.patch_offset(offset);
+			let size = data.placeholder();
+			// arena offsets are relative to the end of its size
+			let base = data.position();
+			let [assets, pal, bsp] = [(); 3].map(|_| data.placeholder());
+			data.bytes(&arena.mti);
+
+			data.patch_u32(assets, (data.position() - base) as u32);
+			let assets_length = data.placeholder();
+			let assets_base = data.position();
+			data.u32(0).u32(arena.meshes.len() as u32).u32(0);
+			let mut mesh_offsets = Vec::new();
+			for (name, _) in &arena.meshes {
+				data.str(name, 8);
+				mesh_offsets.push(data.placeholder());
+			}
+			for (offset, (_, mesh)) in mesh_offsets.into_iter().zip(&arena.meshes) {
+				data.patch_u32(offset, (data.position() - assets_base) as u32);
+				data.u32(0).bytes(mesh);
+			}
+			data.patch_u32(assets_length, (data.position() - assets_base) as u32);
+			// the bsp aligns its material names relative to the arena
+			data.zeroes((base % 4 + 4 - data.position() % 4) % 4);
+
+			data.patch_u32(pal, (data.position() - base) as u32);
+			data.bytes(arena.palette);
+			data.patch_u32(bsp, (data.position() - base) as u32);
+			// no materials, planes, tris, verts or things
+			data.zeroes(5 * 4);
+			data.patch_u32(size, (data.position() - base) as u32);
+		}
+
+		data.str(self.filename, 12);
+		let filesize = data.position() as u32;
+		data.patch_u32(0, filesize - 4);
+		data.patch_u32(16, filesize - 12);
+		data.finish()
+	}
+}
+
+/// Script that sets its resume point and returns
+pub const EMPTY_SCRIPT: &[u8] = &[0x01, 0xFD, 0xFF];
+
+/// Builds a CMI of arenas and meshless entities, each with their own script
+pub struct CmiBuilder<'a> {
+	filename: &'a str,
+	init_scripts: Vec<(String, Vec<u8>)>,
+	entities: Vec<&'a str>,
+	setup_scripts: Vec<(String, Vec<u8>)>,
+	arenas: Vec<(&'a str, &'a str, Vec<u8>)>,
+}
+
+impl<'a> CmiBuilder<'a> {
+	pub fn new(filename: &'a str) -> Self {
+		Self {
+			filename,
+			init_scripts: Vec::new(),
+			entities: Vec::new(),
+			setup_scripts: Vec::new(),
+			arenas: Vec::new(),
+		}
+	}
+	pub fn arena(mut self, name: &'a str, song: &'a str, script: &[u8]) -> Self {
+		self.arenas.push((name, song, script.to_vec()));
+		self
+	}
+	/// Adds an entity with no mesh and init and setup scripts in the given arena
+	pub fn entity(
+		mut self, arena: &str, name: &'a str, id: u32, init_script: &[u8], setup_script: &[u8],
+	) -> Self {
+		self.entities.push(name);
+		self.init_scripts
+			.push((format!("{arena}${name}_{id}"), init_script.to_vec()));
+		self.setup_scripts
+			.push((format!("{arena}${name}"), setup_script.to_vec()));
+		self
+	}
+
+	pub fn build(&self) -> Vec<u8> {
+		let mut data = file_body(self.filename);
+		let mut scripts = Vec::new();
+
+		data.u32(self.init_scripts.len() as u32);
+		for (name, script) in &self.init_scripts {
+			data.pascal_str(name);
+			scripts.push((data.placeholder(), script));
+		}
+
+		data.u32(self.entities.len() as u32);
+		for name in &self.entities {
+			data.pascal_str(name).u32(0);
+		}
+
+		data.u32(self.setup_scripts.len() as u32);
+		for (name, script) in &self.setup_scripts {
+			data.pascal_str(name);
+			scripts.push((data.placeholder(), script));
+		}
+
+		data.u32(self.arenas.len() as u32);
+		let mut arena_offsets = Vec::new();
+		for (name, song, script) in &self.arenas {
+			data.pascal_str(name);
+			arena_offsets.push((data.placeholder(), song, script));
+		}
+		for (offset, song, script) in arena_offsets {
+			data.patch_offset(offset);
+			data.pascal_str("").pascal_str(song);
+			scripts.push((data.placeholder(), script));
+		}
+
+		// scripts can't start at offset 0
+		data.u8(0xFF);
+		for (offset, script) in scripts {
+			data.patch_offset(offset);
+			data.bytes(script);
+		}
+		finish_file(data)
+	}
+}
+
+/// Builds a BNI from raw entries
+#[derive(Default)]
+pub struct BniBuilder<'a> {
+	entries: Vec<(&'a str, &'a [u8])>,
+}
+
+impl<'a> BniBuilder<'a> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	pub fn entry(mut self, name: &'a str, data: &'a [u8]) -> Self {
+		self.entries.push((name, data));
+		self
+	}
+	pub fn build(&self) -> Vec<u8> {
+		let mut body = ByteBuilder::new();
+		body.u32(self.entries.len() as u32);
+		let offsets: Vec<usize> = self
+			.entries
+			.iter()
+			.map(|(name, _)| {
+				body.str(name, 12);
+				body.placeholder()
+			})
+			.collect();
+		for ((_, data), offset) in self.entries.iter().zip(offsets) {
+			body.patch_offset(offset);
+			body.bytes(data);
+		}
+		let body = body.finish();
+		let mut result = (body.len() as u32).to_le_bytes().to_vec();
+		result.extend(body);
+		result
+	}
+}
+
+/// The synthetic files in the `samples` folder, which the documentation examples parse
+pub fn samples() -> Vec<(&'static str, Vec<u8>)> {
+	use crate::data_formats::Texture;
+	use crate::data_formats::image_formats::write_basic_image;
+
+	let palette: Vec<u8> = (0..=255u8).flat_map(|i| [i, i, i]).collect();
+	let wall = write_basic_image(&Texture::new(2, 2, vec![1, 2, 3, 4]));
+	vec![
+		(
+			"SAMPLE.BNI",
+			BniBuilder::new()
+				.entry("WALL", &wall)
+				.entry("PAL", &palette)
+				.build(),
+		),
+		(
+			"SAMPLE.CMI",
+			CmiBuilder::new("SAMPLE.CMI")
+				.arena("ARENA1", "SONG1", EMPTY_SCRIPT)
+				.entity("ARENA1", "GUNT", 1, EMPTY_SCRIPT, EMPTY_SCRIPT)
+				.build(),
+		),
+		(
+			"SAMPLE.DTI",
+			DtiBuilder::new("SAMPLE.DTI")
+				.player_start(0, Vec3::new(1.0, 2.0, 3.0), 0.0)
+				.arena("ARENA1", 0.0)
+				.zone(DtiZone {
+					kind: 1,
+					id: 1,
+					value: 0,
+					min: Vec3::new(-1.0, -1.0, -1.0),
+					extra: DtiZoneExtra::Max(Vec3::new(1.0, 1.0, 1.0)),
+				})
+				.palette(&palette)
+				.skybox(2, 1, &[5, 6], None)
+				.build(),
+		),
+		(
+			"SAMPLE.MTI",
+			MtiBuilder::new("SAMPLE.MTI")
+				.pen("RED", 4)
+				.texture("WALL", 2, 2, &[1, 2, 3, 4])
+				.animated_texture("WATER", 1, 2, &[&[1, 2], &[3, 4]])
+				.build(),
+		),
+		(
+			"SAMPLE.SNI",
+			SniBuilder::new("SAMPLE.SNI")
+				.sound("BEEP", 1, wav(1, 11025, 8, 64))
+				.build(),
+		),
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Keeps the `samples` folder in sync with the builders,
+	/// run with `UPDATE_SAMPLES=1` to regenerate it after changing them
+	#[test]
+	fn test_samples() {
+		let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("samples");
+		let update = std::env::var_os("UPDATE_SAMPLES").is_some();
+		for (name, data) in samples() {
+			let path = dir.join(name);
+			if update {
+				std::fs::create_dir_all(&dir).unwrap();
+				std::fs::write(&path, &data).unwrap();
+			} else {
+				let saved = std::fs::read(&path)
+					.unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+				assert!(
+					saved == data,
+					"{name} is out of date, run with UPDATE_SAMPLES=1"
+				);
+			}
+		}
+	}
+}
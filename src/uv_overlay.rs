@@ -0,0 +1,245 @@
+//! Debug images of how meshes map onto a texture, for spotting UV and palette mistakes.
+//!
+//! The texture is drawn scaled up in the palette it was exported with, darkened where no triangle
+//! samples it and tinted orange where several do, with each mesh's UV triangles drawn over it
+//! in a colour of its own.
+use std::fmt::Write;
+
+use crate::data_formats::mesh::MeshType;
+use crate::data_formats::{Mesh, Pen, Texture};
+use crate::{OutputWriter, Vec2};
+
+/// How many pixels each texel is drawn as
+const SCALE: usize = 4;
+/// Wireframe colours for each mesh, reused if there are more meshes
+const MESH_COLOURS: [[u8; 3]; 8] = [
+	[255, 64, 64],
+	[64, 255, 64],
+	[64, 128, 255],
+	[255, 255, 64],
+	[255, 64, 255],
+	[64, 255, 255],
+	[255, 160, 32],
+	[255, 255, 255],
+];
+/// How far (in texels) UVs can go past the edge of the texture before they count as outside it
+const EDGE_EPSILON: f32 = 0.01;
+
+/// The UV triangles of every mesh using a texture
+pub struct UvOverlay<'a> {
+	texture: &'a Texture<'a>,
+	/// Each mesh's triangles, in texels
+	meshes: Vec<(&'a str, Vec<[Vec2; 3]>)>,
+}
+
+impl<'a> UvOverlay<'a> {
+	pub fn new(texture: &'a Texture<'a>) -> Self {
+		Self {
+			texture,
+			meshes: Vec::new(),
+		}
+	}
+
+	/// Adds the visible triangles of `mesh` that use its material `material_index`
+	pub fn add_mesh(&mut self, name: &'a str, mesh: &Mesh, material_index: usize) {
+		let geos = match &mesh.mesh_data {
+			MeshType::Single(geo) => vec![geo],
+			MeshType::Multimesh { submeshes, .. } => {
+				submeshes.iter().map(|sub| &sub.mesh_data).collect()
+			}
+		};
+		let tris: Vec<[Vec2; 3]> = geos
+			.iter()
+			.flat_map(|geo| &geo.tris)
+			.filter(|tri| tri.material == Pen::Texture(material_index as u8) && !tri.is_hidden())
+			.map(|tri| tri.uvs)
+			.collect();
+		if !tris.is_empty() {
+			self.meshes.push((name, tris));
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.meshes.is_empty()
+	}
+
+	/// Draws the overlay as RGBA pixels, [SCALE] times the size of the texture
+	pub fn render(&self, palette: &[u8]) -> (usize, usize, Vec<[u8; 4]>) {
+		let (width, height) = (self.texture.width as usize, self.texture.height as usize);
+
+		// how many triangles sample each texel
+		let mut coverage = vec![0u32; width * height];
+		for &[a, b, c] in self.meshes.iter().flat_map(|(_, tris)| tris) {
+			let min = |axis: usize| a[axis].min(b[axis]).min(c[axis]).floor().max(0.0) as usize;
+			let max = |axis: usize, size: usize| {
+				(a[axis].max(b[axis]).max(c[axis]).ceil().max(0.0) as usize).min(size)
+			};
+			for y in min(1)..max(1, height) {
+				for x in min(0)..max(0, width) {
+					if contains([a, b, c], [x as f32 + 0.5, y as f32 + 0.5]) {
+						coverage[y * width + x] += 1;
+					}
+				}
+			}
+		}
+
+		let (out_width, out_height) = (width * SCALE, height * SCALE);
+		let mut pixels = Vec::with_capacity(out_width * out_height);
+		for y in 0..out_height {
+			for x in 0..out_width {
+				let texel = y / SCALE * width + x / SCALE;
+				let index = self.texture.pixels[texel] as usize;
+				let colour = palette[index * 3..index * 3 + 3].try_into().unwrap();
+				pixels.push(heat(colour, coverage[texel]));
+			}
+		}
+
+		for (i, (_, tris)) in self.meshes.iter().enumerate() {
+			let [r, g, b] = MESH_COLOURS[i % MESH_COLOURS.len()];
+			for &[p1, p2, p3] in tris {
+				for (from, to) in [(p1, p2), (p2, p3), (p3, p1)] {
+					let scale = |[u, v]: Vec2| [u * SCALE as f32, v * SCALE as f32];
+					draw_line(
+						&mut pixels,
+						out_width,
+						scale(from),
+						scale(to),
+						[r, g, b, 255],
+					);
+				}
+			}
+		}
+
+		(out_width, out_height, pixels)
+	}
+
+	pub fn save_as(&self, name: &str, palette: &[u8], output: &mut OutputWriter) {
+		let (width, height, pixels) = self.render(palette);
+		let mut data = Vec::new();
+		let mut encoder = png::Encoder::new(&mut data, width as u32, height as u32);
+		encoder.set_color(png::ColorType::Rgba);
+		let mut writer = encoder.write_header().unwrap();
+		writer.write_image_data(pixels.as_flattened()).unwrap();
+		writer.finish().unwrap();
+		output.write(name, "png", data);
+	}
+
+	/// Appends a row for each mesh to a TSV with the columns
+	/// `overlay, mesh, colour, triangles, outside` (the triangles that reach past the texture)
+	pub fn write_legend(&self, name: &str, tsv: &mut String) {
+		let (width, height) = (self.texture.width as f32, self.texture.height as f32);
+		for (i, (mesh, tris)) in self.meshes.iter().enumerate() {
+			let [r, g, b] = MESH_COLOURS[i % MESH_COLOURS.len()];
+			let outside = tris
+				.iter()
+				.filter(|tri| {
+					tri.iter().any(|&[u, v]| {
+						!(-EDGE_EPSILON..=width + EDGE_EPSILON).contains(&u)
+							|| !(-EDGE_EPSILON..=height + EDGE_EPSILON).contains(&v)
+					})
+				})
+				.count();
+			writeln!(
+				tsv,
+				"{name}\t{mesh}\t#{r:02X}{g:02X}{b:02X}\t{}\t{outside}",
+				tris.len()
+			)
+			.unwrap();
+		}
+	}
+}
+
+/// Darkens unused texels and warms up ones used more than once
+fn heat([r, g, b]: [u8; 3], coverage: u32) -> [u8; 4] {
+	if coverage == 0 {
+		return [r / 3, g / 3, b / 3, 255];
+	}
+	let amount = (coverage - 1).min(4) as f32 / 8.0;
+	let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount) as u8;
+	[mix(r, 255), mix(g, 128), mix(b, 0), 255]
+}
+
+/// Whether the point is inside the triangle, whichever way it winds
+fn contains([a, b, c]: [Vec2; 3], p: Vec2) -> bool {
+	let edge = |a: Vec2, b: Vec2| (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0]);
+	let edges = [edge(a, b), edge(b, c), edge(c, a)];
+	edges.iter().all(|&e| e >= 0.0) || edges.iter().all(|&e| e <= 0.0)
+}
+
+/// Draws a line, leaving out any part of it past the edge of the image
+fn draw_line(pixels: &mut [[u8; 4]], width: usize, from: Vec2, to: Vec2, colour: [u8; 4]) {
+	let height = pixels.len() / width;
+	let steps = (to[0] - from[0])
+		.abs()
+		.max((to[1] - from[1]).abs())
+		.ceil()
+		.max(1.0);
+	for step in 0..=steps as usize {
+		let t = step as f32 / steps;
+		let x = from[0] + (to[0] - from[0]) * t;
+		let y = from[1] + (to[1] - from[1]) * t;
+		if (0.0..width as f32).contains(&x) && (0.0..height as f32).contains(&y) {
+			pixels[y as usize * width + x as usize] = colour;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use super::*;
+	use crate::data_formats::mesh::{MeshGeo, MeshTri};
+
+	#[test]
+	fn test_uv_overlay() {
+		let texture = Texture::new(4, 4, vec![1; 16]);
+		let mut palette = vec![0; 256 * 3];
+		palette[3..6].copy_from_slice(&[90, 90, 90]);
+		let tri = |uvs, material| MeshTri {
+			indices: [0, 1, 2],
+			material,
+			uvs,
+			flags: 0,
+		};
+		let mesh = Mesh {
+			materials: vec!["OTHER", "WALL"],
+			mesh_data: MeshType::Single(MeshGeo {
+				verts: Vec::new(),
+				tris: vec![
+					// covers the top left half of the texture, and the corner texel twice
+					tri([[0.0, 0.0], [4.0, 0.0], [0.0, 4.0]], Pen::Texture(1)),
+					tri([[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]], Pen::Texture(1)),
+					// past the bottom right corner
+					tri([[4.0, 4.0], [8.0, 4.0], [4.0, 8.0]], Pen::Texture(0)),
+				],
+				bbox: Default::default(),
+			}),
+			reference_points: Vec::new(),
+			reference_point_usages: BTreeMap::new(),
+			part_usages: BTreeMap::new(),
+		};
+
+		let mut overlay = UvOverlay::new(&texture);
+		overlay.add_mesh("WALLS", &mesh, 1);
+		overlay.add_mesh("FLOOR", &mesh, 0);
+		assert!(!overlay.is_empty());
+
+		let (width, height, pixels) = overlay.render(&palette);
+		assert_eq!((width, height), (16, 16));
+		let pixel = |x: usize, y: usize| pixels[y * width + x];
+		assert_eq!(pixel(0, 0), [255, 64, 64, 255]);
+		assert_eq!(pixel(2, 2), [255, 64, 64, 255]);
+		// inside one triangle, then inside both, then outside
+		assert_eq!(pixel(6, 2), [90, 90, 90, 255]);
+		assert_eq!(pixel(3, 3), [110, 94, 78, 255]);
+		assert_eq!(pixel(14, 14), [30, 30, 30, 255]);
+
+		let mut legend = String::new();
+		overlay.write_legend("WALL", &mut legend);
+		assert_eq!(
+			legend,
+			"WALL\tWALLS\t#FF4040\t2\t0\nWALL\tFLOOR\t#40FF40\t1\t1\n"
+		);
+	}
+}
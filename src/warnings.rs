@@ -0,0 +1,124 @@
+//! Anomalies the parsers skipped over instead of panicking on,
+//! so they can be reviewed (and reported) without digging through stderr.
+use std::fmt::Write;
+
+/// Something a parser didn't understand, along with the bytes it was looking at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning<'a> {
+	/// Offset of `data` from the start of the file's data, if it came from there
+	pub offset: Option<usize>,
+	pub message: String,
+	pub data: &'a [u8],
+}
+
+impl std::fmt::Display for Warning<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		const MAX_BYTES: usize = 16;
+		match self.offset {
+			Some(offset) => write!(f, "{offset:06X}: {}", self.message)?,
+			None => write!(f, "??????: {}", self.message)?,
+		}
+		if self.data.is_empty() {
+			return Ok(());
+		}
+		f.write_str(" [")?;
+		for (i, byte) in self.data.iter().take(MAX_BYTES).enumerate() {
+			if i != 0 {
+				f.write_char(' ')?;
+			}
+			write!(f, "{byte:02X}")?;
+		}
+		if self.data.len() > MAX_BYTES {
+			write!(f, " ... ({} bytes)", self.data.len())?;
+		}
+		f.write_char(']')
+	}
+}
+
+/// The warnings from parsing one file
+#[derive(Debug, Clone, Default)]
+pub struct Warnings<'a> {
+	/// Offsets are relative to this, usually everything after the file's size header
+	base: &'a [u8],
+	pub list: Vec<Warning<'a>>,
+}
+
+impl<'a> Warnings<'a> {
+	pub fn new(base: &'a [u8]) -> Self {
+		Self {
+			base,
+			list: Vec::new(),
+		}
+	}
+
+	/// Records a problem with `data`, which should be a slice of the base buffer
+	pub fn push(&mut self, message: impl Into<String>, data: &'a [u8]) {
+		let offset = (data.as_ptr() as usize)
+			.checked_sub(self.base.as_ptr() as usize)
+			.filter(|offset| offset + data.len() <= self.base.len());
+		self.list.push(Warning {
+			offset,
+			message: message.into(),
+			data,
+		});
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.list.is_empty()
+	}
+	pub fn len(&self) -> usize {
+		self.list.len()
+	}
+	pub fn iter(&self) -> std::slice::Iter<'_, Warning<'a>> {
+		self.list.iter()
+	}
+
+	/// One line per warning
+	pub fn report(&self) -> String {
+		let mut result = String::new();
+		for warning in &self.list {
+			writeln!(result, "{warning}").unwrap();
+		}
+		result
+	}
+
+	#[cfg(feature = "export")]
+	/// Saves the report, if there is anything to report
+	pub fn save_as(&self, name: &str, output: &mut crate::OutputWriter) {
+		if !self.is_empty() {
+			output.write(name, "txt", self.report());
+		}
+	}
+}
+
+impl<'a, 'b> IntoIterator for &'b Warnings<'a> {
+	type Item = &'b Warning<'a>;
+	type IntoIter = std::slice::Iter<'b, Warning<'a>>;
+	fn into_iter(self) -> Self::IntoIter {
+		self.list.iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_warnings() {
+		let file = [0u8, 1, 2, 3, 4, 5, 6, 7];
+		let mut warnings = Warnings::new(&file[2..]);
+		warnings.push("unknown thing", &file[4..6]);
+		warnings.push("elsewhere", &[0xAB]);
+		assert_eq!(warnings.list[0].offset, Some(2));
+		assert_eq!(warnings.list[1].offset, None);
+		assert_eq!(
+			warnings.report(),
+			"000002: unknown thing [04 05]\n??????: elsewhere [AB]\n"
+		);
+
+		let long = [0xFFu8; 20];
+		let mut warnings = Warnings::new(&long);
+		warnings.push("long", &long);
+		assert!(warnings.report().ends_with("FF ... (20 bytes)]\n"));
+	}
+}
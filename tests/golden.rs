@@ -0,0 +1,106 @@
+//! Golden output test against a real copy of the game, which isn't included in the repo.
+//!
+//! Run with `MDK_ASSETS=path/to/game cargo test --release --test golden -- --ignored`.
+//! The first run (or any run with `UPDATE_GOLDEN=1`) records a hash of every output file to
+//! `MDK_GOLDEN` (default `golden.tsv`), and later runs fail if any of them changed.
+#![cfg(feature = "cli")]
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[test]
+#[ignore = "needs a copy of the game in MDK_ASSETS"]
+fn test_golden() {
+	let Some(assets) = std::env::var_os("MDK_ASSETS") else {
+		eprintln!("MDK_ASSETS isn't set, skipping");
+		return;
+	};
+	let assets = std::fs::canonicalize(&assets).expect("MDK_ASSETS doesn't exist");
+	let manifest_path = std::env::var_os("MDK_GOLDEN")
+		.map(PathBuf::from)
+		.unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("golden.tsv"));
+
+	// the tool writes to ./output, so run it somewhere empty
+	let dir = std::env::temp_dir().join("mdk-parse-golden");
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+	let status = Command::new(env!("CARGO_BIN_EXE_mdk-parse"))
+		.arg("--assets")
+		.arg(&assets)
+		.current_dir(&dir)
+		.status()
+		.expect("failed to run mdk-parse");
+	assert!(status.success(), "extraction failed ({status})");
+
+	let mut hashes = BTreeMap::new();
+	hash_dir(&dir.join("output"), &dir.join("output"), &mut hashes);
+	assert!(!hashes.is_empty(), "nothing was extracted");
+
+	let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+	if update || !manifest_path.exists() {
+		let manifest: String = hashes
+			.iter()
+			.map(|(path, hash)| format!("{path}\t{hash:016X}\n"))
+			.collect();
+		std::fs::write(&manifest_path, manifest).unwrap();
+		println!(
+			"Recorded {} files to {}",
+			hashes.len(),
+			manifest_path.display()
+		);
+		let _ = std::fs::remove_dir_all(&dir);
+		return;
+	}
+
+	let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+	let mut golden = BTreeMap::new();
+	for line in manifest.lines() {
+		let (path, hash) = line.split_once('\t').expect("invalid golden manifest");
+		let hash = u64::from_str_radix(hash, 16).expect("invalid golden hash");
+		golden.insert(path.to_owned(), hash);
+	}
+
+	let mut differences = Vec::new();
+	for (path, hash) in &golden {
+		match hashes.get(path) {
+			None => differences.push(format!("missing {path}")),
+			Some(new_hash) if new_hash != hash => differences.push(format!("changed {path}")),
+			Some(_) => {}
+		}
+	}
+	for path in hashes.keys().filter(|path| !golden.contains_key(*path)) {
+		differences.push(format!("added {path}"));
+	}
+	assert!(
+		differences.is_empty(),
+		"{} files differ from {} (output kept in {}), run with UPDATE_GOLDEN=1 if that's expected:\n{}",
+		differences.len(),
+		manifest_path.display(),
+		dir.display(),
+		differences.join("\n")
+	);
+	let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Hashes every file under `dir` by its path relative to `root`, except converted videos
+/// (which depend on the installed ffmpeg)
+fn hash_dir(root: &Path, dir: &Path, hashes: &mut BTreeMap<String, u64>) {
+	for entry in std::fs::read_dir(dir).unwrap() {
+		let path = entry.unwrap().path();
+		if path.is_dir() {
+			hash_dir(root, &path, hashes);
+		} else if path.extension().is_none_or(|ext| ext != "mp4") {
+			let relative = path.strip_prefix(root).unwrap();
+			let key = relative.to_string_lossy().replace('\\', "/");
+			hashes.insert(key, fnv1a(&std::fs::read(&path).unwrap()));
+		}
+	}
+}
+
+/// 64 bit FNV-1a, which unlike the std hasher is the same on every Rust version
+fn fnv1a(data: &[u8]) -> u64 {
+	data.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+		(hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+	})
+}